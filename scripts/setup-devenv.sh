@@ -248,6 +248,8 @@ BASE64_JWS_SIGNATURE=$(echo -n "${R}${S}" | xxd -p -r | base64_url_encode)
 
 echo -n "${BASE64_JWS_HEADER}.${BASE64_JWS_PAYLOAD}.${BASE64_JWS_SIGNATURE}" > "${TARGET_DIR}/wallet-config-jws-compact.txt"
 cp "${TARGET_DIR}/wallet-config-jws-compact.txt" "${CS_DIR}/wallet-config-jws-compact.txt"
+# Used instead of the JWS above when the configuration server is configured to sign the configuration itself
+cp "${TARGET_DIR}/wallet-config.json" "${CS_DIR}/wallet-config.json"
 cp "${TARGET_DIR}/wallet-config.json" "${BASE_DIR}/wallet_core/tests_integration/wallet-config.json"
 cp "${TARGET_DIR}/wallet_provider/config_signing.pem" "${BASE_DIR}/wallet_core/tests_integration/config_signing.pem"
 
@@ -36,18 +36,40 @@ void wire_set_lock_stream(int64_t port_);
 
 void wire_clear_lock_stream(int64_t port_);
 
+void wire_set_connectivity_stream(int64_t port_);
+
+void wire_clear_connectivity_stream(int64_t port_);
+
+void wire_set_clock_skew_stream(int64_t port_);
+
+void wire_clear_clock_skew_stream(int64_t port_);
+
+void wire_set_crash_stream(int64_t port_);
+
+void wire_clear_crash_stream(int64_t port_);
+
+void wire_set_locale(int64_t port_, struct wire_uint_8_list *locale);
+
 void wire_set_configuration_stream(int64_t port_);
 
 void wire_clear_configuration_stream(int64_t port_);
 
+void wire_is_feature_enabled(int64_t port_, struct wire_uint_8_list *feature);
+
 void wire_set_cards_stream(int64_t port_);
 
 void wire_clear_cards_stream(int64_t port_);
 
 void wire_unlock_wallet(int64_t port_, struct wire_uint_8_list *pin);
 
+void wire_get_pin_lockout(int64_t port_);
+
 void wire_lock_wallet(int64_t port_);
 
+void wire_register_activity(int64_t port_);
+
+void wire_notify_app_state(int64_t port_, int32_t state);
+
 void wire_has_registration(int64_t port_);
 
 void wire_register(int64_t port_, struct wire_uint_8_list *pin);
@@ -56,6 +78,8 @@ void wire_identify_uri(int64_t port_, struct wire_uint_8_list *uri);
 
 void wire_create_pid_issuance_redirect_uri(int64_t port_);
 
+void wire_create_pid_renewal_redirect_uri(int64_t port_);
+
 void wire_cancel_pid_issuance(int64_t port_);
 
 void wire_continue_pid_issuance(int64_t port_, struct wire_uint_8_list *uri);
@@ -66,6 +90,8 @@ void wire_reject_pid_issuance(int64_t port_);
 
 void wire_start_disclosure(int64_t port_, struct wire_uint_8_list *uri);
 
+void wire_get_disclosure_proposal(int64_t port_);
+
 void wire_cancel_disclosure(int64_t port_);
 
 void wire_accept_disclosure(int64_t port_, struct wire_uint_8_list *pin);
@@ -74,8 +100,14 @@ void wire_get_history(int64_t port_);
 
 void wire_get_history_for_card(int64_t port_, struct wire_uint_8_list *doc_type);
 
+void wire_get_history_page(int64_t port_, uint32_t offset, uint32_t limit);
+
+void wire_get_cards_page(int64_t port_, uint32_t offset, uint32_t limit);
+
 void wire_reset_wallet(int64_t port_);
 
+void wire_deregister_wallet(int64_t port_, struct wire_uint_8_list *pin);
+
 struct wire_uint_8_list *new_uint_8_list_0(int32_t len);
 
 void free_WireSyncReturn(WireSyncReturn ptr);
@@ -87,26 +119,42 @@ static int64_t dummy_method_to_enforce_bundling(void) {
     dummy_var ^= ((int64_t) (void*) wire_is_valid_pin);
     dummy_var ^= ((int64_t) (void*) wire_set_lock_stream);
     dummy_var ^= ((int64_t) (void*) wire_clear_lock_stream);
+    dummy_var ^= ((int64_t) (void*) wire_set_connectivity_stream);
+    dummy_var ^= ((int64_t) (void*) wire_clear_connectivity_stream);
+    dummy_var ^= ((int64_t) (void*) wire_set_clock_skew_stream);
+    dummy_var ^= ((int64_t) (void*) wire_clear_clock_skew_stream);
+    dummy_var ^= ((int64_t) (void*) wire_set_crash_stream);
+    dummy_var ^= ((int64_t) (void*) wire_clear_crash_stream);
+    dummy_var ^= ((int64_t) (void*) wire_set_locale);
     dummy_var ^= ((int64_t) (void*) wire_set_configuration_stream);
     dummy_var ^= ((int64_t) (void*) wire_clear_configuration_stream);
+    dummy_var ^= ((int64_t) (void*) wire_is_feature_enabled);
     dummy_var ^= ((int64_t) (void*) wire_set_cards_stream);
     dummy_var ^= ((int64_t) (void*) wire_clear_cards_stream);
     dummy_var ^= ((int64_t) (void*) wire_unlock_wallet);
+    dummy_var ^= ((int64_t) (void*) wire_get_pin_lockout);
     dummy_var ^= ((int64_t) (void*) wire_lock_wallet);
+    dummy_var ^= ((int64_t) (void*) wire_register_activity);
+    dummy_var ^= ((int64_t) (void*) wire_notify_app_state);
     dummy_var ^= ((int64_t) (void*) wire_has_registration);
     dummy_var ^= ((int64_t) (void*) wire_register);
     dummy_var ^= ((int64_t) (void*) wire_identify_uri);
     dummy_var ^= ((int64_t) (void*) wire_create_pid_issuance_redirect_uri);
+    dummy_var ^= ((int64_t) (void*) wire_create_pid_renewal_redirect_uri);
     dummy_var ^= ((int64_t) (void*) wire_cancel_pid_issuance);
     dummy_var ^= ((int64_t) (void*) wire_continue_pid_issuance);
     dummy_var ^= ((int64_t) (void*) wire_accept_pid_issuance);
     dummy_var ^= ((int64_t) (void*) wire_reject_pid_issuance);
     dummy_var ^= ((int64_t) (void*) wire_start_disclosure);
+    dummy_var ^= ((int64_t) (void*) wire_get_disclosure_proposal);
     dummy_var ^= ((int64_t) (void*) wire_cancel_disclosure);
     dummy_var ^= ((int64_t) (void*) wire_accept_disclosure);
     dummy_var ^= ((int64_t) (void*) wire_get_history);
     dummy_var ^= ((int64_t) (void*) wire_get_history_for_card);
+    dummy_var ^= ((int64_t) (void*) wire_get_history_page);
+    dummy_var ^= ((int64_t) (void*) wire_get_cards_page);
     dummy_var ^= ((int64_t) (void*) wire_reset_wallet);
+    dummy_var ^= ((int64_t) (void*) wire_deregister_wallet);
     dummy_var ^= ((int64_t) (void*) new_uint_8_list_0);
     dummy_var ^= ((int64_t) (void*) free_WireSyncReturn);
     dummy_var ^= ((int64_t) (void*) store_dart_post_cobject);
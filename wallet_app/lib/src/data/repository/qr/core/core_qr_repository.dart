@@ -49,6 +49,8 @@ class CoreQrRepository implements QrRepository {
         return PidIssuanceNavigationRequest(rawValue);
       case IdentifyUriResult.Disclosure:
         return DisclosureNavigationRequest(rawValue);
+      case IdentifyUriResult.IssuanceOffer:
+        return IssuanceNavigationRequest(rawValue);
     }
   }
 }
@@ -23,6 +23,8 @@ class CoreUriRepository implements UriRepository {
         return PidIssuanceNavigationRequest(uri.toString());
       case IdentifyUriResult.Disclosure:
         return DisclosureNavigationRequest(uri.toString());
+      case IdentifyUriResult.IssuanceOffer:
+        return IssuanceNavigationRequest(uri.toString());
     }
   }
 }
@@ -57,7 +57,8 @@ class WalletPersonalizeBloc extends Bloc<WalletPersonalizeEvent, WalletPersonali
           add(WalletPersonalizeLoginWithDigidSucceeded(result.previews));
         case PidIssuanceError():
           //TODO: Currently seeing 'accessDenied' when pressing cancel in the digid connector. To be verified on PROD.
-          final cancelledByUser = result.error == RedirectError.accessDenied;
+          final cancelledByUser =
+              result.error == RedirectError.cancelled || result.error == RedirectError.accessDenied;
           add(WalletPersonalizeLoginWithDigidFailed(cancelledByUser: cancelledByUser));
       }
     } catch (ex) {
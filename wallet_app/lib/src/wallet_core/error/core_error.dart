@@ -35,4 +35,4 @@ class CoreRedirectUriError extends CoreError {
   List<Object?> get props => [description, redirectError];
 }
 
-enum RedirectError { accessDenied, serverError, unknown }
+enum RedirectError { cancelled, expired, accessDenied, serverError, unknown }
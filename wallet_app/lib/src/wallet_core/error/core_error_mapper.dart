@@ -26,6 +26,15 @@ class CoreErrorMapper extends Mapper<String, CoreError> {
   }
 
   RedirectError _mapRedirectError(Map<String, dynamic>? data) {
+    // Prefer the error as classified by the core, fall back to the raw error for the other cases.
+    switch (data?['redirect_error_kind']) {
+      case 'cancelled':
+        return RedirectError.cancelled;
+      case 'expired':
+        return RedirectError.expired;
+      case 'access_denied':
+        return RedirectError.accessDenied;
+    }
     switch (data?['redirect_error']) {
       case 'access_denied':
         return RedirectError.accessDenied;
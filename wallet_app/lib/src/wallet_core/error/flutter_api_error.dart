@@ -5,17 +5,29 @@ part 'flutter_api_error.g.dart';
 @JsonSerializable()
 class FlutterApiError {
   FlutterApiErrorType type;
+  String? code;
+  @JsonKey(defaultValue: false)
+  bool retryable;
+  @JsonKey(name: 'message_key')
+  String? messageKey;
   String? description;
   Map<String, dynamic>? data;
 
-  FlutterApiError({required this.type, this.description, this.data});
+  FlutterApiError({
+    required this.type,
+    this.code,
+    this.retryable = false,
+    this.messageKey,
+    this.description,
+    this.data,
+  });
 
   factory FlutterApiError.fromJson(Map<String, dynamic> json) => _$FlutterApiErrorFromJson(json);
 
   Map<String, dynamic> toJson() => _$FlutterApiErrorToJson(this);
 
   @override
-  String toString() => 'FlutterApiError{type: ${type.name}, description: $description}';
+  String toString() => 'FlutterApiError{type: ${type.name}, code: $code, description: $description}';
 }
 
 enum FlutterApiErrorType {
@@ -8,12 +8,18 @@ part of 'flutter_api_error.dart';
 
 FlutterApiError _$FlutterApiErrorFromJson(Map<String, dynamic> json) => FlutterApiError(
       type: $enumDecode(_$FlutterApiErrorTypeEnumMap, json['type']),
+      code: json['code'] as String?,
+      retryable: json['retryable'] as bool? ?? false,
+      messageKey: json['message_key'] as String?,
       description: json['description'] as String?,
       data: json['data'] as Map<String, dynamic>?,
     );
 
 Map<String, dynamic> _$FlutterApiErrorToJson(FlutterApiError instance) => <String, dynamic>{
       'type': _$FlutterApiErrorTypeEnumMap[instance.type]!,
+      'code': instance.code,
+      'retryable': instance.retryable,
+      'message_key': instance.messageKey,
       'description': instance.description,
       'data': instance.data,
     };
@@ -87,6 +87,8 @@ class TypedWalletCore {
 
   Future<String> createPidIssuanceRedirectUri() => call((core) => core.createPidIssuanceRedirectUri());
 
+  Future<String> createPidRenewalRedirectUri() => call((core) => core.createPidRenewalRedirectUri());
+
   Future<IdentifyUriResult> identifyUri(String uri) => call((core) => core.identifyUri(uri: uri));
 
   Future<void> cancelPidIssuance() => call((core) => core.cancelPidIssuance());
@@ -112,6 +114,14 @@ class TypedWalletCore {
   Future<List<WalletEvent>> getHistoryForCard(String docType) =>
       call((core) => core.getHistoryForCard(docType: docType));
 
+  Future<HistoryPage> getHistoryPage(int offset, int limit) =>
+      call((core) => core.getHistoryPage(offset: offset, limit: limit));
+
+  Future<CardsPage> getCardsPage(int offset, int limit) =>
+      call((core) => core.getCardsPage(offset: offset, limit: limit));
+
+  Future<void> setLocale(String locale) => call((core) => core.setLocale(locale: locale));
+
   /// This function should be used to call through to the core, as it makes sure potential exceptions are processed
   /// before they are (re)thrown.
   Future<T> call<T>(Future<T> Function(WalletCore) runnable) async {
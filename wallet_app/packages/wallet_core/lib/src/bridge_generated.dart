@@ -34,6 +34,37 @@ abstract class WalletCore {
 
   FlutterRustBridgeTaskConstMeta get kClearLockStreamConstMeta;
 
+  Stream<bool> setConnectivityStream({dynamic hint});
+
+  FlutterRustBridgeTaskConstMeta get kSetConnectivityStreamConstMeta;
+
+  Future<void> clearConnectivityStream({dynamic hint});
+
+  FlutterRustBridgeTaskConstMeta get kClearConnectivityStreamConstMeta;
+
+  /// Emits whether the device clock differs significantly from the clock of the Wallet Provider.
+  Stream<bool> setClockSkewStream({dynamic hint});
+
+  FlutterRustBridgeTaskConstMeta get kSetClockSkewStreamConstMeta;
+
+  Future<void> clearClockSkewStream({dynamic hint});
+
+  FlutterRustBridgeTaskConstMeta get kClearClockSkewStreamConstMeta;
+
+  /// Emits panics that occur while executing API calls, serialized as JSON, right before the process exits.
+  Stream<String> setCrashStream({dynamic hint});
+
+  FlutterRustBridgeTaskConstMeta get kSetCrashStreamConstMeta;
+
+  Future<void> clearCrashStream({dynamic hint});
+
+  FlutterRustBridgeTaskConstMeta get kClearCrashStreamConstMeta;
+
+  /// Set the locale of the app, e.g. "nl" or "en-GB", which determines how attribute values are formatted.
+  Future<void> setLocale({required String locale, dynamic hint});
+
+  FlutterRustBridgeTaskConstMeta get kSetLocaleConstMeta;
+
   Stream<FlutterConfiguration> setConfigurationStream({dynamic hint});
 
   FlutterRustBridgeTaskConstMeta get kSetConfigurationStreamConstMeta;
@@ -42,6 +73,10 @@ abstract class WalletCore {
 
   FlutterRustBridgeTaskConstMeta get kClearConfigurationStreamConstMeta;
 
+  Future<bool> isFeatureEnabled({required String feature, dynamic hint});
+
+  FlutterRustBridgeTaskConstMeta get kIsFeatureEnabledConstMeta;
+
   Stream<List<Card>> setCardsStream({dynamic hint});
 
   FlutterRustBridgeTaskConstMeta get kSetCardsStreamConstMeta;
@@ -54,10 +89,22 @@ abstract class WalletCore {
 
   FlutterRustBridgeTaskConstMeta get kUnlockWalletConstMeta;
 
+  Future<PinLockoutState> getPinLockout({dynamic hint});
+
+  FlutterRustBridgeTaskConstMeta get kGetPinLockoutConstMeta;
+
   Future<void> lockWallet({dynamic hint});
 
   FlutterRustBridgeTaskConstMeta get kLockWalletConstMeta;
 
+  Future<void> registerActivity({dynamic hint});
+
+  FlutterRustBridgeTaskConstMeta get kRegisterActivityConstMeta;
+
+  Future<void> notifyAppState({required AppState state, dynamic hint});
+
+  FlutterRustBridgeTaskConstMeta get kNotifyAppStateConstMeta;
+
   Future<bool> hasRegistration({dynamic hint});
 
   FlutterRustBridgeTaskConstMeta get kHasRegistrationConstMeta;
@@ -74,6 +121,10 @@ abstract class WalletCore {
 
   FlutterRustBridgeTaskConstMeta get kCreatePidIssuanceRedirectUriConstMeta;
 
+  Future<String> createPidRenewalRedirectUri({dynamic hint});
+
+  FlutterRustBridgeTaskConstMeta get kCreatePidRenewalRedirectUriConstMeta;
+
   Future<void> cancelPidIssuance({dynamic hint});
 
   FlutterRustBridgeTaskConstMeta get kCancelPidIssuanceConstMeta;
@@ -94,6 +145,10 @@ abstract class WalletCore {
 
   FlutterRustBridgeTaskConstMeta get kStartDisclosureConstMeta;
 
+  Future<StartDisclosureResult> getDisclosureProposal({dynamic hint});
+
+  FlutterRustBridgeTaskConstMeta get kGetDisclosureProposalConstMeta;
+
   Future<void> cancelDisclosure({dynamic hint});
 
   FlutterRustBridgeTaskConstMeta get kCancelDisclosureConstMeta;
@@ -110,9 +165,24 @@ abstract class WalletCore {
 
   FlutterRustBridgeTaskConstMeta get kGetHistoryForCardConstMeta;
 
+  /// Return a page of the history, grouped by month in the locale set through [`set_locale()`].
+  Future<HistoryPage> getHistoryPage({required int offset, required int limit, dynamic hint});
+
+  FlutterRustBridgeTaskConstMeta get kGetHistoryPageConstMeta;
+
+  /// Return a page of the cards, sorted and grouped by the name of their issuer
+  /// in the locale set through [`set_locale()`].
+  Future<CardsPage> getCardsPage({required int offset, required int limit, dynamic hint});
+
+  FlutterRustBridgeTaskConstMeta get kGetCardsPageConstMeta;
+
   Future<void> resetWallet({dynamic hint});
 
   FlutterRustBridgeTaskConstMeta get kResetWalletConstMeta;
+
+  Future<WalletInstructionResult> deregisterWallet({required String pin, dynamic hint});
+
+  FlutterRustBridgeTaskConstMeta get kDeregisterWalletConstMeta;
 }
 
 @freezed
@@ -125,6 +195,11 @@ class AcceptDisclosureResult with _$AcceptDisclosureResult {
   }) = AcceptDisclosureResult_InstructionError;
 }
 
+enum AppState {
+  Foreground,
+  Background,
+}
+
 class Card {
   final CardPersistence persistence;
   final String docType;
@@ -142,10 +217,26 @@ class CardAttribute {
   final List<LocalizedString> labels;
   final CardValue value;
 
+  /// The value formatted for display in the current locale.
+  final String displayValue;
+
   const CardAttribute({
     required this.key,
     required this.labels,
     required this.value,
+    required this.displayValue,
+  });
+}
+
+class CardsPage {
+  final List<Card> cards;
+  final List<ItemGroup> groups;
+  final int totalCount;
+
+  const CardsPage({
+    required this.cards,
+    required this.groups,
+    required this.totalCount,
   });
 }
 
@@ -199,6 +290,19 @@ enum GenderCardValue {
 enum IdentifyUriResult {
   PidIssuance,
   Disclosure,
+  IssuanceOffer,
+}
+
+class HistoryPage {
+  final List<WalletEvent> events;
+  final List<ItemGroup> groups;
+  final int totalCount;
+
+  const HistoryPage({
+    required this.events,
+    required this.groups,
+    required this.totalCount,
+  });
 }
 
 @freezed
@@ -217,6 +321,22 @@ class Image with _$Image {
   }) = Image_Asset;
 }
 
+/// A consecutive range of items on a page that belong together, e.g. the history events of a single month.
+/// The `key` can be used to merge the last group of a page with the first group of the next page.
+class ItemGroup {
+  final String key;
+  final String label;
+  final int startIndex;
+  final int count;
+
+  const ItemGroup({
+    required this.key,
+    required this.label,
+    required this.startIndex,
+    required this.count,
+  });
+}
+
 class LocalizedString {
   final String language;
   final String value;
@@ -261,6 +381,18 @@ class Organization {
   });
 }
 
+class PinLockoutState {
+  final bool isBlocked;
+
+  /// The number of milliseconds left until the PIN may be entered again, if it is timed out.
+  final int? timeoutMillis;
+
+  const PinLockoutState({
+    required this.isBlocked,
+    this.timeoutMillis,
+  });
+}
+
 enum PinValidationResult {
   Ok,
   TooFewUniqueDigits,
@@ -436,6 +568,119 @@ class WalletCoreImpl implements WalletCore {
         argNames: [],
       );
 
+  Stream<bool> setConnectivityStream({dynamic hint}) {
+    return _platform.executeStream(FlutterRustBridgeTask(
+      callFfi: (port_) => _platform.inner.wire_set_connectivity_stream(port_),
+      parseSuccessData: _wire2api_bool,
+      parseErrorData: null,
+      constMeta: kSetConnectivityStreamConstMeta,
+      argValues: [],
+      hint: hint,
+    ));
+  }
+
+  FlutterRustBridgeTaskConstMeta get kSetConnectivityStreamConstMeta => const FlutterRustBridgeTaskConstMeta(
+        debugName: "set_connectivity_stream",
+        argNames: [],
+      );
+
+  Future<void> clearConnectivityStream({dynamic hint}) {
+    return _platform.executeNormal(FlutterRustBridgeTask(
+      callFfi: (port_) => _platform.inner.wire_clear_connectivity_stream(port_),
+      parseSuccessData: _wire2api_unit,
+      parseErrorData: null,
+      constMeta: kClearConnectivityStreamConstMeta,
+      argValues: [],
+      hint: hint,
+    ));
+  }
+
+  FlutterRustBridgeTaskConstMeta get kClearConnectivityStreamConstMeta => const FlutterRustBridgeTaskConstMeta(
+        debugName: "clear_connectivity_stream",
+        argNames: [],
+      );
+
+  Stream<bool> setClockSkewStream({dynamic hint}) {
+    return _platform.executeStream(FlutterRustBridgeTask(
+      callFfi: (port_) => _platform.inner.wire_set_clock_skew_stream(port_),
+      parseSuccessData: _wire2api_bool,
+      parseErrorData: null,
+      constMeta: kSetClockSkewStreamConstMeta,
+      argValues: [],
+      hint: hint,
+    ));
+  }
+
+  FlutterRustBridgeTaskConstMeta get kSetClockSkewStreamConstMeta => const FlutterRustBridgeTaskConstMeta(
+        debugName: "set_clock_skew_stream",
+        argNames: [],
+      );
+
+  Future<void> clearClockSkewStream({dynamic hint}) {
+    return _platform.executeNormal(FlutterRustBridgeTask(
+      callFfi: (port_) => _platform.inner.wire_clear_clock_skew_stream(port_),
+      parseSuccessData: _wire2api_unit,
+      parseErrorData: null,
+      constMeta: kClearClockSkewStreamConstMeta,
+      argValues: [],
+      hint: hint,
+    ));
+  }
+
+  FlutterRustBridgeTaskConstMeta get kClearClockSkewStreamConstMeta => const FlutterRustBridgeTaskConstMeta(
+        debugName: "clear_clock_skew_stream",
+        argNames: [],
+      );
+
+  Stream<String> setCrashStream({dynamic hint}) {
+    return _platform.executeStream(FlutterRustBridgeTask(
+      callFfi: (port_) => _platform.inner.wire_set_crash_stream(port_),
+      parseSuccessData: _wire2api_String,
+      parseErrorData: null,
+      constMeta: kSetCrashStreamConstMeta,
+      argValues: [],
+      hint: hint,
+    ));
+  }
+
+  FlutterRustBridgeTaskConstMeta get kSetCrashStreamConstMeta => const FlutterRustBridgeTaskConstMeta(
+        debugName: "set_crash_stream",
+        argNames: [],
+      );
+
+  Future<void> clearCrashStream({dynamic hint}) {
+    return _platform.executeNormal(FlutterRustBridgeTask(
+      callFfi: (port_) => _platform.inner.wire_clear_crash_stream(port_),
+      parseSuccessData: _wire2api_unit,
+      parseErrorData: null,
+      constMeta: kClearCrashStreamConstMeta,
+      argValues: [],
+      hint: hint,
+    ));
+  }
+
+  FlutterRustBridgeTaskConstMeta get kClearCrashStreamConstMeta => const FlutterRustBridgeTaskConstMeta(
+        debugName: "clear_crash_stream",
+        argNames: [],
+      );
+
+  Future<void> setLocale({required String locale, dynamic hint}) {
+    var arg0 = _platform.api2wire_String(locale);
+    return _platform.executeNormal(FlutterRustBridgeTask(
+      callFfi: (port_) => _platform.inner.wire_set_locale(port_, arg0),
+      parseSuccessData: _wire2api_unit,
+      parseErrorData: null,
+      constMeta: kSetLocaleConstMeta,
+      argValues: [locale],
+      hint: hint,
+    ));
+  }
+
+  FlutterRustBridgeTaskConstMeta get kSetLocaleConstMeta => const FlutterRustBridgeTaskConstMeta(
+        debugName: "set_locale",
+        argNames: ["locale"],
+      );
+
   Stream<FlutterConfiguration> setConfigurationStream({dynamic hint}) {
     return _platform.executeStream(FlutterRustBridgeTask(
       callFfi: (port_) => _platform.inner.wire_set_configuration_stream(port_),
@@ -468,6 +713,23 @@ class WalletCoreImpl implements WalletCore {
         argNames: [],
       );
 
+  Future<bool> isFeatureEnabled({required String feature, dynamic hint}) {
+    var arg0 = _platform.api2wire_String(feature);
+    return _platform.executeNormal(FlutterRustBridgeTask(
+      callFfi: (port_) => _platform.inner.wire_is_feature_enabled(port_, arg0),
+      parseSuccessData: _wire2api_bool,
+      parseErrorData: null,
+      constMeta: kIsFeatureEnabledConstMeta,
+      argValues: [feature],
+      hint: hint,
+    ));
+  }
+
+  FlutterRustBridgeTaskConstMeta get kIsFeatureEnabledConstMeta => const FlutterRustBridgeTaskConstMeta(
+        debugName: "is_feature_enabled",
+        argNames: ["feature"],
+      );
+
   Stream<List<Card>> setCardsStream({dynamic hint}) {
     return _platform.executeStream(FlutterRustBridgeTask(
       callFfi: (port_) => _platform.inner.wire_set_cards_stream(port_),
@@ -517,6 +779,22 @@ class WalletCoreImpl implements WalletCore {
         argNames: ["pin"],
       );
 
+  Future<PinLockoutState> getPinLockout({dynamic hint}) {
+    return _platform.executeNormal(FlutterRustBridgeTask(
+      callFfi: (port_) => _platform.inner.wire_get_pin_lockout(port_),
+      parseSuccessData: _wire2api_pin_lockout_state,
+      parseErrorData: _wire2api_FrbAnyhowException,
+      constMeta: kGetPinLockoutConstMeta,
+      argValues: [],
+      hint: hint,
+    ));
+  }
+
+  FlutterRustBridgeTaskConstMeta get kGetPinLockoutConstMeta => const FlutterRustBridgeTaskConstMeta(
+        debugName: "get_pin_lockout",
+        argNames: [],
+      );
+
   Future<void> lockWallet({dynamic hint}) {
     return _platform.executeNormal(FlutterRustBridgeTask(
       callFfi: (port_) => _platform.inner.wire_lock_wallet(port_),
@@ -533,6 +811,39 @@ class WalletCoreImpl implements WalletCore {
         argNames: [],
       );
 
+  Future<void> registerActivity({dynamic hint}) {
+    return _platform.executeNormal(FlutterRustBridgeTask(
+      callFfi: (port_) => _platform.inner.wire_register_activity(port_),
+      parseSuccessData: _wire2api_unit,
+      parseErrorData: null,
+      constMeta: kRegisterActivityConstMeta,
+      argValues: [],
+      hint: hint,
+    ));
+  }
+
+  FlutterRustBridgeTaskConstMeta get kRegisterActivityConstMeta => const FlutterRustBridgeTaskConstMeta(
+        debugName: "register_activity",
+        argNames: [],
+      );
+
+  Future<void> notifyAppState({required AppState state, dynamic hint}) {
+    var arg0 = api2wire_app_state(state);
+    return _platform.executeNormal(FlutterRustBridgeTask(
+      callFfi: (port_) => _platform.inner.wire_notify_app_state(port_, arg0),
+      parseSuccessData: _wire2api_unit,
+      parseErrorData: null,
+      constMeta: kNotifyAppStateConstMeta,
+      argValues: [state],
+      hint: hint,
+    ));
+  }
+
+  FlutterRustBridgeTaskConstMeta get kNotifyAppStateConstMeta => const FlutterRustBridgeTaskConstMeta(
+        debugName: "notify_app_state",
+        argNames: ["state"],
+      );
+
   Future<bool> hasRegistration({dynamic hint}) {
     return _platform.executeNormal(FlutterRustBridgeTask(
       callFfi: (port_) => _platform.inner.wire_has_registration(port_),
@@ -599,6 +910,22 @@ class WalletCoreImpl implements WalletCore {
         argNames: [],
       );
 
+  Future<String> createPidRenewalRedirectUri({dynamic hint}) {
+    return _platform.executeNormal(FlutterRustBridgeTask(
+      callFfi: (port_) => _platform.inner.wire_create_pid_renewal_redirect_uri(port_),
+      parseSuccessData: _wire2api_String,
+      parseErrorData: _wire2api_FrbAnyhowException,
+      constMeta: kCreatePidRenewalRedirectUriConstMeta,
+      argValues: [],
+      hint: hint,
+    ));
+  }
+
+  FlutterRustBridgeTaskConstMeta get kCreatePidRenewalRedirectUriConstMeta => const FlutterRustBridgeTaskConstMeta(
+        debugName: "create_pid_renewal_redirect_uri",
+        argNames: [],
+      );
+
   Future<void> cancelPidIssuance({dynamic hint}) {
     return _platform.executeNormal(FlutterRustBridgeTask(
       callFfi: (port_) => _platform.inner.wire_cancel_pid_issuance(port_),
@@ -682,6 +1009,22 @@ class WalletCoreImpl implements WalletCore {
         argNames: ["uri"],
       );
 
+  Future<StartDisclosureResult> getDisclosureProposal({dynamic hint}) {
+    return _platform.executeNormal(FlutterRustBridgeTask(
+      callFfi: (port_) => _platform.inner.wire_get_disclosure_proposal(port_),
+      parseSuccessData: _wire2api_start_disclosure_result,
+      parseErrorData: _wire2api_FrbAnyhowException,
+      constMeta: kGetDisclosureProposalConstMeta,
+      argValues: [],
+      hint: hint,
+    ));
+  }
+
+  FlutterRustBridgeTaskConstMeta get kGetDisclosureProposalConstMeta => const FlutterRustBridgeTaskConstMeta(
+        debugName: "get_disclosure_proposal",
+        argNames: [],
+      );
+
   Future<void> cancelDisclosure({dynamic hint}) {
     return _platform.executeNormal(FlutterRustBridgeTask(
       callFfi: (port_) => _platform.inner.wire_cancel_disclosure(port_),
@@ -748,11 +1091,47 @@ class WalletCoreImpl implements WalletCore {
         argNames: ["docType"],
       );
 
+  Future<HistoryPage> getHistoryPage({required int offset, required int limit, dynamic hint}) {
+    var arg0 = api2wire_u32(offset);
+    var arg1 = api2wire_u32(limit);
+    return _platform.executeNormal(FlutterRustBridgeTask(
+      callFfi: (port_) => _platform.inner.wire_get_history_page(port_, arg0, arg1),
+      parseSuccessData: _wire2api_history_page,
+      parseErrorData: _wire2api_FrbAnyhowException,
+      constMeta: kGetHistoryPageConstMeta,
+      argValues: [offset, limit],
+      hint: hint,
+    ));
+  }
+
+  FlutterRustBridgeTaskConstMeta get kGetHistoryPageConstMeta => const FlutterRustBridgeTaskConstMeta(
+        debugName: "get_history_page",
+        argNames: ["offset", "limit"],
+      );
+
+  Future<CardsPage> getCardsPage({required int offset, required int limit, dynamic hint}) {
+    var arg0 = api2wire_u32(offset);
+    var arg1 = api2wire_u32(limit);
+    return _platform.executeNormal(FlutterRustBridgeTask(
+      callFfi: (port_) => _platform.inner.wire_get_cards_page(port_, arg0, arg1),
+      parseSuccessData: _wire2api_cards_page,
+      parseErrorData: _wire2api_FrbAnyhowException,
+      constMeta: kGetCardsPageConstMeta,
+      argValues: [offset, limit],
+      hint: hint,
+    ));
+  }
+
+  FlutterRustBridgeTaskConstMeta get kGetCardsPageConstMeta => const FlutterRustBridgeTaskConstMeta(
+        debugName: "get_cards_page",
+        argNames: ["offset", "limit"],
+      );
+
   Future<void> resetWallet({dynamic hint}) {
     return _platform.executeNormal(FlutterRustBridgeTask(
       callFfi: (port_) => _platform.inner.wire_reset_wallet(port_),
       parseSuccessData: _wire2api_unit,
-      parseErrorData: null,
+      parseErrorData: _wire2api_FrbAnyhowException,
       constMeta: kResetWalletConstMeta,
       argValues: [],
       hint: hint,
@@ -764,6 +1143,23 @@ class WalletCoreImpl implements WalletCore {
         argNames: [],
       );
 
+  Future<WalletInstructionResult> deregisterWallet({required String pin, dynamic hint}) {
+    var arg0 = _platform.api2wire_String(pin);
+    return _platform.executeNormal(FlutterRustBridgeTask(
+      callFfi: (port_) => _platform.inner.wire_deregister_wallet(port_, arg0),
+      parseSuccessData: _wire2api_wallet_instruction_result,
+      parseErrorData: _wire2api_FrbAnyhowException,
+      constMeta: kDeregisterWalletConstMeta,
+      argValues: [pin],
+      hint: hint,
+    ));
+  }
+
+  FlutterRustBridgeTaskConstMeta get kDeregisterWalletConstMeta => const FlutterRustBridgeTaskConstMeta(
+        debugName: "deregister_wallet",
+        argNames: ["pin"],
+      );
+
   void dispose() {
     _platform.dispose();
   }
@@ -832,11 +1228,12 @@ class WalletCoreImpl implements WalletCore {
 
   CardAttribute _wire2api_card_attribute(dynamic raw) {
     final arr = raw as List<dynamic>;
-    if (arr.length != 3) throw Exception('unexpected arr length: expect 3 but see ${arr.length}');
+    if (arr.length != 4) throw Exception('unexpected arr length: expect 4 but see ${arr.length}');
     return CardAttribute(
       key: _wire2api_String(arr[0]),
       labels: _wire2api_list_localized_string(arr[1]),
       value: _wire2api_card_value(arr[2]),
+      displayValue: _wire2api_String(arr[3]),
     );
   }
 
@@ -853,6 +1250,16 @@ class WalletCoreImpl implements WalletCore {
     }
   }
 
+  CardsPage _wire2api_cards_page(dynamic raw) {
+    final arr = raw as List<dynamic>;
+    if (arr.length != 3) throw Exception('unexpected arr length: expect 3 but see ${arr.length}');
+    return CardsPage(
+      cards: _wire2api_list_card(arr[0]),
+      groups: _wire2api_list_item_group(arr[1]),
+      totalCount: _wire2api_u32(arr[2]),
+    );
+  }
+
   CardValue _wire2api_card_value(dynamic raw) {
     switch (raw[0]) {
       case 0:
@@ -893,6 +1300,16 @@ class WalletCoreImpl implements WalletCore {
     return GenderCardValue.values[raw as int];
   }
 
+  HistoryPage _wire2api_history_page(dynamic raw) {
+    final arr = raw as List<dynamic>;
+    if (arr.length != 3) throw Exception('unexpected arr length: expect 3 but see ${arr.length}');
+    return HistoryPage(
+      events: _wire2api_list_wallet_event(arr[0]),
+      groups: _wire2api_list_item_group(arr[1]),
+      totalCount: _wire2api_u32(arr[2]),
+    );
+  }
+
   int _wire2api_i32(dynamic raw) {
     return raw as int;
   }
@@ -924,6 +1341,17 @@ class WalletCoreImpl implements WalletCore {
     }
   }
 
+  ItemGroup _wire2api_item_group(dynamic raw) {
+    final arr = raw as List<dynamic>;
+    if (arr.length != 4) throw Exception('unexpected arr length: expect 4 but see ${arr.length}');
+    return ItemGroup(
+      key: _wire2api_String(arr[0]),
+      label: _wire2api_String(arr[1]),
+      startIndex: _wire2api_u32(arr[2]),
+      count: _wire2api_u32(arr[3]),
+    );
+  }
+
   List<Card> _wire2api_list_card(dynamic raw) {
     return (raw as List<dynamic>).map(_wire2api_card).toList();
   }
@@ -932,6 +1360,10 @@ class WalletCoreImpl implements WalletCore {
     return (raw as List<dynamic>).map(_wire2api_card_attribute).toList();
   }
 
+  List<ItemGroup> _wire2api_list_item_group(dynamic raw) {
+    return (raw as List<dynamic>).map(_wire2api_item_group).toList();
+  }
+
   List<LocalizedString> _wire2api_list_localized_string(dynamic raw) {
     return (raw as List<dynamic>).map(_wire2api_localized_string).toList();
   }
@@ -1002,6 +1434,15 @@ class WalletCoreImpl implements WalletCore {
     );
   }
 
+  PinLockoutState _wire2api_pin_lockout_state(dynamic raw) {
+    final arr = raw as List<dynamic>;
+    if (arr.length != 2) throw Exception('unexpected arr length: expect 2 but see ${arr.length}');
+    return PinLockoutState(
+      isBlocked: _wire2api_bool(arr[0]),
+      timeoutMillis: _wire2api_opt_box_autoadd_u64(arr[1]),
+    );
+  }
+
   PinValidationResult _wire2api_pin_validation_result(dynamic raw) {
     return PinValidationResult.values[raw as int];
   }
@@ -1054,6 +1495,10 @@ class WalletCoreImpl implements WalletCore {
     return raw as int;
   }
 
+  int _wire2api_u32(dynamic raw) {
+    return raw as int;
+  }
+
   int _wire2api_u64(dynamic raw) {
     return castInt(raw);
   }
@@ -1126,6 +1571,21 @@ class WalletCoreImpl implements WalletCore {
 
 // Section: api2wire
 
+@protected
+int api2wire_app_state(AppState raw) {
+  return api2wire_i32(raw.index);
+}
+
+@protected
+int api2wire_i32(int raw) {
+  return raw;
+}
+
+@protected
+int api2wire_u32(int raw) {
+  return raw;
+}
+
 @protected
 int api2wire_u8(int raw) {
   return raw;
@@ -1293,6 +1753,92 @@ class WalletCoreWire implements FlutterRustBridgeWireBase {
       _lookup<ffi.NativeFunction<ffi.Void Function(ffi.Int64)>>('wire_clear_lock_stream');
   late final _wire_clear_lock_stream = _wire_clear_lock_streamPtr.asFunction<void Function(int)>();
 
+  void wire_set_connectivity_stream(
+    int port_,
+  ) {
+    return _wire_set_connectivity_stream(
+      port_,
+    );
+  }
+
+  late final _wire_set_connectivity_streamPtr =
+      _lookup<ffi.NativeFunction<ffi.Void Function(ffi.Int64)>>('wire_set_connectivity_stream');
+  late final _wire_set_connectivity_stream = _wire_set_connectivity_streamPtr.asFunction<void Function(int)>();
+
+  void wire_clear_connectivity_stream(
+    int port_,
+  ) {
+    return _wire_clear_connectivity_stream(
+      port_,
+    );
+  }
+
+  late final _wire_clear_connectivity_streamPtr =
+      _lookup<ffi.NativeFunction<ffi.Void Function(ffi.Int64)>>('wire_clear_connectivity_stream');
+  late final _wire_clear_connectivity_stream = _wire_clear_connectivity_streamPtr.asFunction<void Function(int)>();
+
+  void wire_set_clock_skew_stream(
+    int port_,
+  ) {
+    return _wire_set_clock_skew_stream(
+      port_,
+    );
+  }
+
+  late final _wire_set_clock_skew_streamPtr =
+      _lookup<ffi.NativeFunction<ffi.Void Function(ffi.Int64)>>('wire_set_clock_skew_stream');
+  late final _wire_set_clock_skew_stream = _wire_set_clock_skew_streamPtr.asFunction<void Function(int)>();
+
+  void wire_clear_clock_skew_stream(
+    int port_,
+  ) {
+    return _wire_clear_clock_skew_stream(
+      port_,
+    );
+  }
+
+  late final _wire_clear_clock_skew_streamPtr =
+      _lookup<ffi.NativeFunction<ffi.Void Function(ffi.Int64)>>('wire_clear_clock_skew_stream');
+  late final _wire_clear_clock_skew_stream = _wire_clear_clock_skew_streamPtr.asFunction<void Function(int)>();
+
+  void wire_set_crash_stream(
+    int port_,
+  ) {
+    return _wire_set_crash_stream(
+      port_,
+    );
+  }
+
+  late final _wire_set_crash_streamPtr =
+      _lookup<ffi.NativeFunction<ffi.Void Function(ffi.Int64)>>('wire_set_crash_stream');
+  late final _wire_set_crash_stream = _wire_set_crash_streamPtr.asFunction<void Function(int)>();
+
+  void wire_clear_crash_stream(
+    int port_,
+  ) {
+    return _wire_clear_crash_stream(
+      port_,
+    );
+  }
+
+  late final _wire_clear_crash_streamPtr =
+      _lookup<ffi.NativeFunction<ffi.Void Function(ffi.Int64)>>('wire_clear_crash_stream');
+  late final _wire_clear_crash_stream = _wire_clear_crash_streamPtr.asFunction<void Function(int)>();
+
+  void wire_set_locale(
+    int port_,
+    ffi.Pointer<wire_uint_8_list> locale,
+  ) {
+    return _wire_set_locale(
+      port_,
+      locale,
+    );
+  }
+
+  late final _wire_set_localePtr =
+      _lookup<ffi.NativeFunction<ffi.Void Function(ffi.Int64, ffi.Pointer<wire_uint_8_list>)>>('wire_set_locale');
+  late final _wire_set_locale = _wire_set_localePtr.asFunction<void Function(int, ffi.Pointer<wire_uint_8_list>)>();
+
   void wire_set_configuration_stream(
     int port_,
   ) {
@@ -1317,6 +1863,21 @@ class WalletCoreWire implements FlutterRustBridgeWireBase {
       _lookup<ffi.NativeFunction<ffi.Void Function(ffi.Int64)>>('wire_clear_configuration_stream');
   late final _wire_clear_configuration_stream = _wire_clear_configuration_streamPtr.asFunction<void Function(int)>();
 
+  void wire_is_feature_enabled(
+    int port_,
+    ffi.Pointer<wire_uint_8_list> feature,
+  ) {
+    return _wire_is_feature_enabled(
+      port_,
+      feature,
+    );
+  }
+
+  late final _wire_is_feature_enabledPtr =
+      _lookup<ffi.NativeFunction<ffi.Void Function(ffi.Int64, ffi.Pointer<wire_uint_8_list>)>>('wire_is_feature_enabled');
+  late final _wire_is_feature_enabled =
+      _wire_is_feature_enabledPtr.asFunction<void Function(int, ffi.Pointer<wire_uint_8_list>)>();
+
   void wire_set_cards_stream(
     int port_,
   ) {
@@ -1356,6 +1917,17 @@ class WalletCoreWire implements FlutterRustBridgeWireBase {
   late final _wire_unlock_wallet =
       _wire_unlock_walletPtr.asFunction<void Function(int, ffi.Pointer<wire_uint_8_list>)>();
 
+  void wire_get_pin_lockout(
+    int port_,
+  ) {
+    return _wire_get_pin_lockout(
+      port_,
+    );
+  }
+
+  late final _wire_get_pin_lockoutPtr = _lookup<ffi.NativeFunction<ffi.Void Function(ffi.Int64)>>('wire_get_pin_lockout');
+  late final _wire_get_pin_lockout = _wire_get_pin_lockoutPtr.asFunction<void Function(int)>();
+
   void wire_lock_wallet(
     int port_,
   ) {
@@ -1367,6 +1939,32 @@ class WalletCoreWire implements FlutterRustBridgeWireBase {
   late final _wire_lock_walletPtr = _lookup<ffi.NativeFunction<ffi.Void Function(ffi.Int64)>>('wire_lock_wallet');
   late final _wire_lock_wallet = _wire_lock_walletPtr.asFunction<void Function(int)>();
 
+  void wire_register_activity(
+    int port_,
+  ) {
+    return _wire_register_activity(
+      port_,
+    );
+  }
+
+  late final _wire_register_activityPtr =
+      _lookup<ffi.NativeFunction<ffi.Void Function(ffi.Int64)>>('wire_register_activity');
+  late final _wire_register_activity = _wire_register_activityPtr.asFunction<void Function(int)>();
+
+  void wire_notify_app_state(
+    int port_,
+    int state,
+  ) {
+    return _wire_notify_app_state(
+      port_,
+      state,
+    );
+  }
+
+  late final _wire_notify_app_statePtr =
+      _lookup<ffi.NativeFunction<ffi.Void Function(ffi.Int64, ffi.Int32)>>('wire_notify_app_state');
+  late final _wire_notify_app_state = _wire_notify_app_statePtr.asFunction<void Function(int, int)>();
+
   void wire_has_registration(
     int port_,
   ) {
@@ -1420,6 +2018,19 @@ class WalletCoreWire implements FlutterRustBridgeWireBase {
   late final _wire_create_pid_issuance_redirect_uri =
       _wire_create_pid_issuance_redirect_uriPtr.asFunction<void Function(int)>();
 
+  void wire_create_pid_renewal_redirect_uri(
+    int port_,
+  ) {
+    return _wire_create_pid_renewal_redirect_uri(
+      port_,
+    );
+  }
+
+  late final _wire_create_pid_renewal_redirect_uriPtr =
+      _lookup<ffi.NativeFunction<ffi.Void Function(ffi.Int64)>>('wire_create_pid_renewal_redirect_uri');
+  late final _wire_create_pid_renewal_redirect_uri =
+      _wire_create_pid_renewal_redirect_uriPtr.asFunction<void Function(int)>();
+
   void wire_cancel_pid_issuance(
     int port_,
   ) {
@@ -1491,6 +2102,18 @@ class WalletCoreWire implements FlutterRustBridgeWireBase {
   late final _wire_start_disclosure =
       _wire_start_disclosurePtr.asFunction<void Function(int, ffi.Pointer<wire_uint_8_list>)>();
 
+  void wire_get_disclosure_proposal(
+    int port_,
+  ) {
+    return _wire_get_disclosure_proposal(
+      port_,
+    );
+  }
+
+  late final _wire_get_disclosure_proposalPtr =
+      _lookup<ffi.NativeFunction<ffi.Void Function(ffi.Int64)>>('wire_get_disclosure_proposal');
+  late final _wire_get_disclosure_proposal = _wire_get_disclosure_proposalPtr.asFunction<void Function(int)>();
+
   void wire_cancel_disclosure(
     int port_,
   ) {
@@ -1546,6 +2169,38 @@ class WalletCoreWire implements FlutterRustBridgeWireBase {
   late final _wire_get_history_for_card =
       _wire_get_history_for_cardPtr.asFunction<void Function(int, ffi.Pointer<wire_uint_8_list>)>();
 
+  void wire_get_history_page(
+    int port_,
+    int offset,
+    int limit,
+  ) {
+    return _wire_get_history_page(
+      port_,
+      offset,
+      limit,
+    );
+  }
+
+  late final _wire_get_history_pagePtr =
+      _lookup<ffi.NativeFunction<ffi.Void Function(ffi.Int64, ffi.Uint32, ffi.Uint32)>>('wire_get_history_page');
+  late final _wire_get_history_page = _wire_get_history_pagePtr.asFunction<void Function(int, int, int)>();
+
+  void wire_get_cards_page(
+    int port_,
+    int offset,
+    int limit,
+  ) {
+    return _wire_get_cards_page(
+      port_,
+      offset,
+      limit,
+    );
+  }
+
+  late final _wire_get_cards_pagePtr =
+      _lookup<ffi.NativeFunction<ffi.Void Function(ffi.Int64, ffi.Uint32, ffi.Uint32)>>('wire_get_cards_page');
+  late final _wire_get_cards_page = _wire_get_cards_pagePtr.asFunction<void Function(int, int, int)>();
+
   void wire_reset_wallet(
     int port_,
   ) {
@@ -1557,6 +2212,21 @@ class WalletCoreWire implements FlutterRustBridgeWireBase {
   late final _wire_reset_walletPtr = _lookup<ffi.NativeFunction<ffi.Void Function(ffi.Int64)>>('wire_reset_wallet');
   late final _wire_reset_wallet = _wire_reset_walletPtr.asFunction<void Function(int)>();
 
+  void wire_deregister_wallet(
+    int port_,
+    ffi.Pointer<wire_uint_8_list> pin,
+  ) {
+    return _wire_deregister_wallet(
+      port_,
+      pin,
+    );
+  }
+
+  late final _wire_deregister_walletPtr =
+      _lookup<ffi.NativeFunction<ffi.Void Function(ffi.Int64, ffi.Pointer<wire_uint_8_list>)>>('wire_deregister_wallet');
+  late final _wire_deregister_wallet =
+      _wire_deregister_walletPtr.asFunction<void Function(int, ffi.Pointer<wire_uint_8_list>)>();
+
   ffi.Pointer<wire_uint_8_list> new_uint_8_list_0(
     int len,
   ) {
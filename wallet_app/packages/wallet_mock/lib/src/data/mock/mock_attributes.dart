@@ -10,51 +10,61 @@ final kMockPidDataAttributes = [
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Voornamen')],
     value: _kMockFirstNames,
+    displayValue: 'Willeke Liselotte',
     key: 'mock.firstNames',
   ),
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Achternaam')],
     value: _kMockLastName,
+    displayValue: 'De Bruijn',
     key: 'mock.lastName',
   ),
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Naam bij geboorte')],
     value: CardValue.string(value: 'Molenaar'),
+    displayValue: 'Molenaar',
     key: 'mock.birthName',
   ),
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Geslacht')],
     value: _kMockGender,
+    displayValue: 'Vrouw',
     key: 'mock.gender',
   ),
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Geboortedatum')],
     value: _kMockBirthDate,
+    displayValue: '11 maart 1997',
     key: 'mock.birthDate',
   ),
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Ouder dan 18')],
     value: CardValue.boolean(value: true),
+    displayValue: 'Ja',
     key: 'mock.olderThan18',
   ),
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Geboorteplaats')],
     value: _kMockBirthPlace,
+    displayValue: 'Delft',
     key: 'mock.birthPlace',
   ),
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Geboorteland')],
     value: CardValue.string(value: 'Nederland'),
+    displayValue: 'Nederland',
     key: 'mock.birthCountry',
   ),
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Burgerservicenummer (BSN)')],
     value: CardValue.string(value: '******999'),
+    displayValue: '******999',
     key: 'mock.citizenshipNumber',
   ),
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Nationaliteit')],
     value: CardValue.string(value: 'Nederlands'),
+    displayValue: 'Nederlands',
     key: 'mock.nationality',
   ),
 ];
@@ -63,21 +73,25 @@ final kMockAddressDataAttributes = [
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Straatnaam')],
     value: CardValue.string(value: 'Turfmarkt'),
+    displayValue: 'Turfmarkt',
     key: 'mock.streetName',
   ),
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Huisnummer')],
     value: CardValue.string(value: '147'),
+    displayValue: '147',
     key: 'mock.houseNumber',
   ),
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Postcode')],
     value: CardValue.string(value: '2511 DP'),
+    displayValue: '2511 DP',
     key: 'mock.postalCode',
   ),
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Woonplaats')],
     value: CardValue.string(value: 'Den Haag'),
+    displayValue: 'Den Haag',
     key: 'mock.city',
   ),
 ];
@@ -144,60 +144,70 @@ final _kMockPidDataAttributes = [
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Voornamen')],
     value: _kMockFirstNames,
+    displayValue: 'Willeke Liselotte',
     key: 'mock.firstNames',
     // sourceCardId: _kPidId,
   ),
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Achternaam')],
     value: _kMockLastName,
+    displayValue: 'De Bruijn',
     key: 'mock.lastName',
     // sourceCardId: _kPidId,
   ),
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Naam bij geboorte')],
     value: const CardValue.string(value: 'Molenaar'),
+    displayValue: 'Molenaar',
     key: 'mock.birthName',
     // sourceCardId: _kPidId,
   ),
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Geslacht')],
     value: _kMockGender,
+    displayValue: 'Vrouw',
     key: 'mock.gender',
     // sourceCardId: _kPidId,
   ),
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Geboortedatum')],
     value: _kMockBirthDate,
+    displayValue: '10 maart 1997',
     key: 'mock.birthDate',
     // sourceCardId: _kPidId,
   ),
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Ouder dan 18')],
     value: CardValue.boolean(value: true),
+    displayValue: 'Ja',
     key: 'mock.olderThan18',
     // sourceCardId: _kPidId,
   ),
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Geboorteplaats')],
     value: _kMockBirthPlace,
+    displayValue: 'Delft',
     key: 'mock.birthPlace',
     // sourceCardId: _kPidId,
   ),
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Geboorteland')],
     value: const CardValue.string(value: 'Nederland'),
+    displayValue: 'Nederland',
     key: 'mock.birthCountry',
     // sourceCardId: _kPidId,
   ),
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Burgerservicenummer (BSN)')],
     value: const CardValue.string(value: '******999'),
+    displayValue: '******999',
     key: 'mock.citizenshipNumber',
     // sourceCardId: _kPidId,
   ),
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Nationaliteit')],
     value: const CardValue.string(value: 'Nederlands'),
+    displayValue: 'Nederlands',
     key: 'mock.nationality',
     // sourceCardId: _kPidId,
   ),
@@ -207,24 +217,28 @@ final _kMockAddressDataAttributes = [
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Straatnaam')],
     value: const CardValue.string(value: 'Turfmarkt'),
+    displayValue: 'Turfmarkt',
     key: 'mock.streetName',
     // sourceCardId: _kAddressId,
   ),
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Huisnummer')],
     value: const CardValue.string(value: '147'),
+    displayValue: '147',
     key: 'mock.houseNumber',
     // sourceCardId: _kAddressId,
   ),
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Postcode')],
     value: const CardValue.string(value: '2511 DP'),
+    displayValue: '2511 DP',
     key: 'mock.postalCode',
     // sourceCardId: _kAddressId,
   ),
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Woonplaats')],
     value: const CardValue.string(value: 'Den Haag'),
+    displayValue: 'Den Haag',
     key: 'mock.city',
     // sourceCardId: _kAddressId,
   ),
@@ -234,30 +248,35 @@ final _kMockDiplomaDataAttributes = [
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Onderwijsinstelling')],
     value: const CardValue.string(value: 'Universiteit X'),
+    displayValue: 'Universiteit X',
     key: 'mock.university',
     // sourceCardId: _kDiplomaId,
   ),
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Opleiding')],
     value: const CardValue.string(value: 'WO Bachelor Bedrijfskunde'),
+    displayValue: 'WO Bachelor Bedrijfskunde',
     key: 'mock.education',
     // sourceCardId: _kDiplomaId,
   ),
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Niveau')],
     value: const CardValue.string(value: 'WO'),
+    displayValue: 'WO',
     key: 'mock.educationLevel',
     // sourceCardId: _kDiplomaId,
   ),
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Type')],
     value: const CardValue.string(value: 'Getuigschrift'),
+    displayValue: 'Getuigschrift',
     // sourceCardId: _kDiplomaId,
     key: 'mock.other',
   ),
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Uitgifte datum')],
     value: CardValue.date(value: DateTime(2013, 1, 1).toIso8601String()),
+    displayValue: '1 januari 2013',
     key: 'mock.issuanceDate',
     // sourceCardId: _kDiplomaId,
   ),
@@ -267,30 +286,35 @@ final _kMockMasterDiplomaDataAttributes = [
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Onderwijsinstelling')],
     value: const CardValue.string(value: 'Universiteit X'),
+    displayValue: 'Universiteit X',
     key: 'mock.university',
     // sourceCardId: _kMasterDiplomaId,
   ),
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Opleiding')],
     value: const CardValue.string(value: 'WO Master Bedrijfskunde'),
+    displayValue: 'WO Master Bedrijfskunde',
     key: 'mock.education',
     // sourceCardId: _kMasterDiplomaId,
   ),
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Niveau')],
     value: const CardValue.string(value: 'WO'),
+    displayValue: 'WO',
     key: 'mock.educationLevel',
     // sourceCardId: _kMasterDiplomaId,
   ),
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Type')],
     value: const CardValue.string(value: 'Getuigschrift'),
+    displayValue: 'Getuigschrift',
     // sourceCardId: _kMasterDiplomaId,
     key: 'mock.other',
   ),
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Uitgifte datum')],
     value: CardValue.date(value: DateTime(2015, 1, 1).toIso8601String()),
+    displayValue: '1 januari 2015',
     key: 'mock.issuanceDate',
     // sourceCardId: _kMasterDiplomaId,
   ),
@@ -304,48 +328,56 @@ List<CardAttribute> _buildDrivingLicenseDataAttributes({required String category
     CardAttribute(
       labels: [LocalizedString(language: 'nl', value: 'Voornamen')],
       value: _kMockFirstNames,
+      displayValue: 'Willeke Liselotte',
       key: 'mock.firstNames',
       // sourceCardId: _kDrivingLicenseId,
     ),
     CardAttribute(
       labels: [LocalizedString(language: 'nl', value: 'Naam')],
       value: _kMockLastName,
+      displayValue: 'De Bruijn',
       key: 'mock.lastName',
       // sourceCardId: _kDrivingLicenseId,
     ),
     CardAttribute(
       labels: [LocalizedString(language: 'nl', value: 'Geboortedatum')],
       value: _kMockBirthDate,
+      displayValue: '10 maart 1997',
       key: 'mock.birthDate',
       // sourceCardId: _kDrivingLicenseId,
     ),
     CardAttribute(
       labels: [LocalizedString(language: 'nl', value: 'Geboorteplaats')],
       value: _kMockBirthPlace,
+      displayValue: 'Delft',
       key: 'mock.birthPlace',
       // sourceCardId: _kDrivingLicenseId,
     ),
     CardAttribute(
       labels: [LocalizedString(language: 'nl', value: 'Afgiftedatum')],
       value: CardValue.date(value: DateTime(2018, 4, 23).toIso8601String()),
+      displayValue: '23 april 2018',
       key: 'mock.issuanceDate',
       // sourceCardId: _kDrivingLicenseId,
     ),
     CardAttribute(
       labels: [LocalizedString(language: 'nl', value: 'Datum geldig tot')],
       value: CardValue.date(value: DateTime(2028, 4, 23).toIso8601String()),
+      displayValue: '23 april 2028',
       key: 'mock.expiryDate',
       // sourceCardId: _kDrivingLicenseId,
     ),
     CardAttribute(
       labels: [LocalizedString(language: 'nl', value: 'Rijbewijsnummer')],
       value: const CardValue.string(value: '99999999999'),
+      displayValue: '99999999999',
       // sourceCardId: _kDrivingLicenseId,
       key: 'mock.other',
     ),
     CardAttribute(
       labels: [LocalizedString(language: 'nl', value: 'Rijbewijscategorieën')],
       value: CardValue.string(value: category),
+      displayValue: category,
       key: 'mock.drivingLicenseCategories',
       // sourceCardId: _kDrivingLicenseId,
     ),
@@ -356,42 +388,49 @@ final _kMockHealthInsuranceDataAttributes = [
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Naam')],
     value: _kMockFullName,
+    displayValue: 'Willeke De Bruijn',
     key: 'mock.fullName',
     // sourceCardId: _kHealthInsuranceId,
   ),
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Geslacht')],
     value: _kMockGender,
+    displayValue: 'Vrouw',
     key: 'mock.gender',
     // sourceCardId: _kHealthInsuranceId,
   ),
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Geboortedatum')],
     value: _kMockBirthDate,
+    displayValue: '10 maart 1997',
     key: 'mock.birthDate',
     // sourceCardId: _kHealthInsuranceId,
   ),
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Klantnummer')],
     value: const CardValue.string(value: '12345678'),
+    displayValue: '12345678',
     key: 'mock.healthIssuerClientId',
     // sourceCardId: _kHealthInsuranceId,
   ),
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Kaartnummer')],
     value: const CardValue.string(value: '9999999999'),
+    displayValue: '9999999999',
     key: 'mock.documentNr',
     // sourceCardId: _kHealthInsuranceId,
   ),
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'UZOVI')],
     value: const CardValue.string(value: 'XXXX - 9999'),
+    displayValue: 'XXXX - 9999',
     key: 'mock.healthIssuerId',
     // sourceCardId: _kHealthInsuranceId,
   ),
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Verloopdatum')],
     value: CardValue.date(value: DateTime(2024, 1, 1).toIso8601String()),
+    displayValue: '1 januari 2024',
     key: 'mock.healthInsuranceExpiryDate',
     // sourceCardId: _kHealthInsuranceId,
   ),
@@ -401,12 +440,14 @@ final _kMockVOGDataAttributes = [
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Type')],
     value: const CardValue.string(value: '1'),
+    displayValue: '1',
     key: 'mock.certificateOfConduct',
     // sourceCardId: _kVOGId,
   ),
   CardAttribute(
     labels: [LocalizedString(language: 'nl', value: 'Datum geldig tot')],
     value: CardValue.date(value: DateTime(2023, 2, 5).toIso8601String()),
+    displayValue: '5 februari 2023',
     key: 'mock.expiryDate',
     // sourceCardId: _kVOGId,
   ),
@@ -9,6 +9,8 @@ class PinManager {
 
   bool get isRegistered => _selectedPin != null;
 
+  bool get isBlocked => _attempts >= _kMaxAttempts;
+
   void setPin(String pin) {
     if (isRegistered) throw StateError('Pin already configured');
     _selectedPin = pin;
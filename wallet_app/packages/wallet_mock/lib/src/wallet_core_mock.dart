@@ -56,6 +56,13 @@ class WalletCoreMock extends _FlutterRustBridgeTasksMeta implements WalletCore {
     }
   }
 
+  @override
+  Future<StartDisclosureResult> getDisclosureProposal({hint}) async {
+    final disclosure = _ongoingDisclosure;
+    assert(disclosure != null, 'No ongoing disclosure to get the proposal for');
+    return disclosure!;
+  }
+
   @override
   Future<void> cancelDisclosure({hint}) async {
     final disclosure = _ongoingDisclosure;
@@ -114,6 +121,21 @@ class WalletCoreMock extends _FlutterRustBridgeTasksMeta implements WalletCore {
     // Stub only, no need to clear it on the mock
   }
 
+  @override
+  Future<void> clearClockSkewStream({hint}) async {
+    // Stub only, no need to clear it on the mock
+  }
+
+  @override
+  Future<void> clearConnectivityStream({hint}) async {
+    // Stub only, no need to clear it on the mock
+  }
+
+  @override
+  Future<void> clearCrashStream({hint}) async {
+    // Stub only, no need to clear it on the mock
+  }
+
   @override
   Future<void> clearLockStream({hint}) async {
     // Stub only, no need to clear it on the mock
@@ -125,15 +147,21 @@ class WalletCoreMock extends _FlutterRustBridgeTasksMeta implements WalletCore {
   @override
   Future<String> createPidIssuanceRedirectUri({hint}) async => kMockPidIssuanceRedirectUri;
 
+  @override
+  Future<String> createPidRenewalRedirectUri({hint}) async => kMockPidIssuanceRedirectUri;
+
   @override
   Future<bool> hasRegistration({hint}) async => _pinManager.isRegistered;
 
+  @override
+  Future<bool> isFeatureEnabled({required String feature, hint}) async => false;
+
   @override
   Future<IdentifyUriResult> identifyUri({required String uri, hint}) async {
     final jsonPayload = jsonDecode(Uri.decodeComponent(Uri.parse(uri).fragment));
     final type = jsonPayload['type'] as String;
     if (type == 'verify') return IdentifyUriResult.Disclosure;
-    if (type == 'issue') throw UnsupportedError('Issue not yet supported');
+    if (type == 'issue') return IdentifyUriResult.IssuanceOffer;
     if (type == 'sign') throw UnsupportedError('Sign not yet supported');
     throw UnsupportedError('Unsupported uri: $uri');
   }
@@ -156,6 +184,16 @@ class WalletCoreMock extends _FlutterRustBridgeTasksMeta implements WalletCore {
   @override
   Future<void> lockWallet({hint}) async => _wallet.lock();
 
+  @override
+  Future<void> registerActivity({hint}) async {
+    // Stub only, the mock does not lock automatically
+  }
+
+  @override
+  Future<void> notifyAppState({required AppState state, hint}) async {
+    // Stub only, the mock does not lock automatically
+  }
+
   @override
   Future<void> register({required String pin, hint}) async {
     _pinManager.setPin(pin);
@@ -174,6 +212,15 @@ class WalletCoreMock extends _FlutterRustBridgeTasksMeta implements WalletCore {
     _eventLog.reset();
   }
 
+  @override
+  Future<WalletInstructionResult> deregisterWallet({required String pin, hint}) async {
+    final result = _pinManager.checkPin(pin);
+    if (result is! WalletInstructionResult_Ok) return result;
+
+    await resetWallet();
+    return result;
+  }
+
   @override
   Stream<List<Card>> setCardsStream({hint}) => _wallet.cardsStream;
 
@@ -187,6 +234,20 @@ class WalletCoreMock extends _FlutterRustBridgeTasksMeta implements WalletCore {
     );
   }
 
+  @override
+  Stream<bool> setClockSkewStream({hint}) => Stream.value(false);
+
+  @override
+  Stream<bool> setConnectivityStream({hint}) => Stream.value(true);
+
+  @override
+  Stream<String> setCrashStream({hint}) => const Stream.empty();
+
+  @override
+  Future<void> setLocale({required String locale, hint}) async {
+    // Stub only, the mock does not format attribute values
+  }
+
   @override
   Stream<bool> setLockStream({hint}) => _wallet.lockedStream;
 
@@ -202,12 +263,29 @@ class WalletCoreMock extends _FlutterRustBridgeTasksMeta implements WalletCore {
     return result;
   }
 
+  @override
+  Future<PinLockoutState> getPinLockout({hint}) async => PinLockoutState(isBlocked: _pinManager.isBlocked);
+
   @override
   Future<List<WalletEvent>> getHistory({hint}) async => _eventLog.log;
 
   @override
   Future<List<WalletEvent>> getHistoryForCard({required String docType, hint}) async =>
       _eventLog.logForDocType(docType);
+
+  @override
+  Future<HistoryPage> getHistoryPage({required int offset, required int limit, hint}) async {
+    // The mock does not group the events, it only pages through them
+    final events = _eventLog.log;
+    return HistoryPage(events: events.skip(offset).take(limit).toList(), groups: [], totalCount: events.length);
+  }
+
+  @override
+  Future<CardsPage> getCardsPage({required int offset, required int limit, hint}) async {
+    // The mock does not sort or group the cards, it only pages through them
+    final cards = await _wallet.cardsStream.first;
+    return CardsPage(cards: cards.skip(offset).take(limit).toList(), groups: [], totalCount: cards.length);
+  }
 }
 
 /// Helper class to make [WalletCoreMock] satisfy [WalletCore]
@@ -225,12 +303,24 @@ class _FlutterRustBridgeTasksMeta {
 
   FlutterRustBridgeTaskConstMeta get kClearConfigurationStreamConstMeta => throw UnimplementedError();
 
+  FlutterRustBridgeTaskConstMeta get kClearClockSkewStreamConstMeta => throw UnimplementedError();
+
+  FlutterRustBridgeTaskConstMeta get kClearConnectivityStreamConstMeta => throw UnimplementedError();
+
+  FlutterRustBridgeTaskConstMeta get kClearCrashStreamConstMeta => throw UnimplementedError();
+
   FlutterRustBridgeTaskConstMeta get kClearLockStreamConstMeta => throw UnimplementedError();
 
   FlutterRustBridgeTaskConstMeta get kContinuePidIssuanceConstMeta => throw UnimplementedError();
 
   FlutterRustBridgeTaskConstMeta get kCreatePidIssuanceRedirectUriConstMeta => throw UnimplementedError();
 
+  FlutterRustBridgeTaskConstMeta get kCreatePidRenewalRedirectUriConstMeta => throw UnimplementedError();
+
+  FlutterRustBridgeTaskConstMeta get kEnterBackgroundConstMeta => throw UnimplementedError();
+
+  FlutterRustBridgeTaskConstMeta get kEnterForegroundConstMeta => throw UnimplementedError();
+
   FlutterRustBridgeTaskConstMeta get kHasRegistrationConstMeta => throw UnimplementedError();
 
   FlutterRustBridgeTaskConstMeta get kIdentifyUriConstMeta => throw UnimplementedError();
@@ -243,23 +333,43 @@ class _FlutterRustBridgeTasksMeta {
 
   FlutterRustBridgeTaskConstMeta get kLockWalletConstMeta => throw UnimplementedError();
 
+  FlutterRustBridgeTaskConstMeta get kRegisterActivityConstMeta => throw UnimplementedError();
+
   FlutterRustBridgeTaskConstMeta get kRegisterConstMeta => throw UnimplementedError();
 
   FlutterRustBridgeTaskConstMeta get kRejectPidIssuanceConstMeta => throw UnimplementedError();
 
   FlutterRustBridgeTaskConstMeta get kResetWalletConstMeta => throw UnimplementedError();
 
+  FlutterRustBridgeTaskConstMeta get kDeregisterWalletConstMeta => throw UnimplementedError();
+
   FlutterRustBridgeTaskConstMeta get kSetCardsStreamConstMeta => throw UnimplementedError();
 
   FlutterRustBridgeTaskConstMeta get kSetConfigurationStreamConstMeta => throw UnimplementedError();
 
+  FlutterRustBridgeTaskConstMeta get kSetClockSkewStreamConstMeta => throw UnimplementedError();
+
+  FlutterRustBridgeTaskConstMeta get kSetConnectivityStreamConstMeta => throw UnimplementedError();
+
+  FlutterRustBridgeTaskConstMeta get kSetCrashStreamConstMeta => throw UnimplementedError();
+
+  FlutterRustBridgeTaskConstMeta get kSetLocaleConstMeta => throw UnimplementedError();
+
   FlutterRustBridgeTaskConstMeta get kSetLockStreamConstMeta => throw UnimplementedError();
 
   FlutterRustBridgeTaskConstMeta get kStartDisclosureConstMeta => throw UnimplementedError();
 
   FlutterRustBridgeTaskConstMeta get kUnlockWalletConstMeta => throw UnimplementedError();
 
+  FlutterRustBridgeTaskConstMeta get kGetPinLockoutConstMeta => throw UnimplementedError();
+
+  FlutterRustBridgeTaskConstMeta get kGetCardsPageConstMeta => throw UnimplementedError();
+
   FlutterRustBridgeTaskConstMeta get kGetHistoryConstMeta => throw UnimplementedError();
 
   FlutterRustBridgeTaskConstMeta get kGetHistoryForCardConstMeta => throw UnimplementedError();
+
+  FlutterRustBridgeTaskConstMeta get kGetHistoryPageConstMeta => throw UnimplementedError();
+
+  FlutterRustBridgeTaskConstMeta get kGetDisclosureProposalConstMeta => throw UnimplementedError();
 }
@@ -34,5 +34,12 @@ void main() {
       expect(result.argument, testUri,
           reason: 'The original uri should be passed to the correct screen as an argument');
     });
+
+    test('Issuance offer uri should result in an IssuanceNavigationRequest', () async {
+      const testUri = 'https://issuance_offer.org';
+      when(mockWalletCore.identifyUri(testUri)).thenAnswer((realInvocation) async => IdentifyUriResult.IssuanceOffer);
+      final result = await uriRepository.processUri(Uri.parse(testUri));
+      expect(result, isA<IssuanceNavigationRequest>());
+    });
   });
 }
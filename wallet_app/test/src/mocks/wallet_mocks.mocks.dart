@@ -110,6 +110,26 @@ class _FakeFlutterRustBridgeTaskConstMeta_5 extends _i1.SmartFake implements _i2
         );
 }
 
+class _FakeHistoryPage_6 extends _i1.SmartFake implements _i2.HistoryPage {
+  _FakeHistoryPage_6(
+    Object parent,
+    Invocation parentInvocation,
+  ) : super(
+          parent,
+          parentInvocation,
+        );
+}
+
+class _FakeCardsPage_7 extends _i1.SmartFake implements _i2.CardsPage {
+  _FakeCardsPage_7(
+    Object parent,
+    Invocation parentInvocation,
+  ) : super(
+          parent,
+          parentInvocation,
+        );
+}
+
 /// A class which mocks [Mapper].
 ///
 /// See the documentation for Mockito's code generation for more information.
@@ -564,6 +584,16 @@ class MockTypedWalletCore extends _i1.Mock implements _i18.TypedWalletCore {
         returnValueForMissingStub: _i4.Future<String>.value(''),
       ) as _i4.Future<String>);
 
+  @override
+  _i4.Future<String> createPidRenewalRedirectUri() => (super.noSuchMethod(
+        Invocation.method(
+          #createPidRenewalRedirectUri,
+          [],
+        ),
+        returnValue: _i4.Future<String>.value(''),
+        returnValueForMissingStub: _i4.Future<String>.value(''),
+      ) as _i4.Future<String>);
+
   @override
   _i4.Future<_i2.IdentifyUriResult> identifyUri(String? uri) => (super.noSuchMethod(
         Invocation.method(
@@ -720,6 +750,66 @@ class MockTypedWalletCore extends _i1.Mock implements _i18.TypedWalletCore {
         returnValueForMissingStub: _i4.Future<List<_i2.WalletEvent>>.value(<_i2.WalletEvent>[]),
       ) as _i4.Future<List<_i2.WalletEvent>>);
 
+  @override
+  _i4.Future<_i2.HistoryPage> getHistoryPage(
+    int? offset,
+    int? limit,
+  ) =>
+      (super.noSuchMethod(
+        Invocation.method(
+          #getHistoryPage,
+          [
+            offset,
+            limit,
+          ],
+        ),
+        returnValue: _i4.Future<_i2.HistoryPage>.value(_FakeHistoryPage_6(
+          this,
+          Invocation.method(
+            #getHistoryPage,
+            [
+              offset,
+              limit,
+            ],
+          ),
+        )),
+      ) as _i4.Future<_i2.HistoryPage>);
+
+  @override
+  _i4.Future<_i2.CardsPage> getCardsPage(
+    int? offset,
+    int? limit,
+  ) =>
+      (super.noSuchMethod(
+        Invocation.method(
+          #getCardsPage,
+          [
+            offset,
+            limit,
+          ],
+        ),
+        returnValue: _i4.Future<_i2.CardsPage>.value(_FakeCardsPage_7(
+          this,
+          Invocation.method(
+            #getCardsPage,
+            [
+              offset,
+              limit,
+            ],
+          ),
+        )),
+      ) as _i4.Future<_i2.CardsPage>);
+
+  @override
+  _i4.Future<void> setLocale(String? locale) => (super.noSuchMethod(
+        Invocation.method(
+          #setLocale,
+          [locale],
+        ),
+        returnValue: _i4.Future<void>.value(),
+        returnValueForMissingStub: _i4.Future<void>.value(),
+      ) as _i4.Future<void>);
+
   @override
   _i4.Future<T> call<T>(_i4.Future<T> Function(_i2.WalletCore)? runnable) => (super.noSuchMethod(
         Invocation.method(
@@ -1160,6 +1250,58 @@ class MockWalletCore extends _i1.Mock implements _i2.WalletCore {
         ),
       ) as _i2.FlutterRustBridgeTaskConstMeta);
 
+  @override
+  _i2.FlutterRustBridgeTaskConstMeta get kSetConnectivityStreamConstMeta => (super.noSuchMethod(
+        Invocation.getter(#kSetConnectivityStreamConstMeta),
+        returnValue: _FakeFlutterRustBridgeTaskConstMeta_5(
+          this,
+          Invocation.getter(#kSetConnectivityStreamConstMeta),
+        ),
+        returnValueForMissingStub: _FakeFlutterRustBridgeTaskConstMeta_5(
+          this,
+          Invocation.getter(#kSetConnectivityStreamConstMeta),
+        ),
+      ) as _i2.FlutterRustBridgeTaskConstMeta);
+
+  @override
+  _i2.FlutterRustBridgeTaskConstMeta get kClearConnectivityStreamConstMeta => (super.noSuchMethod(
+        Invocation.getter(#kClearConnectivityStreamConstMeta),
+        returnValue: _FakeFlutterRustBridgeTaskConstMeta_5(
+          this,
+          Invocation.getter(#kClearConnectivityStreamConstMeta),
+        ),
+        returnValueForMissingStub: _FakeFlutterRustBridgeTaskConstMeta_5(
+          this,
+          Invocation.getter(#kClearConnectivityStreamConstMeta),
+        ),
+      ) as _i2.FlutterRustBridgeTaskConstMeta);
+
+  @override
+  _i2.FlutterRustBridgeTaskConstMeta get kSetClockSkewStreamConstMeta => (super.noSuchMethod(
+        Invocation.getter(#kSetClockSkewStreamConstMeta),
+        returnValue: _FakeFlutterRustBridgeTaskConstMeta_5(
+          this,
+          Invocation.getter(#kSetClockSkewStreamConstMeta),
+        ),
+        returnValueForMissingStub: _FakeFlutterRustBridgeTaskConstMeta_5(
+          this,
+          Invocation.getter(#kSetClockSkewStreamConstMeta),
+        ),
+      ) as _i2.FlutterRustBridgeTaskConstMeta);
+
+  @override
+  _i2.FlutterRustBridgeTaskConstMeta get kClearClockSkewStreamConstMeta => (super.noSuchMethod(
+        Invocation.getter(#kClearClockSkewStreamConstMeta),
+        returnValue: _FakeFlutterRustBridgeTaskConstMeta_5(
+          this,
+          Invocation.getter(#kClearClockSkewStreamConstMeta),
+        ),
+        returnValueForMissingStub: _FakeFlutterRustBridgeTaskConstMeta_5(
+          this,
+          Invocation.getter(#kClearClockSkewStreamConstMeta),
+        ),
+      ) as _i2.FlutterRustBridgeTaskConstMeta);
+
   @override
   _i2.FlutterRustBridgeTaskConstMeta get kSetConfigurationStreamConstMeta => (super.noSuchMethod(
         Invocation.getter(#kSetConfigurationStreamConstMeta),
@@ -1186,6 +1328,19 @@ class MockWalletCore extends _i1.Mock implements _i2.WalletCore {
         ),
       ) as _i2.FlutterRustBridgeTaskConstMeta);
 
+  @override
+  _i2.FlutterRustBridgeTaskConstMeta get kIsFeatureEnabledConstMeta => (super.noSuchMethod(
+        Invocation.getter(#kIsFeatureEnabledConstMeta),
+        returnValue: _FakeFlutterRustBridgeTaskConstMeta_5(
+          this,
+          Invocation.getter(#kIsFeatureEnabledConstMeta),
+        ),
+        returnValueForMissingStub: _FakeFlutterRustBridgeTaskConstMeta_5(
+          this,
+          Invocation.getter(#kIsFeatureEnabledConstMeta),
+        ),
+      ) as _i2.FlutterRustBridgeTaskConstMeta);
+
   @override
   _i2.FlutterRustBridgeTaskConstMeta get kSetCardsStreamConstMeta => (super.noSuchMethod(
         Invocation.getter(#kSetCardsStreamConstMeta),
@@ -1290,6 +1445,19 @@ class MockWalletCore extends _i1.Mock implements _i2.WalletCore {
         ),
       ) as _i2.FlutterRustBridgeTaskConstMeta);
 
+  @override
+  _i2.FlutterRustBridgeTaskConstMeta get kCreatePidRenewalRedirectUriConstMeta => (super.noSuchMethod(
+        Invocation.getter(#kCreatePidRenewalRedirectUriConstMeta),
+        returnValue: _FakeFlutterRustBridgeTaskConstMeta_5(
+          this,
+          Invocation.getter(#kCreatePidRenewalRedirectUriConstMeta),
+        ),
+        returnValueForMissingStub: _FakeFlutterRustBridgeTaskConstMeta_5(
+          this,
+          Invocation.getter(#kCreatePidRenewalRedirectUriConstMeta),
+        ),
+      ) as _i2.FlutterRustBridgeTaskConstMeta);
+
   @override
   _i2.FlutterRustBridgeTaskConstMeta get kCancelPidIssuanceConstMeta => (super.noSuchMethod(
         Invocation.getter(#kCancelPidIssuanceConstMeta),
@@ -1482,6 +1650,50 @@ class MockWalletCore extends _i1.Mock implements _i2.WalletCore {
         returnValueForMissingStub: _i4.Future<void>.value(),
       ) as _i4.Future<void>);
 
+  @override
+  _i4.Stream<bool> setConnectivityStream({dynamic hint}) => (super.noSuchMethod(
+        Invocation.method(
+          #setConnectivityStream,
+          [],
+          {#hint: hint},
+        ),
+        returnValue: _i4.Stream<bool>.empty(),
+        returnValueForMissingStub: _i4.Stream<bool>.empty(),
+      ) as _i4.Stream<bool>);
+
+  @override
+  _i4.Future<void> clearConnectivityStream({dynamic hint}) => (super.noSuchMethod(
+        Invocation.method(
+          #clearConnectivityStream,
+          [],
+          {#hint: hint},
+        ),
+        returnValue: _i4.Future<void>.value(),
+        returnValueForMissingStub: _i4.Future<void>.value(),
+      ) as _i4.Future<void>);
+
+  @override
+  _i4.Stream<bool> setClockSkewStream({dynamic hint}) => (super.noSuchMethod(
+        Invocation.method(
+          #setClockSkewStream,
+          [],
+          {#hint: hint},
+        ),
+        returnValue: _i4.Stream<bool>.empty(),
+        returnValueForMissingStub: _i4.Stream<bool>.empty(),
+      ) as _i4.Stream<bool>);
+
+  @override
+  _i4.Future<void> clearClockSkewStream({dynamic hint}) => (super.noSuchMethod(
+        Invocation.method(
+          #clearClockSkewStream,
+          [],
+          {#hint: hint},
+        ),
+        returnValue: _i4.Future<void>.value(),
+        returnValueForMissingStub: _i4.Future<void>.value(),
+      ) as _i4.Future<void>);
+
   @override
   _i4.Stream<_i2.FlutterConfiguration> setConfigurationStream({dynamic hint}) => (super.noSuchMethod(
         Invocation.method(
@@ -1504,6 +1716,24 @@ class MockWalletCore extends _i1.Mock implements _i2.WalletCore {
         returnValueForMissingStub: _i4.Future<void>.value(),
       ) as _i4.Future<void>);
 
+  @override
+  _i4.Future<bool> isFeatureEnabled({
+    required String? feature,
+    dynamic hint,
+  }) =>
+      (super.noSuchMethod(
+        Invocation.method(
+          #isFeatureEnabled,
+          [],
+          {
+            #feature: feature,
+            #hint: hint,
+          },
+        ),
+        returnValue: _i4.Future<bool>.value(false),
+        returnValueForMissingStub: _i4.Future<bool>.value(false),
+      ) as _i4.Future<bool>);
+
   @override
   _i4.Stream<List<_i2.Card>> setCardsStream({dynamic hint}) => (super.noSuchMethod(
         Invocation.method(
@@ -1633,6 +1863,17 @@ class MockWalletCore extends _i1.Mock implements _i2.WalletCore {
         returnValueForMissingStub: _i4.Future<String>.value(''),
       ) as _i4.Future<String>);
 
+  @override
+  _i4.Future<String> createPidRenewalRedirectUri({dynamic hint}) => (super.noSuchMethod(
+        Invocation.method(
+          #createPidRenewalRedirectUri,
+          [],
+          {#hint: hint},
+        ),
+        returnValue: _i4.Future<String>.value(''),
+        returnValueForMissingStub: _i4.Future<String>.value(''),
+      ) as _i4.Future<String>);
+
   @override
   _i4.Future<void> cancelPidIssuance({dynamic hint}) => (super.noSuchMethod(
         Invocation.method(
@@ -14,6 +14,7 @@ const _kSampleCardAttribute = CardAttribute(
   key: 'card.key',
   labels: _kSampleCardAttributeLabels,
   value: _kSampleCardValue,
+  displayValue: '-',
 );
 
 void main() {
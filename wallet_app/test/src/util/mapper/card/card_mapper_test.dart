@@ -12,8 +12,18 @@ import 'package:wallet_core/core.dart';
 import '../../../mocks/wallet_mocks.dart';
 
 const _kSampleDocType = 'pid_id';
-const _kSampleCardAttributeName = CardAttribute(key: 'name', labels: [], value: CardValue_String(value: 'Willeke'));
-const _kSampleCardAttributeCity = CardAttribute(key: 'city', labels: [], value: CardValue_String(value: 'Den Haag'));
+const _kSampleCardAttributeName = CardAttribute(
+  key: 'name',
+  labels: [],
+  value: CardValue_String(value: 'Willeke'),
+  displayValue: 'Willeke',
+);
+const _kSampleCardAttributeCity = CardAttribute(
+  key: 'city',
+  labels: [],
+  value: CardValue_String(value: 'Den Haag'),
+  displayValue: 'Den Haag',
+);
 
 const _kSampleCard = Card(
   persistence: CardPersistence_InMemory(),
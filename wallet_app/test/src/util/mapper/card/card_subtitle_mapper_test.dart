@@ -8,8 +8,18 @@ import 'package:wallet_core/core.dart';
 
 import '../../../mocks/wallet_mocks.dart';
 
-const _kSampleCardAttributeName = CardAttribute(key: 'name', labels: [], value: CardValue_String(value: 'Willeke'));
-const _kSampleCardAttributeCity = CardAttribute(key: 'city', labels: [], value: CardValue_String(value: 'Den Haag'));
+const _kSampleCardAttributeName = CardAttribute(
+  key: 'name',
+  labels: [],
+  value: CardValue_String(value: 'Willeke'),
+  displayValue: 'Willeke',
+);
+const _kSampleCardAttributeCity = CardAttribute(
+  key: 'city',
+  labels: [],
+  value: CardValue_String(value: 'Den Haag'),
+  displayValue: 'Den Haag',
+);
 const _kSampleNameSubtitle = {'en': 'Willeke', 'nl': 'Willeke'};
 const _kSampleCitySubtitle = {'en': 'Den Haag', 'nl': 'Den Haag'};
 
@@ -88,5 +88,18 @@ void main() {
       final result = errorMapper.map(errorJson);
       expect(result, const CoreRedirectUriError(defaultDescription, redirectError: RedirectError.serverError));
     });
+
+    test(
+        'mapping FlutterApiErrorType.redirectUri with cancelled kind results in CoreRedirectUriError with RedirectError.cancelled',
+        () {
+      final error = FlutterApiError(
+        type: FlutterApiErrorType.redirectUri,
+        description: defaultDescription,
+        data: {'redirect_error': 'access_denied', 'redirect_error_kind': 'cancelled'},
+      );
+      final errorJson = jsonEncode(error);
+      final result = errorMapper.map(errorJson);
+      expect(result, const CoreRedirectUriError(defaultDescription, redirectError: RedirectError.cancelled));
+    });
   });
 }
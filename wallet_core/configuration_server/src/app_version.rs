@@ -0,0 +1,170 @@
+//! Selection of the configuration that is served to the app, based on its version, so that breaking changes to the
+//! configuration are only served to versions of the app that understand them.
+use std::{cmp::Ordering, fmt, num::ParseIntError, str::FromStr};
+
+use http::{header, HeaderMap};
+use serde::Deserialize;
+
+use crate::rollout::ServedConfig;
+
+pub const APP_VERSION_HEADER: &str = "x-app-version";
+
+/// A version number consisting of numeric components, e.g. `1.2.3`. Missing components are considered to be zero, so
+/// that `1.2` equals `1.2.0`, and any pre-release or build suffix (e.g. `-rc1` or `+45`) is ignored.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "String")]
+pub struct AppVersion(Vec<u64>);
+
+impl FromStr for AppVersion {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let version = s.trim().split(['-', '+']).next().unwrap_or_default();
+        let components = version.split('.').map(str::parse).collect::<Result<_, _>>()?;
+
+        Ok(Self(components))
+    }
+}
+
+impl TryFrom<String> for AppVersion {
+    type Error = ParseIntError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl fmt::Display for AppVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let components = self.0.iter().map(u64::to_string).collect::<Vec<_>>();
+        f.write_str(&components.join("."))
+    }
+}
+
+impl Ord for AppVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let len = self.0.len().max(other.0.len());
+        let component = |version: &Self, idx: usize| version.0.get(idx).copied().unwrap_or_default();
+
+        (0..len)
+            .map(|idx| component(self, idx).cmp(&component(other, idx)))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for AppVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for AppVersion {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for AppVersion {}
+
+/// Determine the version of the app from the `X-App-Version` header or, when absent, from the `<product>/<version>`
+/// token in the `User-Agent` header of which the product is `user_agent_product`, if configured.
+pub fn app_version(headers: &HeaderMap, user_agent_product: Option<&str>) -> Option<AppVersion> {
+    if let Some(version) = headers.get(APP_VERSION_HEADER) {
+        return version.to_str().ok()?.parse().ok();
+    }
+
+    let user_agent_product = user_agent_product?;
+    headers
+        .get(header::USER_AGENT)?
+        .to_str()
+        .ok()?
+        .split_whitespace()
+        .filter_map(|token| token.split_once('/'))
+        .find(|(product, _)| *product == user_agent_product)
+        .and_then(|(_, version)| version.parse().ok())
+}
+
+/// The configuration JWTs, of which the one with the highest minimum version that the app satisfies is served.
+pub struct ConfigJwts {
+    default: ServedConfig,
+    /// Sorted by descending minimum version.
+    by_min_version: Vec<(AppVersion, ServedConfig)>,
+}
+
+impl ConfigJwts {
+    pub fn new(default: ServedConfig, mut by_min_version: Vec<(AppVersion, ServedConfig)>) -> Self {
+        by_min_version.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+        Self {
+            default,
+            by_min_version,
+        }
+    }
+
+    /// The JWT for the specified version of the app and, if the configuration for that version is being rolled out,
+    /// the rollout ID of the wallet. The default JWT is served when the version is unknown or lower than all configured
+    /// minimum versions.
+    pub fn select(&self, app_version: Option<&AppVersion>, rollout_id: Option<&str>) -> &[u8] {
+        app_version
+            .and_then(|app_version| {
+                self.by_min_version
+                    .iter()
+                    .find(|(min_version, _)| min_version <= app_version)
+            })
+            .map(|(_, served_config)| served_config)
+            .unwrap_or(&self.default)
+            .select(rollout_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::HeaderValue;
+
+    use super::*;
+
+    fn version(s: &str) -> AppVersion {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_app_version_ordering() {
+        assert_eq!(version("1.2"), version("1.2.0"));
+        assert_eq!(version("1.2.3+45"), version("1.2.3"));
+        assert!(version("1.10.0") > version("1.9.9"));
+        assert!(version("2") > version("1.99"));
+        assert!("1.x".parse::<AppVersion>().is_err());
+    }
+
+    #[test]
+    fn test_app_version_from_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::USER_AGENT,
+            HeaderValue::from_static("Dart/3.2 (dart:io) NLWallet/1.4.0"),
+        );
+
+        assert_eq!(app_version(&headers, None), None);
+        assert_eq!(app_version(&headers, Some("NLWallet")), Some(version("1.4.0")));
+
+        // The dedicated header takes precedence over the user agent.
+        headers.insert(APP_VERSION_HEADER, HeaderValue::from_static("1.5.1"));
+        assert_eq!(app_version(&headers, Some("NLWallet")), Some(version("1.5.1")));
+    }
+
+    #[test]
+    fn test_config_jwts_select() {
+        let jwt = |jwt: &[u8]| ServedConfig::new(jwt.to_vec(), None).unwrap();
+        let jwts = ConfigJwts::new(
+            jwt(b"default"),
+            vec![(version("1.2"), jwt(b"1.2")), (version("2.0"), jwt(b"2.0"))],
+        );
+
+        assert_eq!(jwts.select(None, None), b"default");
+        assert_eq!(jwts.select(Some(&version("1.1.9")), None), b"default");
+        assert_eq!(jwts.select(Some(&version("1.2.0")), None), b"1.2");
+        assert_eq!(jwts.select(Some(&version("1.99")), None), b"1.2");
+        assert_eq!(jwts.select(Some(&version("2.1")), None), b"2.0");
+    }
+}
@@ -1,10 +1,20 @@
 use std::{env, path::PathBuf};
 
+pub mod app_version;
+pub mod reload;
+pub mod rollout;
 pub mod server;
 pub mod settings;
+pub mod signing;
 
-pub fn read_config_jwt() -> Vec<u8> {
+/// The file containing the default configuration, which is either signed as compact JWS or, when the server signs the
+/// configurations itself, the configuration as JSON.
+pub fn default_config_path(server_signed: bool) -> PathBuf {
     let root_path = env::var("CARGO_MANIFEST_DIR").map(PathBuf::from).unwrap_or_default();
-    let config_file = root_path.join("wallet-config-jws-compact.txt");
-    std::fs::read(config_file.as_path()).unwrap()
+    let file_name = if server_signed {
+        "wallet-config.json"
+    } else {
+        "wallet-config-jws-compact.txt"
+    };
+    root_path.join(file_name)
 }
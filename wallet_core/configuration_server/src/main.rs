@@ -1,20 +1,24 @@
 use std::error::Error;
 
-use configuration_server::read_config_jwt;
+use configuration_server::default_config_path;
 
 use crate::settings::Settings;
 
+mod app_version;
+mod reload;
+mod rollout;
 mod server;
 mod settings;
+mod signing;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     tracing_subscriber::fmt::init();
 
     let settings = Settings::new()?;
-    let config_jwt = read_config_jwt();
+    let config_path = default_config_path(!settings.signing_keys.is_empty());
 
-    server::serve(settings, config_jwt).await?;
+    server::serve(settings, config_path).await?;
 
     Ok(())
 }
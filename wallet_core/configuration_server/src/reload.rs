@@ -0,0 +1,204 @@
+//! Loading of the served configurations from their files, which are checked for changes periodically, so that a
+//! changed configuration is served without restarting the server.
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime},
+};
+
+use tokio::{task::JoinHandle, time};
+use tracing::{info, warn};
+
+use crate::{
+    app_version::ConfigJwts,
+    rollout::{RolloutError, RolloutJwt, ServedConfig},
+    settings::{AppVersionConfig, RolloutSettings, Settings, SigningKeySettings},
+    signing::{ConfigSigningKeys, SigningError},
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigLoadError {
+    #[error("could not read configuration {0}: {1}")]
+    Io(String, #[source] io::Error),
+    #[error("could not sign configuration: {0}")]
+    Signing(#[from] SigningError),
+    #[error("invalid rollout: {0}")]
+    Rollout(#[from] RolloutError),
+}
+
+/// The configurations that are currently served.
+pub struct LoadedConfigs {
+    pub config_jwts: ConfigJwts,
+    /// The time at which the configurations were loaded, which is used as their modification time.
+    pub last_modified: SystemTime,
+}
+
+pub struct ConfigLoader {
+    default_config_path: PathBuf,
+    rollout: Option<RolloutSettings>,
+    app_version_configs: Vec<AppVersionConfig>,
+    signing_keys: Vec<SigningKeySettings>,
+}
+
+impl ConfigLoader {
+    pub fn new(default_config_path: PathBuf, settings: &Settings) -> Self {
+        Self {
+            default_config_path,
+            rollout: settings.rollout.clone(),
+            app_version_configs: settings.app_version_configs.clone(),
+            signing_keys: settings.signing_keys.clone(),
+        }
+    }
+
+    /// Read the configurations and, when the server holds signing keys, sign them. The signing keys are read again as
+    /// well, so that a replaced key is picked up.
+    pub fn load(&self) -> Result<LoadedConfigs, ConfigLoadError> {
+        let signing_keys = ConfigSigningKeys::read(&self.signing_keys)?;
+
+        let default_config = load_config(&signing_keys, &self.default_config_path, None, self.rollout.as_ref())?;
+        let app_version_configs = self
+            .app_version_configs
+            .iter()
+            .map(|config| {
+                let served_config = load_config(
+                    &signing_keys,
+                    &config.config_path,
+                    config.signing_key.as_deref(),
+                    config.rollout.as_ref(),
+                )?;
+                Ok((config.min_app_version.clone(), served_config))
+            })
+            .collect::<Result<_, ConfigLoadError>>()?;
+
+        Ok(LoadedConfigs {
+            config_jwts: ConfigJwts::new(default_config, app_version_configs),
+            last_modified: SystemTime::now(),
+        })
+    }
+
+    /// The files that the configurations are loaded from, including the signing keys.
+    fn paths(&self) -> Vec<PathBuf> {
+        let rollouts = self
+            .rollout
+            .iter()
+            .chain(self.app_version_configs.iter().flat_map(|config| &config.rollout));
+
+        std::iter::once(&self.default_config_path)
+            .chain(self.app_version_configs.iter().map(|config| &config.config_path))
+            .chain(rollouts.map(|rollout| &rollout.config_path))
+            .chain(self.signing_keys.iter().map(|key| &key.private_key_path))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Load the configuration in the file at `path`, together with the new configuration that is being rolled out, if any.
+fn load_config(
+    signing_keys: &ConfigSigningKeys,
+    path: &Path,
+    signing_key: Option<&str>,
+    rollout: Option<&RolloutSettings>,
+) -> Result<ServedConfig, ConfigLoadError> {
+    let jwt = signing_keys.sign(read_config(path)?, signing_key)?;
+    let rollout = rollout
+        .map(|rollout| {
+            let jwt = signing_keys.sign(read_config(&rollout.config_path)?, rollout.signing_key.as_deref())?;
+            Ok::<_, ConfigLoadError>(RolloutJwt {
+                percentage: rollout.percentage,
+                jwt,
+            })
+        })
+        .transpose()?;
+
+    let served_config = ServedConfig::new(jwt, rollout)?;
+
+    Ok(served_config)
+}
+
+fn read_config(path: &Path) -> Result<Vec<u8>, ConfigLoadError> {
+    fs::read(path).map_err(|e| ConfigLoadError::Io(path.display().to_string(), e))
+}
+
+fn modification_times(paths: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    paths
+        .iter()
+        .map(|path| fs::metadata(path).and_then(|metadata| metadata.modified()).ok())
+        .collect()
+}
+
+/// Check every `interval` whether any of the files of the configurations was modified and if so, replace the served
+/// configurations in `configs` by newly loaded ones. As the ETag is computed from the served configuration, it changes
+/// along with it. When the configurations cannot be loaded, e.g. because a file is being replaced, the current
+/// configurations are kept and loading them is retried at the next check.
+pub fn start_reload_task(
+    loader: ConfigLoader,
+    configs: Arc<RwLock<Arc<LoadedConfigs>>>,
+    interval: Duration,
+) -> JoinHandle<()> {
+    let paths = loader.paths();
+    let mut interval = time::interval(interval);
+    let mut loaded_modification_times = modification_times(&paths);
+
+    tokio::spawn(async move {
+        loop {
+            interval.tick().await;
+
+            let current_modification_times = modification_times(&paths);
+            if current_modification_times == loaded_modification_times {
+                continue;
+            }
+
+            match loader.load() {
+                Ok(loaded_configs) => {
+                    *configs.write().unwrap() = Arc::new(loaded_configs);
+                    loaded_modification_times = current_modification_times;
+
+                    info!("reloaded configurations");
+                }
+                Err(error) => warn!("could not reload configurations, keeping the current ones: {}", error),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RELOAD_INTERVAL: Duration = Duration::from_millis(10);
+
+    #[tokio::test]
+    async fn test_reload_task() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("wallet-config.jws");
+        fs::write(&path, b"first").unwrap();
+
+        let loader = ConfigLoader {
+            default_config_path: path.clone(),
+            rollout: None,
+            app_version_configs: vec![],
+            signing_keys: vec![],
+        };
+        let configs = Arc::new(RwLock::new(Arc::new(loader.load().unwrap())));
+        let _reload_task = start_reload_task(loader, Arc::clone(&configs), RELOAD_INTERVAL);
+        let served_jwt = || configs.read().unwrap().config_jwts.select(None, None).to_vec();
+
+        assert_eq!(served_jwt(), b"first");
+
+        // A changed file is served after the next check.
+        fs::write(&path, b"second").unwrap();
+        time::sleep(RELOAD_INTERVAL * 10).await;
+        assert_eq!(served_jwt(), b"second");
+
+        // When the file cannot be read, the current configuration is kept.
+        fs::remove_file(&path).unwrap();
+        time::sleep(RELOAD_INTERVAL * 10).await;
+        assert_eq!(served_jwt(), b"second");
+
+        // Loading the file is retried, so that it is served once it can be read again.
+        fs::write(&path, b"third").unwrap();
+        time::sleep(RELOAD_INTERVAL * 10).await;
+        assert_eq!(served_jwt(), b"third");
+    }
+}
@@ -0,0 +1,180 @@
+//! Staged rollout of a new configuration, which is served to a configured percentage of the wallets first, so that
+//! problems with it only affect part of the wallets. Wallets are assigned to the rollout based on the random
+//! identifier that they send in the `X-Rollout-Id` header.
+//!
+//! As wallets reject configurations with a lower version than the highest they have accepted, a wallet that took part
+//! in a rollout keeps the new configuration when the rollout is stopped. The new configuration should therefore have
+//! a higher version than the current one, and a rollout is rolled back by publishing yet another, higher version.
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+/// The number of buckets that wallets are divided into, so that every bucket represents a percent of the wallets.
+const ROLLOUT_BUCKETS: u64 = 100;
+
+#[derive(Debug, thiserror::Error)]
+pub enum RolloutError {
+    #[error("could not read version of configuration: {0}")]
+    Version(#[from] jsonwebtoken::errors::Error),
+    #[error("version {rollout} of the rolled out configuration is not higher than current version {current}")]
+    NotNewer { current: u64, rollout: u64 },
+}
+
+/// A new configuration that is served to the percentage of the wallets that takes part in the rollout.
+pub struct RolloutJwt {
+    pub percentage: u8,
+    pub jwt: Vec<u8>,
+}
+
+/// A configuration JWT, optionally together with a new configuration that is being rolled out.
+pub struct ServedConfig {
+    jwt: Vec<u8>,
+    rollout: Option<RolloutJwt>,
+}
+
+impl ServedConfig {
+    /// Combine the current configuration with the new configuration that is being rolled out, which should have a
+    /// higher version. Otherwise, the wallets that take part in the rollout could never be returned to the current
+    /// configuration.
+    pub fn new(jwt: Vec<u8>, rollout: Option<RolloutJwt>) -> Result<Self, RolloutError> {
+        if let Some(rollout) = &rollout {
+            let current = config_version(&jwt)?;
+            let rollout = config_version(&rollout.jwt)?;
+
+            if rollout <= current {
+                return Err(RolloutError::NotNewer { current, rollout });
+            }
+        }
+
+        Ok(Self { jwt, rollout })
+    }
+
+    /// The new configuration when the wallet with `rollout_id` takes part in the rollout, otherwise the current
+    /// configuration. Wallets that do not send a rollout ID never take part in a rollout.
+    pub fn select(&self, rollout_id: Option<&str>) -> &[u8] {
+        match (&self.rollout, rollout_id) {
+            (Some(rollout), Some(rollout_id)) if rollout_bucket(rollout_id) < u64::from(rollout.percentage) => {
+                &rollout.jwt
+            }
+            _ => &self.jwt,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ConfigVersion {
+    version: u64,
+}
+
+/// Read the version from the claims of a configuration JWT. The signature is not verified, as that is up to the app.
+fn config_version(jwt: &[u8]) -> Result<u64, RolloutError> {
+    let mut validation = Validation::new(Algorithm::ES256);
+    validation.insecure_disable_signature_validation();
+    validation.required_spec_claims.clear();
+    validation.validate_exp = false;
+
+    let claims = jsonwebtoken::decode::<ConfigVersion>(
+        &String::from_utf8_lossy(jwt),
+        &DecodingKey::from_secret(&[]),
+        &validation,
+    )?
+    .claims;
+
+    Ok(claims.version)
+}
+
+/// The bucket of the wallet with `rollout_id`. As the bucket only depends on the rollout ID, increasing the percentage
+/// of a rollout adds wallets to it without removing any.
+fn rollout_bucket(rollout_id: &str) -> u64 {
+    let digest = Sha256::digest(rollout_id.as_bytes());
+
+    // The digest is 32 bytes, so its first 8 bytes can always be converted.
+    u64::from_be_bytes(digest[..8].try_into().unwrap()) % ROLLOUT_BUCKETS
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonwebtoken::{EncodingKey, Header};
+    use p256::{ecdsa::SigningKey, elliptic_curve::rand_core::OsRng, pkcs8::EncodePrivateKey};
+    use serde_json::json;
+
+    use super::*;
+
+    fn config_jwt(version: u64) -> Vec<u8> {
+        let der = SigningKey::random(&mut OsRng).to_pkcs8_der().unwrap();
+        jsonwebtoken::encode(
+            &Header::new(Algorithm::ES256),
+            &json!({ "version": version }),
+            &EncodingKey::from_ec_der(der.as_bytes()),
+        )
+        .unwrap()
+        .into_bytes()
+    }
+
+    #[test]
+    fn test_served_config_select() {
+        let current_jwt = config_jwt(1);
+        let new_jwt = config_jwt(2);
+        let served_config = |percentage| {
+            ServedConfig::new(
+                current_jwt.clone(),
+                Some(RolloutJwt {
+                    percentage,
+                    jwt: new_jwt.clone(),
+                }),
+            )
+            .unwrap()
+        };
+        let rollout_ids = (0..1000).map(|i| format!("wallet-{}", i)).collect::<Vec<_>>();
+        let rolled_out = |served_config: &ServedConfig| {
+            rollout_ids
+                .iter()
+                .filter(|rollout_id| served_config.select(Some(rollout_id.as_str())) == new_jwt)
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+
+        assert!(rolled_out(&served_config(0)).is_empty());
+        assert_eq!(rolled_out(&served_config(100)).len(), rollout_ids.len());
+
+        // Roughly the configured percentage of the wallets takes part in the rollout, and the wallets that take part
+        // at a lower percentage keep taking part when the percentage is increased.
+        let rolled_out_10 = rolled_out(&served_config(10));
+        let rolled_out_50 = rolled_out(&served_config(50));
+        assert!((50..150).contains(&rolled_out_10.len()));
+        assert!((400..600).contains(&rolled_out_50.len()));
+        assert!(rolled_out_10
+            .iter()
+            .all(|rollout_id| rolled_out_50.contains(rollout_id)));
+
+        // Wallets without a rollout ID, or when there is no rollout, receive the current configuration.
+        assert_eq!(served_config(100).select(None), current_jwt);
+        assert_eq!(
+            ServedConfig::new(current_jwt.clone(), None)
+                .unwrap()
+                .select(Some("wallet-0")),
+            current_jwt
+        );
+    }
+
+    #[test]
+    fn test_served_config_rollout_not_newer() {
+        let rollout = |version| RolloutJwt {
+            percentage: 10,
+            jwt: config_jwt(version),
+        };
+
+        assert!(matches!(
+            ServedConfig::new(config_jwt(2), Some(rollout(2))),
+            Err(RolloutError::NotNewer { current: 2, rollout: 2 })
+        ));
+        assert!(matches!(
+            ServedConfig::new(config_jwt(2), Some(rollout(1))),
+            Err(RolloutError::NotNewer { current: 2, rollout: 1 })
+        ));
+        assert!(matches!(
+            ServedConfig::new(b"invalid".to_vec(), Some(rollout(3))),
+            Err(RolloutError::Version(_))
+        ));
+    }
+}
@@ -1,33 +1,90 @@
 use std::{
     error::Error,
     net::{SocketAddr, TcpListener},
+    path::PathBuf,
+    sync::{Arc, RwLock},
+    time::Duration,
 };
 
 use axum::{
     extract::State,
+    headers::{HeaderMapExt, IfModifiedSince, LastModified},
+    middleware,
     response::{IntoResponse, Response},
     routing::get,
     Router,
 };
 use etag::EntityTag;
 use http::{header, HeaderMap, HeaderValue, StatusCode};
+use tower_http::compression::CompressionLayer;
 use tracing::{debug, info};
 
-use super::settings::Settings;
+use wallet_common::{
+    config::ROLLOUT_ID_HEADER,
+    metrics::{install_recorder, metrics_router, track_http_metrics},
+    tls::tls_config,
+};
+
+use super::{
+    app_version::{app_version, APP_VERSION_HEADER},
+    reload::{start_reload_task, ConfigLoader, LoadedConfigs},
+    settings::Settings,
+};
+
+struct ApplicationState {
+    configs: Arc<RwLock<Arc<LoadedConfigs>>>,
+    user_agent_product: Option<String>,
+}
 
-pub async fn serve(settings: Settings, config_jwt: Vec<u8>) -> Result<(), Box<dyn Error>> {
+/// Serve the configuration in the file at `config_path` as the default configuration, and the configurations in
+/// `settings.app_version_configs` to the versions of the app that they apply to. When `settings.signing_keys` is
+/// configured, the files contain the configurations as JSON and are signed by the server. The configurations are
+/// reloaded when their files change.
+pub async fn serve(settings: Settings, config_path: PathBuf) -> Result<(), Box<dyn Error>> {
     let socket = SocketAddr::new(settings.ip, settings.port);
     let listener = TcpListener::bind(socket)?;
-    debug!("listening on {}", socket);
 
-    let app = Router::new().nest("/", health_router()).nest(
-        "/config/v1",
-        Router::new()
-            .route("/wallet-config", get(configuration))
-            .with_state(config_jwt),
+    let loader = ConfigLoader::new(config_path, &settings);
+    let configs = Arc::new(RwLock::new(Arc::new(loader.load()?)));
+    let _config_reload_task = start_reload_task(
+        loader,
+        Arc::clone(&configs),
+        Duration::from_secs(settings.reload_interval_in_seconds),
     );
 
-    axum::Server::from_tcp(listener)?.serve(app.into_make_service()).await?;
+    let application_state = Arc::new(ApplicationState {
+        configs,
+        user_agent_product: settings.user_agent_product,
+    });
+
+    let mut app = Router::new()
+        .nest("/", health_router())
+        .nest(
+            "/config/v1",
+            Router::new()
+                .route("/wallet-config", get(configuration))
+                .with_state(application_state)
+                .layer(CompressionLayer::new()),
+        )
+        .layer(middleware::from_fn(track_http_metrics));
+    if settings.metrics_enabled {
+        app = app.merge(metrics_router(install_recorder()?));
+    }
+
+    match settings.tls {
+        Some(tls) => {
+            let (tls_config, _tls_reload_task) = tls_config(&tls)?;
+
+            debug!("listening on {} using TLS", socket);
+            axum_server::from_tcp_rustls(listener, tls_config)
+                .serve(app.into_make_service())
+                .await?;
+        }
+        None => {
+            debug!("listening on {}", socket);
+            axum::Server::from_tcp(listener)?.serve(app.into_make_service()).await?;
+        }
+    }
 
     Ok(())
 }
@@ -37,12 +94,22 @@ fn health_router() -> Router {
 }
 
 async fn configuration(
-    State(config_jwt): State<Vec<u8>>,
+    State(state): State<Arc<ApplicationState>>,
     headers: HeaderMap,
 ) -> std::result::Result<Response, StatusCode> {
     info!("Received configuration request");
 
-    let config_entity_tag = EntityTag::from_data(config_jwt.as_ref());
+    let app_version = app_version(&headers, state.user_agent_product.as_deref());
+    if let Some(app_version) = &app_version {
+        debug!("Selecting configuration for app version {}", app_version);
+    }
+    let configs = Arc::clone(&state.configs.read().unwrap());
+    let rollout_id = headers
+        .get(ROLLOUT_ID_HEADER)
+        .and_then(|rollout_id| rollout_id.to_str().ok());
+    let config_jwt = configs.config_jwts.select(app_version.as_ref(), rollout_id);
+
+    let config_entity_tag = EntityTag::from_data(config_jwt);
 
     if let Some(etag) = headers.get(header::IF_NONE_MATCH) {
         let entity_tag = etag
@@ -56,14 +123,34 @@ async fn configuration(
             debug!("Configuration is not modified");
             return Err(StatusCode::NOT_MODIFIED);
         }
+    } else if let Some(if_modified_since) = headers.typed_get::<IfModifiedSince>() {
+        // As per RFC 9110, If-Modified-Since is only evaluated when the request does not contain If-None-Match.
+        if !if_modified_since.is_modified(configs.last_modified) {
+            debug!("Configuration is not modified since {:?}", if_modified_since);
+            return Err(StatusCode::NOT_MODIFIED);
+        }
     }
 
-    let mut resp: Response = config_jwt.into_response();
+    let mut resp: Response = config_jwt.to_vec().into_response();
     resp.headers_mut().append(
         header::ETAG,
         // We can safely unwrap here because we know for sure there are no non-ascii characters used.
         HeaderValue::from_str(&config_entity_tag.to_string()).unwrap(),
     );
+    resp.headers_mut()
+        .typed_insert(LastModified::from(configs.last_modified));
+    // The configuration that is served depends on the version of the app and on whether the wallet takes part in a
+    // rollout, which caches should take into account.
+    resp.headers_mut().append(
+        header::VARY,
+        HeaderValue::from_str(&format!(
+            "{}, {}, {}",
+            APP_VERSION_HEADER,
+            header::USER_AGENT,
+            ROLLOUT_ID_HEADER
+        ))
+        .unwrap(),
+    );
 
     info!("Replying with the configuration");
     Ok(resp)
@@ -3,10 +3,66 @@ use std::{env, net::IpAddr, path::PathBuf};
 use config::{Config, ConfigError, Environment, File};
 use serde::Deserialize;
 
+use wallet_common::tls::TlsSettings;
+
+use crate::app_version::AppVersion;
+
 #[derive(Clone, Deserialize)]
 pub struct Settings {
     pub ip: IpAddr,
     pub port: u16,
+    pub metrics_enabled: bool,
+    pub tls: Option<TlsSettings>,
+    /// How often the files of the configurations are checked for changes, after which changed configurations are
+    /// served without restarting the server.
+    pub reload_interval_in_seconds: u64,
+    /// Configurations that are served instead of the default configuration to apps of at least a specific version.
+    #[serde(default)]
+    pub app_version_configs: Vec<AppVersionConfig>,
+    /// When configured, the version of apps that do not send the `X-App-Version` header is taken from the
+    /// `<product>/<version>` token in their `User-Agent` header with this product, e.g. `NLWallet`.
+    pub user_agent_product: Option<String>,
+    /// Keys with which the server signs the configurations itself, of which the first is used unless a configuration
+    /// specifies another. When configured, the configuration files contain the wallet configuration as JSON instead
+    /// of as compact JWS.
+    #[serde(default)]
+    pub signing_keys: Vec<SigningKeySettings>,
+    /// A new default configuration that is rolled out to part of the wallets.
+    pub rollout: Option<RolloutSettings>,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct AppVersionConfig {
+    /// The lowest version of the app that is served this configuration, unless another configuration has a higher
+    /// minimum version that the app also satisfies.
+    pub min_app_version: AppVersion,
+    /// File containing the signed configuration as compact JWS or, when the server holds signing keys, the
+    /// configuration as JSON.
+    pub config_path: PathBuf,
+    /// The name of the key with which the server signs this configuration, e.g. the next key during its rotation.
+    pub signing_key: Option<String>,
+    /// A new configuration for these versions of the app that is rolled out to part of the wallets.
+    pub rollout: Option<RolloutSettings>,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct RolloutSettings {
+    /// The percentage of the wallets that is served the new configuration, from 0 to 100. Increasing it adds wallets
+    /// to the rollout without removing any, after which the new configuration can replace the current one.
+    pub percentage: u8,
+    /// File containing the new configuration, in the same format as the current configuration. Its version should be
+    /// higher than that of the current configuration.
+    pub config_path: PathBuf,
+    /// The name of the key with which the server signs the new configuration.
+    pub signing_key: Option<String>,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct SigningKeySettings {
+    /// The name by which configurations refer to the key.
+    pub name: String,
+    /// File containing the private key in PKCS#8 PEM format.
+    pub private_key_path: PathBuf,
 }
 
 impl Settings {
@@ -18,6 +74,8 @@ impl Settings {
         Config::builder()
             .set_default("ip", "0.0.0.0")?
             .set_default("port", 3005)?
+            .set_default("metrics_enabled", false)?
+            .set_default("reload_interval_in_seconds", 60)?
             .add_source(File::from(config_path.join("config_server.toml")).required(false))
             .add_source(
                 Environment::with_prefix("config_server")
@@ -0,0 +1,143 @@
+//! Signing of the configurations by the server itself, with one of multiple keys. This allows the signing key to be
+//! rotated: apps that embed the next public key are served configurations that are signed with the next key, while
+//! installed apps that only embed the current public key keep receiving configurations that they can verify.
+use std::{fs, io, path::Path};
+
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use p256::{ecdsa::SigningKey, pkcs8::DecodePrivateKey};
+use tracing::debug;
+
+use wallet_common::jwt::jwk_thumbprint;
+
+use crate::settings::SigningKeySettings;
+
+#[derive(Debug, thiserror::Error)]
+pub enum SigningError {
+    #[error("could not read signing key: {0}")]
+    Io(#[from] io::Error),
+    #[error("could not parse signing key: {0}")]
+    Key(#[from] p256::pkcs8::Error),
+    #[error("wallet configuration is not valid JSON: {0}")]
+    Configuration(#[from] serde_json::Error),
+    #[error("could not sign wallet configuration: {0}")]
+    Jwt(#[from] jsonwebtoken::errors::Error),
+    #[error("unknown signing key: {0}")]
+    UnknownKey(String),
+}
+
+pub struct ConfigSigningKey {
+    kid: String,
+    encoding_key: EncodingKey,
+}
+
+impl ConfigSigningKey {
+    /// Read a private key in PKCS#8 PEM format. The JWK thumbprint of its public key is used as `kid`, which the app
+    /// uses to select the public key to verify the configuration with.
+    pub fn read(path: &Path) -> Result<Self, SigningError> {
+        let pem = fs::read_to_string(path)?;
+        let kid = jwk_thumbprint(SigningKey::from_pkcs8_pem(&pem)?.verifying_key());
+        let encoding_key = EncodingKey::from_ec_pem(pem.as_bytes())?;
+
+        Ok(Self { kid, encoding_key })
+    }
+
+    pub fn kid(&self) -> &str {
+        &self.kid
+    }
+
+    /// Sign the wallet configuration, which is passed as JSON, and return it as compact JWS.
+    pub fn sign(&self, config: &[u8]) -> Result<Vec<u8>, SigningError> {
+        let wallet_config: serde_json::Value = serde_json::from_slice(config)?;
+        let header = Header {
+            alg: Algorithm::ES256,
+            kid: Some(self.kid.clone()),
+            ..Default::default()
+        };
+        let jwt = jsonwebtoken::encode(&header, &wallet_config, &self.encoding_key)?;
+
+        Ok(jwt.into_bytes())
+    }
+}
+
+/// The signing keys of the server by name, in the order in which they are configured.
+pub struct ConfigSigningKeys(Vec<(String, ConfigSigningKey)>);
+
+impl ConfigSigningKeys {
+    pub fn read(settings: &[SigningKeySettings]) -> Result<Self, SigningError> {
+        let keys = settings
+            .iter()
+            .map(|key| Ok((key.name.clone(), ConfigSigningKey::read(&key.private_key_path)?)))
+            .collect::<Result<_, SigningError>>()?;
+
+        Ok(Self(keys))
+    }
+
+    /// Sign the configuration with the named key or, when no name is specified, with the first key. When the server
+    /// does not hold any signing keys, the configuration is expected to be signed already and is returned as is.
+    pub fn sign(&self, config: Vec<u8>, name: Option<&str>) -> Result<Vec<u8>, SigningError> {
+        let (name, key) = match name {
+            None if self.0.is_empty() => return Ok(config),
+            None => &self.0[0],
+            Some(name) => self
+                .0
+                .iter()
+                .find(|(key_name, _)| key_name == name)
+                .ok_or_else(|| SigningError::UnknownKey(name.to_string()))?,
+        };
+
+        debug!("Signing configuration with key {} ({})", name, key.kid());
+        key.sign(&config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p256::{elliptic_curve::rand_core::OsRng, pkcs8::EncodePrivateKey};
+    use serde_json::{json, Value};
+
+    use wallet_common::jwt::{validations, Jwt};
+
+    use super::*;
+
+    fn signing_key(name: &str) -> (String, ConfigSigningKey, SigningKey) {
+        let signing_key = SigningKey::random(&mut OsRng);
+        let der = signing_key.to_pkcs8_der().unwrap();
+        let key = ConfigSigningKey {
+            kid: jwk_thumbprint(signing_key.verifying_key()),
+            encoding_key: EncodingKey::from_ec_der(der.as_bytes()),
+        };
+
+        (name.to_string(), key, signing_key)
+    }
+
+    #[test]
+    fn test_config_signing_keys_sign() {
+        let (current_name, current_key, current_signing_key) = signing_key("current");
+        let (next_name, next_key, next_signing_key) = signing_key("next");
+        let keys = ConfigSigningKeys(vec![(current_name, current_key), (next_name, next_key)]);
+        let config = serde_json::to_vec(&json!({"version": 2})).unwrap();
+
+        // Without a name, the first key is used, which the app finds by the `kid` in the header.
+        let jwt: Jwt<Value> = String::from_utf8(keys.sign(config.clone(), None).unwrap())
+            .unwrap()
+            .into();
+        let pubkeys = [*next_signing_key.verifying_key(), *current_signing_key.verifying_key()];
+        jwt.parse_and_verify_from_keys(&pubkeys, &validations()).unwrap();
+        jwt.parse_and_verify_from_keys(&pubkeys[..1], &validations())
+            .unwrap_err();
+
+        let jwt: Jwt<Value> = String::from_utf8(keys.sign(config.clone(), Some("next")).unwrap())
+            .unwrap()
+            .into();
+        jwt.parse_and_verify_from_keys(&pubkeys[..1], &validations()).unwrap();
+
+        assert!(matches!(
+            keys.sign(config.clone(), Some("unknown")),
+            Err(SigningError::UnknownKey(_))
+        ));
+
+        // Without keys, the configuration is expected to be signed already.
+        let keys = ConfigSigningKeys(vec![]);
+        assert_eq!(keys.sign(config.clone(), None).unwrap(), config);
+    }
+}
@@ -3,7 +3,10 @@ use quote::{quote, quote_spanned};
 use syn::{parse_macro_input, spanned::Spanned, ItemFn};
 
 /// Converts the body of a function to an asynchronous task and executes it on the flutter_api's tokio runtime.
-/// This macro can only be applied in the `flutter_api` crate, because it generates code using `crate::async_runtime`.
+/// A panic in the task is caught and reported through `crate::crash::report_panic()`, which emits it on the crash
+/// stream before exiting the process.
+/// This macro can only be applied in the `flutter_api` crate, because it generates code using `crate::async_runtime`
+/// and `crate::crash`.
 #[proc_macro_attribute]
 pub fn async_runtime(_attr: TokenStream, item: TokenStream) -> TokenStream {
     let ItemFn {
@@ -25,9 +28,13 @@ pub fn async_runtime(_attr: TokenStream, item: TokenStream) -> TokenStream {
 
     quote! {
         #(#attrs)* #vis #sig {
-            crate::async_runtime::get_async_runtime().block_on(async {
-                #(#stmts)*
-            })
+            let _api_call = crate::crash::ApiCallGuard::enter();
+
+            crate::async_runtime::get_async_runtime()
+                .block_on(::futures::FutureExt::catch_unwind(::std::panic::AssertUnwindSafe(async {
+                    #(#stmts)*
+                })))
+                .unwrap_or_else(|payload| crate::crash::report_panic(payload))
         }
     }
     .into()
@@ -37,8 +44,8 @@ pub fn async_runtime(_attr: TokenStream, item: TokenStream) -> TokenStream {
 /// via `flutter-rust-bridge`. Unfortunately, the bridging code generated can only
 /// propagate error values as Dart exceptions if the [`anyhow::Result`] type is used.
 /// In order to transmit error details to Flutter, the [`anyhow::Error`] type will
-/// contain a JSON encoded string for errors we know the [`wallet::wallet::Wallet`] to
-/// produce.
+/// contain a JSON encoded string, which includes a stable error code for errors we know
+/// the [`wallet::wallet::Wallet`] to produce.
 ///
 /// For this macro to make sense in the context in which it is used, its input should
 /// also be a [`anyhow::Result`]. This way, the function to which it is applied will
@@ -51,8 +58,8 @@ pub fn async_runtime(_attr: TokenStream, item: TokenStream) -> TokenStream {
 ///    The effect of this is that any use of the `?` operator to convert and return
 ///    error values is contained and performed within the closure.
 /// 2. Any [`anyhow::Error`] resulting from the closure is converted to a
-///    [`crate::errors::FlutterApiError`], using its [`TryFrom`] trait implementation.
-///    If this conversion fails, the [`anyhow::Error`] is simply propagated.
+///    [`crate::errors::FlutterApiError`], using its [`From`] trait implementation.
+///    Errors that are not known to be produced by the wallet result in a generic error.
 /// 3. The [`crate::errors::FlutterApiError`] is logged using [`tracing`], by using the
 ///    [`Display`] and [`Debug`] traits on the error.
 /// 4. A new [`anyhow::Error`] is created and propagated, containing the
@@ -65,14 +72,13 @@ pub fn flutter_api_error(_attr: TokenStream, item: TokenStream) -> TokenStream {
     quote! {
         #(#attrs)* #vis #sig {
             (|| #block)()
-            .map_err(|error: ::anyhow::Error| match crate::errors::FlutterApiError::try_from(error) {
-                Ok(flutter_error) => {
-                    ::tracing::warn!("Error: {}", flutter_error);
-                    ::tracing::info!("Error details: {:?}", flutter_error);
+            .map_err(|error: ::anyhow::Error| {
+                let flutter_error = crate::errors::FlutterApiError::from(error);
+
+                ::tracing::warn!("Error: {}", flutter_error);
+                ::tracing::info!("Error details: {:?}", flutter_error);
 
-                    ::anyhow::anyhow!(flutter_error.to_json())
-                },
-                Err(error) => error,
+                ::anyhow::anyhow!(flutter_error.to_json())
             })
         }
     }
@@ -1,5 +1,11 @@
+use std::time::Duration;
+
 use anyhow::Result;
-use tokio::sync::{OnceCell, RwLock};
+use tokio::{
+    sync::{OnceCell, RwLock},
+    time,
+};
+use tracing::{error, warn};
 use url::Url;
 
 use flutter_api_macros::{async_runtime, flutter_api_error};
@@ -7,14 +13,18 @@ use flutter_rust_bridge::StreamSink;
 use wallet::{self, errors::WalletInitError, Wallet};
 
 use crate::{
-    async_runtime::init_async_runtime,
+    async_runtime::{get_async_runtime, init_async_runtime},
+    crash::{clear_crash_sink, init_crash_handler, set_crash_sink, take_crash_marker},
+    locale,
     logging::init_logging,
     models::{
+        app_state::AppState,
         card::Card,
         config::FlutterConfiguration,
         disclosure::{AcceptDisclosureResult, StartDisclosureResult},
         instruction::WalletInstructionResult,
-        pin::PinValidationResult,
+        page::{CardsPage, HistoryPage},
+        pin::{PinLockoutState, PinValidationResult},
         uri::IdentifyUriResult,
         wallet_event::{WalletEvent, WalletEvents},
     },
@@ -23,6 +33,9 @@ use crate::{
 
 static WALLET: OnceCell<RwLock<Wallet>> = OnceCell::const_new();
 
+/// The interval at which the lock timeouts of the wallet are checked.
+const LOCK_TIMEOUT_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
 fn wallet() -> &'static RwLock<Wallet> {
     WALLET
         .get()
@@ -40,9 +53,19 @@ pub fn init() -> Result<()> {
     // This function may also be called safely more than once.
     init_async_runtime();
 
+    // Install the panic hook that writes the crash marker, this should be done after logging is initialized.
+    init_crash_handler();
+
     let initialized = create_wallet()?;
     assert!(initialized, "Wallet can only be initialized once");
 
+    // If the previous session ended in a panic, check that the wallet state is still consistent.
+    if take_crash_marker() {
+        self_check_wallet();
+    }
+
+    start_lock_timeout_task();
+
     Ok(())
 }
 
@@ -70,9 +93,37 @@ async fn create_wallet() -> std::result::Result<bool, WalletInitError> {
     Ok(created)
 }
 
+/// Run the self-check of the wallet and log its result. This is called by [`init()`] after a crash.
+#[async_runtime]
+async fn self_check_wallet() {
+    warn!("Previous session ended in a crash, performing self-check");
+
+    if let Err(error) = wallet().read().await.self_check().await {
+        error!("Self-check failed: {}", error);
+    }
+}
+
+/// Spawn a background task that periodically locks the wallet if any of its lock timeouts expire.
+/// Any change in the locked state is emitted through the lock callback of the wallet.
+fn start_lock_timeout_task() {
+    get_async_runtime().spawn(async {
+        let mut interval = time::interval(LOCK_TIMEOUT_CHECK_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            // If the wallet is currently in use, simply skip this check.
+            if let Ok(mut wallet) = wallet().try_write() {
+                wallet.check_lock_timeouts().await;
+            }
+        }
+    });
+}
+
+#[async_runtime]
 #[flutter_api_error]
-pub fn is_valid_pin(pin: String) -> Result<PinValidationResult> {
-    let result = wallet::validate_pin(&pin).into();
+pub async fn is_valid_pin(pin: String) -> Result<PinValidationResult> {
+    let result = wallet().read().await.validate_pin(&pin).into();
 
     Ok(result)
 }
@@ -89,6 +140,51 @@ pub async fn clear_lock_stream() {
     wallet().write().await.clear_lock_callback();
 }
 
+#[async_runtime]
+pub async fn set_connectivity_stream(sink: StreamSink<bool>) {
+    let sink = ClosingStreamSink::from(sink);
+
+    wallet()
+        .read()
+        .await
+        .set_connectivity_callback(move |is_online| sink.add(is_online));
+}
+
+#[async_runtime]
+pub async fn clear_connectivity_stream() {
+    wallet().read().await.clear_connectivity_callback();
+}
+
+/// Emits whether the device clock differs significantly from the clock of the Wallet Provider.
+#[async_runtime]
+pub async fn set_clock_skew_stream(sink: StreamSink<bool>) {
+    let sink = ClosingStreamSink::from(sink);
+
+    wallet()
+        .read()
+        .await
+        .set_clock_skew_callback(move |is_skewed| sink.add(is_skewed));
+}
+
+#[async_runtime]
+pub async fn clear_clock_skew_stream() {
+    wallet().read().await.clear_clock_skew_callback();
+}
+
+/// Emits panics that occur while executing API calls, serialized as JSON, right before the process exits.
+pub fn set_crash_stream(sink: StreamSink<String>) {
+    set_crash_sink(ClosingStreamSink::from(sink));
+}
+
+pub fn clear_crash_stream() {
+    clear_crash_sink();
+}
+
+/// Set the locale of the app, e.g. "nl" or "en-GB", which determines how attribute values are formatted.
+pub fn set_locale(locale: String) {
+    locale::set_locale(locale);
+}
+
 #[async_runtime]
 pub async fn set_configuration_stream(sink: StreamSink<FlutterConfiguration>) {
     let sink = ClosingStreamSink::from(sink);
@@ -104,6 +200,11 @@ pub async fn clear_configuration_stream() {
     wallet().write().await.clear_config_callback();
 }
 
+#[async_runtime]
+pub async fn is_feature_enabled(feature: String) -> bool {
+    wallet().read().await.is_feature_enabled(&feature)
+}
+
 #[async_runtime]
 pub async fn set_cards_stream(sink: StreamSink<Vec<Card>>) -> Result<()> {
     let sink = ClosingStreamSink::from(sink);
@@ -131,11 +232,43 @@ pub async fn clear_cards_stream() {
 pub async fn unlock_wallet(pin: String) -> Result<WalletInstructionResult> {
     let mut wallet = wallet().write().await;
 
-    let result = wallet.unlock(pin).await.try_into()?;
+    let result = wallet.unlock(pin.clone()).await.try_into()?;
+
+    if let WalletInstructionResult::Ok = result {
+        start_replenish_key_pool_task(pin);
+    }
 
     Ok(result)
 }
 
+/// Spawn a background task that generates remote keys in advance, so that these do not have to be generated
+/// during issuance. As this is only an optimization, any error is logged and otherwise ignored.
+fn start_replenish_key_pool_task(pin: String) {
+    get_async_runtime().spawn(async move {
+        // Only hold the lock on the wallet while preparing, not while waiting for the Wallet Provider.
+        let replenisher = wallet().read().await.key_pool_replenisher(pin).await;
+
+        let result = match replenisher {
+            Ok(replenisher) => replenisher.replenish().await,
+            Err(error) => Err(error),
+        };
+
+        if let Err(error) = result {
+            warn!("Could not replenish key pool: {}", error);
+        }
+    });
+}
+
+#[async_runtime]
+#[flutter_api_error]
+pub async fn get_pin_lockout() -> Result<PinLockoutState> {
+    let wallet = wallet().read().await;
+
+    let lockout = wallet.pin_lockout().await?;
+
+    Ok(lockout.into())
+}
+
 #[async_runtime]
 pub async fn lock_wallet() {
     let mut wallet = wallet().write().await;
@@ -143,6 +276,16 @@ pub async fn lock_wallet() {
     wallet.lock();
 }
 
+#[async_runtime]
+pub async fn register_activity() {
+    wallet().write().await.register_activity();
+}
+
+#[async_runtime]
+pub async fn notify_app_state(state: AppState) {
+    wallet().write().await.notify_app_state(state.into()).await;
+}
+
 #[async_runtime]
 pub async fn has_registration() -> bool {
     wallet().read().await.has_registration()
@@ -178,12 +321,22 @@ pub async fn create_pid_issuance_redirect_uri() -> Result<String> {
     Ok(auth_url.into())
 }
 
+#[async_runtime]
+#[flutter_api_error]
+pub async fn create_pid_renewal_redirect_uri() -> Result<String> {
+    let mut wallet = wallet().write().await;
+
+    let auth_url = wallet.renew_pid().await?;
+
+    Ok(auth_url.into())
+}
+
 #[async_runtime]
 #[flutter_api_error]
 pub async fn cancel_pid_issuance() -> Result<()> {
     let mut wallet = wallet().write().await;
 
-    wallet.cancel_pid_issuance()?;
+    wallet.cancel_pid_issuance().await?;
 
     Ok(())
 }
@@ -234,6 +387,16 @@ pub async fn start_disclosure(uri: String) -> Result<StartDisclosureResult> {
     Ok(result)
 }
 
+#[async_runtime]
+#[flutter_api_error]
+pub async fn get_disclosure_proposal() -> Result<StartDisclosureResult> {
+    let wallet = wallet().read().await;
+
+    let result = wallet.disclosure_proposal().try_into()?;
+
+    Ok(result)
+}
+
 #[async_runtime]
 #[flutter_api_error]
 pub async fn cancel_disclosure() -> Result<()> {
@@ -279,18 +442,57 @@ pub async fn get_history_for_card(doc_type: String) -> Result<Vec<WalletEvent>>
     Ok(history)
 }
 
+/// Return a page of the history, grouped by month in the locale set through [`set_locale()`].
 #[async_runtime]
-pub async fn reset_wallet() {
-    panic!("Unimplemented: UC 9.4")
+#[flutter_api_error]
+pub async fn get_history_page(offset: u32, limit: u32) -> Result<HistoryPage> {
+    let wallet = wallet().read().await;
+    let history = wallet.get_history().await?;
+    let page = HistoryPage::new(history, offset, limit, &locale::current_locale());
+    Ok(page)
+}
+
+/// Return a page of the cards, sorted and grouped by the name of their issuer
+/// in the locale set through [`set_locale()`].
+#[async_runtime]
+#[flutter_api_error]
+pub async fn get_cards_page(offset: u32, limit: u32) -> Result<CardsPage> {
+    let wallet = wallet().read().await;
+    let documents = wallet.documents().await?;
+    let page = CardsPage::new(documents, offset, limit, &locale::current_locale());
+    Ok(page)
+}
+
+#[async_runtime]
+#[flutter_api_error]
+pub async fn reset_wallet() -> Result<()> {
+    let mut wallet = wallet().write().await;
+
+    wallet.reset().await?;
+
+    Ok(())
+}
+
+#[async_runtime]
+#[flutter_api_error]
+pub async fn deregister_wallet(pin: String) -> Result<WalletInstructionResult> {
+    let mut wallet = wallet().write().await;
+
+    let result = wallet.deregister(pin).await.try_into()?;
+
+    Ok(result)
 }
 
 #[cfg(test)]
 mod tests {
+    use wallet::PinPolicyConfiguration;
+
     use super::*;
 
+    // As `is_valid_pin()` requires an initialized wallet, this checks the result for the default PIN policy.
     fn test_is_valid_pin(pin: &str) -> bool {
         matches!(
-            is_valid_pin(pin.to_string()).expect("Could not validate PIN"),
+            PinValidationResult::from(wallet::validate_pin(pin, &PinPolicyConfiguration::default())),
             PinValidationResult::Ok
         )
     }
@@ -26,6 +26,41 @@ pub extern "C" fn wire_clear_lock_stream(port_: i64) {
     wire_clear_lock_stream_impl(port_)
 }
 
+#[no_mangle]
+pub extern "C" fn wire_set_connectivity_stream(port_: i64) {
+    wire_set_connectivity_stream_impl(port_)
+}
+
+#[no_mangle]
+pub extern "C" fn wire_clear_connectivity_stream(port_: i64) {
+    wire_clear_connectivity_stream_impl(port_)
+}
+
+#[no_mangle]
+pub extern "C" fn wire_set_clock_skew_stream(port_: i64) {
+    wire_set_clock_skew_stream_impl(port_)
+}
+
+#[no_mangle]
+pub extern "C" fn wire_clear_clock_skew_stream(port_: i64) {
+    wire_clear_clock_skew_stream_impl(port_)
+}
+
+#[no_mangle]
+pub extern "C" fn wire_set_crash_stream(port_: i64) {
+    wire_set_crash_stream_impl(port_)
+}
+
+#[no_mangle]
+pub extern "C" fn wire_clear_crash_stream(port_: i64) {
+    wire_clear_crash_stream_impl(port_)
+}
+
+#[no_mangle]
+pub extern "C" fn wire_set_locale(port_: i64, locale: *mut wire_uint_8_list) {
+    wire_set_locale_impl(port_, locale)
+}
+
 #[no_mangle]
 pub extern "C" fn wire_set_configuration_stream(port_: i64) {
     wire_set_configuration_stream_impl(port_)
@@ -36,6 +71,11 @@ pub extern "C" fn wire_clear_configuration_stream(port_: i64) {
     wire_clear_configuration_stream_impl(port_)
 }
 
+#[no_mangle]
+pub extern "C" fn wire_is_feature_enabled(port_: i64, feature: *mut wire_uint_8_list) {
+    wire_is_feature_enabled_impl(port_, feature)
+}
+
 #[no_mangle]
 pub extern "C" fn wire_set_cards_stream(port_: i64) {
     wire_set_cards_stream_impl(port_)
@@ -51,11 +91,26 @@ pub extern "C" fn wire_unlock_wallet(port_: i64, pin: *mut wire_uint_8_list) {
     wire_unlock_wallet_impl(port_, pin)
 }
 
+#[no_mangle]
+pub extern "C" fn wire_get_pin_lockout(port_: i64) {
+    wire_get_pin_lockout_impl(port_)
+}
+
 #[no_mangle]
 pub extern "C" fn wire_lock_wallet(port_: i64) {
     wire_lock_wallet_impl(port_)
 }
 
+#[no_mangle]
+pub extern "C" fn wire_register_activity(port_: i64) {
+    wire_register_activity_impl(port_)
+}
+
+#[no_mangle]
+pub extern "C" fn wire_notify_app_state(port_: i64, state: i32) {
+    wire_notify_app_state_impl(port_, state)
+}
+
 #[no_mangle]
 pub extern "C" fn wire_has_registration(port_: i64) {
     wire_has_registration_impl(port_)
@@ -76,6 +131,11 @@ pub extern "C" fn wire_create_pid_issuance_redirect_uri(port_: i64) {
     wire_create_pid_issuance_redirect_uri_impl(port_)
 }
 
+#[no_mangle]
+pub extern "C" fn wire_create_pid_renewal_redirect_uri(port_: i64) {
+    wire_create_pid_renewal_redirect_uri_impl(port_)
+}
+
 #[no_mangle]
 pub extern "C" fn wire_cancel_pid_issuance(port_: i64) {
     wire_cancel_pid_issuance_impl(port_)
@@ -101,6 +161,11 @@ pub extern "C" fn wire_start_disclosure(port_: i64, uri: *mut wire_uint_8_list)
     wire_start_disclosure_impl(port_, uri)
 }
 
+#[no_mangle]
+pub extern "C" fn wire_get_disclosure_proposal(port_: i64) {
+    wire_get_disclosure_proposal_impl(port_)
+}
+
 #[no_mangle]
 pub extern "C" fn wire_cancel_disclosure(port_: i64) {
     wire_cancel_disclosure_impl(port_)
@@ -121,11 +186,26 @@ pub extern "C" fn wire_get_history_for_card(port_: i64, doc_type: *mut wire_uint
     wire_get_history_for_card_impl(port_, doc_type)
 }
 
+#[no_mangle]
+pub extern "C" fn wire_get_history_page(port_: i64, offset: u32, limit: u32) {
+    wire_get_history_page_impl(port_, offset, limit)
+}
+
+#[no_mangle]
+pub extern "C" fn wire_get_cards_page(port_: i64, offset: u32, limit: u32) {
+    wire_get_cards_page_impl(port_, offset, limit)
+}
+
 #[no_mangle]
 pub extern "C" fn wire_reset_wallet(port_: i64) {
     wire_reset_wallet_impl(port_)
 }
 
+#[no_mangle]
+pub extern "C" fn wire_deregister_wallet(port_: i64, pin: *mut wire_uint_8_list) {
+    wire_deregister_wallet_impl(port_, pin)
+}
+
 // Section: allocate functions
 
 #[no_mangle]
@@ -20,6 +20,7 @@ use std::sync::Arc;
 
 // Section: imports
 
+use crate::models::app_state::AppState;
 use crate::models::card::Card;
 use crate::models::card::CardAttribute;
 use crate::models::card::CardPersistence;
@@ -36,6 +37,10 @@ use crate::models::disclosure::RequestedCard;
 use crate::models::disclosure::StartDisclosureResult;
 use crate::models::instruction::WalletInstructionError;
 use crate::models::instruction::WalletInstructionResult;
+use crate::models::page::CardsPage;
+use crate::models::page::HistoryPage;
+use crate::models::page::ItemGroup;
+use crate::models::pin::PinLockoutState;
 use crate::models::pin::PinValidationResult;
 use crate::models::uri::IdentifyUriResult;
 use crate::models::wallet_event::DisclosureStatus;
@@ -96,6 +101,81 @@ fn wire_clear_lock_stream_impl(port_: MessagePort) {
         move || move |task_callback| Result::<_, ()>::Ok(clear_lock_stream()),
     )
 }
+fn wire_set_connectivity_stream_impl(port_: MessagePort) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap::<_, _, _, (), _>(
+        WrapInfo {
+            debug_name: "set_connectivity_stream",
+            port: Some(port_),
+            mode: FfiCallMode::Stream,
+        },
+        move || {
+            move |task_callback| Result::<_, ()>::Ok(set_connectivity_stream(task_callback.stream_sink::<_, bool>()))
+        },
+    )
+}
+fn wire_clear_connectivity_stream_impl(port_: MessagePort) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap::<_, _, _, (), _>(
+        WrapInfo {
+            debug_name: "clear_connectivity_stream",
+            port: Some(port_),
+            mode: FfiCallMode::Normal,
+        },
+        move || move |task_callback| Result::<_, ()>::Ok(clear_connectivity_stream()),
+    )
+}
+fn wire_set_clock_skew_stream_impl(port_: MessagePort) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap::<_, _, _, (), _>(
+        WrapInfo {
+            debug_name: "set_clock_skew_stream",
+            port: Some(port_),
+            mode: FfiCallMode::Stream,
+        },
+        move || move |task_callback| Result::<_, ()>::Ok(set_clock_skew_stream(task_callback.stream_sink::<_, bool>())),
+    )
+}
+fn wire_clear_clock_skew_stream_impl(port_: MessagePort) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap::<_, _, _, (), _>(
+        WrapInfo {
+            debug_name: "clear_clock_skew_stream",
+            port: Some(port_),
+            mode: FfiCallMode::Normal,
+        },
+        move || move |task_callback| Result::<_, ()>::Ok(clear_clock_skew_stream()),
+    )
+}
+fn wire_set_crash_stream_impl(port_: MessagePort) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap::<_, _, _, (), _>(
+        WrapInfo {
+            debug_name: "set_crash_stream",
+            port: Some(port_),
+            mode: FfiCallMode::Stream,
+        },
+        move || move |task_callback| Result::<_, ()>::Ok(set_crash_stream(task_callback.stream_sink::<_, String>())),
+    )
+}
+fn wire_clear_crash_stream_impl(port_: MessagePort) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap::<_, _, _, (), _>(
+        WrapInfo {
+            debug_name: "clear_crash_stream",
+            port: Some(port_),
+            mode: FfiCallMode::Normal,
+        },
+        move || move |task_callback| Result::<_, ()>::Ok(clear_crash_stream()),
+    )
+}
+fn wire_set_locale_impl(port_: MessagePort, locale: impl Wire2Api<String> + UnwindSafe) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap::<_, _, _, (), _>(
+        WrapInfo {
+            debug_name: "set_locale",
+            port: Some(port_),
+            mode: FfiCallMode::Normal,
+        },
+        move || {
+            let api_locale = locale.wire2api();
+            move |task_callback| Result::<_, ()>::Ok(set_locale(api_locale))
+        },
+    )
+}
 fn wire_set_configuration_stream_impl(port_: MessagePort) {
     FLUTTER_RUST_BRIDGE_HANDLER.wrap::<_, _, _, (), _>(
         WrapInfo {
@@ -122,6 +202,19 @@ fn wire_clear_configuration_stream_impl(port_: MessagePort) {
         move || move |task_callback| Result::<_, ()>::Ok(clear_configuration_stream()),
     )
 }
+fn wire_is_feature_enabled_impl(port_: MessagePort, feature: impl Wire2Api<String> + UnwindSafe) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap::<_, _, _, bool, _>(
+        WrapInfo {
+            debug_name: "is_feature_enabled",
+            port: Some(port_),
+            mode: FfiCallMode::Normal,
+        },
+        move || {
+            let api_feature = feature.wire2api();
+            move |task_callback| Result::<_, ()>::Ok(is_feature_enabled(api_feature))
+        },
+    )
+}
 fn wire_set_cards_stream_impl(port_: MessagePort) {
     FLUTTER_RUST_BRIDGE_HANDLER.wrap::<_, _, _, (), _>(
         WrapInfo {
@@ -155,6 +248,16 @@ fn wire_unlock_wallet_impl(port_: MessagePort, pin: impl Wire2Api<String> + Unwi
         },
     )
 }
+fn wire_get_pin_lockout_impl(port_: MessagePort) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap::<_, _, _, PinLockoutState, _>(
+        WrapInfo {
+            debug_name: "get_pin_lockout",
+            port: Some(port_),
+            mode: FfiCallMode::Normal,
+        },
+        move || move |task_callback| get_pin_lockout(),
+    )
+}
 fn wire_lock_wallet_impl(port_: MessagePort) {
     FLUTTER_RUST_BRIDGE_HANDLER.wrap::<_, _, _, (), _>(
         WrapInfo {
@@ -165,6 +268,29 @@ fn wire_lock_wallet_impl(port_: MessagePort) {
         move || move |task_callback| Result::<_, ()>::Ok(lock_wallet()),
     )
 }
+fn wire_register_activity_impl(port_: MessagePort) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap::<_, _, _, (), _>(
+        WrapInfo {
+            debug_name: "register_activity",
+            port: Some(port_),
+            mode: FfiCallMode::Normal,
+        },
+        move || move |task_callback| Result::<_, ()>::Ok(register_activity()),
+    )
+}
+fn wire_notify_app_state_impl(port_: MessagePort, state: impl Wire2Api<AppState> + UnwindSafe) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap::<_, _, _, (), _>(
+        WrapInfo {
+            debug_name: "notify_app_state",
+            port: Some(port_),
+            mode: FfiCallMode::Normal,
+        },
+        move || {
+            let api_state = state.wire2api();
+            move |task_callback| Result::<_, ()>::Ok(notify_app_state(api_state))
+        },
+    )
+}
 fn wire_has_registration_impl(port_: MessagePort) {
     FLUTTER_RUST_BRIDGE_HANDLER.wrap::<_, _, _, bool, _>(
         WrapInfo {
@@ -211,6 +337,16 @@ fn wire_create_pid_issuance_redirect_uri_impl(port_: MessagePort) {
         move || move |task_callback| create_pid_issuance_redirect_uri(),
     )
 }
+fn wire_create_pid_renewal_redirect_uri_impl(port_: MessagePort) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap::<_, _, _, String, _>(
+        WrapInfo {
+            debug_name: "create_pid_renewal_redirect_uri",
+            port: Some(port_),
+            mode: FfiCallMode::Normal,
+        },
+        move || move |task_callback| create_pid_renewal_redirect_uri(),
+    )
+}
 fn wire_cancel_pid_issuance_impl(port_: MessagePort) {
     FLUTTER_RUST_BRIDGE_HANDLER.wrap::<_, _, _, (), _>(
         WrapInfo {
@@ -270,6 +406,16 @@ fn wire_start_disclosure_impl(port_: MessagePort, uri: impl Wire2Api<String> + U
         },
     )
 }
+fn wire_get_disclosure_proposal_impl(port_: MessagePort) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap::<_, _, _, StartDisclosureResult, _>(
+        WrapInfo {
+            debug_name: "get_disclosure_proposal",
+            port: Some(port_),
+            mode: FfiCallMode::Normal,
+        },
+        move || move |task_callback| get_disclosure_proposal(),
+    )
+}
 fn wire_cancel_disclosure_impl(port_: MessagePort) {
     FLUTTER_RUST_BRIDGE_HANDLER.wrap::<_, _, _, (), _>(
         WrapInfo {
@@ -316,6 +462,42 @@ fn wire_get_history_for_card_impl(port_: MessagePort, doc_type: impl Wire2Api<St
         },
     )
 }
+fn wire_get_history_page_impl(
+    port_: MessagePort,
+    offset: impl Wire2Api<u32> + UnwindSafe,
+    limit: impl Wire2Api<u32> + UnwindSafe,
+) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap::<_, _, _, HistoryPage, _>(
+        WrapInfo {
+            debug_name: "get_history_page",
+            port: Some(port_),
+            mode: FfiCallMode::Normal,
+        },
+        move || {
+            let api_offset = offset.wire2api();
+            let api_limit = limit.wire2api();
+            move |task_callback| get_history_page(api_offset, api_limit)
+        },
+    )
+}
+fn wire_get_cards_page_impl(
+    port_: MessagePort,
+    offset: impl Wire2Api<u32> + UnwindSafe,
+    limit: impl Wire2Api<u32> + UnwindSafe,
+) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap::<_, _, _, CardsPage, _>(
+        WrapInfo {
+            debug_name: "get_cards_page",
+            port: Some(port_),
+            mode: FfiCallMode::Normal,
+        },
+        move || {
+            let api_offset = offset.wire2api();
+            let api_limit = limit.wire2api();
+            move |task_callback| get_cards_page(api_offset, api_limit)
+        },
+    )
+}
 fn wire_reset_wallet_impl(port_: MessagePort) {
     FLUTTER_RUST_BRIDGE_HANDLER.wrap::<_, _, _, (), _>(
         WrapInfo {
@@ -323,7 +505,20 @@ fn wire_reset_wallet_impl(port_: MessagePort) {
             port: Some(port_),
             mode: FfiCallMode::Normal,
         },
-        move || move |task_callback| Result::<_, ()>::Ok(reset_wallet()),
+        move || move |task_callback| reset_wallet(),
+    )
+}
+fn wire_deregister_wallet_impl(port_: MessagePort, pin: impl Wire2Api<String> + UnwindSafe) {
+    FLUTTER_RUST_BRIDGE_HANDLER.wrap::<_, _, _, WalletInstructionResult, _>(
+        WrapInfo {
+            debug_name: "deregister_wallet",
+            port: Some(port_),
+            mode: FfiCallMode::Normal,
+        },
+        move || {
+            let api_pin = pin.wire2api();
+            move |task_callback| deregister_wallet(api_pin)
+        },
     )
 }
 // Section: wrapper structs
@@ -349,11 +544,26 @@ where
     }
 }
 
+impl Wire2Api<AppState> for i32 {
+    fn wire2api(self) -> AppState {
+        match self {
+            0 => AppState::Foreground,
+            1 => AppState::Background,
+            _ => unreachable!("Invalid variant for AppState: {}", self),
+        }
+    }
+}
+
 impl Wire2Api<u8> for u8 {
     fn wire2api(self) -> u8 {
         self
     }
 }
+impl Wire2Api<u32> for u32 {
+    fn wire2api(self) -> u32 {
+        self
+    }
+}
 
 // Section: impl IntoDart
 
@@ -396,6 +606,7 @@ impl support::IntoDart for CardAttribute {
             self.key.into_into_dart().into_dart(),
             self.labels.into_into_dart().into_dart(),
             self.value.into_into_dart().into_dart(),
+            self.display_value.into_into_dart().into_dart(),
         ]
         .into_dart()
     }
@@ -441,6 +652,23 @@ impl rust2dart::IntoIntoDart<CardValue> for CardValue {
     }
 }
 
+impl support::IntoDart for CardsPage {
+    fn into_dart(self) -> support::DartAbi {
+        vec![
+            self.cards.into_into_dart().into_dart(),
+            self.groups.into_into_dart().into_dart(),
+            self.total_count.into_into_dart().into_dart(),
+        ]
+        .into_dart()
+    }
+}
+impl support::IntoDartExceptPrimitive for CardsPage {}
+impl rust2dart::IntoIntoDart<CardsPage> for CardsPage {
+    fn into_into_dart(self) -> Self {
+        self
+    }
+}
+
 impl support::IntoDart for DisclosureStatus {
     fn into_dart(self) -> support::DartAbi {
         match self {
@@ -492,11 +720,29 @@ impl rust2dart::IntoIntoDart<GenderCardValue> for GenderCardValue {
     }
 }
 
+impl support::IntoDart for HistoryPage {
+    fn into_dart(self) -> support::DartAbi {
+        vec![
+            self.events.into_into_dart().into_dart(),
+            self.groups.into_into_dart().into_dart(),
+            self.total_count.into_into_dart().into_dart(),
+        ]
+        .into_dart()
+    }
+}
+impl support::IntoDartExceptPrimitive for HistoryPage {}
+impl rust2dart::IntoIntoDart<HistoryPage> for HistoryPage {
+    fn into_into_dart(self) -> Self {
+        self
+    }
+}
+
 impl support::IntoDart for IdentifyUriResult {
     fn into_dart(self) -> support::DartAbi {
         match self {
             Self::PidIssuance => 0,
             Self::Disclosure => 1,
+            Self::IssuanceOffer => 2,
         }
         .into_dart()
     }
@@ -526,6 +772,24 @@ impl rust2dart::IntoIntoDart<Image> for Image {
     }
 }
 
+impl support::IntoDart for ItemGroup {
+    fn into_dart(self) -> support::DartAbi {
+        vec![
+            self.key.into_into_dart().into_dart(),
+            self.label.into_into_dart().into_dart(),
+            self.start_index.into_into_dart().into_dart(),
+            self.count.into_into_dart().into_dart(),
+        ]
+        .into_dart()
+    }
+}
+impl support::IntoDartExceptPrimitive for ItemGroup {}
+impl rust2dart::IntoIntoDart<ItemGroup> for ItemGroup {
+    fn into_into_dart(self) -> Self {
+        self
+    }
+}
+
 impl support::IntoDart for LocalizedString {
     fn into_dart(self) -> support::DartAbi {
         vec![
@@ -578,6 +842,22 @@ impl rust2dart::IntoIntoDart<Organization> for Organization {
     }
 }
 
+impl support::IntoDart for PinLockoutState {
+    fn into_dart(self) -> support::DartAbi {
+        vec![
+            self.is_blocked.into_into_dart().into_dart(),
+            self.timeout_millis.into_dart(),
+        ]
+        .into_dart()
+    }
+}
+impl support::IntoDartExceptPrimitive for PinLockoutState {}
+impl rust2dart::IntoIntoDart<PinLockoutState> for PinLockoutState {
+    fn into_into_dart(self) -> Self {
+        self
+    }
+}
+
 impl support::IntoDart for PinValidationResult {
     fn into_dart(self) -> support::DartAbi {
         match self {
@@ -0,0 +1,185 @@
+use std::{
+    any::Any,
+    cell::Cell,
+    fs, io, panic,
+    path::{Path, PathBuf},
+    sync::{Mutex, Once, PoisonError},
+};
+
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use tracing::warn;
+
+use flutter_api_macros::async_runtime;
+use platform_support::utils::{hardware::HardwareUtilities, PlatformUtilities};
+
+use crate::stream::ClosingStreamSink;
+
+const CRASH_MARKER_FILENAME: &str = "crash_marker";
+
+static CRASH_HANDLER: Once = Once::new();
+static CRASH_SINK: Mutex<Option<ClosingStreamSink<String>>> = Mutex::new(None);
+static CRASH_MARKER_PATH: OnceCell<PathBuf> = OnceCell::new();
+
+thread_local! {
+    static IN_API_CALL: Cell<bool> = Cell::new(false);
+}
+
+/// A panic that occurred in the Rust core, which is sent to Flutter as JSON.
+#[derive(Debug, Serialize)]
+struct CrashEvent<'a> {
+    message: &'a str,
+}
+
+impl<'a> From<&'a (dyn Any + Send)> for CrashEvent<'a> {
+    fn from(value: &'a (dyn Any + Send)) -> Self {
+        // The payload may either be a reference to a [`String`] or a `&'static str`.
+        let message = match (value.downcast_ref::<String>(), value.downcast_ref::<&'static str>()) {
+            (Some(s), _) => s.as_ref(),
+            (_, Some(s)) => *s,
+            (_, _) => "UNKNOWN",
+        };
+
+        CrashEvent { message }
+    }
+}
+
+/// Marks the current thread as executing the body of an API function, for as long as it is in scope. This is used by
+/// the `#[async_runtime]` macro, so that a panic in an API call is reported instead of exiting the process right away.
+pub struct ApiCallGuard(());
+
+impl ApiCallGuard {
+    pub fn enter() -> Self {
+        IN_API_CALL.with(|in_api_call| in_api_call.set(true));
+
+        ApiCallGuard(())
+    }
+}
+
+impl Drop for ApiCallGuard {
+    fn drop(&mut self) {
+        IN_API_CALL.with(|in_api_call| in_api_call.set(false));
+    }
+}
+
+/// Returns `true` if the current thread executes the body of an API function, see [`ApiCallGuard`].
+/// Panics in tasks spawned on the async runtime happen on other threads, so they are not part of an API call.
+pub fn in_api_call() -> bool {
+    IN_API_CALL.with(Cell::get)
+}
+
+/// Install a panic hook that persists a crash marker, so that the next session knows the previous one crashed.
+/// This chains any previously installed hook, so it should be called after the panic logger is initialized.
+/// The marker is written before calling that hook, as it may exit the process.
+pub fn init_crash_handler() {
+    CRASH_HANDLER.call_once(|| {
+        let previous_hook = panic::take_hook();
+
+        panic::set_hook(Box::new(move |panic_info| {
+            if let Some(path) = CRASH_MARKER_PATH.get() {
+                if let Err(error) = fs::write(path, []) {
+                    warn!("Could not write crash marker: {}", error);
+                }
+            }
+
+            previous_hook(panic_info);
+        }));
+    });
+}
+
+pub fn set_crash_sink(sink: ClosingStreamSink<String>) {
+    CRASH_SINK.lock().unwrap_or_else(PoisonError::into_inner).replace(sink);
+}
+
+pub fn clear_crash_sink() {
+    CRASH_SINK.lock().unwrap_or_else(PoisonError::into_inner).take();
+}
+
+/// Emit a panic that was caught in an `#[async_runtime]` API call on the crash stream, if one is set, and exit the
+/// process. The wallet cannot be used after a panic, as it may have been left half-modified, e.g. when the panic
+/// occurred while holding its write lock. As the process exits right away, the event is only delivered on a
+/// best-effort basis. Either way the crash marker has been written, so the next session performs a self-check.
+pub fn report_panic(payload: Box<dyn Any + Send>) -> ! {
+    if let Some(sink) = CRASH_SINK.lock().unwrap_or_else(PoisonError::into_inner).as_ref() {
+        match serde_json::to_string(&CrashEvent::from(payload.as_ref())) {
+            Ok(json) => sink.add(json),
+            Err(error) => warn!("Could not serialize crash event: {}", error),
+        }
+    }
+
+    std::process::exit(1)
+}
+
+/// Determine the location of the crash marker and remove it if it is present.
+/// The returned value indicates if the marker was present, i.e. if the previous session ended in a panic.
+#[async_runtime]
+pub async fn take_crash_marker() -> bool {
+    let path = match CRASH_MARKER_PATH.get() {
+        Some(path) => path,
+        None => match HardwareUtilities::storage_path().await {
+            Ok(storage_path) => CRASH_MARKER_PATH.get_or_init(|| storage_path.join(CRASH_MARKER_FILENAME)),
+            Err(error) => {
+                warn!("Could not determine crash marker location: {}", error);
+
+                return false;
+            }
+        },
+    };
+
+    remove_crash_marker(path).unwrap_or_else(|error| {
+        warn!("Could not remove crash marker: {}", error);
+
+        false
+    })
+}
+
+fn remove_crash_marker(path: &Path) -> io::Result<bool> {
+    match fs::remove_file(path) {
+        Ok(_) => Ok(true),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(error) => Err(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    #[test]
+    fn test_remove_crash_marker() {
+        let path = env::temp_dir().join(format!("{}_{}", CRASH_MARKER_FILENAME, std::process::id()));
+
+        assert!(!remove_crash_marker(&path).unwrap());
+
+        fs::write(&path, []).unwrap();
+
+        assert!(remove_crash_marker(&path).unwrap());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_api_call_guard() {
+        assert!(!in_api_call());
+
+        {
+            let _guard = ApiCallGuard::enter();
+            assert!(in_api_call());
+        }
+
+        assert!(!in_api_call());
+    }
+
+    #[test]
+    fn test_crash_event_message() {
+        let payload = panic::catch_unwind(|| panic!("static message")).unwrap_err();
+        assert_eq!(CrashEvent::from(payload.as_ref()).message, "static message");
+
+        let payload = panic::catch_unwind(|| panic!("formatted {}", "message")).unwrap_err();
+        assert_eq!(CrashEvent::from(payload.as_ref()).message, "formatted message");
+
+        let payload = panic::catch_unwind(|| panic::panic_any(42)).unwrap_err();
+        assert_eq!(CrashEvent::from(payload.as_ref()).message, "UNKNOWN");
+    }
+}
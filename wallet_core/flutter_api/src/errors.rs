@@ -4,8 +4,9 @@ use anyhow::Chain;
 use serde::Serialize;
 
 use wallet::errors::{
-    openid, reqwest, AccountProviderError, DigidError, DisclosureError, HistoryError, InstructionError,
-    PidIssuanceError, UriIdentificationError, WalletInitError, WalletRegistrationError, WalletUnlockError,
+    openid, reqwest, AccountProviderError, CredentialIssuerError, DigidError, DisclosureError, DocumentsError,
+    HistoryError, InstructionError, IssuerErrorCode, PidIssuanceError, UriIdentificationError,
+    WalletDeregistrationError, WalletInitError, WalletRegistrationError, WalletResetError, WalletUnlockError,
 };
 
 /// A type encapsulating data about a Flutter error that
@@ -14,6 +15,9 @@ use wallet::errors::{
 pub struct FlutterApiError {
     #[serde(rename = "type")]
     typ: FlutterApiErrorType,
+    code: FlutterApiErrorCode,
+    retryable: bool,
+    message_key: &'static str,
     description: String,
     data: Option<serde_json::Value>,
     /// This property is present only for debug logging purposes and will not be encoded to JSON.
@@ -29,11 +33,93 @@ enum FlutterApiErrorType {
     RedirectUri,
 }
 
+/// A stable code that identifies the specific error, which is more fine-grained than [`FlutterApiErrorType`].
+/// Contrary to the description, this can be relied upon by Flutter to decide how to present an error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum FlutterApiErrorCode {
+    Unknown,
+    NotRegistered,
+    AlreadyRegistered,
+    Locked,
+    NotLocked,
+    SessionState,
+    NoPidToRenew,
+    InvalidPin,
+    IncorrectPin,
+    PinTimeout,
+    AccountBlocked,
+    AccountSuspended,
+    WalletBusy,
+    RateLimited,
+    IntegrityEvidence,
+    Networking,
+    Server,
+    RedirectUri,
+    InvalidUri,
+    UnknownUri,
+    UnknownIssuer,
+    AttributesNotAvailable,
+    Storage,
+    DigidRejected,
+    PersonNotFound,
+}
+
+impl FlutterApiErrorCode {
+    /// Whether performing the same call again, without any action from the user, may succeed.
+    fn is_retryable(self) -> bool {
+        matches!(
+            self,
+            Self::WalletBusy | Self::RateLimited | Self::Networking | Self::Server
+        )
+    }
+
+    /// The key of the localized message that Flutter can show to the user for this error.
+    fn message_key(self) -> &'static str {
+        match self {
+            Self::Unknown => "errorUnknown",
+            Self::NotRegistered => "errorNotRegistered",
+            Self::AlreadyRegistered => "errorAlreadyRegistered",
+            Self::Locked => "errorLocked",
+            Self::NotLocked => "errorNotLocked",
+            Self::SessionState => "errorSessionState",
+            Self::NoPidToRenew => "errorNoPidToRenew",
+            Self::InvalidPin => "errorInvalidPin",
+            Self::IncorrectPin => "errorIncorrectPin",
+            Self::PinTimeout => "errorPinTimeout",
+            Self::AccountBlocked => "errorAccountBlocked",
+            Self::AccountSuspended => "errorAccountSuspended",
+            Self::WalletBusy => "errorWalletBusy",
+            Self::RateLimited => "errorRateLimited",
+            Self::IntegrityEvidence => "errorIntegrityEvidence",
+            Self::Networking => "errorNetworking",
+            Self::Server => "errorServer",
+            Self::RedirectUri => "errorRedirectUri",
+            Self::InvalidUri => "errorInvalidUri",
+            Self::UnknownUri => "errorUnknownUri",
+            Self::UnknownIssuer => "errorUnknownIssuer",
+            Self::AttributesNotAvailable => "errorAttributesNotAvailable",
+            Self::Storage => "errorStorage",
+            Self::DigidRejected => "errorDigidRejected",
+            Self::PersonNotFound => "errorPersonNotFound",
+        }
+    }
+}
+
 trait FlutterApiErrorFields {
     fn typ(&self) -> FlutterApiErrorType {
         FlutterApiErrorType::Generic
     }
 
+    /// By default, the code is derived from the type, which only distinguishes networking and redirect URI errors.
+    fn code(&self) -> FlutterApiErrorCode {
+        match self.typ() {
+            FlutterApiErrorType::Networking => FlutterApiErrorCode::Networking,
+            FlutterApiErrorType::RedirectUri => FlutterApiErrorCode::RedirectUri,
+            FlutterApiErrorType::Generic | FlutterApiErrorType::WalletState => FlutterApiErrorCode::Unknown,
+        }
+    }
+
     fn data(&self) -> Option<serde_json::Value> {
         None
     }
@@ -60,11 +146,10 @@ impl Error for FlutterApiError {
 }
 
 /// Allow conversion from a [`anyhow::Error`] to a [`FlutterApiError`] through downcasting.
-/// If the conversion fails, the original [`anyhow::Error`] is contained in the [`Result`].
-impl TryFrom<anyhow::Error> for FlutterApiError {
-    type Error = anyhow::Error;
-
-    fn try_from(value: anyhow::Error) -> Result<Self, Self::Error> {
+/// If the error is not one of the known wallet errors, a generic error with an unknown code
+/// is returned, so that every error that reaches Flutter has the same structure.
+impl From<anyhow::Error> for FlutterApiError {
+    fn from(value: anyhow::Error) -> Self {
         value
             .downcast::<WalletInitError>()
             .map(Self::from)
@@ -74,7 +159,19 @@ impl TryFrom<anyhow::Error> for FlutterApiError {
             .or_else(|e| e.downcast::<PidIssuanceError>().map(Self::from))
             .or_else(|e| e.downcast::<DisclosureError>().map(Self::from))
             .or_else(|e| e.downcast::<HistoryError>().map(Self::from))
+            .or_else(|e| e.downcast::<DocumentsError>().map(Self::from))
+            .or_else(|e| e.downcast::<WalletResetError>().map(Self::from))
+            .or_else(|e| e.downcast::<WalletDeregistrationError>().map(Self::from))
             .or_else(|e| e.downcast::<url::ParseError>().map(Self::from))
+            .unwrap_or_else(|error| FlutterApiError {
+                typ: FlutterApiErrorType::Generic,
+                code: FlutterApiErrorCode::Unknown,
+                retryable: false,
+                message_key: FlutterApiErrorCode::Unknown.message_key(),
+                description: error.to_string(),
+                data: None,
+                source: error.into(),
+            })
     }
 }
 
@@ -84,8 +181,13 @@ where
     E: Error + FlutterApiErrorFields + 'static,
 {
     fn from(value: E) -> Self {
+        let code = value.code();
+
         FlutterApiError {
             typ: value.typ(),
+            code,
+            retryable: code.is_retryable(),
+            message_key: code.message_key(),
             description: value.to_string(),
             data: value.data(),
             source: Box::new(value),
@@ -95,7 +197,14 @@ where
 
 // The below traits will output the correct FlutterApiErrorType and data for a given
 // error that can be returned from the Wallet. This can possibly be several layers deep.
-impl FlutterApiErrorFields for WalletInitError {}
+impl FlutterApiErrorFields for WalletInitError {
+    fn code(&self) -> FlutterApiErrorCode {
+        match self {
+            WalletInitError::Database(_) => FlutterApiErrorCode::Storage,
+            _ => FlutterApiErrorCode::Unknown,
+        }
+    }
+}
 
 impl FlutterApiErrorFields for WalletRegistrationError {
     fn typ(&self) -> FlutterApiErrorType {
@@ -106,6 +215,17 @@ impl FlutterApiErrorFields for WalletRegistrationError {
             _ => FlutterApiErrorType::Generic,
         }
     }
+
+    fn code(&self) -> FlutterApiErrorCode {
+        match self {
+            WalletRegistrationError::AlreadyRegistered => FlutterApiErrorCode::AlreadyRegistered,
+            WalletRegistrationError::InvalidPin(_) => FlutterApiErrorCode::InvalidPin,
+            WalletRegistrationError::ChallengeRequest(e) => FlutterApiErrorCode::from(e),
+            WalletRegistrationError::RegistrationRequest(e) => FlutterApiErrorCode::from(e),
+            WalletRegistrationError::StoreCertificate(_) => FlutterApiErrorCode::Storage,
+            _ => FlutterApiErrorCode::Unknown,
+        }
+    }
 }
 
 impl FlutterApiErrorFields for WalletUnlockError {
@@ -113,11 +233,30 @@ impl FlutterApiErrorFields for WalletUnlockError {
         match self {
             WalletUnlockError::NotRegistered | WalletUnlockError::NotLocked => FlutterApiErrorType::WalletState,
             WalletUnlockError::Instruction(e) => FlutterApiErrorType::from(e),
+            WalletUnlockError::Storage(_) => FlutterApiErrorType::Generic,
+        }
+    }
+
+    fn code(&self) -> FlutterApiErrorCode {
+        match self {
+            WalletUnlockError::NotRegistered => FlutterApiErrorCode::NotRegistered,
+            WalletUnlockError::NotLocked => FlutterApiErrorCode::NotLocked,
+            WalletUnlockError::Instruction(e) => FlutterApiErrorCode::from(e),
+            WalletUnlockError::Storage(_) => FlutterApiErrorCode::Storage,
         }
     }
 }
 
-impl FlutterApiErrorFields for UriIdentificationError {}
+impl FlutterApiErrorFields for UriIdentificationError {
+    fn code(&self) -> FlutterApiErrorCode {
+        match self {
+            UriIdentificationError::Parse(_) | UriIdentificationError::IssuanceOfferIssuer => {
+                FlutterApiErrorCode::InvalidUri
+            }
+            UriIdentificationError::Unknown => FlutterApiErrorCode::UnknownUri,
+        }
+    }
+}
 
 impl FlutterApiErrorFields for PidIssuanceError {
     fn typ(&self) -> FlutterApiErrorType {
@@ -140,26 +279,60 @@ impl FlutterApiErrorFields for PidIssuanceError {
         }
 
         match self {
-            PidIssuanceError::NotRegistered | PidIssuanceError::Locked | PidIssuanceError::SessionState => {
-                FlutterApiErrorType::WalletState
+            PidIssuanceError::NotRegistered
+            | PidIssuanceError::Locked
+            | PidIssuanceError::SessionState
+            | PidIssuanceError::NoPidToRenew => FlutterApiErrorType::WalletState,
+            PidIssuanceError::DigidSessionFinish(DigidError::RedirectUriError { .. }) => {
+                FlutterApiErrorType::RedirectUri
             }
-            PidIssuanceError::DigidSessionFinish(DigidError::RedirectUriError {
-                error: _,
-                error_description: _,
-            }) => FlutterApiErrorType::RedirectUri,
             _ => FlutterApiErrorType::Generic,
         }
     }
 
+    fn code(&self) -> FlutterApiErrorCode {
+        match self.typ() {
+            FlutterApiErrorType::Networking => return FlutterApiErrorCode::Networking,
+            FlutterApiErrorType::RedirectUri => return FlutterApiErrorCode::RedirectUri,
+            _ => (),
+        }
+
+        match self {
+            PidIssuanceError::NotRegistered => FlutterApiErrorCode::NotRegistered,
+            PidIssuanceError::Locked => FlutterApiErrorCode::Locked,
+            PidIssuanceError::SessionState => FlutterApiErrorCode::SessionState,
+            PidIssuanceError::NoPidToRenew => FlutterApiErrorCode::NoPidToRenew,
+            PidIssuanceError::Instruction(e) => FlutterApiErrorCode::from(e),
+            PidIssuanceError::UnknownIssuer(_) | PidIssuanceError::MissingIssuerRegistration => {
+                FlutterApiErrorCode::UnknownIssuer
+            }
+            PidIssuanceError::MdocStorage(_)
+            | PidIssuanceError::Documents(DocumentsError::Storage(_))
+            | PidIssuanceError::HistoryStorage(_)
+            | PidIssuanceError::DigidSessionStorage(_) => FlutterApiErrorCode::Storage,
+            // The PID issuer reports whether its errors are temporary, in which case the wallet may retry.
+            PidIssuanceError::PidIssuer(CredentialIssuerError::Issuer(data)) => match data.code {
+                IssuerErrorCode::DigidAccessTokenRejected | IssuerErrorCode::DigidNoBsn => {
+                    FlutterApiErrorCode::DigidRejected
+                }
+                IssuerErrorCode::PersonNotFound | IssuerErrorCode::IncompletePerson => {
+                    FlutterApiErrorCode::PersonNotFound
+                }
+                IssuerErrorCode::RateLimited => FlutterApiErrorCode::RateLimited,
+                _ if data.retryable => FlutterApiErrorCode::Server,
+                _ => FlutterApiErrorCode::Unknown,
+            },
+            _ => FlutterApiErrorCode::Unknown,
+        }
+    }
+
     fn data(&self) -> Option<serde_json::Value> {
         match self {
-            Self::DigidSessionFinish(DigidError::RedirectUriError {
-                error,
-                error_description: _,
-            }) => [("redirect_error", error.clone())]
-                .into_iter()
-                .collect::<serde_json::Value>()
-                .into(),
+            Self::DigidSessionFinish(DigidError::RedirectUriError { kind, error, .. }) => serde_json::json!({
+                "redirect_error": error,
+                "redirect_error_kind": kind,
+            })
+            .into(),
             _ => None,
         }
     }
@@ -181,12 +354,35 @@ impl FlutterApiErrorFields for DisclosureError {
             _ => FlutterApiErrorType::Generic,
         }
     }
+
+    fn code(&self) -> FlutterApiErrorCode {
+        match self {
+            DisclosureError::NotRegistered => FlutterApiErrorCode::NotRegistered,
+            DisclosureError::Locked => FlutterApiErrorCode::Locked,
+            DisclosureError::SessionState => FlutterApiErrorCode::SessionState,
+            DisclosureError::DisclosureUri(_) => FlutterApiErrorCode::InvalidUri,
+            DisclosureError::UnknownRelyingParty => FlutterApiErrorCode::Unknown,
+            DisclosureError::AttributesNotAvailable { .. } => FlutterApiErrorCode::AttributesNotAvailable,
+            DisclosureError::Instruction(e) => FlutterApiErrorCode::from(e),
+            DisclosureError::IncrementUsageCount(_)
+            | DisclosureError::MdocStorage(_)
+            | DisclosureError::HistoryStorage(_) => FlutterApiErrorCode::Storage,
+            DisclosureError::DisclosureSession(_) | DisclosureError::MdocAttributes(_) => match self.typ() {
+                FlutterApiErrorType::Networking => FlutterApiErrorCode::Networking,
+                _ => FlutterApiErrorCode::Unknown,
+            },
+        }
+    }
 }
 
 impl FlutterApiErrorFields for url::ParseError {
     fn typ(&self) -> FlutterApiErrorType {
         FlutterApiErrorType::WalletState
     }
+
+    fn code(&self) -> FlutterApiErrorCode {
+        FlutterApiErrorCode::InvalidUri
+    }
 }
 
 impl From<&AccountProviderError> for FlutterApiErrorType {
@@ -200,11 +396,40 @@ impl From<&InstructionError> for FlutterApiErrorType {
         match value {
             InstructionError::ServerError(e) => FlutterApiErrorType::from(e),
             InstructionError::InstructionValidation => FlutterApiErrorType::Networking,
+            InstructionError::WalletBusy | InstructionError::RateLimited { .. } => FlutterApiErrorType::Networking,
             _ => FlutterApiErrorType::Generic,
         }
     }
 }
 
+impl From<&AccountProviderError> for FlutterApiErrorCode {
+    fn from(value: &AccountProviderError) -> Self {
+        match value {
+            AccountProviderError::Networking(_) => FlutterApiErrorCode::Networking,
+            AccountProviderError::Response(_) => FlutterApiErrorCode::Server,
+            AccountProviderError::BaseUrl(_) => FlutterApiErrorCode::Unknown,
+        }
+    }
+}
+
+impl From<&InstructionError> for FlutterApiErrorCode {
+    fn from(value: &InstructionError) -> Self {
+        match value {
+            InstructionError::IncorrectPin { .. } => FlutterApiErrorCode::IncorrectPin,
+            InstructionError::Timeout { .. } => FlutterApiErrorCode::PinTimeout,
+            InstructionError::Blocked => FlutterApiErrorCode::AccountBlocked,
+            InstructionError::Suspended => FlutterApiErrorCode::AccountSuspended,
+            InstructionError::ServerError(e) => FlutterApiErrorCode::from(e),
+            InstructionError::InstructionValidation => FlutterApiErrorCode::Server,
+            InstructionError::WalletBusy => FlutterApiErrorCode::WalletBusy,
+            InstructionError::RateLimited { .. } => FlutterApiErrorCode::RateLimited,
+            InstructionError::IntegrityEvidence => FlutterApiErrorCode::IntegrityEvidence,
+            InstructionError::StoreInstructionSequenceNumber(_) => FlutterApiErrorCode::Storage,
+            _ => FlutterApiErrorCode::Unknown,
+        }
+    }
+}
+
 impl FlutterApiErrorFields for HistoryError {
     fn typ(&self) -> FlutterApiErrorType {
         match self {
@@ -212,4 +437,159 @@ impl FlutterApiErrorFields for HistoryError {
             _ => FlutterApiErrorType::Generic,
         }
     }
+
+    fn code(&self) -> FlutterApiErrorCode {
+        match self {
+            HistoryError::NotRegistered => FlutterApiErrorCode::NotRegistered,
+            HistoryError::Locked => FlutterApiErrorCode::Locked,
+            HistoryError::Storage(_) => FlutterApiErrorCode::Storage,
+            _ => FlutterApiErrorCode::Unknown,
+        }
+    }
+}
+
+impl FlutterApiErrorFields for DocumentsError {
+    fn typ(&self) -> FlutterApiErrorType {
+        match self {
+            DocumentsError::NotRegistered | DocumentsError::Locked => FlutterApiErrorType::WalletState,
+            DocumentsError::Storage(_) | DocumentsError::MdocAttributes(_) => FlutterApiErrorType::Generic,
+        }
+    }
+
+    fn code(&self) -> FlutterApiErrorCode {
+        match self {
+            DocumentsError::NotRegistered => FlutterApiErrorCode::NotRegistered,
+            DocumentsError::Locked => FlutterApiErrorCode::Locked,
+            DocumentsError::Storage(_) => FlutterApiErrorCode::Storage,
+            DocumentsError::MdocAttributes(_) => FlutterApiErrorCode::Unknown,
+        }
+    }
+}
+
+impl FlutterApiErrorFields for WalletResetError {
+    fn typ(&self) -> FlutterApiErrorType {
+        match self {
+            WalletResetError::Revocation(e) => FlutterApiErrorType::from(e),
+            _ => FlutterApiErrorType::Generic,
+        }
+    }
+
+    fn code(&self) -> FlutterApiErrorCode {
+        match self {
+            WalletResetError::Revocation(e) => FlutterApiErrorCode::from(e),
+            WalletResetError::Storage(_) => FlutterApiErrorCode::Storage,
+            _ => FlutterApiErrorCode::Unknown,
+        }
+    }
+}
+
+impl FlutterApiErrorFields for WalletDeregistrationError {
+    fn typ(&self) -> FlutterApiErrorType {
+        match self {
+            WalletDeregistrationError::NotRegistered | WalletDeregistrationError::Locked => {
+                FlutterApiErrorType::WalletState
+            }
+            WalletDeregistrationError::Instruction(e) => FlutterApiErrorType::from(e),
+            WalletDeregistrationError::LocalState(_) => FlutterApiErrorType::Generic,
+        }
+    }
+
+    fn code(&self) -> FlutterApiErrorCode {
+        match self {
+            WalletDeregistrationError::NotRegistered => FlutterApiErrorCode::NotRegistered,
+            WalletDeregistrationError::Locked => FlutterApiErrorCode::Locked,
+            WalletDeregistrationError::Instruction(e) => FlutterApiErrorCode::from(e),
+            WalletDeregistrationError::LocalState(e) => e.code(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use wallet::errors::IssuerErrorData;
+
+    use super::*;
+
+    #[test]
+    fn test_flutter_api_error_json() {
+        let error = FlutterApiError::from(anyhow::Error::new(WalletUnlockError::Instruction(
+            InstructionError::IncorrectPin {
+                leftover_attempts: 2,
+                is_final_attempt: false,
+            },
+        )));
+        let json = serde_json::from_str::<serde_json::Value>(&error.to_json()).unwrap();
+
+        assert_eq!(json["type"], json!("Generic"));
+        assert_eq!(json["code"], json!("incorrect_pin"));
+        assert_eq!(json["retryable"], json!(false));
+        assert_eq!(json["message_key"], json!("errorIncorrectPin"));
+
+        let error = FlutterApiError::from(anyhow::Error::new(WalletUnlockError::Instruction(
+            InstructionError::WalletBusy,
+        )));
+        let json = serde_json::from_str::<serde_json::Value>(&error.to_json()).unwrap();
+
+        assert_eq!(json["type"], json!("Networking"));
+        assert_eq!(json["code"], json!("wallet_busy"));
+        assert_eq!(json["retryable"], json!(true));
+
+        let error = FlutterApiError::from(anyhow::Error::new(WalletUnlockError::Instruction(
+            InstructionError::RateLimited {
+                retry_after_millis: 5000,
+            },
+        )));
+        let json = serde_json::from_str::<serde_json::Value>(&error.to_json()).unwrap();
+
+        assert_eq!(json["type"], json!("Networking"));
+        assert_eq!(json["code"], json!("rate_limited"));
+        assert_eq!(json["retryable"], json!(true));
+
+        let error = FlutterApiError::from(anyhow::Error::new(WalletUnlockError::Instruction(
+            InstructionError::Suspended,
+        )));
+        let json = serde_json::from_str::<serde_json::Value>(&error.to_json()).unwrap();
+
+        assert_eq!(json["type"], json!("Generic"));
+        assert_eq!(json["code"], json!("account_suspended"));
+        assert_eq!(json["retryable"], json!(false));
+        assert_eq!(json["message_key"], json!("errorAccountSuspended"));
+
+        let error = FlutterApiError::from(anyhow::Error::new(PidIssuanceError::PidIssuer(
+            CredentialIssuerError::Issuer(IssuerErrorData::new(
+                IssuerErrorCode::DigidUnavailable,
+                "title123".to_string(),
+            )),
+        )));
+        let json = serde_json::from_str::<serde_json::Value>(&error.to_json()).unwrap();
+
+        assert_eq!(json["type"], json!("Generic"));
+        assert_eq!(json["code"], json!("server"));
+        assert_eq!(json["retryable"], json!(true));
+
+        let error = FlutterApiError::from(anyhow::Error::new(PidIssuanceError::PidIssuer(
+            CredentialIssuerError::Issuer(IssuerErrorData::new(
+                IssuerErrorCode::DigidAccessTokenRejected,
+                "title123".to_string(),
+            )),
+        )));
+        let json = serde_json::from_str::<serde_json::Value>(&error.to_json()).unwrap();
+
+        assert_eq!(json["code"], json!("digid_rejected"));
+        assert_eq!(json["retryable"], json!(false));
+        assert_eq!(json["message_key"], json!("errorDigidRejected"));
+    }
+
+    #[test]
+    fn test_flutter_api_error_unknown() {
+        let error = FlutterApiError::from(anyhow::anyhow!("something unexpected"));
+        let json = serde_json::from_str::<serde_json::Value>(&error.to_json()).unwrap();
+
+        assert_eq!(json["type"], json!("Generic"));
+        assert_eq!(json["code"], json!("unknown"));
+        assert_eq!(json["retryable"], json!(false));
+        assert_eq!(json["description"], json!("something unexpected"));
+    }
 }
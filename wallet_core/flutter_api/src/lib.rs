@@ -5,7 +5,9 @@ pub mod api;
 mod bridge_generated;
 
 mod async_runtime;
+mod crash;
 mod errors;
+mod locale;
 mod logging;
 mod models;
 mod stream;
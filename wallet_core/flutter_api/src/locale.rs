@@ -0,0 +1,19 @@
+use std::sync::{Mutex, PoisonError};
+
+use wallet::FALLBACK_LANGUAGE;
+
+/// The locale of the app, which is used to format attribute values before they are sent to Flutter.
+static LOCALE: Mutex<Option<String>> = Mutex::new(None);
+
+pub fn set_locale(locale: String) {
+    LOCALE.lock().unwrap_or_else(PoisonError::into_inner).replace(locale);
+}
+
+/// Return the locale set by Flutter, or [`FALLBACK_LANGUAGE`] if it has not been set yet.
+pub fn current_locale() -> String {
+    LOCALE
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+        .unwrap_or_else(|| FALLBACK_LANGUAGE.to_string())
+}
@@ -4,6 +4,8 @@ use backtrace::Backtrace;
 
 use tracing::error;
 
+use crate::crash;
+
 pub fn init_panic_logger() {
     panic::set_hook(Box::new(|panic_info| {
         // Unfortunately, std::backtrace::Backtrace does not work on Android.
@@ -30,7 +32,10 @@ pub fn init_panic_logger() {
             .filter(|backtrace_line| !backtrace_line.is_empty())
             .for_each(|backtrace_line| error!("{}", backtrace_line));
 
-        // Make sure that spawned tasks exit upon panic as well.
-        std::process::exit(1);
+        // Make sure that spawned tasks exit upon panic as well. A panic in an API call is caught by the
+        // `#[async_runtime]` macro instead, which reports it before exiting, see [`crash::report_panic()`].
+        if !crash::in_api_call() {
+            std::process::exit(1);
+        }
     }));
 }
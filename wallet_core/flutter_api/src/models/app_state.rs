@@ -0,0 +1,13 @@
+pub enum AppState {
+    Foreground,
+    Background,
+}
+
+impl From<AppState> for wallet::AppState {
+    fn from(value: AppState) -> Self {
+        match value {
+            AppState::Foreground => Self::Foreground,
+            AppState::Background => Self::Background,
+        }
+    }
+}
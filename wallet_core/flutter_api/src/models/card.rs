@@ -1,7 +1,10 @@
 use wallet::{
     self, Attribute, AttributeValue, Document, DocumentAttributes, DocumentPersistence, GenderAttributeValue,
+    ATTRIBUTE_DISPLAY_REGISTRY,
 };
 
+use crate::locale::current_locale;
+
 pub struct Card {
     pub persistence: CardPersistence,
     pub doc_type: String,
@@ -17,6 +20,8 @@ pub struct CardAttribute {
     pub key: String,
     pub labels: Vec<LocalizedString>,
     pub value: CardValue,
+    /// The value formatted for display in the current locale.
+    pub display_value: String,
 }
 
 pub enum CardValue {
@@ -64,13 +69,13 @@ impl From<Document> for Card {
         Card {
             persistence: value.persistence.into(),
             doc_type: value.doc_type.to_string(),
-            attributes: into_card_attributes(value.attributes),
+            attributes: into_card_attributes(value.doc_type, value.attributes),
         }
     }
 }
 
-impl From<(String, Attribute)> for CardAttribute {
-    fn from((key, attribute): (String, Attribute)) -> Self {
+impl CardAttribute {
+    fn from_attribute(doc_type: &str, key: String, attribute: Attribute, locale: &str) -> Self {
         let labels = attribute
             .key_labels
             .into_iter()
@@ -79,8 +84,14 @@ impl From<(String, Attribute)> for CardAttribute {
                 value: value.to_string(),
             })
             .collect();
+        let display_value = ATTRIBUTE_DISPLAY_REGISTRY.format_value(doc_type, &key, &attribute.value, locale);
         let value = attribute.value.into();
-        CardAttribute { key, labels, value }
+        CardAttribute {
+            key,
+            labels,
+            value,
+            display_value,
+        }
     }
 }
 
@@ -97,9 +108,11 @@ impl From<AttributeValue> for CardValue {
     }
 }
 
-pub(crate) fn into_card_attributes(attributes: DocumentAttributes) -> Vec<CardAttribute> {
+pub(crate) fn into_card_attributes(doc_type: &str, attributes: DocumentAttributes) -> Vec<CardAttribute> {
+    let locale = current_locale();
+
     attributes
         .into_iter()
-        .map(|(key, attribute)| CardAttribute::from((key.to_string(), attribute)))
+        .map(|(key, attribute)| CardAttribute::from_attribute(doc_type, key.to_string(), attribute, &locale))
         .collect()
 }
@@ -143,7 +143,7 @@ impl From<DisclosureDocument> for RequestedCard {
     fn from(value: DisclosureDocument) -> Self {
         RequestedCard {
             doc_type: value.doc_type.to_string(),
-            attributes: into_card_attributes(value.attributes),
+            attributes: into_card_attributes(value.doc_type, value.attributes),
         }
     }
 }
@@ -1,4 +1,4 @@
-use wallet::errors::{InstructionError, PidIssuanceError, WalletUnlockError};
+use wallet::errors::{InstructionError, PidIssuanceError, WalletDeregistrationError, WalletUnlockError};
 
 pub enum WalletInstructionResult {
     Ok,
@@ -79,3 +79,28 @@ impl TryFrom<Result<(), PidIssuanceError>> for WalletInstructionResult {
         }
     }
 }
+
+/// This conversion distinguishes between 3 distinct cases:
+///
+/// 1. In case of a successful result, [`WalletInstructionResult::Ok`] will be returned.
+/// 2. In case of an expected and/or specific error case a different variant of
+///    [`WalletInstructionResult`] by mapping the nested [InstructionError].
+/// 3. In any other cases, this is an unexpected and/or generic error and the
+///    [`WalletDeregistrationError`] will be returned unchanged.
+impl TryFrom<Result<(), WalletDeregistrationError>> for WalletInstructionResult {
+    type Error = WalletDeregistrationError;
+
+    fn try_from(value: Result<(), WalletDeregistrationError>) -> Result<Self, Self::Error> {
+        match value {
+            Ok(_) => Ok(WalletInstructionResult::Ok),
+            Err(WalletDeregistrationError::Instruction(instruction_error)) => {
+                Ok(WalletInstructionResult::InstructionError {
+                    error: instruction_error
+                        .try_into()
+                        .map_err(WalletDeregistrationError::Instruction)?,
+                })
+            }
+            Err(error) => Err(error),
+        }
+    }
+}
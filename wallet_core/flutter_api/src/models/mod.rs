@@ -1,7 +1,9 @@
+pub mod app_state;
 pub mod card;
 pub mod config;
 pub mod disclosure;
 pub mod instruction;
+pub mod page;
 pub mod pin;
 pub mod uri;
 pub mod wallet_event;
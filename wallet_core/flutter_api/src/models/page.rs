@@ -0,0 +1,190 @@
+use std::cmp::Ordering;
+
+use chrono::Datelike;
+
+use wallet::{display_languages, format_year_month, mdoc::LocalizedStrings, Document, HistoryEvent};
+
+use super::{
+    card::Card,
+    wallet_event::{WalletEvent, WalletEvents},
+};
+
+/// A consecutive range of items on a page that belong together, e.g. the history events of a single month.
+/// The `key` can be used to merge the last group of a page with the first group of the next page.
+pub struct ItemGroup {
+    pub key: String,
+    pub label: String,
+    pub start_index: u32,
+    pub count: u32,
+}
+
+pub struct CardsPage {
+    pub cards: Vec<Card>,
+    pub groups: Vec<ItemGroup>,
+    pub total_count: u32,
+}
+
+pub struct HistoryPage {
+    pub events: Vec<WalletEvent>,
+    pub groups: Vec<ItemGroup>,
+    pub total_count: u32,
+}
+
+impl CardsPage {
+    /// Sort the documents by the name of their issuer in the specified locale, keeping the existing order of the
+    /// documents of the same issuer, group them by issuer and return the requested page.
+    pub fn new(documents: Vec<Document>, offset: u32, limit: u32, locale: &str) -> Self {
+        let mut documents = documents
+            .into_iter()
+            .map(|document| {
+                let issuer = document
+                    .issuer_registration
+                    .as_ref()
+                    .map(|registration| localized_string(&registration.organization.display_name, locale))
+                    .unwrap_or_default();
+
+                (issuer, document)
+            })
+            .collect::<Vec<_>>();
+        documents.sort_by(|(issuer1, _), (issuer2, _)| compare_collated(issuer1, issuer2));
+
+        let total_count = documents.len() as u32;
+        let (issuers, documents): (Vec<_>, Vec<_>) = paginate(documents, offset, limit).into_iter().unzip();
+
+        CardsPage {
+            cards: documents.into_iter().map(Card::from).collect(),
+            groups: group_consecutive(&issuers, |issuer| (issuer.clone(), issuer.clone())),
+            total_count,
+        }
+    }
+}
+
+impl HistoryPage {
+    /// Group the history events, which are expected to be sorted by their timestamp,
+    /// by the month they occurred in and return the requested page.
+    pub fn new(history: Vec<HistoryEvent>, offset: u32, limit: u32, locale: &str) -> Self {
+        // A single history event may result in multiple events for Flutter, which all share its timestamp.
+        let events = history
+            .into_iter()
+            .flat_map(|event| {
+                let date = event.timestamp().date_naive();
+
+                WalletEvents::from(event).into_iter().map(move |event| (date, event))
+            })
+            .collect::<Vec<_>>();
+
+        let total_count = events.len() as u32;
+        let (dates, events): (Vec<_>, Vec<_>) = paginate(events, offset, limit).into_iter().unzip();
+
+        HistoryPage {
+            events,
+            groups: group_consecutive(&dates, |date| {
+                (
+                    format!("{:04}-{:02}", date.year(), date.month()),
+                    format_year_month(date, locale),
+                )
+            }),
+            total_count,
+        }
+    }
+}
+
+fn paginate<T>(items: Vec<T>, offset: u32, limit: u32) -> Vec<T> {
+    items.into_iter().skip(offset as usize).take(limit as usize).collect()
+}
+
+/// Create a group for every run of consecutive items that share the same key, as returned by `key_label`
+/// together with the label of the group.
+fn group_consecutive<T>(items: &[T], key_label: impl Fn(&T) -> (String, String)) -> Vec<ItemGroup> {
+    items.iter().enumerate().fold(Vec::new(), |mut groups, (index, item)| {
+        let (key, label) = key_label(item);
+
+        match groups.last_mut() {
+            Some(group) if group.key == key => group.count += 1,
+            _ => groups.push(ItemGroup {
+                key,
+                label,
+                start_index: index as u32,
+                count: 1,
+            }),
+        }
+
+        groups
+    })
+}
+
+/// Select the string for the language of the locale, falling back to the same languages as attribute labels do.
+fn localized_string(strings: &LocalizedStrings, locale: &str) -> String {
+    let LocalizedStrings(strings) = strings;
+
+    display_languages(locale)
+        .find_map(|language| strings.get(language))
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Compare two strings the way a user would expect them to be ordered, i.e. ignoring case and diacritics,
+/// so that "Élan" is sorted between "Eiland" and "Emmen". Strings that are equal in that respect are ordered
+/// by their code points, so that the order is stable.
+fn compare_collated(value1: &str, value2: &str) -> Ordering {
+    collation_key(value1)
+        .cmp(&collation_key(value2))
+        .then_with(|| value1.cmp(value2))
+}
+
+fn collation_key(value: &str) -> String {
+    value.to_lowercase().chars().map(fold_diacritic).collect()
+}
+
+fn fold_diacritic(c: char) -> char {
+    match c {
+        'à'..='å' | 'ā' | 'ă' | 'ą' => 'a',
+        'ç' | 'ć' | 'č' => 'c',
+        'è'..='ë' | 'ē' | 'ė' | 'ę' | 'ě' => 'e',
+        'ì'..='ï' | 'ī' | 'į' => 'i',
+        'ñ' | 'ń' | 'ň' => 'n',
+        'ò'..='ö' | 'ø' | 'ō' | 'ő' => 'o',
+        'ś' | 'š' => 's',
+        'ù'..='ü' | 'ū' | 'ů' | 'ű' => 'u',
+        'ý' | 'ÿ' => 'y',
+        'ź' | 'ż' | 'ž' => 'z',
+        c => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_collated() {
+        let mut names = vec!["Emmen", "élan", "Zwolle", "Eiland", "amsterdam", "Élan"];
+        names.sort_by(|name1, name2| compare_collated(name1, name2));
+
+        assert_eq!(names, vec!["amsterdam", "Eiland", "Élan", "élan", "Emmen", "Zwolle"]);
+    }
+
+    #[test]
+    fn test_group_consecutive() {
+        let items = ["a", "a", "b", "a"];
+        let groups = group_consecutive(&items, |item| (item.to_string(), item.to_uppercase()));
+
+        let groups = groups
+            .iter()
+            .map(|group| (group.label.as_str(), group.start_index, group.count))
+            .collect::<Vec<_>>();
+        assert_eq!(groups, vec![("A", 0, 2), ("B", 2, 1), ("A", 3, 1)]);
+    }
+
+    #[test]
+    fn test_localized_string() {
+        let strings = LocalizedStrings::from(vec![("en", "Municipality"), ("nl", "Gemeente")]);
+
+        assert_eq!(localized_string(&strings, "nl-NL"), "Gemeente");
+        assert_eq!(localized_string(&strings, "fr"), "Municipality");
+        assert_eq!(
+            localized_string(&LocalizedStrings::from(vec![("nl", "Gemeente")]), "en"),
+            "Gemeente"
+        );
+    }
+}
@@ -1,4 +1,6 @@
-use wallet::errors::PinValidationError;
+use chrono::Utc;
+
+use wallet::{errors::PinValidationError, PinLockout};
 
 pub enum PinValidationResult {
     Ok,
@@ -12,7 +14,7 @@ impl From<Result<(), PinValidationError>> for PinValidationResult {
         match value {
             Ok(_) => Self::Ok,
             Err(e) => match e {
-                PinValidationError::NonDigits => Self::OtherIssue,
+                PinValidationError::InvalidCharacters => Self::OtherIssue,
                 PinValidationError::InvalidLength => Self::OtherIssue,
                 PinValidationError::TooFewUniqueDigits => Self::TooFewUniqueDigits,
                 PinValidationError::AscendingDigits => Self::SequentialDigits,
@@ -21,3 +23,20 @@ impl From<Result<(), PinValidationError>> for PinValidationResult {
         }
     }
 }
+
+pub struct PinLockoutState {
+    pub is_blocked: bool,
+    /// The number of milliseconds left until the PIN may be entered again, if it is timed out.
+    pub timeout_millis: Option<u64>,
+}
+
+impl From<PinLockout> for PinLockoutState {
+    fn from(value: PinLockout) -> Self {
+        PinLockoutState {
+            is_blocked: matches!(value, PinLockout::Blocked),
+            timeout_millis: value
+                .remaining(Utc::now())
+                .map(|remaining| remaining.num_milliseconds() as u64),
+        }
+    }
+}
@@ -0,0 +1,209 @@
+//! Shared building blocks for the examples: an in-memory issuer and verifier, HTTP clients that call these directly
+//! instead of going over the network, and a data source for the mdocs held by the wallet.
+
+// Not every example uses every item in this module.
+#![allow(dead_code)]
+
+use std::{
+    collections::{HashMap, HashSet},
+    convert::Infallible,
+    sync::Arc,
+};
+
+use chrono::{Duration, Utc};
+use ciborium::value::Value;
+use indexmap::IndexMap;
+use serde::{de::DeserializeOwned, Serialize};
+use url::Url;
+use webpki::TrustAnchor;
+
+use nl_wallet_mdoc::{
+    basic_sa_ext::{Entry, UnsignedMdoc},
+    holder::{HttpClient, HttpClientResult, MdocCopies, MdocDataSource, StoredMdoc},
+    iso::{device_retrieval::ItemsRequest, mdocs::DocType},
+    issuer::{IssuanceData, Issuer},
+    mock,
+    server_keys::{KeyRing, PrivateKey},
+    server_state::MemorySessionStore,
+    utils::{
+        reader_auth::{mock::reader_registration_mock, ReaderRegistration},
+        serialization,
+        x509::{Certificate, CertificateError, CertificateType},
+    },
+    verifier::{DisclosureData, Verifier},
+};
+
+pub const DOC_TYPE: &str = "com.example.pid";
+pub const NAME_SPACE: &str = "com.example.pid";
+pub const ATTRIBUTES: [(&str, &str); 2] = [("given_name", "Willeke"), ("family_name", "De Bruijn")];
+
+pub const ISSUER_URL: &str = "https://issuer.example.com/";
+pub const VERIFIER_URL: &str = "https://verifier.example.com/";
+
+pub const RP_CA_CN: &str = "ca.rp.example.com";
+pub const RP_CERT_CN: &str = "cert.rp.example.com";
+
+pub type ExampleIssuer = Issuer<SingleKeyRing, MemorySessionStore<IssuanceData>>;
+pub type ExampleVerifier = Verifier<SingleKeyRing, MemorySessionStore<DisclosureData>>;
+
+/// A [`KeyRing`] that uses the same private key for every doctype or usecase.
+pub struct SingleKeyRing(pub PrivateKey);
+
+impl KeyRing for SingleKeyRing {
+    fn private_key(&self, _: &str) -> Option<&PrivateKey> {
+        Some(&self.0)
+    }
+}
+
+/// Passes the messages of the holder directly to an in-memory [`Issuer`].
+pub struct IssuerClient(pub Arc<ExampleIssuer>);
+
+impl HttpClient for IssuerClient {
+    async fn post<R, V>(&self, url: &Url, val: &V) -> HttpClientResult<R>
+    where
+        V: Serialize,
+        R: DeserializeOwned,
+    {
+        let session_token = url.path_segments().unwrap().last().unwrap().to_string();
+        let msg = serialization::cbor_serialize(val).unwrap();
+
+        let response = self.0.process_message(session_token.into(), &msg).await.unwrap();
+
+        Ok(serialization::cbor_deserialize(response.as_slice()).unwrap())
+    }
+}
+
+/// Passes the messages of the holder directly to an in-memory [`Verifier`].
+pub struct VerifierClient(pub Arc<ExampleVerifier>);
+
+impl HttpClient for VerifierClient {
+    async fn post<R, V>(&self, url: &Url, val: &V) -> HttpClientResult<R>
+    where
+        V: Serialize,
+        R: DeserializeOwned,
+    {
+        let session_token = url.path_segments().unwrap().last().unwrap().to_string();
+        let msg = serialization::cbor_serialize(val).unwrap();
+
+        let session_data = self.0.process_message(&msg, session_token.into()).await.unwrap();
+
+        let response = serialization::cbor_serialize(&session_data).unwrap();
+        Ok(serialization::cbor_deserialize(response.as_slice()).unwrap())
+    }
+}
+
+/// Holds the mdocs that the holder received during issuance, so that they can be disclosed.
+pub struct ExampleMdocDataSource(HashMap<DocType, MdocCopies>);
+
+impl From<Vec<MdocCopies>> for ExampleMdocDataSource {
+    fn from(value: Vec<MdocCopies>) -> Self {
+        ExampleMdocDataSource(
+            value
+                .into_iter()
+                .map(|copies| (copies.cred_copies.first().unwrap().doc_type.clone(), copies))
+                .collect(),
+        )
+    }
+}
+
+impl MdocDataSource for ExampleMdocDataSource {
+    type MdocIdentifier = String;
+    type Error = Infallible;
+
+    async fn mdoc_by_doc_types(
+        &self,
+        doc_types: &HashSet<&str>,
+    ) -> Result<Vec<Vec<StoredMdoc<Self::MdocIdentifier>>>, Self::Error> {
+        let stored_mdocs = self
+            .0
+            .iter()
+            .filter(|(doc_type, _)| doc_types.contains(doc_type.as_str()))
+            .map(|(doc_type, mdoc_copies)| {
+                vec![StoredMdoc {
+                    id: format!("{}_id", doc_type),
+                    mdoc: mdoc_copies.cred_copies.first().unwrap().clone(),
+                }]
+            })
+            .collect();
+
+        Ok(stored_mdocs)
+    }
+}
+
+/// Create an in-memory issuer, along with the CA certificate that the holder should trust.
+pub fn setup_issuer() -> Result<(Arc<ExampleIssuer>, Certificate), CertificateError> {
+    let (issuance_key, ca) = mock::generate_issuance_key_and_ca()?;
+    let issuer = ExampleIssuer::new(
+        ISSUER_URL.parse().unwrap(),
+        SingleKeyRing(issuance_key),
+        MemorySessionStore::new(),
+    );
+
+    Ok((Arc::new(issuer), ca))
+}
+
+/// Create an in-memory verifier that trusts the specified mdoc issuers and is allowed to request the attributes of
+/// [`DOC_TYPE`], along with the CA certificate that the holder should trust.
+pub fn setup_verifier(
+    mdoc_trust_anchors: &[TrustAnchor<'_>],
+) -> Result<(Arc<ExampleVerifier>, Certificate), CertificateError> {
+    let reader_registration = ReaderRegistration {
+        attributes: mock::reader_registration_attributes(
+            DOC_TYPE.to_string(),
+            NAME_SPACE.to_string(),
+            ATTRIBUTES.iter().map(|(name, _)| name).copied(),
+        ),
+        ..reader_registration_mock()
+    };
+
+    let (ca, ca_privkey) = Certificate::new_ca(RP_CA_CN)?;
+    let (cert, privkey) = Certificate::new(
+        &ca,
+        &ca_privkey,
+        RP_CERT_CN,
+        CertificateType::ReaderAuth(Box::new(reader_registration).into()),
+    )?;
+
+    let verifier = ExampleVerifier::new(
+        VERIFIER_URL.parse().unwrap(),
+        SingleKeyRing(PrivateKey::new(privkey, cert)),
+        MemorySessionStore::new(),
+        mdoc_trust_anchors.iter().map(|anchor| anchor.into()).collect(),
+    );
+
+    Ok((Arc::new(verifier), ca))
+}
+
+/// The attributes of [`DOC_TYPE`] that are offered by the issuer, valid for one year.
+pub fn unsigned_mdocs() -> Vec<UnsignedMdoc> {
+    let now = Utc::now();
+
+    vec![UnsignedMdoc {
+        doc_type: DOC_TYPE.to_string(),
+        copy_count: 2,
+        valid_from: now.into(),
+        valid_until: (now + Duration::days(365)).into(),
+        attributes: IndexMap::from([(
+            NAME_SPACE.to_string(),
+            ATTRIBUTES
+                .iter()
+                .map(|(name, value)| Entry {
+                    name: name.to_string(),
+                    value: Value::Text(value.to_string()),
+                })
+                .collect(),
+        )]),
+    }]
+}
+
+/// A request for all attributes of [`DOC_TYPE`].
+pub fn items_requests() -> Vec<ItemsRequest> {
+    vec![ItemsRequest {
+        doc_type: DOC_TYPE.to_string(),
+        name_spaces: IndexMap::from([(
+            NAME_SPACE.to_string(),
+            ATTRIBUTES.iter().map(|(name, _)| (name.to_string(), false)).collect(),
+        )]),
+        request_info: None,
+    }]
+}
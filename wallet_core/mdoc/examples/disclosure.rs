@@ -0,0 +1,104 @@
+//! Disclose attributes from mdocs held by the holder to a verifier (relying party), end-to-end: the holder first
+//! receives an mdoc from an issuer, then starts a disclosure session using the reader engagement of the verifier,
+//! discloses the requested attributes after consent, and the verifier retrieves the disclosed attributes.
+//!
+//! Run with `cargo run --features mock --example disclosure`.
+
+mod common;
+
+use nl_wallet_mdoc::{
+    holder::{DisclosureSession, Wallet},
+    mock::SoftwareKeyFactory,
+    utils::serialization,
+    verifier::{SessionType, StatusResponse},
+};
+use wallet_common::generator::TimeGenerator;
+use webpki::TrustAnchor;
+
+use common::{ExampleMdocDataSource, IssuerClient, VerifierClient, ATTRIBUTES, DOC_TYPE, NAME_SPACE};
+
+async fn run() -> anyhow::Result<()> {
+    // Issue an mdoc to the holder, as in the `issuance` example.
+    let (issuer, issuer_ca) = common::setup_issuer()?;
+    let mdoc_trust_anchors: [TrustAnchor; 1] = [(&issuer_ca).try_into()?];
+
+    let service_engagement = issuer.new_session(common::unsigned_mdocs()).await?;
+    let mut wallet = Wallet::new(IssuerClient(issuer));
+    wallet.start_issuance(service_engagement).await?;
+    let mdoc_data_source: ExampleMdocDataSource = wallet
+        .finish_issuance(&mdoc_trust_anchors, &SoftwareKeyFactory::default(), &TimeGenerator)
+        .await?
+        .into();
+
+    // Start a session at the verifier, as in the `verifier` example.
+    let (verifier, verifier_ca) = common::setup_verifier(&mdoc_trust_anchors)?;
+    let reader_trust_anchors: [TrustAnchor; 1] = [(&verifier_ca).try_into()?];
+
+    let (session_id, reader_engagement) = verifier
+        .new_session(
+            common::items_requests().into(),
+            SessionType::CrossDevice,
+            Default::default(),
+            None,
+        )
+        .await?;
+    let reader_engagement_bytes = serialization::cbor_serialize(&reader_engagement)?;
+
+    // The holder starts the session, which authenticates the verifier and matches the request against its mdocs.
+    let disclosure_session = DisclosureSession::start(
+        VerifierClient(verifier.clone()),
+        &reader_engagement_bytes,
+        None,
+        SessionType::CrossDevice,
+        &mdoc_data_source,
+        &reader_trust_anchors,
+    )
+    .await?;
+
+    let proposal = match disclosure_session {
+        DisclosureSession::Proposal(proposal) => proposal,
+        _ => anyhow::bail!("holder should be able to satisfy the request"),
+    };
+
+    // After the user consents, the holder discloses the proposed attributes.
+    proposal.disclose(&SoftwareKeyFactory::default()).await?;
+
+    let status = verifier.status(&session_id).await?;
+    anyhow::ensure!(
+        matches!(status, StatusResponse::Done),
+        "unexpected status: {:?}",
+        status
+    );
+
+    // As no return URL was used, the verifier does not require a transcript hash to hand out the attributes.
+    let disclosed_attributes = verifier.disclosed_attributes(&session_id, None).await?;
+    let disclosed = disclosed_attributes
+        .get(DOC_TYPE)
+        .and_then(|name_spaces| name_spaces.get(NAME_SPACE))
+        .into_iter()
+        .flatten()
+        .map(|entry| (entry.name.as_str(), entry.value.as_text().unwrap_or_default()))
+        .collect::<Vec<_>>();
+    anyhow::ensure!(
+        disclosed == ATTRIBUTES,
+        "unexpected disclosed attributes: {:?}",
+        disclosed
+    );
+
+    println!("Disclosed: {:?}", disclosed);
+
+    Ok(())
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> anyhow::Result<()> {
+    run().await
+}
+
+#[cfg(test)]
+mod tests {
+    #[tokio::test]
+    async fn test_disclosure() {
+        super::run().await.expect("example should succeed");
+    }
+}
@@ -0,0 +1,62 @@
+//! Generate a CA and the certificates that an issuer and a verifier (relying party) use to sign mdocs and to
+//! authenticate themselves to the holder, respectively. Both certificates contain the registration of their owner as
+//! a custom X.509 extension, which the holder reads to show to the user who it is dealing with.
+//!
+//! Run with `cargo run --features mock --example generate_certificates`.
+
+use nl_wallet_mdoc::utils::{
+    issuer_auth::mock::issuer_registration_mock,
+    reader_auth::mock::reader_registration_mock,
+    x509::{Certificate, CertificateType, CertificateUsage},
+};
+use wallet_common::generator::TimeGenerator;
+use webpki::TrustAnchor;
+
+async fn run() -> anyhow::Result<()> {
+    let (ca, ca_privkey) = Certificate::new_ca("ca.example.com")?;
+    let trust_anchors: [TrustAnchor; 1] = [(&ca).try_into()?];
+    println!("CA certificate: {:?}", ca.subject()?);
+
+    let certificates = [
+        (
+            "issuer.example.com",
+            CertificateType::Mdl(Box::new(issuer_registration_mock()).into()),
+        ),
+        (
+            "verifier.example.com",
+            CertificateType::ReaderAuth(Box::new(reader_registration_mock()).into()),
+        ),
+    ];
+
+    for (common_name, certificate_type) in certificates {
+        let usage = CertificateUsage::from(&certificate_type);
+        let (cert, _privkey) = Certificate::new(&ca, &ca_privkey, common_name, certificate_type)?;
+
+        // The holder verifies the certificate against the CA that it trusts for this usage.
+        cert.verify(usage.clone(), &[], &TimeGenerator, &trust_anchors)?;
+
+        // The registration can be read back from the certificate.
+        let registration = match CertificateType::from_certificate(&cert)? {
+            CertificateType::Mdl(registration) => registration.map(|registration| registration.organization),
+            CertificateType::ReaderAuth(registration) => registration.map(|registration| registration.organization),
+        };
+        anyhow::ensure!(registration.is_some(), "certificate should contain a registration");
+
+        println!("{:?} certificate: {:?}", usage, cert.subject()?);
+    }
+
+    Ok(())
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> anyhow::Result<()> {
+    run().await
+}
+
+#[cfg(test)]
+mod tests {
+    #[tokio::test]
+    async fn test_generate_certificates() {
+        super::run().await.expect("example should succeed");
+    }
+}
@@ -0,0 +1,78 @@
+//! Issue mdocs to a holder. The issuer starts a session for a set of unsigned mdocs, after which the holder inspects
+//! the offered attributes and either accepts them, in which case the issuer signs them with the keys generated by the
+//! holder, or rejects them.
+//!
+//! Run with `cargo run --features mock --example issuance`.
+
+mod common;
+
+use nl_wallet_mdoc::{holder::Wallet, mock::SoftwareKeyFactory};
+use wallet_common::generator::TimeGenerator;
+use webpki::TrustAnchor;
+
+use common::{IssuerClient, ATTRIBUTES, DOC_TYPE, NAME_SPACE};
+
+async fn run() -> anyhow::Result<()> {
+    let (issuer, issuer_ca) = common::setup_issuer()?;
+    let trust_anchors: [TrustAnchor; 1] = [(&issuer_ca).try_into()?];
+
+    // The issuer offers the attributes to the holder by means of a service engagement.
+    let service_engagement = issuer.new_session(common::unsigned_mdocs()).await?;
+
+    let mut wallet = Wallet::new(IssuerClient(issuer));
+    let offered_mdocs = wallet.start_issuance(service_engagement).await?;
+    println!(
+        "Offered: {:?}",
+        offered_mdocs
+            .iter()
+            .map(|unsigned| &unsigned.doc_type)
+            .collect::<Vec<_>>()
+    );
+
+    // After the user consents, the holder generates the keys and receives the signed mdocs. The issuer certificate
+    // is verified against the trust anchors of the holder.
+    let mdocs = wallet
+        .finish_issuance(&trust_anchors, &SoftwareKeyFactory::default(), &TimeGenerator)
+        .await?;
+
+    let mdoc = mdocs
+        .first()
+        .and_then(|copies| copies.cred_copies.first())
+        .ok_or_else(|| anyhow::anyhow!("no mdoc was issued"))?;
+    anyhow::ensure!(mdoc.doc_type == DOC_TYPE, "unexpected doctype");
+
+    let attributes = mdoc.attributes();
+    let names = attributes
+        .get(NAME_SPACE)
+        .into_iter()
+        .flatten()
+        .map(|entry| entry.name.as_str())
+        .collect::<Vec<_>>();
+    anyhow::ensure!(
+        names == ATTRIBUTES.map(|(name, _)| name),
+        "unexpected attributes: {:?}",
+        names
+    );
+
+    println!(
+        "Issued {} copies of {}: {:?}",
+        mdocs[0].cred_copies.len(),
+        DOC_TYPE,
+        names
+    );
+
+    Ok(())
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> anyhow::Result<()> {
+    run().await
+}
+
+#[cfg(test)]
+mod tests {
+    #[tokio::test]
+    async fn test_issuance() {
+        super::run().await.expect("example should succeed");
+    }
+}
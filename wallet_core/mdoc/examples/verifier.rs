@@ -0,0 +1,61 @@
+//! Set up a verifier (relying party) and start a disclosure session. The verifier returns a reader engagement, which
+//! is presented to the holder (e.g. as a QR code or a universal link) to start the session on the holder side. In
+//! the meantime, the frontend of the relying party polls the status of the session.
+//!
+//! Run with `cargo run --features mock --example verifier`.
+
+mod common;
+
+use nl_wallet_mdoc::{
+    utils::serialization,
+    verifier::{SessionType, StatusResponse},
+};
+use webpki::TrustAnchor;
+
+async fn run() -> anyhow::Result<()> {
+    // The verifier only accepts mdocs signed by issuers it trusts.
+    let (_issuer, issuer_ca) = common::setup_issuer()?;
+    let mdoc_trust_anchors: [TrustAnchor; 1] = [(&issuer_ca).try_into()?];
+    let (verifier, _verifier_ca) = common::setup_verifier(&mdoc_trust_anchors)?;
+
+    let (session_id, reader_engagement) = verifier
+        .new_session(
+            common::items_requests().into(),
+            SessionType::CrossDevice,
+            Default::default(),
+            None,
+        )
+        .await?;
+
+    let reader_engagement_bytes = serialization::cbor_serialize(&reader_engagement)?;
+    println!(
+        "Reader engagement for session {:?}: {} bytes",
+        session_id,
+        reader_engagement_bytes.len()
+    );
+
+    // As long as the holder has not contacted the verifier, the session remains in its initial state.
+    let status = verifier.status(&session_id).await?;
+    anyhow::ensure!(
+        matches!(status, StatusResponse::Created),
+        "unexpected status: {:?}",
+        status
+    );
+
+    println!("Session status: {:?}", status);
+
+    Ok(())
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> anyhow::Result<()> {
+    run().await
+}
+
+#[cfg(test)]
+mod tests {
+    #[tokio::test]
+    async fn test_verifier() {
+        super::run().await.expect("example should succeed");
+    }
+}
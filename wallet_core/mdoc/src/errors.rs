@@ -2,6 +2,7 @@ use crate::{
     holder::HolderError,
     issuer_shared::IssuanceError,
     server_keys::KeysError,
+    status_list::StatusListError,
     utils::{cose::CoseError, crypto::CryptoError, serialization::CborError},
     verifier::VerificationError,
 };
@@ -24,4 +25,6 @@ pub enum Error {
     Verification(#[from] VerificationError),
     #[error("keys error: {0}")]
     KeysError(#[from] KeysError),
+    #[error("status list error: {0}")]
+    StatusList(#[from] StatusListError),
 }
@@ -72,8 +72,18 @@ impl ReaderEngagement {
 }
 
 impl DeviceEngagement {
-    pub fn new_device_engagement(referrer_url: Url) -> Result<(DeviceEngagement, SecretKey)> {
+    /// Create a new [`DeviceEngagement`] and its ephemeral private key. The first [`OriginInfo`] states how the
+    /// [`ReaderEngagement`] was received, which depends on the [`SessionType`]: from a website with the specified
+    /// referrer URL when using a universal link, or from a QR code when scanning one.
+    pub fn new_device_engagement(
+        session_type: SessionType,
+        referrer_url: Url,
+    ) -> Result<(DeviceEngagement, SecretKey)> {
         let privkey = SecretKey::random(&mut OsRng);
+        let received = match session_type {
+            SessionType::SameDevice => OriginInfoType::Website(referrer_url),
+            SessionType::CrossDevice => OriginInfoType::OnDeviceQRCode,
+        };
 
         let engagement = Engagement {
             version: EngagementVersion::V1_0,
@@ -82,7 +92,7 @@ impl DeviceEngagement {
             origin_infos: vec![
                 OriginInfo {
                     cat: OriginInfoDirection::Received,
-                    typ: OriginInfoType::Website(referrer_url),
+                    typ: received,
                 },
                 OriginInfo {
                     cat: OriginInfoDirection::Delivered,
@@ -2,11 +2,13 @@ use std::{collections::HashSet, error::Error};
 
 use super::Mdoc;
 
+pub use preview::DisclosurePreview;
 pub use session::{DisclosureMissingAttributes, DisclosureProposal, DisclosureSession, ProposedAttributes};
 
 mod device_signed;
 mod engagement;
 mod issuer_signed;
+mod preview;
 mod proposed_document;
 mod request;
 mod response;
@@ -0,0 +1,120 @@
+use crate::{device_retrieval::DeviceRequest, errors::Result, holder::HolderError, identifiers::AttributeIdentifier};
+
+use super::{
+    request::{self, DeviceRequestMatch},
+    MdocDataSource, ProposedAttributes,
+};
+
+/// The outcome of matching the attributes in a [`DeviceRequest`] against the `Mdoc`s held by the holder,
+/// without starting a disclosure session with a verifier. This allows the holder to check in advance
+/// whether a request can be satisfied, e.g. based on a static QR code that describes the request.
+#[derive(Debug, Clone)]
+pub enum DisclosurePreview<I> {
+    /// Not all of the requested attributes are available.
+    MissingAttributes(Vec<AttributeIdentifier>),
+    /// All of the requested attributes are available, these would be disclosed from the `Mdoc`s identified.
+    Proposal {
+        source_identifiers: Vec<I>,
+        proposed_attributes: ProposedAttributes,
+    },
+}
+
+impl DeviceRequest {
+    /// Preview disclosure of this [`DeviceRequest`], by matching its requested attributes against the
+    /// `Mdoc`s provided by `mdoc_data_source`. Note that reader authentication is not verified here,
+    /// as it can only be checked in the context of an actual session.
+    pub async fn preview<S, I>(&self, mdoc_data_source: &S) -> Result<DisclosurePreview<I>>
+    where
+        S: MdocDataSource<MdocIdentifier = I>,
+    {
+        // A device request without any attributes is useless, so return an error.
+        if !self.has_attributes() {
+            return Err(HolderError::NoAttributesRequested.into());
+        }
+
+        // As nothing will be signed for a preview, there is no need for an actual `DeviceSigned` challenge.
+        let candidates_by_doc_type = match self
+            .match_stored_documents_with_challenge(mdoc_data_source, |_| Ok(Vec::new()))
+            .await?
+        {
+            DeviceRequestMatch::Candidates(candidates) => candidates,
+            DeviceRequestMatch::MissingAttributes(missing_attributes) => {
+                return Ok(DisclosurePreview::MissingAttributes(missing_attributes))
+            }
+        };
+
+        // If we have multiple candidates for any of the doc types, return an error, as a session would.
+        let proposed_documents = request::flatten_single_candidates(candidates_by_doc_type)?;
+
+        let proposed_attributes = proposed_documents
+            .iter()
+            .map(|document| (document.doc_type.clone(), document.name_spaces()))
+            .collect();
+        let source_identifiers = proposed_documents
+            .into_iter()
+            .map(|document| document.source_identifier)
+            .collect();
+
+        let preview = DisclosurePreview::Proposal {
+            source_identifiers,
+            proposed_attributes,
+        };
+
+        Ok(preview)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+
+    use crate::{
+        errors::Error,
+        examples::{EXAMPLE_DOC_TYPE, EXAMPLE_NAMESPACE},
+    };
+
+    use super::{super::test_utils::*, *};
+
+    #[tokio::test]
+    async fn test_device_request_preview() {
+        let mdoc_data_source = MockMdocDataSource::default();
+
+        // A request for attributes that are present should result in a proposal.
+        let device_request = DeviceRequest::new(vec![example_items_request()]);
+        let preview = device_request
+            .preview(&mdoc_data_source)
+            .await
+            .expect("Could not preview device request");
+
+        assert_matches!(
+            preview,
+            DisclosurePreview::Proposal { source_identifiers, proposed_attributes }
+                if source_identifiers.len() == 1 && proposed_attributes.contains_key(EXAMPLE_DOC_TYPE)
+        );
+
+        // A request for an attribute that is not present should result in missing attributes.
+        let device_request = DeviceRequest::new(vec![items_request(
+            EXAMPLE_DOC_TYPE.to_string(),
+            EXAMPLE_NAMESPACE.to_string(),
+            ["foobar"].into_iter(),
+        )]);
+        let preview = device_request
+            .preview(&mdoc_data_source)
+            .await
+            .expect("Could not preview device request");
+
+        assert_matches!(
+            preview,
+            DisclosurePreview::MissingAttributes(missing_attributes)
+                if missing_attributes.len() == 1 && missing_attributes[0].attribute == "foobar"
+        );
+
+        // A request without any attributes should result in an error.
+        let error = DeviceRequest::new(vec![emtpy_items_request()])
+            .preview(&mdoc_data_source)
+            .await
+            .expect_err("Previewing empty device request should have resulted in an error");
+
+        assert_matches!(error, Error::Holder(HolderError::NoAttributesRequested));
+    }
+}
@@ -29,6 +29,29 @@ pub(super) enum DeviceRequestMatch<I> {
     MissingAttributes(Vec<AttributeIdentifier>), // TODO: Report on missing attributes per `Mdoc` candidate.
 }
 
+/// Flatten the candidates per `doc_type` to a single `Vec`, which
+/// results in an error if there are multiple candidates for any `doc_type`.
+// TODO: Support having the user choose between multiple candidates.
+pub(super) fn flatten_single_candidates<I>(
+    candidates_by_doc_type: HashMap<DocType, Vec<ProposedDocument<I>>>,
+) -> Result<Vec<ProposedDocument<I>>> {
+    if candidates_by_doc_type.values().any(|candidates| candidates.len() > 1) {
+        let duplicate_doc_types = candidates_by_doc_type
+            .into_iter()
+            .filter(|(_, candidates)| candidates.len() > 1)
+            .map(|(doc_type, _)| doc_type)
+            .collect();
+
+        return Err(HolderError::MultipleCandidates(duplicate_doc_types).into());
+    }
+
+    // Now that we know that we have exactly one candidate for every `doc_type`,
+    // we can flatten these candidates to a 1-dimensional `Vec`.
+    let proposed_documents = candidates_by_doc_type.into_values().flatten().collect();
+
+    Ok(proposed_documents)
+}
+
 impl DeviceRequest {
     /// Returns `true` if this request has any attributes at all.
     pub fn has_attributes(&self) -> bool {
@@ -102,6 +125,29 @@ impl DeviceRequest {
     ) -> Result<DeviceRequestMatch<I>>
     where
         S: MdocDataSource<MdocIdentifier = I>,
+    {
+        self.match_stored_documents_with_challenge(mdoc_data_source, |doc_type| {
+            // Calculate the `DeviceAuthentication` for this `doc_type` and turn it into bytes,
+            // so that it can be used as a challenge when constructing `DeviceSigned` later on.
+            let device_authentication =
+                DeviceAuthentication::from_session_transcript(session_transcript.clone(), doc_type.to_string());
+            let device_signed_challenge = serialization::cbor_serialize(&TaggedBytes(device_authentication))?;
+
+            Ok(device_signed_challenge)
+        })
+        .await
+    }
+
+    /// Match the requested attributes against the `Mdoc`s provided by `mdoc_data_source`, using the
+    /// `device_signed_challenge` closure to calculate the `DeviceSigned` challenge for every `doc_type`.
+    pub(super) async fn match_stored_documents_with_challenge<S, I, F>(
+        &self,
+        mdoc_data_source: &S,
+        device_signed_challenge: F,
+    ) -> Result<DeviceRequestMatch<I>>
+    where
+        S: MdocDataSource<MdocIdentifier = I>,
+        F: Fn(&str) -> Result<Vec<u8>>,
     {
         // Make a `HashSet` of doc types from the `DeviceRequest` to account
         // for potential duplicate doc types in the request, then fetch them
@@ -146,7 +192,7 @@ impl DeviceRequest {
         //   and any subsequent `Mdoc`s should have the same `doc_type`. This is part of
         //   the contract of `MdocDataSource` that is not enforceable.
         // * Calculate the challenge needed to create the `DeviceSigned` for this
-        //   `doc_type` later on during actual disclosure.
+        //   `doc_type` later on during actual disclosure, using the provided closure.
         // * Convert all `Mdoc`s that satisfy the requirement to `ProposedDocument`,
         //   while collecting any missing attributes separately.
         // * Collect the candidates in a `HashMap` per `doc_type`.
@@ -166,11 +212,9 @@ impl DeviceRequest {
             .filter(|doc_type_mdocs| !doc_type_mdocs.is_empty())
             .collect::<Vec<_>>();
 
-        let mdocs_count = stored_mdocs.len();
         let candidates_by_doc_type = stored_mdocs
             .into_iter()
-            .zip(itertools::repeat_n(session_transcript, mdocs_count))
-            .map(|(doc_type_stored_mdocs, session_transcript)| {
+            .map(|doc_type_stored_mdocs| {
                 // First, remove the `IndexSet` of attributes that are required for this
                 // `doc_type` from the global `HashSet`. If this cannot be found, then
                 // `MdocDataSource` did not obey the contract as noted in the comment above.
@@ -187,11 +231,7 @@ impl DeviceRequest {
                     }
                 }
 
-                // Calculate the `DeviceAuthentication` for this `doc_type` and turn it into bytes,
-                // so that it can be used as a challenge when constructing `DeviceSigned` later on.
-                let device_authentication =
-                    DeviceAuthentication::from_session_transcript(session_transcript, doc_type.to_string());
-                let device_signed_challenge = serialization::cbor_serialize(&TaggedBytes(device_authentication))?;
+                let device_signed_challenge = device_signed_challenge(doc_type)?;
 
                 // Get all the candidates and missing attributes from the provided `Mdoc`s.
                 let (candidates, missing_attributes) =
@@ -25,7 +25,11 @@ use crate::{
     verifier::SessionType,
 };
 
-use super::{proposed_document::ProposedDocument, request::DeviceRequestMatch, MdocDataSource};
+use super::{
+    proposed_document::ProposedDocument,
+    request::{self, DeviceRequestMatch},
+    MdocDataSource,
+};
 
 const REFERRER_URL: &str = "https://referrer.url/";
 const TRANSCRIPT_HASH_PARAM: &str = "transcript_hash";
@@ -106,7 +110,7 @@ where
         // Create a new `DeviceEngagement` message and private key. Use a
         // static referrer URL, as this is not a feature we actually use.
         let (device_engagement, ephemeral_privkey) =
-            DeviceEngagement::new_device_engagement(Url::parse(REFERRER_URL).unwrap())?;
+            DeviceEngagement::new_device_engagement(session_type, Url::parse(REFERRER_URL).unwrap())?;
 
         // Derive the session transcript and keys in both directions from the
         // `ReaderEngagement`, the `DeviceEngagement` and the ephemeral private key.
@@ -246,20 +250,7 @@ where
         };
 
         // If we have multiple candidates for any of the doc types, return an error.
-        // TODO: Support having the user choose between multiple candidates.
-        if candidates_by_doc_type.values().any(|candidates| candidates.len() > 1) {
-            let duplicate_doc_types = candidates_by_doc_type
-                .into_iter()
-                .filter(|(_, candidates)| candidates.len() > 1)
-                .map(|(doc_type, _)| doc_type)
-                .collect();
-
-            return Err(HolderError::MultipleCandidates(duplicate_doc_types).into());
-        }
-
-        // Now that we know that we have exactly one candidate for every `doc_type`,
-        // we can flatten these candidates to a 1-dimensional `Vec`.
-        let proposed_documents = candidates_by_doc_type.into_values().flatten().collect::<Vec<_>>();
+        let proposed_documents = request::flatten_single_candidates(candidates_by_doc_type)?;
 
         let result = VerifierSessionDataCheckResult::ProposedDocuments(proposed_documents);
 
@@ -109,7 +109,8 @@ pub fn create_basic_session_transcript() -> SessionTranscript {
     let (reader_engagement, _reader_private_key) =
         ReaderEngagement::new_reader_engagement(SESSION_URL.parse().unwrap()).unwrap();
     let (device_engagement, _device_private_key) =
-        DeviceEngagement::new_device_engagement("https://example.com".parse().unwrap()).unwrap();
+        DeviceEngagement::new_device_engagement(SessionType::SameDevice, "https://example.com".parse().unwrap())
+            .unwrap();
 
     SessionTranscript::new(SessionType::SameDevice, &reader_engagement, &device_engagement).unwrap()
 }
@@ -1,8 +1,10 @@
-use futures::future::TryFutureExt;
+use reqwest::StatusCode;
 use serde::{de::DeserializeOwned, Serialize};
 use url::Url;
 use x509_parser::nom::AsBytes;
 
+use wallet_common::correlation::CorrelationTracker;
+
 use crate::{
     utils::serialization::{cbor_deserialize, cbor_serialize, CborError},
     Error,
@@ -16,6 +18,8 @@ pub enum HttpClientError {
     Cbor(#[from] CborError),
     #[error("HTTP request error: {0}")]
     Request(#[from] reqwest::Error),
+    #[error("session has expired")]
+    SessionExpired,
 }
 
 impl From<HttpClientError> for Error {
@@ -33,8 +37,18 @@ pub trait HttpClient {
         R: DeserializeOwned;
 }
 
-/// Send and receive CBOR-encoded messages over HTTP using a [`reqwest::Client`].
-pub struct CborHttpClient(pub reqwest::Client);
+/// Send and receive CBOR-encoded messages over HTTP using a [`reqwest::Client`]. The correlation ID of the current
+/// operation, if any, is sent along with every request.
+pub struct CborHttpClient {
+    client: reqwest::Client,
+    correlation: CorrelationTracker,
+}
+
+impl CborHttpClient {
+    pub fn new(client: reqwest::Client, correlation: CorrelationTracker) -> Self {
+        Self { client, correlation }
+    }
+}
 
 impl HttpClient for CborHttpClient {
     async fn post<R, V>(&self, url: &Url, val: &V) -> HttpClientResult<R>
@@ -43,14 +57,20 @@ impl HttpClient for CborHttpClient {
         R: DeserializeOwned,
     {
         let bytes = cbor_serialize(val)?;
-        let response_bytes = self
-            .0
+        let response = self
+            .client
             .post(url.clone())
+            .headers(self.correlation.headers())
             .body(bytes)
             .send()
-            .and_then(|response| async { response.error_for_status()?.bytes().await })
-            .await
-            .map_err(HttpClientError::Request)?;
+            .await?;
+
+        // The server responds with 410 Gone when the session has expired.
+        if response.status() == StatusCode::GONE {
+            return Err(HttpClientError::SessionExpired);
+        }
+
+        let response_bytes = response.error_for_status()?.bytes().await?;
         let response = cbor_deserialize(response_bytes.as_bytes())?;
         Ok(response)
     }
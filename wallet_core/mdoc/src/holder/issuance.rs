@@ -1,10 +1,12 @@
+use chrono::{DateTime, Utc};
 use futures::future;
 use indexmap::IndexMap;
+use p256::ecdsa::VerifyingKey;
 use serde_bytes::ByteBuf;
 use url::Url;
 pub use webpki::TrustAnchor;
 
-use wallet_common::generator::TimeGenerator;
+use wallet_common::generator::Generator;
 
 use crate::{
     basic_sa_ext::{
@@ -27,8 +29,14 @@ use super::{HolderError, HttpClient, HttpClientResult, Mdoc, MdocCopies, Wallet}
 pub(crate) struct IssuanceSessionState {
     url: Url,
     request: RequestKeyGenerationMessage,
+    /// The keys that were generated for each mdoc, as identifier and public key, together with the responses sent
+    /// to the issuer. These are retained when finishing issuance fails, so that it can be retried with the same keys.
+    generated_keys: Option<(GeneratedKeys, KeyGenerationResponseMessage)>,
 }
 
+/// The identifiers and public keys of the keys that were generated, per mdoc.
+type GeneratedKeys = Vec<Vec<(String, VerifyingKey)>>;
+
 impl<H: HttpClient> Wallet<H> {
     pub fn has_issuance_session(&self) -> bool {
         self.session_state.is_some()
@@ -63,29 +71,59 @@ impl<H: HttpClient> Wallet<H> {
         self.session_state.replace(IssuanceSessionState {
             url: url.clone(),
             request,
+            generated_keys: None,
         });
 
         Ok(&self.session_state.as_ref().unwrap().request.unsigned_mdocs)
     }
 
+    /// Finish the issuance session by generating the keys and receiving the mdocs, which are verified against the
+    /// trust anchors at the time produced by `time`.
+    ///
+    /// If this fails after the keys have been generated, e.g. because the response of the issuer was lost, the
+    /// session is retained and calling this again resends the same responses. The issuer then returns the same mdocs,
+    /// so that no new keys are generated and the keys that were generated before do not go to waste.
     pub async fn finish_issuance<K: MdocEcdsaKey>(
         &mut self,
         trust_anchors: &[TrustAnchor<'_>],
         key_factory: &impl KeyFactory<Key = K>,
+        time: &impl Generator<DateTime<Utc>>,
     ) -> Result<Vec<MdocCopies>> {
         let state = self
             .session_state
-            .as_ref()
+            .as_mut()
             .ok_or(HolderError::MissingIssuanceSessionState)?;
 
-        // Compute responses
-        let (keys, responses) = state.keys_and_responses::<K>(key_factory).await?;
+        // Compute responses, or reuse those of an earlier attempt
+        let (keys, responses) = match state.generated_keys {
+            Some((ref key_identifiers, ref responses)) => {
+                let keys = key_identifiers
+                    .iter()
+                    .map(|keys| {
+                        keys.iter()
+                            .map(|(identifier, public_key)| key_factory.generate_existing(identifier, *public_key))
+                            .collect()
+                    })
+                    .collect();
+
+                (keys, responses.clone())
+            }
+            None => {
+                let (keys, responses) = state.keys_and_responses::<K>(key_factory).await?;
+                let key_identifiers = IssuanceSessionState::key_identifiers(&keys).await?;
+                state.generated_keys.replace((key_identifiers, responses.clone()));
+
+                (keys, responses)
+            }
+        };
 
         // Finish issuance protocol
         let issuer_response: DataToIssueMessage = self.client.post(&state.url, &responses).await?;
 
         // Process issuer response to obtain and save new mdocs
-        let creds = state.construct_mdocs(keys, issuer_response, trust_anchors).await?;
+        let creds = state
+            .construct_mdocs(keys, issuer_response, trust_anchors, time)
+            .await?;
 
         // Clear session state now that all fallible operations have not failed
         self.session_state.take();
@@ -94,7 +132,7 @@ impl<H: HttpClient> Wallet<H> {
     }
 
     pub async fn stop_issuance(&mut self) -> Result<()> {
-        let IssuanceSessionState { request, url } = self
+        let IssuanceSessionState { request, url, .. } = self
             .session_state
             .take()
             .ok_or(HolderError::MissingIssuanceSessionState)?;
@@ -118,11 +156,26 @@ impl IssuanceSessionState {
         Ok((private_keys, response))
     }
 
+    async fn key_identifiers<K: MdocEcdsaKey>(keys: &[Vec<K>]) -> Result<Vec<Vec<(String, VerifyingKey)>>> {
+        future::try_join_all(keys.iter().map(|keys| {
+            future::try_join_all(keys.iter().map(|key| async {
+                let public_key = key
+                    .verifying_key()
+                    .await
+                    .map_err(|e| IssuanceError::PrivatePublicKeyConversion(e.into()))?;
+
+                Ok((key.identifier().to_string(), public_key))
+            }))
+        }))
+        .await
+    }
+
     pub async fn construct_mdocs<K: MdocEcdsaKey>(
         &self,
         private_keys: Vec<Vec<K>>,
         issuer_response: DataToIssueMessage,
         trust_anchors: &[TrustAnchor<'_>],
+        time: &impl Generator<DateTime<Utc>>,
     ) -> Result<Vec<MdocCopies>> {
         future::try_join_all(
             issuer_response
@@ -130,7 +183,7 @@ impl IssuanceSessionState {
                 .iter()
                 .zip(&self.request.unsigned_mdocs)
                 .zip(&private_keys)
-                .map(|((doc, unsigned), keys)| Self::create_cred_copies(doc, unsigned, keys, trust_anchors)),
+                .map(|((doc, unsigned), keys)| Self::create_cred_copies(doc, unsigned, keys, trust_anchors, time)),
         )
         .await
     }
@@ -140,12 +193,13 @@ impl IssuanceSessionState {
         unsigned: &UnsignedMdoc,
         keys: &[K],
         trust_anchors: &[TrustAnchor<'_>],
+        time: &impl Generator<DateTime<Utc>>,
     ) -> Result<MdocCopies> {
         let cred_copies = future::try_join_all(
             doc.sparse_issuer_signed
                 .iter()
                 .zip(keys)
-                .map(|(iss_signature, key)| iss_signature.to_mdoc(key, unsigned, trust_anchors)),
+                .map(|(iss_signature, key)| iss_signature.to_mdoc(key, unsigned, trust_anchors, time)),
         )
         .await?;
 
@@ -159,6 +213,7 @@ impl SparseIssuerSigned {
         private_key: &K,
         unsigned: &UnsignedMdoc,
         trust_anchors: &[TrustAnchor<'_>],
+        time: &impl Generator<DateTime<Utc>>,
     ) -> Result<Mdoc> {
         let name_spaces: IssuerNameSpaces = unsigned
             .attributes
@@ -183,6 +238,7 @@ impl SparseIssuerSigned {
                 .try_into()?,
             doc_type: unsigned.doc_type.clone(),
             validity_info: self.sparse_issuer_auth.validity_info.clone(),
+            status: self.sparse_issuer_auth.status.clone(),
         };
         let issuer_auth = self
             .sparse_issuer_auth
@@ -195,12 +251,7 @@ impl SparseIssuerSigned {
         };
 
         // Construct the mdoc, also verifying it (using `IssuerSigned::verify()`).
-        let cred = Mdoc::new::<K>(
-            private_key.identifier().to_string(),
-            issuer_signed,
-            &TimeGenerator,
-            trust_anchors,
-        )?;
+        let cred = Mdoc::new::<K>(private_key.identifier().to_string(), issuer_signed, time, trust_anchors)?;
         Ok(cred)
     }
 
@@ -1,15 +1,16 @@
 use chrono::{DateTime, Utc};
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
+use serde_bytes::ByteBuf;
 use webpki::TrustAnchor;
 
 use wallet_common::generator::Generator;
 
 use crate::{
-    basic_sa_ext::Entry,
+    basic_sa_ext::{Entry, ReissuanceRequest},
     iso::*,
     utils::{
-        keys::{MdocEcdsaKey, MdocKeyType},
+        keys::{KeyFactory, MdocEcdsaKey, MdocKeyType},
         x509::Certificate,
     },
     verifier::ValidityRequirement,
@@ -100,4 +101,53 @@ impl Mdoc {
     pub fn issuer_certificate(&self) -> Result<Certificate> {
         self.issuer_signed.issuer_auth.signing_cert()
     }
+
+    /// Get the [`ValidityInfo`] of the mdoc, as contained in its MSO.
+    pub fn validity_info(&self) -> Result<ValidityInfo> {
+        // The MSO does not need to be verified here, as this was already done when constructing the `Mdoc`.
+        let validity_info = self
+            .issuer_signed
+            .issuer_auth
+            .dangerous_parse_unverified()?
+            .0
+            .validity_info;
+
+        Ok(validity_info)
+    }
+
+    /// Get the reference to the status list of the mdoc, if its MSO contains one.
+    pub fn status(&self) -> Result<Option<MsoStatus>> {
+        // As above, the MSO has already been verified when constructing the `Mdoc`.
+        let status = self.issuer_signed.issuer_auth.dangerous_parse_unverified()?.0.status;
+
+        Ok(status)
+    }
+
+    /// Prove possession of this mdoc to its issuer by signing the `challenge` obtained from the issuer with the
+    /// private key of the mdoc, in order to request fresh copies of the mdocs of the holder.
+    pub async fn reissuance_request<K, KF>(&self, challenge: Vec<u8>, key_factory: &KF) -> Result<ReissuanceRequest>
+    where
+        K: MdocEcdsaKey,
+        KF: KeyFactory<Key = K>,
+    {
+        let public_key = self.issuer_signed.public_key()?;
+        let key: K = key_factory.generate_existing(&self.private_key_id, public_key);
+
+        let (_, device_signed) = DeviceSigned::new_signatures(vec![(key, challenge.as_slice())], key_factory)
+            .await?
+            .pop()
+            .expect("a signature should be returned for the single key");
+
+        let request = ReissuanceRequest {
+            challenge: ByteBuf::from(challenge),
+            document: Document {
+                doc_type: self.doc_type.clone(),
+                issuer_signed: self.issuer_signed.clone(),
+                device_signed,
+                errors: None,
+            },
+        };
+
+        Ok(request)
+    }
 }
@@ -102,6 +102,8 @@ impl From<HttpClientError> for DisclosureError {
             HttpClientError::Cbor(CborError::Deserialization(_)) => true,
             // When connection cannot be established, no data is shared
             HttpClientError::Request(ref reqwest_error) => !reqwest_error.is_connect(),
+            // The request has been sent, even though the server no longer accepted it
+            HttpClientError::SessionExpired => true,
         };
         Self::new(data_shared, Error::Holder(HolderError::RequestError(source)))
     }
@@ -25,8 +25,8 @@ use crate::{
         cose::{CoseKey, MdocCose},
         serialization::TaggedBytes,
     },
-    Attributes, DataElementIdentifier, DataElementValue, DigestAlgorithm, DocType, MobileSecurityObject,
-    MobileSecurityObjectVersion, NameSpace, Tdate, ValidityInfo,
+    Attributes, DataElementIdentifier, DataElementValue, DigestAlgorithm, DocType, Document, MobileSecurityObject,
+    MobileSecurityObjectVersion, MsoStatus, NameSpace, Tdate, ValidityInfo,
 };
 
 pub const START_ISSUING_MSG_TYPE: &str = "nl.referencewallet.issuance.StartIssuing";
@@ -134,6 +134,17 @@ impl ResponseSignaturePayload {
     }
 }
 
+/// Holder -> issuer, outside of an issuance session. Requests a new issuance session for fresh copies of the mdocs of
+/// the holder, without authenticating the holder anew, by proving possession of a valid mdoc previously issued by the
+/// issuer. The `document` contains that mdoc, of which the private key has signed the `challenge` obtained from the
+/// issuer. The issuer replies with a [`ServiceEngagement`](super::ServiceEngagement) to start the session.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ReissuanceRequest {
+    pub challenge: ByteBuf,
+    pub document: Document,
+}
+
 /// Issuer -> holder, in reply to [`KeyGenerationResponseMessage`]. Contains all data of the signed mdocs being issued
 /// (in particular the issuer signatures) that the holder has not yet already learned during the protocol so far.
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -171,5 +182,7 @@ pub struct SparseIssuerAuth {
     pub version: MobileSecurityObjectVersion,
     pub digest_algorithm: DigestAlgorithm,
     pub validity_info: ValidityInfo,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<MsoStatus>,
     pub issuer_auth: MdocCose<CoseSign1, TaggedBytes<MobileSecurityObject>>,
 }
@@ -12,6 +12,7 @@ use serde::{Deserialize, Serialize};
 use serde_bytes::ByteBuf;
 use serde_with::skip_serializing_none;
 use std::fmt::Debug;
+use url::Url;
 
 use wallet_common::utils::random_bytes;
 
@@ -150,6 +151,23 @@ pub struct MobileSecurityObject {
     pub device_key_info: DeviceKeyInfo,
     pub doc_type: String,
     pub validity_info: ValidityInfo,
+    /// Reference to the revocation status of the mdoc, absent if the issuer does not support revocation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<MsoStatus>,
+}
+
+/// Revocation status information of an mdoc, included in its [`MobileSecurityObject`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MsoStatus {
+    pub status_list: StatusListReference,
+}
+
+/// Points to the index within a status list, published by the issuer at `uri`, containing the status of an mdoc.
+/// See [`crate::status_list`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StatusListReference {
+    pub idx: u64,
+    pub uri: Url,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
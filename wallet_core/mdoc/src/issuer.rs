@@ -3,7 +3,7 @@
 //! the holder.
 
 use core::panic;
-use std::{future::Future, sync::Arc, time::Duration};
+use std::{future::Future, num::NonZeroUsize, sync::Arc, time::Duration};
 
 use chrono::Utc;
 use ciborium::value::Value;
@@ -11,7 +11,8 @@ use coset::{CoseSign1, HeaderBuilder};
 use futures::future::try_join_all;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_bytes::ByteBuf;
-use tokio::task::JoinHandle;
+use tokio::{sync::Semaphore, task::JoinHandle};
+use tracing::instrument;
 use url::Url;
 
 use wallet_common::utils::random_bytes;
@@ -26,6 +27,7 @@ use crate::{
     issuer_shared::IssuanceError,
     server_keys::{KeyRing, PrivateKey},
     server_state::{SessionState, SessionStore, SessionToken, CLEANUP_INTERVAL_SECONDS},
+    status_list::StatusListAllocator,
     utils::{
         cose::{ClonePayload, CoseKey, MdocCose, COSE_X5CHAIN_HEADER_LABEL},
         serialization::{cbor_deserialize, cbor_serialize, TaggedBytes},
@@ -33,6 +35,10 @@ use crate::{
     Error, Result,
 };
 
+/// The number of MSOs that are signed at the same time when no other limit is configured using
+/// [`Issuer::with_max_concurrent_signatures()`].
+pub const DEFAULT_MAX_CONCURRENT_SIGNATURES: usize = 32;
+
 #[derive(Debug, Clone)]
 enum IssuanceStatus {
     Created,
@@ -59,10 +65,20 @@ impl IssuanceStatus {
     }
 }
 
+/// Is informed of the mdocs that are issued, e.g. to record these in an audit log.
+pub trait IssuanceObserver: std::fmt::Debug + Send + Sync {
+    /// Called after mdocs of the specified doc types have been issued in a session that was started for `subject`,
+    /// if any. This is not called again when the same mdocs are returned to a holder that resends its response.
+    fn issued(&self, doc_types: &[String], subject: Option<&str>);
+}
+
 pub struct Issuer<K, S> {
     url: Url,
     keys: K,
     sessions: Arc<S>,
+    status_lists: Option<Arc<dyn StatusListAllocator>>,
+    observer: Option<Arc<dyn IssuanceObserver>>,
+    signing_permits: Semaphore,
     cleanup_task: JoinHandle<()>,
 }
 
@@ -92,12 +108,53 @@ where
             url,
             keys,
             sessions,
+            status_lists: None,
+            observer: None,
+            signing_permits: Semaphore::new(DEFAULT_MAX_CONCURRENT_SIGNATURES),
         }
     }
 
+    /// Include a reference to a status list in each mdoc that is issued, allocated by `status_lists`, so that the
+    /// mdocs can be revoked later on.
+    pub fn with_status_lists(mut self, status_lists: Arc<dyn StatusListAllocator>) -> Self {
+        self.status_lists = Some(status_lists);
+        self
+    }
+
+    /// Inform `observer` of every session in which mdocs are issued.
+    pub fn with_observer(mut self, observer: Arc<dyn IssuanceObserver>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// Limit the number of MSOs that are signed at the same time, across all sessions, e.g. to the number of sessions
+    /// that an HSM allows.
+    pub fn with_max_concurrent_signatures(mut self, max_concurrent_signatures: NonZeroUsize) -> Self {
+        self.signing_permits = Semaphore::new(max_concurrent_signatures.get());
+        self
+    }
+
+    pub fn keys(&self) -> &K {
+        &self.keys
+    }
+
     /// Start a new issuance session for the specified (unsigned) mdocs. Returns the [`ServiceEngagement`] to be
     /// presented to the user.
     pub async fn new_session(&self, docs: Vec<UnsignedMdoc>) -> Result<ServiceEngagement> {
+        self.start_session(docs, None).await
+    }
+
+    /// Start a new issuance session like [`Issuer::new_session()`], for mdocs that are issued to `subject`. The
+    /// subject is passed to the [`StatusListAllocator`], so that the mdocs can be looked up when revoking them.
+    pub async fn new_session_with_subject(
+        &self,
+        docs: Vec<UnsignedMdoc>,
+        subject: String,
+    ) -> Result<ServiceEngagement> {
+        self.start_session(docs, Some(subject)).await
+    }
+
+    async fn start_session(&self, docs: Vec<UnsignedMdoc>, subject: Option<String>) -> Result<ServiceEngagement> {
         self.check_keys(&docs)?;
 
         let challenge = ByteBuf::from(random_bytes(32));
@@ -112,7 +169,7 @@ where
         self.sessions
             .write(&SessionState::new(
                 token.clone(),
-                IssuanceData::new(request, session_id),
+                IssuanceData::new(request, session_id, subject),
             ))
             .await
             .map_err(|e| IssuanceError::SessionStore(e.into()))?;
@@ -147,6 +204,9 @@ where
             sessions: self.sessions.as_ref(),
             session_data: &mut session_data,
             keys: &self.keys,
+            status_lists: self.status_lists.as_deref(),
+            observer: self.observer.as_deref(),
+            signing_permits: &self.signing_permits,
         };
 
         // If this is not the very first protocol message, the session ID is expected in every message.
@@ -176,6 +236,11 @@ where
                 Self::expect_message_type(&msg_type, KEY_GEN_RESP_MSG_TYPE)?;
                 Self::handle_cbor(Session::process_response, session, msg).await
             }
+            // The holder may resend its response if it did not receive the issued mdocs, e.g. because of a network
+            // error. In that case the same mdocs are returned again, provided that the response is exactly the same.
+            Done if msg_type == KEY_GEN_RESP_MSG_TYPE => {
+                Self::handle_cbor(Session::process_resent_response, session, msg).await
+            }
             Done | Failed | Cancelled => Err(IssuanceError::SessionEnded.into()),
         }
     }
@@ -241,21 +306,35 @@ where
     sessions: &'a S,
     session_data: &'a mut SessionState<IssuanceData>,
     keys: &'a K,
+    status_lists: Option<&'a dyn StatusListAllocator>,
+    observer: Option<&'a dyn IssuanceObserver>,
+    signing_permits: &'a Semaphore,
 }
 
 #[derive(Debug, Clone)]
 pub struct IssuanceData {
     request: RequestKeyGenerationMessage,
     id: SessionId,
+    subject: Option<String>,
     state: IssuanceStatus,
+    issued: Option<IssuedData>,
+}
+
+/// The CBOR-encoded response of the holder and the mdocs that were issued in return, kept for when it is resent.
+#[derive(Debug, Clone)]
+struct IssuedData {
+    response: Vec<u8>,
+    data_to_issue: DataToIssueMessage,
 }
 
 impl IssuanceData {
-    fn new(request: RequestKeyGenerationMessage, id: SessionId) -> Self {
+    fn new(request: RequestKeyGenerationMessage, id: SessionId, subject: Option<String>) -> Self {
         Self {
             request,
             id,
+            subject,
             state: Created,
+            issued: None,
         }
     }
 }
@@ -265,6 +344,7 @@ impl<'a, K: KeyRing, S> Session<'a, K, S>
 where
     S: SessionStore<Data = SessionState<IssuanceData>>,
 {
+    #[instrument(skip(self))]
     async fn update_state(&mut self, new_state: IssuanceStatus) -> Result<()> {
         self.session_data.session_data.state.update(new_state);
         self.session_data.last_active = Utc::now();
@@ -275,6 +355,7 @@ where
             .map_err(|e| IssuanceError::SessionStore(Box::new(e)))?)
     }
 
+    #[instrument(skip_all)]
     async fn process_start(mut self, _: StartProvisioningMessage) -> Result<ReadyToProvisionMessage> {
         self.update_state(Started).await?;
         let response = ReadyToProvisionMessage {
@@ -283,20 +364,59 @@ where
         Ok(response)
     }
 
+    #[instrument(skip_all)]
     async fn process_get_request(mut self, _: StartIssuingMessage) -> Result<RequestKeyGenerationMessage> {
         self.update_state(WaitingForResponse).await?;
         Ok(self.session_data.session_data.request.clone())
     }
 
+    #[instrument(skip_all)]
     async fn process_response(mut self, device_response: KeyGenerationResponseMessage) -> Result<DataToIssueMessage> {
+        let response = cbor_serialize(&device_response)?;
         let issuance_result = self.issue(device_response).await;
         match issuance_result {
-            Ok(_) => self.update_state(Done).await?,
+            Ok(ref data_to_issue) => {
+                self.session_data.session_data.issued = Some(IssuedData {
+                    response,
+                    data_to_issue: data_to_issue.clone(),
+                });
+                self.update_state(Done).await?;
+
+                if let Some(observer) = self.observer {
+                    let doc_types: Vec<_> = data_to_issue
+                        .mobile_eid_documents
+                        .iter()
+                        .map(|document| document.doc_type.clone())
+                        .collect();
+                    observer.issued(&doc_types, self.session_data.session_data.subject.as_deref());
+                }
+            }
             Err(_) => self.update_state(Failed).await?,
         }
         issuance_result
     }
 
+    #[instrument(skip_all)]
+    async fn process_resent_response(
+        self,
+        device_response: KeyGenerationResponseMessage,
+    ) -> Result<DataToIssueMessage> {
+        let issued = self
+            .session_data
+            .session_data
+            .issued
+            .as_ref()
+            .ok_or(IssuanceError::SessionEnded)?;
+
+        // Only the holder that received the original request can produce the same response.
+        if cbor_serialize(&device_response)? != issued.response {
+            return Err(IssuanceError::SessionEnded.into());
+        }
+
+        Ok(issued.data_to_issue.clone())
+    }
+
+    #[instrument(skip_all)]
     async fn process_cancel(mut self, _: RequestEndSessionMessage) -> Result<EndSessionMessage> {
         self.update_state(Cancelled).await?;
         let response = EndSessionMessage {
@@ -309,10 +429,34 @@ where
     }
 
     async fn issue_cred(&self, unsigned_mdoc: UnsignedMdoc, response: Response) -> Result<SparseIssuerSigned> {
-        // Presence of the key in the keyring has already been checked by new_session().
-        let private_key = self.keys.private_key(&unsigned_mdoc.doc_type).unwrap();
-
-        let (signed, mso) = IssuerSigned::sign(unsigned_mdoc, response.public_key, private_key).await?;
+        // Presence of the key in the keyring has already been checked by new_session(), but the key that is selected
+        // may have expired since, after which there is no key to sign with.
+        let private_key = self
+            .keys
+            .private_key(&unsigned_mdoc.doc_type)
+            .ok_or_else(|| IssuanceError::MissingPrivateKey(unsigned_mdoc.doc_type.clone()))?;
+
+        let status = self
+            .status_lists
+            .map(|status_lists| {
+                status_lists.allocate(
+                    &unsigned_mdoc.doc_type,
+                    private_key.certificate(),
+                    self.session_data.session_data.subject.as_deref(),
+                )
+            })
+            .transpose()?
+            .flatten()
+            .map(|status_list| MsoStatus { status_list });
+
+        let (signed, mso) = {
+            let _permit = self
+                .signing_permits
+                .acquire()
+                .await
+                .expect("signing permits should never be closed");
+            IssuerSigned::sign(unsigned_mdoc, response.public_key, private_key, status).await?
+        };
 
         let sparse = SparseIssuerSigned {
             randoms: signed
@@ -325,6 +469,7 @@ where
                 version: mso.version,
                 digest_algorithm: mso.digest_algorithm,
                 validity_info: mso.validity_info,
+                status: mso.status,
                 issuer_auth: signed.issuer_auth.clone_without_payload(),
             },
         };
@@ -340,8 +485,8 @@ where
         &self,
         doctype_responses: MdocResponses,
         unsigned: &UnsignedMdoc,
-    ) -> Result<Vec<SparseIssuerSigned>> {
-        let issue_creds = try_join_all(
+    ) -> Result<MobileeIDDocuments> {
+        let sparse_issuer_signed = try_join_all(
             doctype_responses
                 .responses
                 .into_iter()
@@ -349,23 +494,25 @@ where
         )
         .await?;
 
-        Ok(issue_creds)
+        Ok(MobileeIDDocuments {
+            doc_type: unsigned.doc_type.clone(),
+            sparse_issuer_signed,
+        })
     }
 
+    /// Sign the copies of all doc types concurrently, as far as the signing permits of the issuer allow, keeping
+    /// them in the order of the request.
     pub async fn issue(&self, device_response: KeyGenerationResponseMessage) -> Result<DataToIssueMessage> {
         device_response.verify(&self.session_data.session_data.request)?;
 
-        let mut docs = vec![];
-        for (responses, unsigned) in device_response
-            .mdoc_responses
-            .into_iter()
-            .zip(&self.session_data.session_data.request.unsigned_mdocs)
-        {
-            docs.push(MobileeIDDocuments {
-                doc_type: unsigned.doc_type.clone(),
-                sparse_issuer_signed: self.issue_creds(responses, unsigned).await?,
-            })
-        }
+        let docs = try_join_all(
+            device_response
+                .mdoc_responses
+                .into_iter()
+                .zip(&self.session_data.session_data.request.unsigned_mdocs)
+                .map(|(responses, unsigned)| self.issue_creds(responses, unsigned)),
+        )
+        .await?;
 
         let response = DataToIssueMessage {
             e_session_id: self.session_data.session_data.request.e_session_id.clone(),
@@ -380,6 +527,7 @@ impl IssuerSigned {
         unsigned_mdoc: UnsignedMdoc,
         device_public_key: CoseKey,
         key: &PrivateKey,
+        status: Option<MsoStatus>,
     ) -> Result<(Self, MobileSecurityObject)> {
         let now = Utc::now();
         let validity = ValidityInfo {
@@ -403,6 +551,7 @@ impl IssuerSigned {
             value_digests: (&attrs).try_into()?,
             device_key_info: device_public_key.into(),
             validity_info: validity,
+            status,
         };
 
         let headers = HeaderBuilder::new()
@@ -437,7 +586,7 @@ mod tests {
         server_state::{MemorySessionStore, SessionStore},
     };
 
-    use super::{IssuanceData, KeyRing, SessionState};
+    use super::{IssuanceData, KeyRing, Semaphore, SessionState, DEFAULT_MAX_CONCURRENT_SIGNATURES};
 
     struct EmptyKeyRing;
     impl KeyRing for EmptyKeyRing {
@@ -462,6 +611,9 @@ mod tests {
             url: "https://example.com".parse().unwrap(),
             keys: EmptyKeyRing,
             sessions,
+            status_lists: None,
+            observer: None,
+            signing_permits: Semaphore::new(DEFAULT_MAX_CONCURRENT_SIGNATURES),
         };
 
         // insert a fresh session
@@ -499,6 +651,9 @@ mod tests {
                 url: "https://example.com".parse().unwrap(),
                 keys: EmptyKeyRing,
                 sessions: Arc::clone(&sessions),
+                status_lists: None,
+                observer: None,
+                signing_permits: Semaphore::new(DEFAULT_MAX_CONCURRENT_SIGNATURES),
             };
 
             // insert a stale session
@@ -525,6 +680,7 @@ mod tests {
                     unsigned_mdocs: vec![],
                 },
                 "123".to_string().as_bytes().to_vec().into(),
+                None,
             ),
         )
     }
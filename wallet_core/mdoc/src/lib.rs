@@ -14,6 +14,9 @@ pub mod issuer_shared;
 pub mod server_keys;
 pub mod server_state;
 
+// Revocation of mdocs using status lists
+pub mod status_list;
+
 /// General code used throughout the crate.
 pub mod utils;
 
@@ -17,6 +17,8 @@ pub struct SessionState<T> {
 pub enum SessionStoreError {
     #[error("key not found")]
     NotFound,
+    #[error("session has expired")]
+    Expired,
     #[error("error while serializing: {0}")]
     Serialize(#[source] Box<dyn std::error::Error + Send + Sync + 'static>),
     #[error("error while deserializing: {0}")]
@@ -32,6 +34,8 @@ pub trait SessionStore {
 
     fn get(&self, id: &SessionToken) -> impl Future<Output = Result<Option<Self::Data>, SessionStoreError>> + Send;
     fn write(&self, session: &Self::Data) -> impl Future<Output = Result<(), SessionStoreError>> + Send;
+    /// Returns all sessions that have not expired.
+    fn list(&self) -> impl Future<Output = Result<Vec<Self::Data>, SessionStoreError>> + Send;
     fn cleanup(&self) -> impl Future<Output = Result<(), SessionStoreError>> + Send;
 
     fn start_cleanup_task(self: Arc<Self>, interval: Duration) -> JoinHandle<()>
@@ -48,17 +52,55 @@ pub trait SessionStore {
     }
 }
 
-#[derive(Debug, Default)]
+/// Keeps sessions in memory. A session expires when it has not been active for the TTL of the store, after which
+/// [`SessionStore::get()`] returns [`SessionStoreError::Expired`] for it until the cleanup task forgets the session
+/// completely. When a maximum number of sessions is configured, writing a new session while the store is full evicts
+/// the least recently active session, which is then also reported as expired.
+#[derive(Debug)]
 pub struct MemorySessionStore<T> {
     pub(crate) sessions: DashMap<SessionToken, SessionState<T>>,
+    expired: DashMap<SessionToken, DateTime<Utc>>,
+    ttl: chrono::Duration,
+    max_sessions: Option<usize>,
 }
 
 impl<T> MemorySessionStore<T> {
     pub fn new() -> Self {
+        Self::with_limits(chrono::Duration::minutes(SESSION_EXPIRY_MINUTES as i64), None)
+    }
+
+    pub fn with_limits(ttl: chrono::Duration, max_sessions: Option<usize>) -> Self {
         Self {
             sessions: DashMap::new(),
+            expired: DashMap::new(),
+            ttl,
+            max_sessions,
         }
     }
+
+    fn expire(&self, token: SessionToken, now: DateTime<Utc>) {
+        self.sessions.remove(&token);
+        self.expired.insert(token, now);
+    }
+
+    /// Evict the least recently active session.
+    fn evict(&self, now: DateTime<Utc>) {
+        let oldest = self
+            .sessions
+            .iter()
+            .min_by_key(|session| session.last_active)
+            .map(|session| session.key().clone());
+
+        if let Some(token) = oldest {
+            self.expire(token, now);
+        }
+    }
+}
+
+impl<T> Default for MemorySessionStore<T> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<T> SessionState<T> {
@@ -81,18 +123,59 @@ impl<T: Clone + Send + Sync> SessionStore for MemorySessionStore<T> {
     type Data = SessionState<T>;
 
     async fn get(&self, token: &SessionToken) -> Result<Option<SessionState<T>>, SessionStoreError> {
-        Ok(self.sessions.get(token).map(|s| s.clone()))
+        let now = Utc::now();
+
+        // Clone the session before checking its expiry, so that the lock on the map is released before expiring it.
+        let session = self.sessions.get(token).map(|s| s.clone());
+        match session {
+            Some(session) if now - session.last_active >= self.ttl => {
+                self.expire(session.token, now);
+                Err(SessionStoreError::Expired)
+            }
+            Some(session) => Ok(Some(session)),
+            None if self.expired.contains_key(token) => Err(SessionStoreError::Expired),
+            None => Ok(None),
+        }
     }
 
     async fn write(&self, session: &SessionState<T>) -> Result<(), SessionStoreError> {
+        if let Some(max_sessions) = self.max_sessions {
+            if !self.sessions.contains_key(&session.token) && self.sessions.len() >= max_sessions {
+                self.evict(Utc::now());
+            }
+        }
+
         self.sessions.insert(session.token.clone(), session.clone());
         Ok(())
     }
 
+    async fn list(&self) -> Result<Vec<SessionState<T>>, SessionStoreError> {
+        let now = Utc::now();
+
+        let sessions = self
+            .sessions
+            .iter()
+            .filter(|session| now - session.last_active < self.ttl)
+            .map(|session| session.clone())
+            .collect();
+
+        Ok(sessions)
+    }
+
     async fn cleanup(&self) -> Result<(), SessionStoreError> {
         let now = Utc::now();
-        let cutoff = chrono::Duration::minutes(SESSION_EXPIRY_MINUTES as i64);
-        self.sessions.retain(|_, session| now - session.last_active < cutoff);
+
+        let expired_tokens = self
+            .sessions
+            .iter()
+            .filter(|session| now - session.last_active >= self.ttl)
+            .map(|session| session.key().clone())
+            .collect::<Vec<_>>();
+        expired_tokens.into_iter().for_each(|token| self.expire(token, now));
+
+        // Sessions are reported as expired for another TTL, after which these are forgotten.
+        self.expired.retain(|_, expired_at| now - *expired_at < self.ttl);
+
         Ok(())
     }
 }
@@ -127,3 +210,65 @@ impl Display for SessionToken {
         self.0.fmt(f)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+
+    use super::*;
+
+    fn session(last_active: DateTime<Utc>) -> SessionState<()> {
+        let mut session = SessionState::new(SessionToken::new(), ());
+        session.last_active = last_active;
+        session
+    }
+
+    #[tokio::test]
+    async fn test_memory_session_store_expiry() {
+        let store = MemorySessionStore::with_limits(chrono::Duration::minutes(5), None);
+
+        let fresh = session(Utc::now());
+        let stale = session(Utc::now() - chrono::Duration::minutes(10));
+        store.write(&fresh).await.unwrap();
+        store.write(&stale).await.unwrap();
+
+        // Only the sessions that have not expired are listed.
+        let listed = store.list().await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].token, fresh.token);
+
+        assert!(store.get(&fresh.token).await.unwrap().is_some());
+        assert_matches!(store.get(&stale.token).await, Err(SessionStoreError::Expired));
+        assert_matches!(store.get(&SessionToken::new()).await, Ok(None));
+
+        // The cleanup removes the stale session, but it is still reported as expired.
+        let stale = session(Utc::now() - chrono::Duration::minutes(10));
+        store.write(&stale).await.unwrap();
+        store.cleanup().await.unwrap();
+
+        assert_eq!(store.sessions.len(), 1);
+        assert_matches!(store.get(&stale.token).await, Err(SessionStoreError::Expired));
+    }
+
+    #[tokio::test]
+    async fn test_memory_session_store_eviction() {
+        let store = MemorySessionStore::with_limits(chrono::Duration::minutes(5), Some(2));
+
+        let oldest = session(Utc::now() - chrono::Duration::minutes(2));
+        let older = session(Utc::now() - chrono::Duration::minutes(1));
+        store.write(&oldest).await.unwrap();
+        store.write(&older).await.unwrap();
+
+        // Updating an existing session does not evict another one.
+        store.write(&older).await.unwrap();
+        assert_eq!(store.sessions.len(), 2);
+
+        let newest = session(Utc::now());
+        store.write(&newest).await.unwrap();
+
+        assert_eq!(store.sessions.len(), 2);
+        assert_matches!(store.get(&oldest.token).await, Err(SessionStoreError::Expired));
+        assert!(store.get(&older.token).await.unwrap().is_some());
+        assert!(store.get(&newest.token).await.unwrap().is_some());
+    }
+}
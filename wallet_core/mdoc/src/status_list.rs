@@ -0,0 +1,660 @@
+//! Revocation of mdocs using status lists, based on the IETF
+//! [Token Status List](https://datatracker.ietf.org/doc/draft-ietf-oauth-status-list/) draft.
+//!
+//! An issuer includes a [`StatusListReference`] in the [`MobileSecurityObject`] of each mdoc that it issues, which
+//! points to an index in a [`StatusList`]. The issuer publishes this list at the referenced URI as a
+//! [`StatusListToken`], signed using the same key as the mdoc. Verifiers and holders fetch and cache these tokens
+//! using a [`StatusListCache`], to check whether an mdoc has been revoked.
+
+use std::{
+    io::{Read, Write},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use coset::CoseSign1;
+use dashmap::DashMap;
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+use futures::future::TryFutureExt;
+use serde::{Deserialize, Serialize};
+use serde_bytes::ByteBuf;
+use serde_repr::{Deserialize_repr, Serialize_repr};
+use serde_with::skip_serializing_none;
+use url::Url;
+use webpki::TrustAnchor;
+
+use wallet_common::generator::Generator;
+
+use crate::{
+    iso::*,
+    server_keys::PrivateKey,
+    utils::{
+        cose::{new_certificate_header, MdocCose},
+        serialization::{cbor_deserialize, TaggedBytes},
+        x509::{Certificate, CertificateUsage},
+    },
+    Result,
+};
+
+#[derive(thiserror::Error, Debug)]
+pub enum StatusListError {
+    #[error("could not decompress status list: {0}")]
+    Decompression(#[source] std::io::Error),
+    #[error("index {0} is out of bounds of the status list")]
+    IndexOutOfBounds(u64),
+    #[error("status {status} does not fit in {bits} bits")]
+    StatusOutOfRange { status: u8, bits: u8 },
+    #[error("status list token is published at {found}, expected {expected}")]
+    UriMismatch { expected: Url, found: Url },
+    #[error("status list token is expired")]
+    Expired,
+    #[error("status list token was not signed by the issuer of the mdoc")]
+    IssuerMismatch,
+    #[error("could not fetch status list token: {0}")]
+    Fetch(#[source] reqwest::Error),
+    #[error("no unused index left in status list for doctype {0}")]
+    Exhausted(DocType),
+    #[error("could not allocate index in status list: {0}")]
+    Allocation(#[source] Box<dyn std::error::Error + Send + Sync>),
+    #[error("mdoc of doctype {doc_type} has status {status:?}")]
+    NotValid { doc_type: DocType, status: StatusType },
+}
+
+/// The status of a single mdoc within a [`StatusList`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusType {
+    Valid,
+    Invalid,
+    Suspended,
+    /// Any other status, the meaning of which is specific to the issuer.
+    Other(u8),
+}
+
+impl From<u8> for StatusType {
+    fn from(value: u8) -> Self {
+        match value {
+            0x00 => Self::Valid,
+            0x01 => Self::Invalid,
+            0x02 => Self::Suspended,
+            other => Self::Other(other),
+        }
+    }
+}
+
+impl From<StatusType> for u8 {
+    fn from(value: StatusType) -> Self {
+        match value {
+            StatusType::Valid => 0x00,
+            StatusType::Invalid => 0x01,
+            StatusType::Suspended => 0x02,
+            StatusType::Other(other) => other,
+        }
+    }
+}
+
+/// The number of bits used for each status in a [`StatusList`].
+#[derive(Serialize_repr, Deserialize_repr, Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum StatusBits {
+    One = 1,
+    Two = 2,
+    Four = 4,
+    Eight = 8,
+}
+
+impl StatusBits {
+    fn mask(self) -> u8 {
+        ((1u16 << self as u8) - 1) as u8
+    }
+}
+
+/// A list of statuses of mdocs, indexed by [`StatusListReference::idx`]. When serialized, the statuses are packed
+/// into bytes, starting at the least significant bits of the first byte, and compressed using DEFLATE.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(try_from = "CompressedStatusList", into = "CompressedStatusList")]
+pub struct StatusList {
+    bits: StatusBits,
+    statuses: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CompressedStatusList {
+    bits: StatusBits,
+    lst: ByteBuf,
+}
+
+impl StatusList {
+    /// Create a new status list containing `len` statuses, all of which are [`StatusType::Valid`].
+    pub fn new(bits: StatusBits, len: usize) -> Self {
+        StatusList {
+            bits,
+            statuses: vec![u8::from(StatusType::Valid); len],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.statuses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.statuses.is_empty()
+    }
+
+    pub fn get(&self, idx: u64) -> Option<StatusType> {
+        usize::try_from(idx)
+            .ok()
+            .and_then(|idx| self.statuses.get(idx))
+            .map(|status| StatusType::from(*status))
+    }
+
+    pub fn set(&mut self, idx: u64, status: StatusType) -> std::result::Result<(), StatusListError> {
+        let value = u8::from(status);
+        if value & !self.bits.mask() != 0 {
+            return Err(StatusListError::StatusOutOfRange {
+                status: value,
+                bits: self.bits as u8,
+            });
+        }
+
+        let entry = usize::try_from(idx)
+            .ok()
+            .and_then(|idx| self.statuses.get_mut(idx))
+            .ok_or(StatusListError::IndexOutOfBounds(idx))?;
+        *entry = value;
+
+        Ok(())
+    }
+
+    fn pack(&self) -> Vec<u8> {
+        let bits = self.bits as usize;
+        let mut packed = vec![0u8; (self.statuses.len() * bits).div_ceil(8)];
+
+        for (idx, status) in self.statuses.iter().enumerate() {
+            let offset = idx * bits;
+            packed[offset / 8] |= status << (offset % 8);
+        }
+
+        packed
+    }
+
+    fn unpack(bits: StatusBits, packed: &[u8]) -> Vec<u8> {
+        let (mask, bits) = (bits.mask(), bits as usize);
+
+        (0..packed.len() * 8 / bits)
+            .map(|idx| {
+                let offset = idx * bits;
+                (packed[offset / 8] >> (offset % 8)) & mask
+            })
+            .collect()
+    }
+}
+
+impl TryFrom<CompressedStatusList> for StatusList {
+    type Error = StatusListError;
+
+    fn try_from(value: CompressedStatusList) -> std::result::Result<Self, Self::Error> {
+        let mut packed = Vec::new();
+        ZlibDecoder::new(value.lst.as_slice())
+            .read_to_end(&mut packed)
+            .map_err(StatusListError::Decompression)?;
+
+        let status_list = StatusList {
+            bits: value.bits,
+            statuses: Self::unpack(value.bits, &packed),
+        };
+
+        Ok(status_list)
+    }
+}
+
+impl From<StatusList> for CompressedStatusList {
+    fn from(value: StatusList) -> Self {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+        encoder
+            .write_all(&value.pack())
+            .expect("writing to a Vec should always succeed");
+        let lst = encoder.finish().expect("writing to a Vec should always succeed");
+
+        CompressedStatusList {
+            bits: value.bits,
+            lst: ByteBuf::from(lst),
+        }
+    }
+}
+
+/// The payload of a [`StatusListToken`].
+#[skip_serializing_none]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StatusListClaims {
+    /// The URI at which the status list is published, which should match [`StatusListReference::uri`].
+    pub sub: Url,
+    pub iat: u64,
+    pub exp: Option<u64>,
+    /// The maximum number of seconds that the status list may be cached.
+    pub ttl: Option<u64>,
+    pub status_list: StatusList,
+}
+
+/// A [`StatusList`] signed by the issuer of the mdocs that refer to it.
+pub type StatusListToken = MdocCose<CoseSign1, StatusListClaims>;
+
+impl StatusListClaims {
+    /// Sign the status list using the same key with which the mdocs referring to it are signed.
+    pub async fn sign(&self, key: &PrivateKey) -> Result<StatusListToken> {
+        MdocCose::sign(self, new_certificate_header(&key.cert_bts), key, true).await
+    }
+}
+
+impl StatusListToken {
+    /// Verify the token against the trust anchors, and check that it has been signed with the same certificate as
+    /// the mdoc (`issuer_certificate`) that refers to the status list at `uri`.
+    pub fn verify_status_list(
+        &self,
+        uri: &Url,
+        issuer_certificate: &Certificate,
+        time: &impl Generator<DateTime<Utc>>,
+        trust_anchors: &[TrustAnchor],
+    ) -> Result<StatusListClaims> {
+        let claims = self.verify_against_trust_anchors(CertificateUsage::Mdl, time, trust_anchors)?;
+
+        if self.signing_cert()? != *issuer_certificate {
+            return Err(StatusListError::IssuerMismatch.into());
+        }
+        if claims.sub != *uri {
+            return Err(StatusListError::UriMismatch {
+                expected: uri.clone(),
+                found: claims.sub,
+            }
+            .into());
+        }
+        if claims
+            .exp
+            .is_some_and(|exp| time.generate().timestamp() > i64::try_from(exp).unwrap_or(i64::MAX))
+        {
+            return Err(StatusListError::Expired.into());
+        }
+
+        Ok(claims)
+    }
+}
+
+pub trait StatusListClient {
+    async fn fetch(&self, uri: &Url) -> Result<StatusListToken>;
+}
+
+/// Fetch CBOR-encoded [`StatusListToken`]s over HTTP using a [`reqwest::Client`].
+#[derive(Debug, Default)]
+pub struct HttpStatusListClient(pub reqwest::Client);
+
+impl StatusListClient for HttpStatusListClient {
+    async fn fetch(&self, uri: &Url) -> Result<StatusListToken> {
+        let bytes = self
+            .0
+            .get(uri.clone())
+            .send()
+            .and_then(|response| async { response.error_for_status()?.bytes().await })
+            .await
+            .map_err(StatusListError::Fetch)?;
+        let token = cbor_deserialize(bytes.as_ref())?;
+
+        Ok(token)
+    }
+}
+
+#[derive(Debug)]
+struct CachedStatusList {
+    status_list: StatusList,
+    issuer_certificate: Certificate,
+    expires_at: i64,
+}
+
+/// Keeps verified status lists in memory by their URI, fetching them using a [`StatusListClient`] when they are
+/// not present or no longer fresh. A status list is cached no longer than `max_ttl`, or the `ttl` and `exp` of
+/// its token if those are shorter.
+#[derive(Debug)]
+pub struct StatusListCache<C = HttpStatusListClient> {
+    client: C,
+    max_ttl: Duration,
+    entries: DashMap<Url, CachedStatusList>,
+}
+
+impl<C> StatusListCache<C>
+where
+    C: StatusListClient,
+{
+    pub fn new(client: C, max_ttl: Duration) -> Self {
+        StatusListCache {
+            client,
+            max_ttl,
+            entries: DashMap::new(),
+        }
+    }
+
+    /// Get the status of an mdoc, signed with `issuer_certificate`, from the status list it refers to.
+    pub async fn status(
+        &self,
+        reference: &StatusListReference,
+        issuer_certificate: &Certificate,
+        time: &impl Generator<DateTime<Utc>>,
+        trust_anchors: &[TrustAnchor<'_>],
+    ) -> Result<StatusType> {
+        let now = time.generate().timestamp();
+
+        if let Some(cached) = self
+            .entries
+            .get(&reference.uri)
+            .filter(|cached| cached.expires_at > now)
+        {
+            return Self::lookup(&cached, reference, issuer_certificate);
+        }
+
+        let token = self.client.fetch(&reference.uri).await?;
+        let claims = token.verify_status_list(&reference.uri, issuer_certificate, time, trust_anchors)?;
+
+        let max_ttl = i64::try_from(self.max_ttl.as_secs()).unwrap_or(i64::MAX);
+        let ttl = claims
+            .ttl
+            .map_or(max_ttl, |ttl| i64::try_from(ttl).unwrap_or(i64::MAX).min(max_ttl));
+        let expires_at = claims
+            .exp
+            .map(|exp| i64::try_from(exp).unwrap_or(i64::MAX))
+            .into_iter()
+            .fold(now.saturating_add(ttl), i64::min);
+
+        let cached = CachedStatusList {
+            status_list: claims.status_list,
+            issuer_certificate: issuer_certificate.clone(),
+            expires_at,
+        };
+        let status = Self::lookup(&cached, reference, issuer_certificate)?;
+        self.entries.insert(reference.uri.clone(), cached);
+
+        Ok(status)
+    }
+
+    fn lookup(
+        cached: &CachedStatusList,
+        reference: &StatusListReference,
+        issuer_certificate: &Certificate,
+    ) -> Result<StatusType> {
+        if cached.issuer_certificate != *issuer_certificate {
+            return Err(StatusListError::IssuerMismatch.into());
+        }
+
+        let status = cached
+            .status_list
+            .get(reference.idx)
+            .ok_or(StatusListError::IndexOutOfBounds(reference.idx))?;
+
+        Ok(status)
+    }
+
+    /// Check that none of the (already verified) documents have been revoked or suspended by their issuer.
+    /// Documents of which the MSO does not refer to a status list are considered to be valid.
+    pub async fn verify_documents(
+        &self,
+        documents: &[Document],
+        time: &impl Generator<DateTime<Utc>>,
+        trust_anchors: &[TrustAnchor<'_>],
+    ) -> Result<()> {
+        for document in documents {
+            let issuer_auth = &document.issuer_signed.issuer_auth;
+            let TaggedBytes(mso) = issuer_auth.dangerous_parse_unverified()?;
+
+            let Some(MsoStatus { status_list }) = mso.status else {
+                continue;
+            };
+
+            let status = self
+                .status(&status_list, &issuer_auth.signing_cert()?, time, trust_anchors)
+                .await?;
+
+            if status != StatusType::Valid {
+                return Err(StatusListError::NotValid {
+                    doc_type: mso.doc_type,
+                    status,
+                }
+                .into());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Assigns an unused index in a status list to each mdoc that an [`Issuer`](crate::issuer::Issuer) issues.
+pub trait StatusListAllocator: std::fmt::Debug + Send + Sync {
+    /// Allocate an index for a new mdoc of the specified doctype, or return `None` if mdocs of this doctype cannot
+    /// be revoked. As the status list has to be signed with the same certificate as the mdoc, the allocated index
+    /// should be in a status list of `issuer_certificate`. The `subject` is the one the issuance session was started
+    /// for, if any.
+    fn allocate(
+        &self,
+        doc_type: &str,
+        issuer_certificate: &Certificate,
+        subject: Option<&str>,
+    ) -> std::result::Result<Option<StatusListReference>, StatusListError>;
+}
+
+#[cfg(any(test, feature = "mock"))]
+pub mod mock {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    /// Status lists that are kept in memory, one for each doctype, of which the indices are allocated sequentially.
+    #[derive(Debug)]
+    pub struct MemoryStatusLists {
+        lists: DashMap<DocType, Mutex<(Url, StatusList, u64)>>,
+    }
+
+    impl MemoryStatusLists {
+        pub fn new(uris: impl IntoIterator<Item = (DocType, Url)>, len: usize) -> Self {
+            let lists = uris
+                .into_iter()
+                .map(|(doc_type, uri)| (doc_type, Mutex::new((uri, StatusList::new(StatusBits::One, len), 0))))
+                .collect();
+
+            MemoryStatusLists { lists }
+        }
+
+        pub fn set_status(
+            &self,
+            doc_type: &str,
+            idx: u64,
+            status: StatusType,
+        ) -> std::result::Result<(), StatusListError> {
+            let list = self
+                .lists
+                .get(doc_type)
+                .ok_or_else(|| StatusListError::Exhausted(doc_type.to_string()))?;
+            let (_, status_list, _) = &mut *list.lock().unwrap();
+
+            status_list.set(idx, status)
+        }
+
+        pub async fn token(&self, doc_type: &str, key: &PrivateKey, ttl: Option<u64>) -> StatusListToken {
+            let claims = {
+                let list = self.lists.get(doc_type).unwrap();
+                let (uri, status_list, _) = &*list.lock().unwrap();
+
+                StatusListClaims {
+                    sub: uri.clone(),
+                    iat: Utc::now().timestamp() as u64,
+                    exp: None,
+                    ttl,
+                    status_list: status_list.clone(),
+                }
+            };
+
+            claims.sign(key).await.unwrap()
+        }
+    }
+
+    impl StatusListAllocator for MemoryStatusLists {
+        fn allocate(
+            &self,
+            doc_type: &str,
+            _: &Certificate,
+            _: Option<&str>,
+        ) -> std::result::Result<Option<StatusListReference>, StatusListError> {
+            let Some(list) = self.lists.get(doc_type) else {
+                return Ok(None);
+            };
+            let (uri, status_list, next_idx) = &mut *list.lock().unwrap();
+
+            if *next_idx >= status_list.len() as u64 {
+                return Err(StatusListError::Exhausted(doc_type.to_string()));
+            }
+
+            let reference = StatusListReference {
+                idx: *next_idx,
+                uri: uri.clone(),
+            };
+            *next_idx += 1;
+
+            Ok(Some(reference))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use assert_matches::assert_matches;
+    use rstest::rstest;
+
+    use wallet_common::generator::TimeGenerator;
+
+    use crate::{mock::generate_issuance_key_and_ca, utils::serialization::cbor_serialize, Error};
+
+    use super::{mock::MemoryStatusLists, *};
+
+    #[rstest]
+    #[case(StatusBits::One, StatusType::Invalid)]
+    #[case(StatusBits::Two, StatusType::Suspended)]
+    #[case(StatusBits::Four, StatusType::Other(0x0f))]
+    #[case(StatusBits::Eight, StatusType::Other(0xff))]
+    fn test_status_list_serialization(#[case] bits: StatusBits, #[case] status: StatusType) {
+        let mut status_list = StatusList::new(bits, 100);
+        status_list.set(0, status).unwrap();
+        status_list.set(42, status).unwrap();
+        status_list.set(99, StatusType::Invalid).unwrap();
+
+        let bts = cbor_serialize(&status_list).unwrap();
+        let deserialized: StatusList = cbor_deserialize(bts.as_slice()).unwrap();
+
+        // Padding may have resulted in additional valid statuses at the end of the list.
+        assert!(deserialized.len() >= status_list.len());
+        for idx in 0..status_list.len() as u64 {
+            assert_eq!(deserialized.get(idx), status_list.get(idx));
+        }
+        assert_eq!(deserialized.get(42), Some(status));
+        assert_eq!(deserialized.get(43), Some(StatusType::Valid));
+    }
+
+    #[test]
+    fn test_status_list_packing() {
+        // Example from the Token Status List draft: 16 statuses of 1 bit each.
+        let mut status_list = StatusList::new(StatusBits::One, 16);
+        for idx in [0, 3, 4, 5, 7, 8, 9, 13, 15] {
+            status_list.set(idx, StatusType::Invalid).unwrap();
+        }
+
+        assert_eq!(status_list.pack(), vec![0xb9, 0xa3]);
+        assert_eq!(StatusList::unpack(StatusBits::One, &[0xb9, 0xa3]), status_list.statuses);
+    }
+
+    #[test]
+    fn test_status_list_set_errors() {
+        let mut status_list = StatusList::new(StatusBits::One, 8);
+
+        assert_matches!(
+            status_list.set(8, StatusType::Invalid),
+            Err(StatusListError::IndexOutOfBounds(8))
+        );
+        assert_matches!(
+            status_list.set(0, StatusType::Suspended),
+            Err(StatusListError::StatusOutOfRange { status: 2, bits: 1 })
+        );
+    }
+
+    /// A [`StatusListClient`] that returns a fixed token, counting the number of times it is called.
+    struct MockStatusListClient {
+        token: StatusListToken,
+        fetch_count: AtomicUsize,
+    }
+
+    impl StatusListClient for MockStatusListClient {
+        async fn fetch(&self, _uri: &Url) -> Result<StatusListToken> {
+            self.fetch_count.fetch_add(1, Ordering::Relaxed);
+
+            Ok(self.token.clone())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_status_list_cache() {
+        let doc_type = "com.example.pid".to_string();
+        let uri: Url = "https://example.com/status/pid".parse().unwrap();
+        let (key, ca) = generate_issuance_key_and_ca().unwrap();
+        let key = &key;
+        let trust_anchors = [(&ca).try_into().unwrap()];
+
+        let status_lists = MemoryStatusLists::new([(doc_type.clone(), uri.clone())], 16);
+        let valid = status_lists
+            .allocate(&doc_type, key.certificate(), None)
+            .unwrap()
+            .unwrap();
+        let revoked = status_lists
+            .allocate(&doc_type, key.certificate(), None)
+            .unwrap()
+            .unwrap();
+        status_lists
+            .set_status(&doc_type, revoked.idx, StatusType::Invalid)
+            .unwrap();
+
+        let cache = StatusListCache::new(
+            MockStatusListClient {
+                token: status_lists.token(&doc_type, key, Some(60)).await,
+                fetch_count: AtomicUsize::new(0),
+            },
+            Duration::from_secs(3600),
+        );
+
+        // The status list should only be fetched once, as it is cached afterwards.
+        let status = cache
+            .status(&valid, &key.cert_bts, &TimeGenerator, &trust_anchors)
+            .await
+            .unwrap();
+        assert_eq!(status, StatusType::Valid);
+
+        let status = cache
+            .status(&revoked, &key.cert_bts, &TimeGenerator, &trust_anchors)
+            .await
+            .unwrap();
+        assert_eq!(status, StatusType::Invalid);
+
+        assert_eq!(cache.client.fetch_count.load(Ordering::Relaxed), 1);
+
+        // An mdoc from another issuer may not refer to this status list.
+        let (other_key, _) = generate_issuance_key_and_ca().unwrap();
+        let error = cache
+            .status(&valid, &other_key.cert_bts, &TimeGenerator, &trust_anchors)
+            .await
+            .expect_err("status should not be returned for other issuer");
+        assert_matches!(error, Error::StatusList(StatusListError::IssuerMismatch));
+
+        // A token published at another URI should be rejected.
+        let other_reference = StatusListReference {
+            idx: 0,
+            uri: "https://example.com/status/other".parse().unwrap(),
+        };
+        let error = cache
+            .status(&other_reference, &key.cert_bts, &TimeGenerator, &trust_anchors)
+            .await
+            .expect_err("status should not be returned for other URI");
+        assert_matches!(error, Error::StatusList(StatusListError::UriMismatch { .. }));
+    }
+}
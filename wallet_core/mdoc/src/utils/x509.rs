@@ -1,6 +1,6 @@
 use std::borrow::Cow;
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, TimeZone, Utc};
 use indexmap::IndexMap;
 use p256::{
     ecdsa::VerifyingKey,
@@ -159,6 +159,17 @@ impl Certificate {
         self.try_into()
     }
 
+    /// The time after which the certificate is no longer valid.
+    pub fn not_after(&self) -> Result<DateTime<Utc>, CertificateError> {
+        let timestamp = self.to_x509()?.validity().not_after.timestamp();
+        let not_after = Utc
+            .timestamp_opt(timestamp, 0)
+            .single()
+            .expect("X.509 time should be representable as DateTime");
+
+        Ok(not_after)
+    }
+
     pub fn subject(&self) -> Result<IndexMap<String, String>, CertificateError> {
         let subject = self
             .to_x509()?
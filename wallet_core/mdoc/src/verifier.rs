@@ -1,14 +1,19 @@
 //! RP software, for verifying mdoc disclosures, see [`DeviceResponse::verify()`].
 
-use std::{sync::Arc, time::Duration};
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
 
 use chrono::{DateTime, Utc};
 use futures::future::try_join_all;
-use indexmap::IndexMap;
+use indexmap::{IndexMap, IndexSet};
 use p256::{elliptic_curve::rand_core::OsRng, SecretKey};
 use serde::{Deserialize, Serialize};
+use serde_with::{base64::Base64, serde_as};
 use strum;
 use tokio::task::JoinHandle;
+use tracing::instrument;
 use url::Url;
 use webpki::TrustAnchor;
 
@@ -20,16 +25,18 @@ use wallet_common::{
 };
 
 use crate::{
-    basic_sa_ext::Entry,
+    basic_sa_ext::{Entry, ReissuanceRequest},
     identifiers::{AttributeIdentifier, AttributeIdentifierHolder},
     iso::*,
     server_keys::{KeyRing, PrivateKey},
     server_state::{SessionState, SessionStore, SessionStoreError, SessionToken, CLEANUP_INTERVAL_SECONDS},
+    status_list::StatusListCache,
     utils::{
         cose::{self, ClonePayload, MdocCose},
         crypto::{cbor_digest, dh_hmac_key, SessionKey, SessionKeyUser},
+        reader_auth::ReaderRegistration,
         serialization::{cbor_deserialize, cbor_hex, cbor_serialize, CborSeq, TaggedBytes},
-        x509::CertificateUsage,
+        x509::{CertificateError, CertificateUsage},
     },
     Error, Result, SessionData,
 };
@@ -64,6 +71,11 @@ pub enum VerificationError {
     MissingOriginInfo(usize),
     #[error("incorrect OriginInfo in engagement")]
     IncorrectOriginInfo,
+    #[error("session type mismatch: expected {expected}, device engagement indicates {received}")]
+    SessionTypeMismatch {
+        expected: SessionType,
+        received: SessionType,
+    },
     #[error("unexpected input: session is done")]
     UnexpectedInput,
     #[error("unknown certificate")]
@@ -80,6 +92,16 @@ pub enum VerificationError {
     SessionNotDone(StatusResponse),
     #[error("transcript hash '{0:?}' does not match expected")]
     TranscriptHashMismatch(Option<Vec<u8>>),
+    #[error("could not extract reader registration from certificate: {0}")]
+    ReaderRegistration(#[source] CertificateError),
+    #[error("requested attributes not registered in reader certificate: {0:?}")]
+    UnregisteredAttributes(Vec<AttributeIdentifier>),
+    #[error("engagement requested for disclosure session with status: {0}")]
+    SessionNotCreated(StatusResponse),
+    #[error("session terminated by verifier: {0}")]
+    Terminated(String),
+    #[error("proof of possession should be a device signature")]
+    DeviceMacNotAllowed,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -90,6 +112,15 @@ impl From<Vec<ItemsRequest>> for ItemsRequests {
     }
 }
 
+impl AttributeIdentifierHolder for ItemsRequests {
+    fn attribute_identifiers(&self) -> IndexSet<AttributeIdentifier> {
+        self.0
+            .iter()
+            .flat_map(|items_request| items_request.attribute_identifiers())
+            .collect()
+    }
+}
+
 /// A disclosure session. `S` must implement [`DisclosureState`] and is the state that the session is in.
 /// The session progresses through the possible states using a state engine that uses the typestate pattern:
 /// for each state `S`, `Session<S>` has its own state transition method that consume the previous state.
@@ -104,7 +135,8 @@ pub struct Created {
     items_requests: ItemsRequests,
     session_type: SessionType,
     usecase_id: String,
-    return_url_used: bool,
+    /// The template of the return URL of the requester, which is opaque to the verifier.
+    return_url_template: Option<String>,
     ephemeral_privkey: DerSecretKey,
     #[serde(with = "cbor_hex")]
     reader_engagement: ReaderEngagement,
@@ -116,17 +148,41 @@ pub struct Created {
 pub struct WaitingForResponse {
     #[allow(unused)] // TODO write function that matches this field against the disclosed attributes
     items_requests: ItemsRequests,
+    usecase_id: String,
     return_url_used: bool,
     their_key: SessionKey,
     ephemeral_privkey: DerSecretKey,
     #[serde(with = "cbor_hex")]
     session_transcript: SessionTranscript,
+    /// Absent in sessions that were stored before the reader authentications were retained.
+    #[serde(default, with = "cbor_hex")]
+    reader_auths: Vec<ReaderAuth>,
 }
 
 /// State for a session that has ended (for any reason).
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Done {
+    usecase_id: String,
     session_result: SessionResult,
+    /// Only present if the device request was sent to the wallet, i.e. if the session ended after the device
+    /// engagement was processed.
+    evidence: Option<SessionEvidence>,
+}
+
+/// Evidence of a disclosure session in which the device request was sent to the wallet, which may be retained for
+/// investigating disputes.
+#[serde_as]
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SessionEvidence {
+    #[serde(with = "cbor_hex")]
+    pub session_transcript: SessionTranscript,
+    /// The reader authentication sent to the wallet, one for each [`DocRequest`].
+    #[serde(with = "cbor_hex")]
+    pub reader_auths: Vec<ReaderAuth>,
+    /// SHA256 digest of the CBOR serialization of the [`DisclosedAttributes`], if the disclosure was received from
+    /// the wallet and successfully verified.
+    #[serde_as(as = "Option<Base64>")]
+    pub result_digest: Option<Vec<u8>>,
 }
 
 /// The outcome of a session: the disclosed attributes if they have been sucessfully received and verified.
@@ -143,12 +199,37 @@ pub enum SessionResult {
     Cancelled,
 }
 
+/// Summary of a session that has not expired, as returned by [`Verifier::list_sessions()`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SessionSummary {
+    pub session_id: SessionToken,
+    pub usecase: String,
+    #[serde(flatten)]
+    pub status: StatusResponse,
+    pub last_active: DateTime<Utc>,
+}
+
 /// Disclosure session states for use as `T` in `Session<T>`.
-pub trait DisclosureState {}
+pub trait DisclosureState {
+    /// The usecase for which the session was started.
+    fn usecase_id(&self) -> &str;
+}
 
-impl DisclosureState for Created {}
-impl DisclosureState for WaitingForResponse {}
-impl DisclosureState for Done {}
+impl DisclosureState for Created {
+    fn usecase_id(&self) -> &str {
+        &self.usecase_id
+    }
+}
+impl DisclosureState for WaitingForResponse {
+    fn usecase_id(&self) -> &str {
+        &self.usecase_id
+    }
+}
+impl DisclosureState for Done {
+    fn usecase_id(&self) -> &str {
+        &self.usecase_id
+    }
+}
 
 /// Disclosure-specific session data, of any state, for storing in a session store.
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -158,6 +239,35 @@ pub enum DisclosureData {
     Done(Done),
 }
 
+impl DisclosureData {
+    fn usecase_id(&self) -> &str {
+        match self {
+            DisclosureData::Created(created) => created.usecase_id(),
+            DisclosureData::WaitingForResponse(waiting) => waiting.usecase_id(),
+            DisclosureData::Done(done) => done.usecase_id(),
+        }
+    }
+
+    fn status(&self) -> StatusResponse {
+        match self {
+            DisclosureData::Created(_) => StatusResponse::Created,
+            DisclosureData::WaitingForResponse(_) => StatusResponse::WaitingForResponse,
+            DisclosureData::Done(Done {
+                session_result: SessionResult::Done { .. },
+                ..
+            }) => StatusResponse::Done,
+            DisclosureData::Done(Done {
+                session_result: SessionResult::Failed { .. },
+                ..
+            }) => StatusResponse::Failed,
+            DisclosureData::Done(Done {
+                session_result: SessionResult::Cancelled,
+                ..
+            }) => StatusResponse::Cancelled,
+        }
+    }
+}
+
 impl SessionState<Created> {
     fn into_enum(self) -> SessionState<DisclosureData> {
         SessionState {
@@ -189,14 +299,24 @@ impl SessionState<Done> {
 }
 
 /// status without the underlying data
-#[derive(Debug, strum::Display, Deserialize, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::Display, strum::EnumString, Deserialize, Serialize)]
 #[serde(rename_all = "UPPERCASE", tag = "status")]
+#[strum(ascii_case_insensitive)]
 pub enum StatusResponse {
     Created,
     WaitingForResponse,
     Done,
     Failed,
     Cancelled,
+    /// The session was not completed in time, or was evicted to make room for newer sessions.
+    Expired,
+}
+
+impl StatusResponse {
+    /// Returns `true` if the session has ended, i.e. its status will not change anymore.
+    pub fn is_final(&self) -> bool {
+        matches!(self, Self::Done | Self::Failed | Self::Cancelled | Self::Expired)
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, strum::Display)]
@@ -211,10 +331,12 @@ pub enum SessionType {
 
 pub struct Verifier<K, S> {
     url: Url,
-    keys: K,
+    // The keys and trust anchors can be replaced while the verifier is in use, see [`Verifier::update_keys()`].
+    keys: RwLock<Arc<K>>,
     sessions: Arc<S>,
     cleanup_task: JoinHandle<()>,
-    trust_anchors: Vec<OwnedTrustAnchor>,
+    trust_anchors: RwLock<Arc<Vec<OwnedTrustAnchor>>>,
+    status_lists: Option<StatusListCache>,
 }
 
 impl<K, S> Drop for Verifier<K, S> {
@@ -244,37 +366,75 @@ where
         let sessions = Arc::new(sessions);
         Self {
             url,
-            keys,
+            keys: RwLock::new(Arc::new(keys)),
             cleanup_task: sessions
                 .clone()
                 .start_cleanup_task(Duration::from_secs(CLEANUP_INTERVAL_SECONDS)),
             sessions,
-            trust_anchors,
+            trust_anchors: RwLock::new(Arc::new(trust_anchors)),
+            status_lists: None,
         }
     }
 
+    /// Replace the keys of the usecases, e.g. after a reader certificate has been renewed. Sessions that were already
+    /// started remain valid; if the wallet has not yet sent its device engagement, the new key is used to sign the
+    /// device request.
+    pub fn update_keys(&self, keys: K) {
+        *self.keys.write().unwrap() = Arc::new(keys);
+    }
+
+    /// Replace the trust anchors against which the disclosed mdocs are verified. This applies to all responses that
+    /// are received from now on, including those of sessions that were started before.
+    pub fn update_trust_anchors(&self, trust_anchors: Vec<OwnedTrustAnchor>) {
+        *self.trust_anchors.write().unwrap() = Arc::new(trust_anchors);
+    }
+
+    /// Check the status of each disclosed mdoc that refers to a status list, rejecting the disclosure if any of
+    /// them has been revoked by its issuer.
+    pub fn with_status_lists(mut self, status_lists: StatusListCache) -> Self {
+        self.status_lists = Some(status_lists);
+        self
+    }
+
     /// Start a new disclosure session. Returns a [`ReaderEngagement`] instance that should be put in a QR
     /// or Universal Link or `mdoc://` URI.
     ///
     /// - `items_requests` contains the attributes to be requested.
     /// - `usecase_id` should point to an existing item in the `certificates` parameter.
+    /// - `return_url_template` is the template of the URL to which the wallet returns the user, if any. It is stored
+    ///   along with the session, so that it can be retrieved using [`Verifier::engagement`].
+    ///
+    /// If the certificate of the usecase contains a [`ReaderRegistration`], the requested attributes should all be
+    /// registered in it, as the wallet would otherwise refuse the request.
+    #[instrument(skip_all, fields(usecase_id = %usecase_id))]
     pub async fn new_session(
         &self,
         items_requests: ItemsRequests,
         session_type: SessionType,
         usecase_id: String,
-        return_url_used: bool,
+        return_url_template: Option<String>,
     ) -> Result<(SessionToken, ReaderEngagement)> {
-        if !self.keys.contains_key(&usecase_id) {
-            return Err(VerificationError::UnknownCertificate(usecase_id.clone()).into());
-        }
+        // Take the current keys, so that these are not locked while the session is being created.
+        let keys = Arc::clone(&self.keys.read().unwrap());
+        let private_key = keys
+            .private_key(&usecase_id)
+            .ok_or_else(|| VerificationError::UnknownCertificate(usecase_id.clone()))?;
 
         if items_requests.0.is_empty() {
             return Err(VerificationError::NoItemsRequests.into());
         }
 
+        if let Some(reader_registration) = ReaderRegistration::from_certificate(&private_key.cert_bts)
+            .map_err(VerificationError::ReaderRegistration)?
+        {
+            let unregistered: Vec<_> = items_requests.difference(&reader_registration).into_iter().collect();
+            if !unregistered.is_empty() {
+                return Err(VerificationError::UnregisteredAttributes(unregistered).into());
+            }
+        }
+
         let (session_token, reader_engagement, session_state) =
-            Session::<Created>::new(items_requests, session_type, usecase_id, return_url_used, &self.url)?;
+            Session::<Created>::new(items_requests, session_type, usecase_id, return_url_template, &self.url)?;
         self.sessions
             .write(&session_state.state.into_enum())
             .await
@@ -303,8 +463,9 @@ where
                         last_active: state.last_active,
                     },
                 };
+                let keys = Arc::clone(&self.keys.read().unwrap());
                 let (response, session) = session
-                    .process_device_engagement(cbor_deserialize(msg)?, &self.keys)
+                    .process_device_engagement(cbor_deserialize(msg)?, keys.as_ref())
                     .await;
                 match session {
                     Ok(next) => Ok((response, next.state.into_enum())),
@@ -319,16 +480,25 @@ where
                         last_active: state.last_active,
                     },
                 };
-                let (response, session) = session.process_response(
-                    cbor_deserialize(msg)?,
-                    self.trust_anchors
-                        .iter()
-                        .map(Into::<TrustAnchor<'_>>::into)
-                        .collect::<Vec<_>>()
-                        .as_slice(),
-                );
+                let trust_anchors = Arc::clone(&self.trust_anchors.read().unwrap());
+                let (response, session) = session
+                    .process_response(
+                        cbor_deserialize(msg)?,
+                        trust_anchors
+                            .iter()
+                            .map(Into::<TrustAnchor<'_>>::into)
+                            .collect::<Vec<_>>()
+                            .as_slice(),
+                        self.status_lists.as_ref(),
+                    )
+                    .await;
                 Ok((response, session.state.into_enum()))
             }
+            // Inform the wallet that the session has ended if it was cancelled, e.g. by the requester.
+            DisclosureData::Done(Done {
+                session_result: SessionResult::Cancelled,
+                ..
+            }) => return Ok(SessionData::new_termination()),
             DisclosureData::Done(_) => Err(Error::from(VerificationError::UnexpectedInput)),
         }?;
 
@@ -341,25 +511,76 @@ where
     }
 
     pub async fn status(&self, session_id: &SessionToken) -> Result<StatusResponse> {
-        match self
+        let session = match self.sessions.get(session_id).await {
+            Err(SessionStoreError::Expired) => return Ok(StatusResponse::Expired),
+            result => result.map_err(VerificationError::SessionStore)?,
+        };
+
+        let status = session
+            .ok_or(VerificationError::UnknownSessionId(session_id.clone()))?
+            .session_data
+            .status();
+
+        Ok(status)
+    }
+
+    /// Returns a summary of the sessions that have not expired, optionally only of those for the specified usecase
+    /// and with the specified status, ordered from most to least recently active.
+    pub async fn list_sessions(
+        &self,
+        usecase_id: Option<&str>,
+        status: Option<StatusResponse>,
+    ) -> Result<Vec<SessionSummary>> {
+        let mut sessions = self
+            .sessions
+            .list()
+            .await
+            .map_err(VerificationError::SessionStore)?
+            .into_iter()
+            .map(|session| SessionSummary {
+                usecase: session.session_data.usecase_id().to_string(),
+                status: session.session_data.status(),
+                session_id: session.token,
+                last_active: session.last_active,
+            })
+            .filter(|summary| {
+                usecase_id.map_or(true, |usecase_id| summary.usecase == usecase_id)
+                    && status.map_or(true, |status| summary.status == status)
+            })
+            .collect::<Vec<_>>();
+
+        sessions.sort_by(|a, b| {
+            b.last_active
+                .cmp(&a.last_active)
+                .then_with(|| a.session_id.0.cmp(&b.session_id.0))
+        });
+
+        Ok(sessions)
+    }
+
+    /// Returns the [`ReaderEngagement`] of a session, along with its [`SessionType`] and return URL template, so that
+    /// the engagement can be presented to the wallet (again). This is only possible as long as the wallet has not
+    /// contacted the verifier, i.e. for a session with status `Created`, and returns an error otherwise.
+    pub async fn engagement(
+        &self,
+        session_id: &SessionToken,
+    ) -> Result<(ReaderEngagement, SessionType, Option<String>)> {
+        let session_data = self
             .sessions
             .get(session_id)
             .await
             .map_err(VerificationError::SessionStore)?
             .ok_or(VerificationError::UnknownSessionId(session_id.clone()))?
-            .session_data
-        {
-            DisclosureData::Created(_) => Ok(StatusResponse::Created),
-            DisclosureData::WaitingForResponse(_) => Ok(StatusResponse::WaitingForResponse),
-            DisclosureData::Done(Done {
-                session_result: SessionResult::Done { .. },
-            }) => Ok(StatusResponse::Done),
-            DisclosureData::Done(Done {
-                session_result: SessionResult::Failed { .. },
-            }) => Ok(StatusResponse::Failed),
-            DisclosureData::Done(Done {
-                session_result: SessionResult::Cancelled { .. },
-            }) => Ok(StatusResponse::Cancelled),
+            .session_data;
+
+        match session_data {
+            DisclosureData::Created(Created {
+                reader_engagement,
+                session_type,
+                return_url_template,
+                ..
+            }) => Ok((reader_engagement, session_type, return_url_template)),
+            _ => Err(VerificationError::SessionNotCreated(self.status(session_id).await?).into()),
         }
     }
 
@@ -381,7 +602,7 @@ where
             DisclosureData::WaitingForResponse(_) => {
                 Err(VerificationError::SessionNotDone(StatusResponse::WaitingForResponse).into())
             }
-            DisclosureData::Done(Done { session_result }) => match session_result {
+            DisclosureData::Done(Done { session_result, .. }) => match session_result {
                 SessionResult::Failed { .. } => Err(VerificationError::SessionNotDone(StatusResponse::Failed).into()),
                 SessionResult::Cancelled { .. } => {
                     Err(VerificationError::SessionNotDone(StatusResponse::Cancelled).into())
@@ -401,6 +622,158 @@ where
             },
         }
     }
+
+    /// Returns the [`SessionEvidence`] of a session, which is only present if the session has been completed by
+    /// successfully verifying the disclosure of the wallet.
+    pub async fn session_evidence(&self, session_id: &SessionToken) -> Result<Option<SessionEvidence>> {
+        let evidence = match self
+            .sessions
+            .get(session_id)
+            .await
+            .map_err(VerificationError::SessionStore)?
+            .ok_or(VerificationError::UnknownSessionId(session_id.clone()))?
+            .session_data
+        {
+            DisclosureData::Done(Done { evidence, .. }) => evidence,
+            _ => None,
+        };
+
+        Ok(evidence)
+    }
+
+    /// Returns the [`SessionResult`] of a session, which is only present if the session has ended. Contrary to
+    /// [`Verifier::disclosed_attributes`], the transcript hash is not checked, so the result should not be handed out
+    /// to the requester as is.
+    pub async fn session_result(&self, session_id: &SessionToken) -> Result<Option<SessionResult>> {
+        let session_result = match self
+            .sessions
+            .get(session_id)
+            .await
+            .map_err(VerificationError::SessionStore)?
+            .ok_or(VerificationError::UnknownSessionId(session_id.clone()))?
+            .session_data
+        {
+            DisclosureData::Done(Done { session_result, .. }) => Some(session_result),
+            _ => None,
+        };
+
+        Ok(session_result)
+    }
+
+    /// Returns the usecase for which a session was started.
+    pub async fn usecase(&self, session_id: &SessionToken) -> Result<String> {
+        let usecase = self
+            .sessions
+            .get(session_id)
+            .await
+            .map_err(VerificationError::SessionStore)?
+            .ok_or(VerificationError::UnknownSessionId(session_id.clone()))?
+            .session_data
+            .usecase_id()
+            .to_string();
+
+        Ok(usecase)
+    }
+
+    /// Finish a session that is not yet done with the specified `disclosed_attributes`, without any involvement of a
+    /// wallet. This is only meant for simulating disclosures, e.g. for relying parties that integrate against a
+    /// sandboxed instance before the wallet app is available to them. It should never be exposed in production.
+    pub async fn simulate_disclosure(
+        &self,
+        session_id: &SessionToken,
+        disclosed_attributes: DisclosedAttributes,
+    ) -> Result<()> {
+        let state = self
+            .sessions
+            .get(session_id)
+            .await
+            .map_err(VerificationError::SessionStore)?
+            .ok_or_else(|| VerificationError::UnknownSessionId(session_id.clone()))?;
+
+        // As no wallet is involved, there is no session transcript and therefore no transcript hash.
+        let next = match state.session_data {
+            DisclosureData::Created(session_data) => Session::<Created> {
+                state: SessionState {
+                    session_data,
+                    token: state.token,
+                    last_active: state.last_active,
+                },
+            }
+            .transition_finish(disclosed_attributes, None, None),
+            DisclosureData::WaitingForResponse(session_data) => Session::<WaitingForResponse> {
+                state: SessionState {
+                    session_data,
+                    token: state.token,
+                    last_active: state.last_active,
+                },
+            }
+            .transition_finish(disclosed_attributes, None, None),
+            DisclosureData::Done(_) => return Err(VerificationError::UnexpectedInput.into()),
+        };
+
+        self.sessions
+            .write(&next.state.into_enum())
+            .await
+            .map_err(VerificationError::SessionStore)?;
+
+        Ok(())
+    }
+
+    /// End a session that is not yet done, which then has the status `Failed` with the specified `reason`. This can be
+    /// used when the wallet should not be allowed to continue the session, e.g. because it sent too many requests.
+    pub async fn terminate_session(&self, session_id: &SessionToken, reason: String) -> Result<()> {
+        let error = Error::from(VerificationError::Terminated(reason));
+
+        self.end_session(
+            session_id,
+            SessionResult::Failed {
+                error: error.to_string(),
+            },
+        )
+        .await
+    }
+
+    /// Cancel a session that is not yet done on behalf of the requester, which then has the status `Cancelled`. When
+    /// the wallet contacts the verifier afterwards, it is informed that the session has been terminated.
+    pub async fn cancel_session(&self, session_id: &SessionToken) -> Result<()> {
+        self.end_session(session_id, SessionResult::Cancelled).await
+    }
+
+    async fn end_session(&self, session_id: &SessionToken, session_result: SessionResult) -> Result<()> {
+        let state = self
+            .sessions
+            .get(session_id)
+            .await
+            .map_err(VerificationError::SessionStore)?
+            .ok_or_else(|| VerificationError::UnknownSessionId(session_id.clone()))?;
+
+        let next = match state.session_data {
+            DisclosureData::Created(session_data) => Session::<Created> {
+                state: SessionState {
+                    session_data,
+                    token: state.token,
+                    last_active: state.last_active,
+                },
+            }
+            .transition_end(session_result),
+            DisclosureData::WaitingForResponse(session_data) => Session::<WaitingForResponse> {
+                state: SessionState {
+                    session_data,
+                    token: state.token,
+                    last_active: state.last_active,
+                },
+            }
+            .transition_end(session_result),
+            DisclosureData::Done(_) => return Err(VerificationError::UnexpectedInput.into()),
+        };
+
+        self.sessions
+            .write(&next.state.into_enum())
+            .await
+            .map_err(VerificationError::SessionStore)?;
+
+        Ok(())
+    }
 }
 
 // Implementation of the typestate state engine follows.
@@ -408,16 +781,38 @@ where
 // Transitioning functions and helpers valid for any state
 impl<T: DisclosureState> Session<T> {
     fn transition_fail(self, error: Error) -> Session<Done> {
-        self.transition(Done {
-            session_result: SessionResult::Failed {
-                error: error.to_string(),
-            },
+        self.transition_end(SessionResult::Failed {
+            error: error.to_string(),
         })
     }
 
     fn transition_abort(self, status: SessionStatus) -> Session<Done> {
+        self.transition_end(status.into())
+    }
+
+    fn transition_end(self, session_result: SessionResult) -> Session<Done> {
+        let usecase_id = self.state().usecase_id().to_string();
+        self.transition(Done {
+            usecase_id,
+            session_result,
+            evidence: None,
+        })
+    }
+
+    fn transition_finish(
+        self,
+        disclosed_attributes: DisclosedAttributes,
+        transcript_hash: Option<Vec<u8>>,
+        evidence: Option<SessionEvidence>,
+    ) -> Session<Done> {
+        let usecase_id = self.state().usecase_id().to_string();
         self.transition(Done {
-            session_result: status.into(),
+            usecase_id,
+            session_result: SessionResult::Done {
+                disclosed_attributes,
+                transcript_hash,
+            },
+            evidence,
         })
     }
 
@@ -443,7 +838,7 @@ impl Session<Created> {
         items_requests: ItemsRequests,
         session_type: SessionType,
         usecase_id: String,
-        return_url_used: bool,
+        return_url_template: Option<String>,
         base_url: &Url,
     ) -> Result<(SessionToken, ReaderEngagement, Session<Created>)> {
         let session_token = SessionToken::new();
@@ -456,7 +851,7 @@ impl Session<Created> {
                     items_requests,
                     session_type,
                     usecase_id,
-                    return_url_used,
+                    return_url_template,
                     ephemeral_privkey: ephemeral_privkey.into(),
                     reader_engagement: reader_engagement.clone(),
                 },
@@ -468,6 +863,7 @@ impl Session<Created> {
 
     /// Process the device's [`DeviceEngagement`],
     /// returning a response to answer the device with and the next session state.
+    #[instrument(skip_all)]
     async fn process_device_engagement(
         self,
         device_engagement: DeviceEngagement,
@@ -477,9 +873,15 @@ impl Session<Created> {
         std::result::Result<Session<WaitingForResponse>, Session<Done>>,
     ) {
         let (response, next) = match self.process_device_engagement_inner(&device_engagement, keys).await {
-            Ok((response, items_requests, their_key, ephemeral_privkey, session_transcript)) => (
+            Ok((response, items_requests, their_key, ephemeral_privkey, session_transcript, reader_auths)) => (
                 response,
-                Ok(self.transition_wait_for_response(items_requests, their_key, ephemeral_privkey, session_transcript)),
+                Ok(self.transition_wait_for_response(
+                    items_requests,
+                    their_key,
+                    ephemeral_privkey,
+                    session_transcript,
+                    reader_auths,
+                )),
             ),
             Err(e) => (SessionData::new_decoding_error(), Err(self.transition_fail(e))),
         };
@@ -492,8 +894,15 @@ impl Session<Created> {
         &self,
         device_engagement: &DeviceEngagement,
         keys: &impl KeyRing,
-    ) -> Result<(SessionData, ItemsRequests, SessionKey, SecretKey, SessionTranscript)> {
-        Self::verify_origin_infos(&device_engagement.0.origin_infos)?;
+    ) -> Result<(
+        SessionData,
+        ItemsRequests,
+        SessionKey,
+        SecretKey,
+        SessionTranscript,
+        Vec<ReaderAuth>,
+    )> {
+        Self::verify_origin_infos(&device_engagement.0.origin_infos, self.state().session_type)?;
 
         // Compute the session transcript whose CBOR serialization acts as the challenge throughout the protocol
         let session_transcript = SessionTranscript::new(
@@ -530,6 +939,11 @@ impl Session<Created> {
         )?;
 
         let response = SessionData::serialize_and_encrypt(&device_request, &our_key)?;
+        let reader_auths = device_request
+            .doc_requests
+            .into_iter()
+            .filter_map(|doc_request| doc_request.reader_auth)
+            .collect();
 
         Ok((
             response,
@@ -537,11 +951,15 @@ impl Session<Created> {
             their_key,
             self.state().ephemeral_privkey.clone().0,
             session_transcript,
+            reader_auths,
         ))
     }
 
-    fn verify_origin_infos(origin_infos: &[OriginInfo]) -> Result<()> {
-        if origin_infos.len() != 2 {
+    /// Checks the [`OriginInfo`]s of the [`DeviceEngagement`], of which the first should state that the
+    /// [`ReaderEngagement`] was received in the way that matches the [`SessionType`] of the session. This prevents an
+    /// engagement intended for one device from being relayed to and used on another, or vice versa.
+    fn verify_origin_infos(origin_infos: &[OriginInfo], session_type: SessionType) -> Result<()> {
+        if origin_infos.len() != 2 || origin_infos[0].cat != OriginInfoDirection::Received {
             return Err(VerificationError::IncorrectOriginInfo.into());
         }
 
@@ -549,10 +967,17 @@ impl Session<Created> {
         // possible for the wallet to reliably determine the referrer URL, so we can't enforce it here to be equal
         // to something.
         // TODO: implement this once we have decided on a sensible thing to do with this.
-        if origin_infos[0].cat != OriginInfoDirection::Received
-            || !matches!(origin_infos[0].typ, OriginInfoType::Website(_))
-        {
-            return Err(VerificationError::IncorrectOriginInfo.into());
+        let received = match origin_infos[0].typ {
+            OriginInfoType::Website(_) => SessionType::SameDevice,
+            OriginInfoType::OnDeviceQRCode => SessionType::CrossDevice,
+            OriginInfoType::MessageData => return Err(VerificationError::IncorrectOriginInfo.into()),
+        };
+        if received != session_type {
+            return Err(VerificationError::SessionTypeMismatch {
+                expected: session_type,
+                received,
+            }
+            .into());
         }
 
         if origin_infos[1]
@@ -573,14 +998,18 @@ impl Session<Created> {
         their_key: SessionKey,
         ephemeral_privkey: SecretKey,
         session_transcript: SessionTranscript,
+        reader_auths: Vec<ReaderAuth>,
     ) -> Session<WaitingForResponse> {
-        let return_url_used = self.state.session_data.return_url_used;
+        let usecase_id = self.state.session_data.usecase_id.clone();
+        let return_url_used = self.state.session_data.return_url_template.is_some();
         self.transition(WaitingForResponse {
             items_requests,
+            usecase_id,
             their_key,
             return_url_used,
             ephemeral_privkey: ephemeral_privkey.into(),
             session_transcript,
+            reader_auths,
         })
     }
 
@@ -621,32 +1050,49 @@ impl Session<Created> {
 impl Session<WaitingForResponse> {
     /// Process the user's encrypted [`DeviceResponse`], i.e. its disclosure,
     /// returning a response to answer the device with and the next session state.
-    fn process_response(
+    #[instrument(skip_all)]
+    async fn process_response(
         self,
         session_data: SessionData,
-        trust_anchors: &[TrustAnchor],
+        trust_anchors: &[TrustAnchor<'_>],
+        status_lists: Option<&StatusListCache>,
     ) -> (SessionData, Session<Done>) {
-        // Abort if user wants to abort
+        // Abort if user wants to abort, retaining the evidence of what was requested from the user
         if let Some(status) = session_data.status {
-            return (SessionData::new_termination(), self.transition_abort(status));
+            let evidence = self.evidence(None);
+            return (
+                SessionData::new_termination(),
+                self.transition_abort(status).with_evidence(evidence),
+            );
         };
 
-        let (response, next) = match self.process_response_inner(&session_data, trust_anchors) {
-            Ok((response, disclosed_attributes, transcript_hash)) => {
-                (response, self.transition_finish(disclosed_attributes, transcript_hash))
+        let (response, next) = match self
+            .process_response_inner(&session_data, trust_anchors, status_lists)
+            .await
+        {
+            Ok((response, disclosed_attributes, transcript_hash, evidence)) => (
+                response,
+                self.transition_finish(disclosed_attributes, transcript_hash, Some(evidence)),
+            ),
+            Err(e) => {
+                let evidence = self.evidence(None);
+                (
+                    SessionData::new_decoding_error(),
+                    self.transition_fail(e).with_evidence(evidence),
+                )
             }
-            Err(e) => (SessionData::new_decoding_error(), self.transition_fail(e)),
         };
 
         (response, next)
     }
 
     // Helper function that returns ordinary errors instead of `Session<Done>`
-    fn process_response_inner(
+    async fn process_response_inner(
         &self,
         session_data: &SessionData,
-        trust_anchors: &[TrustAnchor],
-    ) -> Result<(SessionData, DisclosedAttributes, Option<Vec<u8>>)> {
+        trust_anchors: &[TrustAnchor<'_>],
+        status_lists: Option<&StatusListCache>,
+    ) -> Result<(SessionData, DisclosedAttributes, Option<Vec<u8>>, SessionEvidence)> {
         let device_response: DeviceResponse = session_data.decrypt_and_deserialize(&self.state().their_key)?;
 
         let disclosed_attributes = device_response.verify(
@@ -657,6 +1103,16 @@ impl Session<WaitingForResponse> {
         )?;
         self.state().items_requests.match_against_response(&device_response)?;
 
+        if let Some(status_lists) = status_lists {
+            status_lists
+                .verify_documents(
+                    device_response.documents.as_deref().unwrap_or_default(),
+                    &TimeGenerator,
+                    trust_anchors,
+                )
+                .await?;
+        }
+
         let response = SessionData {
             data: None,
             status: Some(SessionStatus::Termination),
@@ -671,20 +1127,24 @@ impl Session<WaitingForResponse> {
             })
             .transpose()?;
 
-        Ok((response, disclosed_attributes, transcript_hash))
+        let evidence = self.evidence(Some(utils::sha256(&cbor_serialize(&disclosed_attributes)?)));
+
+        Ok((response, disclosed_attributes, transcript_hash, evidence))
     }
 
-    fn transition_finish(
-        self,
-        disclosed_attributes: DisclosedAttributes,
-        transcript_hash: Option<Vec<u8>>,
-    ) -> Session<Done> {
-        self.transition(Done {
-            session_result: SessionResult::Done {
-                disclosed_attributes,
-                transcript_hash,
-            },
-        })
+    fn evidence(&self, result_digest: Option<Vec<u8>>) -> SessionEvidence {
+        SessionEvidence {
+            session_transcript: self.state().session_transcript.clone(),
+            reader_auths: self.state().reader_auths.clone(),
+            result_digest,
+        }
+    }
+}
+
+impl Session<Done> {
+    fn with_evidence(mut self, evidence: SessionEvidence) -> Self {
+        self.state.session_data.evidence = Some(evidence);
+        self
     }
 }
 
@@ -931,6 +1391,40 @@ impl Document {
     }
 }
 
+impl ReissuanceRequest {
+    /// Verify that the document contains a valid mdoc, signed by one of the trust anchors, of which the private key
+    /// has signed the challenge. Returns the attributes and the MSO of the mdoc. Note that the caller should check
+    /// that the challenge was issued by it and has not been used before.
+    pub fn verify(
+        &self,
+        time: &impl Generator<DateTime<Utc>>,
+        trust_anchors: &[TrustAnchor],
+    ) -> Result<(DocumentDisclosedAttributes, MobileSecurityObject)> {
+        let (attrs, mso) = self
+            .document
+            .issuer_signed
+            .verify(ValidityRequirement::Valid, time, trust_anchors)?;
+
+        if mso.doc_type != self.document.doc_type {
+            return Err(VerificationError::WrongDocType {
+                document: self.document.doc_type.clone(),
+                mso: mso.doc_type,
+            }
+            .into());
+        }
+
+        let device_key = (&mso.device_key_info.device_key).try_into()?;
+        match &self.document.device_signed.device_auth {
+            DeviceAuth::DeviceSignature(sig) => {
+                sig.clone_with_payload(self.challenge.to_vec()).verify(&device_key)?;
+            }
+            DeviceAuth::DeviceMac(_) => return Err(VerificationError::DeviceMacNotAllowed.into()),
+        }
+
+        Ok((attrs, mso))
+    }
+}
+
 impl ItemsRequest {
     /// Returns requested attributes, if any, that are not present in the `issuer_signed`.
     pub fn match_against_issuer_signed(&self, document: &Document) -> Vec<AttributeIdentifier> {
@@ -946,13 +1440,16 @@ impl ItemsRequest {
 mod tests {
     use std::ops::Add;
 
+    use assert_matches::assert_matches;
     use chrono::{Duration, Utc};
     use indexmap::IndexMap;
     use rstest::rstest;
+    use serde_bytes::ByteBuf;
 
     use wallet_common::trust_anchor::DerTrustAnchor;
 
     use crate::{
+        basic_sa_ext::Entry,
         examples::{
             Example, Examples, IsoCertTimeGenerator, EXAMPLE_ATTR_NAME, EXAMPLE_ATTR_VALUE, EXAMPLE_DOC_TYPE,
             EXAMPLE_NAMESPACE,
@@ -963,11 +1460,12 @@ mod tests {
         server_state::MemorySessionStore,
         utils::{
             crypto::{SessionKey, SessionKeyUser},
+            reader_auth::{reader_registration_mock, ReaderRegistration},
             serialization::cbor_serialize,
             x509::{Certificate, CertificateType},
         },
         verifier::{
-            SessionType, ValidityError,
+            SessionResult, SessionType, StatusResponse, ValidityError,
             ValidityRequirement::{AllowNotYetValid, Valid},
             VerificationError, Verifier,
         },
@@ -1107,19 +1605,36 @@ mod tests {
                 new_disclosure_request(),
                 SessionType::SameDevice,
                 DISCLOSURE_USECASE.to_string(),
-                false,
+                None,
             )
             .await
             .unwrap();
 
+        // The engagement can be retrieved as long as the device has not contacted us
+        let (stored_engagement, session_type, return_url_template) = verifier.engagement(&session_token).await.unwrap();
+        assert_eq!(
+            cbor_serialize(&stored_engagement).unwrap(),
+            cbor_serialize(&reader_engagement).unwrap()
+        );
+        assert_eq!(session_type, SessionType::SameDevice);
+        assert_eq!(return_url_template, None);
+
         // Construct first device protocol message
         let (device_engagement, device_eph_key) =
-            DeviceEngagement::new_device_engagement("https://example.com/".parse().unwrap()).unwrap();
+            DeviceEngagement::new_device_engagement(SessionType::SameDevice, "https://example.com/".parse().unwrap())
+                .unwrap();
         let msg = cbor_serialize(&device_engagement).unwrap();
 
         // send first device protocol message
         let encrypted_device_request = verifier.process_message(&msg, session_token.clone()).await.unwrap();
 
+        assert_matches!(
+            verifier.engagement(&session_token).await,
+            Err(Error::Verification(VerificationError::SessionNotCreated(
+                StatusResponse::WaitingForResponse
+            )))
+        );
+
         // decrypt server response
         // Note that the unwraps here are safe, as we created the `ReaderEngagement`.
         let rp_key = SessionKey::new(
@@ -1138,11 +1653,382 @@ mod tests {
         })
         .unwrap();
         let ended_session_response = verifier
-            .process_message(&end_session_message, session_token)
+            .process_message(&end_session_message, session_token.clone())
             .await
             .unwrap();
 
         assert_eq!(ended_session_response.status.unwrap(), SessionStatus::Termination);
+
+        // The evidence of what was requested is retained, but nothing was disclosed
+        let evidence = verifier.session_evidence(&session_token).await.unwrap().unwrap();
+        assert_eq!(
+            cbor_serialize(&evidence.session_transcript).unwrap(),
+            cbor_serialize(
+                &SessionTranscript::new(SessionType::SameDevice, &reader_engagement, &device_engagement).unwrap()
+            )
+            .unwrap()
+        );
+        assert_eq!(evidence.reader_auths.len(), 1);
+        assert!(evidence.result_digest.is_none());
+    }
+
+    #[tokio::test]
+    async fn new_session_unregistered_attributes() {
+        // Register only the first of the requested attributes in the reader certificate
+        let reader_registration = ReaderRegistration {
+            attributes: mock::reader_registration_attributes(
+                DISCLOSURE_DOC_TYPE.to_string(),
+                DISCLOSURE_NAME_SPACE.to_string(),
+                DISCLOSURE_ATTRS.iter().take(1).map(|(name, _)| *name),
+            ),
+            ..reader_registration_mock()
+        };
+        let (ca, ca_privkey) = Certificate::new_ca(RP_CA_CN).unwrap();
+        let (rp_cert, rp_privkey) = Certificate::new(
+            &ca,
+            &ca_privkey,
+            RP_CERT_CN,
+            CertificateType::ReaderAuth(Box::new(reader_registration).into()),
+        )
+        .unwrap();
+
+        let verifier = Verifier::new(
+            "https://example.com".parse().unwrap(),
+            SingleKeyRing(PrivateKey::new(rp_privkey, rp_cert)),
+            MemorySessionStore::new(),
+            vec![],
+        );
+
+        let error = verifier
+            .new_session(
+                new_disclosure_request(),
+                SessionType::SameDevice,
+                DISCLOSURE_USECASE.to_string(),
+                None,
+            )
+            .await
+            .expect_err("starting a session with unregistered attributes should fail");
+
+        assert_matches!(
+            error,
+            Error::Verification(VerificationError::UnregisteredAttributes(attributes))
+                if attributes == vec![AttributeIdentifier {
+                    doc_type: DISCLOSURE_DOC_TYPE.to_string(),
+                    namespace: DISCLOSURE_NAME_SPACE.to_string(),
+                    attribute: DISCLOSURE_ATTRS[1].0.to_string(),
+                }]
+        );
+    }
+
+    #[tokio::test]
+    async fn update_keys() {
+        let (ca, ca_privkey) = Certificate::new_ca(RP_CA_CN).unwrap();
+        let (rp_cert, rp_privkey) = Certificate::new(
+            &ca,
+            &ca_privkey,
+            RP_CERT_CN,
+            CertificateType::ReaderAuth(Default::default()),
+        )
+        .unwrap();
+
+        let verifier = Verifier::new(
+            "https://example.com".parse().unwrap(),
+            SingleKeyRing(PrivateKey::new(rp_privkey, rp_cert)),
+            MemorySessionStore::new(),
+            vec![],
+        );
+
+        verifier
+            .new_session(
+                new_disclosure_request(),
+                SessionType::SameDevice,
+                DISCLOSURE_USECASE.to_string(),
+                None,
+            )
+            .await
+            .unwrap();
+
+        // Replace the key by one of which the certificate only registers the first of the requested attributes
+        let reader_registration = ReaderRegistration {
+            attributes: mock::reader_registration_attributes(
+                DISCLOSURE_DOC_TYPE.to_string(),
+                DISCLOSURE_NAME_SPACE.to_string(),
+                DISCLOSURE_ATTRS.iter().take(1).map(|(name, _)| *name),
+            ),
+            ..reader_registration_mock()
+        };
+        let (rp_cert, rp_privkey) = Certificate::new(
+            &ca,
+            &ca_privkey,
+            RP_CERT_CN,
+            CertificateType::ReaderAuth(Box::new(reader_registration).into()),
+        )
+        .unwrap();
+        verifier.update_keys(SingleKeyRing(PrivateKey::new(rp_privkey, rp_cert)));
+
+        // New sessions are checked against the new certificate
+        assert_matches!(
+            verifier
+                .new_session(
+                    new_disclosure_request(),
+                    SessionType::SameDevice,
+                    DISCLOSURE_USECASE.to_string(),
+                    None,
+                )
+                .await,
+            Err(Error::Verification(VerificationError::UnregisteredAttributes(_)))
+        );
+    }
+
+    #[tokio::test]
+    async fn simulate_disclosure() {
+        let (ca, ca_privkey) = Certificate::new_ca(RP_CA_CN).unwrap();
+        let (rp_cert, rp_privkey) = Certificate::new(
+            &ca,
+            &ca_privkey,
+            RP_CERT_CN,
+            CertificateType::ReaderAuth(Default::default()),
+        )
+        .unwrap();
+        let verifier = Verifier::new(
+            "https://example.com".parse().unwrap(),
+            SingleKeyRing(PrivateKey::new(rp_privkey, rp_cert)),
+            MemorySessionStore::new(),
+            vec![],
+        );
+
+        let (session_token, _) = verifier
+            .new_session(
+                new_disclosure_request(),
+                SessionType::CrossDevice,
+                DISCLOSURE_USECASE.to_string(),
+                None,
+            )
+            .await
+            .unwrap();
+        assert!(verifier.session_result(&session_token).await.unwrap().is_none());
+
+        let canned_attributes = IndexMap::from([(
+            DISCLOSURE_DOC_TYPE.to_string(),
+            IndexMap::from([(
+                DISCLOSURE_NAME_SPACE.to_string(),
+                vec![Entry {
+                    name: "first_name".to_string(),
+                    value: ciborium::Value::Text("John".to_string()),
+                }],
+            )]),
+        )]);
+
+        verifier
+            .simulate_disclosure(&session_token, canned_attributes.clone())
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            verifier.status(&session_token).await.unwrap(),
+            StatusResponse::Done
+        ));
+        let disclosed_attributes = verifier.disclosed_attributes(&session_token, None).await.unwrap();
+        assert_eq!(disclosed_attributes, canned_attributes);
+        assert_matches!(
+            verifier.session_result(&session_token).await.unwrap(),
+            Some(SessionResult::Done { disclosed_attributes, transcript_hash: None })
+                if disclosed_attributes == canned_attributes
+        );
+
+        // No evidence is retained of a simulated disclosure
+        assert!(verifier.session_evidence(&session_token).await.unwrap().is_none());
+
+        // A session that is done cannot be simulated again
+        let error = verifier
+            .simulate_disclosure(&session_token, canned_attributes)
+            .await
+            .expect_err("simulating a finished session should fail");
+        assert!(matches!(error, Error::Verification(VerificationError::UnexpectedInput)));
+    }
+
+    #[tokio::test]
+    async fn terminate_session() {
+        let (ca, ca_privkey) = Certificate::new_ca(RP_CA_CN).unwrap();
+        let (rp_cert, rp_privkey) = Certificate::new(
+            &ca,
+            &ca_privkey,
+            RP_CERT_CN,
+            CertificateType::ReaderAuth(Default::default()),
+        )
+        .unwrap();
+        let verifier = Verifier::new(
+            "https://example.com".parse().unwrap(),
+            SingleKeyRing(PrivateKey::new(rp_privkey, rp_cert)),
+            MemorySessionStore::new(),
+            vec![],
+        );
+
+        let (session_token, _) = verifier
+            .new_session(
+                new_disclosure_request(),
+                SessionType::CrossDevice,
+                DISCLOSURE_USECASE.to_string(),
+                None,
+            )
+            .await
+            .unwrap();
+
+        verifier
+            .terminate_session(&session_token, "too many requests".to_string())
+            .await
+            .unwrap();
+
+        assert_matches!(verifier.status(&session_token).await.unwrap(), StatusResponse::Failed);
+        assert_matches!(
+            verifier.session_result(&session_token).await.unwrap(),
+            Some(SessionResult::Failed { error }) if error.contains("too many requests")
+        );
+
+        // A session that is done cannot be terminated again
+        let error = verifier
+            .terminate_session(&session_token, "too many requests".to_string())
+            .await
+            .expect_err("terminating a finished session should fail");
+        assert_matches!(error, Error::Verification(VerificationError::UnexpectedInput));
+    }
+
+    #[tokio::test]
+    async fn cancel_and_list_sessions() {
+        let (ca, ca_privkey) = Certificate::new_ca(RP_CA_CN).unwrap();
+        let (rp_cert, rp_privkey) = Certificate::new(
+            &ca,
+            &ca_privkey,
+            RP_CERT_CN,
+            CertificateType::ReaderAuth(Default::default()),
+        )
+        .unwrap();
+        let verifier = Verifier::new(
+            "https://example.com".parse().unwrap(),
+            SingleKeyRing(PrivateKey::new(rp_privkey, rp_cert)),
+            MemorySessionStore::new(),
+            vec![],
+        );
+
+        let mut session_tokens = Vec::new();
+        for _ in 0..2 {
+            let (session_token, _) = verifier
+                .new_session(
+                    new_disclosure_request(),
+                    SessionType::CrossDevice,
+                    DISCLOSURE_USECASE.to_string(),
+                    None,
+                )
+                .await
+                .unwrap();
+            session_tokens.push(session_token);
+        }
+
+        verifier.cancel_session(&session_tokens[0]).await.unwrap();
+        assert_matches!(
+            verifier.status(&session_tokens[0]).await.unwrap(),
+            StatusResponse::Cancelled
+        );
+
+        // The cancelled session is the most recently active one
+        let sessions = verifier.list_sessions(None, None).await.unwrap();
+        assert_eq!(
+            sessions
+                .iter()
+                .map(|summary| (summary.session_id.clone(), summary.status))
+                .collect::<Vec<_>>(),
+            vec![
+                (session_tokens[0].clone(), StatusResponse::Cancelled),
+                (session_tokens[1].clone(), StatusResponse::Created)
+            ]
+        );
+        assert!(sessions.iter().all(|summary| summary.usecase == DISCLOSURE_USECASE));
+
+        let sessions = verifier
+            .list_sessions(Some(DISCLOSURE_USECASE), Some(StatusResponse::Created))
+            .await
+            .unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].session_id, session_tokens[1]);
+        assert!(verifier
+            .list_sessions(Some("other_usecase"), None)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // The wallet is told that the cancelled session has been terminated
+        let (device_engagement, _) =
+            DeviceEngagement::new_device_engagement(SessionType::CrossDevice, "https://example.com/".parse().unwrap())
+                .unwrap();
+        let response = verifier
+            .process_message(&cbor_serialize(&device_engagement).unwrap(), session_tokens[0].clone())
+            .await
+            .unwrap();
+        assert_matches!(
+            response,
+            SessionData {
+                data: None,
+                status: Some(SessionStatus::Termination)
+            }
+        );
+
+        // A session that is done cannot be cancelled again
+        let error = verifier
+            .cancel_session(&session_tokens[0])
+            .await
+            .expect_err("cancelling a finished session should fail");
+        assert_matches!(error, Error::Verification(VerificationError::UnexpectedInput));
+    }
+
+    #[tokio::test]
+    async fn session_type_mismatch() {
+        let (ca, ca_privkey) = Certificate::new_ca(RP_CA_CN).unwrap();
+        let (rp_cert, rp_privkey) = Certificate::new(
+            &ca,
+            &ca_privkey,
+            RP_CERT_CN,
+            CertificateType::ReaderAuth(Default::default()),
+        )
+        .unwrap();
+        let verifier = Verifier::new(
+            "https://example.com".parse().unwrap(),
+            SingleKeyRing(PrivateKey::new(rp_privkey, rp_cert)),
+            MemorySessionStore::new(),
+            vec![],
+        );
+
+        let (session_token, _) = verifier
+            .new_session(
+                new_disclosure_request(),
+                SessionType::CrossDevice,
+                DISCLOSURE_USECASE.to_string(),
+                None,
+            )
+            .await
+            .unwrap();
+
+        // A device engagement claiming to have received the engagement through a universal link is rejected
+        // for a session that was started for a QR code, without sending the device request.
+        let (device_engagement, _) =
+            DeviceEngagement::new_device_engagement(SessionType::SameDevice, "https://example.com/".parse().unwrap())
+                .unwrap();
+        let response = verifier
+            .process_message(&cbor_serialize(&device_engagement).unwrap(), session_token.clone())
+            .await
+            .unwrap();
+
+        assert_matches!(
+            response,
+            SessionData {
+                data: None,
+                status: Some(SessionStatus::DecodingError)
+            }
+        );
+        assert_matches!(verifier.status(&session_token).await.unwrap(), StatusResponse::Failed);
+        assert_matches!(
+            verifier.session_result(&session_token).await.unwrap(),
+            Some(SessionResult::Failed { error }) if error.contains("session type mismatch")
+        );
     }
 
     fn example_items_requests() -> ItemsRequests {
@@ -1311,4 +2197,29 @@ mod tests {
         let missing = vec![attribute_identifiers(&items_requests).last().unwrap().clone()];
         (device_response, items_requests, Err(missing))
     }
+
+    #[tokio::test]
+    async fn reissuance_request() {
+        let trust_anchors = Examples::iaca_trust_anchors();
+        let mdoc = mock::mdoc_from_example_device_response(trust_anchors);
+
+        let request = mdoc
+            .reissuance_request(b"challenge".to_vec(), &mock::SoftwareKeyFactory::default())
+            .await
+            .unwrap();
+
+        let (attrs, mso) = request.verify(&IsoCertTimeGenerator, trust_anchors).unwrap();
+        assert_eq!(mso.doc_type, EXAMPLE_DOC_TYPE);
+        assert!(attrs[EXAMPLE_NAMESPACE]
+            .iter()
+            .any(|entry| entry.name == EXAMPLE_ATTR_NAME && entry.value == *EXAMPLE_ATTR_VALUE));
+
+        // The signature does not verify against another challenge.
+        let mut other_request = request.clone();
+        other_request.challenge = ByteBuf::from(b"other_challenge".to_vec());
+        assert_matches!(
+            other_request.verify(&IsoCertTimeGenerator, trust_anchors),
+            Err(Error::Cose(_))
+        );
+    }
 }
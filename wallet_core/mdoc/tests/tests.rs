@@ -34,6 +34,7 @@ use nl_wallet_mdoc::{
     },
     verifier::{DisclosureData, SessionType, Verifier},
 };
+use wallet_common::generator::TimeGenerator;
 use webpki::TrustAnchor;
 
 const ISSUANCE_DOC_TYPE: &str = "example_doctype";
@@ -259,7 +260,11 @@ where
     }
 
     let mdocs = wallet
-        .finish_issuance(&[ca.try_into().unwrap()], &SoftwareKeyFactory::default())
+        .finish_issuance(
+            &[ca.try_into().unwrap()],
+            &SoftwareKeyFactory::default(),
+            &TimeGenerator,
+        )
         .await
         .expect("finishing issuance on the Wallet should succeed");
 
@@ -324,6 +329,33 @@ async fn test_issuance() {
     );
 }
 
+#[tokio::test]
+async fn test_issuance_resend_response() {
+    let (mut wallet, server, ca) = setup_issuance_test();
+    let (_, other_ca) = mock::generate_issuance_key_and_ca().unwrap();
+    let key_factory = SoftwareKeyFactory::default();
+
+    let service_engagement = server.new_session(new_issuance_request()).await.unwrap();
+    wallet.start_issuance(service_engagement).await.unwrap();
+
+    // Finishing issuance with the wrong trust anchor fails after the issuer has issued the mdocs.
+    wallet
+        .finish_issuance(&[(&other_ca).try_into().unwrap()], &key_factory, &TimeGenerator)
+        .await
+        .expect_err("finishing issuance with the wrong trust anchor should fail");
+    assert!(wallet.has_issuance_session());
+
+    // Retrying should resend the same responses, for which the issuer returns the same mdocs.
+    let mdocs = wallet
+        .finish_issuance(&[(&ca).try_into().unwrap()], &key_factory, &TimeGenerator)
+        .await
+        .expect("retrying to finish issuance on the Wallet should succeed");
+
+    assert_eq!(1, mdocs.len());
+    assert_eq!(mdocs.first().unwrap().cred_copies.len(), 2);
+    assert!(!wallet.has_issuance_session());
+}
+
 #[rstest]
 #[case(SessionType::SameDevice, None)]
 #[case(SessionType::SameDevice, Some("http://example.com/return_url".parse().unwrap()))]
@@ -357,7 +389,12 @@ async fn test_issuance_and_disclosure(#[case] session_type: SessionType, #[case]
     .into();
 
     let (session_id, reader_engagement) = verifier
-        .new_session(items_requests, session_type, Default::default(), return_url.is_some())
+        .new_session(
+            items_requests,
+            session_type,
+            Default::default(),
+            return_url.as_ref().map(Url::to_string),
+        )
         .await
         .expect("creating new verifier session should succeed");
 
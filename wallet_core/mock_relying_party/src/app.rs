@@ -47,7 +47,10 @@ struct ApplicationState {
 
 pub async fn create_router(settings: Settings) -> anyhow::Result<Router> {
     let application_state = Arc::new(ApplicationState {
-        client: WalletServerClient::new(settings.wallet_server_url.clone()),
+        client: WalletServerClient::new(
+            settings.wallet_server_url.clone(),
+            settings.wallet_server_api_key.as_deref(),
+        ),
         public_url: settings.public_url,
         usecases: settings.usecases,
     });
@@ -1,4 +1,7 @@
-use reqwest::Client;
+use reqwest::{
+    header::{self, HeaderMap, HeaderValue},
+    Client,
+};
 use url::Url;
 
 use nl_wallet_mdoc::{
@@ -13,9 +16,22 @@ pub struct WalletServerClient {
 }
 
 impl WalletServerClient {
-    pub fn new(base_url: Url) -> Self {
+    /// Create a new [`WalletServerClient`]. When the requester API of the wallet server requires an API key, it is
+    /// sent as bearer token with every request.
+    pub fn new(base_url: Url, api_key: Option<&str>) -> Self {
+        let mut headers = HeaderMap::new();
+        if let Some(api_key) = api_key {
+            let mut value =
+                HeaderValue::from_str(&format!("Bearer {api_key}")).expect("API key should be a valid header");
+            value.set_sensitive(true);
+            headers.insert(header::AUTHORIZATION, value);
+        }
+
         Self {
-            client: reqwest::Client::new(),
+            client: reqwest::Client::builder()
+                .default_headers(headers)
+                .build()
+                .expect("could not build wallet server client"),
             base_url,
         }
     }
@@ -36,6 +52,7 @@ impl WalletServerClient {
                 items_requests,
                 session_type,
                 return_url_template,
+                callback_url: None,
             })
             .send()
             .await?
@@ -9,6 +9,8 @@ use url::Url;
 pub struct Settings {
     pub webserver: Server,
     pub wallet_server_url: Url,
+    // API key of the requester API of the wallet server, if required
+    pub wallet_server_api_key: Option<String>,
     pub public_url: Url,
     pub usecases: HashMap<String, ItemsRequests>,
 }
@@ -1,28 +1,59 @@
-use std::sync::Arc;
+use std::{collections::HashMap, net::SocketAddr, sync::Arc};
 
 use axum::{
     body::Bytes,
-    extract::{Path, State},
+    extract::{ConnectInfo, Path, State},
     headers::{authorization::Bearer, Authorization},
+    middleware,
     response::{IntoResponse, Response},
     routing::{get, post},
-    Json, Router, TypedHeader,
+    Form, Json, Router, TypedHeader,
 };
 use base64::prelude::*;
+use chrono::Utc;
 use futures::TryFutureExt;
-use http::StatusCode;
+use http::{header, HeaderMap, HeaderValue, StatusCode};
+use indexmap::IndexMap;
+use metrics::{counter, increment_counter};
+use openid::error as openid_errors;
+use serde::{Deserialize, Serialize};
 use tower_http::trace::TraceLayer;
 use tracing::{debug, error, warn};
 
 use nl_wallet_mdoc::{
-    basic_sa_ext::UnsignedMdoc,
+    basic_sa_ext::{ReissuanceRequest, UnsignedMdoc},
     issuer::{IssuanceData, Issuer},
-    server_keys::{PrivateKey, SingleKeyRing},
+    issuer_shared::IssuanceError,
     server_state::MemorySessionStore,
+    utils::serialization::{cbor_deserialize, cbor_serialize},
     ServiceEngagement,
 };
+use wallet_common::{
+    api_key::require_api_key,
+    correlation::track_correlation_id,
+    issuer_error::{IssuerErrorCode, IssuerErrorData},
+    metrics::track_http_metrics,
+    utils::sha256,
+};
+
+use crate::{
+    attributes::{Attributes, DocTypes, MappingError},
+    audit_log::{AuditAction, AuditEvent, AuditLog, AuditLogError, AuditQuery},
+    brp,
+    digid::{self, DigidUser},
+    health::{HealthChecks, HealthResponse, HealthStatus},
+    keys::{IssuerKeyRing, KeyStatus, RotatingKey},
+    openid4vci::{
+        AuthorizationServerMetadata, CredentialIssuerMetadata, CredentialOffer, CredentialRequest, CredentialResponse,
+        OpenId4VciError, OpenId4VciIssuer, TokenRequest, TokenResponse,
+    },
+    rate_limits::{RateLimitExceeded, RateLimiter},
+    reissuance::{Reissuance, ReissuanceError},
+    settings::{DocTypeMapping, Settings},
+    status_lists::{RegistryError, RevokedMdoc, StatusRegistry},
+};
 
-use crate::{digid, settings::Settings};
+const APPLICATION_PROBLEM_JSON: &str = "application/problem+json";
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -34,38 +65,116 @@ pub enum Error {
     NoAttributesFound,
     #[error("mdoc session error: {0}")]
     Mdoc(#[source] nl_wallet_mdoc::Error),
+    #[error("BRP error: {0}")]
+    Brp(#[from] brp::Error),
+    #[error("could not map attributes: {0}")]
+    Mapping(#[from] MappingError),
+    #[error("status list not found")]
+    StatusListNotFound,
+    #[error("status list registry error: {0}")]
+    Registry(#[from] RegistryError),
+    #[error(transparent)]
+    RateLimited(#[from] RateLimitExceeded),
+    #[error("re-issuance error: {0}")]
+    Reissuance(#[from] ReissuanceError),
+    #[error("audit log error: {0}")]
+    AuditLog(#[from] AuditLogError),
+    #[error("none of the doc types are granted by the scopes of the user")]
+    NoDocTypesGranted,
+}
+
+impl Error {
+    fn code(&self) -> IssuerErrorCode {
+        match self {
+            Error::Digid(digid::Error::OpenId(openid_errors::Error::Http(_)))
+            | Error::Digid(digid::Error::OpenIdClient(openid_errors::ClientError::Reqwest(_))) => {
+                IssuerErrorCode::DigidUnavailable
+            }
+            Error::Digid(digid::Error::OpenIdClient(openid_errors::ClientError::OAuth2(_))) => {
+                IssuerErrorCode::DigidAccessTokenRejected
+            }
+            Error::Digid(digid::Error::NoBSN) => IssuerErrorCode::DigidNoBsn,
+            Error::NoAttributesFound => IssuerErrorCode::PersonNotFound,
+            Error::Brp(brp::Error::IncompletePerson(_)) => IssuerErrorCode::IncompletePerson,
+            Error::Brp(_) => IssuerErrorCode::AttributesUnavailable,
+            Error::StartMdoc(nl_wallet_mdoc::Error::Issuance(
+                IssuanceError::UnknownSessionId(_) | IssuanceError::SessionEnded,
+            ))
+            | Error::Mdoc(nl_wallet_mdoc::Error::Issuance(
+                IssuanceError::UnknownSessionId(_) | IssuanceError::SessionEnded,
+            )) => IssuerErrorCode::SessionState,
+            Error::StatusListNotFound => IssuerErrorCode::StatusListNotFound,
+            Error::RateLimited(_) => IssuerErrorCode::RateLimited,
+            Error::Reissuance(_) => IssuerErrorCode::ReissuanceRejected,
+            Error::NoDocTypesGranted => IssuerErrorCode::NoDocTypesGranted,
+            Error::Digid(_)
+            | Error::StartMdoc(_)
+            | Error::Mdoc(_)
+            | Error::Mapping(_)
+            | Error::Registry(_)
+            | Error::AuditLog(_) => IssuerErrorCode::Unexpected,
+        }
+    }
 }
 
-// TODO: Implement proper error handling.
+/// Errors are returned as [`IssuerErrorData`] JSON, so that the wallet can distinguish between them.
 impl IntoResponse for Error {
     fn into_response(self) -> Response {
         warn!("error result: {:?}", self);
 
-        let status_code = match self {
-            Error::NoAttributesFound => StatusCode::NOT_FOUND,
-            _ => StatusCode::INTERNAL_SERVER_ERROR,
-        };
+        let code = self.code();
+        let body = IssuerErrorData::new(code, self.to_string());
+
+        let mut response = (
+            StatusCode::from(code),
+            [(header::CONTENT_TYPE, APPLICATION_PROBLEM_JSON)],
+            serde_json::to_vec(&body).expect("Could not encode IssuerErrorData to JSON"),
+        )
+            .into_response();
+
+        // Also communicate the time after which to retry in the standard header, which is specified in seconds.
+        if let Error::RateLimited(error) = &self {
+            response.headers_mut().insert(
+                header::RETRY_AFTER,
+                HeaderValue::from(error.retry_after.as_millis().div_ceil(1000) as u64),
+            );
+        }
 
-        (status_code, format!("{}", self)).into_response()
+        response
     }
 }
 
-/// Given a BSN, determine the attributes to be issued. Contract for the BRP query.
-pub trait AttributesLookup {
-    fn attributes(&self, bsn: &str) -> Option<Vec<UnsignedMdoc>>;
+/// Given a BSN, determine the attributes of the person, from which the mdocs to be issued are created. Contract for
+/// the BRP query, which returns `None` if no person is registered with the BSN.
+#[trait_variant::make(AttributesLookup: Send)]
+pub trait LocalAttributesLookup {
+    async fn attributes(&self, bsn: &str) -> Result<Option<Attributes>, brp::Error>;
+
+    /// Check that the attributes can be looked up, without looking up those of a person.
+    async fn check_health(&self) -> Result<(), brp::Error>;
 }
 
-/// Given an access token, lookup a BSN: a trait modeling the OIDC [`Client`](crate::openid::Client).
-/// Contract for the DigiD bridge.
+/// Given an access token, lookup a BSN and the granted scopes: a trait modeling the OIDC
+/// [`Client`](crate::openid::Client). Contract for the DigiD bridge.
 #[trait_variant::make(BsnLookup: Send)]
 pub trait LocalBsnLookup {
-    async fn bsn(&self, access_token: &str) -> Result<String, digid::Error>;
+    async fn user(&self, access_token: &str) -> Result<DigidUser, digid::Error>;
+
+    /// Check that BSNs can be looked up, without an access token.
+    async fn check_health(&self) -> Result<(), digid::Error>;
 }
 
 struct ApplicationState<A, B> {
     attributes_lookup: A,
     openid_client: B,
-    issuer: Issuer<SingleKeyRing, MemorySessionStore<IssuanceData>>,
+    doc_types: DocTypes,
+    status_registry: Option<Arc<StatusRegistry>>,
+    rate_limiter: Option<RateLimiter>,
+    reissuance: Option<Reissuance>,
+    openid4vci: Option<OpenId4VciIssuer>,
+    audit_log: Option<Arc<AuditLog>>,
+    health_checks: Option<HealthChecks>,
+    issuer: Issuer<IssuerKeyRing, MemorySessionStore<IssuanceData>>,
 }
 
 pub async fn create_router<A, B>(settings: Settings, attributes_lookup: A, openid_client: B) -> anyhow::Result<Router>
@@ -75,10 +184,25 @@ where
 {
     debug!("DigiD issuer discovered, starting HTTP server");
 
-    let key = SingleKeyRing(PrivateKey::from_der(
-        &BASE64_STANDARD.decode(&settings.issuer_key.private_key)?,
-        &BASE64_STANDARD.decode(&settings.issuer_key.certificate)?,
-    )?);
+    let default_keys = std::iter::once(&settings.issuer_key)
+        .chain(&settings.additional_issuer_keys)
+        .map(RotatingKey::try_from)
+        .collect::<Result<_, _>>()?;
+    let mut doc_type_keys: HashMap<_, Vec<_>> = HashMap::new();
+    for doc_type_key in &settings.doc_type_issuer_keys {
+        doc_type_keys
+            .entry(doc_type_key.doc_type.clone())
+            .or_default()
+            .push(RotatingKey::try_from(&doc_type_key.key)?);
+    }
+    let key_ring = IssuerKeyRing::new(default_keys, doc_type_keys);
+
+    let doc_types = DocTypes::try_new(DocTypeMapping::load(settings.doc_types_path.as_deref())?)?;
+    let reissuance = settings
+        .reissuance
+        .as_ref()
+        .map(|reissuance| Reissuance::try_new(reissuance, &doc_types))
+        .transpose()?;
 
     let mut public_url = settings.public_url;
     if !public_url.as_str().ends_with('/') {
@@ -88,24 +212,152 @@ where
         // are not followed by a `/` slash".
         public_url.path_segments_mut().unwrap().push("/");
     }
-    let public_url = public_url.join("mdoc/")?;
 
+    let status_registry = match &settings.status_lists {
+        Some(status_lists) => Some(Arc::new(StatusRegistry::open(
+            status_lists,
+            public_url.join("status_lists/")?,
+        )?)),
+        None => None,
+    };
+
+    let openid4vci = settings
+        .openid4vci
+        .as_ref()
+        .map(|openid4vci| OpenId4VciIssuer::new(openid4vci, public_url.clone()));
+
+    let mut issuer = Issuer::new(public_url.join("mdoc/")?, key_ring, MemorySessionStore::new());
+    if let Some(max_concurrent_signatures) = settings.max_concurrent_signatures {
+        issuer = issuer.with_max_concurrent_signatures(max_concurrent_signatures);
+    }
+    if let Some(status_registry) = &status_registry {
+        issuer = issuer.with_status_lists(Arc::clone(status_registry) as _);
+    }
+
+    let audit_log = settings
+        .audit_log
+        .as_ref()
+        .map(|audit_log| AuditLog::open(audit_log).map(Arc::new))
+        .transpose()?;
+    if let Some(audit_log) = &audit_log {
+        issuer = issuer.with_observer(Arc::clone(audit_log) as _);
+    }
+
+    let status_lists_enabled = status_registry.is_some();
+    let reissuance_enabled = reissuance.is_some();
+    let openid4vci_enabled = openid4vci.is_some();
+    let audit_log_enabled = audit_log.is_some();
+    let health_checks = settings
+        .health_checks
+        .as_ref()
+        .map(|health_checks| HealthChecks::new(health_checks, settings.brp.is_some()));
+    let health_checks_enabled = health_checks.is_some();
     let application_state = Arc::new(ApplicationState {
         attributes_lookup,
         openid_client,
-        issuer: Issuer::new(public_url, key, MemorySessionStore::new()),
+        doc_types,
+        status_registry,
+        rate_limiter: settings.rate_limits.as_ref().map(RateLimiter::new),
+        reissuance,
+        openid4vci,
+        audit_log,
+        health_checks,
+        issuer,
     });
 
-    let app = Router::new()
-        .route("/health", get(|| async {}))
+    let mut app = Router::new()
         .route("/mdoc/:session_token", post(mdoc_route))
         .route("/start", post(start_route))
+        .route("/issuer/certificates", get(certificates_route));
+    if health_checks_enabled {
+        app = app.route("/health", get(health_route));
+    } else {
+        app = app.route("/health", get(|| async {}));
+    }
+    if status_lists_enabled {
+        app = app.route("/status_lists/:doc_type/:certificate", get(status_list_route));
+    }
+    if reissuance_enabled {
+        app = app
+            .route("/reissue/challenge", post(reissuance_challenge_route))
+            .route("/reissue", post(reissue_route));
+    }
+    if openid4vci_enabled {
+        app = app
+            .route(
+                "/.well-known/openid-credential-issuer",
+                get(openid4vci_issuer_metadata_route),
+            )
+            .route(
+                "/.well-known/oauth-authorization-server",
+                get(openid4vci_authorization_server_metadata_route),
+            )
+            .route("/openid4vci/credential_offer", post(credential_offer_route))
+            .route("/openid4vci/token", post(openid4vci_token_route))
+            .route("/openid4vci/credential", post(openid4vci_credential_route));
+    }
+
+    if let Some(admin) = &settings.admin {
+        let api_key_digest = Arc::new(sha256(admin.api_key.as_bytes()));
+        let mut admin_router = Router::new().route("/admin/keys", get(keys_route));
+        if status_lists_enabled {
+            admin_router = admin_router.route("/admin/revocations", post(revoke_route));
+        }
+        if audit_log_enabled {
+            admin_router = admin_router.route("/admin/audit_log", post(audit_log_route));
+        }
+        let admin_router = admin_router.route_layer(middleware::from_fn_with_state(api_key_digest, require_api_key));
+
+        app = app.merge(admin_router);
+    }
+
+    let app = app
         .layer(TraceLayer::new_for_http())
+        .layer(middleware::from_fn(track_http_metrics))
+        .layer(middleware::from_fn(track_correlation_id))
         .with_state(application_state);
 
     Ok(app)
 }
 
+/// Reports whether DigiD, the BRP (when configured) and the signing keys are available, responding with 503 Service
+/// Unavailable if any of them is not, so that it can be used as readiness probe.
+async fn health_route<A, B>(State(state): State<Arc<ApplicationState<A, B>>>) -> (StatusCode, Json<HealthResponse>)
+where
+    A: LocalAttributesLookup,
+    B: LocalBsnLookup,
+{
+    let health_checks = state
+        .health_checks
+        .as_ref()
+        .expect("route should only exist when health checks are configured");
+
+    let digid = health_checks.check("DigiD", state.openid_client.check_health());
+    let brp = async {
+        if health_checks.brp_enabled {
+            Some(health_checks.check("BRP", state.attributes_lookup.check_health()).await)
+        } else {
+            None
+        }
+    };
+    let signing_keys = health_checks.check("signing keys", state.issuer.keys().check_signing_keys(Utc::now()));
+    let (digid, brp, signing_keys) = futures::join!(digid, brp, signing_keys);
+
+    let mut dependencies = IndexMap::from([("digid", digid)]);
+    if let Some(brp) = brp {
+        dependencies.insert("brp", brp);
+    }
+    dependencies.insert("signing_keys", signing_keys);
+
+    let response = HealthResponse::new(dependencies);
+    let status_code = match response.status {
+        HealthStatus::Ok => StatusCode::OK,
+        HealthStatus::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+    };
+
+    (status_code, Json(response))
+}
+
 async fn mdoc_route<A, B>(
     State(state): State<Arc<ApplicationState<A, B>>>,
     Path(session_token): Path<String>,
@@ -121,27 +373,391 @@ async fn mdoc_route<A, B>(
 
 async fn start_route<A, B>(
     State(state): State<Arc<ApplicationState<A, B>>>,
+    headers: HeaderMap,
+    peer: Option<ConnectInfo<SocketAddr>>,
     TypedHeader(authorization_header): TypedHeader<Authorization<Bearer>>,
 ) -> Result<Json<ServiceEngagement>, Error>
 where
-    A: AttributesLookup,
+    A: LocalAttributesLookup,
     B: LocalBsnLookup,
 {
+    // Reject clients that attempt issuance too often before involving DigiD
+    state.check_ip_rate_limit(&headers, peer)?;
+
     // Using the access_token that the user specified, lookup the user's BSN at the OIDC IdP (DigiD bridge)
     let access_token = authorization_header.token();
-    let bsn: String = state
+    let DigidUser { bsn, scopes } = state
         .openid_client
-        .bsn(access_token)
+        .user(access_token)
         .inspect_err(|error| error!("error while looking up BSN: {}", error))
         .await?;
 
-    // Start the session, and return the initial mdoc protocol message (containing the URL at which the wallet can
-    // find us) to the wallet
-    let attributes = state
-        .attributes_lookup
-        .attributes(&bsn)
-        .ok_or(Error::NoAttributesFound)?;
-    let service_engagement = state.issuer.new_session(attributes).map_err(Error::StartMdoc).await?;
+    state.check_bsn_rate_limit(&bsn)?;
+
+    let service_engagement = state.start_session(bsn, &scopes, AuditAction::IssuanceStarted).await?;
+
+    increment_counter!("pid_issuer_issuance_sessions_started_total");
+
+    Ok(Json(service_engagement))
+}
+
+impl<A, B> ApplicationState<A, B> {
+    fn check_ip_rate_limit(&self, headers: &HeaderMap, peer: Option<ConnectInfo<SocketAddr>>) -> Result<(), Error> {
+        if let Some(rate_limiter) = &self.rate_limiter {
+            let ip = rate_limiter.client_ip(headers, peer.map(|ConnectInfo(peer)| peer));
+            rate_limiter.check_ip(ip).map_err(|error| {
+                warn!("rejecting issuance attempt: {}", error);
+                error
+            })?;
+        }
+
+        Ok(())
+    }
+
+    fn check_bsn_rate_limit(&self, bsn: &str) -> Result<(), Error> {
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter.check_bsn(bsn).map_err(|error| {
+                warn!("rejecting issuance attempt: {}", error);
+                error
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Create the mdocs to be issued to the person with the specified BSN from their attributes, of the doc types that
+    /// are granted by `scopes`.
+    async fn unsigned_mdocs(&self, bsn: &str, scopes: &[String]) -> Result<Vec<UnsignedMdoc>, Error>
+    where
+        A: LocalAttributesLookup,
+    {
+        let attributes = self
+            .attributes_lookup
+            .attributes(bsn)
+            .inspect_err(|error| error!("error while looking up attributes: {}", error))
+            .await?
+            .ok_or(Error::NoAttributesFound)?;
+        let unsigned_mdocs = self.doc_types.unsigned_mdocs(attributes, scopes)?;
+        if unsigned_mdocs.is_empty() {
+            return Err(Error::NoDocTypesGranted);
+        }
+
+        Ok(unsigned_mdocs)
+    }
+
+    /// Record the start of issuance of `unsigned_mdocs` in the audit log, if configured. Issuance does not proceed
+    /// when this fails.
+    fn audit(&self, action: AuditAction, bsn: &str, unsigned_mdocs: &[UnsignedMdoc]) -> Result<(), Error> {
+        if let Some(audit_log) = &self.audit_log {
+            let doc_types = unsigned_mdocs
+                .iter()
+                .map(|unsigned_mdoc| unsigned_mdoc.doc_type.clone())
+                .collect();
+            audit_log.record(action, Some(bsn), doc_types)?;
+        }
+
+        Ok(())
+    }
+
+    /// Start the session, and return the initial mdoc protocol message (containing the URL at which the wallet can
+    /// find us) to the wallet
+    async fn start_session(
+        &self,
+        bsn: String,
+        scopes: &[String],
+        action: AuditAction,
+    ) -> Result<ServiceEngagement, Error>
+    where
+        A: LocalAttributesLookup,
+    {
+        let unsigned_mdocs = self.unsigned_mdocs(&bsn, scopes).await?;
+        self.audit(action, &bsn, &unsigned_mdocs)?;
+        let service_engagement = self
+            .issuer
+            .new_session_with_subject(unsigned_mdocs, bsn)
+            .map_err(Error::StartMdoc)
+            .await?;
+
+        Ok(service_engagement)
+    }
+}
+
+#[derive(Serialize)]
+struct ChallengeResponse {
+    /// URL-safe Base64 encoded challenge, without padding.
+    challenge: String,
+}
+
+async fn reissuance_challenge_route<A, B>(State(state): State<Arc<ApplicationState<A, B>>>) -> Json<ChallengeResponse> {
+    let reissuance = state
+        .reissuance
+        .as_ref()
+        .expect("route should only exist when re-issuance is configured");
+    let challenge = BASE64_URL_SAFE_NO_PAD.encode(reissuance.new_challenge());
+
+    Json(ChallengeResponse { challenge })
+}
+
+/// Starts an issuance session for a holder that proves possession of a valid mdoc, using the BSN in that mdoc instead
+/// of the one provided by DigiD. The body is a CBOR encoded [`ReissuanceRequest`].
+async fn reissue_route<A, B>(
+    State(state): State<Arc<ApplicationState<A, B>>>,
+    headers: HeaderMap,
+    peer: Option<ConnectInfo<SocketAddr>>,
+    msg: Bytes,
+) -> Result<Json<ServiceEngagement>, Error>
+where
+    A: LocalAttributesLookup,
+{
+    let reissuance = state
+        .reissuance
+        .as_ref()
+        .expect("route should only exist when re-issuance is configured");
+
+    state.check_ip_rate_limit(&headers, peer)?;
+
+    let request: ReissuanceRequest =
+        cbor_deserialize(msg.as_ref()).map_err(|error| ReissuanceError::Decoding(error.into()))?;
+    let bsn = reissuance
+        .verify(&request, state.issuer.keys(), state.status_registry.as_deref())
+        .map_err(|error| {
+            warn!("rejecting re-issuance request: {}", error);
+            error
+        })?;
+
+    state.check_bsn_rate_limit(&bsn)?;
+
+    // The holder is granted the scopes with which the presented mdoc was issued, so that re-issuance does not yield
+    // doc types that were not issued before.
+    let scopes = state.doc_types.scopes(reissuance.doc_type());
+    let service_engagement = state.start_session(bsn, scopes, AuditAction::ReissuanceStarted).await?;
+
+    increment_counter!("pid_issuer_reissuance_sessions_started_total");
 
     Ok(Json(service_engagement))
 }
+
+/// Creates an OpenID4VCI credential offer for the user authenticated by DigiD, in the same way as an issuance session
+/// is started by [`start_route`].
+async fn credential_offer_route<A, B>(
+    State(state): State<Arc<ApplicationState<A, B>>>,
+    headers: HeaderMap,
+    peer: Option<ConnectInfo<SocketAddr>>,
+    TypedHeader(authorization_header): TypedHeader<Authorization<Bearer>>,
+) -> Result<Json<CredentialOffer>, Error>
+where
+    A: LocalAttributesLookup,
+    B: LocalBsnLookup,
+{
+    let openid4vci = state
+        .openid4vci
+        .as_ref()
+        .expect("route should only exist when OpenID4VCI is configured");
+
+    state.check_ip_rate_limit(&headers, peer)?;
+
+    let DigidUser { bsn, scopes } = state
+        .openid_client
+        .user(authorization_header.token())
+        .inspect_err(|error| error!("error while looking up BSN: {}", error))
+        .await?;
+
+    state.check_bsn_rate_limit(&bsn)?;
+
+    let unsigned_mdocs = state.unsigned_mdocs(&bsn, &scopes).await?;
+    state.audit(AuditAction::CredentialOfferCreated, &bsn, &unsigned_mdocs)?;
+    let offer = openid4vci.new_offer(bsn, unsigned_mdocs);
+
+    increment_counter!("pid_issuer_openid4vci_offers_created_total");
+
+    Ok(Json(offer))
+}
+
+async fn openid4vci_issuer_metadata_route<A, B>(
+    State(state): State<Arc<ApplicationState<A, B>>>,
+) -> Json<CredentialIssuerMetadata> {
+    let openid4vci = state
+        .openid4vci
+        .as_ref()
+        .expect("route should only exist when OpenID4VCI is configured");
+
+    Json(openid4vci.issuer_metadata(state.doc_types.doc_types()))
+}
+
+async fn openid4vci_authorization_server_metadata_route<A, B>(
+    State(state): State<Arc<ApplicationState<A, B>>>,
+) -> Json<AuthorizationServerMetadata> {
+    let openid4vci = state
+        .openid4vci
+        .as_ref()
+        .expect("route should only exist when OpenID4VCI is configured");
+
+    Json(openid4vci.authorization_server_metadata())
+}
+
+async fn openid4vci_token_route<A, B>(
+    State(state): State<Arc<ApplicationState<A, B>>>,
+    Form(request): Form<TokenRequest>,
+) -> Result<Json<TokenResponse>, OpenId4VciError> {
+    let openid4vci = state
+        .openid4vci
+        .as_ref()
+        .expect("route should only exist when OpenID4VCI is configured");
+
+    let response = openid4vci.token(request).map_err(|error| {
+        warn!("rejecting OpenID4VCI token request: {}", error);
+        error
+    })?;
+
+    Ok(Json(response))
+}
+
+async fn openid4vci_credential_route<A, B>(
+    State(state): State<Arc<ApplicationState<A, B>>>,
+    TypedHeader(authorization_header): TypedHeader<Authorization<Bearer>>,
+    Json(request): Json<CredentialRequest>,
+) -> Result<Json<CredentialResponse>, OpenId4VciError> {
+    let openid4vci = state
+        .openid4vci
+        .as_ref()
+        .expect("route should only exist when OpenID4VCI is configured");
+
+    let response = openid4vci
+        .credential(
+            authorization_header.token(),
+            request,
+            state.issuer.keys(),
+            state.status_registry.as_deref().map(|registry| registry as _),
+            state.audit_log.as_deref().map(|audit_log| audit_log as _),
+        )
+        .inspect_err(|error| warn!("rejecting OpenID4VCI credential request: {}", error))
+        .await?;
+
+    increment_counter!("pid_issuer_openid4vci_credentials_issued_total");
+
+    Ok(Json(response))
+}
+
+#[derive(Serialize)]
+struct CertificatesResponse {
+    /// Base64 encoded DER certificates.
+    certificates: Vec<String>,
+}
+
+/// The certificates that relying parties should trust, which includes those of retired keys until they expire.
+async fn certificates_route<A, B>(State(state): State<Arc<ApplicationState<A, B>>>) -> Json<CertificatesResponse> {
+    let certificates = state
+        .issuer
+        .keys()
+        .trusted_certificates(Utc::now())
+        .into_iter()
+        .map(|certificate| BASE64_STANDARD.encode(certificate.as_bytes()))
+        .collect();
+
+    Json(CertificatesResponse { certificates })
+}
+
+async fn keys_route<A, B>(State(state): State<Arc<ApplicationState<A, B>>>) -> Json<Vec<KeyStatus>> {
+    Json(state.issuer.keys().key_statuses(Utc::now()))
+}
+
+/// Serves the status list of the mdocs of the specified doc type that were signed with the certificate with the
+/// specified fingerprint, signed using that same certificate.
+async fn status_list_route<A, B>(
+    State(state): State<Arc<ApplicationState<A, B>>>,
+    Path((doc_type, certificate)): Path<(String, String)>,
+) -> Result<Vec<u8>, Error> {
+    let status_registry = state.status_registry.as_ref().ok_or(Error::StatusListNotFound)?;
+    let key = state
+        .issuer
+        .keys()
+        .key_by_fingerprint(&certificate)
+        .ok_or(Error::StatusListNotFound)?;
+
+    let token = status_registry
+        .token(&doc_type, &certificate, key)
+        .await
+        .map_err(Error::Mdoc)?
+        .ok_or(Error::StatusListNotFound)?;
+
+    let response = cbor_serialize(&token).map_err(|error| Error::Mdoc(error.into()))?;
+
+    Ok(response)
+}
+
+#[derive(Deserialize)]
+struct RevocationRequest {
+    bsn: String,
+    reason: Option<String>,
+}
+
+#[derive(Serialize)]
+struct RevocationResponse {
+    revoked: Vec<RevokedMdoc>,
+}
+
+#[derive(Serialize)]
+struct AuditLogResponse {
+    events: Vec<AuditEvent>,
+}
+
+/// Revokes all mdocs that were issued to the person with the specified BSN, e.g. after their death or in case of
+/// identity fraud.
+async fn revoke_route<A, B>(
+    State(state): State<Arc<ApplicationState<A, B>>>,
+    Json(request): Json<RevocationRequest>,
+) -> Result<Json<RevocationResponse>, Error> {
+    let status_registry = state.status_registry.as_ref().ok_or(Error::StatusListNotFound)?;
+    let revoked = status_registry.revoke_subject(&request.bsn, request.reason)?;
+
+    if let Some(audit_log) = &state.audit_log {
+        let doc_types = revoked.iter().map(|mdoc| mdoc.doc_type.clone()).collect();
+        audit_log.record(AuditAction::Revoked, Some(&request.bsn), doc_types)?;
+    }
+
+    counter!("pid_issuer_revoked_mdocs_total", revoked.len() as u64);
+
+    Ok(Json(RevocationResponse { revoked }))
+}
+
+/// Queries the audit log. The BSN to query for is passed in the body rather than in the URL, so that it does not end up
+/// in the logs of proxies.
+async fn audit_log_route<A, B>(
+    State(state): State<Arc<ApplicationState<A, B>>>,
+    Json(query): Json<AuditQuery>,
+) -> Result<Json<AuditLogResponse>, Error> {
+    let audit_log = state
+        .audit_log
+        .as_ref()
+        .expect("route should only exist when the audit log is configured");
+    let events = audit_log.query(&query)?;
+
+    Ok(Json(AuditLogResponse { events }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reqwest_error() -> reqwest::Error {
+        // An invalid URL results in an error without sending a request.
+        reqwest::Client::new()
+            .get("http://[::1")
+            .build()
+            .expect_err("building request should fail")
+    }
+
+    #[test]
+    fn test_error_code_digid_unavailable() {
+        let errors = [
+            Error::Digid(digid::Error::OpenId(openid_errors::Error::Http(reqwest_error()))),
+            Error::Digid(digid::Error::OpenIdClient(openid_errors::ClientError::Reqwest(
+                reqwest_error(),
+            ))),
+        ];
+
+        for error in errors {
+            assert_eq!(error.code(), IssuerErrorCode::DigidUnavailable);
+        }
+    }
+}
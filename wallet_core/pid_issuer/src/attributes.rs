@@ -0,0 +1,390 @@
+//! The attributes of a person, regardless of where these are retrieved from, and the creation of the mdocs to be
+//! issued from these according to the configured doc type mapping.
+use std::ops::Add;
+
+use chrono::{Days, Utc};
+use ciborium::Value;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+use nl_wallet_mdoc::{
+    basic_sa_ext::{Entry, UnsignedMdoc},
+    Tdate,
+};
+
+use crate::settings::{DocTypeMapping, Transform};
+
+// ISO/IEC 5218
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum Gender {
+    Unknown,
+    Male,
+    Female,
+    NotApplicable,
+}
+
+impl From<Gender> for Value {
+    fn from(value: Gender) -> Value {
+        use Gender::*;
+        let value = match value {
+            Unknown => 0,
+            Male => 1,
+            Female => 2,
+            NotApplicable => 9,
+        };
+        Value::Integer(value.into())
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct PersonAttributes {
+    pub bsn: String,
+    pub family_name: String,
+    pub given_name: String,
+    pub birth_date: chrono::NaiveDate,
+    pub age_over_18: bool,
+    // age_over_NN: Option<bool>,
+    // age_in_years: Option<u32>,
+    // age_birth_year: Option<u32>,
+    pub family_name_birth: Option<String>,
+    pub given_name_birth: Option<String>,
+    pub birth_place: Option<String>,
+    pub birth_country: Option<String>,
+    pub birth_state: Option<String>,
+    pub birth_city: Option<String>,
+    pub gender: Option<Gender>,
+    pub nationality: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct ResidentAttributes {
+    pub address: Option<String>,
+    pub country: Option<String>,
+    pub state: Option<String>,
+    pub city: Option<String>,
+    pub postal_code: Option<String>,
+    pub street: Option<String>,
+    pub house_number: Option<String>,
+}
+
+/// All attributes of a person, which are the source fields of the attributes that are issued.
+#[derive(Debug, Default, Serialize, Clone)]
+pub struct Attributes {
+    pub person: PersonAttributes,
+    pub resident: Option<ResidentAttributes>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum MappingError {
+    #[error("doc type {0} should be issued at least once and be valid for at least a day")]
+    InvalidIssuance(String),
+    #[error("attribute {attribute} of doc type {doc_type} has unknown source field {field}")]
+    UnknownSource {
+        doc_type: String,
+        attribute: String,
+        field: String,
+    },
+    #[error("value of source field {field} cannot be transformed using {transform:?}")]
+    InvalidValue { field: String, transform: Transform },
+}
+
+/// Look up the value of a source field, e.g. `person.given_name`, in the serialized attributes.
+fn source_value<'a>(attributes: &'a serde_json::Value, field: &str) -> Option<&'a serde_json::Value> {
+    field.split('.').try_fold(attributes, |value, key| {
+        value.as_object().and_then(|object| object.get(key))
+    })
+}
+
+fn transform_value(value: serde_json::Value, field: &str, transform: Transform) -> Result<Value, MappingError> {
+    let invalid_value = || MappingError::InvalidValue {
+        field: field.to_string(),
+        transform,
+    };
+
+    let value = match (transform, value) {
+        (Transform::None, serde_json::Value::String(text)) => Value::Text(text),
+        (Transform::None, serde_json::Value::Bool(bool)) => Value::Bool(bool),
+        (Transform::None, serde_json::Value::Number(number)) => {
+            Value::Integer(number.as_i64().ok_or_else(invalid_value)?.into())
+        }
+        (Transform::Uppercase, serde_json::Value::String(text)) => Value::Text(text.to_uppercase()),
+        (Transform::Lowercase, serde_json::Value::String(text)) => Value::Text(text.to_lowercase()),
+        (Transform::Iso5218, value) => serde_json::from_value::<Gender>(value)
+            .map_err(|_| invalid_value())?
+            .into(),
+        _ => return Err(invalid_value()),
+    };
+
+    Ok(value)
+}
+
+/// The doc types to be issued, each of which is created from the attributes of a person according to its mapping.
+#[derive(Debug)]
+pub struct DocTypes(Vec<DocTypeMapping>);
+
+impl DocTypes {
+    /// Checks that all attributes in the mappings refer to an existing source field.
+    pub fn try_new(mappings: Vec<DocTypeMapping>) -> Result<Self, MappingError> {
+        let all_fields = serde_json::to_value(Attributes {
+            person: PersonAttributes::default(),
+            resident: Some(ResidentAttributes::default()),
+        })
+        .expect("attributes should serialize");
+
+        for mapping in &mappings {
+            if mapping.copy_count == 0 || mapping.validity_in_days == 0 {
+                return Err(MappingError::InvalidIssuance(mapping.doc_type.clone()));
+            }
+
+            for attribute in &mapping.attributes {
+                if source_value(&all_fields, &attribute.source).is_none() {
+                    return Err(MappingError::UnknownSource {
+                        doc_type: mapping.doc_type.clone(),
+                        attribute: attribute.name.clone(),
+                        field: attribute.source.clone(),
+                    });
+                }
+            }
+        }
+
+        Ok(Self(mappings))
+    }
+
+    /// The doc types that are issued.
+    pub fn doc_types(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(|mapping| mapping.doc_type.as_str())
+    }
+
+    /// The scopes that grant issuance of `doc_type`, which are empty for doc types that are always issued.
+    pub fn scopes(&self, doc_type: &str) -> &[String] {
+        self.0
+            .iter()
+            .find(|mapping| mapping.doc_type == doc_type)
+            .map(|mapping| mapping.scopes.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// The namespace and name of the attribute of `doc_type` that takes its untransformed value from the source field
+    /// `source`, if any.
+    pub fn source_attribute(&self, doc_type: &str, source: &str) -> Option<(String, String)> {
+        let mapping = self.0.iter().find(|mapping| mapping.doc_type == doc_type)?;
+        let attribute = mapping
+            .attributes
+            .iter()
+            .find(|attribute| attribute.source == source && attribute.transform == Transform::None)?;
+        let namespace = mapping.namespace.as_ref().unwrap_or(&mapping.doc_type).clone();
+
+        Some((namespace, attribute.name.clone()))
+    }
+
+    /// Create the mdocs to be issued for a person, of the doc types that are granted by `granted_scopes`. Attributes of
+    /// which the source field has no value are left out, so a doc type may be issued without attributes, e.g. the
+    /// address when the residence of the person is unknown.
+    pub fn unsigned_mdocs(
+        &self,
+        attributes: Attributes,
+        granted_scopes: &[String],
+    ) -> Result<Vec<UnsignedMdoc>, MappingError> {
+        let attributes = serde_json::to_value(attributes).expect("attributes should serialize");
+
+        self.0
+            .iter()
+            .filter(|mapping| {
+                mapping.scopes.is_empty() || mapping.scopes.iter().any(|scope| granted_scopes.contains(scope))
+            })
+            .map(|mapping| {
+                let entries = mapping
+                    .attributes
+                    .iter()
+                    .filter_map(|attribute| {
+                        source_value(&attributes, &attribute.source)
+                            .filter(|value| !value.is_null())
+                            .map(|value| {
+                                transform_value(value.clone(), &attribute.source, attribute.transform).map(|value| {
+                                    Entry {
+                                        name: attribute.name.clone(),
+                                        value,
+                                    }
+                                })
+                            })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                let namespace = mapping.namespace.as_ref().unwrap_or(&mapping.doc_type).clone();
+
+                Ok(UnsignedMdoc {
+                    doc_type: mapping.doc_type.clone(),
+                    copy_count: mapping.copy_count,
+                    valid_from: Tdate::now(),
+                    valid_until: Utc::now().add(Days::new(mapping.validity_in_days)).into(),
+                    attributes: IndexMap::from([(namespace, entries)]),
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use chrono::{DateTime, NaiveDate};
+
+    use crate::settings::AttributeMapping;
+
+    use super::*;
+
+    fn attribute(name: &str, source: &str, transform: Transform) -> AttributeMapping {
+        AttributeMapping {
+            name: name.to_string(),
+            source: source.to_string(),
+            transform,
+        }
+    }
+
+    fn attributes() -> Attributes {
+        Attributes {
+            person: PersonAttributes {
+                bsn: "999991772".to_string(),
+                family_name: "De Bruijn".to_string(),
+                birth_date: NaiveDate::from_ymd_opt(1997, 5, 10).unwrap(),
+                gender: Some(Gender::Female),
+                ..Default::default()
+            },
+            resident: None,
+        }
+    }
+
+    #[test]
+    fn test_doc_types_unsigned_mdocs() {
+        let doc_types = DocTypes::try_new(vec![
+            DocTypeMapping {
+                doc_type: "com.example.pid".to_string(),
+                namespace: Some("com.example.pid.1".to_string()),
+                copy_count: 5,
+                validity_in_days: 30,
+                scopes: vec![],
+                attributes: vec![
+                    attribute("family_name", "person.family_name", Transform::Uppercase),
+                    attribute("birth_date", "person.birth_date", Transform::None),
+                    attribute("gender", "person.gender", Transform::Iso5218),
+                    attribute("nationality", "person.nationality", Transform::None),
+                ],
+            },
+            DocTypeMapping {
+                doc_type: "com.example.address".to_string(),
+                namespace: None,
+                copy_count: 10,
+                validity_in_days: 365,
+                scopes: vec![],
+                attributes: vec![attribute("resident_city", "resident.city", Transform::None)],
+            },
+        ])
+        .unwrap();
+
+        let mdocs = doc_types.unsigned_mdocs(attributes(), &[]).unwrap();
+        assert_eq!(mdocs.len(), 2);
+        assert_eq!(mdocs[0].copy_count, 5);
+        let validity = DateTime::<Utc>::try_from(&mdocs[0].valid_until).unwrap()
+            - DateTime::<Utc>::try_from(&mdocs[0].valid_from).unwrap();
+        assert_eq!(validity.num_days(), 30);
+
+        let entries = &mdocs[0].attributes["com.example.pid.1"];
+        assert_eq!(
+            entries
+                .iter()
+                .map(|entry| (entry.name.as_str(), entry.value.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("family_name", Value::Text("DE BRUIJN".to_string())),
+                ("birth_date", Value::Text("1997-05-10".to_string())),
+                ("gender", Value::Integer(2.into())),
+            ]
+        );
+
+        // The residence of the person is unknown, so the address is issued without attributes.
+        assert!(mdocs[1].attributes["com.example.address"].is_empty());
+    }
+
+    #[test]
+    fn test_default_doc_types() {
+        let doc_types = DocTypes::try_new(DocTypeMapping::load(None).unwrap()).unwrap();
+
+        let mdocs = doc_types.unsigned_mdocs(attributes(), &[]).unwrap();
+        assert_eq!(
+            mdocs.iter().map(|mdoc| mdoc.doc_type.as_str()).collect::<Vec<_>>(),
+            vec!["com.example.pid", "com.example.address"]
+        );
+        assert_eq!(
+            doc_types.source_attribute("com.example.pid", "person.bsn"),
+            Some(("com.example.pid".to_string(), "bsn".to_string()))
+        );
+        assert_eq!(doc_types.source_attribute("com.example.address", "person.bsn"), None);
+    }
+
+    #[test]
+    fn test_doc_types_scopes() {
+        let mapping = |doc_type: &str, scopes: &[&str]| DocTypeMapping {
+            doc_type: doc_type.to_string(),
+            namespace: None,
+            copy_count: 10,
+            validity_in_days: 365,
+            scopes: scopes.iter().map(|scope| scope.to_string()).collect(),
+            attributes: vec![attribute("birth_date", "person.birth_date", Transform::None)],
+        };
+        let doc_types = DocTypes::try_new(vec![
+            mapping("com.example.age", &[]),
+            mapping("com.example.pid", &["pid", "pid+address"]),
+            mapping("com.example.address", &["pid+address"]),
+        ])
+        .unwrap();
+
+        let issued_doc_types = |granted_scopes: &[&str]| {
+            let granted_scopes: Vec<_> = granted_scopes.iter().map(|scope| scope.to_string()).collect();
+            doc_types
+                .unsigned_mdocs(attributes(), &granted_scopes)
+                .unwrap()
+                .into_iter()
+                .map(|mdoc| mdoc.doc_type)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(issued_doc_types(&[]), vec!["com.example.age"]);
+        assert_eq!(issued_doc_types(&["pid"]), vec!["com.example.age", "com.example.pid"]);
+        assert_eq!(
+            issued_doc_types(&["openid", "pid+address"]),
+            vec!["com.example.age", "com.example.pid", "com.example.address"]
+        );
+        assert_eq!(doc_types.scopes("com.example.address"), ["pid+address"]);
+        assert!(doc_types.scopes("com.example.unknown").is_empty());
+    }
+
+    #[test]
+    fn test_doc_types_invalid_mapping() {
+        let mapping = |source, transform| DocTypeMapping {
+            doc_type: "com.example.pid".to_string(),
+            namespace: None,
+            copy_count: 10,
+            validity_in_days: 365,
+            scopes: vec![],
+            attributes: vec![attribute("attribute", source, transform)],
+        };
+
+        assert_matches!(
+            DocTypes::try_new(vec![mapping("person.shoe_size", Transform::None)]),
+            Err(MappingError::UnknownSource { .. })
+        );
+        assert_matches!(
+            DocTypes::try_new(vec![mapping("person.age_over_18", Transform::Uppercase)])
+                .unwrap()
+                .unsigned_mdocs(attributes(), &[]),
+            Err(MappingError::InvalidValue { .. })
+        );
+        assert_matches!(
+            DocTypes::try_new(vec![DocTypeMapping {
+                copy_count: 0,
+                ..mapping("person.bsn", Transform::None)
+            }]),
+            Err(MappingError::InvalidIssuance(_))
+        );
+    }
+}
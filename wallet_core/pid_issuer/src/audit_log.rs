@@ -0,0 +1,308 @@
+//! Audit log of the issuance events of the PID issuer, i.e. the start of issuance sessions, the issued mdocs and their
+//! revocation.
+//!
+//! Every event is appended as a line of JSON to the log file. Only the doc types and the time of the events are
+//! recorded, along with the BSN of the person as a digest that is salted with a secret, so that the events of a person
+//! can be found without the log containing BSNs or attributes. Events older than the retention period are removed
+//! when the log is opened and once a day after that.
+use std::{
+    fmt::{self, Debug},
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+
+use nl_wallet_mdoc::issuer::IssuanceObserver;
+
+use crate::{settings, status_lists::subject_digest};
+
+#[derive(Debug, thiserror::Error)]
+pub enum AuditLogError {
+    #[error("could not access audit log: {0}")]
+    Io(#[from] io::Error),
+    #[error("could not parse line {line} of audit log: {source}")]
+    Parsing {
+        line: usize,
+        #[source]
+        source: serde_json::Error,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    /// An issuance session was started for a person authenticated by DigiD.
+    IssuanceStarted,
+    /// An issuance session was started for a holder that proved possession of a valid PID.
+    ReissuanceStarted,
+    /// An OpenID4VCI credential offer was created for a person authenticated by DigiD.
+    CredentialOfferCreated,
+    /// Mdocs were issued to the holder.
+    Issued,
+    /// Mdocs were revoked by an administrator.
+    Revoked,
+}
+
+/// A line in the audit log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEvent {
+    pub at: DateTime<Utc>,
+    pub action: AuditAction,
+    /// The salted digest of the BSN of the person, if known.
+    pub subject: Option<String>,
+    pub doc_types: Vec<String>,
+}
+
+/// Selects the events of the audit log, all of which are optional.
+#[derive(Debug, Default, Deserialize)]
+pub struct AuditQuery {
+    /// Only the events of the person with this BSN.
+    pub bsn: Option<String>,
+    /// Only the events at or after this time.
+    pub from: Option<DateTime<Utc>>,
+    /// Only the events before this time.
+    pub until: Option<DateTime<Utc>>,
+}
+
+struct AuditLogState {
+    file: File,
+    last_pruned: DateTime<Utc>,
+}
+
+/// Appends the issuance events to the log file and queries these.
+///
+/// Note that only a single instance of the PID issuer should use the same log file, as the file is replaced when the
+/// expired events are removed.
+pub struct AuditLog {
+    path: PathBuf,
+    subject_salt: Vec<u8>,
+    retention: Duration,
+    state: Mutex<AuditLogState>,
+}
+
+impl Debug for AuditLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuditLog")
+            .field("path", &self.path)
+            .field("retention", &self.retention)
+            .finish_non_exhaustive()
+    }
+}
+
+fn open_for_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().append(true).create(true).open(path)
+}
+
+fn read_events(path: &Path) -> Result<Vec<AuditEvent>, AuditLogError> {
+    let file = File::open(path)?;
+
+    BufReader::new(file)
+        .lines()
+        .enumerate()
+        .map(|(line_idx, line)| {
+            serde_json::from_str(&line?).map_err(|source| AuditLogError::Parsing {
+                line: line_idx + 1,
+                source,
+            })
+        })
+        .collect()
+}
+
+fn append(file: &mut File, event: &AuditEvent) -> io::Result<()> {
+    let mut line = serde_json::to_vec(event)?;
+    line.push(b'\n');
+    file.write_all(&line)?;
+
+    file.sync_data()
+}
+
+impl AuditLog {
+    /// Open the audit log, removing the events that are older than the retention period.
+    pub fn open(settings: &settings::AuditLog) -> Result<Self, AuditLogError> {
+        let now = Utc::now();
+        let audit_log = Self {
+            path: settings.path.clone(),
+            subject_salt: settings.subject_salt.as_bytes().to_vec(),
+            retention: Duration::days(settings.retention_in_days as i64),
+            state: Mutex::new(AuditLogState {
+                file: open_for_append(&settings.path)?,
+                last_pruned: now,
+            }),
+        };
+
+        audit_log.prune(&mut audit_log.state.lock().unwrap(), now)?;
+
+        Ok(audit_log)
+    }
+
+    /// Record an event for the person with the specified BSN, if known.
+    pub fn record(&self, action: AuditAction, bsn: Option<&str>, doc_types: Vec<String>) -> Result<(), AuditLogError> {
+        self.record_at(Utc::now(), action, bsn, doc_types)
+    }
+
+    fn record_at(
+        &self,
+        at: DateTime<Utc>,
+        action: AuditAction,
+        bsn: Option<&str>,
+        doc_types: Vec<String>,
+    ) -> Result<(), AuditLogError> {
+        let event = AuditEvent {
+            at,
+            action,
+            subject: bsn.map(|bsn| subject_digest(&self.subject_salt, bsn)),
+            doc_types,
+        };
+
+        let mut state = self.state.lock().unwrap();
+        if at - state.last_pruned >= Duration::days(1) {
+            self.prune(&mut state, at)?;
+        }
+
+        append(&mut state.file, &event)?;
+
+        Ok(())
+    }
+
+    /// Replace the log file by one without the events that are older than the retention period, if there are any.
+    fn prune(&self, state: &mut AuditLogState, now: DateTime<Utc>) -> Result<(), AuditLogError> {
+        let cutoff = now - self.retention;
+        let events = read_events(&self.path)?;
+        let expired_count = events.iter().filter(|event| event.at < cutoff).count();
+
+        if expired_count > 0 {
+            let mut temp_path = self.path.clone().into_os_string();
+            temp_path.push(".tmp");
+
+            let mut temp_file = File::create(&temp_path)?;
+            for event in events.iter().filter(|event| event.at >= cutoff) {
+                let mut line = serde_json::to_vec(event).map_err(io::Error::from)?;
+                line.push(b'\n');
+                temp_file.write_all(&line)?;
+            }
+            temp_file.sync_all()?;
+
+            fs::rename(&temp_path, &self.path)?;
+            state.file = open_for_append(&self.path)?;
+
+            info!("removed {} expired events from audit log", expired_count);
+        }
+
+        state.last_pruned = now;
+
+        Ok(())
+    }
+
+    /// The events that match the query, in the order in which these were recorded.
+    pub fn query(&self, query: &AuditQuery) -> Result<Vec<AuditEvent>, AuditLogError> {
+        let subject = query.bsn.as_deref().map(|bsn| subject_digest(&self.subject_salt, bsn));
+
+        // Prevent the file from being replaced while it is read.
+        let _state = self.state.lock().unwrap();
+        let events = read_events(&self.path)?
+            .into_iter()
+            .filter(|event| subject.is_none() || event.subject == subject)
+            .filter(|event| query.from.map_or(true, |from| event.at >= from))
+            .filter(|event| query.until.map_or(true, |until| event.at < until))
+            .collect();
+
+        Ok(events)
+    }
+}
+
+impl IssuanceObserver for AuditLog {
+    fn issued(&self, doc_types: &[String], subject: Option<&str>) {
+        // The mdocs have already been issued at this point, so the error can only be logged.
+        if let Err(error) = self.record(AuditAction::Issued, subject, doc_types.to_vec()) {
+            error!("could not record issued mdocs in audit log: {}", error);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(dir: &tempfile::TempDir) -> settings::AuditLog {
+        settings::AuditLog {
+            path: dir.path().join("audit_log.jsonl"),
+            subject_salt: "salt".to_string(),
+            retention_in_days: 30,
+        }
+    }
+
+    #[test]
+    fn test_audit_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let audit_log = AuditLog::open(&settings(&dir)).unwrap();
+        let start = Utc::now();
+        let doc_types = vec!["com.example.pid".to_string()];
+
+        audit_log
+            .record_at(
+                start,
+                AuditAction::IssuanceStarted,
+                Some("999991772"),
+                doc_types.clone(),
+            )
+            .unwrap();
+        audit_log.issued(&doc_types, Some("999991772"));
+        audit_log
+            .record_at(
+                start + Duration::hours(1),
+                AuditAction::IssuanceStarted,
+                Some("999991773"),
+                doc_types.clone(),
+            )
+            .unwrap();
+
+        // The log contains neither the BSN nor the attributes.
+        let contents = fs::read_to_string(dir.path().join("audit_log.jsonl")).unwrap();
+        assert!(!contents.contains("999991772"));
+
+        let events = audit_log
+            .query(&AuditQuery {
+                bsn: Some("999991772".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(
+            events.iter().map(|event| event.action).collect::<Vec<_>>(),
+            vec![AuditAction::IssuanceStarted, AuditAction::Issued]
+        );
+        assert!(events.iter().all(|event| event.doc_types == doc_types));
+
+        assert_eq!(
+            audit_log
+                .query(&AuditQuery {
+                    until: Some(start + Duration::minutes(1)),
+                    ..Default::default()
+                })
+                .unwrap()
+                .len(),
+            2
+        );
+
+        // The log is replayed when opened again.
+        let audit_log = AuditLog::open(&settings(&dir)).unwrap();
+        assert_eq!(audit_log.query(&AuditQuery::default()).unwrap().len(), 3);
+
+        // Events older than the retention period are removed once a day.
+        audit_log
+            .record_at(
+                Utc::now() + Duration::days(31),
+                AuditAction::Revoked,
+                Some("999991772"),
+                doc_types.clone(),
+            )
+            .unwrap();
+        let events = audit_log.query(&AuditQuery::default()).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].action, AuditAction::Revoked);
+    }
+}
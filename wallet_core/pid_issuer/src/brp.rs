@@ -0,0 +1,419 @@
+//! Client of the HaalCentraal "BRP Personen bevragen" API, from which the attributes of a person are retrieved by BSN.
+use std::{fs, path::Path, time::Duration};
+
+use chrono::{Months, NaiveDate, Utc};
+use http::header;
+use reqwest::{Certificate, Identity, StatusCode};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{
+    app::AttributesLookup,
+    attributes::{Attributes, Gender, PersonAttributes, ResidentAttributes},
+    settings,
+};
+
+const API_KEY_HEADER: &str = "X-API-KEY";
+const APPLICATION_JSON: &str = "application/json";
+
+/// The fields of a person that are requested, which are the only ones the BRP returns.
+const PERSON_FIELDS: &[&str] = &[
+    "burgerservicenummer",
+    "naam",
+    "geboorte",
+    "geslacht",
+    "nationaliteiten",
+    "verblijfplaats",
+];
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("could not read PEM file {0}: {1}")]
+    Pem(String, #[source] std::io::Error),
+    #[error("could not create BRP client: {0}")]
+    Client(#[source] reqwest::Error),
+    #[error("could not reach BRP: {0}")]
+    Networking(#[source] reqwest::Error),
+    #[error("BRP did not accept the credentials of the PID issuer: {0}")]
+    Unauthorized(StatusCode),
+    #[error("BRP responded with {status}: {detail}")]
+    Response { status: StatusCode, detail: String },
+    #[error("could not deserialize BRP response: {0}")]
+    Deserialization(#[source] serde_json::Error),
+    #[error("BRP returned {0} persons for a single BSN")]
+    UnexpectedPersonCount(usize),
+    #[error("BRP returned a person with a different BSN than requested")]
+    BsnMismatch,
+    #[error("person in BRP lacks {0}, which is required for the PID")]
+    IncompletePerson(&'static str),
+}
+
+#[derive(Serialize)]
+struct PersonenQuery<'a> {
+    #[serde(rename = "type")]
+    query_type: &'static str,
+    burgerservicenummer: [&'a str; 1],
+    fields: &'static [&'static str],
+}
+
+#[derive(Deserialize)]
+struct PersonenResponse {
+    #[serde(default)]
+    personen: Vec<Persoon>,
+}
+
+/// The problem details (RFC 7807) that the BRP responds with in case of an error.
+#[derive(Deserialize)]
+struct Problem {
+    title: Option<String>,
+    detail: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Persoon {
+    burgerservicenummer: String,
+    #[serde(default)]
+    naam: Naam,
+    #[serde(default)]
+    geboorte: Geboorte,
+    geslacht: Option<Waardetabel>,
+    #[serde(default)]
+    nationaliteiten: Vec<Nationaliteit>,
+    verblijfplaats: Option<Verblijfplaats>,
+}
+
+#[derive(Default, Deserialize)]
+struct Naam {
+    voornamen: Option<String>,
+    voorvoegsel: Option<String>,
+    geslachtsnaam: Option<String>,
+}
+
+#[derive(Default, Deserialize)]
+struct Geboorte {
+    datum: Option<Datum>,
+    plaats: Option<Waardetabel>,
+    land: Option<Waardetabel>,
+}
+
+/// A date in the BRP, which may be (partially) unknown.
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+enum Datum {
+    Datum {
+        datum: NaiveDate,
+    },
+    #[serde(other)]
+    Onvolledig,
+}
+
+/// A value from one of the tables of the BRP, e.g. countries or municipalities.
+#[derive(Deserialize)]
+struct Waardetabel {
+    code: Option<String>,
+    omschrijving: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Nationaliteit {
+    nationaliteit: Option<Waardetabel>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+enum Verblijfplaats {
+    Adres {
+        verblijfadres: Adres,
+    },
+    VerblijfplaatsBuitenland {
+        verblijfadres: AdresBuitenland,
+    },
+    #[serde(other)]
+    Overig,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Adres {
+    officiele_straatnaam: Option<String>,
+    huisnummer: Option<u32>,
+    huisletter: Option<String>,
+    huisnummertoevoeging: Option<String>,
+    postcode: Option<String>,
+    woonplaats: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct AdresBuitenland {
+    regel1: Option<String>,
+    regel2: Option<String>,
+    regel3: Option<String>,
+    land: Option<Waardetabel>,
+}
+
+impl Persoon {
+    fn into_attributes(self, today: NaiveDate) -> Result<Attributes, Error> {
+        let family_name = self.naam.geslachtsnaam.ok_or(Error::IncompletePerson("family name"))?;
+        let birth_date = match self.geboorte.datum {
+            Some(Datum::Datum { datum }) => datum,
+            _ => return Err(Error::IncompletePerson("complete birth date")),
+        };
+
+        let person = PersonAttributes {
+            bsn: self.burgerservicenummer,
+            family_name: match self.naam.voorvoegsel {
+                Some(voorvoegsel) => format!("{} {}", voorvoegsel, family_name),
+                None => family_name,
+            },
+            // not every person has a given name
+            given_name: self.naam.voornamen.unwrap_or_default(),
+            birth_date,
+            age_over_18: birth_date
+                .checked_add_months(Months::new(18 * 12))
+                .is_some_and(|adult_date| adult_date <= today),
+            birth_city: self.geboorte.plaats.and_then(|plaats| plaats.omschrijving),
+            birth_country: self.geboorte.land.and_then(|land| land.omschrijving),
+            gender: self.geslacht.and_then(|geslacht| match geslacht.code.as_deref() {
+                Some("M") => Some(Gender::Male),
+                Some("V") => Some(Gender::Female),
+                Some("O") => Some(Gender::Unknown),
+                _ => None,
+            }),
+            nationality: self
+                .nationaliteiten
+                .into_iter()
+                .find_map(|nationaliteit| nationaliteit.nationaliteit.and_then(|tabel| tabel.omschrijving)),
+            ..Default::default()
+        };
+
+        let resident = match self.verblijfplaats {
+            Some(Verblijfplaats::Adres { verblijfadres: adres }) => Some(ResidentAttributes {
+                street: adres.officiele_straatnaam,
+                house_number: adres.huisnummer.map(|huisnummer| {
+                    let mut house_number = huisnummer.to_string();
+                    house_number.extend(adres.huisletter);
+                    if let Some(toevoeging) = adres.huisnummertoevoeging {
+                        house_number.push('-');
+                        house_number.push_str(&toevoeging);
+                    }
+                    house_number
+                }),
+                postal_code: adres.postcode,
+                city: adres.woonplaats,
+                country: Some("Nederland".to_string()),
+                ..Default::default()
+            }),
+            Some(Verblijfplaats::VerblijfplaatsBuitenland { verblijfadres: adres }) => {
+                let address = [adres.regel1, adres.regel2, adres.regel3]
+                    .into_iter()
+                    .flatten()
+                    .collect::<Vec<_>>();
+
+                Some(ResidentAttributes {
+                    address: (!address.is_empty()).then(|| address.join(", ")),
+                    country: adres.land.and_then(|land| land.omschrijving),
+                    ..Default::default()
+                })
+            }
+            Some(Verblijfplaats::Overig) | None => None,
+        };
+
+        Ok(Attributes { person, resident })
+    }
+}
+
+/// Looks up the attributes of a person in the BRP, authenticating using an API key and/or a client certificate.
+pub struct BrpClient {
+    http_client: reqwest::Client,
+    personen_url: Url,
+    api_key: Option<String>,
+}
+
+fn read_pem(path: &Path) -> Result<Vec<u8>, Error> {
+    fs::read(path).map_err(|e| Error::Pem(path.display().to_string(), e))
+}
+
+impl BrpClient {
+    pub fn new(settings: &settings::Brp) -> Result<Self, Error> {
+        let mut builder = reqwest::Client::builder().timeout(Duration::from_millis(settings.timeout_in_ms));
+
+        if let Some(mtls) = &settings.mtls {
+            let mut pem = read_pem(&mtls.certificate_path)?;
+            pem.extend(read_pem(&mtls.private_key_path)?);
+            builder = builder.identity(Identity::from_pem(&pem).map_err(Error::Client)?);
+        }
+        if let Some(ca_certificate_path) = &settings.ca_certificate_path {
+            let certificate = Certificate::from_pem(&read_pem(ca_certificate_path)?).map_err(Error::Client)?;
+            builder = builder.add_root_certificate(certificate);
+        }
+
+        let mut base_url = settings.url.clone();
+        if !base_url.path().ends_with('/') {
+            // Otherwise .join() would replace the last path segment of the base URL.
+            base_url.set_path(&format!("{}/", base_url.path()));
+        }
+
+        Ok(Self {
+            http_client: builder.build().map_err(Error::Client)?,
+            personen_url: base_url
+                .join("personen")
+                .expect("joining a relative path should always succeed"),
+            api_key: settings.api_key.clone(),
+        })
+    }
+
+    async fn persoon(&self, bsn: &str) -> Result<Option<Persoon>, Error> {
+        let query = PersonenQuery {
+            query_type: "RaadpleegMetBurgerservicenummer",
+            burgerservicenummer: [bsn],
+            fields: PERSON_FIELDS,
+        };
+
+        let mut request = self
+            .http_client
+            .post(self.personen_url.clone())
+            .header(header::CONTENT_TYPE, APPLICATION_JSON)
+            .body(serde_json::to_vec(&query).expect("query should serialize"));
+        if let Some(api_key) = &self.api_key {
+            request = request.header(API_KEY_HEADER, api_key);
+        }
+
+        let response = request.send().await.map_err(Error::Networking)?;
+        let status = response.status();
+        let body = response.bytes().await.map_err(Error::Networking)?;
+
+        match status {
+            status if status.is_success() => {}
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => return Err(Error::Unauthorized(status)),
+            status => {
+                let detail = serde_json::from_slice::<Problem>(&body)
+                    .ok()
+                    .and_then(|problem| problem.detail.or(problem.title))
+                    .unwrap_or_default();
+
+                return Err(Error::Response { status, detail });
+            }
+        }
+
+        let mut personen = serde_json::from_slice::<PersonenResponse>(&body)
+            .map_err(Error::Deserialization)?
+            .personen;
+
+        match personen.len() {
+            0 => Ok(None),
+            1 => {
+                let persoon = personen.pop().unwrap();
+                if persoon.burgerservicenummer != bsn {
+                    return Err(Error::BsnMismatch);
+                }
+
+                Ok(Some(persoon))
+            }
+            count => Err(Error::UnexpectedPersonCount(count)),
+        }
+    }
+}
+
+impl AttributesLookup for BrpClient {
+    async fn attributes(&self, bsn: &str) -> Result<Option<Attributes>, Error> {
+        self.persoon(bsn)
+            .await?
+            .map(|persoon| persoon.into_attributes(Utc::now().date_naive()))
+            .transpose()
+    }
+
+    /// The `/personen` endpoint only accepts POST requests, so any response to a GET request other than an
+    /// authentication or server error shows that the BRP can be reached using our credentials.
+    async fn check_health(&self) -> Result<(), Error> {
+        let mut request = self.http_client.get(self.personen_url.clone());
+        if let Some(api_key) = &self.api_key {
+            request = request.header(API_KEY_HEADER, api_key);
+        }
+
+        let status = request.send().await.map_err(Error::Networking)?.status();
+        match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Err(Error::Unauthorized(status)),
+            status if status.is_server_error() => Err(Error::Response {
+                status,
+                detail: String::new(),
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use serde_json::json;
+
+    use super::*;
+
+    fn persoon(datum: serde_json::Value) -> Persoon {
+        serde_json::from_value(json!({
+            "burgerservicenummer": "999991772",
+            "naam": {
+                "voornamen": "Willeke Liselotte",
+                "voorvoegsel": "de",
+                "geslachtsnaam": "Bruijn"
+            },
+            "geboorte": {
+                "datum": datum,
+                "plaats": { "code": "0503", "omschrijving": "Delft" },
+                "land": { "code": "6030", "omschrijving": "Nederland" }
+            },
+            "geslacht": { "code": "V", "omschrijving": "vrouw" },
+            "nationaliteiten": [
+                { "nationaliteit": { "code": "0001", "omschrijving": "Nederlandse" } }
+            ],
+            "verblijfplaats": {
+                "type": "Adres",
+                "verblijfadres": {
+                    "officieleStraatnaam": "Turfmarkt",
+                    "huisnummer": 147,
+                    "huisletter": "a",
+                    "postcode": "2511DP",
+                    "woonplaats": "Den Haag"
+                }
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_persoon_into_attributes() {
+        let today = NaiveDate::from_ymd_opt(2015, 5, 10).unwrap();
+        let Attributes { person, resident } = persoon(json!({ "type": "Datum", "datum": "1997-05-10" }))
+            .into_attributes(today)
+            .unwrap();
+
+        assert_eq!(person.family_name, "de Bruijn");
+        assert_eq!(person.given_name, "Willeke Liselotte");
+        assert_eq!(person.birth_date, NaiveDate::from_ymd_opt(1997, 5, 10).unwrap());
+        assert!(person.age_over_18);
+        assert_eq!(person.birth_city.as_deref(), Some("Delft"));
+        assert_matches!(person.gender, Some(Gender::Female));
+        assert_eq!(person.nationality.as_deref(), Some("Nederlandse"));
+
+        let resident = resident.unwrap();
+        assert_eq!(resident.street.as_deref(), Some("Turfmarkt"));
+        assert_eq!(resident.house_number.as_deref(), Some("147a"));
+        assert_eq!(resident.city.as_deref(), Some("Den Haag"));
+
+        // The day before the 18th birthday, the person is not yet of age.
+        let attributes = persoon(json!({ "type": "Datum", "datum": "1997-05-10" }))
+            .into_attributes(today.pred_opt().unwrap())
+            .unwrap();
+        assert!(!attributes.person.age_over_18);
+    }
+
+    #[test]
+    fn test_persoon_incomplete_birth_date() {
+        let today = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+
+        assert_matches!(
+            persoon(json!({ "type": "JaarMaandDatum", "jaar": 1997, "maand": 5 })).into_attributes(today),
+            Err(Error::IncompletePerson(_))
+        );
+    }
+}
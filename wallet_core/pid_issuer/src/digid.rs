@@ -20,6 +20,7 @@ use crate::{app::BsnLookup, settings};
 
 const APPLICATION_JWT: &str = "application/jwt";
 const BSN_KEY: &str = "uzi_id";
+const SCOPE_KEY: &str = "scope";
 
 const CLIENT_TIMEOUT: Duration = Duration::from_secs(30);
 
@@ -49,6 +50,13 @@ pub enum Error {
     Serde(#[from] serde_json::Error),
 }
 
+/// A user authenticated by DigiD, along with the scopes they granted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DigidUser {
+    pub bsn: String,
+    pub scopes: Vec<String>,
+}
+
 /// An OIDC client for exchanging an access token provided by the user for their BSN at the IdP.
 pub struct OpenIdClient {
     client_id: String,
@@ -57,12 +65,22 @@ pub struct OpenIdClient {
 }
 
 impl BsnLookup for OpenIdClient {
-    async fn bsn(&self, access_token: &str) -> Result<String> {
+    async fn user(&self, access_token: &str) -> Result<DigidUser> {
         let userinfo_claims: UserInfoJWT = self
             .request_userinfo_decrypted_claims(access_token, &self.decrypter_private_key)
             .await?;
 
-        OpenIdClient::bsn_from_claims(&userinfo_claims)?.ok_or(Error::NoBSN)
+        let bsn = OpenIdClient::bsn_from_claims(&userinfo_claims)?.ok_or(Error::NoBSN)?;
+        let scopes = OpenIdClient::scopes_from_claims(&userinfo_claims)?;
+
+        Ok(DigidUser { bsn, scopes })
+    }
+
+    /// DigiD is considered available when its discovery document can be retrieved.
+    async fn check_health(&self) -> Result<()> {
+        self.discover_client().await?;
+
+        Ok(())
     }
 }
 
@@ -127,6 +145,21 @@ impl OpenIdClient {
         Ok(bsn)
     }
 
+    /// The scopes that the user granted, which are taken from the space separated `scope` claim. When the claim is
+    /// absent, no scopes were granted.
+    pub fn scopes_from_claims(userinfo_token: &UserInfoJWT) -> Result<Vec<String>> {
+        let userinfo_payload = userinfo_token.payload()?;
+
+        let scopes = userinfo_payload
+            .private
+            .get(SCOPE_KEY)
+            .and_then(|value| value.as_str())
+            .map(|scope| scope.split_whitespace().map(|scope| scope.to_string()).collect())
+            .unwrap_or_default();
+
+        Ok(scopes)
+    }
+
     pub async fn request_userinfo_decrypted_claims<C, H>(
         &self,
         access_token: impl AsRef<str>,
@@ -0,0 +1,95 @@
+//! Readiness checks of the dependencies of the PID issuer, which are reported by `/health` when enabled.
+use std::{fmt::Display, future::Future, time::Duration};
+
+use indexmap::IndexMap;
+use serde::Serialize;
+use tracing::warn;
+
+use crate::settings;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    Ok,
+    Unavailable,
+}
+
+/// The status of the PID issuer as a whole, which is only ok when all of its dependencies are.
+#[derive(Debug, Serialize)]
+pub struct HealthResponse {
+    pub status: HealthStatus,
+    pub dependencies: IndexMap<&'static str, HealthStatus>,
+}
+
+impl HealthResponse {
+    pub fn new(dependencies: IndexMap<&'static str, HealthStatus>) -> Self {
+        let status = if dependencies.values().all(|status| *status == HealthStatus::Ok) {
+            HealthStatus::Ok
+        } else {
+            HealthStatus::Unavailable
+        };
+
+        Self { status, dependencies }
+    }
+}
+
+pub struct HealthChecks {
+    timeout: Duration,
+    /// Whether the BRP is configured, instead of mock attributes.
+    pub brp_enabled: bool,
+}
+
+impl HealthChecks {
+    pub fn new(settings: &settings::HealthChecks, brp_enabled: bool) -> Self {
+        Self {
+            timeout: Duration::from_millis(settings.timeout_in_ms),
+            brp_enabled,
+        }
+    }
+
+    /// Await the check of the named dependency. The reason for a failure is only logged, so that the details of the
+    /// dependencies are not exposed to the clients of the PID issuer.
+    pub async fn check<E: Display>(&self, name: &str, check: impl Future<Output = Result<(), E>>) -> HealthStatus {
+        match tokio::time::timeout(self.timeout, check).await {
+            Ok(Ok(())) => HealthStatus::Ok,
+            Ok(Err(error)) => {
+                warn!("health check of {} failed: {}", name, error);
+                HealthStatus::Unavailable
+            }
+            Err(_) => {
+                warn!("health check of {} timed out", name);
+                HealthStatus::Unavailable
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_health_checks() {
+        let health_checks = HealthChecks::new(&settings::HealthChecks { timeout_in_ms: 10 }, false);
+
+        let ok = health_checks.check("ok", async { Ok::<_, String>(()) }).await;
+        let failing = health_checks
+            .check("failing", async { Err("unreachable".to_string()) })
+            .await;
+        let slow = health_checks
+            .check("slow", async {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                Ok::<_, String>(())
+            })
+            .await;
+
+        assert_eq!(ok, HealthStatus::Ok);
+        assert_eq!(failing, HealthStatus::Unavailable);
+        assert_eq!(slow, HealthStatus::Unavailable);
+
+        let response = HealthResponse::new(IndexMap::from([("ok", ok)]));
+        assert_eq!(response.status, HealthStatus::Ok);
+        let response = HealthResponse::new(IndexMap::from([("ok", ok), ("slow", slow)]));
+        assert_eq!(response.status, HealthStatus::Unavailable);
+    }
+}
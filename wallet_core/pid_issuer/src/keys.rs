@@ -0,0 +1,354 @@
+use std::{collections::HashMap, iter, ptr};
+
+use base64::prelude::*;
+use chrono::{DateTime, Utc};
+use p256::ecdsa::signature::Verifier;
+use serde::Serialize;
+
+use nl_wallet_mdoc::{
+    server_keys::{KeyRing, PrivateKey},
+    utils::x509::{Certificate, CertificateError},
+};
+use wallet_common::{keys::EcdsaKey, utils::sha256};
+
+use crate::settings::IssuerKey;
+
+/// A private key with the period during which it may be used for signing. Consecutive keys for the same doc type
+/// may overlap, so that a new key can be rolled out before the old one is retired. The certificate of a retired key
+/// remains trusted until it expires, as the mdocs that were signed with it are still in use.
+pub struct RotatingKey {
+    pub key: PrivateKey,
+    pub not_before: Option<DateTime<Utc>>,
+    pub not_after: Option<DateTime<Utc>>,
+    pub certificate_not_after: DateTime<Utc>,
+}
+
+impl RotatingKey {
+    fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.not_before.map_or(true, |not_before| not_before <= now)
+            && self.not_after.map_or(true, |not_after| now < not_after)
+    }
+
+    fn is_trusted(&self, now: DateTime<Utc>) -> bool {
+        now < self.certificate_not_after
+    }
+}
+
+impl TryFrom<&IssuerKey> for RotatingKey {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &IssuerKey) -> Result<Self, Self::Error> {
+        let key = PrivateKey::from_der(
+            &BASE64_STANDARD.decode(&value.private_key)?,
+            &BASE64_STANDARD.decode(&value.certificate)?,
+        )?;
+        let certificate_not_after = key.certificate().not_after()?;
+
+        Ok(Self {
+            key,
+            not_before: value.not_before,
+            not_after: value.not_after,
+            certificate_not_after,
+        })
+    }
+}
+
+/// Identifies a certificate by the URL safe base64 encoded SHA-256 digest of its DER encoding.
+pub fn certificate_fingerprint(certificate: &Certificate) -> String {
+    BASE64_URL_SAFE_NO_PAD.encode(sha256(certificate.as_bytes()))
+}
+
+/// Of the keys that are active at `now`, the one that became active last.
+fn newest_active_key(keys: &[RotatingKey], now: DateTime<Utc>) -> Option<&RotatingKey> {
+    keys.iter()
+        .filter(|key| key.is_active(now))
+        .max_by_key(|key| key.not_before)
+}
+
+/// An implementation of [`KeyRing`] that keeps separate signing keys per doc type, so that the compromise of one
+/// key only affects a single type of credential. Doc types without keys of their own are signed with the default
+/// keys. As the certificate is stored alongside each key, the x5chain included in the mdoc always matches the key.
+pub struct IssuerKeyRing {
+    default_keys: Vec<RotatingKey>,
+    doc_type_keys: HashMap<String, Vec<RotatingKey>>,
+}
+
+impl IssuerKeyRing {
+    pub fn new(default_keys: Vec<RotatingKey>, doc_type_keys: HashMap<String, Vec<RotatingKey>>) -> Self {
+        Self {
+            default_keys,
+            doc_type_keys,
+        }
+    }
+
+    /// Select the key to sign the specified doc type with at `now`. When multiple keys are active, the one that
+    /// became active last is used. Keys of other doc types are never used, even if none of the keys configured for
+    /// the doc type are active.
+    pub fn select_key(&self, doc_type: &str, now: DateTime<Utc>) -> Option<&PrivateKey> {
+        let keys = self.doc_type_keys.get(doc_type).unwrap_or(&self.default_keys);
+
+        newest_active_key(keys, now).map(|key| &key.key)
+    }
+}
+
+/// The message that is signed when checking the keys.
+const HEALTH_CHECK_MESSAGE: &[u8] = b"pid_issuer health check";
+
+#[derive(Debug, thiserror::Error)]
+pub enum KeyCheckError {
+    #[error("no active key for {}", .0.as_deref().unwrap_or("the default keys"))]
+    NoActiveKey(Option<String>),
+    #[error("could not sign using key {0}: {1}")]
+    Signing(String, #[source] p256::ecdsa::Error),
+    #[error("could not read public key from certificate {0}: {1}")]
+    Certificate(String, #[source] CertificateError),
+    #[error("signature of key {0} does not verify against its certificate")]
+    Verification(String),
+}
+
+/// The state of one of the keys in the key ring, as reported to the administrator of the PID issuer.
+#[derive(Debug, Serialize)]
+pub struct KeyStatus {
+    /// The doc type the key is configured for, or `None` for the default keys.
+    pub doc_type: Option<String>,
+    /// See [`certificate_fingerprint()`].
+    pub certificate_fingerprint: String,
+    pub not_before: Option<DateTime<Utc>>,
+    pub not_after: Option<DateTime<Utc>>,
+    pub certificate_not_after: DateTime<Utc>,
+    /// Whether the key may currently be used for signing.
+    pub active: bool,
+    /// Whether the key is the one that is currently used for signing.
+    pub signing: bool,
+    /// Whether the certificate is part of the published trust set.
+    pub trusted: bool,
+}
+
+impl IssuerKeyRing {
+    /// The default keys and the keys of every doc type, each with the doc type they are configured for.
+    fn key_groups(&self) -> impl Iterator<Item = (Option<&str>, &[RotatingKey])> {
+        iter::once((None, self.default_keys.as_slice())).chain(
+            self.doc_type_keys
+                .iter()
+                .map(|(doc_type, keys)| (Some(doc_type.as_str()), keys.as_slice())),
+        )
+    }
+
+    /// The certificates that have not yet expired, which includes those of keys that are not yet or no longer used
+    /// for signing. Relying parties should trust all of these, so that both mdocs signed with a retired key and those
+    /// signed with a newly activated key can be verified.
+    pub fn trusted_certificates(&self, now: DateTime<Utc>) -> Vec<&Certificate> {
+        self.key_groups()
+            .flat_map(|(_, keys)| keys)
+            .filter(|key| key.is_trusted(now))
+            .map(|key| key.key.certificate())
+            .collect()
+    }
+
+    /// Find the key of which the certificate has the specified fingerprint, regardless of whether it is active.
+    pub fn key_by_fingerprint(&self, fingerprint: &str) -> Option<&PrivateKey> {
+        self.key_groups()
+            .flat_map(|(_, keys)| keys)
+            .map(|key| &key.key)
+            .find(|key| certificate_fingerprint(key.certificate()) == fingerprint)
+    }
+
+    /// Check that the default keys and the keys of every doc type include a key that is active at `now`, and that this
+    /// key produces signatures that verify against its certificate.
+    pub async fn check_signing_keys(&self, now: DateTime<Utc>) -> Result<(), KeyCheckError> {
+        for (doc_type, keys) in self.key_groups() {
+            let key = newest_active_key(keys, now)
+                .map(|key| &key.key)
+                .ok_or_else(|| KeyCheckError::NoActiveKey(doc_type.map(str::to_string)))?;
+            let fingerprint = certificate_fingerprint(key.certificate());
+
+            let signature = key
+                .try_sign(HEALTH_CHECK_MESSAGE)
+                .await
+                .map_err(|error| KeyCheckError::Signing(fingerprint.clone(), error))?;
+            key.certificate()
+                .public_key()
+                .map_err(|error| KeyCheckError::Certificate(fingerprint.clone(), error))?
+                .verify(HEALTH_CHECK_MESSAGE, &signature)
+                .map_err(|_| KeyCheckError::Verification(fingerprint))?;
+        }
+
+        Ok(())
+    }
+
+    /// Report the state of all keys at `now`.
+    pub fn key_statuses(&self, now: DateTime<Utc>) -> Vec<KeyStatus> {
+        self.key_groups()
+            .flat_map(|(doc_type, keys)| {
+                let signing_key = newest_active_key(keys, now);
+
+                keys.iter().map(move |key| KeyStatus {
+                    doc_type: doc_type.map(str::to_string),
+                    certificate_fingerprint: certificate_fingerprint(key.key.certificate()),
+                    not_before: key.not_before,
+                    not_after: key.not_after,
+                    certificate_not_after: key.certificate_not_after,
+                    active: key.is_active(now),
+                    signing: signing_key.is_some_and(|signing_key| ptr::eq(signing_key, key)),
+                    trusted: key.is_trusted(now),
+                })
+            })
+            .collect()
+    }
+}
+
+impl KeyRing for IssuerKeyRing {
+    fn private_key(&self, id: &str) -> Option<&PrivateKey> {
+        self.select_key(id, Utc::now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use assert_matches::assert_matches;
+
+    use nl_wallet_mdoc::mock::generate_issuance_key_and_ca;
+
+    use super::*;
+
+    fn rotating_key(not_before: Option<DateTime<Utc>>, not_after: Option<DateTime<Utc>>) -> RotatingKey {
+        let key = generate_issuance_key_and_ca().unwrap().0;
+        let certificate_not_after = key.certificate().not_after().unwrap();
+
+        RotatingKey {
+            key,
+            not_before,
+            not_after,
+            certificate_not_after,
+        }
+    }
+
+    #[test]
+    fn test_select_key_per_doc_type() {
+        let key_ring = IssuerKeyRing::new(
+            vec![rotating_key(None, None)],
+            HashMap::from([("address".to_string(), vec![rotating_key(None, None)])]),
+        );
+        let now = Utc::now();
+
+        let default_key = key_ring.select_key("pid", now).unwrap();
+        let address_key = key_ring.select_key("address", now).unwrap();
+
+        assert!(std::ptr::eq(default_key, &key_ring.default_keys[0].key));
+        assert!(std::ptr::eq(address_key, &key_ring.doc_type_keys["address"][0].key));
+    }
+
+    #[test]
+    fn test_select_key_rotation() {
+        let now = Utc::now();
+        let key_ring = IssuerKeyRing::new(
+            vec![],
+            HashMap::from([(
+                "pid".to_string(),
+                vec![
+                    rotating_key(None, Some(now + Duration::days(30))),
+                    rotating_key(Some(now + Duration::days(10)), None),
+                ],
+            )]),
+        );
+        let keys = &key_ring.doc_type_keys["pid"];
+
+        // Before the new key becomes active, the old key is used.
+        let key = key_ring.select_key("pid", now).unwrap();
+        assert!(std::ptr::eq(key, &keys[0].key));
+
+        // While both keys are active, the newest key is used.
+        let key = key_ring.select_key("pid", now + Duration::days(20)).unwrap();
+        assert!(std::ptr::eq(key, &keys[1].key));
+
+        // After the old key has been retired, the new key is still used.
+        let key = key_ring.select_key("pid", now + Duration::days(40)).unwrap();
+        assert!(std::ptr::eq(key, &keys[1].key));
+    }
+
+    #[test]
+    fn test_select_key_none_active() {
+        let now = Utc::now();
+        let key_ring = IssuerKeyRing::new(
+            vec![rotating_key(None, None)],
+            HashMap::from([("pid".to_string(), vec![rotating_key(None, Some(now))])]),
+        );
+
+        // The default key is not used when the keys of the doc type itself have expired.
+        assert!(key_ring.select_key("pid", now).is_none());
+        assert!(key_ring.select_key("address", now).is_some());
+    }
+
+    #[test]
+    fn test_trusted_certificates_and_key_statuses() {
+        let now = Utc::now();
+        let key_ring = IssuerKeyRing::new(
+            vec![
+                // retired, but its certificate is still valid
+                rotating_key(None, Some(now - Duration::days(10))),
+                rotating_key(Some(now - Duration::days(20)), None),
+                // retired, of which the certificate has expired
+                RotatingKey {
+                    certificate_not_after: now - Duration::days(1),
+                    ..rotating_key(None, Some(now - Duration::days(30)))
+                },
+            ],
+            HashMap::new(),
+        );
+
+        let trusted_certificates = key_ring.trusted_certificates(now);
+        assert_eq!(trusted_certificates.len(), 2);
+        assert!(std::ptr::eq(
+            trusted_certificates[0],
+            key_ring.default_keys[0].key.certificate()
+        ));
+
+        let statuses = key_ring.key_statuses(now);
+        assert_eq!(
+            statuses
+                .iter()
+                .map(|status| (status.active, status.signing, status.trusted))
+                .collect::<Vec<_>>(),
+            vec![(false, false, true), (true, true, true), (false, false, false)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_check_signing_keys() {
+        let now = Utc::now();
+        let key_ring = IssuerKeyRing::new(
+            vec![rotating_key(None, None)],
+            HashMap::from([("pid".to_string(), vec![rotating_key(None, None)])]),
+        );
+        key_ring.check_signing_keys(now).await.unwrap();
+
+        // A key of which the certificate belongs to another key is rejected.
+        let (_, other_signing_key) = Certificate::new_ca("other").unwrap();
+        let key_ring = IssuerKeyRing::new(
+            vec![RotatingKey {
+                key: PrivateKey::new(
+                    other_signing_key,
+                    generate_issuance_key_and_ca().unwrap().0.certificate().clone(),
+                ),
+                ..rotating_key(None, None)
+            }],
+            HashMap::new(),
+        );
+        assert_matches!(
+            key_ring.check_signing_keys(now).await,
+            Err(KeyCheckError::Verification(_))
+        );
+
+        // Every doc type needs an active key.
+        let key_ring = IssuerKeyRing::new(
+            vec![rotating_key(None, None)],
+            HashMap::from([("pid".to_string(), vec![rotating_key(None, Some(now))])]),
+        );
+        assert_matches!(
+            key_ring.check_signing_keys(now).await,
+            Err(KeyCheckError::NoActiveKey(Some(doc_type))) if doc_type == "pid"
+        );
+    }
+}
@@ -1,7 +1,16 @@
 pub mod app;
+pub mod attributes;
+pub mod audit_log;
+pub mod brp;
 pub mod digid;
+pub mod health;
+pub mod keys;
+pub mod openid4vci;
+pub mod rate_limits;
+pub mod reissuance;
 pub mod server;
 pub mod settings;
+pub mod status_lists;
 
 #[cfg(feature = "mock")]
 pub mod mock;
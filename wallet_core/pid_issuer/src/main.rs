@@ -1,22 +1,33 @@
 use anyhow::Result;
 use tracing::debug;
 
-use pid_issuer::{digid::OpenIdClient, mock::MockAttributesLookup, server, settings::Settings};
+use pid_issuer::{brp::BrpClient, digid::OpenIdClient, mock::MockAttributesLookup, server, settings::Settings};
+use wallet_common::telemetry;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize tracing.
-    tracing_subscriber::fmt::init();
-
     let settings = Settings::new()?;
 
+    // Initialize tracing.
+    telemetry::init_tracing(settings.otlp.as_ref(), "pid_issuer")?;
+
     debug!("Discovering DigiD issuer...");
     let bsn_lookup = OpenIdClient::new(&settings.digid).await?;
 
-    // TODO: `MockAttributesLookup` issues a configured set of mock attributes. Replace with BRP query.
-    let attributes_lookup = MockAttributesLookup::from(settings.mock_data.clone().unwrap_or_default());
-    // This will block until the server shuts down.
-    server::serve(settings, attributes_lookup, bsn_lookup).await?;
+    // The attributes are retrieved from the BRP when it is configured, otherwise the configured mock attributes are
+    // issued. This will block until the server shuts down.
+    let result = match settings.brp.clone() {
+        Some(brp) => {
+            let attributes_lookup = BrpClient::new(&brp)?;
+            server::serve(settings, attributes_lookup, bsn_lookup).await
+        }
+        None => {
+            let attributes_lookup = MockAttributesLookup::from(settings.mock_attributes()?);
+            server::serve(settings, attributes_lookup, bsn_lookup).await
+        }
+    };
+    telemetry::shutdown_tracing();
+    result?;
 
     Ok(())
 }
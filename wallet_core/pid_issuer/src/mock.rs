@@ -1,20 +1,15 @@
 /// Mock implementations of the two traits abstracting other components
-use std::{collections::HashMap, ops::Add};
+use std::collections::HashMap;
 
-use chrono::{Days, Utc};
-use ciborium::Value;
-use indexmap::IndexMap;
-
-use nl_wallet_mdoc::{
-    basic_sa_ext::{Entry, UnsignedMdoc},
-    Tdate,
-};
 use rand::Rng;
-use serde::Deserialize;
-
-use crate::{digid, settings::MockAttributes};
 
-use crate::app::{AttributesLookup, BsnLookup};
+use crate::{
+    app::{AttributesLookup, BsnLookup},
+    attributes::{Attributes, Gender, PersonAttributes, ResidentAttributes},
+    brp,
+    digid::{self, DigidUser},
+    settings::MockAttributes,
+};
 
 pub struct MockBsnLookup(Vec<String>);
 
@@ -31,201 +26,24 @@ impl From<Vec<MockAttributes>> for MockBsnLookup {
 }
 
 impl BsnLookup for MockBsnLookup {
-    async fn bsn(&self, _access_token: &str) -> Result<String, digid::Error> {
-        Ok(self.0[rand::thread_rng().gen_range(0..self.0.len())].clone())
+    /// Returns the BSN that equals the access token, so that tests can select a specific person, or a random one of
+    /// the BSNs otherwise.
+    async fn user(&self, access_token: &str) -> Result<DigidUser, digid::Error> {
+        let bsn = self
+            .0
+            .iter()
+            .find(|bsn| *bsn == access_token)
+            .unwrap_or_else(|| &self.0[rand::thread_rng().gen_range(0..self.0.len())])
+            .clone();
+
+        Ok(DigidUser { bsn, scopes: vec![] })
     }
-}
 
-// ISO/IEC 5218
-#[allow(dead_code)]
-#[derive(Deserialize, Clone)]
-enum Gender {
-    Unknown,
-    Male,
-    Female,
-    NotApplicable,
-}
-
-impl From<Gender> for Value {
-    fn from(value: Gender) -> Value {
-        use Gender::*;
-        let value = match value {
-            Unknown => 0,
-            Male => 1,
-            Female => 2,
-            NotApplicable => 9,
-        };
-        Value::Integer(value.into())
+    async fn check_health(&self) -> Result<(), digid::Error> {
+        Ok(())
     }
 }
 
-const PID_BSN: &str = "bsn";
-
-const PID_FAMILY_NAME: &str = "family_name";
-const PID_GIVEN_NAME: &str = "given_name";
-const PID_BIRTH_DATE: &str = "birth_date";
-const PID_AGE_OVER_18: &str = "age_over_18";
-// const PID_AGE_OVER_NN: &str = "age_over_NN";
-// const PID_AGE_IN_YEARS: &str = "age_in_years";
-// const PID_AGE_BIRTH_YEAR: &str = "age_birth_year";
-const PID_FAMILY_NAME_BIRTH: &str = "family_name_birth";
-const PID_GIVEN_NAME_BIRTH: &str = "given_name_birth";
-const PID_BIRTH_PLACE: &str = "birth_place";
-const PID_BIRTH_COUNTRY: &str = "birth_country";
-const PID_BIRTH_STATE: &str = "birth_state";
-const PID_BIRTH_CITY: &str = "birth_city";
-const PID_RESIDENT_ADDRESS: &str = "resident_address";
-const PID_RESIDENT_COUNTRY: &str = "resident_country";
-const PID_RESIDENT_STATE: &str = "resident_state";
-const PID_RESIDENT_CITY: &str = "resident_city";
-const PID_RESIDENT_POSTAL_CODE: &str = "resident_postal_code";
-const PID_RESIDENT_STREET: &str = "resident_street";
-const PID_RESIDENT_HOUSE_NUMBER: &str = "resident_house_number";
-const PID_GENDER: &str = "gender";
-const PID_NATIONALITY: &str = "nationality";
-
-#[derive(Default, Deserialize, Clone)]
-pub struct PersonAttributes {
-    bsn: String,
-    family_name: String,
-    given_name: String,
-    birth_date: chrono::NaiveDate,
-    age_over_18: bool,
-    // age_over_NN: Option<bool>,
-    // age_in_years: Option<u32>,
-    // age_birth_year: Option<u32>,
-    family_name_birth: Option<String>,
-    given_name_birth: Option<String>,
-    birth_place: Option<String>,
-    birth_country: Option<String>,
-    birth_state: Option<String>,
-    birth_city: Option<String>,
-    gender: Option<Gender>,
-    nationality: Option<String>,
-}
-
-impl From<PersonAttributes> for Vec<Entry> {
-    fn from(value: PersonAttributes) -> Vec<Entry> {
-        vec![
-            Entry {
-                name: PID_BSN.to_string(),
-                value: Value::Text(value.bsn),
-            }
-            .into(),
-            Entry {
-                name: PID_FAMILY_NAME.to_string(),
-                value: Value::Text(value.family_name),
-            }
-            .into(),
-            Entry {
-                name: PID_GIVEN_NAME.to_string(),
-                value: Value::Text(value.given_name),
-            }
-            .into(),
-            Entry {
-                name: PID_BIRTH_DATE.to_string(),
-                value: Value::Text(value.birth_date.format("%Y-%m-%d").to_string()),
-            }
-            .into(),
-            Entry {
-                name: PID_AGE_OVER_18.to_string(),
-                value: Value::Bool(value.age_over_18),
-            }
-            .into(),
-            value.family_name_birth.map(|v| Entry {
-                name: PID_FAMILY_NAME_BIRTH.to_string(),
-                value: Value::Text(v),
-            }),
-            value.given_name_birth.map(|v| Entry {
-                name: PID_GIVEN_NAME_BIRTH.to_string(),
-                value: Value::Text(v),
-            }),
-            value.birth_place.map(|v| Entry {
-                name: PID_BIRTH_PLACE.to_string(),
-                value: Value::Text(v),
-            }),
-            value.birth_country.map(|v| Entry {
-                name: PID_BIRTH_COUNTRY.to_string(),
-                // TODO according to ISO 3166-1
-                value: Value::Text(v),
-            }),
-            value.birth_state.map(|v| Entry {
-                name: PID_BIRTH_STATE.to_string(),
-                value: Value::Text(v),
-            }),
-            value.birth_city.map(|v| Entry {
-                name: PID_BIRTH_CITY.to_string(),
-                value: Value::Text(v),
-            }),
-            value.gender.map(|v| Entry {
-                name: PID_GENDER.to_string(),
-                value: v.into(),
-            }),
-            value.nationality.map(|v| Entry {
-                name: PID_NATIONALITY.to_string(),
-                // TODO according to ISO 3166-1
-                value: Value::Text(v),
-            }),
-        ]
-        .into_iter()
-        .flatten()
-        .collect()
-    }
-}
-
-#[derive(Default, Deserialize, Clone)]
-pub struct ResidentAttributes {
-    address: Option<String>,
-    country: Option<String>,
-    state: Option<String>,
-    city: Option<String>,
-    postal_code: Option<String>,
-    street: Option<String>,
-    house_number: Option<String>,
-}
-
-impl From<ResidentAttributes> for Vec<Entry> {
-    fn from(value: ResidentAttributes) -> Vec<Entry> {
-        vec![
-            value.address.map(|v| Entry {
-                name: PID_RESIDENT_ADDRESS.to_string(),
-                value: Value::Text(v),
-            }),
-            value.country.map(|v| Entry {
-                name: PID_RESIDENT_COUNTRY.to_string(),
-                value: Value::Text(v),
-            }),
-            value.state.map(|v| Entry {
-                name: PID_RESIDENT_STATE.to_string(),
-                value: Value::Text(v),
-            }),
-            value.city.map(|v| Entry {
-                name: PID_RESIDENT_CITY.to_string(),
-                value: Value::Text(v),
-            }),
-            value.postal_code.map(|v| Entry {
-                name: PID_RESIDENT_POSTAL_CODE.to_string(),
-                value: Value::Text(v),
-            }),
-            value.street.map(|v| Entry {
-                name: PID_RESIDENT_STREET.to_string(),
-                value: Value::Text(v),
-            }),
-            value.house_number.map(|v| Entry {
-                name: PID_RESIDENT_HOUSE_NUMBER.to_string(),
-                value: Value::Text(v),
-            }),
-        ]
-        .into_iter()
-        .flatten()
-        .collect()
-    }
-}
-
-const MOCK_PID_DOCTYPE: &str = "com.example.pid";
-const MOCK_ADDRESS_DOCTYPE: &str = "com.example.address";
-
-type Attributes = (PersonAttributes, Option<ResidentAttributes>);
 pub struct MockAttributesLookup(HashMap<String, Attributes>);
 
 impl Default for MockAttributesLookup {
@@ -233,8 +51,8 @@ impl Default for MockAttributesLookup {
         let mut map = HashMap::new();
         map.insert(
             "999991772".to_owned(),
-            (
-                PersonAttributes {
+            Attributes {
+                person: PersonAttributes {
                     bsn: "999991772".to_owned(),
                     given_name: "Willeke Liselotte".to_owned(),
                     family_name: "De Bruijn".to_owned(),
@@ -248,7 +66,7 @@ impl Default for MockAttributesLookup {
                     nationality: Some("NL".to_owned()),
                     ..PersonAttributes::default()
                 },
-                Some(ResidentAttributes {
+                resident: Some(ResidentAttributes {
                     street: Some("Turfmarkt".to_owned()),
                     house_number: Some("147".to_owned()),
                     postal_code: Some("2511 DP".to_owned()),
@@ -257,7 +75,7 @@ impl Default for MockAttributesLookup {
                     country: Some("NL".to_owned()),
                     ..ResidentAttributes::default()
                 }),
-            ),
+            },
         );
         Self(map)
     }
@@ -268,34 +86,55 @@ impl From<Vec<MockAttributes>> for MockAttributesLookup {
         Self(
             value
                 .iter()
-                .map(|p| (p.person.bsn.clone(), (p.person.clone(), p.resident.clone())))
+                .map(|p| {
+                    (
+                        p.person.bsn.clone(),
+                        Attributes {
+                            person: p.person.clone(),
+                            resident: p.resident.clone(),
+                        },
+                    )
+                })
                 .collect(),
         )
     }
 }
 
 impl AttributesLookup for MockAttributesLookup {
-    fn attributes(&self, bsn: &str) -> Option<Vec<UnsignedMdoc>> {
-        self.0.get(bsn).map(|(person, residence)| {
-            vec![
-                UnsignedMdoc {
-                    doc_type: MOCK_PID_DOCTYPE.to_string(),
-                    copy_count: 10,
-                    valid_from: Tdate::now(),
-                    valid_until: Utc::now().add(Days::new(365)).into(),
-                    attributes: IndexMap::from([(MOCK_PID_DOCTYPE.to_string(), person.clone().into())]),
-                },
-                UnsignedMdoc {
-                    doc_type: MOCK_ADDRESS_DOCTYPE.to_string(),
-                    copy_count: 10,
-                    valid_from: Tdate::now(),
-                    valid_until: Utc::now().add(Days::new(365)).into(),
-                    attributes: IndexMap::from([(
-                        MOCK_ADDRESS_DOCTYPE.to_string(),
-                        residence.clone().unwrap_or_default().into(),
-                    )]),
-                },
-            ]
-        })
+    async fn attributes(&self, bsn: &str) -> Result<Option<Attributes>, brp::Error> {
+        Ok(self.0.get(bsn).cloned())
+    }
+
+    async fn check_health(&self) -> Result<(), brp::Error> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_test_persons() {
+        let persons = MockAttributes::load(&Path::new(env!("CARGO_MANIFEST_DIR")).join("test_persons.toml")).unwrap();
+        let bsn_lookup = MockBsnLookup::from(persons.clone());
+        let attributes_lookup = MockAttributesLookup::from(persons);
+
+        // The access token selects the person with that BSN.
+        let user = bsn_lookup.user("999991796").await.unwrap();
+        assert_eq!(user.bsn, "999991796");
+
+        let attributes = attributes_lookup.attributes(&user.bsn).await.unwrap().unwrap();
+        assert_eq!(attributes.person.given_name, "Zoë Anaïs");
+        assert_eq!(attributes.person.birth_city.as_deref(), Some("İzmir"));
+
+        // Persons without a known residence are supported.
+        let attributes = attributes_lookup.attributes("999991826").await.unwrap().unwrap();
+        assert!(attributes.resident.is_none());
+        assert!(attributes.person.gender.is_none());
+
+        assert!(attributes_lookup.attributes("123456789").await.unwrap().is_none());
     }
 }
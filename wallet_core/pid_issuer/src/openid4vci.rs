@@ -0,0 +1,605 @@
+//! Issuance of the configured doc types using OpenID for Verifiable Credential Issuance (draft 13), alongside the
+//! issuance protocol of the NL wallet, so that standards-compliant wallets of third parties can obtain PIDs as well,
+//! e.g. in test environments.
+//!
+//! Only the pre-authorized code flow is supported. The credential offer is created after the user has authenticated
+//! with DigiD, after which the wallet redeems the pre-authorized code for an access token. Using that access token,
+//! the wallet requests every copy of the offered doc types in the `mso_mdoc` format, each of which is bound to the key
+//! in the JWT proof of that request.
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use axum::{
+    response::{IntoResponse, Response},
+    Json,
+};
+use base64::prelude::*;
+use http::StatusCode;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use nl_wallet_mdoc::{
+    basic_sa_ext::UnsignedMdoc,
+    issuer::IssuanceObserver,
+    issuer_shared::IssuanceError,
+    server_keys::KeyRing,
+    status_list::StatusListAllocator,
+    utils::{cose::CoseKey, serialization::cbor_serialize},
+    IssuerSigned, MsoStatus,
+};
+use wallet_common::{
+    jwt::{validations, Jwt, JwtError},
+    utils::random_string,
+};
+
+use crate::settings;
+
+pub const PRE_AUTHORIZED_CODE_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:pre-authorized_code";
+const PROOF_JWT_TYPE: &str = "openid4vci-proof+jwt";
+const MSO_MDOC_FORMAT: &str = "mso_mdoc";
+const SIGNING_ALGORITHM: &str = "ES256";
+
+const CODE_LENGTH: usize = 32;
+
+#[derive(Debug, thiserror::Error)]
+pub enum OpenId4VciError {
+    #[error("unsupported grant type: {0}")]
+    UnsupportedGrantType(String),
+    #[error("pre-authorized code is missing, unknown or has expired")]
+    InvalidGrant,
+    #[error("access token is unknown or has expired")]
+    InvalidToken,
+    #[error("invalid credential request: {0}")]
+    InvalidCredentialRequest(&'static str),
+    #[error("unsupported credential format: {0}")]
+    UnsupportedCredentialFormat(String),
+    #[error("doc type {0} has not been offered or all of its copies have been issued")]
+    UnsupportedCredentialType(String),
+    #[error("invalid proof: {0}")]
+    InvalidProof(#[source] JwtError),
+    #[error("proof does not contain the current nonce")]
+    InvalidNonce,
+    #[error("could not issue credential: {0}")]
+    Issuance(#[source] nl_wallet_mdoc::Error),
+}
+
+impl OpenId4VciError {
+    /// The error code as defined by RFC 6749 for the token endpoint and by OpenID4VCI for the credential endpoint.
+    fn error_code(&self) -> &'static str {
+        match self {
+            OpenId4VciError::UnsupportedGrantType(_) => "unsupported_grant_type",
+            OpenId4VciError::InvalidGrant => "invalid_grant",
+            OpenId4VciError::InvalidToken => "invalid_token",
+            OpenId4VciError::InvalidCredentialRequest(_) => "invalid_credential_request",
+            OpenId4VciError::UnsupportedCredentialFormat(_) => "unsupported_credential_format",
+            OpenId4VciError::UnsupportedCredentialType(_) => "unsupported_credential_type",
+            OpenId4VciError::InvalidProof(_) | OpenId4VciError::InvalidNonce => "invalid_proof",
+            OpenId4VciError::Issuance(_) => "server_error",
+        }
+    }
+
+    fn status_code(&self) -> StatusCode {
+        match self {
+            OpenId4VciError::InvalidToken => StatusCode::UNAUTHORIZED,
+            OpenId4VciError::Issuance(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ErrorResponse {
+    error: &'static str,
+    error_description: String,
+}
+
+impl IntoResponse for OpenId4VciError {
+    fn into_response(self) -> Response {
+        let body = ErrorResponse {
+            error: self.error_code(),
+            error_description: self.to_string(),
+        };
+
+        (self.status_code(), Json(body)).into_response()
+    }
+}
+
+#[derive(Serialize)]
+pub struct CredentialIssuerMetadata {
+    credential_issuer: Url,
+    credential_endpoint: Url,
+    credential_configurations_supported: IndexMap<String, CredentialConfiguration>,
+}
+
+#[derive(Serialize)]
+struct CredentialConfiguration {
+    format: &'static str,
+    doctype: String,
+    cryptographic_binding_methods_supported: [&'static str; 1],
+    credential_signing_alg_values_supported: [&'static str; 1],
+    proof_types_supported: ProofTypesSupported,
+}
+
+#[derive(Serialize)]
+struct ProofTypesSupported {
+    jwt: ProofTypeSupported,
+}
+
+#[derive(Serialize)]
+struct ProofTypeSupported {
+    proof_signing_alg_values_supported: [&'static str; 1],
+}
+
+/// As the PID issuer is its own authorization server, this is published alongside the credential issuer metadata.
+#[derive(Serialize)]
+pub struct AuthorizationServerMetadata {
+    issuer: Url,
+    token_endpoint: Url,
+    grant_types_supported: [&'static str; 1],
+    #[serde(rename = "pre-authorized_grant_anonymous_access_supported")]
+    pre_authorized_grant_anonymous_access_supported: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CredentialOffer {
+    pub credential_issuer: Url,
+    pub credential_configuration_ids: Vec<String>,
+    pub grants: CredentialOfferGrants,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CredentialOfferGrants {
+    #[serde(rename = "urn:ietf:params:oauth:grant-type:pre-authorized_code")]
+    pub pre_authorized_code: PreAuthorizedCodeGrant,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PreAuthorizedCodeGrant {
+    #[serde(rename = "pre-authorized_code")]
+    pub pre_authorized_code: String,
+}
+
+/// The form encoded body of a token request.
+#[derive(Debug, Deserialize)]
+pub struct TokenRequest {
+    pub grant_type: String,
+    #[serde(rename = "pre-authorized_code")]
+    pub pre_authorized_code: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TokenResponse {
+    pub access_token: String,
+    pub token_type: &'static str,
+    pub expires_in: u64,
+    pub c_nonce: String,
+    pub c_nonce_expires_in: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CredentialRequest {
+    pub format: String,
+    pub doctype: Option<String>,
+    pub proof: Option<CredentialRequestProof>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "proof_type", rename_all = "snake_case")]
+pub enum CredentialRequestProof {
+    Jwt { jwt: Jwt<ProofClaims> },
+}
+
+/// The claims of a JWT proof. The audience is checked while verifying the JWT, the issuance time only has to be
+/// present.
+#[derive(Debug, Deserialize)]
+pub struct ProofClaims {
+    pub nonce: Option<String>,
+    pub iat: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CredentialResponse {
+    pub format: &'static str,
+    /// URL-safe Base64 encoded CBOR `IssuerSigned`, without padding.
+    pub credential: String,
+    pub c_nonce: String,
+    pub c_nonce_expires_in: u64,
+}
+
+struct Offer {
+    bsn: String,
+    unsigned_mdocs: Vec<UnsignedMdoc>,
+    expires: Instant,
+}
+
+/// The `copy_count` of the unsigned mdocs is the number of copies that can still be requested.
+struct Session {
+    bsn: String,
+    unsigned_mdocs: Vec<UnsignedMdoc>,
+    c_nonce: String,
+    expires: Instant,
+}
+
+/// Keeps the credential offers and the sessions of the wallets that redeemed these in memory, so that these are lost
+/// on restart and are not shared between instances.
+pub struct OpenId4VciIssuer {
+    credential_issuer: Url,
+    ttl: Duration,
+    offers: Mutex<HashMap<String, Offer>>,
+    sessions: Mutex<HashMap<String, Session>>,
+}
+
+impl OpenId4VciIssuer {
+    /// The `public_url` of the PID issuer, ending with a slash, is used as the credential issuer identifier.
+    pub fn new(settings: &settings::OpenId4Vci, public_url: Url) -> Self {
+        Self {
+            credential_issuer: public_url,
+            ttl: Duration::from_secs(settings.session_ttl_in_seconds),
+            offers: Mutex::new(HashMap::new()),
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn endpoint(&self, path: &str) -> Url {
+        self.credential_issuer
+            .join(path)
+            .expect("endpoint should be a valid relative URL")
+    }
+
+    pub fn issuer_metadata<'a>(&self, doc_types: impl Iterator<Item = &'a str>) -> CredentialIssuerMetadata {
+        let credential_configurations_supported = doc_types
+            .map(|doc_type| {
+                let configuration = CredentialConfiguration {
+                    format: MSO_MDOC_FORMAT,
+                    doctype: doc_type.to_string(),
+                    cryptographic_binding_methods_supported: ["cose_key"],
+                    credential_signing_alg_values_supported: [SIGNING_ALGORITHM],
+                    proof_types_supported: ProofTypesSupported {
+                        jwt: ProofTypeSupported {
+                            proof_signing_alg_values_supported: [SIGNING_ALGORITHM],
+                        },
+                    },
+                };
+
+                (doc_type.to_string(), configuration)
+            })
+            .collect();
+
+        CredentialIssuerMetadata {
+            credential_issuer: self.credential_issuer.clone(),
+            credential_endpoint: self.endpoint("openid4vci/credential"),
+            credential_configurations_supported,
+        }
+    }
+
+    pub fn authorization_server_metadata(&self) -> AuthorizationServerMetadata {
+        AuthorizationServerMetadata {
+            issuer: self.credential_issuer.clone(),
+            token_endpoint: self.endpoint("openid4vci/token"),
+            grant_types_supported: [PRE_AUTHORIZED_CODE_GRANT_TYPE],
+            pre_authorized_grant_anonymous_access_supported: true,
+        }
+    }
+
+    /// Offer the mdocs to be issued to the person with the specified BSN, which can be obtained by any wallet that the
+    /// offer is passed on to within the configured time.
+    pub fn new_offer(&self, bsn: String, unsigned_mdocs: Vec<UnsignedMdoc>) -> CredentialOffer {
+        let pre_authorized_code = random_string(CODE_LENGTH);
+        let credential_configuration_ids = unsigned_mdocs
+            .iter()
+            .map(|unsigned_mdoc| unsigned_mdoc.doc_type.clone())
+            .collect();
+        let now = Instant::now();
+
+        let mut offers = self.offers.lock().unwrap();
+        // Offers that are never redeemed would otherwise accumulate.
+        offers.retain(|_, offer| offer.expires > now);
+        offers.insert(
+            pre_authorized_code.clone(),
+            Offer {
+                bsn,
+                unsigned_mdocs,
+                expires: now + self.ttl,
+            },
+        );
+
+        CredentialOffer {
+            credential_issuer: self.credential_issuer.clone(),
+            credential_configuration_ids,
+            grants: CredentialOfferGrants {
+                pre_authorized_code: PreAuthorizedCodeGrant { pre_authorized_code },
+            },
+        }
+    }
+
+    /// Redeem a pre-authorized code for an access token, which can only be done once.
+    pub fn token(&self, request: TokenRequest) -> Result<TokenResponse, OpenId4VciError> {
+        if request.grant_type != PRE_AUTHORIZED_CODE_GRANT_TYPE {
+            return Err(OpenId4VciError::UnsupportedGrantType(request.grant_type));
+        }
+
+        let now = Instant::now();
+        let offer = request
+            .pre_authorized_code
+            .and_then(|code| self.offers.lock().unwrap().remove(&code))
+            .filter(|offer| offer.expires > now)
+            .ok_or(OpenId4VciError::InvalidGrant)?;
+
+        let access_token = random_string(CODE_LENGTH);
+        let c_nonce = random_string(CODE_LENGTH);
+
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions.retain(|_, session| session.expires > now);
+        sessions.insert(
+            access_token.clone(),
+            Session {
+                bsn: offer.bsn,
+                unsigned_mdocs: offer.unsigned_mdocs,
+                c_nonce: c_nonce.clone(),
+                expires: now + self.ttl,
+            },
+        );
+
+        Ok(TokenResponse {
+            access_token,
+            token_type: "Bearer",
+            expires_in: self.ttl.as_secs(),
+            c_nonce,
+            c_nonce_expires_in: self.ttl.as_secs(),
+        })
+    }
+
+    /// Issue a copy of the requested doc type, bound to the key of the proof in the request. Every nonce can be used
+    /// for a single copy only, so a new nonce is returned along with the credential.
+    pub async fn credential<K: KeyRing>(
+        &self,
+        access_token: &str,
+        request: CredentialRequest,
+        keys: &K,
+        status_lists: Option<&dyn StatusListAllocator>,
+        observer: Option<&dyn IssuanceObserver>,
+    ) -> Result<CredentialResponse, OpenId4VciError> {
+        if request.format != MSO_MDOC_FORMAT {
+            return Err(OpenId4VciError::UnsupportedCredentialFormat(request.format));
+        }
+        let doc_type = request
+            .doctype
+            .ok_or(OpenId4VciError::InvalidCredentialRequest("doctype is missing"))?;
+        let Some(CredentialRequestProof::Jwt { jwt }) = request.proof else {
+            return Err(OpenId4VciError::InvalidCredentialRequest("proof is missing"));
+        };
+
+        let mut validation_options = validations();
+        validation_options.set_audience(&[self.credential_issuer.as_str()]);
+        validation_options.required_spec_claims.insert("aud".to_string());
+
+        let (unsigned_mdoc, bsn, pubkey, c_nonce) = {
+            let mut sessions = self.sessions.lock().unwrap();
+            let session = sessions
+                .get_mut(access_token)
+                .filter(|session| session.expires > Instant::now())
+                .ok_or(OpenId4VciError::InvalidToken)?;
+
+            let unsigned_mdoc = session
+                .unsigned_mdocs
+                .iter_mut()
+                .find(|unsigned_mdoc| unsigned_mdoc.doc_type == doc_type && unsigned_mdoc.copy_count > 0)
+                .ok_or_else(|| OpenId4VciError::UnsupportedCredentialType(doc_type.clone()))?;
+
+            let (claims, pubkey) = jwt
+                .parse_and_verify_with_jwk(PROOF_JWT_TYPE, &validation_options)
+                .map_err(OpenId4VciError::InvalidProof)?;
+            if claims.nonce.as_deref() != Some(session.c_nonce.as_str()) {
+                return Err(OpenId4VciError::InvalidNonce);
+            }
+
+            unsigned_mdoc.copy_count -= 1;
+            session.c_nonce = random_string(CODE_LENGTH);
+
+            (
+                unsigned_mdoc.clone(),
+                session.bsn.clone(),
+                pubkey,
+                session.c_nonce.clone(),
+            )
+        };
+
+        let private_key = keys
+            .private_key(&doc_type)
+            .ok_or_else(|| OpenId4VciError::Issuance(IssuanceError::MissingPrivateKey(doc_type.clone()).into()))?;
+        let status = status_lists
+            .map(|status_lists| status_lists.allocate(&doc_type, private_key.certificate(), Some(&bsn)))
+            .transpose()
+            .map_err(|error| OpenId4VciError::Issuance(error.into()))?
+            .flatten()
+            .map(|status_list| MsoStatus { status_list });
+        let device_public_key = CoseKey::try_from(&pubkey).map_err(OpenId4VciError::Issuance)?;
+
+        let (issuer_signed, _) = IssuerSigned::sign(unsigned_mdoc, device_public_key, private_key, status)
+            .await
+            .map_err(OpenId4VciError::Issuance)?;
+        let credential = cbor_serialize(&issuer_signed).map_err(|error| OpenId4VciError::Issuance(error.into()))?;
+
+        if let Some(observer) = observer {
+            observer.issued(&[doc_type], Some(&bsn));
+        }
+
+        Ok(CredentialResponse {
+            format: MSO_MDOC_FORMAT,
+            credential: BASE64_URL_SAFE_NO_PAD.encode(credential),
+            c_nonce,
+            c_nonce_expires_in: self.ttl.as_secs(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use chrono::{Days, Utc};
+    use ciborium::Value;
+    use serde_json::json;
+
+    use nl_wallet_mdoc::{
+        basic_sa_ext::Entry, mock::generate_issuance_key_and_ca, server_keys::PrivateKey,
+        utils::serialization::cbor_deserialize, Tdate,
+    };
+    use wallet_common::{
+        jwt::{header, Jwk},
+        keys::EcdsaKey,
+    };
+
+    use super::*;
+
+    const DOC_TYPE: &str = "com.example.pid";
+
+    struct SingleKeyRing(PrivateKey);
+
+    impl KeyRing for SingleKeyRing {
+        fn private_key(&self, _: &str) -> Option<&PrivateKey> {
+            Some(&self.0)
+        }
+    }
+
+    #[derive(Serialize)]
+    struct TestProofClaims<'a> {
+        aud: &'a str,
+        nonce: &'a str,
+        iat: i64,
+    }
+
+    async fn credential_request(holder_key: &PrivateKey, nonce: &str) -> CredentialRequest {
+        let jwk = Jwk::new(&holder_key.verifying_key().await.unwrap(), "sig");
+        let mut header = header();
+        header.typ = Some(PROOF_JWT_TYPE.to_string());
+        header.jwk = serde_json::from_value(json!({"kty": jwk.kty, "crv": jwk.crv, "x": jwk.x, "y": jwk.y})).unwrap();
+
+        let claims = TestProofClaims {
+            aud: "https://pid.example.com/",
+            nonce,
+            iat: Utc::now().timestamp(),
+        };
+        let jwt = Jwt::sign(&claims, &header, holder_key).await.unwrap();
+
+        CredentialRequest {
+            format: MSO_MDOC_FORMAT.to_string(),
+            doctype: Some(DOC_TYPE.to_string()),
+            proof: Some(CredentialRequestProof::Jwt { jwt: jwt.0.into() }),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pre_authorized_code_flow() {
+        let issuer = OpenId4VciIssuer::new(
+            &settings::OpenId4Vci {
+                session_ttl_in_seconds: 60,
+            },
+            "https://pid.example.com/".parse().unwrap(),
+        );
+        let keys = SingleKeyRing(generate_issuance_key_and_ca().unwrap().0);
+        let holder_key = generate_issuance_key_and_ca().unwrap().0;
+
+        let unsigned_mdoc = UnsignedMdoc {
+            doc_type: DOC_TYPE.to_string(),
+            valid_from: Tdate::now(),
+            valid_until: (Utc::now() + Days::new(1)).into(),
+            attributes: IndexMap::from([(
+                DOC_TYPE.to_string(),
+                vec![Entry {
+                    name: "bsn".to_string(),
+                    value: Value::Text("999991772".to_string()),
+                }],
+            )]),
+            copy_count: 2,
+        };
+        let offer = issuer.new_offer("999991772".to_string(), vec![unsigned_mdoc]);
+        assert_eq!(offer.credential_configuration_ids, vec![DOC_TYPE.to_string()]);
+        let pre_authorized_code = offer.grants.pre_authorized_code.pre_authorized_code;
+
+        assert_matches!(
+            issuer.token(TokenRequest {
+                grant_type: "authorization_code".to_string(),
+                pre_authorized_code: Some(pre_authorized_code.clone()),
+            }),
+            Err(OpenId4VciError::UnsupportedGrantType(_))
+        );
+
+        let token_request = || TokenRequest {
+            grant_type: PRE_AUTHORIZED_CODE_GRANT_TYPE.to_string(),
+            pre_authorized_code: Some(pre_authorized_code.clone()),
+        };
+        let token_response = issuer.token(token_request()).unwrap();
+
+        // The pre-authorized code can only be redeemed once.
+        assert_matches!(issuer.token(token_request()), Err(OpenId4VciError::InvalidGrant));
+
+        // A proof with another nonce is rejected.
+        assert_matches!(
+            issuer
+                .credential(
+                    &token_response.access_token,
+                    credential_request(&holder_key, "other_nonce").await,
+                    &keys,
+                    None,
+                    None,
+                )
+                .await,
+            Err(OpenId4VciError::InvalidNonce)
+        );
+
+        // Every copy is bound to the key of the proof, using a new nonce for every copy.
+        let mut c_nonce = token_response.c_nonce;
+        for _ in 0..2 {
+            let response = issuer
+                .credential(
+                    &token_response.access_token,
+                    credential_request(&holder_key, &c_nonce).await,
+                    &keys,
+                    None,
+                    None,
+                )
+                .await
+                .unwrap();
+            assert_ne!(response.c_nonce, c_nonce);
+            c_nonce = response.c_nonce;
+
+            let issuer_signed: IssuerSigned =
+                cbor_deserialize(BASE64_URL_SAFE_NO_PAD.decode(response.credential).unwrap().as_slice()).unwrap();
+            assert_eq!(
+                issuer_signed.public_key().unwrap(),
+                holder_key.verifying_key().await.unwrap()
+            );
+        }
+
+        // No more copies can be requested than were offered.
+        assert_matches!(
+            issuer
+                .credential(
+                    &token_response.access_token,
+                    credential_request(&holder_key, &c_nonce).await,
+                    &keys,
+                    None,
+                    None,
+                )
+                .await,
+            Err(OpenId4VciError::UnsupportedCredentialType(_))
+        );
+
+        assert_matches!(
+            issuer
+                .credential(
+                    "other_token",
+                    credential_request(&holder_key, &c_nonce).await,
+                    &keys,
+                    None,
+                    None,
+                )
+                .await,
+            Err(OpenId4VciError::InvalidToken)
+        );
+    }
+}
@@ -0,0 +1,308 @@
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use http::HeaderMap;
+use metrics::increment_counter;
+
+use wallet_common::utils::random_bytes;
+
+use crate::{
+    settings::{RateLimit, RateLimits},
+    status_lists::subject_digest,
+};
+
+/// Header containing the addresses of the client and the proxies that forwarded the request, of which the last one is
+/// appended by the reverse proxy directly in front of the PID issuer.
+const FORWARDED_FOR_HEADER: &str = "x-forwarded-for";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitScope {
+    Ip,
+    Bsn,
+}
+
+impl RateLimitScope {
+    fn as_str(self) -> &'static str {
+        match self {
+            RateLimitScope::Ip => "ip",
+            RateLimitScope::Bsn => "bsn",
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("too many issuance attempts per {}, retry after {} ms", .scope.as_str(), .retry_after.as_millis())]
+pub struct RateLimitExceeded {
+    pub scope: RateLimitScope,
+    pub retry_after: Duration,
+}
+
+/// The BSN is only kept as a keyed digest, so that the rate limiter does not hold the BSNs of everyone that recently
+/// attempted issuance. As the key is generated randomly per process and never leaves it, the BSNs cannot be recovered
+/// from the digests by going over all possible BSNs.
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum RateLimitKey {
+    Ip(IpAddr),
+    Bsn(String),
+}
+
+struct RateLimitWindow {
+    start: Instant,
+    attempts: u32,
+}
+
+struct RateLimitState {
+    last_cleanup: Instant,
+    windows: HashMap<RateLimitKey, RateLimitWindow>,
+}
+
+/// Limits the number of issuance attempts, both per IP address of the client and per BSN, within a fixed window of
+/// time. Every IP address and BSN has its own window, which starts at its first attempt. The IP address is checked
+/// before DigiD is contacted, the BSN as soon as it is known.
+///
+/// Note that the windows are kept in memory, so these only limit the attempts that are handled by the same instance.
+pub struct RateLimiter {
+    per_ip: Option<(u32, Duration)>,
+    per_bsn: Option<(u32, Duration)>,
+    use_forwarded_for: bool,
+    max_window: Duration,
+    bsn_secret: Vec<u8>,
+    state: Mutex<RateLimitState>,
+}
+
+fn limit(rate_limit: Option<RateLimit>) -> Option<(u32, Duration)> {
+    rate_limit.map(|rate_limit| (rate_limit.max_requests, Duration::from_millis(rate_limit.window_in_ms)))
+}
+
+impl RateLimiter {
+    pub fn new(settings: &RateLimits) -> Self {
+        let per_ip = limit(settings.per_ip);
+        let per_bsn = limit(settings.per_bsn);
+
+        Self {
+            per_ip,
+            per_bsn,
+            use_forwarded_for: settings.use_forwarded_for,
+            max_window: [per_ip, per_bsn]
+                .into_iter()
+                .flatten()
+                .map(|(_, window)| window)
+                .max()
+                .unwrap_or_default(),
+            bsn_secret: random_bytes(32),
+            state: Mutex::new(RateLimitState {
+                last_cleanup: Instant::now(),
+                windows: HashMap::new(),
+            }),
+        }
+    }
+
+    /// Determines the IP address of the client, which is taken from the `X-Forwarded-For` header if configured and
+    /// present, and from the connection otherwise.
+    pub fn client_ip(&self, headers: &HeaderMap, peer: Option<SocketAddr>) -> Option<IpAddr> {
+        let forwarded_for = self
+            .use_forwarded_for
+            .then(|| headers.get(FORWARDED_FOR_HEADER))
+            .flatten()
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.rsplit(',').next())
+            .and_then(|address| address.trim().parse().ok());
+
+        forwarded_for.or(peer.map(|peer| peer.ip()))
+    }
+
+    /// Registers an issuance attempt from the client with the specified IP address, returning an error if it exceeds
+    /// the limit. Attempts of which the IP address is not known are not limited per IP address.
+    pub fn check_ip(&self, ip: Option<IpAddr>) -> Result<(), RateLimitExceeded> {
+        self.check_at(RateLimitScope::Ip, ip.map(RateLimitKey::Ip), Instant::now())
+    }
+
+    /// Registers an issuance attempt for the person with the specified BSN, returning an error if it exceeds the limit.
+    pub fn check_bsn(&self, bsn: &str) -> Result<(), RateLimitExceeded> {
+        self.check_at(RateLimitScope::Bsn, Some(self.bsn_key(bsn)), Instant::now())
+    }
+
+    fn bsn_key(&self, bsn: &str) -> RateLimitKey {
+        RateLimitKey::Bsn(subject_digest(&self.bsn_secret, bsn))
+    }
+
+    fn check_at(
+        &self,
+        scope: RateLimitScope,
+        key: Option<RateLimitKey>,
+        now: Instant,
+    ) -> Result<(), RateLimitExceeded> {
+        let limit = match scope {
+            RateLimitScope::Ip => self.per_ip,
+            RateLimitScope::Bsn => self.per_bsn,
+        };
+        let Some(((max_attempts, window), key)) = limit.zip(key) else {
+            return Ok(());
+        };
+
+        let result = self
+            .state
+            .lock()
+            .unwrap()
+            .check(key, max_attempts, window, self.max_window, now)
+            .map_err(|retry_after| RateLimitExceeded { scope, retry_after });
+
+        if result.is_err() {
+            increment_counter!("pid_issuer_rate_limited_attempts_total", "scope" => scope.as_str());
+        }
+
+        result
+    }
+}
+
+impl RateLimitState {
+    /// Registers an attempt in the window of `key`, returning the time remaining until the window ends if the attempt
+    /// exceeds the maximum.
+    fn check(
+        &mut self,
+        key: RateLimitKey,
+        max_attempts: u32,
+        window: Duration,
+        max_window: Duration,
+        now: Instant,
+    ) -> Result<(), Duration> {
+        // Remove the windows that have ended at most once per longest window, so that these do not accumulate without
+        // having to go over all windows on every attempt.
+        if now.saturating_duration_since(self.last_cleanup) >= max_window {
+            self.windows
+                .retain(|_, window| now.saturating_duration_since(window.start) < max_window);
+            self.last_cleanup = now;
+        }
+
+        let entry = self.windows.entry(key).or_insert(RateLimitWindow {
+            start: now,
+            attempts: 0,
+        });
+
+        if now.saturating_duration_since(entry.start) >= window {
+            entry.start = now;
+            entry.attempts = 0;
+        }
+
+        if entry.attempts >= max_attempts {
+            return Err(window - now.saturating_duration_since(entry.start));
+        }
+
+        entry.attempts += 1;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use http::HeaderValue;
+
+    use super::*;
+
+    fn rate_limiter(use_forwarded_for: bool) -> RateLimiter {
+        RateLimiter::new(&RateLimits {
+            per_ip: Some(RateLimit {
+                max_requests: 3,
+                window_in_ms: 10_000,
+            }),
+            per_bsn: Some(RateLimit {
+                max_requests: 2,
+                window_in_ms: 10_000,
+            }),
+            use_forwarded_for,
+        })
+    }
+
+    #[test]
+    fn test_rate_limiter() {
+        let rate_limiter = rate_limiter(false);
+        let bsn_key = |bsn: &str| Some(rate_limiter.bsn_key(bsn));
+        let ip = Some(RateLimitKey::Ip("192.0.2.1".parse().unwrap()));
+        let start = Instant::now();
+
+        rate_limiter
+            .check_at(RateLimitScope::Bsn, bsn_key("999991772"), start)
+            .unwrap();
+        rate_limiter
+            .check_at(RateLimitScope::Bsn, bsn_key("999991772"), start)
+            .unwrap();
+
+        // The third attempt for the same BSN is rejected, with the time left until the window ends.
+        assert_matches!(
+            rate_limiter.check_at(RateLimitScope::Bsn, bsn_key("999991772"), start + Duration::from_secs(4)),
+            Err(RateLimitExceeded { scope: RateLimitScope::Bsn, retry_after })
+                if retry_after == Duration::from_secs(6)
+        );
+
+        // Other BSNs are not affected.
+        rate_limiter
+            .check_at(RateLimitScope::Bsn, bsn_key("999991773"), start)
+            .unwrap();
+
+        // The fourth attempt from the same IP address is rejected.
+        for _ in 0..3 {
+            rate_limiter.check_at(RateLimitScope::Ip, ip.clone(), start).unwrap();
+        }
+        assert_matches!(
+            rate_limiter.check_at(RateLimitScope::Ip, ip.clone(), start),
+            Err(RateLimitExceeded {
+                scope: RateLimitScope::Ip,
+                ..
+            })
+        );
+
+        // Attempts of which the IP address is unknown are not limited.
+        rate_limiter.check_at(RateLimitScope::Ip, None, start).unwrap();
+
+        // After the windows have ended, attempts are accepted again.
+        rate_limiter
+            .check_at(
+                RateLimitScope::Bsn,
+                bsn_key("999991772"),
+                start + Duration::from_secs(10),
+            )
+            .unwrap();
+        rate_limiter
+            .check_at(RateLimitScope::Ip, ip, start + Duration::from_secs(10))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_bsn_key() {
+        let (first, second) = (rate_limiter(false), rate_limiter(false));
+
+        // The digest of a BSN is stable within a rate limiter, but differs between rate limiters.
+        assert!(first.bsn_key("999991772") == first.bsn_key("999991772"));
+        assert!(first.bsn_key("999991772") != first.bsn_key("999991773"));
+        assert!(first.bsn_key("999991772") != second.bsn_key("999991772"));
+    }
+
+    #[test]
+    fn test_client_ip() {
+        let peer = Some("192.0.2.1:1234".parse().unwrap());
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            FORWARDED_FOR_HEADER,
+            HeaderValue::from_static("198.51.100.1, 203.0.113.1"),
+        );
+
+        assert_eq!(
+            rate_limiter(false).client_ip(&headers, peer),
+            Some("192.0.2.1".parse().unwrap())
+        );
+        assert_eq!(
+            rate_limiter(true).client_ip(&headers, peer),
+            Some("203.0.113.1".parse().unwrap())
+        );
+        assert_eq!(
+            rate_limiter(true).client_ip(&HeaderMap::new(), peer),
+            Some("192.0.2.1".parse().unwrap())
+        );
+    }
+}
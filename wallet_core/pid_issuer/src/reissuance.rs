@@ -0,0 +1,167 @@
+//! Re-issuance of the mdocs of a holder that proves possession of a valid mdoc issued before, e.g. to replenish the
+//! copies of its mdocs, without authenticating the holder using DigiD again.
+//!
+//! The holder first obtains a challenge, which it signs with the private key of the mdoc. The BSN of the holder is
+//! taken from the attributes of that mdoc, after which a regular issuance session is started.
+use std::{
+    collections::HashMap,
+    error::Error,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use base64::prelude::*;
+use ciborium::Value;
+
+use nl_wallet_mdoc::{basic_sa_ext::ReissuanceRequest, status_list::StatusType};
+use wallet_common::{
+    generator::TimeGenerator,
+    trust_anchor::{DerTrustAnchor, OwnedTrustAnchor},
+    utils::random_bytes,
+};
+
+use crate::{
+    attributes::DocTypes,
+    keys::{certificate_fingerprint, IssuerKeyRing},
+    settings,
+    status_lists::StatusRegistry,
+};
+
+/// The source field of the attribute containing the BSN of the holder.
+const BSN_SOURCE: &str = "person.bsn";
+
+const CHALLENGE_LENGTH: usize = 32;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ReissuanceError {
+    #[error("could not decode trust anchor: {0}")]
+    TrustAnchor(#[source] Box<dyn Error + Send + Sync>),
+    #[error("doc type {0} should contain an attribute with source field {BSN_SOURCE}")]
+    MissingBsnAttribute(String),
+    #[error("challenge is unknown, expired or has already been used")]
+    UnknownChallenge,
+    #[error("could not decode re-issuance request: {0}")]
+    Decoding(#[source] nl_wallet_mdoc::Error),
+    #[error("could not verify proof of possession: {0}")]
+    Verification(#[source] nl_wallet_mdoc::Error),
+    #[error("proof of possession should be of doc type {expected}, received {received}")]
+    UnexpectedDocType { expected: String, received: String },
+    #[error("mdoc is not signed with a certificate of the PID issuer")]
+    UnknownCertificate,
+    #[error("mdoc has been revoked")]
+    Revoked,
+    #[error("mdoc does not contain a BSN")]
+    MissingBsn,
+}
+
+/// Hands out single-use challenges and verifies the proofs of possession of the mdocs of holders.
+pub struct Reissuance {
+    doc_type: String,
+    bsn_attribute: (String, String),
+    trust_anchors: Vec<OwnedTrustAnchor>,
+    challenge_ttl: Duration,
+    challenges: Mutex<HashMap<Vec<u8>, Instant>>,
+}
+
+impl Reissuance {
+    /// Checks that the doc type of which possession is proven contains the BSN of the holder.
+    pub fn try_new(settings: &settings::Reissuance, doc_types: &DocTypes) -> Result<Self, ReissuanceError> {
+        let bsn_attribute = doc_types
+            .source_attribute(&settings.doc_type, BSN_SOURCE)
+            .ok_or_else(|| ReissuanceError::MissingBsnAttribute(settings.doc_type.clone()))?;
+
+        let trust_anchors = settings
+            .trust_anchors
+            .iter()
+            .map(|certificate| {
+                let der = BASE64_STANDARD
+                    .decode(certificate)
+                    .map_err(|error| ReissuanceError::TrustAnchor(error.into()))?;
+                DerTrustAnchor::from_der(der)
+                    .map(|anchor| anchor.owned_trust_anchor)
+                    .map_err(|error| ReissuanceError::TrustAnchor(error.into()))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            doc_type: settings.doc_type.clone(),
+            bsn_attribute,
+            trust_anchors,
+            challenge_ttl: Duration::from_secs(settings.challenge_ttl_in_seconds),
+            challenges: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Generate a challenge that can be used once, within the configured time.
+    pub fn new_challenge(&self) -> Vec<u8> {
+        let challenge = random_bytes(CHALLENGE_LENGTH);
+        let now = Instant::now();
+
+        let mut challenges = self.challenges.lock().unwrap();
+        // Challenges that are never used would otherwise accumulate.
+        challenges.retain(|_, expires| *expires > now);
+        challenges.insert(challenge.clone(), now + self.challenge_ttl);
+
+        challenge
+    }
+
+    /// The doc type of the mdoc of which possession has to be proven.
+    pub fn doc_type(&self) -> &str {
+        &self.doc_type
+    }
+
+    /// Verify the proof of possession of an mdoc issued by this PID issuer, which has not been revoked, returning the
+    /// BSN of its holder. The challenge is used up, regardless of the outcome.
+    pub fn verify(
+        &self,
+        request: &ReissuanceRequest,
+        keys: &IssuerKeyRing,
+        status_registry: Option<&StatusRegistry>,
+    ) -> Result<String, ReissuanceError> {
+        let expires = self.challenges.lock().unwrap().remove(request.challenge.as_slice());
+        if !expires.is_some_and(|expires| expires > Instant::now()) {
+            return Err(ReissuanceError::UnknownChallenge);
+        }
+
+        let trust_anchors: Vec<_> = self.trust_anchors.iter().map(Into::into).collect();
+        let (attributes, mso) = request
+            .verify(&TimeGenerator, &trust_anchors)
+            .map_err(ReissuanceError::Verification)?;
+
+        if mso.doc_type != self.doc_type {
+            return Err(ReissuanceError::UnexpectedDocType {
+                expected: self.doc_type.clone(),
+                received: mso.doc_type,
+            });
+        }
+
+        // The trust anchors may also have issued certificates to other issuers.
+        let certificate = request
+            .document
+            .issuer_signed
+            .issuer_auth
+            .signing_cert()
+            .map_err(ReissuanceError::Verification)?;
+        let fingerprint = certificate_fingerprint(&certificate);
+        if keys.key_by_fingerprint(&fingerprint).is_none() {
+            return Err(ReissuanceError::UnknownCertificate);
+        }
+
+        if let (Some(status_registry), Some(status)) = (status_registry, &mso.status) {
+            let status = status_registry.status(&mso.doc_type, &fingerprint, status.status_list.idx);
+            if status != Some(StatusType::Valid) {
+                return Err(ReissuanceError::Revoked);
+            }
+        }
+
+        let (namespace, name) = &self.bsn_attribute;
+        attributes
+            .get(namespace)
+            .and_then(|entries| entries.iter().find(|entry| entry.name == *name))
+            .and_then(|entry| match &entry.value {
+                Value::Text(bsn) => Some(bsn.clone()),
+                _ => None,
+            })
+            .ok_or(ReissuanceError::MissingBsn)
+    }
+}
@@ -3,6 +3,11 @@ use std::net::SocketAddr;
 use anyhow::Result;
 use tracing::debug;
 
+use wallet_common::{
+    metrics::{install_recorder, metrics_router},
+    tls::tls_config,
+};
+
 use crate::app::{create_router, AttributesLookup, BsnLookup};
 
 use super::settings::Settings;
@@ -13,11 +18,31 @@ where
     B: BsnLookup + Send + Sync + 'static,
 {
     let socket = SocketAddr::new(settings.webserver.ip, settings.webserver.port);
+    let tls = settings.webserver.tls.clone();
+
+    let metrics_handle = settings.metrics_enabled.then(install_recorder).transpose()?;
+
+    let mut app = create_router(settings, attributes_lookup, openid_client).await?;
+    if let Some(metrics_handle) = metrics_handle {
+        app = app.merge(metrics_router(metrics_handle));
+    }
 
-    let app = create_router(settings, attributes_lookup, openid_client).await?;
-    debug!("listening on {}", socket);
+    match tls {
+        Some(tls) => {
+            let (tls_config, _tls_reload_task) = tls_config(&tls)?;
 
-    axum::Server::bind(&socket).serve(app.into_make_service()).await?;
+            debug!("listening on {} using TLS", socket);
+            axum_server::bind_rustls(socket, tls_config)
+                .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+                .await?;
+        }
+        None => {
+            debug!("listening on {}", socket);
+            axum::Server::bind(&socket)
+                .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+                .await?;
+        }
+    }
 
     Ok(())
 }
@@ -1,20 +1,62 @@
-use std::{env, net::IpAddr, path::PathBuf};
+use std::{
+    env,
+    net::IpAddr,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+};
 
-use config::{Config, ConfigError, Environment, File};
+use chrono::{DateTime, Utc};
+use config::{Config, ConfigError, Environment, File, FileFormat};
 use serde::Deserialize;
 use url::Url;
 
+use wallet_common::{telemetry::OtlpSettings, tls::TlsSettings};
+
 #[cfg(feature = "mock")]
-use crate::mock::{PersonAttributes, ResidentAttributes};
+use crate::attributes::{PersonAttributes, ResidentAttributes};
 
 #[derive(Clone, Deserialize)]
 pub struct Settings {
     pub webserver: Webserver,
     pub digid: Digid,
     pub issuer_key: IssuerKey,
+    /// Keys that are used alongside `issuer_key` for doc types without keys of their own, e.g. the next key while
+    /// rotating. Of the keys that are valid at the time of signing, the one that became valid last is used.
+    #[serde(default)]
+    pub additional_issuer_keys: Vec<IssuerKey>,
+    #[serde(default)]
+    pub doc_type_issuer_keys: Vec<DocTypeIssuerKey>,
+    /// The number of MSOs that are signed at the same time, across all sessions. When not configured,
+    /// [`DEFAULT_MAX_CONCURRENT_SIGNATURES`](nl_wallet_mdoc::issuer::DEFAULT_MAX_CONCURRENT_SIGNATURES) is used.
+    pub max_concurrent_signatures: Option<NonZeroUsize>,
     pub public_url: Url,
+    pub metrics_enabled: bool,
+    pub otlp: Option<OtlpSettings>,
+    /// When configured, the attributes are retrieved from the HaalCentraal BRP API instead of the mock data.
+    pub brp: Option<Brp>,
+    /// TOML or JSON file containing the doc types to be issued and the mapping of their attributes. When not
+    /// configured, the mapping in `doc_types.toml` is used.
+    pub doc_types_path: Option<PathBuf>,
+    /// When configured, the issued mdocs refer to a status list, through which these can be revoked.
+    pub status_lists: Option<StatusLists>,
+    /// When configured, the administrative endpoints are enabled, which require this API key as bearer token.
+    pub admin: Option<Admin>,
+    /// When configured, limits the number of issuance attempts per IP address and per BSN.
+    pub rate_limits: Option<RateLimits>,
+    /// When configured, holders of a valid mdoc can obtain fresh copies of their mdocs without using DigiD.
+    pub reissuance: Option<Reissuance>,
+    /// When configured, PIDs can also be obtained using OpenID4VCI, e.g. by wallets of third parties in test
+    /// environments.
+    pub openid4vci: Option<OpenId4Vci>,
+    /// When configured, issuance events are recorded in an audit log, which contains neither BSNs nor attributes.
+    pub audit_log: Option<AuditLog>,
+    /// When configured, `/health` checks the reachability of DigiD and the BRP, and the signing keys.
+    pub health_checks: Option<HealthChecks>,
     #[cfg(feature = "mock")]
     pub mock_data: Option<Vec<MockAttributes>>,
+    /// TOML or JSON file containing test persons, which are issued in addition to `mock_data`.
+    #[cfg(feature = "mock")]
+    pub mock_data_path: Option<PathBuf>,
 }
 
 #[derive(Clone, Deserialize)]
@@ -24,16 +66,214 @@ pub struct Digid {
     pub client_id: String,
 }
 
+#[derive(Clone, Deserialize)]
+pub struct StatusLists {
+    /// Journal file in which the issued mdocs and their revocations are recorded.
+    pub registry_path: PathBuf,
+    /// Secret with which the BSNs are pseudonymized before these are recorded in the journal.
+    pub subject_secret: String,
+    /// The number of mdocs in each status list.
+    #[serde(default = "default_status_list_size")]
+    pub list_size: usize,
+    /// The number of seconds that a published status list may be cached.
+    #[serde(default = "default_status_list_ttl_in_seconds")]
+    pub ttl_in_seconds: u64,
+}
+
+fn default_status_list_size() -> usize {
+    100_000
+}
+
+fn default_status_list_ttl_in_seconds() -> u64 {
+    3600
+}
+
+#[derive(Clone, Deserialize)]
+pub struct Reissuance {
+    /// The doc type of the mdoc of which the holder proves possession, which has to contain the BSN.
+    pub doc_type: String,
+    /// Base64 encoded DER CA certificates, which have issued the certificates of the issuer keys.
+    pub trust_anchors: Vec<String>,
+    /// The number of seconds within which a challenge has to be used.
+    #[serde(default = "default_challenge_ttl_in_seconds")]
+    pub challenge_ttl_in_seconds: u64,
+}
+
+fn default_challenge_ttl_in_seconds() -> u64 {
+    300
+}
+
+#[derive(Clone, Deserialize)]
+pub struct OpenId4Vci {
+    /// The number of seconds within which a credential offer has to be redeemed, and within which the credentials
+    /// have to be requested after that.
+    pub session_ttl_in_seconds: u64,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct AuditLog {
+    /// File to which the events are appended as lines of JSON.
+    pub path: PathBuf,
+    /// Secret with which the BSNs are salted before these are hashed, so that the events of a person can be found.
+    pub subject_salt: String,
+    /// The number of days after which events are removed from the log.
+    #[serde(default = "default_audit_log_retention_in_days")]
+    pub retention_in_days: u64,
+}
+
+fn default_audit_log_retention_in_days() -> u64 {
+    365
+}
+
+#[derive(Clone, Deserialize)]
+pub struct HealthChecks {
+    /// The number of milliseconds after which a dependency that has not responded is considered unavailable.
+    #[serde(default = "default_health_check_timeout_in_ms")]
+    pub timeout_in_ms: u64,
+}
+
+fn default_health_check_timeout_in_ms() -> u64 {
+    5000
+}
+
+#[derive(Clone, Deserialize)]
+pub struct RateLimits {
+    /// Limit of the issuance attempts from a single IP address, checked before DigiD is contacted.
+    pub per_ip: Option<RateLimit>,
+    /// Limit of the issuance attempts for a single BSN, from any IP address.
+    pub per_bsn: Option<RateLimit>,
+    /// When enabled, the IP address of the client is taken from the last address in the X-Forwarded-For header, as
+    /// appended by the reverse proxy in front of the PID issuer. MUST NOT be enabled without such a proxy.
+    #[serde(default)]
+    pub use_forwarded_for: bool,
+}
+
+#[derive(Clone, Copy, Deserialize)]
+pub struct RateLimit {
+    pub max_requests: u32,
+    pub window_in_ms: u64,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct Admin {
+    pub api_key: String,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct Brp {
+    /// The base URL of the "BRP Personen bevragen" API, to which `/personen` is appended.
+    pub url: Url,
+    /// Sent in the `X-API-KEY` header, when configured.
+    pub api_key: Option<String>,
+    /// When configured, the PID issuer authenticates to the BRP using this client certificate.
+    pub mtls: Option<BrpMutualTls>,
+    /// PEM encoded CA certificate with which the certificate of the BRP is verified, in addition to the web PKI.
+    pub ca_certificate_path: Option<PathBuf>,
+    #[serde(default = "default_brp_timeout_in_ms")]
+    pub timeout_in_ms: u64,
+}
+
+fn default_brp_timeout_in_ms() -> u64 {
+    10_000
+}
+
+#[derive(Clone, Deserialize)]
+pub struct BrpMutualTls {
+    /// PEM encoded certificate chain of the client.
+    pub certificate_path: PathBuf,
+    /// PEM encoded PKCS8 private key of the client.
+    pub private_key_path: PathBuf,
+}
+
+/// The mapping that is used when no `doc_types_path` is configured.
+const DEFAULT_DOC_TYPES: &str = include_str!("../doc_types.toml");
+
+#[derive(Deserialize)]
+struct DocTypesFile {
+    doc_types: Vec<DocTypeMapping>,
+}
+
+/// A doc type to be issued, of which the attributes are created from the attributes of a person.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DocTypeMapping {
+    pub doc_type: String,
+    /// The namespace of the attributes, which defaults to the doc type.
+    pub namespace: Option<String>,
+    /// The number of copies of the mdoc that are issued, each with a key of its own so that disclosures using
+    /// different copies cannot be linked. More copies means more signatures by the issuer.
+    #[serde(default = "default_copy_count")]
+    pub copy_count: u64,
+    /// The number of days after issuance at which the MSO of the mdoc expires.
+    #[serde(default = "default_validity_in_days")]
+    pub validity_in_days: u64,
+    /// The DigiD scopes of which at least one has to be granted by the user for the doc type to be issued. When empty,
+    /// the doc type is issued regardless of the granted scopes.
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    pub attributes: Vec<AttributeMapping>,
+}
+
+fn default_copy_count() -> u64 {
+    10
+}
+
+fn default_validity_in_days() -> u64 {
+    365
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AttributeMapping {
+    pub name: String,
+    /// The field of the attributes of the person, e.g. `person.given_name` or `resident.city`.
+    pub source: String,
+    #[serde(default)]
+    pub transform: Transform,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Transform {
+    #[default]
+    None,
+    Uppercase,
+    Lowercase,
+    /// Encodes a gender as an integer according to ISO/IEC 5218.
+    Iso5218,
+}
+
+impl DocTypeMapping {
+    /// Load the mapping from the file at `path`, of which the format is determined by its extension, or the default
+    /// mapping when no path is specified.
+    pub fn load(path: Option<&Path>) -> Result<Vec<Self>, ConfigError> {
+        let builder = match path {
+            Some(path) => Config::builder().add_source(File::from(path)),
+            None => Config::builder().add_source(File::from_str(DEFAULT_DOC_TYPES, FileFormat::Toml)),
+        };
+
+        Ok(builder.build()?.try_deserialize::<DocTypesFile>()?.doc_types)
+    }
+}
+
 #[derive(Clone, Deserialize)]
 pub struct Webserver {
     pub ip: IpAddr,
     pub port: u16,
+    pub tls: Option<TlsSettings>,
 }
 
 #[derive(Clone, Deserialize)]
 pub struct IssuerKey {
     pub private_key: String,
     pub certificate: String,
+    pub not_before: Option<DateTime<Utc>>,
+    pub not_after: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct DocTypeIssuerKey {
+    pub doc_type: String,
+    #[serde(flatten)]
+    pub key: IssuerKey,
 }
 
 #[cfg(feature = "mock")]
@@ -43,6 +283,26 @@ pub struct MockAttributes {
     pub resident: Option<ResidentAttributes>,
 }
 
+#[cfg(feature = "mock")]
+#[derive(Deserialize)]
+struct MockDataFile {
+    persons: Vec<MockAttributes>,
+}
+
+#[cfg(feature = "mock")]
+impl MockAttributes {
+    /// Load the test persons from the file at `path`, of which the format is determined by its extension.
+    pub fn load(path: &Path) -> Result<Vec<Self>, ConfigError> {
+        let persons = Config::builder()
+            .add_source(File::from(path))
+            .build()?
+            .try_deserialize::<MockDataFile>()?
+            .persons;
+
+        Ok(persons)
+    }
+}
+
 impl Settings {
     pub fn new() -> Result<Self, ConfigError> {
         // Look for a config file that is in the same directory as Cargo.toml if run through cargo,
@@ -54,6 +314,7 @@ impl Settings {
             .set_default("webserver.ip", "0.0.0.0")?
             .set_default("webserver.port", 3003)?
             .set_default("public_url", "http://localhost:3003/")?
+            .set_default("metrics_enabled", false)?
             .set_default("digid.issuer_url", "https://localhost:8006/")?
             .set_default("digid.client_id", "37692967-0a74-4e91-85ec-a4250e7ad5e8")?
             .add_source(File::from(config_path.join("pid_issuer.toml")).required(false))
@@ -65,4 +326,15 @@ impl Settings {
             .build()?
             .try_deserialize()
     }
+
+    /// The persons configured in `mock_data`, followed by those in the file at `mock_data_path`.
+    #[cfg(feature = "mock")]
+    pub fn mock_attributes(&self) -> Result<Vec<MockAttributes>, ConfigError> {
+        let mut mock_attributes = self.mock_data.clone().unwrap_or_default();
+        if let Some(mock_data_path) = &self.mock_data_path {
+            mock_attributes.extend(MockAttributes::load(mock_data_path)?);
+        }
+
+        Ok(mock_attributes)
+    }
 }
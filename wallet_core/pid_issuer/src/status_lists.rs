@@ -0,0 +1,416 @@
+//! Registry of the mdocs that are issued with a reference to a status list, through which these can be revoked.
+//!
+//! Every issued mdoc and every revocation is appended as a line of JSON to a journal file, which is replayed when the
+//! PID issuer starts. The BSN of the holder is only recorded as a keyed digest, so that the journal does not contain
+//! BSNs but the mdocs of a person can still be found when these are revoked.
+use std::{
+    collections::HashMap,
+    fmt::{self, Debug},
+    fs::{File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    sync::Mutex,
+};
+
+use base64::prelude::*;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use url::Url;
+
+use nl_wallet_mdoc::{
+    server_keys::PrivateKey,
+    status_list::{
+        StatusBits, StatusList, StatusListAllocator, StatusListClaims, StatusListError, StatusListToken, StatusType,
+    },
+    utils::x509::Certificate,
+    StatusListReference,
+};
+
+use crate::{keys::certificate_fingerprint, settings};
+
+#[derive(Debug, thiserror::Error)]
+pub enum RegistryError {
+    #[error("could not access status list registry: {0}")]
+    Io(#[from] io::Error),
+    #[error("could not parse line {line} of status list registry: {source}")]
+    Parsing {
+        line: usize,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("line {line} of status list registry refers to an unexpected index in the status list")]
+    UnexpectedIndex { line: usize },
+}
+
+/// A line in the journal of the registry.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum RegistryEvent {
+    Issued {
+        doc_type: String,
+        certificate: String,
+        idx: u64,
+        subject: Option<String>,
+        at: DateTime<Utc>,
+    },
+    Revoked {
+        doc_type: String,
+        certificate: String,
+        idx: u64,
+        reason: Option<String>,
+        at: DateTime<Utc>,
+    },
+}
+
+/// An mdoc that was revoked, identified by its status list and index.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RevokedMdoc {
+    pub doc_type: String,
+    pub certificate: String,
+    pub idx: u64,
+}
+
+/// A status list for mdocs of a single doc type that are signed with the same certificate, which is identified by
+/// its fingerprint. The status list has to be signed with that certificate as well.
+type ListKey = (String, String);
+
+#[derive(Debug)]
+struct RegisteredList {
+    status_list: StatusList,
+    /// The subject digest of every allocated index, of which the number is the next index to be allocated.
+    subjects: Vec<Option<String>>,
+}
+
+#[derive(Debug)]
+struct RegistryState {
+    lists: HashMap<ListKey, RegisteredList>,
+    journal: File,
+}
+
+/// Allocates the indices in the status lists of the mdocs that are issued, records these in a journal and signs the
+/// status lists that are published.
+///
+/// Note that only a single instance of the PID issuer should use the same journal, as the journal is only read when
+/// the registry is opened.
+pub struct StatusRegistry {
+    base_url: Url,
+    list_size: usize,
+    ttl_in_seconds: u64,
+    subject_secret: Vec<u8>,
+    state: Mutex<RegistryState>,
+}
+
+impl Debug for StatusRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StatusRegistry")
+            .field("base_url", &self.base_url)
+            .field("list_size", &self.list_size)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Pseudonymize a subject, i.e. a BSN, using a keyed digest, so that it cannot be recovered without the secret.
+pub fn subject_digest(secret: &[u8], subject: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC can take a key of any size");
+    mac.update(subject.as_bytes());
+
+    BASE64_STANDARD.encode(mac.finalize().into_bytes())
+}
+
+fn append(journal: &mut File, event: &RegistryEvent) -> io::Result<()> {
+    let mut line = serde_json::to_vec(event)?;
+    line.push(b'\n');
+    journal.write_all(&line)?;
+
+    // An index that is lost in a crash could be allocated again, so the line has to be persisted before the mdoc
+    // referring to it is issued.
+    journal.sync_data()
+}
+
+impl StatusRegistry {
+    /// Open the registry, replaying its journal. The status lists are published at `base_url`, followed by the doc
+    /// type and the fingerprint of the certificate.
+    pub fn open(settings: &settings::StatusLists, base_url: Url) -> Result<Self, RegistryError> {
+        let mut journal = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(&settings.registry_path)?;
+
+        let mut lists: HashMap<ListKey, RegisteredList> = HashMap::new();
+        for (line_idx, line) in BufReader::new(&mut journal).lines().enumerate() {
+            let line_number = line_idx + 1;
+            let event = serde_json::from_str(&line?).map_err(|source| RegistryError::Parsing {
+                line: line_number,
+                source,
+            })?;
+
+            match event {
+                RegistryEvent::Issued {
+                    doc_type,
+                    certificate,
+                    idx,
+                    subject,
+                    ..
+                } => {
+                    let list = lists.entry((doc_type, certificate)).or_insert_with(|| RegisteredList {
+                        status_list: StatusList::new(StatusBits::One, settings.list_size),
+                        subjects: vec![],
+                    });
+                    if idx != list.subjects.len() as u64 || idx >= list.status_list.len() as u64 {
+                        return Err(RegistryError::UnexpectedIndex { line: line_number });
+                    }
+                    list.subjects.push(subject);
+                }
+                RegistryEvent::Revoked {
+                    doc_type,
+                    certificate,
+                    idx,
+                    ..
+                } => {
+                    lists
+                        .get_mut(&(doc_type, certificate))
+                        .filter(|list| idx < list.subjects.len() as u64)
+                        .ok_or(RegistryError::UnexpectedIndex { line: line_number })?
+                        .status_list
+                        .set(idx, StatusType::Invalid)
+                        .map_err(|_| RegistryError::UnexpectedIndex { line: line_number })?;
+                }
+            }
+        }
+
+        Ok(Self {
+            base_url,
+            list_size: settings.list_size,
+            ttl_in_seconds: settings.ttl_in_seconds,
+            subject_secret: settings.subject_secret.as_bytes().to_vec(),
+            state: Mutex::new(RegistryState { lists, journal }),
+        })
+    }
+
+    fn subject_digest(&self, subject: &str) -> String {
+        subject_digest(&self.subject_secret, subject)
+    }
+
+    fn uri(&self, doc_type: &str, certificate: &str) -> Url {
+        let mut uri = self.base_url.clone();
+        uri.path_segments_mut()
+            .expect("base URL should be a valid base")
+            .pop_if_empty()
+            .push(doc_type)
+            .push(certificate);
+
+        uri
+    }
+
+    /// Revoke all mdocs that were issued to `subject` and have not been revoked yet, returning the revoked mdocs.
+    pub fn revoke_subject(&self, subject: &str, reason: Option<String>) -> Result<Vec<RevokedMdoc>, RegistryError> {
+        let subject = Some(self.subject_digest(subject));
+        let mut state = self.state.lock().unwrap();
+        let RegistryState { lists, journal } = &mut *state;
+
+        let mut revoked = vec![];
+        for ((doc_type, certificate), list) in lists.iter_mut() {
+            for (idx, _) in list
+                .subjects
+                .iter()
+                .enumerate()
+                .filter(|(_, issued_subject)| **issued_subject == subject)
+            {
+                let idx = idx as u64;
+                if list.status_list.get(idx) != Some(StatusType::Valid) {
+                    continue;
+                }
+
+                append(
+                    journal,
+                    &RegistryEvent::Revoked {
+                        doc_type: doc_type.clone(),
+                        certificate: certificate.clone(),
+                        idx,
+                        reason: reason.clone(),
+                        at: Utc::now(),
+                    },
+                )?;
+                list.status_list
+                    .set(idx, StatusType::Invalid)
+                    .expect("allocated index should be within the status list");
+
+                revoked.push(RevokedMdoc {
+                    doc_type: doc_type.clone(),
+                    certificate: certificate.clone(),
+                    idx,
+                });
+            }
+        }
+
+        Ok(revoked)
+    }
+
+    /// The status of an mdoc of the specified doc type and certificate, or `None` if that mdoc was not issued.
+    pub fn status(&self, doc_type: &str, certificate: &str, idx: u64) -> Option<StatusType> {
+        let state = self.state.lock().unwrap();
+        let list = state.lists.get(&(doc_type.to_string(), certificate.to_string()))?;
+
+        (idx < list.subjects.len() as u64)
+            .then(|| list.status_list.get(idx))
+            .flatten()
+    }
+
+    /// Sign the status list of the specified doc type and certificate using `key`, which should be the key of that
+    /// certificate. Returns `None` if no mdocs have been issued in this status list.
+    pub async fn token(
+        &self,
+        doc_type: &str,
+        certificate: &str,
+        key: &PrivateKey,
+    ) -> nl_wallet_mdoc::Result<Option<StatusListToken>> {
+        let status_list = {
+            let state = self.state.lock().unwrap();
+            match state.lists.get(&(doc_type.to_string(), certificate.to_string())) {
+                Some(list) => list.status_list.clone(),
+                None => return Ok(None),
+            }
+        };
+
+        let claims = StatusListClaims {
+            sub: self.uri(doc_type, certificate),
+            iat: Utc::now().timestamp() as u64,
+            exp: None,
+            ttl: Some(self.ttl_in_seconds),
+            status_list,
+        };
+
+        claims.sign(key).await.map(Some)
+    }
+}
+
+impl StatusListAllocator for StatusRegistry {
+    fn allocate(
+        &self,
+        doc_type: &str,
+        issuer_certificate: &Certificate,
+        subject: Option<&str>,
+    ) -> Result<Option<StatusListReference>, StatusListError> {
+        let certificate = certificate_fingerprint(issuer_certificate);
+        let subject = subject.map(|subject| self.subject_digest(subject));
+
+        let mut state = self.state.lock().unwrap();
+        let RegistryState { lists, journal } = &mut *state;
+
+        let list = lists
+            .entry((doc_type.to_string(), certificate.clone()))
+            .or_insert_with(|| RegisteredList {
+                status_list: StatusList::new(StatusBits::One, self.list_size),
+                subjects: vec![],
+            });
+
+        let idx = list.subjects.len() as u64;
+        if idx >= list.status_list.len() as u64 {
+            return Err(StatusListError::Exhausted(doc_type.to_string()));
+        }
+
+        append(
+            journal,
+            &RegistryEvent::Issued {
+                doc_type: doc_type.to_string(),
+                certificate: certificate.clone(),
+                idx,
+                subject: subject.clone(),
+                at: Utc::now(),
+            },
+        )
+        .map_err(|error| StatusListError::Allocation(error.into()))?;
+        list.subjects.push(subject);
+
+        Ok(Some(StatusListReference {
+            idx,
+            uri: self.uri(doc_type, &certificate),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nl_wallet_mdoc::mock::generate_issuance_key_and_ca;
+
+    use super::*;
+
+    fn settings(dir: &tempfile::TempDir) -> settings::StatusLists {
+        settings::StatusLists {
+            registry_path: dir.path().join("status_lists.jsonl"),
+            subject_secret: "secret".to_string(),
+            list_size: 16,
+            ttl_in_seconds: 60,
+        }
+    }
+
+    fn open(dir: &tempfile::TempDir) -> StatusRegistry {
+        StatusRegistry::open(&settings(dir), "https://example.com/status_lists/".parse().unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_status_registry() {
+        let dir = tempfile::tempdir().unwrap();
+        let (key, _) = generate_issuance_key_and_ca().unwrap();
+        let certificate = certificate_fingerprint(key.certificate());
+
+        let registry = open(&dir);
+        let revoked_reference = registry
+            .allocate("com.example.pid", key.certificate(), Some("999991772"))
+            .unwrap()
+            .unwrap();
+        let valid_reference = registry
+            .allocate("com.example.pid", key.certificate(), Some("999991773"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            revoked_reference.uri.as_str(),
+            format!("https://example.com/status_lists/com.example.pid/{}", certificate)
+        );
+        assert_eq!((revoked_reference.idx, valid_reference.idx), (0, 1));
+
+        let revoked = registry
+            .revoke_subject("999991772", Some("deceased".to_string()))
+            .unwrap();
+        assert_eq!(
+            revoked,
+            vec![RevokedMdoc {
+                doc_type: "com.example.pid".to_string(),
+                certificate: certificate.clone(),
+                idx: 0,
+            }]
+        );
+
+        // Revoking again does not revoke anything, as the mdoc was already revoked.
+        assert!(registry.revoke_subject("999991772", None).unwrap().is_empty());
+        drop(registry);
+
+        // After reopening the registry, the allocated indices and revocations are restored.
+        let registry = open(&dir);
+        let next_reference = registry
+            .allocate("com.example.pid", key.certificate(), None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(next_reference.idx, 2);
+
+        let token = registry
+            .token("com.example.pid", &certificate, &key)
+            .await
+            .unwrap()
+            .unwrap();
+        let status_list = token
+            .verify_and_parse(&key.certificate().public_key().unwrap())
+            .unwrap()
+            .status_list;
+        assert_eq!(status_list.get(0), Some(StatusType::Invalid));
+        assert_eq!(status_list.get(1), Some(StatusType::Valid));
+
+        assert!(registry
+            .token("com.example.address", &certificate, &key)
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
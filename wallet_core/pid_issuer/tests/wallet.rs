@@ -15,7 +15,7 @@ use pid_issuer::{
 };
 use wallet::{
     mock::{default_configuration, LocalConfigurationRepository, MockAccountProviderClient, MockStorage},
-    wallet_deps::{DigidSession, HttpDigidSession, HttpPidIssuerClient},
+    wallet_deps::{DigidSession, HttpCredentialIssuerClient, HttpDigidSession},
     Wallet,
 };
 use wallet_common::keys::software::SoftwareEcdsaKey;
@@ -36,14 +36,14 @@ pub type WalletWithMocks =
 /// Create an instance of [`Wallet`].
 async fn create_test_wallet<D: DigidSession>(
     base_url: Url,
-    pid_issuer_client: HttpPidIssuerClient,
+    pid_issuer_client: HttpCredentialIssuerClient,
 ) -> Wallet<
     LocalConfigurationRepository,
     MockStorage,
     SoftwareEcdsaKey,
     MockAccountProviderClient,
     D,
-    HttpPidIssuerClient,
+    HttpCredentialIssuerClient,
 > {
     Wallet::init_registration(
         test_wallet_config(base_url.clone()),
@@ -95,9 +95,10 @@ where
 async fn test_pid_issuance_digid_bridge() {
     let (settings, port) = pid_issuer_settings();
     let bsn_lookup = OpenIdClient::new(&settings.digid).await.unwrap();
-    let attributes_lookup = MockAttributesLookup::from(settings.mock_data.clone().unwrap_or_default());
+    let attributes_lookup = MockAttributesLookup::from(settings.mock_attributes().unwrap());
     start_pid_issuer(settings, attributes_lookup, bsn_lookup);
-    let mut wallet = create_test_wallet::<HttpDigidSession>(local_base_url(port), HttpPidIssuerClient::default()).await;
+    let mut wallet =
+        create_test_wallet::<HttpDigidSession>(local_base_url(port), HttpCredentialIssuerClient::default()).await;
 
     // Prepare DigiD flow
     let authorization_url = wallet
@@ -48,4 +48,17 @@ abstract class KeyBridge(val context: Context) {
      */
     protected fun deleteEntry(keyAlias: String) = keyStore.deleteEntry(keyAlias)
 
+    /**
+     * Deletes the key associated with the provided [keyAlias]
+     * from the KeyStore, if it exists.
+     */
+    @Throws(KeyStoreException::class)
+    protected fun deleteKey(keyAlias: String) {
+        try {
+            if (keyExists(keyAlias)) deleteEntry(keyAlias)
+        } catch (ex: Exception) {
+            throw KeyStoreKeyError.DeleteKeyError(ex).keyException
+        }
+    }
+
 }
\ No newline at end of file
@@ -11,6 +11,8 @@ sealed class KeyStoreKeyError(private val ex: Exception) {
     class SignKeyError(ex: Exception) : KeyStoreKeyError(ex)
     class CreateKeyError(ex: Exception) : KeyStoreKeyError(ex)
     class FetchKeyError(ex: Exception) : KeyStoreKeyError(ex)
+    class DeleteKeyError(ex: Exception) : KeyStoreKeyError(ex)
+    class IntegrityEvidenceError(ex: Exception) : KeyStoreKeyError(ex)
     class MissingHardwareError(keySecurityLevel: Int?) :
         KeyStoreKeyError(Exception("Key security level: $keySecurityLevel"))
 
@@ -21,6 +23,8 @@ sealed class KeyStoreKeyError(private val ex: Exception) {
                 is SignKeyError -> "Could not sign with private key"
                 is CreateKeyError -> "Could not create private key"
                 is FetchKeyError -> "Could not fetch private key"
+                is DeleteKeyError -> "Could not delete key"
+                is IntegrityEvidenceError -> "Could not obtain integrity evidence"
                 is MissingHardwareError -> "Could not generate hardware backed key"
             }
             return KeyException("$errorMessage. Reason: ${ex.message}")
@@ -33,6 +33,8 @@ class EncryptionKeyBridge(context: Context) : KeyBridge(context), RustEncryption
         return key.decrypt(payload)
     }
 
+    override fun delete(identifier: String) = deleteKey(ENCRYPT_KEY_PREFIX + identifier)
+
     override fun clean() =
         keyStore.aliases().asSequence().filter { it.startsWith(ENCRYPT_KEY_PREFIX) }
             .forEach(::deleteEntry)
@@ -1,8 +1,14 @@
 package nl.rijksoverheid.edi.wallet.platform_support.keystore.signing
 
 import android.content.Context
+import android.util.Base64
+import com.google.android.gms.tasks.Tasks
+import com.google.android.play.core.integrity.IntegrityManagerFactory
+import com.google.android.play.core.integrity.IntegrityTokenRequest
 import nl.rijksoverheid.edi.wallet.platform_support.keystore.KeyBridge
 import nl.rijksoverheid.edi.wallet.platform_support.keystore.KeyStoreKeyError
+import nl.rijksoverheid.edi.wallet.platform_support.util.toByteArray
+import uniffi.platform_support.IntegrityEvidence
 import uniffi.platform_support.KeyStoreException
 import uniffi.platform_support.SigningKeyBridge as RustSigningBridge
 
@@ -33,6 +39,26 @@ class SigningKeyBridge(context: Context) : KeyBridge(context), RustSigningBridge
         return key.sign(payload)
     }
 
+    override fun delete(identifier: String) = deleteKey(SIGN_KEY_PREFIX + identifier)
+
+    /**
+     * Requests an integrity verdict from the Play Integrity API, using the
+     * request hash as nonce. Note that this blocks until the verdict is received.
+     */
+    override fun integrityEvidence(requestHash: List<UByte>): IntegrityEvidence? {
+        try {
+            val nonce = Base64.encodeToString(
+                requestHash.toByteArray(),
+                Base64.URL_SAFE or Base64.NO_WRAP or Base64.NO_PADDING
+            )
+            val request = IntegrityTokenRequest.builder().setNonce(nonce).build()
+            val response = Tasks.await(IntegrityManagerFactory.create(context).requestIntegrityToken(request))
+            return IntegrityEvidence.PlayIntegrity(response.token())
+        } catch (ex: Exception) {
+            throw KeyStoreKeyError.IntegrityEvidenceError(ex).keyException
+        }
+    }
+
     override fun clean() =
         keyStore.aliases().asSequence().filter { it.startsWith(SIGN_KEY_PREFIX) }
             .forEach(::deleteEntry)
@@ -31,4 +31,12 @@ extension EncryptionKey: EncryptionKeyBridge {
             throw KeyStoreError.from(error)
         }
     }
+
+    func delete(identifier: String) throws {
+        do {
+            try SecureEnclaveKey.deleteKey(with: "\(Self.identifierPrefix)_\(identifier)")
+        } catch let error as SecureEnclaveKeyError {
+            throw KeyStoreError.from(error)
+        }
+    }
 }
@@ -5,6 +5,7 @@
 //  Created by Wallet Developer on 24/02/2023.
 //
 
+import DeviceCheck
 import Foundation
 
 final class SigningKey {
@@ -13,6 +14,51 @@ final class SigningKey {
     private func secureEnclaveKey(for identifier: String) throws -> SecureEnclaveKey {
         return try SecureEnclaveKey(identifier: "\(Self.identifierPrefix)_\(identifier)")
     }
+
+    // As the Wallet Provider does not keep track of attested keys, a new App Attest key is generated
+    // and attested for every request, using the request hash as the client data hash.
+    private func attestNewAppAttestKey(clientDataHash: Data) throws -> IntegrityEvidence? {
+        let service = DCAppAttestService.shared
+
+        guard service.isSupported else {
+            return nil
+        }
+
+        let semaphore = DispatchSemaphore(value: 0)
+        var result: Result<(String, Data), Error>?
+
+        service.generateKey { keyId, error in
+            guard let keyId else {
+                result = .failure(SecureEnclaveKeyError.attest(appAttestError: error))
+                semaphore.signal()
+
+                return
+            }
+
+            service.attestKey(keyId, clientDataHash: clientDataHash) { attestation, error in
+                if let attestation {
+                    result = .success((keyId, attestation))
+                } else {
+                    result = .failure(SecureEnclaveKeyError.attest(appAttestError: error))
+                }
+                semaphore.signal()
+            }
+        }
+
+        semaphore.wait()
+
+        guard let result else {
+            throw SecureEnclaveKeyError.attest(appAttestError: nil)
+        }
+
+        // The key identifier is the base64 encoded SHA-256 hash of the public key.
+        let (keyId, attestation) = try result.get()
+        guard let keyIdData = Data(base64Encoded: keyId) else {
+            throw SecureEnclaveKeyError.attest(appAttestError: nil)
+        }
+
+        return .appAttest(keyId: Array(keyIdData), attestation: Array(attestation))
+    }
 }
 
 extension SigningKey: SigningKeyBridge {
@@ -31,4 +77,20 @@ extension SigningKey: SigningKeyBridge {
             throw KeyStoreError.from(error)
         }
     }
+
+    func delete(identifier: String) throws {
+        do {
+            try SecureEnclaveKey.deleteKey(with: "\(Self.identifierPrefix)_\(identifier)")
+        } catch let error as SecureEnclaveKeyError {
+            throw KeyStoreError.from(error)
+        }
+    }
+
+    func integrityEvidence(requestHash: [UInt8]) throws -> IntegrityEvidence? {
+        do {
+            return try self.attestNewAppAttestKey(clientDataHash: Data(requestHash))
+        } catch let error as SecureEnclaveKeyError {
+            throw KeyStoreError.from(error)
+        }
+    }
 }
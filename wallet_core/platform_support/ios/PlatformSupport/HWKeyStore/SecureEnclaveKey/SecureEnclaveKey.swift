@@ -108,6 +108,35 @@ final class SecureEnclaveKey {
         return key
     }
 
+    static func deleteKey(with identifier: String) throws {
+        let query: [String: Any] = [
+            kSecClass as String: kSecClassKey,
+            kSecAttrTokenID as String: kSecAttrTokenIDSecureEnclave,
+            kSecAttrApplicationTag as String: self.tag(from: identifier),
+            kSecAttrKeyType as String: kSecAttrKeyTypeEC
+        ]
+
+        let status = self.queue.sync {
+            SecItemDelete(query as CFDictionary)
+        }
+
+        switch status {
+        case errSecSuccess, errSecItemNotFound:
+            return
+        default:
+            let errorMessage: String? = {
+                guard #available(iOS 11.3, *),
+                      let errorMessage = SecCopyErrorMessageString(status, nil) else {
+                    return nil
+                }
+
+                return errorMessage as String
+            }()
+
+            throw SecureEnclaveKeyError.delete(errorMessage: errorMessage)
+        }
+    }
+
     private static func encode(publicKey: SecKey) throws -> Data {
         var error: Unmanaged<CFError>?
         guard let keyData = SecKeyCopyExternalRepresentation(publicKey, &error) else {
@@ -2,7 +2,7 @@ use std::fmt::Debug;
 
 use super::get_bridge_collection;
 
-pub use crate::hw_keystore::KeyStoreError;
+pub use crate::hw_keystore::{IntegrityEvidence, KeyStoreError};
 
 // this is required to catch UnexpectedUniFFICallbackError
 impl From<uniffi::UnexpectedUniFFICallbackError> for KeyStoreError {
@@ -15,11 +15,14 @@ impl From<uniffi::UnexpectedUniFFICallbackError> for KeyStoreError {
 pub trait SigningKeyBridge: Send + Sync + Debug {
     fn public_key(&self, identifier: String) -> Result<Vec<u8>, KeyStoreError>;
     fn sign(&self, identifier: String, payload: Vec<u8>) -> Result<Vec<u8>, KeyStoreError>;
+    fn delete(&self, identifier: String) -> Result<(), KeyStoreError>;
+    fn integrity_evidence(&self, request_hash: Vec<u8>) -> Result<Option<IntegrityEvidence>, KeyStoreError>;
 }
 
 pub trait EncryptionKeyBridge: Send + Sync + Debug {
     fn encrypt(&self, identifier: String, payload: Vec<u8>) -> Result<Vec<u8>, KeyStoreError>;
     fn decrypt(&self, identifier: String, payload: Vec<u8>) -> Result<Vec<u8>, KeyStoreError>;
+    fn delete(&self, identifier: String) -> Result<(), KeyStoreError>;
 }
 
 pub fn get_signing_key_bridge() -> &'static dyn SigningKeyBridge {
@@ -10,7 +10,7 @@ use wallet_common::{
 
 use crate::bridge::hw_keystore::{get_encryption_key_bridge, get_signing_key_bridge};
 
-use super::{HardwareKeyStoreError, KeyStoreError, PlatformEcdsaKey};
+use super::{HardwareKeyStoreError, IntegrityEvidence, KeyStoreError, PlatformEcdsaKey};
 
 impl From<KeyStoreError> for p256::ecdsa::Error {
     // wrap KeyStoreError in p256::ecdsa::signature::error,
@@ -68,7 +68,21 @@ impl WithIdentifier for HardwareEcdsaKey {
     }
 }
 
-impl PlatformEcdsaKey for HardwareEcdsaKey {}
+impl PlatformEcdsaKey for HardwareEcdsaKey {
+    async fn delete(&self) -> Result<(), Self::Error> {
+        let identifier = self.identifier.to_owned();
+        spawn::blocking(|| get_signing_key_bridge().delete(identifier)).await?;
+
+        Ok(())
+    }
+
+    async fn integrity_evidence(&self, request_hash: &[u8]) -> Result<Option<IntegrityEvidence>, Self::Error> {
+        let request_hash = request_hash.to_vec();
+        let evidence = spawn::blocking(|| get_signing_key_bridge().integrity_evidence(request_hash)).await?;
+
+        Ok(evidence)
+    }
+}
 
 // HardwareEncryptionKey wraps EncryptionKeyBridge from native
 #[derive(Clone)]
@@ -106,4 +120,10 @@ impl SecureEncryptionKey for HardwareEncryptionKey {
         let decrypted = spawn::blocking(|| get_encryption_key_bridge().decrypt(identifier, payload)).await?;
         Ok(decrypted)
     }
+
+    async fn delete(&self) -> Result<(), HardwareKeyStoreError> {
+        let identifier = self.identifier.to_owned();
+        spawn::blocking(|| get_encryption_key_bridge().delete(identifier)).await?;
+        Ok(())
+    }
 }
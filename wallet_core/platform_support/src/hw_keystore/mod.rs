@@ -1,6 +1,12 @@
 pub mod hardware;
 
-use wallet_common::keys::{ConstructibleWithIdentifier, SecureEcdsaKey};
+use wallet_common::{
+    account::messages::instructions,
+    keys::{ConstructibleWithIdentifier, SecureEcdsaKey},
+};
+
+#[cfg(feature = "software")]
+use wallet_common::keys::WithIdentifier;
 
 #[derive(Debug, thiserror::Error)]
 pub enum HardwareKeyStoreError {
@@ -21,13 +27,47 @@ pub enum KeyStoreError {
     BridgingError { reason: String },
 }
 
+// implementation of IntegrityEvidence from UDL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntegrityEvidence {
+    PlayIntegrity { token: String },
+    AppAttest { key_id: Vec<u8>, attestation: Vec<u8> },
+}
+
+impl From<IntegrityEvidence> for instructions::IntegrityEvidence {
+    fn from(value: IntegrityEvidence) -> Self {
+        match value {
+            IntegrityEvidence::PlayIntegrity { token } => Self::PlayIntegrity { token },
+            IntegrityEvidence::AppAttest { key_id, attestation } => Self::AppAttest {
+                key_id: key_id.into(),
+                attestation: attestation.into(),
+            },
+        }
+    }
+}
+
 /// Contract for ECDSA private keys suitable for use in the wallet, e.g. as the authentication key for the WP.
 /// Should be sufficiently secured e.g. through Android's TEE/StrongBox or Apple's SE.
 /// Handles to private keys are requested through [`ConstructibleWithIdentifier::new()`].
 pub trait PlatformEcdsaKey: ConstructibleWithIdentifier + SecureEcdsaKey {
     // from ConstructibleWithIdentifier: new(), identifier()
     // from SecureSigningKey: verifying_key(), try_sign() and sign() methods
+
+    /// Permanently delete the private key. A new key will be created when this identifier is used again.
+    async fn delete(&self) -> Result<(), Self::Error>;
+
+    /// Collect evidence of the integrity of the app and the device that hold this key, which is bound to
+    /// `request_hash`. This returns `None` if the platform is not able to provide such evidence.
+    async fn integrity_evidence(&self, _request_hash: &[u8]) -> Result<Option<IntegrityEvidence>, Self::Error> {
+        Ok(None)
+    }
 }
 
 #[cfg(feature = "software")]
-impl PlatformEcdsaKey for wallet_common::keys::software::SoftwareEcdsaKey {}
+impl PlatformEcdsaKey for wallet_common::keys::software::SoftwareEcdsaKey {
+    async fn delete(&self) -> Result<(), Self::Error> {
+        wallet_common::keys::software::SoftwareEcdsaKey::remove(self.identifier());
+
+        Ok(())
+    }
+}
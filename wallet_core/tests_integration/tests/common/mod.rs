@@ -27,17 +27,21 @@ use pid_issuer::{
 };
 use platform_support::utils::{software::SoftwareUtilities, PlatformUtilities};
 use wallet::{
-    mock::{default_configuration, MockDigidSession, MockStorage},
+    mock::{default_configuration, DigidSessionState, MockDigidSession, MockStorage},
     wallet_deps::{
-        ConfigServerConfiguration, HttpAccountProviderClient, HttpConfigurationRepository, HttpPidIssuerClient,
+        ConfigServerConfiguration, HttpAccountProviderClient, HttpConfigurationRepository, HttpCredentialIssuerClient,
         UpdateableConfigurationRepository,
     },
     Wallet,
 };
-use wallet_common::{config::wallet_config::WalletConfiguration, keys::software::SoftwareEcdsaKey};
+use wallet_common::{
+    config::wallet_config::{RetryConfiguration, WalletConfiguration},
+    correlation::CorrelationTracker,
+    keys::software::SoftwareEcdsaKey,
+};
 use wallet_provider::settings::Settings as WpSettings;
 use wallet_provider_persistence::entity::wallet_user;
-use wallet_server::settings::{Server, Settings as WsSettings};
+use wallet_server::{settings::Settings as WsSettings, store::StoreConnection};
 
 #[ctor]
 fn init_logging() {
@@ -61,6 +65,18 @@ pub fn local_pid_base_url(port: &u16) -> Url {
     Url::parse(&format!("http://localhost:{}/", port)).expect("Could not create url")
 }
 
+/// The state returned by a `MockDigidSession` when it is persisted.
+pub fn mock_digid_session_state() -> DigidSessionState {
+    DigidSessionState {
+        redirect_uri_base: Url::parse("http://localhost/").unwrap(),
+        csrf_token: "mock_csrf_token".to_string(),
+        nonce: "mock_nonce".to_string(),
+        pkce_code_verifier: "mock_pkce_code_verifier".to_string(),
+        auth_url: Url::parse("http://localhost/").unwrap(),
+        refresh_token: None,
+    }
+}
+
 pub async fn database_connection(settings: &WpSettings) -> DatabaseConnection {
     Database::connect(settings.database.connection_string())
         .await
@@ -73,7 +89,7 @@ pub type WalletWithMocks = Wallet<
     SoftwareEcdsaKey,
     HttpAccountProviderClient,
     MockDigidSession,
-    HttpPidIssuerClient,
+    HttpCredentialIssuerClient,
 >;
 
 pub async fn setup_wallet_and_default_env() -> WalletWithMocks {
@@ -109,16 +125,28 @@ pub async fn setup_wallet_and_env(
 
     start_config_server(cs_settings, config_jwt(&served_wallet_config)).await;
     start_wallet_provider(wp_settings).await;
-    start_wallet_server(ws_settings, MemorySessionStore::new()).await;
+    start_wallet_server(ws_settings, StoreConnection::Memory, MemorySessionStore::new()).await;
     start_pid_issuer(pid_settings, MockAttributesLookup::default(), MockBsnLookup::default()).await;
 
-    let pid_issuer_client = HttpPidIssuerClient::new(MdocWallet::new(CborHttpClient(reqwest::Client::new())));
+    let pid_issuer_client = HttpCredentialIssuerClient::new(
+        MdocWallet::new(CborHttpClient::new(
+            reqwest::Client::new(),
+            CorrelationTracker::default(),
+        )),
+        RetryConfiguration::default(),
+        CorrelationTracker::default(),
+    );
 
     let config_repository = HttpConfigurationRepository::new(
         config_server_config.base_url,
-        config_server_config.signing_public_key.into(),
+        config_server_config
+            .signing_public_keys
+            .into_iter()
+            .map(|key| key.0)
+            .collect(),
         SoftwareUtilities::storage_path().await.unwrap(),
         wallet_config,
+        0,
     )
     .await
     .unwrap();
@@ -183,10 +211,15 @@ pub fn wallet_provider_settings() -> WpSettings {
     settings
 }
 
-pub async fn start_config_server(settings: CsSettings, config_jwt: Vec<u8>) {
+/// Start the configuration server, returning the path of the file that it reads the configuration from.
+pub async fn start_config_server(settings: CsSettings, config_jwt: Vec<u8>) -> PathBuf {
     let base_url = local_config_base_url(&settings.port);
+    // The configuration server reads the configuration from a file, which is unique per server as its port is.
+    let config_path = env::temp_dir().join(format!("wallet-config-{}.jws", settings.port));
+    std::fs::write(&config_path, config_jwt).unwrap();
+    let served_config_path = config_path.clone();
     tokio::spawn(async {
-        if let Err(error) = configuration_server::server::serve(settings, config_jwt).await {
+        if let Err(error) = configuration_server::server::serve(settings, served_config_path).await {
             println!("Could not start config_server: {:?}", error);
 
             process::exit(1);
@@ -194,12 +227,14 @@ pub async fn start_config_server(settings: CsSettings, config_jwt: Vec<u8>) {
     });
 
     wait_for_server(base_url).await;
+
+    config_path
 }
 
 pub async fn start_wallet_provider(settings: WpSettings) {
     let base_url = local_wp_base_url(&settings.webserver.port);
     tokio::spawn(async {
-        if let Err(error) = wallet_provider::server::serve(settings).await {
+        if let Err(error) = wallet_provider::server::serve(settings, None).await {
             println!("Could not start wallet_provider: {:?}", error);
 
             process::exit(1);
@@ -244,23 +279,21 @@ pub fn wallet_server_settings() -> WsSettings {
     settings.wallet_server.port = ws_port;
 
     let requester_port = find_listener_port();
-    settings.requester_server = Server {
-        ip: IpAddr::from_str("127.0.0.1").unwrap(),
-        port: requester_port,
-    };
+    settings.requester_server.ip = IpAddr::from_str("127.0.0.1").unwrap();
+    settings.requester_server.port = requester_port;
 
     settings.public_url = Url::parse(&format!("http://localhost:{}/", ws_port)).unwrap();
     settings.internal_url = Url::parse(&format!("http://localhost:{}/", requester_port)).unwrap();
     settings
 }
 
-pub async fn start_wallet_server<S>(settings: WsSettings, sessions: S)
+pub async fn start_wallet_server<S>(settings: WsSettings, store_connection: StoreConnection, sessions: S)
 where
     S: SessionStore<Data = SessionState<DisclosureData>> + Send + Sync + 'static,
 {
     let public_url = settings.public_url.clone();
     tokio::spawn(async move {
-        if let Err(error) = wallet_server::server::serve::<S>(&settings, sessions).await {
+        if let Err(error) = wallet_server::server::serve::<S>(&settings, store_connection, sessions).await {
             println!("Could not start wallet_server: {:?}", error);
 
             process::exit(1);
@@ -1,12 +1,16 @@
-use std::env;
+use std::{env, time::Duration};
 
 use assert_matches::assert_matches;
 use jsonwebtoken::{Algorithm, EncodingKey, Header};
 use p256::{ecdsa::SigningKey, elliptic_curve::rand_core::OsRng, pkcs8::EncodePrivateKey};
 use regex::Regex;
-use reqwest::header::HeaderValue;
+use reqwest::{
+    header::{HeaderValue, ACCEPT_ENCODING, CONTENT_ENCODING, ETAG, IF_MODIFIED_SINCE, LAST_MODIFIED},
+    StatusCode,
+};
 use serial_test::serial;
-use tokio::fs;
+use tokio::{fs, time};
+use url::Url;
 
 use wallet::{
     errors::ConfigurationError,
@@ -51,9 +55,14 @@ async fn test_wallet_config() {
 
     let http_config = HttpConfigurationRepository::new(
         config_server_config.base_url,
-        config_server_config.signing_public_key.into(),
+        config_server_config
+            .signing_public_keys
+            .into_iter()
+            .map(|key| key.0)
+            .collect(),
         storage_path.clone(),
         wallet_config,
+        0,
     )
     .await
     .unwrap();
@@ -99,9 +108,14 @@ async fn test_wallet_config_stale() {
 
     let http_config = HttpConfigurationRepository::new(
         config_server_config.base_url,
-        config_server_config.signing_public_key.into(),
+        config_server_config
+            .signing_public_keys
+            .into_iter()
+            .map(|key| key.0)
+            .collect(),
         env::temp_dir(),
         wallet_config,
+        0,
     )
     .await
     .unwrap();
@@ -114,6 +128,59 @@ async fn test_wallet_config_stale() {
     assert_eq!(before.version, after.version);
 }
 
+#[tokio::test]
+#[serial]
+#[cfg_attr(not(feature = "db_test"), ignore)]
+async fn test_wallet_config_downgrade() {
+    let settings = wallet_provider_settings();
+
+    let mut served_wallet_config = default_configuration();
+    served_wallet_config.account_server.base_url = local_wp_base_url(&settings.webserver.port);
+    served_wallet_config.version = 2;
+
+    let cs_settings = config_server_settings();
+    let config_server_config = ConfigServerConfiguration {
+        base_url: local_config_base_url(&cs_settings.port),
+        ..Default::default()
+    };
+    start_config_server(cs_settings, config_jwt(&served_wallet_config)).await;
+
+    start_wallet_provider(settings.clone()).await;
+
+    let mut wallet_config = default_configuration();
+    wallet_config.account_server.base_url = local_wp_base_url(&settings.webserver.port);
+
+    let storage_path = env::temp_dir();
+    let _ = fs::remove_file(storage_path.join("latest-configuration-etag.txt")).await;
+
+    let http_config = HttpConfigurationRepository::new(
+        config_server_config.base_url,
+        config_server_config
+            .signing_public_keys
+            .into_iter()
+            .map(|key| key.0)
+            .collect(),
+        storage_path,
+        wallet_config,
+        // A higher version of the configuration was accepted before, e.g. before the stored configuration was replaced.
+        3,
+    )
+    .await
+    .unwrap();
+
+    // The correctly signed, but older configuration should be rejected.
+    let result = http_config.fetch().await.expect_err("Expecting downgrade error");
+
+    assert_matches!(
+        result,
+        ConfigurationError::Downgrade {
+            version: 2,
+            highest_version: 3
+        }
+    );
+    assert_eq!(http_config.config().version, default_configuration().version);
+}
+
 #[tokio::test]
 #[serial]
 #[cfg_attr(not(feature = "db_test"), ignore)]
@@ -154,9 +221,14 @@ async fn test_wallet_config_signature_verification_failed() {
 
     let http_config = HttpConfigurationRepository::new(
         config_server_config.base_url,
-        config_server_config.signing_public_key.into(),
+        config_server_config
+            .signing_public_keys
+            .into_iter()
+            .map(|key| key.0)
+            .collect(),
         env::temp_dir(),
         wallet_config,
+        0,
     )
     .await
     .unwrap();
@@ -168,3 +240,114 @@ async fn test_wallet_config_signature_verification_failed() {
 
     assert_matches!(result, ConfigurationError::Jwt(JwtError::Validation(e)) if *e.kind() == jsonwebtoken::errors::ErrorKind::InvalidSignature);
 }
+
+/// Fetch the configuration, returning its ETag and contents.
+async fn fetch_config(client: &reqwest::Client, base_url: &Url) -> (HeaderValue, Vec<u8>) {
+    let response = client
+        .get(base_url.join("wallet-config").unwrap())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let etag = response.headers().get(ETAG).unwrap().to_owned();
+    let body = response.bytes().await.unwrap().to_vec();
+
+    (etag, body)
+}
+
+#[tokio::test]
+async fn test_config_server_reload() {
+    let mut cs_settings = config_server_settings();
+    cs_settings.reload_interval_in_seconds = 1;
+    let base_url = local_config_base_url(&cs_settings.port);
+
+    let served_jwt = config_jwt(&default_configuration());
+    let config_path = start_config_server(cs_settings, served_jwt.clone()).await;
+
+    let client = reqwest::Client::new();
+    let (etag, body) = fetch_config(&client, &base_url).await;
+    assert_eq!(body, served_jwt);
+
+    // A changed configuration file is served after it has been reloaded, with a new ETag.
+    let mut new_wallet_config = default_configuration();
+    new_wallet_config.version += 1;
+    let new_jwt = config_jwt(&new_wallet_config);
+    fs::write(&config_path, &new_jwt).await.unwrap();
+    time::sleep(Duration::from_secs(2)).await;
+
+    let (new_etag, body) = fetch_config(&client, &base_url).await;
+    assert_eq!(body, new_jwt);
+    assert_ne!(new_etag, etag);
+
+    // When the configuration file cannot be read, the current configuration is still served.
+    fs::remove_file(&config_path).await.unwrap();
+    time::sleep(Duration::from_secs(2)).await;
+
+    assert_eq!(fetch_config(&client, &base_url).await, (new_etag, new_jwt));
+}
+
+#[tokio::test]
+async fn test_config_server_if_modified_since() {
+    let mut cs_settings = config_server_settings();
+    cs_settings.reload_interval_in_seconds = 1;
+    let base_url = local_config_base_url(&cs_settings.port);
+    let config_path = start_config_server(cs_settings, config_jwt(&default_configuration())).await;
+
+    let client = reqwest::Client::new();
+    let config_url = base_url.join("wallet-config").unwrap();
+    let response = client.get(config_url.clone()).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let last_modified = response.headers().get(LAST_MODIFIED).unwrap().to_owned();
+
+    // The configuration has not been modified since it was last fetched.
+    let response = client
+        .get(config_url.clone())
+        .header(IF_MODIFIED_SINCE, &last_modified)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+    // Once a newer configuration has been loaded, it is returned in full.
+    let mut new_wallet_config = default_configuration();
+    new_wallet_config.version += 1;
+    let new_jwt = config_jwt(&new_wallet_config);
+    fs::write(&config_path, &new_jwt).await.unwrap();
+    time::sleep(Duration::from_secs(2)).await;
+
+    let response = client
+        .get(config_url)
+        .header(IF_MODIFIED_SINCE, &last_modified)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_ne!(response.headers().get(LAST_MODIFIED).unwrap(), &last_modified);
+    assert_eq!(response.bytes().await.unwrap(), new_jwt);
+}
+
+#[tokio::test]
+async fn test_config_server_compression() {
+    let cs_settings = config_server_settings();
+    let base_url = local_config_base_url(&cs_settings.port);
+    start_config_server(cs_settings, config_jwt(&default_configuration())).await;
+
+    // Do not let the client decompress the response, so that its encoding can be checked.
+    let client = reqwest::Client::builder().no_gzip().build().unwrap();
+    let config_url = base_url.join("wallet-config").unwrap();
+
+    let response = client
+        .get(config_url.clone())
+        .header(ACCEPT_ENCODING, "gzip")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers().get(CONTENT_ENCODING).unwrap(), "gzip");
+
+    // Without the Accept-Encoding header, the response is not compressed.
+    let response = client.get(config_url).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().get(CONTENT_ENCODING).is_none());
+}
@@ -35,7 +35,7 @@ async fn get_verifier_status(client: &reqwest::Client, session_url: Url) -> Stat
 #[cfg_attr(not(feature = "db_test"), ignore)]
 async fn test_disclosure_ok(#[case] session_type: SessionType, #[case] return_url: Option<ReturnUrlTemplate>) {
     let digid_context = MockDigidSession::start_context();
-    digid_context.expect().return_once(|_, _, _| {
+    digid_context.expect().return_once(|_, _| {
         let mut session = MockDigidSession::default();
 
         session
@@ -43,6 +43,8 @@ async fn test_disclosure_ok(#[case] session_type: SessionType, #[case] return_ur
             .return_const(Url::parse("http://localhost/").unwrap());
 
         // Return a mock access token from the mock DigiD client that the `MockBsnLookup` always accepts.
+        session.expect_to_state().return_const(mock_digid_session_state());
+        session.expect_can_refresh_access_token().return_const(false);
         session
             .expect_get_access_token()
             .returning(|_| Ok("mock_token".to_string()));
@@ -84,6 +86,7 @@ async fn test_disclosure_ok(#[case] session_type: SessionType, #[case] return_ur
         // The setup script is hardcoded to include "http://localhost:3004/" in the `ReaderRegistration`
         // contained in the certificate, so we have to specify a return URL prefixed with that.
         return_url_template: return_url,
+        callback_url: None,
     };
     let response = client
         .post(
@@ -176,7 +179,7 @@ async fn test_disclosure_ok(#[case] session_type: SessionType, #[case] return_ur
 #[cfg_attr(not(feature = "db_test"), ignore)]
 async fn test_disclosure_without_pid() {
     let digid_context = MockDigidSession::start_context();
-    digid_context.expect().return_once(|_, _, _| {
+    digid_context.expect().return_once(|_, _| {
         let mut session = MockDigidSession::default();
 
         session
@@ -184,6 +187,8 @@ async fn test_disclosure_without_pid() {
             .return_const(Url::parse("http://localhost/").unwrap());
 
         // Return a mock access token from the mock DigiD client that the `MockBsnLookup` always accepts.
+        session.expect_to_state().return_const(mock_digid_session_state());
+        session.expect_can_refresh_access_token().return_const(false);
         session
             .expect_get_access_token()
             .returning(|_| Ok("mock_token".to_string()));
@@ -222,6 +227,7 @@ async fn test_disclosure_without_pid() {
         }]
         .into(),
         return_url_template: None,
+        callback_url: None,
     };
     let response = client
         .post(
@@ -12,7 +12,7 @@ pub mod common;
 #[cfg_attr(not(feature = "db_test"), ignore)]
 async fn test_pid_ok() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let digid_context = MockDigidSession::start_context();
-    digid_context.expect().return_once(|_, _, _| {
+    digid_context.expect().return_once(|_, _| {
         let mut session = MockDigidSession::default();
 
         session
@@ -20,6 +20,8 @@ async fn test_pid_ok() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
             .return_const(Url::parse("http://localhost/").unwrap());
 
         // Return a mock access token from the mock DigiD client that the `MockBsnLookup` always accepts.
+        session.expect_to_state().return_const(mock_digid_session_state());
+        session.expect_can_refresh_access_token().return_const(false);
         session
             .expect_get_access_token()
             .returning(|_| Ok("mock_token".to_string()));
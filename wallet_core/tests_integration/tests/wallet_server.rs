@@ -3,23 +3,24 @@ use std::collections::HashMap;
 use base64::prelude::*;
 use indexmap::IndexMap;
 use p256::pkcs8::EncodePrivateKey;
-use reqwest::StatusCode;
+use reqwest::{header::CONTENT_TYPE, StatusCode};
 use url::Url;
 
 use nl_wallet_mdoc::{
+    mock::reader_registration_attributes,
     server_state::SessionToken,
     utils::{
-        reader_auth::reader_registration_mock,
+        reader_auth::{reader_registration_mock, ReaderRegistration},
         serialization::cbor_deserialize,
         x509::{Certificate, CertificateType},
     },
-    verifier::SessionType,
+    verifier::{SessionSummary, SessionType, StatusResponse},
     ItemsRequest, ReaderEngagement,
 };
 use wallet_server::{
-    settings::{KeyPair, Settings},
-    store::DisclosureSessionStore,
-    verifier::{StartDisclosureRequest, StartDisclosureResponse},
+    settings::{Settings, Usecase},
+    store::{DisclosureSessionStore, StoreConnection},
+    verifier::{EngagementResponse, StartDisclosureRequest, StartDisclosureResponse},
 };
 
 use crate::common::*;
@@ -40,18 +41,26 @@ fn wallet_server_settings() -> Settings {
         settings.store_url = "memory://".parse().unwrap();
     }
 
+    let reader_registration = ReaderRegistration {
+        attributes: reader_registration_attributes(
+            "example_doctype".to_owned(),
+            "example_namespace".to_owned(),
+            ["first_name", "family_name"].into_iter(),
+        ),
+        ..reader_registration_mock()
+    };
     let (ca, ca_privkey) = Certificate::new_ca("ca.example.com").unwrap();
     let (cert, cert_privkey) = Certificate::new(
         &ca,
         &ca_privkey,
         "cert.example.com",
-        CertificateType::ReaderAuth(Box::new(reader_registration_mock()).into()),
+        CertificateType::ReaderAuth(Box::new(reader_registration).into()),
     )
     .unwrap();
 
     settings.usecases.insert(
         "example_usecase".to_owned(),
-        KeyPair {
+        Usecase {
             certificate: cert.as_bytes().to_vec().into(),
             private_key: cert_privkey
                 .to_pkcs8_der()
@@ -59,6 +68,8 @@ fn wallet_server_settings() -> Settings {
                 .as_bytes()
                 .to_vec()
                 .into(),
+            allowed_attributes: None,
+            return_url_prefixes: vec![],
         },
     );
 
@@ -93,17 +104,8 @@ fn parse_wallet_url(engagement_url: Url) -> Url {
         .clone()
 }
 
-#[tokio::test]
-#[cfg_attr(not(feature = "db_test"), ignore)]
-async fn test_start_session() {
-    let settings = wallet_server_settings();
-    let sessions = DisclosureSessionStore::init(settings.store_url.clone()).await.unwrap();
-
-    start_wallet_server(settings.clone(), sessions).await;
-
-    let client = reqwest::Client::new();
-
-    let start_request = StartDisclosureRequest {
+fn start_disclosure_request() -> StartDisclosureRequest {
+    StartDisclosureRequest {
         usecase: "example_usecase".to_owned(),
         session_type: SessionType::SameDevice,
         items_requests: vec![ItemsRequest {
@@ -120,7 +122,21 @@ async fn test_start_session() {
         }]
         .into(),
         return_url_template: None,
-    };
+        callback_url: None,
+    }
+}
+
+#[tokio::test]
+#[cfg_attr(not(feature = "db_test"), ignore)]
+async fn test_start_session() {
+    let settings = wallet_server_settings();
+    let store_connection = StoreConnection::try_new(settings.store_url.clone()).await.unwrap();
+    let sessions = DisclosureSessionStore::init(store_connection.clone(), &settings.sessions);
+
+    start_wallet_server(settings.clone(), store_connection, sessions).await;
+
+    let client = reqwest::Client::new();
+
     let response = client
         .post(
             settings
@@ -128,7 +144,7 @@ async fn test_start_session() {
                 .join("sessions")
                 .expect("could not join url with endpoint"),
         )
-        .json(&start_request)
+        .json(&start_disclosure_request())
         .send()
         .await
         .unwrap();
@@ -141,10 +157,32 @@ async fn test_start_session() {
         engagement_url,
         ..
     } = response.json::<StartDisclosureResponse>().await.unwrap();
-    let response = client.get(session_url).send().await.unwrap();
+    let response = client.get(session_url.clone()).send().await.unwrap();
 
     assert_eq!(response.status(), StatusCode::OK);
 
+    // can the frontend of the RP retrieve the engagement, also as a QR code?
+    let response = client
+        .get(session_url.join("engagement").unwrap())
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.json::<EngagementResponse>().await.unwrap().engagement_url,
+        engagement_url
+    );
+
+    let response = client
+        .get(session_url.join("engagement/qr?format=png").unwrap())
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[CONTENT_TYPE], "image/png");
+
     // does it exist for the wallet side of things?
     let wallet_url = parse_wallet_url(engagement_url);
     let response = client.post(wallet_url).body("hello").send().await.unwrap();
@@ -155,13 +193,89 @@ async fn test_start_session() {
     assert_eq!(response.status(), StatusCode::BAD_REQUEST);
 }
 
+#[tokio::test]
+#[cfg_attr(not(feature = "db_test"), ignore)]
+async fn test_cancel_session() {
+    let settings = wallet_server_settings();
+    let store_connection = StoreConnection::try_new(settings.store_url.clone()).await.unwrap();
+    let sessions = DisclosureSessionStore::init(store_connection.clone(), &settings.sessions);
+
+    start_wallet_server(settings.clone(), store_connection, sessions).await;
+
+    let client = reqwest::Client::new();
+    let sessions_url = settings.internal_url.join("sessions").unwrap();
+
+    let StartDisclosureResponse {
+        session_url,
+        engagement_url,
+        ..
+    } = client
+        .post(sessions_url.clone())
+        .json(&start_disclosure_request())
+        .send()
+        .await
+        .unwrap()
+        .json::<StartDisclosureResponse>()
+        .await
+        .unwrap();
+    let session_id = session_url
+        .path_segments()
+        .and_then(|mut segments| segments.next())
+        .unwrap()
+        .to_string();
+
+    // the new session is listed for its usecase
+    let response = client
+        .get(sessions_url.clone())
+        .query(&[("usecase", "example_usecase"), ("status", "CREATED")])
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response
+        .json::<Vec<SessionSummary>>()
+        .await
+        .unwrap()
+        .iter()
+        .any(|summary| summary.session_id.to_string() == session_id));
+
+    // cancel the session, after which it cannot be cancelled again
+    let cancel_url = settings.internal_url.join(&format!("sessions/{}", session_id)).unwrap();
+    let response = client.delete(cancel_url.clone()).send().await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = client.delete(cancel_url).send().await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = client.get(session_url).send().await.unwrap();
+
+    assert_eq!(
+        response.json::<StatusResponse>().await.unwrap(),
+        StatusResponse::Cancelled
+    );
+
+    // the wallet is told that the session has been terminated, regardless of what it sends
+    let response = client
+        .post(parse_wallet_url(engagement_url))
+        .body("hello")
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
 #[tokio::test]
 #[cfg_attr(not(feature = "db_test"), ignore)]
 async fn test_session_not_found() {
     let settings = wallet_server_settings();
-    let sessions = DisclosureSessionStore::init(settings.store_url.clone()).await.unwrap();
+    let store_connection = StoreConnection::try_new(settings.store_url.clone()).await.unwrap();
+    let sessions = DisclosureSessionStore::init(store_connection.clone(), &settings.sessions);
 
-    start_wallet_server(settings.clone(), sessions).await;
+    start_wallet_server(settings.clone(), store_connection, sessions).await;
 
     let client = reqwest::Client::new();
     // does it exist for the RP side of things?
@@ -187,3 +301,49 @@ async fn test_session_not_found() {
 
     assert_eq!(response.status(), StatusCode::NOT_FOUND);
 }
+
+#[tokio::test]
+#[cfg_attr(not(feature = "db_test"), ignore)]
+async fn test_requester_api_key() {
+    let mut settings = wallet_server_settings();
+    settings.requester_server.api_key = Some("secret_key".to_owned());
+    let store_connection = StoreConnection::try_new(settings.store_url.clone()).await.unwrap();
+    let sessions = DisclosureSessionStore::init(store_connection.clone(), &settings.sessions);
+
+    start_wallet_server(settings.clone(), store_connection, sessions).await;
+
+    let client = reqwest::Client::new();
+    let disclosed_attributes_url = settings
+        .internal_url
+        .join(&format!("sessions/{}/disclosed_attributes", SessionToken::new()))
+        .unwrap();
+
+    // requests without a valid API key are rejected
+    let response = client.get(disclosed_attributes_url.clone()).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = client
+        .get(disclosed_attributes_url.clone())
+        .bearer_auth("wrong_key")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // with the API key, the request reaches the endpoint
+    let response = client
+        .get(disclosed_attributes_url)
+        .bearer_auth("secret_key")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // the health check does not require the API key
+    let response = client
+        .get(settings.internal_url.join("sessions/health").unwrap())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
@@ -3,4 +3,5 @@ pub mod history_event;
 pub mod history_event_doc_type;
 pub mod keyed_data;
 pub mod mdoc;
+pub mod mdoc_attribute;
 pub mod mdoc_copy;
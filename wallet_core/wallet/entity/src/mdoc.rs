@@ -13,6 +13,8 @@ pub struct Model {
 pub enum Relation {
     #[sea_orm(has_many = "super::mdoc_copy::Entity")]
     MdocCopy,
+    #[sea_orm(has_many = "super::mdoc_attribute::Entity")]
+    MdocAttribute,
 }
 
 impl Related<super::mdoc_copy::Entity> for Entity {
@@ -21,4 +23,10 @@ impl Related<super::mdoc_copy::Entity> for Entity {
     }
 }
 
+impl Related<super::mdoc_attribute::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MdocAttribute.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,45 @@
+use sea_orm::entity::prelude::*;
+use uuid::Uuid;
+
+#[derive(Clone, Debug, Eq, PartialEq, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+pub enum ValueType {
+    #[sea_orm(string_value = "Text")]
+    Text,
+    #[sea_orm(string_value = "Bool")]
+    Bool,
+    #[sea_orm(string_value = "Integer")]
+    Integer,
+    #[sea_orm(string_value = "Other")]
+    Other,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "mdoc_attribute")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    pub mdoc_id: Uuid,
+    pub name_space: String,
+    pub name: String,
+    pub value_type: ValueType,
+    pub value: Json,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::mdoc::Entity",
+        from = "Column::MdocId",
+        to = "super::mdoc::Column::Id"
+    )]
+    Mdoc,
+}
+
+impl Related<super::mdoc::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Mdoc.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
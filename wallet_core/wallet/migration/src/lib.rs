@@ -3,6 +3,7 @@ pub use sea_orm_migration::prelude::*;
 mod m20230425_140221_create_keyed_data_table;
 mod m20230922_095234_create_mdoc_tables;
 mod m20231115_100948_create_history_tables;
+mod m20240105_101500_create_mdoc_attribute_table;
 
 pub struct Migrator;
 
@@ -12,6 +13,7 @@ impl MigratorTrait for Migrator {
             Box::new(m20230425_140221_create_keyed_data_table::Migration),
             Box::new(m20230922_095234_create_mdoc_tables::Migration),
             Box::new(m20231115_100948_create_history_tables::Migration),
+            Box::new(m20240105_101500_create_mdoc_attribute_table::Migration),
         ]
     }
 }
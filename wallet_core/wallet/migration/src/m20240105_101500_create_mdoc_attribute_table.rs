@@ -0,0 +1,51 @@
+use async_trait::async_trait;
+use sea_orm_migration::prelude::*;
+
+use crate::m20230922_095234_create_mdoc_tables::Mdoc;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(MdocAttribute::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(MdocAttribute::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(MdocAttribute::MdocId).uuid().not_null())
+                    .col(ColumnDef::new(MdocAttribute::NameSpace).text().not_null())
+                    .col(ColumnDef::new(MdocAttribute::Name).text().not_null())
+                    .col(ColumnDef::new(MdocAttribute::ValueType).text().not_null())
+                    .col(ColumnDef::new(MdocAttribute::Value).json().not_null())
+                    // In sqlite/sqlcipher foreign keys can only be created as part of the create table statement.
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(MdocAttribute::Table, MdocAttribute::MdocId)
+                            .to(Mdoc::Table, Mdoc::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(MdocAttribute::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum MdocAttribute {
+    Table,
+    Id,
+    MdocId,
+    NameSpace,
+    Name,
+    ValueType,
+    Value,
+}
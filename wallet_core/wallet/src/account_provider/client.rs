@@ -1,31 +1,47 @@
 use http::{header, HeaderMap, HeaderValue};
 use mime::Mime;
-use reqwest::{Client, Request};
+use reqwest::{Client, Request, Response, StatusCode};
 use serde::{de::DeserializeOwned, Serialize};
 use url::Url;
 
-use wallet_common::account::{
-    messages::{
-        auth::{Certificate, Challenge, Registration, WalletCertificate},
-        errors::ErrorData,
-        instructions::{
-            Instruction, InstructionChallengeRequestMessage, InstructionEndpoint, InstructionResult,
-            InstructionResultMessage,
+use wallet_common::{
+    account::{
+        messages::{
+            auth::{Certificate, Challenge, Registration, RevocationRequestMessage, WalletCertificate},
+            errors::ErrorData,
+            instructions::{
+                Instruction, InstructionChallengeRequestMessage, InstructionEndpoint, InstructionResult,
+                InstructionResultMessage,
+            },
         },
+        signed::SignedDouble,
     },
-    signed::SignedDouble,
+    config::wallet_config::RetryConfiguration,
+    correlation::CorrelationTracker,
 };
 
-use crate::utils::reqwest::default_reqwest_client_builder;
+use crate::{
+    clock_skew::ClockSkew,
+    utils::{
+        reqwest::default_reqwest_client_builder,
+        retry::{retry_with_backoff, Idempotency},
+    },
+};
 
 use super::{AccountProviderClient, AccountProviderError, AccountProviderResponseError};
 
+#[derive(Clone)]
 pub struct HttpAccountProviderClient {
     http_client: Client,
+    retry_config: RetryConfiguration,
+    clock_skew: ClockSkew,
+    correlation: CorrelationTracker,
 }
 
 impl HttpAccountProviderClient {
-    fn new() -> Self {
+    /// Create a new client, which reports the time of the Wallet Provider from its responses to `clock_skew` and sends
+    /// the correlation ID of the current operation of `correlation` along with every request.
+    pub fn new(retry_config: RetryConfiguration, clock_skew: ClockSkew, correlation: CorrelationTracker) -> Self {
         let http_client = default_reqwest_client_builder()
             .default_headers(HeaderMap::from_iter([(
                 header::ACCEPT,
@@ -34,25 +50,79 @@ impl HttpAccountProviderClient {
             .build()
             .expect("Could not build reqwest HTTP client");
 
-        HttpAccountProviderClient { http_client }
+        HttpAccountProviderClient {
+            http_client,
+            retry_config,
+            clock_skew,
+            correlation,
+        }
     }
 
-    async fn send_json_post_request<S, T>(&self, url: Url, json: &S) -> Result<T, AccountProviderError>
+    async fn send_json_post_request<S, T>(
+        &self,
+        url: Url,
+        json: &S,
+        idempotency: Idempotency,
+    ) -> Result<T, AccountProviderError>
     where
         S: Serialize,
         T: DeserializeOwned,
     {
         let request = self.http_client.post(url).json(json).build()?;
-        self.send_json_request::<T>(request).await
+        self.send_json_request::<T>(request, idempotency).await
     }
 
-    async fn send_json_request<T>(&self, request: Request) -> Result<T, AccountProviderError>
+    async fn send_json_request<T>(&self, request: Request, idempotency: Idempotency) -> Result<T, AccountProviderError>
     where
         T: DeserializeOwned,
     {
+        let response = self.send_request(request, idempotency).await?;
+        let body = response.json().await?;
+
+        Ok(body)
+    }
+
+    /// Send the request, retrying it with backoff when it fails because of a transient error.
+    /// Which errors are considered to be transient depends on the idempotency of the request.
+    async fn send_request(&self, request: Request, idempotency: Idempotency) -> Result<Response, AccountProviderError> {
+        retry_with_backoff(
+            &self.retry_config,
+            |error| Self::can_retry(error, idempotency),
+            move || {
+                // Requests sent to the Wallet Provider either have no body or a JSON body, which can always be cloned.
+                let request = request.try_clone().expect("Could not clone request");
+
+                self.execute_request(request)
+            },
+        )
+        .await
+    }
+
+    fn can_retry(error: &AccountProviderError, idempotency: Idempotency) -> bool {
+        match error {
+            AccountProviderError::Networking(error) => idempotency.can_retry_reqwest_error(error),
+            // These status codes indicate that the Wallet Provider is temporarily unavailable. As it is unknown
+            // if the request was processed before the error occurred, it may only be retried if it is idempotent.
+            AccountProviderError::Response(error) => {
+                idempotency == Idempotency::Idempotent
+                    && matches!(
+                        error.status(),
+                        StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT
+                    )
+            }
+            AccountProviderError::BaseUrl(_) => false,
+        }
+    }
+
+    async fn execute_request(&self, mut request: Request) -> Result<Response, AccountProviderError> {
+        request.headers_mut().extend(self.correlation.headers());
+
         let response = self.http_client.execute(request).await?;
         let status = response.status();
 
+        // Any response, including an error response, contains the time of the Wallet Provider.
+        self.clock_skew.report_headers(response.headers());
+
         // In case of a 4xx or 5xx response...
         if status.is_client_error() || status.is_server_error() {
             let content_length = response.content_length();
@@ -95,15 +165,17 @@ impl HttpAccountProviderClient {
             return Err(AccountProviderError::Response(error));
         }
 
-        let body = response.json().await?;
-
-        Ok(body)
+        Ok(response)
     }
 }
 
 impl Default for HttpAccountProviderClient {
     fn default() -> Self {
-        Self::new()
+        Self::new(
+            RetryConfiguration::default(),
+            ClockSkew::default(),
+            CorrelationTracker::default(),
+        )
     }
 }
 
@@ -111,7 +183,9 @@ impl AccountProviderClient for HttpAccountProviderClient {
     async fn registration_challenge(&self, base_url: &Url) -> Result<Vec<u8>, AccountProviderError> {
         let url = base_url.join("enroll")?;
         let request = self.http_client.post(url).build()?;
-        let challenge: Challenge = self.send_json_request::<Challenge>(request).await?;
+        let challenge: Challenge = self
+            .send_json_request::<Challenge>(request, Idempotency::Idempotent)
+            .await?;
 
         Ok(challenge.challenge.0)
     }
@@ -122,18 +196,38 @@ impl AccountProviderClient for HttpAccountProviderClient {
         registration_message: SignedDouble<Registration>,
     ) -> Result<WalletCertificate, AccountProviderError> {
         let url = base_url.join("createwallet")?;
-        let cert: Certificate = self.send_json_post_request(url, &registration_message).await?;
+        // The challenge in the registration message can only be used once, so the request is not idempotent.
+        let cert: Certificate = self
+            .send_json_post_request(url, &registration_message, Idempotency::NonIdempotent)
+            .await?;
 
         Ok(cert.certificate)
     }
 
+    async fn revoke(
+        &self,
+        base_url: &Url,
+        revocation_request: RevocationRequestMessage,
+    ) -> Result<(), AccountProviderError> {
+        let url = base_url.join("revokewallet")?;
+        let request = self.http_client.post(url).json(&revocation_request).build()?;
+        // Revoking deletes the wallet, after which a repeated request fails, so the request is not idempotent.
+        self.send_request(request, Idempotency::NonIdempotent).await?;
+
+        Ok(())
+    }
+
     async fn instruction_challenge(
         &self,
         base_url: &Url,
         challenge_request: InstructionChallengeRequestMessage,
     ) -> Result<Vec<u8>, AccountProviderError> {
         let url = base_url.join("instructions/challenge")?;
-        let challenge: Challenge = self.send_json_post_request(url, &challenge_request).await?;
+        // The Wallet Provider persists the sequence number in the challenge request and rejects the same sequence
+        // number when it is sent again, so the request is not idempotent.
+        let challenge: Challenge = self
+            .send_json_post_request(url, &challenge_request, Idempotency::NonIdempotent)
+            .await?;
 
         Ok(challenge.challenge.0)
     }
@@ -147,7 +241,10 @@ impl AccountProviderClient for HttpAccountProviderClient {
         I: InstructionEndpoint,
     {
         let url = base_url.join(&format!("instructions/{}", I::ENDPOINT))?;
-        let message: InstructionResultMessage<I::Result> = self.send_json_post_request(url, &instruction).await?;
+        // An instruction must never be executed twice, so it is only retried when it was never sent.
+        let message: InstructionResultMessage<I::Result> = self
+            .send_json_post_request(url, &instruction, Idempotency::NonIdempotent)
+            .await?;
 
         Ok(message.result)
     }
@@ -165,11 +262,11 @@ mod tests {
     use serde::{Deserialize, Serialize};
     use serde_json::json;
     use wiremock::{
-        matchers::{method, path},
+        matchers::{self, method, path},
         Mock, MockServer, ResponseTemplate,
     };
 
-    use wallet_common::account::messages::errors::ErrorType;
+    use wallet_common::{account::messages::errors::ErrorType, correlation::CORRELATION_ID_HEADER};
 
     use super::*;
 
@@ -192,7 +289,9 @@ mod tests {
     ) -> Result<ExampleBody, AccountProviderError> {
         let request = client.http_client.post(url).build().expect("Could not create request");
 
-        client.send_json_request::<ExampleBody>(request).await
+        client
+            .send_json_request::<ExampleBody>(request, Idempotency::NonIdempotent)
+            .await
     }
 
     #[tokio::test]
@@ -322,4 +421,101 @@ mod tests {
             AccountProviderError::Response(AccountProviderResponseError::Status(StatusCode::SERVICE_UNAVAILABLE))
         ));
     }
+
+    #[tokio::test]
+    async fn test_http_account_server_client_send_json_request_retry() {
+        let (server, base_url) = create_mock_server().await;
+
+        // The first two requests are answered with a 503, after which the server recovers.
+        Mock::given(method("POST"))
+            .and(path("/foobar_retry"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .expect(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/foobar_retry"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(ExampleBody {
+                foo: "blah".to_string(),
+                bar: 1234,
+            }))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = HttpAccountProviderClient::new(
+            RetryConfiguration {
+                max_attempts: 3,
+                initial_backoff_ms: 1,
+                max_backoff_ms: 1,
+            },
+            ClockSkew::default(),
+            CorrelationTracker::default(),
+        );
+        let request = client
+            .http_client
+            .post(base_url.join("foobar_retry").unwrap())
+            .build()
+            .expect("Could not create request");
+
+        let body = client
+            .send_json_request::<ExampleBody>(request, Idempotency::Idempotent)
+            .await
+            .expect("Could not get succesful response from server after retrying");
+
+        assert_eq!(body.foo, "blah");
+        assert_eq!(body.bar, 1234);
+    }
+
+    #[tokio::test]
+    async fn test_http_account_server_client_report_clock_skew() {
+        let (server, base_url) = create_mock_server().await;
+
+        // Respond with a date that is far in the past, as if the device clock is ahead.
+        Mock::given(method("POST"))
+            .and(path("/foobar_404"))
+            .respond_with(ResponseTemplate::new(404).insert_header("Date", "Sun, 06 Nov 1994 08:49:37 GMT"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let clock_skew = ClockSkew::default();
+        let client = HttpAccountProviderClient::new(
+            RetryConfiguration::default(),
+            clock_skew.clone(),
+            CorrelationTracker::default(),
+        );
+        let _ = post_example_request(&client, base_url.join("foobar_404").unwrap()).await;
+
+        assert!(clock_skew.is_significant());
+        assert!(clock_skew.skew() < chrono::Duration::zero());
+    }
+
+    #[tokio::test]
+    async fn test_http_account_server_client_correlation_id() {
+        let (server, base_url) = create_mock_server().await;
+
+        let correlation = CorrelationTracker::default();
+        let correlation_id = correlation.start_operation();
+
+        // Only a request that contains the correlation ID of the current operation is answered successfully.
+        Mock::given(method("POST"))
+            .and(path("/foobar_correlation"))
+            .and(matchers::header(CORRELATION_ID_HEADER, correlation_id.as_str()))
+            .respond_with(ResponseTemplate::new(200).set_body_json(ExampleBody {
+                foo: "blah".to_string(),
+                bar: 1234,
+            }))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = HttpAccountProviderClient::new(RetryConfiguration::default(), ClockSkew::default(), correlation);
+        let body = post_example_request(&client, base_url.join("foobar_correlation").unwrap())
+            .await
+            .expect("Could not get successful response from server");
+
+        assert_eq!(body.foo, "blah");
+    }
 }
@@ -5,7 +5,7 @@ use url::{ParseError, Url};
 
 use wallet_common::account::{
     messages::{
-        auth::{Registration, WalletCertificate},
+        auth::{Registration, RevocationRequestMessage, WalletCertificate},
         errors::ErrorData,
         instructions::{Instruction, InstructionChallengeRequestMessage, InstructionEndpoint, InstructionResult},
     },
@@ -34,6 +34,14 @@ pub enum AccountProviderResponseError {
     Data(StatusCode, ErrorData),
 }
 
+impl AccountProviderResponseError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::Status(status) | Self::Text(status, _) | Self::Data(status, _) => *status,
+        }
+    }
+}
+
 #[cfg_attr(any(test, feature = "mock"), mockall::automock)]
 pub trait AccountProviderClient {
     async fn registration_challenge(&self, base_url: &Url) -> Result<Vec<u8>, AccountProviderError>;
@@ -44,6 +52,12 @@ pub trait AccountProviderClient {
         registration_message: SignedDouble<Registration>,
     ) -> Result<WalletCertificate, AccountProviderError>;
 
+    async fn revoke(
+        &self,
+        base_url: &Url,
+        revocation_request: RevocationRequestMessage,
+    ) -> Result<(), AccountProviderError>;
+
     async fn instruction_challenge(
         &self,
         base_url: &Url,
@@ -0,0 +1,196 @@
+use std::{
+    fmt::Debug,
+    sync::{Arc, Mutex},
+};
+
+use chrono::{DateTime, Duration, Utc};
+use http::{header, HeaderMap};
+use tracing::{info, warn};
+
+use wallet_common::generator::Generator;
+
+/// The difference between the device clock and the server clock above which the device clock is considered to be
+/// significantly off. As the `Date` header only has a resolution of a second and is set by the server before the
+/// response is sent, the measured skew always includes some latency, which this should comfortably exceed.
+const SIGNIFICANT_SKEW_SECONDS: i64 = 5 * 60;
+
+pub type ClockSkewCallback = Box<dyn FnMut(bool) + Send + Sync>;
+
+/// This keeps track of the difference between the clock of the device and the clock of the Wallet Provider, based on
+/// the `Date` header of its responses, which are passed to [`Self::report_headers()`]. When this difference becomes
+/// significant, an optional callback is called so that the app can warn the user about it. In that case, the time
+/// produced by [`Self::now()`], which is used to evaluate the validity of JWTs and mdocs, is corrected for the skew.
+///
+/// Cloning this type is cheap and all clones share the same state, so that the HTTP client that observes the server
+/// time and the wallet that uses the corrected time can each hold a clone.
+#[derive(Clone)]
+pub struct ClockSkew(Arc<ClockSkewState>);
+
+struct ClockSkewState {
+    skew: Mutex<Duration>,
+    update_callback: Mutex<Option<ClockSkewCallback>>,
+}
+
+impl ClockSkew {
+    /// Create a new instance, which assumes that the device clock is correct until a server time is reported.
+    pub fn new() -> Self {
+        ClockSkew(Arc::new(ClockSkewState {
+            skew: Mutex::new(Duration::zero()),
+            update_callback: Mutex::new(None),
+        }))
+    }
+
+    /// The time of the server minus the time of the device, which is positive if the device clock is behind.
+    pub fn skew(&self) -> Duration {
+        *self.0.skew.lock().unwrap()
+    }
+
+    pub fn is_significant(&self) -> bool {
+        Self::skew_is_significant(self.skew())
+    }
+
+    fn skew_is_significant(skew: Duration) -> bool {
+        skew.num_seconds().abs() > SIGNIFICANT_SKEW_SECONDS
+    }
+
+    /// The current time, corrected for the skew of the device clock if that is significant.
+    /// A skew that is not significant is ignored, as it is mostly the result of latency.
+    pub fn now(&self) -> DateTime<Utc> {
+        let now = Utc::now();
+        let skew = self.skew();
+
+        if Self::skew_is_significant(skew) {
+            now + skew
+        } else {
+            now
+        }
+    }
+
+    /// Update the skew from the `Date` header of a server response, if present and valid.
+    pub fn report_headers(&self, headers: &HeaderMap) {
+        let server_time = headers
+            .get(header::DATE)
+            .and_then(|date| date.to_str().ok())
+            .and_then(|date| DateTime::parse_from_rfc2822(date).ok());
+
+        if let Some(server_time) = server_time {
+            self.report_server_time(server_time.into(), Utc::now());
+        }
+    }
+
+    /// Update the skew from a time reported by the server, as compared to the device time at which it was received.
+    pub fn report_server_time(&self, server_time: DateTime<Utc>, device_time: DateTime<Utc>) {
+        let skew = server_time - device_time;
+        let was_significant = Self::skew_is_significant(std::mem::replace(&mut *self.0.skew.lock().unwrap(), skew));
+        let is_significant = Self::skew_is_significant(skew);
+
+        if is_significant != was_significant {
+            if is_significant {
+                warn!("Device clock is off by {} seconds", skew.num_seconds());
+            } else {
+                info!("Device clock is no longer significantly off");
+            }
+
+            if let Some(ref mut update_callback) = *self.0.update_callback.lock().unwrap() {
+                update_callback(is_significant)
+            }
+        }
+    }
+
+    pub fn set_clock_skew_callback<F>(&self, mut callback: F)
+    where
+        F: FnMut(bool) + Send + Sync + 'static,
+    {
+        callback(self.is_significant());
+        self.0.update_callback.lock().unwrap().replace(Box::new(callback));
+    }
+
+    pub fn clear_clock_skew_callback(&self) {
+        self.0.update_callback.lock().unwrap().take();
+    }
+}
+
+impl Default for ClockSkew {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debug for ClockSkew {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClockSkew").field("skew", &self.skew()).finish()
+    }
+}
+
+impl Generator<DateTime<Utc>> for ClockSkew {
+    fn generate(&self) -> DateTime<Utc> {
+        self.now()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http::HeaderValue;
+
+    use super::*;
+
+    #[test]
+    fn test_clock_skew_report_server_time() {
+        let clock_skew = ClockSkew::new();
+        let states = Arc::new(Mutex::new(Vec::new()));
+        let callback_states = Arc::clone(&states);
+
+        clock_skew.set_clock_skew_callback(move |is_significant| callback_states.lock().unwrap().push(is_significant));
+
+        // A skew of a few seconds is not significant and is not corrected for.
+        let device_time = Utc::now();
+        clock_skew.report_server_time(device_time + Duration::seconds(3), device_time);
+        assert_eq!(clock_skew.skew(), Duration::seconds(3));
+        assert!(!clock_skew.is_significant());
+        assert!((clock_skew.now() - Utc::now()).num_seconds().abs() < 1);
+
+        // A device clock that is an hour behind should be corrected for.
+        clock_skew.report_server_time(device_time + Duration::hours(1), device_time);
+        assert!(clock_skew.is_significant());
+        assert!(
+            (clock_skew.now() - (Utc::now() + Duration::hours(1)))
+                .num_seconds()
+                .abs()
+                < 1
+        );
+
+        clock_skew.report_server_time(device_time - Duration::hours(2), device_time);
+        assert!(clock_skew.is_significant());
+
+        clock_skew.report_server_time(device_time, device_time);
+        assert!(!clock_skew.is_significant());
+
+        // The callback should only be called on changes in significance.
+        assert_eq!(*states.lock().unwrap(), vec![false, true, false]);
+
+        clock_skew.clear_clock_skew_callback();
+        clock_skew.report_server_time(device_time + Duration::hours(1), device_time);
+
+        assert_eq!(states.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_clock_skew_report_headers() {
+        let clock_skew = ClockSkew::new();
+
+        // Headers without a valid date should be ignored.
+        clock_skew.report_headers(&HeaderMap::new());
+        clock_skew.report_headers(&HeaderMap::from_iter([(
+            header::DATE,
+            HeaderValue::from_static("yesterday"),
+        )]));
+        assert_eq!(clock_skew.skew(), Duration::zero());
+
+        clock_skew.report_headers(&HeaderMap::from_iter([(
+            header::DATE,
+            HeaderValue::from_static("Sun, 06 Nov 1994 08:49:37 GMT"),
+        )]));
+        assert!(clock_skew.is_significant());
+        assert!(clock_skew.skew() < Duration::zero());
+    }
+}
@@ -2,62 +2,118 @@ use std::path::{Path, PathBuf};
 
 use tokio::fs;
 
-use wallet_common::config::wallet_config::WalletConfiguration;
+use wallet_common::{config::wallet_config::WalletConfiguration, keys::SecureEncryptionKey};
 
 use super::FileStorageError;
 
-pub async fn get_config_file(storage_path: &Path) -> Result<Option<WalletConfiguration>, FileStorageError> {
+/// The configuration used to be stored unencrypted in this file, which is removed when the configuration is stored.
+const LEGACY_CONFIG_FILENAME: &str = "configuration.json";
+
+/// Read the stored configuration, which is encrypted with `encryption_key`. As the encryption is authenticated, a
+/// configuration that was modified on disk cannot be decrypted and results in an error.
+pub async fn get_config_file(
+    storage_path: &Path,
+    encryption_key: &impl SecureEncryptionKey,
+) -> Result<Option<WalletConfiguration>, FileStorageError> {
     let path = path_for_config_file(storage_path);
 
     if !path.try_exists()? {
         return Ok(None);
     }
 
-    let config = read_config(path.as_path()).await?;
+    let config = read_config(path.as_path(), encryption_key).await?;
     Ok(Some(config))
 }
 
-pub async fn update_config_file(storage_path: &Path, config: &WalletConfiguration) -> Result<(), FileStorageError> {
+pub async fn update_config_file(
+    storage_path: &Path,
+    config: &WalletConfiguration,
+    encryption_key: &impl SecureEncryptionKey,
+) -> Result<(), FileStorageError> {
     let path = path_for_config_file(storage_path);
-    write_config(path.as_path(), config).await
+    write_config(path.as_path(), config, encryption_key).await?;
+
+    // Ignore any errors when removing the legacy file, as it is not used anymore.
+    let _ = fs::remove_file(storage_path.join(LEGACY_CONFIG_FILENAME)).await;
+
+    Ok(())
 }
 
-async fn write_config(path: &Path, config: &WalletConfiguration) -> Result<(), FileStorageError> {
+async fn write_config(
+    path: &Path,
+    config: &WalletConfiguration,
+    encryption_key: &impl SecureEncryptionKey,
+) -> Result<(), FileStorageError> {
     let contents = serde_json::to_vec(config)?;
-    fs::write(path, contents).await?;
+    let encrypted_contents = encryption_key
+        .encrypt(&contents)
+        .await
+        .map_err(|e| FileStorageError::Encryption(e.into()))?;
+    fs::write(path, encrypted_contents).await?;
     Ok(())
 }
 
-async fn read_config(path: &Path) -> Result<WalletConfiguration, FileStorageError> {
-    let content = fs::read(path).await?;
+async fn read_config(
+    path: &Path,
+    encryption_key: &impl SecureEncryptionKey,
+) -> Result<WalletConfiguration, FileStorageError> {
+    let encrypted_content = fs::read(path).await?;
+    let content = encryption_key
+        .decrypt(&encrypted_content)
+        .await
+        .map_err(|e| FileStorageError::Encryption(e.into()))?;
     let config = serde_json::from_slice(&content)?;
     Ok(config)
 }
 
 fn path_for_config_file(storage_path: &Path) -> PathBuf {
-    storage_path.join("configuration.json")
+    storage_path.join("configuration.enc")
 }
 
 #[cfg(test)]
 mod tests {
+    use tokio::fs;
+
+    use wallet_common::keys::{software::SoftwareEncryptionKey, ConstructibleWithIdentifier};
+
     use crate::config::{
-        config_file::{get_config_file, update_config_file},
-        default_configuration,
+        config_file::{get_config_file, path_for_config_file, update_config_file},
+        default_configuration, FileStorageError,
     };
 
     #[tokio::test]
     async fn should_read_and_update_config() {
         let tempdir = tempfile::tempdir().unwrap();
+        let encryption_key = SoftwareEncryptionKey::new("should_read_and_update_config");
 
-        assert!(get_config_file(tempdir.path()).await.unwrap().is_none());
+        assert!(get_config_file(tempdir.path(), &encryption_key)
+            .await
+            .unwrap()
+            .is_none());
 
         let mut config = default_configuration();
         config.lock_timeouts.background_timeout = 1500;
-        update_config_file(tempdir.path(), &config).await.unwrap();
+        update_config_file(tempdir.path(), &config, &encryption_key)
+            .await
+            .unwrap();
 
-        let updated = get_config_file(tempdir.path()).await.unwrap().unwrap();
+        let updated = get_config_file(tempdir.path(), &encryption_key).await.unwrap().unwrap();
 
         assert_ne!(&default_configuration(), &updated);
         assert_eq!(1500, updated.lock_timeouts.background_timeout);
+
+        // The stored configuration should be encrypted, which means it cannot be modified undetected.
+        let path = path_for_config_file(tempdir.path());
+        let mut contents = fs::read(path.as_path()).await.unwrap();
+        assert!(serde_json::from_slice::<serde_json::Value>(&contents).is_err());
+
+        let last_byte = contents.last_mut().unwrap();
+        *last_byte ^= 0xff;
+        fs::write(path.as_path(), contents).await.unwrap();
+
+        let error = get_config_file(tempdir.path(), &encryption_key)
+            .await
+            .expect_err("Reading a modified configuration should fail");
+        assert!(matches!(error, FileStorageError::Encryption(_)));
     }
 }
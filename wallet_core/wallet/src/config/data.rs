@@ -7,7 +7,8 @@ use url::Url;
 use wallet_common::{
     account::serialization::DerVerifyingKey,
     config::wallet_config::{
-        AccountServerConfiguration, DisclosureConfiguration, LockTimeoutConfiguration, PidIssuanceConfiguration,
+        AccountServerConfiguration, DisclosureConfiguration, FeatureFlags, IssuanceOfferConfiguration,
+        LockTimeoutConfiguration, PidIssuanceConfiguration, PinPolicyConfiguration, RetryConfiguration,
         WalletConfiguration,
     },
     trust_anchor::DerTrustAnchor,
@@ -20,7 +21,9 @@ const WALLET_CONFIG_VERSION: &str = "1";
 
 const CONFIG_SERVER_BASE_URL: &str = "http://localhost:3000/config/v1/";
 
-const CONFIG_SERVER_SIGNING_PUBLIC_KEY: &str =
+// The keys with which the configuration may be signed, separated by `|`. During rotation of the signing key, both the
+// current and the next key are embedded, so that installed apps keep accepting the configuration.
+const CONFIG_SERVER_SIGNING_PUBLIC_KEYS: &str =
     "MFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAEW2zhAd/0VH7PzLdmAfDEmHpSWwbVRfr5H31fo2rQWtyU\
      oWZT/C5WSeVm5Ktp6nCwnOwhhJLLGb4K3LtUJeLKjA==";
 
@@ -38,21 +41,10 @@ const INSTRUCTION_RESULT_PUBLIC_KEY: &str = "MFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgA
 
 const PID_ISSUER_URL: &str = "http://localhost:3003/";
 
-const MDOC_TRUST_ANCHORS: &str = "MIIBlTCCATqgAwIBAgIURlVkuYVVlqtiuecbOwVySS9jdFwwCgYIKoZIzj0EAwIwGTEXMBUGA1UEAwwO\
-                                  Y2EuZXhhbXBsZS5jb20wHhcNMjMxMTI3MDc1NDMyWhcNMjQxMTI2MDc1NDMyWjAZMRcwFQYDVQQDDA5j\
-                                  YS5leGFtcGxlLmNvbTBZMBMGByqGSM49AgEGCCqGSM49AwEHA0IABFPE9hj71n7dNJpV1lCBBExbCK1B\
-                                  8KYu8q22Z5sPWzzzuUfRAM+K7NgsfQVprob1rR6U+pvemR1e992K8rua5gGjYDBeMB0GA1UdDgQWBBQv\
-                                  7ArBe8g9qs+S0QVagvo1xhFd7TAfBgNVHSMEGDAWgBQv7ArBe8g9qs+S0QVagvo1xhFd7TAPBgNVHRMB\
-                                  Af8EBTADAQH/MAsGA1UdDwQEAwIBBjAKBggqhkjOPQQDAgNJADBGAiEAuITZR9Rbj5zfzN39+PEymrnk\
-                                  K8WVHjOID8jeajR4DC0CIQD9XnpbZLDYMCWqkVVeBMphwv8R3P1t3NSpXRQyLRIO2w==";
-
-const RP_TRUST_ANCHORS: &str = "MIIBlDCCATqgAwIBAgIUMmfPjx+jkrbY6twjDTCNHtnoPB4wCgYIKoZIzj0EAwIwGTEXMBUGA1UEAwwO\
-                                Y2EuZXhhbXBsZS5jb20wHhcNMjMxMTA3MTA1NDEzWhcNMjQxMTA2MTA1NDEzWjAZMRcwFQYDVQQDDA5j\
-                                YS5leGFtcGxlLmNvbTBZMBMGByqGSM49AgEGCCqGSM49AwEHA0IABPD39ZBr4/cNp76DturjGWRtjjqU\
-                                qQgt+Xny57i2xFYHRSogzdQYQbqdUOzEfBZeW3GkBjzmbCmug2zHr5B54UKjYDBeMB0GA1UdDgQWBBR4\
-                                cYdOOiKhp1xTmK4ZW8JMG4CggzAfBgNVHSMEGDAWgBR4cYdOOiKhp1xTmK4ZW8JMG4CggzAPBgNVHRMB\
-                                Af8EBTADAQH/MAsGA1UdDwQEAwIBBjAKBggqhkjOPQQDAgNIADBFAiBpJ/sEsPeTm8A2XYwRmu6NOkoL\
-                                NqhPN569XKLTR6rVdwIhANOMtj2LwDUG2YcLkSBPSdhh/i/iCgTeuZQpOI8y+kBw";
+// The trust anchors of the issuers and relying parties are distributed through the signed configuration, so no
+// trust anchors are embedded by default. They can still be provided at compile time for local development.
+const MDOC_TRUST_ANCHORS: &str = "";
+const RP_TRUST_ANCHORS: &str = "";
 
 macro_rules! config_default {
     ($name:ident) => {
@@ -69,7 +61,7 @@ macro_rules! config_default {
 #[derive(Debug, Clone)]
 pub struct ConfigServerConfiguration {
     pub base_url: Url,
-    pub signing_public_key: DerVerifyingKey,
+    pub signing_public_keys: Vec<DerVerifyingKey>,
     pub update_frequency: Duration,
 }
 
@@ -77,13 +69,7 @@ impl Default for ConfigServerConfiguration {
     fn default() -> Self {
         Self {
             base_url: Url::parse(config_default!(CONFIG_SERVER_BASE_URL)).unwrap(),
-            signing_public_key: VerifyingKey::from_public_key_der(
-                &BASE64_STANDARD
-                    .decode(config_default!(CONFIG_SERVER_SIGNING_PUBLIC_KEY))
-                    .unwrap(),
-            )
-            .unwrap()
-            .into(),
+            signing_public_keys: parse_public_keys(config_default!(CONFIG_SERVER_SIGNING_PUBLIC_KEYS)),
             update_frequency: Duration::from_secs(
                 config_default!(CONFIG_SERVER_UPDATE_FREQUENCY_IN_SEC).parse().unwrap(),
             ),
@@ -91,9 +77,21 @@ impl Default for ConfigServerConfiguration {
     }
 }
 
+fn parse_public_keys(source: &str) -> Vec<DerVerifyingKey> {
+    source
+        .split('|')
+        .map(|key| {
+            VerifyingKey::from_public_key_der(&BASE64_STANDARD.decode(key).unwrap())
+                .expect("failed to parse public key")
+                .into()
+        })
+        .collect()
+}
+
 fn parse_trust_anchors(source: &str) -> Vec<DerTrustAnchor> {
     source
         .split('|')
+        .filter(|anchor| !anchor.is_empty())
         .map(|anchor| serde_json::from_str(format!("\"{}\"", anchor).as_str()).expect("failed to parse trust anchor"))
         .collect()
 }
@@ -102,6 +100,8 @@ pub fn default_configuration() -> WalletConfiguration {
     WalletConfiguration {
         version: u64::from_str(config_default!(WALLET_CONFIG_VERSION)).unwrap(),
         lock_timeouts: LockTimeoutConfiguration::default(),
+        retry: RetryConfiguration::default(),
+        pin_policy: PinPolicyConfiguration::default(),
         account_server: AccountServerConfiguration {
             base_url: Url::parse(config_default!(WALLET_PROVIDER_BASE_URL)).unwrap(),
             certificate_public_key: VerifyingKey::from_public_key_der(
@@ -116,17 +116,24 @@ pub fn default_configuration() -> WalletConfiguration {
             )
             .unwrap()
             .into(),
+            previous_certificate_public_keys: vec![],
+            previous_instruction_result_public_keys: vec![],
         },
         pid_issuance: PidIssuanceConfiguration {
             pid_issuer_url: Url::parse(config_default!(PID_ISSUER_URL)).unwrap(),
             digid_url: Url::parse(config_default!(DIGID_URL)).unwrap(),
             digid_client_id: String::from(config_default!(DIGID_CLIENT_ID)),
             digid_redirect_path: "authentication".to_string(),
+            digid_scopes: vec![],
         },
+        credential_issuers: vec![],
         disclosure: DisclosureConfiguration {
             uri_base_path: "disclosure".to_string(),
             rp_trust_anchors: parse_trust_anchors(config_default!(RP_TRUST_ANCHORS)),
+            rp_registrations: vec![],
         },
+        issuance_offer: IssuanceOfferConfiguration::default(),
+        feature_flags: FeatureFlags::default(),
         mdoc_trust_anchors: parse_trust_anchors(config_default!(MDOC_TRUST_ANCHORS)),
     }
 }
@@ -1,55 +1,128 @@
 use std::{path::PathBuf, sync::Arc};
 
+use p256::ecdsa::VerifyingKey;
+use tokio::sync::RwLock;
+use tracing::warn;
 use url::Url;
 
-use wallet_common::{config::wallet_config::WalletConfiguration, jwt::EcdsaDecodingKey};
+use wallet_common::{config::wallet_config::WalletConfiguration, keys::SecureEncryptionKey};
+
+use crate::storage::{ConfigurationVersionData, DatabaseStorage, Storage, StorageError, StorageState};
 
 use super::{
-    config_file, ConfigurationError, ConfigurationRepository, ConfigurationUpdateState, HttpConfigurationRepository,
-    UpdateableConfigurationRepository,
+    config_file, http_client::HttpConfigurationClient, ConfigurationError, ConfigurationRepository,
+    ConfigurationUpdateState, HttpConfigurationRepository, UpdateableConfigurationRepository,
 };
 
-pub struct FileStorageConfigurationRepository<T> {
+/// The identifier of the key with which the stored configuration is encrypted.
+const ENCRYPTION_KEY_IDENTIFIER: &str = "configuration_file";
+
+/// Stores the latest configuration on disk, encrypted with a key of type `K`, so that the wallet starts with the
+/// latest known configuration, even when it is offline. The highest version that was ever accepted is kept in the
+/// database of the wallet, so that neither the stored nor a fetched configuration can be replaced by an older one.
+pub struct FileStorageConfigurationRepository<T, K> {
     wrapped: T,
     storage_path: PathBuf,
+    encryption_key: K,
+    database_storage: Arc<RwLock<DatabaseStorage<K>>>,
 }
 
-impl FileStorageConfigurationRepository<HttpConfigurationRepository> {
+impl<K> FileStorageConfigurationRepository<HttpConfigurationRepository, K>
+where
+    K: SecureEncryptionKey,
+{
+    /// Initialize the repository from the stored configuration. The database of `database_storage` should have been
+    /// opened already, if it exists.
     pub async fn init(
         storage_path: PathBuf,
         base_url: Url,
-        signing_public_key: EcdsaDecodingKey,
+        signing_public_keys: Vec<VerifyingKey>,
         initial_config: WalletConfiguration,
+        database_storage: Arc<RwLock<DatabaseStorage<K>>>,
     ) -> Result<Self, ConfigurationError> {
-        let default_config = match config_file::get_config_file(storage_path.as_path()).await? {
+        let encryption_key = K::new(ENCRYPTION_KEY_IDENTIFIER);
+
+        let highest_version = fetch_highest_version(&database_storage).await?;
+
+        let stored_config = match config_file::get_config_file(storage_path.as_path(), &encryption_key).await {
+            Ok(stored_config) => stored_config,
+            Err(error) => {
+                // A stored configuration that cannot be decrypted, e.g. because it was modified, is discarded.
+                warn!("Discarding stored wallet configuration: {}", error);
+                None
+            }
+        };
+
+        // A stored configuration that is older than a configuration that was accepted before has been replaced on
+        // disk, so it is discarded as well.
+        let stored_config = stored_config.filter(|stored_config| match highest_version {
+            Some(highest_version) if stored_config.version < highest_version => {
+                warn!(
+                    "Discarding stored wallet configuration with version: {}, as version: {} was accepted before",
+                    stored_config.version, highest_version
+                );
+                false
+            }
+            _ => true,
+        });
+
+        let default_config = match stored_config {
             Some(stored_config) if initial_config.version > stored_config.version => {
                 // When the initial configuration is newer than the stored configuration (e.g. due to an app update) that
                 // version is used and the stored configuration is overwritten.
-                config_file::update_config_file(storage_path.as_path(), &initial_config).await?;
+                config_file::update_config_file(storage_path.as_path(), &initial_config, &encryption_key).await?;
                 initial_config
             }
             Some(stored_config) => stored_config,
-            None => initial_config,
+            None => {
+                // Without a stored configuration, the configuration that belongs to the stored ETag is not known,
+                // so remove the ETag to make sure the latest configuration is fetched in full.
+                HttpConfigurationClient::remove_latest_etag(storage_path.as_path()).await?;
+                initial_config
+            }
         };
 
+        // The configuration that is used from now on may be newer than the stored highest version, e.g. when the
+        // database was created after that configuration was accepted, so store its version as well.
+        let highest_version = highest_version.unwrap_or_default().max(default_config.version);
+        update_highest_version(&database_storage, highest_version).await?;
+
         Ok(Self::new(
-            HttpConfigurationRepository::new(base_url, signing_public_key, storage_path.clone(), default_config)
-                .await?,
+            HttpConfigurationRepository::new(
+                base_url,
+                signing_public_keys,
+                storage_path.clone(),
+                default_config,
+                highest_version,
+            )
+            .await?,
             storage_path,
+            encryption_key,
+            database_storage,
         ))
     }
 }
 
-impl<T> FileStorageConfigurationRepository<T>
+impl<T, K> FileStorageConfigurationRepository<T, K>
 where
     T: ConfigurationRepository,
 {
-    fn new(wrapped: T, storage_path: PathBuf) -> FileStorageConfigurationRepository<T> {
-        Self { wrapped, storage_path }
+    fn new(
+        wrapped: T,
+        storage_path: PathBuf,
+        encryption_key: K,
+        database_storage: Arc<RwLock<DatabaseStorage<K>>>,
+    ) -> FileStorageConfigurationRepository<T, K> {
+        Self {
+            wrapped,
+            storage_path,
+            encryption_key,
+            database_storage,
+        }
     }
 }
 
-impl<T> ConfigurationRepository for FileStorageConfigurationRepository<T>
+impl<T, K> ConfigurationRepository for FileStorageConfigurationRepository<T, K>
 where
     T: ConfigurationRepository,
 {
@@ -58,36 +131,118 @@ where
     }
 }
 
-impl<T> UpdateableConfigurationRepository for FileStorageConfigurationRepository<T>
+impl<T, K> UpdateableConfigurationRepository for FileStorageConfigurationRepository<T, K>
 where
     T: UpdateableConfigurationRepository + Sync,
+    K: SecureEncryptionKey + Send + Sync,
 {
     async fn fetch(&self) -> Result<ConfigurationUpdateState, ConfigurationError> {
         let result = self.wrapped.fetch().await?;
 
         if let ConfigurationUpdateState::Updated = result {
             let wrapped_config = self.wrapped.config();
-            config_file::update_config_file(self.storage_path.as_path(), wrapped_config.as_ref()).await?;
+            // Store the new highest version before the configuration itself, so that it is never lost.
+            update_highest_version(&self.database_storage, wrapped_config.version).await?;
+            config_file::update_config_file(
+                self.storage_path.as_path(),
+                wrapped_config.as_ref(),
+                &self.encryption_key,
+            )
+            .await?;
         }
 
         Ok(result)
     }
 }
 
+/// Fetch the highest configuration version from the database. A wallet without a database is not registered, so it
+/// does not have a highest version yet. This means that an unregistered wallet is not protected against downgrades
+/// across restarts: until registration creates the database, it only rejects configurations that are older than the
+/// one it started with. Any other failure to read the version is an error, so that the version cannot be circumvented
+/// by corrupting it.
+async fn fetch_highest_version<K>(database_storage: &RwLock<DatabaseStorage<K>>) -> Result<Option<u64>, StorageError>
+where
+    K: SecureEncryptionKey,
+{
+    let storage = database_storage.read().await;
+
+    if let StorageState::Uninitialized = storage.state().await? {
+        return Ok(None);
+    }
+
+    let version = storage
+        .fetch_data::<ConfigurationVersionData>()
+        .await?
+        .map(|data| data.highest_version);
+
+    Ok(version)
+}
+
+/// Store `version` as the highest configuration version in the database, unless a higher version is stored already.
+/// Without a database, the version is stored after the database has been created, when the repository is initialized
+/// the next time or the configuration is updated.
+async fn update_highest_version<K>(
+    database_storage: &RwLock<DatabaseStorage<K>>,
+    version: u64,
+) -> Result<(), StorageError>
+where
+    K: SecureEncryptionKey,
+{
+    let mut storage = database_storage.write().await;
+
+    if let StorageState::Uninitialized = storage.state().await? {
+        return Ok(());
+    }
+
+    let data = ConfigurationVersionData {
+        highest_version: version,
+    };
+
+    match storage.fetch_data::<ConfigurationVersionData>().await? {
+        Some(stored) if stored.highest_version >= version => Ok(()),
+        Some(_) => storage.update_data(&data).await,
+        None => storage.insert_data(&data).await,
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use std::sync::{Arc, RwLock};
+    use std::{
+        path::Path,
+        sync::{Arc, RwLock},
+    };
 
+    use assert_matches::assert_matches;
     use p256::{ecdsa::SigningKey, elliptic_curve::rand_core::OsRng};
+    use tokio::{fs, sync::RwLock as AsyncRwLock};
     use url::Url;
 
-    use wallet_common::{config::wallet_config::WalletConfiguration, jwt::EcdsaDecodingKey};
+    use wallet_common::{
+        config::wallet_config::WalletConfiguration,
+        keys::{software::SoftwareEncryptionKey, ConstructibleWithIdentifier},
+    };
 
-    use crate::config::{
-        config_file, default_configuration, ConfigurationError, ConfigurationRepository, ConfigurationUpdateState,
-        FileStorageConfigurationRepository, UpdateableConfigurationRepository,
+    use crate::{
+        config::{
+            config_file, default_configuration, ConfigurationError, ConfigurationRepository, ConfigurationUpdateState,
+            FileStorageConfigurationRepository, UpdateableConfigurationRepository,
+        },
+        storage::{DatabaseStorage, Storage, StorageError},
     };
 
+    use super::{fetch_highest_version, update_highest_version, ENCRYPTION_KEY_IDENTIFIER};
+
+    fn encryption_key() -> SoftwareEncryptionKey {
+        SoftwareEncryptionKey::new(ENCRYPTION_KEY_IDENTIFIER)
+    }
+
+    async fn open_database_storage(path: &Path) -> Arc<AsyncRwLock<DatabaseStorage<SoftwareEncryptionKey>>> {
+        let mut storage = DatabaseStorage::init(path.to_path_buf());
+        storage.open().await.unwrap();
+
+        Arc::new(AsyncRwLock::new(storage))
+    }
+
     struct TestConfigRepo(RwLock<WalletConfiguration>);
 
     impl ConfigurationRepository for TestConfigRepo {
@@ -112,9 +267,13 @@ mod tests {
         let config_dir = tempfile::tempdir().unwrap();
         let path = config_dir.into_path();
 
+        let database_storage = open_database_storage(&path).await;
+
         let repo = FileStorageConfigurationRepository::new(
             TestConfigRepo(RwLock::new(initial_wallet_config.clone())),
             path.clone(),
+            encryption_key(),
+            Arc::clone(&database_storage),
         );
 
         let config = repo.config();
@@ -131,9 +290,19 @@ mod tests {
             "should return value set by TestConfigRepo.fetch()"
         );
 
-        let file_config = config_file::get_config_file(path.as_path()).await.unwrap().unwrap();
+        let file_config = config_file::get_config_file(path.as_path(), &encryption_key())
+            .await
+            .unwrap()
+            .unwrap();
+        let highest_version = fetch_highest_version(&database_storage).await.unwrap();
+        assert_eq!(highest_version, Some(file_config.version));
 
-        let repo = FileStorageConfigurationRepository::new(TestConfigRepo(RwLock::new(file_config)), path);
+        let repo = FileStorageConfigurationRepository::new(
+            TestConfigRepo(RwLock::new(file_config)),
+            path,
+            encryption_key(),
+            database_storage,
+        );
 
         let config = repo.config();
         assert_eq!(
@@ -147,21 +316,22 @@ mod tests {
         let config_dir = tempfile::tempdir().unwrap();
         let path = config_dir.into_path();
         let verifying_key = *SigningKey::random(&mut OsRng).verifying_key();
-        let config_decoding_key: EcdsaDecodingKey = verifying_key.into();
+        let database_storage = open_database_storage(&path).await;
 
         let mut initially_stored_wallet_config = default_configuration();
         initially_stored_wallet_config.version = 10;
 
         // store initial wallet config having version 10
-        config_file::update_config_file(path.as_path(), &initially_stored_wallet_config)
+        config_file::update_config_file(path.as_path(), &initially_stored_wallet_config, &encryption_key())
             .await
             .unwrap();
 
-        let repo = FileStorageConfigurationRepository::init(
+        let repo = FileStorageConfigurationRepository::<_, SoftwareEncryptionKey>::init(
             path.clone(),
             Url::parse("http://localhost").unwrap(),
-            config_decoding_key.clone(),
+            vec![verifying_key],
             default_configuration(),
+            Arc::clone(&database_storage),
         )
         .await
         .unwrap();
@@ -170,20 +340,136 @@ mod tests {
         let mut embedded_wallet_config = default_configuration();
         embedded_wallet_config.version = 20;
 
-        let repo = FileStorageConfigurationRepository::init(
+        let repo = FileStorageConfigurationRepository::<_, SoftwareEncryptionKey>::init(
             path.clone(),
             Url::parse("http://localhost").unwrap(),
-            config_decoding_key,
+            vec![verifying_key],
             embedded_wallet_config,
+            Arc::clone(&database_storage),
         )
         .await
         .unwrap();
         assert_eq!(20, repo.config().version, "should use newer embedded config");
 
-        let stored_config = config_file::get_config_file(path.as_path()).await.unwrap().unwrap();
+        let stored_config = config_file::get_config_file(path.as_path(), &encryption_key())
+            .await
+            .unwrap()
+            .unwrap();
         assert_eq!(
             20, stored_config.version,
             "newer embedded config should have been stored"
         );
     }
+
+    #[tokio::test]
+    async fn should_discard_modified_stored_config() {
+        let config_dir = tempfile::tempdir().unwrap();
+        let path = config_dir.into_path();
+        let verifying_key = *SigningKey::random(&mut OsRng).verifying_key();
+        let database_storage = open_database_storage(&path).await;
+
+        let mut stored_wallet_config = default_configuration();
+        stored_wallet_config.version = 10;
+
+        config_file::update_config_file(path.as_path(), &stored_wallet_config, &encryption_key())
+            .await
+            .unwrap();
+        fs::write(path.join("latest-configuration-etag.txt"), b"\"etag\"")
+            .await
+            .unwrap();
+
+        // Modify the encrypted configuration on disk, which should be detected when it is decrypted.
+        let config_file_path = path.join("configuration.enc");
+        let mut contents = fs::read(config_file_path.as_path()).await.unwrap();
+        *contents.last_mut().unwrap() ^= 0xff;
+        fs::write(config_file_path.as_path(), contents).await.unwrap();
+
+        let repo = FileStorageConfigurationRepository::<_, SoftwareEncryptionKey>::init(
+            path.clone(),
+            Url::parse("http://localhost").unwrap(),
+            vec![verifying_key],
+            default_configuration(),
+            Arc::clone(&database_storage),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            default_configuration().version,
+            repo.config().version,
+            "should use embedded config"
+        );
+        assert!(
+            !path.join("latest-configuration-etag.txt").exists(),
+            "ETag of the discarded config should have been removed"
+        );
+    }
+
+    #[tokio::test]
+    async fn should_discard_stored_config_below_highest_version() {
+        let config_dir = tempfile::tempdir().unwrap();
+        let path = config_dir.into_path();
+        let verifying_key = *SigningKey::random(&mut OsRng).verifying_key();
+        let database_storage = open_database_storage(&path).await;
+
+        let mut stored_wallet_config = default_configuration();
+        stored_wallet_config.version = 10;
+
+        config_file::update_config_file(path.as_path(), &stored_wallet_config, &encryption_key())
+            .await
+            .unwrap();
+        fs::write(path.join("latest-configuration-etag.txt"), b"\"etag\"")
+            .await
+            .unwrap();
+
+        // A configuration with version 20 has been accepted before, so the stored one is a rollback.
+        update_highest_version(&database_storage, 20).await.unwrap();
+
+        let repo = FileStorageConfigurationRepository::<_, SoftwareEncryptionKey>::init(
+            path.clone(),
+            Url::parse("http://localhost").unwrap(),
+            vec![verifying_key],
+            default_configuration(),
+            Arc::clone(&database_storage),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            default_configuration().version,
+            repo.config().version,
+            "should use embedded config"
+        );
+        assert!(
+            !path.join("latest-configuration-etag.txt").exists(),
+            "ETag of the discarded config should have been removed"
+        );
+
+        let highest_version = fetch_highest_version(&database_storage).await.unwrap();
+        assert_eq!(highest_version, Some(20), "highest version should never decrease");
+    }
+
+    #[tokio::test]
+    async fn should_fail_when_highest_version_cannot_be_read() {
+        let config_dir = tempfile::tempdir().unwrap();
+        let path = config_dir.into_path();
+        let verifying_key = *SigningKey::random(&mut OsRng).verifying_key();
+
+        // Create the database, but hand the repository a storage that has not been opened.
+        let _ = open_database_storage(&path).await;
+        let database_storage = Arc::new(AsyncRwLock::new(DatabaseStorage::init(path.clone())));
+
+        let error = FileStorageConfigurationRepository::<_, SoftwareEncryptionKey>::init(
+            path,
+            Url::parse("http://localhost").unwrap(),
+            vec![verifying_key],
+            default_configuration(),
+            database_storage,
+        )
+        .await
+        .map(|_| ())
+        .expect_err("initializing should fail when the highest version cannot be read");
+
+        assert_matches!(error, ConfigurationError::VersionStorage(StorageError::NotOpened));
+    }
 }
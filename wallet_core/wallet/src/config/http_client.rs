@@ -3,13 +3,16 @@ use std::{
     sync::Mutex,
 };
 
-use http::{header, HeaderMap, HeaderValue, StatusCode};
+use http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode};
+use p256::ecdsa::VerifyingKey;
 use tokio::fs;
+use tracing::warn;
 use url::Url;
+use uuid::Uuid;
 
 use wallet_common::{
-    config::wallet_config::WalletConfiguration,
-    jwt::{validations, EcdsaDecodingKey, Jwt},
+    config::{wallet_config::WalletConfiguration, ROLLOUT_ID_HEADER},
+    jwt::{validations, Jwt},
 };
 
 use crate::{config::ConfigurationError, utils::reqwest::default_reqwest_client_builder};
@@ -19,31 +22,38 @@ use super::FileStorageError;
 pub struct HttpConfigurationClient {
     http_client: reqwest::Client,
     base_url: Url,
-    signing_public_key: EcdsaDecodingKey,
+    /// The keys with which the configuration may be signed, of which the one that is referred to by the `kid` in the
+    /// header of the JWT is used.
+    signing_public_keys: Vec<VerifyingKey>,
     storage_path: PathBuf,
     latest_etag: Mutex<Option<HeaderValue>>,
 }
 
 const ETAG_FILENAME: &str = "latest-configuration-etag.txt";
+const ROLLOUT_ID_FILENAME: &str = "configuration-rollout-id.txt";
 
 impl HttpConfigurationClient {
     pub async fn new(
         base_url: Url,
-        signing_public_key: EcdsaDecodingKey,
+        signing_public_keys: Vec<VerifyingKey>,
         storage_path: PathBuf,
     ) -> Result<Self, ConfigurationError> {
         let initial_etag = Self::read_latest_etag(storage_path.as_path()).await?;
+        let rollout_id = Self::read_or_create_rollout_id(storage_path.as_path()).await?;
 
         let client = Self {
             http_client: default_reqwest_client_builder()
-                .default_headers(HeaderMap::from_iter([(
-                    header::ACCEPT,
-                    HeaderValue::from_static(mime::APPLICATION_JSON.as_ref()),
-                )]))
+                .default_headers(HeaderMap::from_iter([
+                    (
+                        header::ACCEPT,
+                        HeaderValue::from_static(mime::APPLICATION_JSON.as_ref()),
+                    ),
+                    (HeaderName::from_static(ROLLOUT_ID_HEADER), rollout_id),
+                ]))
                 .build()
                 .expect("Could not build reqwest HTTP client"),
             base_url,
-            signing_public_key,
+            signing_public_keys,
             storage_path,
             latest_etag: Mutex::new(initial_etag),
         };
@@ -70,10 +80,45 @@ impl HttpConfigurationClient {
         Ok(())
     }
 
+    /// Remove the stored ETag, so that the configuration is fetched in full the next time.
+    pub async fn remove_latest_etag(storage_path: &Path) -> Result<(), FileStorageError> {
+        let path = Self::path_for_etag_file(storage_path);
+
+        if path.try_exists()? {
+            fs::remove_file(path).await?;
+        }
+
+        Ok(())
+    }
+
     fn path_for_etag_file(storage_path: &Path) -> PathBuf {
         storage_path.join(ETAG_FILENAME)
     }
 
+    /// Read the identifier by which the configuration server assigns this wallet to a rollout, which is generated
+    /// randomly when the wallet first fetches the configuration. When the stored identifier is empty or cannot be
+    /// sent as a header, it is treated as missing and replaced by a new one.
+    async fn read_or_create_rollout_id(storage_path: &Path) -> Result<HeaderValue, FileStorageError> {
+        let path = storage_path.join(ROLLOUT_ID_FILENAME);
+
+        if path.try_exists()? {
+            let content = fs::read(&path).await?;
+            let rollout_id = String::from_utf8_lossy(&content);
+
+            match HeaderValue::from_str(rollout_id.trim()) {
+                Ok(rollout_id) if !rollout_id.is_empty() => return Ok(rollout_id),
+                _ => warn!("Stored configuration rollout ID is invalid, generating a new one"),
+            }
+        }
+
+        let rollout_id = Uuid::new_v4().to_string();
+        fs::write(path, &rollout_id).await?;
+
+        let rollout_id = HeaderValue::from_str(&rollout_id)?;
+
+        Ok(rollout_id)
+    }
+
     pub async fn get_wallet_config(&self) -> Result<Option<WalletConfiguration>, ConfigurationError> {
         let url = self.base_url.join("wallet-config")?;
         let mut request_builder = self.http_client.get(url);
@@ -109,8 +154,43 @@ impl HttpConfigurationClient {
         }
 
         let body = response.text().await?;
-        let wallet_config = Jwt::from(body).parse_and_verify(&self.signing_public_key, &validations())?;
+        let wallet_config = Jwt::from(body).parse_and_verify_from_keys(&self.signing_public_keys, &validations())?;
 
         Ok(Some(wallet_config))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_read_or_create_rollout_id() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let path = tempdir.path().join(ROLLOUT_ID_FILENAME);
+
+        // A rollout ID is generated and stored when there is none yet, after which the stored one is used.
+        let rollout_id = HttpConfigurationClient::read_or_create_rollout_id(tempdir.path())
+            .await
+            .unwrap();
+        assert_eq!(fs::read(&path).await.unwrap(), rollout_id.as_bytes());
+        assert_eq!(
+            HttpConfigurationClient::read_or_create_rollout_id(tempdir.path())
+                .await
+                .unwrap(),
+            rollout_id
+        );
+
+        // An empty or corrupt rollout ID is replaced by a new one.
+        for content in [b"".as_slice(), b"  \n", b"corrupt\nrollout id", b"\xff\x00"] {
+            fs::write(&path, content).await.unwrap();
+
+            let new_rollout_id = HttpConfigurationClient::read_or_create_rollout_id(tempdir.path())
+                .await
+                .unwrap();
+            assert_ne!(new_rollout_id, rollout_id);
+            assert!(Uuid::parse_str(new_rollout_id.to_str().unwrap()).is_ok());
+            assert_eq!(fs::read(&path).await.unwrap(), new_rollout_id.as_bytes());
+        }
+    }
+}
@@ -1,12 +1,16 @@
 use std::{
     path::PathBuf,
-    sync::{Arc, RwLock},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
 };
 
-use tracing::info;
+use p256::ecdsa::VerifyingKey;
+use tracing::{info, warn};
 use url::Url;
 
-use wallet_common::{config::wallet_config::WalletConfiguration, jwt::EcdsaDecodingKey};
+use wallet_common::config::wallet_config::WalletConfiguration;
 
 use crate::config::{
     http_client::HttpConfigurationClient, ConfigurationError, ConfigurationRepository, ConfigurationUpdateState,
@@ -16,18 +20,27 @@ use crate::config::{
 pub struct HttpConfigurationRepository {
     client: HttpConfigurationClient,
     config: RwLock<Arc<WalletConfiguration>>,
+    /// The highest version of the configuration that was ever accepted. Configurations with a lower version are
+    /// rejected, even when they are signed correctly, so that an old configuration cannot be replayed.
+    highest_version: AtomicU64,
 }
 
 impl HttpConfigurationRepository {
+    /// Create a repository that starts with `initial_config` and rejects any configuration with a version lower than
+    /// `highest_version` or the version of `initial_config`, whichever is higher.
     pub async fn new(
         base_url: Url,
-        signing_public_key: EcdsaDecodingKey,
+        signing_public_keys: Vec<VerifyingKey>,
         storage_path: PathBuf,
         initial_config: WalletConfiguration,
+        highest_version: u64,
     ) -> Result<Self, ConfigurationError> {
+        let highest_version = highest_version.max(initial_config.version);
+
         Ok(Self {
-            client: HttpConfigurationClient::new(base_url, signing_public_key, storage_path).await?,
+            client: HttpConfigurationClient::new(base_url, signing_public_keys, storage_path).await?,
             config: RwLock::new(Arc::new(initial_config)),
+            highest_version: AtomicU64::new(highest_version),
         })
     }
 }
@@ -43,6 +56,18 @@ impl ConfigurationRepository for HttpConfigurationRepository {
 impl UpdateableConfigurationRepository for HttpConfigurationRepository {
     async fn fetch(&self) -> Result<ConfigurationUpdateState, ConfigurationError> {
         if let Some(new_config) = self.client.get_wallet_config().await? {
+            let highest_version = self.highest_version.load(Ordering::Relaxed);
+            if new_config.version < highest_version {
+                warn!(
+                    "Rejecting wallet configuration with version: {}, as version: {} was accepted before",
+                    new_config.version, highest_version
+                );
+                return Err(ConfigurationError::Downgrade {
+                    version: new_config.version,
+                    highest_version,
+                });
+            }
+
             {
                 let current_config = self.config.read().unwrap();
                 if new_config.version <= current_config.version {
@@ -54,6 +79,8 @@ impl UpdateableConfigurationRepository for HttpConfigurationRepository {
                 }
             }
 
+            self.highest_version.store(new_config.version, Ordering::Relaxed);
+
             let mut config = self.config.write().unwrap();
             *config = Arc::new(new_config);
             Ok(ConfigurationUpdateState::Updated)
@@ -11,8 +11,11 @@ use std::sync::Arc;
 
 use url::ParseError;
 
+use platform_support::hw_keystore::hardware::HardwareEncryptionKey;
 use wallet_common::{config::wallet_config::WalletConfiguration, jwt::JwtError};
 
+use crate::storage::StorageError;
+
 pub use self::{
     data::{default_configuration, ConfigServerConfiguration},
     file_repository::FileStorageConfigurationRepository,
@@ -20,8 +23,9 @@ pub use self::{
     updating_repository::UpdatingConfigurationRepository,
 };
 
-pub type UpdatingFileHttpConfigurationRepository =
-    UpdatingConfigurationRepository<FileStorageConfigurationRepository<HttpConfigurationRepository>>;
+pub type UpdatingFileHttpConfigurationRepository = UpdatingConfigurationRepository<
+    FileStorageConfigurationRepository<HttpConfigurationRepository, HardwareEncryptionKey>,
+>;
 
 #[cfg(any(test, feature = "mock"))]
 pub use self::mock::LocalConfigurationRepository;
@@ -36,8 +40,14 @@ pub enum ConfigurationError {
     BaseUrl(#[from] ParseError),
     #[error("could not store or load configuration: {0}")]
     ConfigFile(#[from] FileStorageError),
+    #[error("could not store or load highest configuration version: {0}")]
+    VersionStorage(#[from] StorageError),
     #[error("could not validate JWT: {0}")]
     Jwt(#[from] JwtError),
+    #[error(
+        "received wallet configuration with version {version}, while version {highest_version} was accepted before"
+    )]
+    Downgrade { version: u64, highest_version: u64 },
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -46,6 +56,10 @@ pub enum FileStorageError {
     Io(#[from] std::io::Error),
     #[error("serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
+    #[error("config file encryption error: {0}")]
+    Encryption(#[source] Box<dyn std::error::Error + Send + Sync>),
+    #[error("config file contains invalid header value: {0}")]
+    HeaderValue(#[from] http::header::InvalidHeaderValue),
 }
 
 #[derive(Debug)]
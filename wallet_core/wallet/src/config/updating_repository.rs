@@ -1,20 +1,30 @@
 use std::{path::PathBuf, sync::Arc, time::Duration};
 
 use tokio::{
-    sync::watch::{channel, Receiver, Sender},
+    sync::{
+        watch::{channel, Receiver, Sender},
+        RwLock,
+    },
     task::JoinHandle,
     time::{self, MissedTickBehavior},
 };
 use tracing::info;
 
+use platform_support::hw_keystore::hardware::HardwareEncryptionKey;
 use wallet_common::config::wallet_config::WalletConfiguration;
 
+use crate::{connectivity::Connectivity, storage::DatabaseStorage};
+
 use super::{
     ConfigServerConfiguration, ConfigurationError, ConfigurationRepository, ConfigurationUpdateState,
     FileStorageConfigurationRepository, ObservableConfigurationRepository, UpdateableConfigurationRepository,
     UpdatingFileHttpConfigurationRepository,
 };
 
+/// While the wallet is offline, the configuration is fetched at this interval instead, as the fetch then doubles
+/// as a probe that detects connectivity returning.
+const OFFLINE_PROBE_INTERVAL: Duration = Duration::from_secs(30);
+
 pub struct UpdatingConfigurationRepository<T> {
     wrapped: Arc<T>,
     updating_task: JoinHandle<()>,
@@ -28,15 +38,18 @@ impl UpdatingFileHttpConfigurationRepository {
         storage_path: PathBuf,
         config: ConfigServerConfiguration,
         initial_config: WalletConfiguration,
+        database_storage: Arc<RwLock<DatabaseStorage<HardwareEncryptionKey>>>,
+        connectivity: Connectivity,
     ) -> Result<Self, ConfigurationError> {
         let wrapped = FileStorageConfigurationRepository::init(
             storage_path,
             config.base_url,
-            config.signing_public_key.into(),
+            config.signing_public_keys.into_iter().map(|key| key.0).collect(),
             initial_config,
+            database_storage,
         )
         .await?;
-        let config = Self::new(wrapped, config.update_frequency).await;
+        let config = Self::new(wrapped, config.update_frequency, connectivity).await;
         Ok(config)
     }
 }
@@ -45,10 +58,16 @@ impl<T> UpdatingConfigurationRepository<T>
 where
     T: UpdateableConfigurationRepository + Send + Sync + 'static,
 {
-    pub async fn new(wrapped: T, update_frequency: Duration) -> UpdatingConfigurationRepository<T> {
+    /// Create a new repository that periodically fetches the configuration. The fetch is deferred while the wallet is
+    /// offline, according to `connectivity`, and is performed as soon as connectivity returns.
+    pub async fn new(
+        wrapped: T,
+        update_frequency: Duration,
+        connectivity: Connectivity,
+    ) -> UpdatingConfigurationRepository<T> {
         let (tx, rx) = channel::<CallbackFunction>(Box::new(|_| {}));
         let wrapped = Arc::new(wrapped);
-        let updating_task = Self::start_update_task(Arc::clone(&wrapped), rx, update_frequency).await;
+        let updating_task = Self::start_update_task(Arc::clone(&wrapped), rx, update_frequency, connectivity).await;
         Self {
             wrapped,
             updating_task,
@@ -57,17 +76,33 @@ where
     }
 
     // This function is marked as async to force using a Tokio runtime and to prevent runtime panics of used without.
-    async fn start_update_task(wrapped: Arc<T>, rx: Receiver<CallbackFunction>, interval: Duration) -> JoinHandle<()> {
+    async fn start_update_task(
+        wrapped: Arc<T>,
+        rx: Receiver<CallbackFunction>,
+        interval: Duration,
+        connectivity: Connectivity,
+    ) -> JoinHandle<()> {
         tokio::spawn(async move {
             let mut interval = time::interval(interval);
             interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
 
             loop {
-                interval.tick().await;
+                if connectivity.is_online() {
+                    interval.tick().await;
+
+                    info!("Wallet configuration update timer expired, fetching from remote...");
+                } else {
+                    info!("Wallet is offline, deferring configuration update until connectivity returns");
+
+                    // Stop waiting when another HTTP client reports that connectivity has returned,
+                    // or when it is time to probe for connectivity by fetching the configuration.
+                    let _ = time::timeout(OFFLINE_PROBE_INTERVAL, connectivity.wait_until_online()).await;
+                    interval.reset();
 
-                info!("Wallet configuration update timer expired, fetching from remote...");
+                    info!("Fetching wallet configuration from remote after being offline...");
+                }
 
-                if let Ok(ConfigurationUpdateState::Updated) = wrapped.fetch().await {
+                if let Ok(ConfigurationUpdateState::Updated) = connectivity.observe(wrapped.fetch().await) {
                     let config = wrapped.config();
                     let callback = rx.borrow();
                     callback(config);
@@ -122,9 +157,13 @@ mod tests {
 
     use wallet_common::config::wallet_config::WalletConfiguration;
 
-    use crate::config::{
-        default_configuration, ConfigurationError, ConfigurationRepository, ConfigurationUpdateState,
-        ObservableConfigurationRepository, UpdateableConfigurationRepository, UpdatingConfigurationRepository,
+    use crate::{
+        account_provider::AccountProviderError,
+        config::{
+            default_configuration, ConfigurationError, ConfigurationRepository, ConfigurationUpdateState,
+            ObservableConfigurationRepository, UpdateableConfigurationRepository, UpdatingConfigurationRepository,
+        },
+        connectivity::Connectivity,
     };
 
     struct TestConfigRepo(RwLock<WalletConfiguration>);
@@ -151,9 +190,12 @@ mod tests {
         time::pause();
         let update_frequency = Duration::from_millis(1000);
 
-        let config =
-            UpdatingConfigurationRepository::new(TestConfigRepo(RwLock::new(initial_wallet_config)), update_frequency)
-                .await;
+        let config = UpdatingConfigurationRepository::new(
+            TestConfigRepo(RwLock::new(initial_wallet_config)),
+            update_frequency,
+            Connectivity::default(),
+        )
+        .await;
 
         assert_eq!(300, config.config().lock_timeouts.background_timeout);
 
@@ -199,6 +241,7 @@ mod tests {
             let config = UpdatingConfigurationRepository::new(
                 TestConfigRepo(RwLock::new(initial_wallet_config)),
                 update_frequency,
+                Connectivity::default(),
             )
             .await;
 
@@ -228,4 +271,52 @@ mod tests {
              and the count should not have been updated"
         );
     }
+
+    #[tokio::test]
+    async fn should_defer_update_while_offline() {
+        let initial_wallet_config = default_configuration();
+
+        // Mark the wallet as offline by reporting a request that could not connect.
+        let connectivity = Connectivity::default();
+        let connect_error = reqwest::Client::new()
+            .get("http://127.0.0.1:9")
+            .send()
+            .await
+            .expect_err("request should fail to connect");
+        connectivity.report::<(), _>(&Err(AccountProviderError::Networking(connect_error)));
+
+        // pause time so we can advance it later
+        time::pause();
+        let update_frequency = Duration::from_millis(1000);
+
+        let config = UpdatingConfigurationRepository::new(
+            TestConfigRepo(RwLock::new(initial_wallet_config)),
+            update_frequency,
+            connectivity.clone(),
+        )
+        .await;
+
+        let notifier = Arc::new(Notify::new());
+        let callback_notifier = notifier.clone();
+
+        let counter = Arc::new(AtomicU64::new(0));
+        let callback_counter = Arc::clone(&counter);
+        config.register_callback_on_update(move |_| {
+            callback_counter.fetch_add(1, Ordering::SeqCst);
+            callback_notifier.notify_one();
+        });
+
+        // No update should occur while offline, even though several update intervals pass.
+        for _ in 0..3000 {
+            time::advance(Duration::from_millis(1)).await;
+        }
+        assert_eq!(0, counter.load(Ordering::SeqCst));
+
+        // As soon as connectivity returns, the configuration should be updated.
+        connectivity.report::<_, AccountProviderError>(&Ok(()));
+        notifier.notified().await;
+
+        assert_eq!(1, counter.load(Ordering::SeqCst));
+        assert_eq!(900, config.config().lock_timeouts.background_timeout);
+    }
 }
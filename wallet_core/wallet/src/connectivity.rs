@@ -0,0 +1,233 @@
+use std::{
+    error::Error,
+    fmt::Debug,
+    sync::{Arc, Mutex},
+};
+
+use tokio::sync::watch::{self, Sender};
+use tracing::info;
+
+pub type ConnectivityCallback = Box<dyn FnMut(bool) + Send + Sync>;
+
+/// This models whether the wallet is currently able to reach its backends. The state is derived from the results
+/// of the requests made by the HTTP clients of the wallet, which are passed to [`Self::report()`]. A request that
+/// fails because the server could not be reached, or because it timed out, marks the wallet as offline. Any
+/// successful request, or any request that failed for another reason, means that the server responded, which marks
+/// the wallet as online again. Optionally, a callback can be set to get notified whenever this state changes.
+///
+/// Cloning this type is cheap and all clones share the same state, so that it can be handed to each subsystem that
+/// either makes requests or wants to defer them until connectivity returns.
+#[derive(Clone)]
+pub struct Connectivity(Arc<ConnectivityState>);
+
+struct ConnectivityState {
+    is_online: Sender<bool>,
+    update_callback: Mutex<Option<ConnectivityCallback>>,
+}
+
+impl Connectivity {
+    /// Create a new instance, which optimistically assumes that the wallet is online.
+    pub fn new() -> Self {
+        let (is_online, _) = watch::channel(true);
+
+        Connectivity(Arc::new(ConnectivityState {
+            is_online,
+            update_callback: Mutex::new(None),
+        }))
+    }
+
+    pub fn is_online(&self) -> bool {
+        *self.0.is_online.borrow()
+    }
+
+    fn set_online(&self, is_online: bool) {
+        let changed = self.0.is_online.send_if_modified(|current| {
+            let changed = *current != is_online;
+            *current = is_online;
+
+            changed
+        });
+
+        if changed {
+            info!("Wallet connectivity changed, is online: {}", is_online);
+
+            if let Some(ref mut update_callback) = *self.0.update_callback.lock().unwrap() {
+                update_callback(is_online)
+            }
+        }
+    }
+
+    /// Update the connectivity state based on the result of a request. Errors that do not originate
+    /// from the network at all, e.g. because a request could not be signed, leave the state unchanged.
+    pub fn report<T, E>(&self, result: &Result<T, E>)
+    where
+        E: Error + 'static,
+    {
+        match result {
+            Ok(_) => self.set_online(true),
+            Err(error) => self.report_error(error),
+        }
+    }
+
+    /// Update the connectivity state based on an error returned by a request, see [`Self::report()`].
+    pub fn report_error(&self, error: &(dyn Error + 'static)) {
+        if let Some(is_network_error) = classify_error(error) {
+            self.set_online(!is_network_error);
+        }
+    }
+
+    /// Convenience method that calls [`Self::report()`] and passes the result through,
+    /// so that it can wrap a request without needing a separate binding.
+    pub fn observe<T, E>(&self, result: Result<T, E>) -> Result<T, E>
+    where
+        E: Error + 'static,
+    {
+        self.report(&result);
+
+        result
+    }
+
+    /// Wait until the wallet is online, which returns immediately if it already is.
+    pub async fn wait_until_online(&self) {
+        let mut receiver = self.0.is_online.subscribe();
+
+        // This can only return an error when the sender is dropped, which is impossible as we hold a reference to it.
+        let _ = receiver.wait_for(|is_online| *is_online).await;
+    }
+
+    pub fn set_connectivity_callback<F>(&self, mut callback: F)
+    where
+        F: FnMut(bool) + Send + Sync + 'static,
+    {
+        callback(self.is_online());
+        self.0.update_callback.lock().unwrap().replace(Box::new(callback));
+    }
+
+    pub fn clear_connectivity_callback(&self) {
+        self.0.update_callback.lock().unwrap().take();
+    }
+}
+
+impl Default for Connectivity {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debug for Connectivity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Connectivity")
+            .field("is_online", &self.is_online())
+            .finish()
+    }
+}
+
+/// Look for a `reqwest::Error` in the source chain of an error and classify it. This returns `Some(true)` if the
+/// server could not be reached, `Some(false)` if the server did respond and `None` if the error does not contain
+/// a `reqwest::Error`, in which case nothing is known about connectivity.
+pub fn classify_error(error: &(dyn Error + 'static)) -> Option<bool> {
+    let mut source = Some(error);
+
+    while let Some(error) = source {
+        // The `openid::error::Error::Http` variant holds a `reqwest::Error` with the `transparent` error attribute,
+        // which means that the `reqwest::Error` itself will be skipped in the source chain and has to be extracted.
+        if let Some(openid::error::Error::Http(error)) = error.downcast_ref::<openid::error::Error>() {
+            return Some(is_network_error(error));
+        }
+
+        if let Some(error) = error.downcast_ref::<reqwest::Error>() {
+            return Some(is_network_error(error));
+        }
+
+        source = error.source();
+    }
+
+    None
+}
+
+fn is_network_error(error: &reqwest::Error) -> bool {
+    error.is_connect() || error.is_timeout()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::time;
+
+    use crate::account_provider::AccountProviderError;
+
+    use super::*;
+
+    /// Produce a `reqwest::Error` by connecting to a port on which nothing should be listening.
+    async fn connect_error() -> reqwest::Error {
+        reqwest::Client::new()
+            .get("http://127.0.0.1:9")
+            .send()
+            .await
+            .expect_err("request should fail to connect")
+    }
+
+    #[tokio::test]
+    async fn test_classify_error() {
+        let error = AccountProviderError::Networking(connect_error().await);
+        assert_eq!(classify_error(&error), Some(true));
+
+        let error = AccountProviderError::BaseUrl(url::ParseError::EmptyHost);
+        assert_eq!(classify_error(&error), None);
+    }
+
+    #[tokio::test]
+    async fn test_connectivity_report() {
+        let connectivity = Connectivity::new();
+        let states = Arc::new(Mutex::new(Vec::new()));
+        let callback_states = Arc::clone(&states);
+
+        connectivity.set_connectivity_callback(move |is_online| callback_states.lock().unwrap().push(is_online));
+
+        // A network error marks the wallet as offline, an unrelated error does not change that.
+        connectivity.report::<(), _>(&Err(AccountProviderError::Networking(connect_error().await)));
+        assert!(!connectivity.is_online());
+
+        connectivity.report::<(), _>(&Err(AccountProviderError::BaseUrl(url::ParseError::EmptyHost)));
+        assert!(!connectivity.is_online());
+
+        // A successful request marks the wallet as online, which happens only once.
+        connectivity.report::<_, AccountProviderError>(&Ok(()));
+        connectivity.report::<_, AccountProviderError>(&Ok(()));
+        assert!(connectivity.is_online());
+
+        assert_eq!(*states.lock().unwrap(), vec![true, false, true]);
+
+        connectivity.clear_connectivity_callback();
+        connectivity.report::<(), _>(&Err(AccountProviderError::Networking(connect_error().await)));
+
+        assert_eq!(states.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_connectivity_wait_until_online() {
+        let connectivity = Connectivity::new();
+
+        // Waiting while online should return immediately.
+        time::timeout(Duration::from_millis(100), connectivity.wait_until_online())
+            .await
+            .expect("waiting should not time out while online");
+
+        connectivity.report::<(), _>(&Err(AccountProviderError::Networking(connect_error().await)));
+
+        let waiting_connectivity = connectivity.clone();
+        let wait_task = tokio::spawn(async move { waiting_connectivity.wait_until_online().await });
+
+        time::sleep(Duration::from_millis(10)).await;
+        assert!(!wait_task.is_finished());
+
+        // Any clone reporting a successful request should end the wait.
+        connectivity.clone().report::<_, AccountProviderError>(&Ok(()));
+
+        time::timeout(Duration::from_millis(100), wait_task)
+            .await
+            .expect("waiting should not time out after connectivity returns")
+            .unwrap();
+    }
+}
@@ -0,0 +1,274 @@
+use base64::prelude::*;
+use chrono::{DateTime, Utc};
+use futures::future::TryFutureExt;
+use http::{header, HeaderMap, HeaderValue};
+use serde::Deserialize;
+use url::Url;
+
+use nl_wallet_mdoc::{
+    basic_sa_ext::UnsignedMdoc,
+    holder::{CborHttpClient, Mdoc, MdocCopies, TrustAnchor, Wallet as MdocWallet},
+    utils::{
+        keys::{KeyFactory, MdocEcdsaKey},
+        serialization::cbor_serialize,
+    },
+    ServiceEngagement,
+};
+use wallet_common::{
+    config::wallet_config::RetryConfiguration, correlation::CorrelationTracker, generator::Generator,
+    issuer_error::IssuerErrorData,
+};
+
+use crate::utils::{
+    reqwest::default_reqwest_client_builder,
+    retry::{retry_with_backoff, Idempotency},
+};
+
+use super::{CredentialIssuerClient, CredentialIssuerError};
+
+pub struct HttpCredentialIssuerClient {
+    http_client: reqwest::Client,
+    mdoc_wallet: MdocWallet,
+    issuer_url: Option<Url>,
+    retry_config: RetryConfiguration,
+    correlation: CorrelationTracker,
+}
+
+impl HttpCredentialIssuerClient {
+    pub fn new(mdoc_wallet: MdocWallet, retry_config: RetryConfiguration, correlation: CorrelationTracker) -> Self {
+        let http_client = default_reqwest_client_builder()
+            .default_headers(HeaderMap::from_iter([(
+                header::ACCEPT,
+                HeaderValue::from_static(mime::APPLICATION_JSON.as_ref()),
+            )]))
+            .build()
+            .expect("Could not build reqwest HTTP client");
+
+        HttpCredentialIssuerClient {
+            http_client,
+            mdoc_wallet,
+            issuer_url: None,
+            retry_config,
+            correlation,
+        }
+    }
+
+    /// Create a client with the default HTTP client for the mdoc wallet and the provided retry configuration.
+    pub fn with_retry_config(retry_config: RetryConfiguration, correlation: CorrelationTracker) -> Self {
+        let http_client = default_reqwest_client_builder()
+            .build()
+            .expect("Could not build reqwest HTTP client");
+
+        Self::new(
+            MdocWallet::new(CborHttpClient::new(http_client, correlation.clone())),
+            retry_config,
+            correlation,
+        )
+    }
+}
+
+#[derive(Deserialize)]
+struct ChallengeResponse {
+    challenge: String,
+}
+
+/// Try to get the body from any 4xx or 5xx error responses and parse it as the JSON error of the issuer, in order to
+/// create a [`CredentialIssuerError::Issuer`]. If the body is something else, e.g. when the error originates from a
+/// proxy, the body is returned as is.
+async fn check_issuer_response(response: reqwest::Response) -> Result<reqwest::Response, CredentialIssuerError> {
+    match response.error_for_status_ref() {
+        Ok(_) => Ok(response),
+        Err(error) => {
+            let error = match response.text().await.ok() {
+                Some(body) => match serde_json::from_str::<IssuerErrorData>(&body) {
+                    Ok(error_data) => CredentialIssuerError::Issuer(error_data),
+                    Err(_) => CredentialIssuerError::Response(error, body),
+                },
+                None => CredentialIssuerError::Networking(error),
+            };
+
+            Err(error)
+        }
+    }
+}
+
+impl Default for HttpCredentialIssuerClient {
+    fn default() -> Self {
+        Self::with_retry_config(RetryConfiguration::default(), CorrelationTracker::default())
+    }
+}
+
+impl CredentialIssuerClient for HttpCredentialIssuerClient {
+    fn session_issuer_url(&self) -> Option<&Url> {
+        // The issuer URL is only relevant as long as the session that was started with it is still active.
+        self.issuer_url
+            .as_ref()
+            .filter(|_| self.mdoc_wallet.has_issuance_session())
+    }
+
+    async fn start_issuance(
+        &mut self,
+        issuer_url: &Url,
+        access_token: &str,
+    ) -> Result<Vec<UnsignedMdoc>, CredentialIssuerError> {
+        let url = issuer_url
+            .join("start")
+            .expect("Could not create \"start\" URL from credential issuer base URL");
+
+        // The access token may only be valid for a single use, so the request is not idempotent.
+        let service_engagement = retry_with_backoff(
+            &self.retry_config,
+            |error: &CredentialIssuerError| match error {
+                CredentialIssuerError::Networking(error) => Idempotency::NonIdempotent.can_retry_reqwest_error(error),
+                _ => false,
+            },
+            || {
+                self.http_client
+                    .post(url.clone())
+                    .bearer_auth(access_token)
+                    .headers(self.correlation.headers())
+                    .send()
+                    .map_err(CredentialIssuerError::from)
+            },
+        )
+        .and_then(check_issuer_response)
+        .await?
+        .json::<ServiceEngagement>()
+        .await?;
+
+        let unsigned_mdocs = self.mdoc_wallet.start_issuance(service_engagement).await?;
+        self.issuer_url.replace(issuer_url.clone());
+
+        Ok(unsigned_mdocs.to_vec())
+    }
+
+    async fn start_reissuance<K: MdocEcdsaKey>(
+        &mut self,
+        issuer_url: &Url,
+        mdoc: &Mdoc,
+        key_factory: &impl KeyFactory<Key = K>,
+    ) -> Result<Vec<UnsignedMdoc>, CredentialIssuerError> {
+        let challenge_url = issuer_url
+            .join("reissue/challenge")
+            .expect("Could not create \"reissue/challenge\" URL from credential issuer base URL");
+        let reissue_url = issuer_url
+            .join("reissue")
+            .expect("Could not create \"reissue\" URL from credential issuer base URL");
+
+        // Every challenge can only be used once, so neither request is retried.
+        let challenge = self
+            .http_client
+            .post(challenge_url)
+            .headers(self.correlation.headers())
+            .send()
+            .map_err(CredentialIssuerError::from)
+            .and_then(check_issuer_response)
+            .await?
+            .json::<ChallengeResponse>()
+            .await?
+            .challenge;
+        let challenge = BASE64_URL_SAFE_NO_PAD.decode(challenge)?;
+
+        let request = mdoc.reissuance_request(challenge, key_factory).await?;
+        let body = cbor_serialize(&request).map_err(nl_wallet_mdoc::Error::from)?;
+
+        let service_engagement = self
+            .http_client
+            .post(reissue_url)
+            .headers(self.correlation.headers())
+            .header(header::CONTENT_TYPE, HeaderValue::from_static("application/cbor"))
+            .body(body)
+            .send()
+            .map_err(CredentialIssuerError::from)
+            .and_then(check_issuer_response)
+            .await?
+            .json::<ServiceEngagement>()
+            .await?;
+
+        let unsigned_mdocs = self.mdoc_wallet.start_issuance(service_engagement).await?;
+        self.issuer_url.replace(issuer_url.clone());
+
+        Ok(unsigned_mdocs.to_vec())
+    }
+
+    async fn accept_issuance<K: MdocEcdsaKey>(
+        &mut self,
+        mdoc_trust_anchors: &[TrustAnchor<'_>],
+        key_factory: &impl KeyFactory<Key = K>,
+        time: &impl Generator<DateTime<Utc>>,
+    ) -> Result<Vec<MdocCopies>, CredentialIssuerError> {
+        let mdocs = self
+            .mdoc_wallet
+            .finish_issuance(mdoc_trust_anchors, key_factory, time)
+            .await?;
+
+        Ok(mdocs)
+    }
+
+    async fn reject_issuance(&mut self) -> Result<(), CredentialIssuerError> {
+        self.mdoc_wallet.stop_issuance().await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use serde_json::json;
+    use wiremock::{
+        matchers::{header, method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use wallet_common::issuer_error::IssuerErrorCode;
+
+    use super::*;
+
+    async fn start_issuance_with_response(response: ResponseTemplate) -> CredentialIssuerError {
+        let server = MockServer::start().await;
+        let issuer_url = Url::parse(&server.uri()).unwrap().join("issuer/").unwrap();
+
+        Mock::given(method("POST"))
+            .and(path("/issuer/start"))
+            .and(header("Authorization", "Bearer access_token"))
+            .respond_with(response)
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        HttpCredentialIssuerClient::default()
+            .start_issuance(&issuer_url, "access_token")
+            .await
+            .expect_err("starting issuance should fail")
+    }
+
+    #[tokio::test]
+    async fn test_http_credential_issuer_client_start_issuance_error_data() {
+        let error = start_issuance_with_response(
+            ResponseTemplate::new(401)
+                .insert_header("Content-Type", "application/problem+json")
+                .set_body_json(json!({
+                    "code": "digid_access_token_rejected",
+                    "title": "Error title",
+                    "retryable": false,
+                })),
+        )
+        .await;
+
+        assert_matches!(
+            error,
+            CredentialIssuerError::Issuer(data) if data.code == IssuerErrorCode::DigidAccessTokenRejected
+                && data.title == "Error title"
+                && !data.retryable
+        );
+    }
+
+    #[tokio::test]
+    async fn test_http_credential_issuer_client_start_issuance_error_text() {
+        let error =
+            start_issuance_with_response(ResponseTemplate::new(502).set_body_string("Your gateway is bad")).await;
+
+        assert_matches!(error, CredentialIssuerError::Response(_, body) if body == "Your gateway is bad");
+    }
+}
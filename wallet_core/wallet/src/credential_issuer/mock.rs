@@ -0,0 +1,67 @@
+use chrono::{DateTime, Utc};
+
+use nl_wallet_mdoc::{
+    basic_sa_ext::UnsignedMdoc,
+    holder::{Mdoc, MdocCopies, TrustAnchor},
+    utils::keys::{KeyFactory, MdocEcdsaKey},
+};
+use url::Url;
+use wallet_common::generator::Generator;
+
+use super::{CredentialIssuerClient, CredentialIssuerError};
+
+#[derive(Default)]
+pub struct MockCredentialIssuerClient {
+    pub session_issuer_url: Option<Url>,
+    pub unsigned_mdocs: Vec<UnsignedMdoc>,
+    pub mdoc_copies: Vec<MdocCopies>,
+    pub next_error: Option<CredentialIssuerError>,
+}
+
+impl CredentialIssuerClient for MockCredentialIssuerClient {
+    fn session_issuer_url(&self) -> Option<&Url> {
+        self.session_issuer_url.as_ref()
+    }
+
+    async fn start_issuance(
+        &mut self,
+        _issuer_url: &Url,
+        _access_token: &str,
+    ) -> Result<Vec<UnsignedMdoc>, CredentialIssuerError> {
+        match self.next_error.take() {
+            None => Ok(self.unsigned_mdocs.clone()),
+            Some(error) => Err(error),
+        }
+    }
+
+    async fn start_reissuance<K: MdocEcdsaKey>(
+        &mut self,
+        _issuer_url: &Url,
+        _mdoc: &Mdoc,
+        _key_factory: &impl KeyFactory<Key = K>,
+    ) -> Result<Vec<UnsignedMdoc>, CredentialIssuerError> {
+        match self.next_error.take() {
+            None => Ok(self.unsigned_mdocs.clone()),
+            Some(error) => Err(error),
+        }
+    }
+
+    async fn accept_issuance<K: MdocEcdsaKey>(
+        &mut self,
+        _mdoc_trust_anchors: &[TrustAnchor<'_>],
+        _key_factory: &impl KeyFactory<Key = K>,
+        _time: &impl Generator<DateTime<Utc>>,
+    ) -> Result<Vec<MdocCopies>, CredentialIssuerError> {
+        match self.next_error.take() {
+            None => Ok(self.mdoc_copies.clone()),
+            Some(error) => Err(error),
+        }
+    }
+
+    async fn reject_issuance(&mut self) -> Result<(), CredentialIssuerError> {
+        match self.next_error.take() {
+            None => Ok(()),
+            Some(error) => Err(error),
+        }
+    }
+}
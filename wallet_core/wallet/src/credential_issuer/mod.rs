@@ -0,0 +1,72 @@
+mod client;
+
+#[cfg(any(test, feature = "mock"))]
+mod mock;
+
+use chrono::{DateTime, Utc};
+use url::Url;
+
+use nl_wallet_mdoc::{
+    basic_sa_ext::UnsignedMdoc,
+    holder::{Mdoc, MdocCopies, TrustAnchor},
+    utils::keys::{KeyFactory, MdocEcdsaKey},
+};
+use wallet_common::{generator::Generator, issuer_error::IssuerErrorData};
+
+pub use client::HttpCredentialIssuerClient;
+
+#[cfg(any(test, feature = "mock"))]
+pub use self::mock::MockCredentialIssuerClient;
+
+#[derive(Debug, thiserror::Error)]
+pub enum CredentialIssuerError {
+    #[error("could not get credentials from issuer: {0}")]
+    Networking(#[from] reqwest::Error),
+    #[error("could not get credentials from issuer: {0} - Response body: {1}")]
+    Response(#[source] reqwest::Error, String),
+    #[error("issuer responded with error: {0}")]
+    Issuer(IssuerErrorData),
+    #[error("could not decode challenge from issuer: {0}")]
+    Challenge(#[from] base64::DecodeError),
+    #[error("mdoc error: {0}")]
+    MdocError(#[from] nl_wallet_mdoc::Error),
+}
+
+/// A client for the issuers of credentials, i.e. the PID issuer and any other issuers of cards (e.g. diplomas
+/// or permits) that are present in the configuration. It holds at most one issuance session at a time, which is
+/// started with a particular issuer by its base URL.
+pub trait CredentialIssuerClient {
+    /// The base URL of the issuer with which there currently is an active issuance session, if any.
+    fn session_issuer_url(&self) -> Option<&Url>;
+
+    fn has_session(&self) -> bool {
+        self.session_issuer_url().is_some()
+    }
+
+    async fn start_issuance(
+        &mut self,
+        issuer_url: &Url,
+        access_token: &str,
+    ) -> Result<Vec<UnsignedMdoc>, CredentialIssuerError>;
+
+    /// Start an issuance session without DigiD, by proving possession of a valid `mdoc` that was issued before by the
+    /// issuer at `issuer_url`. This is used to obtain fresh copies of the mdocs of the holder.
+    async fn start_reissuance<K: MdocEcdsaKey>(
+        &mut self,
+        issuer_url: &Url,
+        mdoc: &Mdoc,
+        key_factory: &impl KeyFactory<Key = K>,
+    ) -> Result<Vec<UnsignedMdoc>, CredentialIssuerError>;
+
+    /// Accept the offered mdocs, which are verified against the trust anchors at the time produced by `time`.
+    /// If this fails, the session is retained so that accepting can be retried. A retry reuses the keys that
+    /// were generated by the failed attempt, for which the issuer returns the mdocs it issued before, if any.
+    async fn accept_issuance<K: MdocEcdsaKey>(
+        &mut self,
+        mdoc_trust_anchors: &[TrustAnchor<'_>],
+        key_factory: &impl KeyFactory<Key = K>,
+        time: &impl Generator<DateTime<Utc>>,
+    ) -> Result<Vec<MdocCopies>, CredentialIssuerError>;
+
+    async fn reject_issuance(&mut self) -> Result<(), CredentialIssuerError>;
+}
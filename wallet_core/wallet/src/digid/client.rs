@@ -1,7 +1,7 @@
 use base64::prelude::*;
 use url::Url;
 
-use wallet_common::utils;
+use wallet_common::{config::wallet_config::IdentityProviderConfiguration, utils};
 
 use crate::{
     pkce::{PkcePair, S256PkcePair},
@@ -9,14 +9,15 @@ use crate::{
 };
 
 use super::{
-    openid_client::{HttpOpenIdClient, OpenIdClient},
-    DigidError, DigidSession,
+    openid_client::{AuthorizationResponse, HttpOpenIdClient, OpenIdClient},
+    DigidError, DigidSession, DigidSessionState, RedirectUriErrorKind,
 };
 
 const PARAM_ERROR: &str = "error";
 const PARAM_ERROR_DESCRIPTION: &str = "error_description";
 const PARAM_STATE: &str = "state";
 const PARAM_CODE: &str = "code";
+const PARAM_RESPONSE: &str = "response";
 
 #[derive(Debug)]
 pub struct HttpDigidSession<C = HttpOpenIdClient, P = S256PkcePair> {
@@ -30,6 +31,10 @@ pub struct HttpDigidSession<C = HttpOpenIdClient, P = S256PkcePair> {
     nonce: String,
     /// The PKCE pair used.
     pkce_pair: P,
+    /// The authentication URL, which may refer to a pushed authorization request.
+    auth_url: Url,
+    /// The refresh token, if received together with the access token.
+    refresh_token: Option<String>,
 }
 
 impl<C, P> DigidSession for HttpDigidSession<C, P>
@@ -37,20 +42,25 @@ where
     P: PkcePair + 'static,
     C: OpenIdClient,
 {
-    async fn start(issuer_url: Url, client_id: String, redirect_uri: Url) -> Result<Self, DigidError> {
+    async fn start(config: IdentityProviderConfiguration, redirect_uri: Url) -> Result<Self, DigidError> {
         // Remember the `redirect_uri` base.
         let mut redirect_uri_base = redirect_uri.clone();
         redirect_uri_base.set_fragment(None);
         redirect_uri_base.set_query(None);
 
         // Perform OpenID discovery at the issuer.
-        let openid_client = C::discover(issuer_url, client_id, redirect_uri).await?;
+        let openid_client = C::discover(config.url, config.client_id, config.scopes, redirect_uri).await?;
 
         // Generate a random CSRF token and nonce.
         let csrf_token = BASE64_URL_SAFE_NO_PAD.encode(utils::random_bytes(16));
         let nonce = BASE64_URL_SAFE_NO_PAD.encode(utils::random_bytes(16));
         let pkce_pair = P::generate();
 
+        // Generate the authentication URL up front, as this may involve a pushed authorization request.
+        let auth_url = openid_client
+            .auth_url(csrf_token.clone(), nonce.clone(), &pkce_pair)
+            .await?;
+
         // Store the client, generated tokens and auth url in a session for when the redirect URI returns.
         let session = HttpDigidSession {
             redirect_uri_base,
@@ -58,14 +68,50 @@ where
             csrf_token,
             nonce,
             pkce_pair,
+            auth_url,
+            refresh_token: None,
+        };
+
+        Ok(session)
+    }
+
+    async fn restore(config: IdentityProviderConfiguration, state: DigidSessionState) -> Result<Self, DigidError> {
+        // Discovery is performed with the redirect URI base, which is
+        // equal to the redirect URI that was provided when starting.
+        let openid_client = C::discover(
+            config.url,
+            config.client_id,
+            config.scopes,
+            state.redirect_uri_base.clone(),
+        )
+        .await?;
+
+        let session = HttpDigidSession {
+            redirect_uri_base: state.redirect_uri_base,
+            openid_client,
+            csrf_token: state.csrf_token,
+            nonce: state.nonce,
+            pkce_pair: P::from_code_verifier(state.pkce_code_verifier),
+            auth_url: state.auth_url,
+            refresh_token: state.refresh_token,
         };
 
         Ok(session)
     }
 
+    fn to_state(&self) -> DigidSessionState {
+        DigidSessionState {
+            redirect_uri_base: self.redirect_uri_base.clone(),
+            csrf_token: self.csrf_token.clone(),
+            nonce: self.nonce.clone(),
+            pkce_code_verifier: self.pkce_pair.code_verifier().to_string(),
+            auth_url: self.auth_url.clone(),
+            refresh_token: self.refresh_token.clone(),
+        }
+    }
+
     fn auth_url(&self) -> Url {
-        self.openid_client
-            .auth_url(self.csrf_token.clone(), self.nonce.clone(), &self.pkce_pair)
+        self.auth_url.clone()
     }
 
     fn matches_received_redirect_uri(&self, received_redirect_uri: &Url) -> bool {
@@ -74,45 +120,78 @@ where
             .starts_with(self.redirect_uri_base.as_str())
     }
 
-    async fn get_access_token(self, received_redirect_uri: &Url) -> Result<String, DigidError> {
+    async fn get_access_token(&mut self, received_redirect_uri: &Url) -> Result<String, DigidError> {
         // Check if the redirect URL received actually belongs to us.
         if !self.matches_received_redirect_uri(received_redirect_uri) {
             return Err(DigidError::RedirectUriMismatch);
         }
 
-        // Check if the `error` query parameter is populated, if so create an
-        // error from it and a potential `error_description` query parameter.
-        let error = url_find_first_query_value(received_redirect_uri, PARAM_ERROR);
-        if let Some(error) = error {
-            let error_description = url_find_first_query_value(received_redirect_uri, PARAM_ERROR_DESCRIPTION);
+        // If the `response` query parameter is populated, this is a JWT secured authorization
+        // response that needs to be validated. Otherwise, use the query parameters directly.
+        let response = match url_find_first_query_value(received_redirect_uri, PARAM_RESPONSE) {
+            Some(response) => self.openid_client.decode_authorization_response(&response)?,
+            None => {
+                let find_query_value =
+                    |key| url_find_first_query_value(received_redirect_uri, key).map(|value| value.into_owned());
+
+                AuthorizationResponse {
+                    code: find_query_value(PARAM_CODE),
+                    state: find_query_value(PARAM_STATE),
+                    error: find_query_value(PARAM_ERROR),
+                    error_description: find_query_value(PARAM_ERROR_DESCRIPTION),
+                }
+            }
+        };
+
+        // Check if the `error` parameter is populated, if so create an
+        // error from it and a potential `error_description` parameter.
+        if let Some(error) = response.error {
             let error = DigidError::RedirectUriError {
-                error: error.into_owned(),
-                error_description: error_description.map(|d| d.into_owned()),
+                kind: RedirectUriErrorKind::from_redirect_error(&error, response.error_description.as_deref()),
+                error,
+                error_description: response.error_description,
             };
 
             return Err(error);
         }
 
-        // Verify that the state query parameter matches the csrf_token.
-        let state =
-            url_find_first_query_value(received_redirect_uri, PARAM_STATE).ok_or(DigidError::StateTokenMismatch)?;
+        // Verify that the state parameter matches the csrf_token.
+        let state = response.state.ok_or(DigidError::StateTokenMismatch)?;
 
         if state != self.csrf_token {
             return Err(DigidError::StateTokenMismatch);
         }
 
-        // Parse the authorization code from the redirect URL.
-        let authorization_code =
-            url_find_first_query_value(received_redirect_uri, PARAM_CODE).ok_or(DigidError::NoAuthCode)?;
+        // Parse the authorization code from the response.
+        let authorization_code = response.code.ok_or(DigidError::NoAuthCode)?;
 
         // Use the authorization code and the PKCE verifier to request the
         // access token and verify the result.
-        let access_token = self
+        let tokens = self
             .openid_client
             .authenticate(&authorization_code, &self.nonce, &self.pkce_pair)
             .await?;
 
-        Ok(access_token)
+        self.refresh_token = tokens.refresh_token;
+
+        Ok(tokens.access_token)
+    }
+
+    fn can_refresh_access_token(&self) -> bool {
+        self.refresh_token.is_some()
+    }
+
+    async fn refresh_access_token(&mut self) -> Result<String, DigidError> {
+        let refresh_token = self.refresh_token.as_deref().ok_or(DigidError::NoRefreshToken)?;
+
+        let tokens = self.openid_client.refresh(refresh_token).await?;
+
+        // DigiD may rotate the refresh token, in which case the previous one is no longer valid.
+        if let Some(refresh_token) = tokens.refresh_token {
+            self.refresh_token = Some(refresh_token);
+        }
+
+        Ok(tokens.access_token)
     }
 }
 
@@ -122,7 +201,11 @@ mod tests {
     use mockall::predicate::*;
     use serial_test::serial;
 
-    use crate::{digid::openid_client::MockOpenIdClient, pkce::MockPkcePair, utils::url::url_with_query_pairs};
+    use crate::{
+        digid::openid_client::{AccessTokens, MockOpenIdClient, OpenIdError},
+        pkce::MockPkcePair,
+        utils::url::url_with_query_pairs,
+    };
 
     use super::*;
 
@@ -135,6 +218,16 @@ mod tests {
     const AUTH_URL: &str = "http://example.com/auth";
     const AUTH_CODE: &str = "the_authentication_code";
     const ACCESS_CODE: &str = "the_access_code";
+    const REFRESH_TOKEN: &str = "the_refresh_token";
+    const SCOPE: &str = "openid";
+
+    fn identity_provider_config() -> IdentityProviderConfiguration {
+        IdentityProviderConfiguration {
+            url: Url::parse(ISSUER_URL).unwrap(),
+            client_id: CLIENT_ID.to_string(),
+            scopes: vec![SCOPE.to_string()],
+        }
+    }
 
     // Helper function for creating a `HttpDigidSession` with hardcoded state.
     fn create_digid_session() -> HttpDigidSession<MockOpenIdClient, MockPkcePair> {
@@ -144,6 +237,8 @@ mod tests {
             csrf_token: CSRF_TOKEN.to_string(),
             nonce: NONCE.to_string(),
             pkce_pair: MockPkcePair::new(),
+            auth_url: Url::parse(AUTH_URL).unwrap(),
+            refresh_token: None,
         }
     }
 
@@ -154,12 +249,11 @@ mod tests {
         let discover_context = MockOpenIdClient::discover_context();
         discover_context
             .expect()
-            .return_once(|_, _, _| Err(openid::error::Error::CannotBeABase.into()));
+            .return_once(|_, _, _, _| Err(openid::error::Error::CannotBeABase.into()));
 
         // Start a DigiD session, which should return an error.
         let error = HttpDigidSession::<MockOpenIdClient, MockPkcePair>::start(
-            Url::parse(ISSUER_URL).unwrap(),
-            CLIENT_ID.to_string(),
+            identity_provider_config(),
             Url::parse(REDIRECT_URI).unwrap(),
         )
         .await
@@ -187,48 +281,103 @@ mod tests {
         discover_context
             .expect()
             .with(
-                eq(redirect_uri.clone()),
+                eq(Url::parse(ISSUER_URL).unwrap()),
                 eq(CLIENT_ID.to_string()),
-                eq(Url::parse(REDIRECT_URI).unwrap()),
+                eq(vec![SCOPE.to_string()]),
+                eq(redirect_uri.clone()),
             )
-            .return_once(|_, _, _| Ok(MockOpenIdClient::new()));
+            .return_once(|_, _, _, _| {
+                let mut openid_client = MockOpenIdClient::new();
+
+                openid_client
+                    .expect_auth_url()
+                    .return_once(|_, _, _: &MockPkcePair| Ok(Url::parse(AUTH_URL).unwrap()));
+
+                Ok(openid_client)
+            });
 
         let generate_context = MockPkcePair::generate_context();
         generate_context.expect().return_once(MockPkcePair::new);
 
         // Create the session and check the result.
-        let session = HttpDigidSession::<MockOpenIdClient, MockPkcePair>::start(
-            redirect_uri,
-            CLIENT_ID.to_string(),
-            Url::parse(REDIRECT_URI).unwrap(),
-        )
-        .await
-        .expect("Could not start DigiD session");
+        let session =
+            HttpDigidSession::<MockOpenIdClient, MockPkcePair>::start(identity_provider_config(), redirect_uri)
+                .await
+                .expect("Could not start DigiD session");
 
         assert_eq!(session.redirect_uri_base.as_str(), REDIRECT_URI);
         assert!(!session.csrf_token.is_empty());
         assert!(!session.nonce.is_empty());
+        assert_eq!(session.auth_url(), Url::parse(AUTH_URL).unwrap());
     }
 
-    #[test]
-    fn test_http_digid_session_auth_url() {
-        // Create session and set up expectation of `OpenIdClient.auth_url_and_pkce()` being called.
-        let session = {
-            let mut session = create_digid_session();
+    #[tokio::test]
+    #[serial]
+    async fn test_http_digid_session_to_state_restore() {
+        let session = HttpDigidSession {
+            redirect_uri_base: Url::parse(REDIRECT_URI).unwrap(),
+            openid_client: MockOpenIdClient::new(),
+            csrf_token: CSRF_TOKEN.to_string(),
+            nonce: NONCE.to_string(),
+            pkce_pair: S256PkcePair::generate(),
+            auth_url: Url::parse(AUTH_URL).unwrap(),
+            refresh_token: Some(REFRESH_TOKEN.to_string()),
+        };
 
-            session
-                .openid_client
+        let state = session.to_state();
+
+        // Restoring the session should perform discovery using the redirect URI base.
+        let discover_context = MockOpenIdClient::discover_context();
+        discover_context
+            .expect()
+            .with(
+                eq(Url::parse(ISSUER_URL).unwrap()),
+                eq(CLIENT_ID.to_string()),
+                eq(vec![SCOPE.to_string()]),
+                eq(Url::parse(REDIRECT_URI).unwrap()),
+            )
+            .return_once(|_, _, _, _| Ok(MockOpenIdClient::new()));
+
+        let restored_session =
+            HttpDigidSession::<MockOpenIdClient, S256PkcePair>::restore(identity_provider_config(), state.clone())
+                .await
+                .expect("Could not restore DigiD session");
+
+        assert_eq!(restored_session.to_state(), state);
+        assert_eq!(
+            restored_session.pkce_pair.code_challenge(),
+            session.pkce_pair.code_challenge()
+        );
+        assert!(restored_session.can_refresh_access_token());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_http_digid_session_start_auth_url_error() {
+        // Set up for an error being returned when generating the authentication URL.
+        let discover_context = MockOpenIdClient::discover_context();
+        discover_context.expect().return_once(|_, _, _, _| {
+            let mut openid_client = MockOpenIdClient::new();
+
+            openid_client
                 .expect_auth_url()
-                .with(eq(CSRF_TOKEN.to_string()), eq(NONCE.to_string()), always())
-                .return_once(|_, _, _: &MockPkcePair| Url::parse(AUTH_URL).unwrap());
+                .return_once(|_, _, _: &MockPkcePair| Err(openid::error::Error::MissingOpenidScope.into()));
 
-            session
-        };
+            Ok(openid_client)
+        });
 
-        // The authentication URl returned should match the expectation above.
-        let auth_url = session.auth_url();
+        let generate_context = MockPkcePair::generate_context();
+        generate_context.expect().return_once(MockPkcePair::new);
 
-        assert_eq!(auth_url, Url::parse(AUTH_URL).unwrap());
+        // Start a DigiD session, which should return an error.
+        let error = HttpDigidSession::<MockOpenIdClient, MockPkcePair>::start(
+            identity_provider_config(),
+            Url::parse(REDIRECT_URI).unwrap(),
+        )
+        .await
+        .expect_err("Starting DigiD session should have failed");
+
+        assert_matches!(error, DigidError::OpenId(_));
     }
 
     #[test]
@@ -250,7 +399,7 @@ mod tests {
     // Helper function for testing `HttpDigidSession.get_access_token()`
     // calls that should result in an error.
     async fn create_session_and_get_access_token_error(uri: &Url) -> DigidError {
-        let session = create_digid_session();
+        let mut session = create_digid_session();
 
         session
             .get_access_token(uri)
@@ -281,6 +430,7 @@ mod tests {
         let error = create_session_and_get_access_token_error(&uri).await;
 
         assert_matches!(error, DigidError::RedirectUriError {
+            kind: RedirectUriErrorKind::Unknown,
             ref error,
             error_description: Some(ref error_description)
         } if error == "error_type" && error_description == "this is the error description");
@@ -294,11 +444,58 @@ mod tests {
         let error = create_session_and_get_access_token_error(&uri).await;
 
         assert_matches!(error, DigidError::RedirectUriError {
+            kind: RedirectUriErrorKind::Unknown,
             ref error,
             error_description: _
         } if error == "foobar");
     }
 
+    #[tokio::test]
+    async fn test_http_digid_session_get_access_token_redirect_uri_error_kind() {
+        // An access denied error that mentions cancellation should be classified as such.
+        let uri = url_with_query_pairs(
+            Url::parse(REDIRECT_URI).unwrap(),
+            &[
+                (PARAM_ERROR, "access_denied"),
+                (PARAM_ERROR_DESCRIPTION, "Authentication cancelled by user"),
+            ],
+        );
+
+        let error = create_session_and_get_access_token_error(&uri).await;
+
+        assert_matches!(
+            error,
+            DigidError::RedirectUriError {
+                kind: RedirectUriErrorKind::Cancelled,
+                ..
+            }
+        );
+    }
+
+    #[test]
+    fn test_redirect_uri_error_kind_from_redirect_error() {
+        assert_eq!(
+            RedirectUriErrorKind::from_redirect_error("access_denied", None),
+            RedirectUriErrorKind::AccessDenied
+        );
+        assert_eq!(
+            RedirectUriErrorKind::from_redirect_error("access_denied", Some("User CANCELED the login")),
+            RedirectUriErrorKind::Cancelled
+        );
+        assert_eq!(
+            RedirectUriErrorKind::from_redirect_error("cancelled", None),
+            RedirectUriErrorKind::Cancelled
+        );
+        assert_eq!(
+            RedirectUriErrorKind::from_redirect_error("session_expired", Some("too slow")),
+            RedirectUriErrorKind::Expired
+        );
+        assert_eq!(
+            RedirectUriErrorKind::from_redirect_error("server_error", None),
+            RedirectUriErrorKind::Unknown
+        );
+    }
+
     #[tokio::test]
     async fn test_http_digid_session_get_access_token_state_token_mismatch() {
         // This URI contains an incorrect `state` query parameter.
@@ -323,7 +520,7 @@ mod tests {
     async fn test_http_digid_session_get_access_openid_error() {
         // Create session and set up expectation to have `OpenIdClient.authenticate()`
         // return an error.
-        let session = {
+        let mut session = {
             let mut session = create_digid_session();
 
             session
@@ -353,14 +550,19 @@ mod tests {
     async fn test_http_digid_session_get_access() {
         // Create session and set up expectation to have `OpenIdClient.authenticate()`
         // return an access token.
-        let session = {
+        let mut session = {
             let mut session = create_digid_session();
 
             session
                 .openid_client
                 .expect_authenticate()
                 .with(eq(AUTH_CODE), eq(NONCE), always())
-                .return_once(|_, _, _: &MockPkcePair| Ok(ACCESS_CODE.to_string()));
+                .return_once(|_, _, _: &MockPkcePair| {
+                    Ok(AccessTokens {
+                        access_token: ACCESS_CODE.to_string(),
+                        refresh_token: REFRESH_TOKEN.to_string().into(),
+                    })
+                });
 
             session
         };
@@ -371,6 +573,9 @@ mod tests {
             &[(PARAM_STATE, CSRF_TOKEN), (PARAM_CODE, AUTH_CODE)],
         );
 
+        // Without an access token, the session cannot be refreshed yet.
+        assert!(!session.can_refresh_access_token());
+
         // Get the access token and test the result.
         let access_token = session
             .get_access_token(&uri)
@@ -378,5 +583,113 @@ mod tests {
             .expect("Could not get access token");
 
         assert_eq!(access_token, ACCESS_CODE);
+        assert!(session.can_refresh_access_token());
+    }
+
+    #[tokio::test]
+    async fn test_http_digid_session_refresh_access_token() {
+        let mut session = create_digid_session();
+
+        // Without a refresh token, refreshing should fail.
+        let error = session
+            .refresh_access_token()
+            .await
+            .expect_err("Refreshing access token should have failed");
+
+        assert_matches!(error, DigidError::NoRefreshToken);
+
+        // The refresh token should be used and replaced by the one received from DigiD.
+        session.refresh_token = REFRESH_TOKEN.to_string().into();
+        session
+            .openid_client
+            .expect_refresh()
+            .with(eq(REFRESH_TOKEN))
+            .return_once(|_| {
+                Ok(AccessTokens {
+                    access_token: "new_access_code".to_string(),
+                    refresh_token: "new_refresh_token".to_string().into(),
+                })
+            });
+
+        let access_token = session
+            .refresh_access_token()
+            .await
+            .expect("Could not refresh access token");
+
+        assert_eq!(access_token, "new_access_code");
+        assert_eq!(session.refresh_token.as_deref(), Some("new_refresh_token"));
+    }
+
+    #[tokio::test]
+    async fn test_http_digid_session_get_access_token_jarm_error() {
+        // Create session and set up expectation to have `OpenIdClient.decode_authorization_response()`
+        // return an error.
+        let mut session = {
+            let mut session = create_digid_session();
+
+            session
+                .openid_client
+                .expect_decode_authorization_response()
+                .with(eq("jarm_response"))
+                .return_once(|_| Err(OpenIdError::JarmKeyNotFound));
+
+            session
+        };
+
+        let uri = url_with_query_pairs(Url::parse(REDIRECT_URI).unwrap(), &[(PARAM_RESPONSE, "jarm_response")]);
+
+        let error = session
+            .get_access_token(&uri)
+            .await
+            .expect_err("Getting access token should have failed");
+
+        assert_matches!(error, DigidError::OpenId(OpenIdError::JarmKeyNotFound));
+    }
+
+    #[tokio::test]
+    async fn test_http_digid_session_get_access_token_jarm() {
+        // Create session and set up expectations to have the JARM response decoded
+        // and then have `OpenIdClient.authenticate()` return an access token.
+        let mut session = {
+            let mut session = create_digid_session();
+
+            session
+                .openid_client
+                .expect_decode_authorization_response()
+                .with(eq("jarm_response"))
+                .return_once(|_| {
+                    Ok(AuthorizationResponse {
+                        code: Some(AUTH_CODE.to_string()),
+                        state: Some(CSRF_TOKEN.to_string()),
+                        ..Default::default()
+                    })
+                });
+
+            session
+                .openid_client
+                .expect_authenticate()
+                .with(eq(AUTH_CODE), eq(NONCE), always())
+                .return_once(|_, _, _: &MockPkcePair| {
+                    Ok(AccessTokens {
+                        access_token: ACCESS_CODE.to_string(),
+                        refresh_token: None,
+                    })
+                });
+
+            session
+        };
+
+        // The query parameters outside of the JARM response should be ignored.
+        let uri = url_with_query_pairs(
+            Url::parse(REDIRECT_URI).unwrap(),
+            &[(PARAM_RESPONSE, "jarm_response"), (PARAM_CODE, "ignored_code")],
+        );
+
+        let access_token = session
+            .get_access_token(&uri)
+            .await
+            .expect("Could not get access token");
+
+        assert_eq!(access_token, ACCESS_CODE);
     }
 }
@@ -2,8 +2,11 @@ mod client;
 mod openid_client;
 mod openid_pkce;
 
+use serde::{Deserialize, Serialize};
 use url::Url;
 
+use wallet_common::config::wallet_config::IdentityProviderConfiguration;
+
 pub use self::openid_client::OpenIdError;
 
 pub use self::client::HttpDigidSession;
@@ -16,6 +19,7 @@ pub enum DigidError {
     RedirectUriMismatch,
     #[error("unsuccessful DigiD stepout: {}", .error_description.as_ref().unwrap_or(.error))]
     RedirectUriError {
+        kind: RedirectUriErrorKind,
         error: String,
         error_description: Option<String>,
     },
@@ -23,16 +27,80 @@ pub enum DigidError {
     StateTokenMismatch,
     #[error("no authorization code received in redirect URI")]
     NoAuthCode,
+    #[error("no refresh token available to refresh access token")]
+    NoRefreshToken,
+}
+
+/// The state of a [`DigidSession`] that can be persisted, so that the session can be restored with
+/// [`DigidSession::restore()`] if the app is terminated while the user is authenticating in the browser.
+/// Note that this contains secrets, so it should only be persisted in encrypted storage.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DigidSessionState {
+    pub redirect_uri_base: Url,
+    pub csrf_token: String,
+    pub nonce: String,
+    pub pkce_code_verifier: String,
+    pub auth_url: Url,
+    pub refresh_token: Option<String>,
+}
+
+/// The reason why DigiD did not complete authentication, as classified from
+/// the `error` and `error_description` parameters of the redirect URI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RedirectUriErrorKind {
+    /// The user cancelled authentication.
+    Cancelled,
+    /// The authentication session expired before the user completed it.
+    Expired,
+    /// The user could not be authenticated or did not give consent.
+    AccessDenied,
+    /// Any other error, for which only the original `error` parameter is available.
+    Unknown,
+}
+
+impl RedirectUriErrorKind {
+    pub fn from_redirect_error(error: &str, error_description: Option<&str>) -> Self {
+        match error {
+            // DigiD reports cancellation by the user as a denial of access, which can only be
+            // told apart from other denials by its description.
+            "access_denied"
+                if error_description
+                    .map(|description| description.to_lowercase().contains("cancel"))
+                    .unwrap_or_default() =>
+            {
+                Self::Cancelled
+            }
+            "access_denied" => Self::AccessDenied,
+            "cancelled" | "canceled" | "user_cancelled" => Self::Cancelled,
+            "expired" | "expired_token" | "session_expired" | "login_timeout" => Self::Expired,
+            _ => Self::Unknown,
+        }
+    }
 }
 
+/// An authentication session with an OpenID Connect identity provider. While this is named after DigiD, which
+/// authenticates the user for PID issuance, any identity provider that supports the same flow can be used, e.g.
+/// eHerkenning for credential issuers that issue to organisations.
 #[cfg_attr(any(test, feature = "mock"), mockall::automock)]
 pub trait DigidSession {
-    /// Start a new DigiD session by performing OpenID discovery and returning
-    /// an authorization URL that can be sent to the system browser.
-    async fn start(issuer_url: Url, client_id: String, redirect_uri: Url) -> Result<Self, DigidError>
+    /// Start a new session by performing OpenID discovery at the identity provider and
+    /// returning an authorization URL that can be sent to the system browser. The provider URL,
+    /// client ID and scopes are all taken from the provided configuration. As discovery
+    /// is performed for every session, changes to this configuration apply to the next session.
+    async fn start(config: IdentityProviderConfiguration, redirect_uri: Url) -> Result<Self, DigidError>
     where
         Self: Sized;
 
+    /// Restore a session from its persisted state. This performs OpenID discovery again,
+    /// but reuses the tokens and authorization URL that were generated when the session started.
+    async fn restore(config: IdentityProviderConfiguration, state: DigidSessionState) -> Result<Self, DigidError>
+    where
+        Self: Sized;
+
+    /// Export the state of the session, so that it can be persisted.
+    fn to_state(&self) -> DigidSessionState;
+
     /// Generate an authentication URL for the session.
     fn auth_url(&self) -> Url;
 
@@ -40,12 +108,17 @@ pub trait DigidSession {
     fn matches_received_redirect_uri(&self, received_redirect_uri: &Url) -> bool;
 
     /// Retrieve the access token from DigiD, based on the contents
-    /// of the redirect URI received.
+    /// of the redirect URI received. If DigiD also provides a refresh token,
+    /// this is retained in the session for use by [`Self::refresh_access_token()`].
     ///
-    /// Note that this consumes the [`DigidSession`], either on success or failure.
-    /// Retrying this operation is entirely possible, but most likely not something
-    /// that the UI will present to the user, instead they will have to start a new session.
-    /// For the purpose of simplification, that means that this operation is transactional
-    /// here as well.
-    async fn get_access_token(self, received_redirect_uri: &Url) -> Result<String, DigidError>;
+    /// Note that the authorization code in the redirect URI can only be used once,
+    /// so calling this again after it succeeded will fail.
+    async fn get_access_token(&mut self, received_redirect_uri: &Url) -> Result<String, DigidError>;
+
+    /// Check if the session holds a refresh token, received when retrieving the access token.
+    fn can_refresh_access_token(&self) -> bool;
+
+    /// Retrieve a new access token from DigiD using the refresh token, which allows e.g. retrying
+    /// issuance without having the user authenticate with DigiD again.
+    async fn refresh_access_token(&mut self) -> Result<String, DigidError>;
 }
@@ -1,9 +1,17 @@
+use jsonwebtoken::{jwk::JwkSet, DecodingKey, Validation};
 use openid::Options;
+use serde::Deserialize;
+use tracing::{info, warn};
 use url::Url;
 
-use crate::{pkce::PkcePair, utils::reqwest::default_reqwest_client_builder};
+use crate::{
+    pkce::PkcePair,
+    utils::{reqwest::default_reqwest_client_builder, url::url_with_query_pairs},
+};
 
-use super::openid_pkce::Client;
+use super::openid_pkce::{Client, ExtendedMetadata};
+
+const PARAM_RESPONSE_MODE: &str = "response_mode";
 
 #[derive(Debug, thiserror::Error)]
 pub enum OpenIdError {
@@ -11,77 +19,249 @@ pub enum OpenIdError {
     OpenId(#[from] openid::error::Error),
     #[error("no ID token received during authentication")]
     NoIdToken,
+    #[error("received JWT secured authorization response, which was not requested")]
+    JarmNotRequested,
+    #[error("no key found to validate JWT secured authorization response")]
+    JarmKeyNotFound,
+    #[error("could not validate JWT secured authorization response: {0}")]
+    JarmValidation(#[from] jsonwebtoken::errors::Error),
+}
+
+/// The parameters of an authorization response, which are either received
+/// as query parameters or as claims in a JWT secured authorization response.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct AuthorizationResponse {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+    pub error_description: Option<String>,
+}
+
+/// The tokens received from the issuer. The refresh token is only present if the issuer supports refreshing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessTokens {
+    pub access_token: String,
+    pub refresh_token: Option<String>,
 }
 
 /// This trait is used to isolate the [`openid`] dependency, along with
 /// [`reqwest`] on which [`openid`] depends.
 #[cfg_attr(test, mockall::automock)]
 pub trait OpenIdClient {
-    /// Perform OpenID discovery and return a client instance on success.
-    async fn discover(issuer_url: Url, client_id: String, redirect_uri: Url) -> Result<Self, OpenIdError>
+    /// Perform OpenID discovery and return a client instance on success. The client requests the provided
+    /// scopes, or all scopes supported by the issuer if none are provided.
+    async fn discover(
+        issuer_url: Url,
+        client_id: String,
+        scopes: Vec<String>,
+        redirect_uri: Url,
+    ) -> Result<Self, OpenIdError>
     where
         Self: Sized;
 
     /// Generate an authentication URL for the configured issuer This takes two
-    /// generated tokens and a generated PKCE pair as parameters.
-    fn auth_url<P>(&self, csrf_token: String, nonce: String, pkce_pair: &P) -> Url
+    /// generated tokens and a generated PKCE pair as parameters. If supported by
+    /// the issuer, this performs a Pushed Authorization Request.
+    async fn auth_url<P>(&self, csrf_token: String, nonce: String, pkce_pair: &P) -> Result<Url, OpenIdError>
     where
         P: PkcePair + 'static;
 
+    /// Validate and decode the `response` parameter of a JWT secured authorization response.
+    fn decode_authorization_response(&self, response: &str) -> Result<AuthorizationResponse, OpenIdError>;
+
     /// Use an authentication code received in the redirect URI to fetch and validate an access token
     /// from the issuer. This requires both the nonce provided when generating the authentication URL
     /// and the PKCE verifier string that matches the PKCE challenge provided in the authentication URL.
-    async fn authenticate<P>(&self, auth_code: &str, nonce: &str, pkce_pair: &P) -> Result<String, OpenIdError>
+    async fn authenticate<P>(&self, auth_code: &str, nonce: &str, pkce_pair: &P) -> Result<AccessTokens, OpenIdError>
     where
         P: PkcePair + 'static;
+
+    /// Use a refresh token received earlier to fetch a new access token from the issuer.
+    async fn refresh(&self, refresh_token: &str) -> Result<AccessTokens, OpenIdError>;
 }
 
 pub struct HttpOpenIdClient {
     openid_client: Client,
+    scopes: Vec<String>,
+    par: Option<PushedAuthorization>,
+    jarm: Option<JwtSecuredResponse>,
+}
+
+struct PushedAuthorization {
+    endpoint: Url,
+    // If this is `true`, falling back to a regular authorization request is not allowed.
+    required: bool,
+}
+
+struct JwtSecuredResponse {
+    response_mode: &'static str,
+    issuer: String,
+    jwks: JwkSet,
+}
+
+impl HttpOpenIdClient {
+    async fn discover_jarm(openid_client: &Client, metadata: &ExtendedMetadata) -> Option<JwtSecuredResponse> {
+        let response_mode = metadata.jarm_response_mode()?;
+        let issuer = metadata.issuer.clone()?;
+        let jwks_uri = metadata.jwks_uri.clone()?;
+
+        // Without the keys of the issuer JARM responses cannot be validated, so fall back to regular responses.
+        let jwks = openid_client
+            .jwks(jwks_uri)
+            .await
+            .map_err(|error| warn!("Could not fetch JWKS, not using JARM: {}", error))
+            .ok()?;
+
+        let jarm = JwtSecuredResponse {
+            response_mode,
+            issuer,
+            jwks,
+        };
+
+        Some(jarm)
+    }
 }
 
 impl OpenIdClient for HttpOpenIdClient {
-    async fn discover(issuer_url: Url, client_id: String, redirect_uri: Url) -> Result<Self, OpenIdError> {
+    async fn discover(
+        issuer_url: Url,
+        client_id: String,
+        scopes: Vec<String>,
+        redirect_uri: Url,
+    ) -> Result<Self, OpenIdError> {
         // Configure a simple `reqwest` HTTP client with a timeout.
         let http_client = default_reqwest_client_builder()
             .build()
             .expect("Could not build reqwest HTTP client");
 
         // Perform OpenID discovery at the issuer, using our modified `Client`.
-        let openid_client =
-            Client::discover_with_client(http_client, client_id, None, Some(redirect_uri.into()), issuer_url).await?;
+        let openid_client = Client::discover_with_client(
+            http_client,
+            client_id,
+            None,
+            Some(redirect_uri.into()),
+            issuer_url.clone(),
+        )
+        .await?;
+
+        // Check the provider metadata for support of PAR and JARM. When this fails,
+        // simply fall back to regular authorization requests and responses.
+        let metadata = openid_client
+            .extended_metadata(issuer_url)
+            .await
+            .unwrap_or_else(|error| {
+                warn!("Could not fetch extended provider metadata: {}", error);
+
+                ExtendedMetadata::default()
+            });
+
+        let par = metadata
+            .pushed_authorization_request_endpoint
+            .clone()
+            .map(|endpoint| PushedAuthorization {
+                endpoint,
+                required: metadata.require_pushed_authorization_requests,
+            });
+        let jarm = Self::discover_jarm(&openid_client, &metadata).await;
+
+        info!(
+            "Discovered OpenID provider, PAR supported: {}, JARM supported: {}",
+            par.is_some(),
+            jarm.is_some()
+        );
+
         // Wrap the newly created `Client` instance in our newtype.
-        let client = HttpOpenIdClient { openid_client };
+        let client = HttpOpenIdClient {
+            openid_client,
+            scopes,
+            par,
+            jarm,
+        };
 
         Ok(client)
     }
 
-    fn auth_url<P>(&self, csrf_token: String, nonce: String, pkce_pair: &P) -> Url
+    async fn auth_url<P>(&self, csrf_token: String, nonce: String, pkce_pair: &P) -> Result<Url, OpenIdError>
     where
         P: PkcePair,
     {
-        // Collect all scopes supported by the issuer (as populated during discovery)
-        // and join them together, separated by spaces.
-        let scopes_supported = self
-            .openid_client
-            .config()
-            .scopes_supported
-            .as_ref()
-            .map(|scopes| scopes.join(" "))
-            .unwrap_or_default();
+        // Use the configured scopes or, if there are none, collect all scopes supported
+        // by the issuer (as populated during discovery). Join them together, separated by spaces.
+        let scopes = if self.scopes.is_empty() {
+            self.openid_client
+                .config()
+                .scopes_supported
+                .as_ref()
+                .map(|scopes| scopes.join(" "))
+                .unwrap_or_default()
+        } else {
+            self.scopes.join(" ")
+        };
 
         // Generate the authentication URL containing these scopes and the provided tokens.
         let options = Options {
-            scope: Some(scopes_supported),
+            scope: Some(scopes),
             state: Some(csrf_token),
             nonce: Some(nonce),
             ..Default::default()
         };
 
-        self.openid_client.auth_url(&options, pkce_pair)
+        let auth_url = self.openid_client.auth_url(&options, pkce_pair);
+
+        // Request a JWT secured authorization response, if supported.
+        let auth_url = match self.jarm {
+            Some(ref jarm) => url_with_query_pairs(auth_url, &[(PARAM_RESPONSE_MODE, jarm.response_mode)]),
+            None => auth_url,
+        };
+
+        let Some(ref par) = self.par else {
+            return Ok(auth_url);
+        };
+
+        // Push the authorization request to the issuer, falling back to the
+        // regular authentication URL if allowed when this does not succeed.
+        match self
+            .openid_client
+            .pushed_auth_url(&par.endpoint, auth_url.clone())
+            .await
+        {
+            Ok(pushed_auth_url) => Ok(pushed_auth_url),
+            Err(error) if !par.required => {
+                warn!(
+                    "Pushed Authorization Request failed, falling back to regular request: {}",
+                    error
+                );
+
+                Ok(auth_url)
+            }
+            Err(error) => Err(openid::error::Error::from(error).into()),
+        }
     }
 
-    async fn authenticate<P>(&self, auth_code: &str, nonce: &str, pkce_pair: &P) -> Result<String, OpenIdError>
+    fn decode_authorization_response(&self, response: &str) -> Result<AuthorizationResponse, OpenIdError> {
+        let jarm = self.jarm.as_ref().ok_or(OpenIdError::JarmNotRequested)?;
+
+        // Find the key of the issuer used to sign the response, which
+        // may be omitted from the header if the issuer only has one.
+        let header = jsonwebtoken::decode_header(response)?;
+        let jwk = match header.kid {
+            Some(ref kid) => jarm.jwks.find(kid),
+            None => jarm.jwks.keys.first(),
+        }
+        .ok_or(OpenIdError::JarmKeyNotFound)?;
+        let key = DecodingKey::from_jwk(jwk)?;
+
+        // Only accept the algorithm specified by the key, if present.
+        let mut validation = Validation::new(jwk.common.algorithm.unwrap_or(header.alg));
+        validation.set_audience(&[self.openid_client.client_id()]);
+        validation.set_issuer(&[&jarm.issuer]);
+
+        let response = jsonwebtoken::decode::<AuthorizationResponse>(response, &key, &validation)?.claims;
+
+        Ok(response)
+    }
+
+    async fn authenticate<P>(&self, auth_code: &str, nonce: &str, pkce_pair: &P) -> Result<AccessTokens, OpenIdError>
     where
         P: PkcePair,
     {
@@ -97,10 +277,28 @@ impl OpenIdClient for HttpOpenIdClient {
             return Err(OpenIdError::NoIdToken);
         }
 
-        // Extract the resulting access token and return it.
-        let access_token = token.bearer.access_token;
+        // Extract the resulting access token and the optional refresh token and return them.
+        let tokens = AccessTokens {
+            access_token: token.bearer.access_token,
+            refresh_token: token.bearer.refresh_token,
+        };
 
-        Ok(access_token)
+        Ok(tokens)
+    }
+
+    async fn refresh(&self, refresh_token: &str) -> Result<AccessTokens, OpenIdError> {
+        let bearer = self
+            .openid_client
+            .request_refresh_token(refresh_token)
+            .await
+            .map_err(openid::error::Error::from)?;
+
+        let tokens = AccessTokens {
+            access_token: bearer.access_token,
+            refresh_token: bearer.refresh_token,
+        };
+
+        Ok(tokens)
     }
 }
 
@@ -108,16 +306,134 @@ impl OpenIdClient for HttpOpenIdClient {
 /// HTTP responses are mocked using the [`wiremock`] crate.
 #[cfg(test)]
 mod tests {
-    use serde_json::json;
+    use assert_matches::assert_matches;
+    use base64::prelude::*;
+    use jsonwebtoken::{Algorithm, Header};
+    use p256::{ecdsa::SigningKey, elliptic_curve::rand_core::OsRng};
+    use serde::Serialize;
+    use serde_json::{json, Value};
+    use wallet_common::jwt::Jwt;
     use wiremock::{
-        matchers::{method, path},
+        matchers::{body_string_contains, method, path},
         Mock, MockServer, ResponseTemplate,
     };
 
-    use crate::pkce::MockPkcePair;
+    use crate::{pkce::MockPkcePair, utils::url::url_find_first_query_value};
 
     use super::*;
 
+    const CLIENT_ID: &str = "client-1";
+    const KEY_ID: &str = "key-1";
+    const REQUEST_URI: &str = "urn:ietf:params:oauth:request_uri:request-1";
+
+    #[derive(Serialize)]
+    struct JarmClaims {
+        iss: String,
+        aud: String,
+        exp: u64,
+        code: String,
+        state: String,
+    }
+
+    fn mock_pkce_pair() -> MockPkcePair {
+        let mut pkce_pair = MockPkcePair::new();
+
+        pkce_pair
+            .expect_code_challenge()
+            .return_const("pkcecodechallenge".to_string());
+
+        pkce_pair
+    }
+
+    fn jwk_for_key(key: &SigningKey) -> Value {
+        let point = key.verifying_key().to_encoded_point(false);
+
+        json!({
+            "kty": "EC",
+            "crv": "P-256",
+            "use": "sig",
+            "alg": "ES256",
+            "kid": KEY_ID,
+            "x": BASE64_URL_SAFE_NO_PAD.encode(point.x().unwrap()),
+            "y": BASE64_URL_SAFE_NO_PAD.encode(point.y().unwrap()),
+        })
+    }
+
+    /// Start a mock server that supports both PAR and JARM, using the provided response for the PAR endpoint.
+    async fn start_par_jarm_server(
+        par_required: bool,
+        par_response: ResponseTemplate,
+        key: &SigningKey,
+    ) -> (MockServer, Url) {
+        let server = MockServer::start().await;
+        let server_url = Url::parse(&server.uri()).unwrap();
+
+        // The configuration is fetched both by the `openid` crate and to discover PAR and JARM support.
+        Mock::given(method("GET"))
+            .and(path("/.well-known/openid-configuration"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "issuer": server_url,
+                "authorization_endpoint": server_url.join("/oauth2/auth").unwrap(),
+                "token_endpoint": server_url.join("/oauth2/token").unwrap(),
+                "jwks_uri": server_url.join("/.well-known/jwks.json").unwrap(),
+                "response_types_supported": ["code"],
+                "response_modes_supported": ["query", "query.jwt"],
+                "pushed_authorization_request_endpoint": server_url.join("/oauth2/par").unwrap(),
+                "require_pushed_authorization_requests": par_required
+            })))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/.well-known/jwks.json"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "keys": [jwk_for_key(key)]
+            })))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path("/oauth2/par"))
+            .and(body_string_contains("response_mode=query.jwt"))
+            .and(body_string_contains("code_challenge=pkcecodechallenge"))
+            .respond_with(par_response)
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        (server, server_url)
+    }
+
+    async fn discover_client(server_url: &Url) -> HttpOpenIdClient {
+        HttpOpenIdClient::discover(
+            server_url.clone(),
+            CLIENT_ID.to_string(),
+            vec![],
+            Url::parse("http://example-client.com/oauth2/callback").unwrap(),
+        )
+        .await
+        .expect("Could not perform OpenID discovery")
+    }
+
+    async fn sign_jarm_response(key: &SigningKey, iss: String, aud: &str) -> String {
+        let claims = JarmClaims {
+            iss,
+            aud: aud.to_string(),
+            exp: jsonwebtoken::get_current_timestamp() + 60,
+            code: "authcode".to_string(),
+            state: "csrftoken".to_string(),
+        };
+        let header = Header {
+            alg: Algorithm::ES256,
+            kid: Some(KEY_ID.to_string()),
+            ..Default::default()
+        };
+
+        Jwt::sign(&claims, &header, key).await.unwrap().0
+    }
+
     #[tokio::test]
     async fn test_http_open_id_client() {
         let server = MockServer::start().await;
@@ -133,7 +449,7 @@ mod tests {
                 "jwks_uri": server_url.join("/.well-known/jwks.json").unwrap(),
                 "response_types_supported": ["code", "id_token", "token id_token"]
             })))
-            .expect(1)
+            .expect(2)
             .mount(&server)
             .await;
 
@@ -154,9 +470,10 @@ mod tests {
         let nonce = "thisisthenonce";
 
         // Perform OpenID discovery
-        let client = HttpOpenIdClient::discover(server_url.clone(), client_id.to_string(), redirect_uri.clone())
-            .await
-            .expect("Could not perform OpenID discovery");
+        let client =
+            HttpOpenIdClient::discover(server_url.clone(), client_id.to_string(), vec![], redirect_uri.clone())
+                .await
+                .expect("Could not perform OpenID discovery");
         let pkce_pair = {
             let mut pkce_pair = MockPkcePair::new();
 
@@ -168,7 +485,10 @@ mod tests {
         };
 
         // Generate authentication URL
-        let url = client.auth_url(csrf_token.to_string(), nonce.to_string(), &pkce_pair);
+        let url = client
+            .auth_url(csrf_token.to_string(), nonce.to_string(), &pkce_pair)
+            .await
+            .expect("Could not generate authentication URL");
 
         assert_eq!(
             url,
@@ -184,4 +504,156 @@ mod tests {
         // TODO: Add test for the authenticate() method by mocking an authentication
         //       response that can actually be verified by the client.
     }
+
+    #[tokio::test]
+    async fn test_http_open_id_client_par_jarm() {
+        let key = SigningKey::random(&mut OsRng);
+        let par_response = ResponseTemplate::new(201).set_body_json(json!({
+            "request_uri": REQUEST_URI,
+            "expires_in": 60
+        }));
+        let (_server, server_url) = start_par_jarm_server(true, par_response, &key).await;
+
+        let client = discover_client(&server_url).await;
+
+        // The authentication URL should only contain the client ID and the request URI.
+        let url = client
+            .auth_url("csrftoken".to_string(), "nonce".to_string(), &mock_pkce_pair())
+            .await
+            .expect("Could not generate authentication URL");
+
+        assert_eq!(
+            url,
+            url_with_query_pairs(
+                server_url.join("/oauth2/auth").unwrap(),
+                &[("client_id", CLIENT_ID), ("request_uri", REQUEST_URI)]
+            )
+        );
+
+        // A JARM response signed by the issuer should be accepted.
+        let response = sign_jarm_response(&key, server_url.to_string(), CLIENT_ID).await;
+        let authorization_response = client
+            .decode_authorization_response(&response)
+            .expect("Could not decode JARM response");
+
+        assert_eq!(
+            authorization_response,
+            AuthorizationResponse {
+                code: Some("authcode".to_string()),
+                state: Some("csrftoken".to_string()),
+                ..Default::default()
+            }
+        );
+
+        // A JARM response intended for another client should be rejected.
+        let response = sign_jarm_response(&key, server_url.to_string(), "other-client").await;
+        let error = client
+            .decode_authorization_response(&response)
+            .expect_err("Decoding JARM response should have failed");
+
+        assert_matches!(error, OpenIdError::JarmValidation(_));
+
+        // A JARM response signed by another key should be rejected.
+        let response = sign_jarm_response(&SigningKey::random(&mut OsRng), server_url.to_string(), CLIENT_ID).await;
+        let error = client
+            .decode_authorization_response(&response)
+            .expect_err("Decoding JARM response should have failed");
+
+        assert_matches!(error, OpenIdError::JarmValidation(_));
+    }
+
+    #[tokio::test]
+    async fn test_http_open_id_client_par_fallback() {
+        let key = SigningKey::random(&mut OsRng);
+        let par_response = ResponseTemplate::new(400).set_body_json(json!({
+            "error": "invalid_request"
+        }));
+        let (_server, server_url) = start_par_jarm_server(false, par_response, &key).await;
+
+        let client = discover_client(&server_url).await;
+
+        // As PAR is not required, the regular authentication URL should be returned.
+        let url = client
+            .auth_url("csrftoken".to_string(), "nonce".to_string(), &mock_pkce_pair())
+            .await
+            .expect("Could not generate authentication URL");
+
+        assert_eq!(url.path(), "/oauth2/auth");
+        assert_eq!(
+            url_find_first_query_value(&url, "response_mode").as_deref(),
+            Some("query.jwt")
+        );
+        assert_eq!(url_find_first_query_value(&url, "state").as_deref(), Some("csrftoken"));
+    }
+
+    #[tokio::test]
+    async fn test_http_open_id_client_par_required_error() {
+        let key = SigningKey::random(&mut OsRng);
+        let par_response = ResponseTemplate::new(400).set_body_json(json!({
+            "error": "invalid_request"
+        }));
+        let (_server, server_url) = start_par_jarm_server(true, par_response, &key).await;
+
+        let client = discover_client(&server_url).await;
+
+        // As PAR is required, the error should be returned.
+        let error = client
+            .auth_url("csrftoken".to_string(), "nonce".to_string(), &mock_pkce_pair())
+            .await
+            .expect_err("Generating authentication URL should have failed");
+
+        assert_matches!(error, OpenIdError::OpenId(_));
+    }
+
+    #[tokio::test]
+    async fn test_http_open_id_client_refresh() {
+        let server = MockServer::start().await;
+        let server_url = Url::parse(&server.uri()).unwrap();
+
+        Mock::given(method("GET"))
+            .and(path("/.well-known/openid-configuration"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "issuer": server_url,
+                "authorization_endpoint": server_url.join("/oauth2/auth").unwrap(),
+                "token_endpoint": server_url.join("/oauth2/token").unwrap(),
+                "jwks_uri": server_url.join("/.well-known/jwks.json").unwrap(),
+                "response_types_supported": ["code"]
+            })))
+            .mount(&server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/.well-known/jwks.json"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "keys": []
+            })))
+            .mount(&server)
+            .await;
+
+        // Mock the token endpoint, which should only accept the refresh token grant.
+        Mock::given(method("POST"))
+            .and(path("/oauth2/token"))
+            .and(body_string_contains("grant_type=refresh_token"))
+            .and(body_string_contains("refresh_token=refreshtoken"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "access_token": "newaccesstoken",
+                "token_type": "Bearer",
+                "refresh_token": "newrefreshtoken"
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = discover_client(&server_url).await;
+
+        let tokens = client.refresh("refreshtoken").await.expect("Could not refresh token");
+
+        assert_eq!(
+            tokens,
+            AccessTokens {
+                access_token: "newaccesstoken".to_string(),
+                refresh_token: Some("newrefreshtoken".to_string()),
+            }
+        );
+    }
 }
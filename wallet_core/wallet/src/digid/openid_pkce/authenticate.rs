@@ -65,6 +65,23 @@ impl Client {
         Ok(token)
     }
 
+    /// Use a refresh token to request a new access token, as described in section 6 of RFC 6749.
+    /// Note that the response may or may not contain a new refresh token.
+    pub async fn request_refresh_token(&self, refresh_token: &str) -> Result<Bearer, ClientError> {
+        let body = {
+            let mut body = Serializer::new(String::new());
+            body.append_pair("grant_type", "refresh_token");
+            body.append_pair("refresh_token", refresh_token);
+            body.append_pair("client_id", &self.0.client_id);
+
+            body.finish()
+        };
+
+        let json = self.post_token_jwt(body).await?;
+        let token: Bearer = serde_json::from_value(json)?;
+        Ok(token)
+    }
+
     /// This copies `openid::Client.post_token()` and changes it to
     /// not use basic auth and receive JSON of content type "application/jwt".
     async fn post_token_jwt(&self, body: String) -> Result<Value, ClientError> {
@@ -0,0 +1,89 @@
+use jsonwebtoken::jwk::JwkSet;
+use openid::error::{ClientError, Error};
+use serde::{de::DeserializeOwned, Deserialize};
+use url::Url;
+
+use super::Client;
+
+const RESPONSE_MODE_QUERY_JWT: &str = "query.jwt";
+const RESPONSE_MODE_JWT: &str = "jwt";
+
+/// Provider metadata that is not parsed by the `openid` crate during discovery, namely
+/// the fields for Pushed Authorization Requests (RFC 9126) and JWT Secured Authorization
+/// Response Mode (JARM).
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ExtendedMetadata {
+    pub issuer: Option<String>,
+    pub jwks_uri: Option<Url>,
+    pub pushed_authorization_request_endpoint: Option<Url>,
+    #[serde(default)]
+    pub require_pushed_authorization_requests: bool,
+    #[serde(default)]
+    pub response_modes_supported: Vec<String>,
+}
+
+impl ExtendedMetadata {
+    /// Returns the JARM response mode to request, if supported by the provider.
+    /// Note that for the authorization code flow "jwt" is equivalent to "query.jwt".
+    pub fn jarm_response_mode(&self) -> Option<&'static str> {
+        [RESPONSE_MODE_QUERY_JWT, RESPONSE_MODE_JWT]
+            .into_iter()
+            .find(|mode| self.response_modes_supported.iter().any(|supported| supported == mode))
+    }
+}
+
+impl Client {
+    /// Fetch the provider metadata a second time, in order to parse the fields
+    /// that are not supported by `openid::Config`.
+    pub async fn extended_metadata(&self, mut issuer_url: Url) -> Result<ExtendedMetadata, Error> {
+        issuer_url
+            .path_segments_mut()
+            .map_err(|_| Error::CannotBeABase)?
+            .pop_if_empty()
+            .extend(&[".well-known", "openid-configuration"]);
+
+        let metadata = self.get_json(issuer_url).await?;
+
+        Ok(metadata)
+    }
+
+    /// Fetch the JSON Web Key Set of the provider, which is used to validate JARM responses.
+    pub async fn jwks(&self, jwks_uri: Url) -> Result<JwkSet, Error> {
+        let jwks = self.get_json(jwks_uri).await?;
+
+        Ok(jwks)
+    }
+
+    async fn get_json<T: DeserializeOwned>(&self, url: Url) -> Result<T, ClientError> {
+        let json = self
+            .0
+            .http_client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use serde_json::json;
+
+    use super::*;
+
+    #[rstest]
+    #[case(json!({}), None)]
+    #[case(json!({"response_modes_supported": ["query", "fragment"]}), None)]
+    #[case(json!({"response_modes_supported": ["query", "jwt"]}), Some("jwt"))]
+    #[case(json!({"response_modes_supported": ["jwt", "query.jwt"]}), Some("query.jwt"))]
+    fn test_extended_metadata_jarm_response_mode(#[case] metadata: serde_json::Value, #[case] expected: Option<&str>) {
+        let metadata = serde_json::from_value::<ExtendedMetadata>(metadata).unwrap();
+
+        assert_eq!(metadata.jarm_response_mode(), expected);
+    }
+}
@@ -1,10 +1,14 @@
 mod auth_url;
 mod authenticate;
+mod metadata;
+mod par;
 mod validate_fix;
 
 use openid::{error::Error, Config};
 use url::Url;
 
+pub use self::metadata::ExtendedMetadata;
+
 /// This wraps `openid::Client` in order to add some enhancements and fixes.
 pub struct Client(openid::Client);
 
@@ -26,4 +30,8 @@ impl Client {
     pub fn config(&self) -> &Config {
         self.0.config()
     }
+
+    pub fn client_id(&self) -> &str {
+        &self.0.client_id
+    }
 }
@@ -0,0 +1,61 @@
+use http::header::{ACCEPT, CONTENT_TYPE};
+use mime::{APPLICATION_JSON, APPLICATION_WWW_FORM_URLENCODED};
+use openid::{error::ClientError, OAuth2Error};
+use serde::Deserialize;
+use serde_json::Value;
+use url::Url;
+
+use crate::utils::url::url_with_query_pairs;
+
+use super::Client;
+
+const PARAM_CLIENT_ID: &str = "client_id";
+const PARAM_REQUEST_URI: &str = "request_uri";
+
+#[derive(Debug, Deserialize)]
+struct PushedAuthorizationResponse {
+    request_uri: String,
+}
+
+impl Client {
+    /// Perform a Pushed Authorization Request (RFC 9126) by sending the query parameters of a regular
+    /// authentication URL to the PAR endpoint. This returns a new authentication URL that only contains
+    /// the client ID and the `request_uri` received from the provider.
+    pub async fn pushed_auth_url(&self, par_endpoint: &Url, auth_url: Url) -> Result<Url, ClientError> {
+        // The query of the authentication URL is already form encoded, so it can be used as body as is.
+        let body = auth_url.query().unwrap_or_default().to_string();
+
+        let json = self
+            .0
+            .http_client
+            .post(par_endpoint.clone())
+            .header(ACCEPT, APPLICATION_JSON.as_ref())
+            .header(CONTENT_TYPE, APPLICATION_WWW_FORM_URLENCODED.as_ref())
+            .body(body)
+            .send()
+            .await?
+            .json::<Value>()
+            .await?;
+
+        let error: Result<OAuth2Error, _> = serde_json::from_value(json.clone());
+
+        if let Ok(error) = error {
+            return Err(ClientError::from(error));
+        }
+
+        let response: PushedAuthorizationResponse = serde_json::from_value(json)?;
+
+        let mut url = auth_url;
+        url.set_query(None);
+
+        let url = url_with_query_pairs(
+            url,
+            &[
+                (PARAM_CLIENT_ID, self.client_id()),
+                (PARAM_REQUEST_URI, &response.request_uri),
+            ],
+        );
+
+        Ok(url)
+    }
+}
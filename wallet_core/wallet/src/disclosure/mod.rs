@@ -16,6 +16,8 @@ use nl_wallet_mdoc::{
     },
 };
 
+use wallet_common::correlation::CorrelationTracker;
+
 use crate::utils;
 
 pub use self::uri::{DisclosureUriData, DisclosureUriError};
@@ -37,6 +39,7 @@ pub trait MdocDisclosureSession<D> {
         disclosure_uri: DisclosureUriData,
         mdoc_data_source: &D,
         trust_anchors: &[TrustAnchor<'a>],
+        correlation: &CorrelationTracker,
     ) -> nl_wallet_mdoc::Result<Self>
     where
         Self: Sized;
@@ -75,13 +78,14 @@ where
         disclosure_uri: DisclosureUriData,
         mdoc_data_source: &D,
         trust_anchors: &[TrustAnchor<'a>],
+        correlation: &CorrelationTracker,
     ) -> nl_wallet_mdoc::Result<Self> {
         let http_client = utils::reqwest::default_reqwest_client_builder()
             .build()
             .expect("Could not build reqwest HTTP client");
 
         Self::start(
-            CborHttpClient(http_client),
+            CborHttpClient::new(http_client, correlation.clone()),
             &disclosure_uri.reader_engagement_bytes,
             disclosure_uri.return_url,
             disclosure_uri.session_type,
@@ -263,6 +267,7 @@ mod mock {
             disclosure_uri: DisclosureUriData,
             _mdoc_data_source: &D,
             _trust_anchors: &[TrustAnchor<'a>],
+            _correlation: &CorrelationTracker,
         ) -> nl_wallet_mdoc::Result<Self> {
             if let Some(error) = NEXT_START_ERROR.lock().unwrap().take() {
                 return Err(error);
@@ -0,0 +1,359 @@
+use std::collections::HashMap;
+
+use chrono::{Datelike, NaiveDate};
+use once_cell::sync::Lazy;
+
+use super::{
+    mapping::MDOC_DOCUMENT_MAPPING, mdoc::AttributeValueType, AttributeLabels, AttributeValue, GenderAttributeValue,
+};
+
+const MDL_DOCTYPE: &str = "org.iso.18013.5.1.mDL";
+const MDL_NAMESPACE: &str = "org.iso.18013.5.1";
+
+/// The language that is used when no labels or formatting are available for the requested locale.
+pub const FALLBACK_LANGUAGE: &str = "en";
+
+const SUPPORTED_LANGUAGES: [&str; 2] = ["en", "nl"];
+
+const MONTHS_EN: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+const MONTHS_NL: [&str; 12] = [
+    "januari",
+    "februari",
+    "maart",
+    "april",
+    "mei",
+    "juni",
+    "juli",
+    "augustus",
+    "september",
+    "oktober",
+    "november",
+    "december",
+];
+
+/// The vehicle categories of ISO/IEC 18013-5 driving privileges, with their English and Dutch descriptions.
+const DRIVING_PRIVILEGE_CODES: [(&str, &str, &str); 15] = [
+    ("AM", "moped", "bromfiets"),
+    ("A1", "light motorcycle", "lichte motorfiets"),
+    ("A2", "medium motorcycle", "middelzware motorfiets"),
+    ("A", "motorcycle", "motorfiets"),
+    ("B", "car", "personenauto"),
+    ("BE", "car with trailer", "personenauto met aanhangwagen"),
+    ("C1", "medium truck", "middelzware vrachtauto"),
+    (
+        "C1E",
+        "medium truck with trailer",
+        "middelzware vrachtauto met aanhangwagen",
+    ),
+    ("C", "truck", "vrachtauto"),
+    ("CE", "truck with trailer", "vrachtauto met aanhangwagen"),
+    ("D1", "minibus", "kleine bus"),
+    ("D1E", "minibus with trailer", "kleine bus met aanhangwagen"),
+    ("D", "bus", "bus"),
+    ("DE", "bus with trailer", "bus met aanhangwagen"),
+    ("T", "tractor", "trekker"),
+];
+
+/// Determine the language to display attributes in from a locale such as "nl" or "nl-NL",
+/// falling back to [`FALLBACK_LANGUAGE`] if the language is not supported.
+fn display_language(locale: &str) -> &'static str {
+    let language = locale.split(['-', '_']).next().unwrap_or_default().to_lowercase();
+
+    SUPPORTED_LANGUAGES
+        .into_iter()
+        .find(|supported| *supported == language)
+        .unwrap_or(FALLBACK_LANGUAGE)
+}
+
+/// The languages to try when displaying text for a locale, in order of preference: the language determined by
+/// [`display_language()`], then [`FALLBACK_LANGUAGE`] and then any of the supported languages.
+pub fn display_languages(locale: &str) -> impl Iterator<Item = &'static str> {
+    [display_language(locale), FALLBACK_LANGUAGE]
+        .into_iter()
+        .chain(SUPPORTED_LANGUAGES)
+}
+
+/// Describes how the value of an attribute should be formatted for display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttributeFormatter {
+    Text,
+    Boolean,
+    Date,
+    Gender,
+    /// A list of vehicle category codes, separated by commas or whitespace.
+    DrivingPrivileges,
+}
+
+impl From<AttributeValueType> for AttributeFormatter {
+    fn from(value: AttributeValueType) -> Self {
+        match value {
+            AttributeValueType::String => Self::Text,
+            AttributeValueType::Bool => Self::Boolean,
+            AttributeValueType::Date => Self::Date,
+            AttributeValueType::Gender => Self::Gender,
+        }
+    }
+}
+
+impl AttributeFormatter {
+    /// Format the value for the specified locale. If the value does not have the type this formatter
+    /// expects, it is formatted based on its own type instead.
+    pub fn format(&self, value: &AttributeValue, locale: &str) -> String {
+        let language = display_language(locale);
+
+        match (self, value) {
+            (Self::DrivingPrivileges, AttributeValue::String(codes)) => format_driving_privileges(codes, language),
+            (_, AttributeValue::String(value)) => value.clone(),
+            (_, AttributeValue::Boolean(value)) => format_boolean(*value, language).to_string(),
+            (_, AttributeValue::Date(value)) => format_date(value, language),
+            (_, AttributeValue::Gender(value)) => format_gender(*value, language).to_string(),
+        }
+    }
+}
+
+fn format_boolean(value: bool, language: &str) -> &'static str {
+    match (value, language) {
+        (true, "nl") => "Ja",
+        (false, "nl") => "Nee",
+        (true, _) => "Yes",
+        (false, _) => "No",
+    }
+}
+
+fn format_date(value: &NaiveDate, language: &str) -> String {
+    let months = match language {
+        "nl" => MONTHS_NL,
+        _ => MONTHS_EN,
+    };
+
+    format!("{} {} {}", value.day(), months[value.month0() as usize], value.year())
+}
+
+/// Format the month and year of a date for the specified locale, e.g. "May 1997" or "mei 1997".
+pub fn format_year_month(value: &NaiveDate, locale: &str) -> String {
+    let months = match display_language(locale) {
+        "nl" => MONTHS_NL,
+        _ => MONTHS_EN,
+    };
+
+    format!("{} {}", months[value.month0() as usize], value.year())
+}
+
+fn format_gender(value: GenderAttributeValue, language: &str) -> &'static str {
+    match (value, language) {
+        (GenderAttributeValue::Unknown, "nl") => "Onbekend",
+        (GenderAttributeValue::Male, "nl") => "Man",
+        (GenderAttributeValue::Female, "nl") => "Vrouw",
+        (GenderAttributeValue::NotApplicable, "nl") => "Niet van toepassing",
+        (GenderAttributeValue::Unknown, _) => "Unknown",
+        (GenderAttributeValue::Male, _) => "Male",
+        (GenderAttributeValue::Female, _) => "Female",
+        (GenderAttributeValue::NotApplicable, _) => "Not applicable",
+    }
+}
+
+fn format_driving_privileges(codes: &str, language: &str) -> String {
+    codes
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|code| !code.is_empty())
+        .map(|code| {
+            DRIVING_PRIVILEGE_CODES
+                .iter()
+                .find(|(known_code, _, _)| known_code.eq_ignore_ascii_case(code))
+                .map(|(known_code, en, nl)| {
+                    let description = match language {
+                        "nl" => nl,
+                        _ => en,
+                    };
+
+                    format!("{} ({})", known_code, description)
+                })
+                // Codes that are not known are displayed as is.
+                .unwrap_or_else(|| code.to_string())
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The localized label and value formatter for a single attribute.
+#[derive(Debug, Clone)]
+pub struct AttributeDisplay {
+    pub labels: AttributeLabels,
+    pub formatter: AttributeFormatter,
+}
+
+impl AttributeDisplay {
+    /// Return the label for the specified locale, falling back to the languages returned by [`display_languages()`].
+    pub fn label(&self, locale: &str) -> Option<&'static str> {
+        display_languages(locale)
+            .find_map(|language| self.labels.get(language))
+            .copied()
+    }
+
+    pub fn format(&self, value: &AttributeValue, locale: &str) -> String {
+        self.formatter.format(value, locale)
+    }
+}
+
+type DisplayKey = (&'static str, &'static str, &'static str);
+
+/// Maps attributes, identified by their doc type, namespace and data element identifier,
+/// to their localized labels and value formatters. The maps are nested per part of the identifier,
+/// so that they can be queried using non-static string slices.
+#[derive(Debug)]
+pub struct AttributeDisplayRegistry(
+    HashMap<&'static str, HashMap<&'static str, HashMap<&'static str, AttributeDisplay>>>,
+);
+
+impl AttributeDisplayRegistry {
+    pub fn get(&self, doc_type: &str, name_space: &str, name: &str) -> Option<&AttributeDisplay> {
+        self.0.get(doc_type)?.get(name_space)?.get(name)
+    }
+
+    /// Look up an attribute by the key it has in a [`super::Document`], which does not contain the namespace.
+    pub fn get_by_key(&self, doc_type: &str, key: &str) -> Option<&AttributeDisplay> {
+        self.0
+            .get(doc_type)?
+            .values()
+            .find_map(|attributes| attributes.get(key))
+    }
+
+    /// Format an attribute value for the specified locale. Attributes that are not present in the registry
+    /// are formatted based on the type of their value.
+    pub fn format_value(&self, doc_type: &str, key: &str, value: &AttributeValue, locale: &str) -> String {
+        self.get_by_key(doc_type, key)
+            .map(|display| display.formatter)
+            .unwrap_or(AttributeFormatter::Text)
+            .format(value, locale)
+    }
+}
+
+impl FromIterator<(DisplayKey, AttributeDisplay)> for AttributeDisplayRegistry {
+    fn from_iter<I: IntoIterator<Item = (DisplayKey, AttributeDisplay)>>(iter: I) -> Self {
+        let mut entries: HashMap<_, HashMap<_, HashMap<_, _>>> = HashMap::new();
+
+        for ((doc_type, name_space, name), display) in iter {
+            entries
+                .entry(doc_type)
+                .or_default()
+                .entry(name_space)
+                .or_default()
+                .insert(name, display);
+        }
+
+        AttributeDisplayRegistry(entries)
+    }
+}
+
+pub static ATTRIBUTE_DISPLAY_REGISTRY: Lazy<AttributeDisplayRegistry> = Lazy::new(|| {
+    let mapped_attributes = MDOC_DOCUMENT_MAPPING.iter().flat_map(|(doc_type, mapping)| {
+        mapping.iter().map(|((name_space, name), value_mapping)| {
+            (
+                (*doc_type, *name_space, *name),
+                AttributeDisplay {
+                    labels: value_mapping.key_labels.clone(),
+                    formatter: value_mapping.value_type.into(),
+                },
+            )
+        })
+    });
+
+    let mdl_attributes = [(
+        (MDL_DOCTYPE, MDL_NAMESPACE, "driving_privileges"),
+        AttributeDisplay {
+            labels: HashMap::from([("en", "Driving privileges"), ("nl", "Rijbevoegdheden")]),
+            formatter: AttributeFormatter::DrivingPrivileges,
+        },
+    )];
+
+    AttributeDisplayRegistry::from_iter(mapped_attributes.chain(mdl_attributes))
+});
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::{super::PID_DOCTYPE, *};
+
+    #[rstest]
+    #[case("nl", "Geboortedatum", "10 mei 1997")]
+    #[case("nl-NL", "Geboortedatum", "10 mei 1997")]
+    #[case("en", "Birth date", "10 May 1997")]
+    #[case("fr", "Birth date", "10 May 1997")]
+    fn test_attribute_display_date(#[case] locale: &str, #[case] expected_label: &str, #[case] expected_value: &str) {
+        let display = ATTRIBUTE_DISPLAY_REGISTRY
+            .get(PID_DOCTYPE, PID_DOCTYPE, "birth_date")
+            .expect("birth date should be in registry");
+        let value = AttributeValue::Date(NaiveDate::from_ymd_opt(1997, 5, 10).unwrap());
+
+        assert_eq!(display.label(locale), Some(expected_label));
+        assert_eq!(display.format(&value, locale), expected_value);
+    }
+
+    #[test]
+    fn test_display_languages() {
+        assert_eq!(display_languages("nl-NL").next(), Some("nl"));
+        assert_eq!(display_languages("fr").next(), Some(FALLBACK_LANGUAGE));
+        assert!(display_languages("en").any(|language| language == "nl"));
+    }
+
+    #[test]
+    fn test_format_year_month() {
+        let date = NaiveDate::from_ymd_opt(1997, 5, 10).unwrap();
+
+        assert_eq!(format_year_month(&date, "nl-NL"), "mei 1997");
+        assert_eq!(format_year_month(&date, "en"), "May 1997");
+    }
+
+    #[test]
+    fn test_attribute_display_boolean_and_gender() {
+        assert_eq!(
+            ATTRIBUTE_DISPLAY_REGISTRY.format_value(PID_DOCTYPE, "age_over_18", &AttributeValue::Boolean(true), "nl"),
+            "Ja"
+        );
+        assert_eq!(
+            ATTRIBUTE_DISPLAY_REGISTRY.format_value(
+                PID_DOCTYPE,
+                "gender",
+                &AttributeValue::Gender(GenderAttributeValue::Female),
+                "en"
+            ),
+            "Female"
+        );
+    }
+
+    #[test]
+    fn test_attribute_display_driving_privileges() {
+        let display = ATTRIBUTE_DISPLAY_REGISTRY
+            .get(MDL_DOCTYPE, MDL_NAMESPACE, "driving_privileges")
+            .expect("driving privileges should be in registry");
+        let value = AttributeValue::String("AM, b,X".to_string());
+
+        assert_eq!(display.format(&value, "en"), "AM (moped), B (car), X");
+        assert_eq!(display.format(&value, "nl"), "AM (bromfiets), B (personenauto), X");
+    }
+
+    #[test]
+    fn test_attribute_display_unknown_attribute() {
+        let value = AttributeValue::String("foo".to_string());
+
+        assert!(ATTRIBUTE_DISPLAY_REGISTRY.get_by_key(PID_DOCTYPE, "foo").is_none());
+        assert_eq!(
+            ATTRIBUTE_DISPLAY_REGISTRY.format_value(PID_DOCTYPE, "foo", &value, "nl"),
+            "foo"
+        );
+    }
+}
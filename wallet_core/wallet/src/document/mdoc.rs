@@ -1,16 +1,18 @@
-use chrono::NaiveDate;
+use chrono::{DateTime, NaiveDate, ParseError, Utc};
 use ciborium::value::Integer;
 use indexmap::IndexMap;
 
 use nl_wallet_mdoc::{
     basic_sa_ext::{Entry, UnsignedMdoc},
+    holder::Mdoc,
     identifiers::AttributeIdentifier,
-    DataElementIdentifier, DataElementValue, NameSpace,
+    utils::x509::{CertificateError, CertificateType},
+    DataElementIdentifier, DataElementValue, NameSpace, Tdate, ValidityInfo,
 };
 
 use super::{
     mapping::{AttributeMapping, DataElementValueMapping, MappingDocType, MDOC_DOCUMENT_MAPPING},
-    Attribute, AttributeValue, DisclosureDocument, Document, DocumentAttributes, DocumentPersistence,
+    Attribute, AttributeValue, DisclosureDocument, Document, DocumentAttributes, DocumentPersistence, DocumentValidity,
     GenderAttributeValue, MissingDisclosureAttributes,
 };
 
@@ -42,6 +44,12 @@ pub enum DocumentMdocError {
         name: DataElementIdentifier,
         value: Option<DataElementValue>,
     },
+    #[error("could not read mdoc: {0}")]
+    Mdoc(#[from] nl_wallet_mdoc::Error),
+    #[error("could not parse mdoc validity: {0}")]
+    Validity(#[from] ParseError),
+    #[error("could not read issuer registration from certificate: {0}")]
+    Certificate(#[from] CertificateError),
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -154,7 +162,13 @@ impl TryFrom<UnsignedMdoc> for Document {
     type Error = DocumentMdocError;
 
     fn try_from(value: UnsignedMdoc) -> Result<Self, Self::Error> {
-        Document::from_mdoc_attributes(DocumentPersistence::InMemory, &value.doc_type, value.attributes)
+        let validity = DocumentValidity::from_tdates(&value.valid_from, &value.valid_until)?;
+        let document = Document {
+            validity: Some(validity),
+            ..Document::from_mdoc_attributes(DocumentPersistence::InMemory, &value.doc_type, value.attributes)?
+        };
+
+        Ok(document)
     }
 }
 
@@ -170,12 +184,50 @@ impl Document {
             persistence,
             doc_type,
             attributes: document_attributes,
+            validity: None,
+            issuer_registration: None,
+        };
+
+        Ok(document)
+    }
+
+    /// Create a [`Document`] from an issued [`Mdoc`], which includes its validity and the issuer registration.
+    pub(crate) fn from_mdoc(persistence: DocumentPersistence, mdoc: &Mdoc) -> Result<Self, DocumentMdocError> {
+        let validity = DocumentValidity::try_from(&mdoc.validity_info()?)?;
+        let issuer_registration = match CertificateType::from_certificate(&mdoc.issuer_certificate()?)? {
+            CertificateType::Mdl(issuer_registration) => issuer_registration,
+            CertificateType::ReaderAuth(_) => None,
+        };
+
+        let document = Document {
+            validity: Some(validity),
+            issuer_registration,
+            ..Document::from_mdoc_attributes(persistence, &mdoc.doc_type, mdoc.attributes())?
         };
 
         Ok(document)
     }
 }
 
+impl DocumentValidity {
+    fn from_tdates(valid_from: &Tdate, valid_until: &Tdate) -> Result<Self, ParseError> {
+        let validity = DocumentValidity {
+            valid_from: DateTime::<Utc>::try_from(valid_from)?,
+            valid_until: DateTime::<Utc>::try_from(valid_until)?,
+        };
+
+        Ok(validity)
+    }
+}
+
+impl TryFrom<&ValidityInfo> for DocumentValidity {
+    type Error = ParseError;
+
+    fn try_from(value: &ValidityInfo) -> Result<Self, Self::Error> {
+        DocumentValidity::from_tdates(&value.valid_from, &value.valid_until)
+    }
+}
+
 impl TryFrom<(DataElementValue, &DataElementValueMapping)> for Attribute {
     type Error = DataElementValue;
 
@@ -258,7 +310,7 @@ impl MissingDisclosureAttributes {
                             .or_default()
                             .insert(value_mapping.key, value_mapping.key_labels.clone());
 
-                        Ok(attributes_by_doc_type)
+                        Ok::<_, DocumentMdocError>(attributes_by_doc_type)
                     }
                 })?;
 
@@ -442,10 +494,21 @@ pub mod tests {
     fn test_minimal_unsigned_mdoc_to_document_mapping() {
         let unsigned_mdoc = create_minimal_unsigned_pid_mdoc();
 
+        let valid_from = DateTime::<Utc>::try_from(&unsigned_mdoc.valid_from).unwrap();
+        let valid_until = DateTime::<Utc>::try_from(&unsigned_mdoc.valid_until).unwrap();
+
         let document = Document::try_from(unsigned_mdoc).expect("Could not convert minimal mdoc to document");
 
         assert_matches!(document.persistence, DocumentPersistence::InMemory);
         assert_eq!(document.doc_type, PID_DOCTYPE);
+        assert_eq!(
+            document.validity,
+            Some(DocumentValidity {
+                valid_from,
+                valid_until
+            })
+        );
+        assert!(document.issuer_registration.is_none());
         assert_eq!(
             document.attributes.keys().cloned().collect::<Vec<_>>(),
             vec!["given_name", "family_name", "birth_date", "age_over_18", "bsn"]
@@ -1,11 +1,18 @@
+mod display;
 mod mapping;
 mod mdoc;
 
 use std::collections::HashMap;
 
-use chrono::NaiveDate;
+use chrono::{DateTime, NaiveDate, Utc};
 use indexmap::IndexMap;
 
+use nl_wallet_mdoc::utils::issuer_auth::IssuerRegistration;
+
+pub use display::{
+    display_languages, format_year_month, AttributeDisplay, AttributeDisplayRegistry, AttributeFormatter,
+    ATTRIBUTE_DISPLAY_REGISTRY, FALLBACK_LANGUAGE,
+};
 pub use mdoc::{AttributeValueType, DocumentMdocError};
 
 #[cfg(feature = "mock")]
@@ -14,8 +21,8 @@ pub use mdoc::mock::{
     create_minimal_unsigned_pid_mdoc,
 };
 
-const PID_DOCTYPE: &str = "com.example.pid";
-const ADDRESS_DOCTYPE: &str = "com.example.address";
+pub(crate) const PID_DOCTYPE: &str = "com.example.pid";
+pub(crate) const ADDRESS_DOCTYPE: &str = "com.example.address";
 
 pub type DocumentType = &'static str;
 pub type AttributeKey = &'static str;
@@ -26,6 +33,10 @@ pub struct Document {
     pub persistence: DocumentPersistence,
     pub doc_type: DocumentType,
     pub attributes: DocumentAttributes,
+    /// The validity period of the document, if known.
+    pub validity: Option<DocumentValidity>,
+    /// The registration of the issuer of the document, which is only known for issued documents.
+    pub issuer_registration: Option<Box<IssuerRegistration>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -34,6 +45,12 @@ pub enum DocumentPersistence {
     Stored(String),
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocumentValidity {
+    pub valid_from: DateTime<Utc>,
+    pub valid_until: DateTime<Utc>,
+}
+
 pub type AttributeLabelLanguage = &'static str;
 pub type AttributeLabel = &'static str;
 pub type AttributeLabels = HashMap<AttributeLabelLanguage, AttributeLabel>;
@@ -97,6 +114,8 @@ mod tests {
             persistence: DocumentPersistence::InMemory,
             doc_type,
             attributes: Default::default(),
+            validity: None,
+            issuer_registration: None,
         }
     }
 
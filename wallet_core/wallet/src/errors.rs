@@ -10,14 +10,17 @@ pub mod reqwest {
 pub use crate::{
     account_provider::{AccountProviderError, AccountProviderResponseError},
     config::{ConfigurationError, FileStorageError},
-    digid::{DigidError, OpenIdError},
+    credential_issuer::CredentialIssuerError,
+    digid::{DigidError, OpenIdError, RedirectUriErrorKind},
     document::{AttributeValueType, DocumentMdocError},
     instruction::{InstructionError, RemoteEcdsaKeyError},
-    pid_issuer::PidIssuerError,
     pin::{key::PinKeyError, validation::PinValidationError},
     storage::{KeyFileError, StorageError},
     wallet::{
-        DisclosureError, HistoryError, PidIssuanceError, UriIdentificationError, WalletInitError,
-        WalletRegistrationError, WalletUnlockError,
+        DisclosureError, DocumentsError, HistoryError, KeyPoolError, PidIssuanceError, UriIdentificationError,
+        UriProcessingError, WalletDataExportError, WalletDeregistrationError, WalletInitError, WalletRegistrationError,
+        WalletResetError, WalletSelfCheckError, WalletUnlockError,
     },
 };
+
+pub use wallet_common::issuer_error::{IssuerErrorCode, IssuerErrorData};
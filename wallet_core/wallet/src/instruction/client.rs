@@ -1,19 +1,21 @@
 use std::future::Future;
+
+use chrono::Utc;
+use p256::ecdsa::VerifyingKey;
 use tokio::sync::{RwLock, RwLockWriteGuard};
+use tracing::{info, warn};
 
 use platform_support::hw_keystore::PlatformEcdsaKey;
 use url::Url;
-use wallet_common::{
-    account::messages::instructions::{
-        Instruction, InstructionChallengeRequest, InstructionChallengeRequestMessage, InstructionEndpoint,
-    },
-    jwt::EcdsaDecodingKey,
+use wallet_common::account::messages::instructions::{
+    integrity_request_hash, Instruction, InstructionChallengeRequest, InstructionChallengeRequestMessage,
+    InstructionEndpoint, IntegrityEvidence,
 };
 
 use crate::{
     account_provider::AccountProviderClient,
-    pin::key::PinKey,
-    storage::{InstructionData, RegistrationData, Storage},
+    pin::{key::PinKey, lockout::PinLockout},
+    storage::{InstructionData, RegistrationData, Storage, StorageError},
 };
 
 use super::InstructionError;
@@ -25,7 +27,7 @@ pub struct InstructionClient<'a, S, K, A> {
     account_provider_client: &'a A,
     registration: &'a RegistrationData,
     account_provider_base_url: &'a Url,
-    instruction_result_public_key: &'a EcdsaDecodingKey,
+    instruction_result_public_keys: &'a [VerifyingKey],
 }
 
 impl<'a, S, K, A> InstructionClient<'a, S, K, A>
@@ -41,7 +43,7 @@ where
         account_provider_client: &'a A,
         registration: &'a RegistrationData,
         account_provider_base_url: &'a Url,
-        instruction_result_public_key: &'a EcdsaDecodingKey,
+        instruction_result_public_keys: &'a [VerifyingKey],
     ) -> Self {
         Self {
             pin,
@@ -50,7 +52,7 @@ where
             account_provider_client,
             registration,
             account_provider_base_url,
-            instruction_result_public_key,
+            instruction_result_public_keys,
         }
     }
 
@@ -79,7 +81,48 @@ where
             .map_err(InstructionError::Signing)
     }
 
+    /// Move the stored sequence number forward to the one last seen by the Wallet Provider, so that the next
+    /// instruction will be accepted. The stored sequence number is never moved backwards.
+    async fn fast_forward_sequence_number(
+        &self,
+        storage: &mut RwLockWriteGuard<'_, S>,
+        sequence_number: u64,
+    ) -> Result<(), InstructionError> {
+        let instruction_data = InstructionData {
+            instruction_sequence_number: sequence_number,
+        };
+
+        match storage.fetch_data::<InstructionData>().await? {
+            Some(stored) if stored.instruction_sequence_number >= sequence_number => (),
+            Some(_) => storage.update_data(&instruction_data).await?,
+            None => storage.insert_data(&instruction_data).await?,
+        }
+
+        Ok(())
+    }
+
     async fn instruction_challenge(&self, storage: &mut RwLockWriteGuard<'_, S>) -> Result<Vec<u8>, InstructionError> {
+        match self.request_instruction_challenge(storage).await {
+            // The Wallet Provider may have already seen our sequence number, e.g. when the database was restored
+            // or when storing the incremented sequence number did not succeed. In that case it reports the last
+            // sequence number it knows about, which we fast-forward to before trying exactly once more.
+            Err(InstructionError::SequenceNumberMismatch { sequence_number }) => {
+                info!(
+                    "Instruction sequence number out of sync, fast-forwarding to {}",
+                    sequence_number
+                );
+
+                self.fast_forward_sequence_number(storage, sequence_number).await?;
+                self.request_instruction_challenge(storage).await
+            }
+            result => result,
+        }
+    }
+
+    async fn request_instruction_challenge(
+        &self,
+        storage: &mut RwLockWriteGuard<'_, S>,
+    ) -> Result<Vec<u8>, InstructionError> {
         let message = self
             .with_sequence_number(storage, |seq_num| {
                 InstructionChallengeRequest::new_signed(seq_num, "wallet", self.hw_privkey)
@@ -99,18 +142,81 @@ where
         Ok(result)
     }
 
+    /// Collect evidence of the integrity of the app and device for the instruction with the provided challenge. Not
+    /// every platform is able to provide this, so any error is logged and otherwise ignored. It is up to the Wallet
+    /// Provider to decide whether it accepts an instruction without this evidence.
+    async fn integrity_evidence(&self, challenge: &[u8]) -> Option<IntegrityEvidence> {
+        match self
+            .hw_privkey
+            .integrity_evidence(&integrity_request_hash(challenge))
+            .await
+        {
+            Ok(evidence) => evidence.map(IntegrityEvidence::from),
+            Err(error) => {
+                warn!("Could not collect integrity evidence: {}", error);
+
+                None
+            }
+        }
+    }
+
+    async fn store_pin_lockout(
+        storage: &mut RwLockWriteGuard<'_, S>,
+        stored_lockout: Option<PinLockout>,
+        next_lockout: PinLockout,
+    ) -> Result<(), StorageError> {
+        match stored_lockout {
+            Some(lockout) if lockout == next_lockout => Ok(()),
+            Some(_) => storage.update_data(&next_lockout).await,
+            None if next_lockout == PinLockout::default() => Ok(()),
+            None => storage.insert_data(&next_lockout).await,
+        }
+    }
+
     pub async fn send<I>(&self, instruction: I) -> Result<I::Result, InstructionError>
     where
         I: InstructionEndpoint + 'static,
     {
         let mut storage = self.storage.write().await;
 
-        let challenge = self.instruction_challenge(&mut storage).await?;
+        // Refuse to send the instruction while the PIN is timed out or blocked, as the Wallet Provider would reject it.
+        let now = Utc::now();
+        let stored_lockout = storage.fetch_data::<PinLockout>().await?;
+        let lockout = stored_lockout.unwrap_or_default();
+        lockout.check(now)?;
+
+        let result = self.send_instruction(&mut storage, instruction).await;
+
+        // A failure to store the lockout should not hide the result of the instruction itself, as the Wallet Provider
+        // will still enforce the lockout when it is not present locally.
+        let next_lockout = lockout.next(&result, now);
+        if let Err(error) = Self::store_pin_lockout(&mut storage, stored_lockout, next_lockout).await {
+            warn!("Could not store PIN lockout state: {}", error);
+        }
+
+        result
+    }
+
+    async fn send_instruction<I>(
+        &self,
+        storage: &mut RwLockWriteGuard<'_, S>,
+        instruction: I,
+    ) -> Result<I::Result, InstructionError>
+    where
+        I: InstructionEndpoint + 'static,
+    {
+        let challenge = self.instruction_challenge(storage).await?;
+
+        let integrity_evidence = if instruction.requires_integrity_evidence() {
+            self.integrity_evidence(&challenge).await
+        } else {
+            None
+        };
 
         let pin_key = PinKey::new(&self.pin, &self.registration.pin_salt.0);
 
-        let instruction = self
-            .with_sequence_number(&mut storage, |seq_num| {
+        let mut instruction = self
+            .with_sequence_number(storage, |seq_num| {
                 Instruction::new_signed(
                     instruction,
                     seq_num,
@@ -121,6 +227,7 @@ where
                 )
             })
             .await?;
+        instruction.integrity_evidence = integrity_evidence;
 
         let signed_result = self
             .account_provider_client
@@ -129,7 +236,7 @@ where
             .map_err(InstructionError::from)?;
 
         let result = signed_result
-            .parse_and_verify_with_sub(self.instruction_result_public_key)
+            .parse_and_verify_with_sub_from_keys(self.instruction_result_public_keys)
             .map_err(InstructionError::InstructionResultValidation)?
             .result;
 
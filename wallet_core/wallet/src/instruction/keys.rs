@@ -1,11 +1,13 @@
-use std::{collections::HashMap, iter};
+use std::{collections::HashMap, iter, sync::Mutex};
 
 use p256::ecdsa::{signature, signature::Verifier, Signature, VerifyingKey};
 
 use nl_wallet_mdoc::utils::keys::{KeyFactory, MdocEcdsaKey, MdocKeyType};
 use platform_support::hw_keystore::PlatformEcdsaKey;
 use wallet_common::{
-    account::messages::instructions::{GenerateKey, GenerateKeyResult, Sign},
+    account::messages::instructions::{
+        BatchedInstructionResult, GenerateKey, GenerateKeyResult, InstructionBatch, Sign, SignResult,
+    },
     keys::{EcdsaKey, SecureEcdsaKey, WithIdentifier},
     utils::random_string,
 };
@@ -24,8 +26,13 @@ pub enum RemoteEcdsaKeyError {
     KeyNotFound(String),
 }
 
+/// A message, together with all of the keys it should be signed with.
+type MessageWithKeys<'a, S, K, A> = (Vec<u8>, Vec<RemoteEcdsaKey<'a, S, K, A>>);
+
 pub struct RemoteEcdsaKeyFactory<'a, S, K, A> {
     instruction_client: &'a InstructionClient<'a, S, K, A>,
+    key_pool: Mutex<Vec<(String, VerifyingKey)>>,
+    verifying_keys: Mutex<HashMap<String, VerifyingKey>>,
 }
 
 pub struct RemoteEcdsaKey<'a, S, K, A> {
@@ -36,7 +43,119 @@ pub struct RemoteEcdsaKey<'a, S, K, A> {
 
 impl<'a, S, K, A> RemoteEcdsaKeyFactory<'a, S, K, A> {
     pub fn new(instruction_client: &'a InstructionClient<'a, S, K, A>) -> Self {
-        Self { instruction_client }
+        Self::new_with_key_pool(instruction_client, Vec::new())
+    }
+
+    /// Create a factory that hands out keys that were already generated in the Wallet Provider, before
+    /// generating new ones. These keys should not have been used for anything else yet.
+    pub fn new_with_key_pool(
+        instruction_client: &'a InstructionClient<'a, S, K, A>,
+        key_pool: Vec<(String, VerifyingKey)>,
+    ) -> Self {
+        Self {
+            instruction_client,
+            key_pool: Mutex::new(key_pool),
+            verifying_keys: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Consume the factory and return the keys from the pool that have not been handed out.
+    pub fn into_unused_pooled_keys(self) -> Vec<(String, VerifyingKey)> {
+        self.key_pool.into_inner().unwrap()
+    }
+
+    /// Take at most `count` keys from the pool.
+    fn take_pooled_keys(&self, count: usize) -> Vec<(String, VerifyingKey)> {
+        let mut key_pool = self.key_pool.lock().unwrap();
+        let pooled_count = count.min(key_pool.len());
+
+        key_pool.drain(..pooled_count).collect()
+    }
+
+    fn to_key(&'a self, identifier: String, public_key: VerifyingKey) -> RemoteEcdsaKey<'a, S, K, A> {
+        self.verifying_keys
+            .lock()
+            .unwrap()
+            .insert(identifier.clone(), public_key);
+
+        RemoteEcdsaKey {
+            identifier,
+            public_key,
+            key_factory: self,
+        }
+    }
+
+    fn to_keys(&'a self, public_keys: Vec<(String, VerifyingKey)>) -> Vec<RemoteEcdsaKey<'a, S, K, A>> {
+        public_keys
+            .into_iter()
+            .map(|(identifier, public_key)| self.to_key(identifier, public_key))
+            .collect()
+    }
+
+    /// Check that the Wallet Provider returned a valid signature for every message and key identifier that was
+    /// sent in a [`Sign`] instruction, using the cached verifying key of each identifier. The signatures are returned
+    /// per message, in the same order as the identifiers for that message.
+    fn verify_signatures(
+        &self,
+        messages_with_identifiers: &[(Vec<u8>, Vec<String>)],
+        mut result: SignResult,
+    ) -> Result<Vec<Vec<Signature>>, RemoteEcdsaKeyError> {
+        let verifying_keys = self.verifying_keys.lock().unwrap();
+
+        messages_with_identifiers
+            .iter()
+            .map(|(msg, identifiers)| {
+                identifiers
+                    .iter()
+                    .map(|identifier| {
+                        let signature = result
+                            .signatures_by_identifier
+                            .remove(identifier)
+                            .ok_or_else(|| RemoteEcdsaKeyError::KeyNotFound(identifier.clone()))?
+                            .0;
+                        let verifying_key = verifying_keys
+                            .get(identifier)
+                            .ok_or_else(|| RemoteEcdsaKeyError::KeyNotFound(identifier.clone()))?;
+
+                        verifying_key.verify(msg, &signature)?;
+
+                        Ok(signature)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Sign every message with all of its keys in a single instruction, returning the keys and the verified
+    /// signatures in the same order as they were provided.
+    async fn sign_messages(
+        &self,
+        messages_and_keys: Vec<MessageWithKeys<'a, S, K, A>>,
+    ) -> Result<Vec<(RemoteEcdsaKey<'a, S, K, A>, Signature)>, RemoteEcdsaKeyError>
+    where
+        S: Storage,
+        K: PlatformEcdsaKey,
+        A: AccountProviderClient,
+    {
+        let (messages, keys): (Vec<_>, Vec<Vec<_>>) = messages_and_keys.into_iter().unzip();
+        let messages_with_identifiers = messages
+            .into_iter()
+            .zip(&keys)
+            .map(|(msg, keys)| (msg, keys.iter().map(|key| key.identifier.clone()).collect()))
+            .collect::<Vec<(Vec<u8>, Vec<String>)>>();
+
+        let result = self
+            .instruction_client
+            .send(sign_instruction(&messages_with_identifiers))
+            .await?;
+
+        let signatures = self.verify_signatures(&messages_with_identifiers, result)?;
+
+        Ok(keys
+            .into_iter()
+            .flatten()
+            .zip(signatures.into_iter().flatten())
+            .collect())
     }
 }
 
@@ -50,28 +169,30 @@ where
     type Error = RemoteEcdsaKeyError;
 
     async fn generate_new_multiple(&self, count: u64) -> Result<Vec<Self::Key>, Self::Error> {
-        let identifiers = iter::repeat_with(|| random_string(32)).take(count as usize).collect();
-        let result: GenerateKeyResult = self.instruction_client.send(GenerateKey { identifiers }).await?;
+        let count = count as usize;
 
-        let keys = result
-            .public_keys
-            .into_iter()
-            .map(|(identifier, public_key)| RemoteEcdsaKey {
-                identifier,
-                public_key: public_key.0,
-                key_factory: self,
-            })
-            .collect();
+        // Take as many keys as possible from the pool, so that only the remainder has to be generated.
+        let mut public_keys = self.take_pooled_keys(count);
+
+        if public_keys.len() < count {
+            let identifiers = iter::repeat_with(|| random_string(32))
+                .take(count - public_keys.len())
+                .collect();
+            let result: GenerateKeyResult = self.instruction_client.send(GenerateKey { identifiers }).await?;
+
+            public_keys.extend(
+                result
+                    .public_keys
+                    .into_iter()
+                    .map(|(identifier, public_key)| (identifier, public_key.0)),
+            );
+        }
 
-        Ok(keys)
+        Ok(self.to_keys(public_keys))
     }
 
     fn generate_existing<I: Into<String>>(&self, identifier: I, public_key: VerifyingKey) -> Self::Key {
-        RemoteEcdsaKey {
-            identifier: identifier.into(),
-            public_key,
-            key_factory: self,
-        }
+        self.to_key(identifier.into(), public_key)
     }
 
     async fn sign_with_new_keys(
@@ -79,48 +200,83 @@ where
         msg: Vec<u8>,
         number_of_keys: u64,
     ) -> Result<Vec<(Self::Key, Signature)>, Self::Error> {
-        let keys = self.generate_new_multiple(number_of_keys).await?;
-        self.sign_with_existing_keys(vec![(msg, keys)]).await
-    }
+        let count = number_of_keys as usize;
+        let pooled_keys = self.take_pooled_keys(count);
 
-    async fn sign_with_existing_keys(
-        &self,
-        messages_and_keys: Vec<(Vec<u8>, Vec<Self::Key>)>,
-    ) -> Result<Vec<(Self::Key, Signature)>, Self::Error> {
-        let (messages, keys): (Vec<_>, Vec<Vec<_>>) = messages_and_keys.into_iter().unzip();
+        if pooled_keys.len() == count {
+            return self
+                .sign_with_existing_keys(vec![(msg, self.to_keys(pooled_keys))])
+                .await;
+        }
 
-        let identifiers = keys
-            .iter()
-            .map(|keys| keys.iter().map(|key| key.identifier.clone()).collect::<Vec<String>>())
+        // Generate the remaining keys and sign with all of the keys in a single batch of instructions,
+        // which saves a round trip to the Wallet Provider.
+        let identifiers = iter::repeat_with(|| random_string(32))
+            .take(count - pooled_keys.len())
             .collect::<Vec<_>>();
+        let messages_with_identifiers = vec![(
+            msg,
+            pooled_keys
+                .iter()
+                .map(|(identifier, _)| identifier.clone())
+                .chain(identifiers.iter().cloned())
+                .collect::<Vec<_>>(),
+        )];
+        let batch = InstructionBatch {
+            instructions: vec![
+                GenerateKey { identifiers }.into(),
+                sign_instruction(&messages_with_identifiers).into(),
+            ],
+        };
 
-        let result = self
-            .instruction_client
-            .send(Sign {
-                messages_with_identifiers: messages
-                    .into_iter()
-                    .zip(identifiers)
-                    .map(|(msg, keys)| (msg.into(), keys))
-                    .collect(),
-            })
-            .await?;
+        let results = self.instruction_client.send(batch).await?.results;
+        let (generate_key_result, sign_result) = match <[_; 2]>::try_from(results) {
+            Ok([BatchedInstructionResult::GenerateKey(generate_key), BatchedInstructionResult::Sign(sign)]) => {
+                (generate_key, sign)
+            }
+            _ => return Err(InstructionError::BatchResultMismatch.into()),
+        };
 
-        let mut keys_by_identifier: HashMap<String, Self::Key> = keys
+        // Look up the generated keys by identifier, so that the keys are in the same order as the signed identifiers.
+        let mut public_keys = pooled_keys
             .into_iter()
-            .flat_map(|keys| {
-                keys.into_iter()
-                    .map(|key| (key.identifier.clone(), key))
-                    .collect::<Vec<_>>()
+            .chain(
+                generate_key_result
+                    .public_keys
+                    .into_iter()
+                    .map(|(identifier, public_key)| (identifier, public_key.0)),
+            )
+            .collect::<HashMap<_, _>>();
+        let keys = messages_with_identifiers[0]
+            .1
+            .iter()
+            .map(|identifier| {
+                public_keys
+                    .remove(identifier)
+                    .map(|public_key| self.to_key(identifier.clone(), public_key))
+                    .ok_or_else(|| RemoteEcdsaKeyError::KeyNotFound(identifier.clone()))
             })
-            .collect();
+            .collect::<Result<Vec<_>, _>>()?;
+        let signatures = self.verify_signatures(&messages_with_identifiers, sign_result)?;
 
-        let keys_and_signatures = result
-            .signatures_by_identifier
-            .into_iter()
-            .map(|(key, value)| (keys_by_identifier.remove(&key).unwrap(), value.0))
-            .collect();
+        Ok(keys.into_iter().zip(signatures.into_iter().flatten()).collect())
+    }
 
-        Ok(keys_and_signatures)
+    async fn sign_with_existing_keys(
+        &self,
+        messages_and_keys: Vec<(Vec<u8>, Vec<Self::Key>)>,
+    ) -> Result<Vec<(Self::Key, Signature)>, Self::Error> {
+        self.sign_messages(messages_and_keys).await
+    }
+}
+
+/// Convert the messages and identifiers to the [`Sign`] instruction, while retaining them for verification.
+fn sign_instruction(messages_with_identifiers: &[(Vec<u8>, Vec<String>)]) -> Sign {
+    Sign {
+        messages_with_identifiers: messages_with_identifiers
+            .iter()
+            .map(|(msg, identifiers)| (msg.clone().into(), identifiers.clone()))
+            .collect(),
     }
 }
 
@@ -143,22 +299,23 @@ where
     }
 
     async fn try_sign(&self, msg: &[u8]) -> Result<Signature, Self::Error> {
+        let messages_with_identifiers = [(msg.to_vec(), vec![self.identifier.clone()])];
+
         let result = self
             .key_factory
             .instruction_client
-            .send(Sign {
-                messages_with_identifiers: vec![(msg.to_vec().into(), vec![self.identifier.clone()])],
-            })
+            .send(sign_instruction(&messages_with_identifiers))
             .await?;
 
-        let signature = result
-            .signatures_by_identifier
-            .get(&self.identifier)
-            .ok_or(RemoteEcdsaKeyError::KeyNotFound(self.identifier.clone()))?;
-
-        self.public_key.verify(msg, &signature.0)?;
+        let signature = self
+            .key_factory
+            .verify_signatures(&messages_with_identifiers, result)?
+            .into_iter()
+            .flatten()
+            .next()
+            .ok_or_else(|| RemoteEcdsaKeyError::KeyNotFound(self.identifier.clone()))?;
 
-        Ok(signature.0)
+        Ok(signature)
     }
 }
 
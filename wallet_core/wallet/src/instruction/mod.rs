@@ -26,14 +26,28 @@ pub enum InstructionError {
     Timeout { timeout_millis: u64 },
     #[error("unlock permanently disabled")]
     Blocked,
+    #[error("wallet has been suspended by the Wallet Provider")]
+    Suspended,
     #[error("server error: {0}")]
     ServerError(#[source] AccountProviderError),
     #[error("Wallet Provider could not validate instruction")]
     InstructionValidation,
+    #[error("instruction sequence number was rejected by Wallet Provider (known: {sequence_number})")]
+    SequenceNumberMismatch { sequence_number: u64 },
+    #[error("Wallet Provider is still handling another instruction")]
+    WalletBusy,
+    #[error("Wallet Provider received too many instructions, retry after {retry_after_millis} ms")]
+    RateLimited { retry_after_millis: u64 },
+    #[error("Wallet Provider could not verify the integrity of the app or device")]
+    IntegrityEvidence,
+    #[error("Wallet Provider refused to create more signatures with the keys of this wallet")]
+    KeyUsageLimit,
     #[error("could not sign instruction: {0}")]
     Signing(#[source] wallet_common::errors::Error),
     #[error("could not validate instruction result received from Wallet Provider: {0}")]
     InstructionResultValidation(#[source] JwtError),
+    #[error("results received from Wallet Provider do not match the batched instructions")]
+    BatchResultMismatch,
     #[error("could not store instruction sequence number in database: {0}")]
     StoreInstructionSequenceNumber(#[from] StorageError),
 }
@@ -50,7 +64,17 @@ impl From<AccountProviderError> for InstructionError {
                     is_final_attempt: data.is_final_attempt,
                 },
                 ErrorType::AccountBlocked => Self::Blocked,
+                ErrorType::AccountSuspended => Self::Suspended,
                 ErrorType::InstructionValidation => Self::InstructionValidation,
+                ErrorType::InstructionSequenceNumber(data) => Self::SequenceNumberMismatch {
+                    sequence_number: data.sequence_number,
+                },
+                ErrorType::WalletBusy => Self::WalletBusy,
+                ErrorType::RateLimited(data) => Self::RateLimited {
+                    retry_after_millis: data.retry_after_in_ms,
+                },
+                ErrorType::IntegrityEvidence => Self::IntegrityEvidence,
+                ErrorType::KeyUsageLimit => Self::KeyUsageLimit,
                 _ => Self::ServerError(value),
             }
         } else {
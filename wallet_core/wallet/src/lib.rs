@@ -1,11 +1,13 @@
 mod account_provider;
+mod clock_skew;
 mod config;
+mod connectivity;
+mod credential_issuer;
 mod digid;
 mod disclosure;
 mod document;
 mod instruction;
 mod lock;
-mod pid_issuer;
 mod pin;
 mod pkce;
 mod storage;
@@ -16,12 +18,17 @@ pub mod errors;
 
 pub use crate::{
     document::{
-        Attribute, AttributeLabel, AttributeLabelLanguage, AttributeLabels, AttributeValue, DisclosureDocument,
-        Document, DocumentAttributes, DocumentPersistence, DocumentType, GenderAttributeValue,
-        MissingDisclosureAttributes,
+        display_languages, format_year_month, Attribute, AttributeDisplay, AttributeDisplayRegistry,
+        AttributeFormatter, AttributeLabel, AttributeLabelLanguage, AttributeLabels, AttributeValue,
+        DisclosureDocument, Document, DocumentAttributes, DocumentPersistence, DocumentType, DocumentValidity,
+        GenderAttributeValue, MissingDisclosureAttributes, ATTRIBUTE_DISPLAY_REGISTRY, FALLBACK_LANGUAGE,
+    },
+    lock::AppState,
+    pin::{lockout::PinLockout, validation::validate_pin},
+    wallet::{
+        DisclosureProposal, DisclosureRequestPreview, EventStatus, HistoryEvent, MissingAttributesReport,
+        UriProcessingResult, UriType, Wallet,
     },
-    pin::validation::validate_pin,
-    wallet::{DisclosureProposal, EventStatus, HistoryEvent, UriType, Wallet},
 };
 
 pub mod mdoc {
@@ -31,7 +38,7 @@ pub mod mdoc {
     };
 }
 
-pub use wallet_common::config::wallet_config::{LockTimeoutConfiguration, WalletConfiguration};
+pub use wallet_common::config::wallet_config::{LockTimeoutConfiguration, PinPolicyConfiguration, WalletConfiguration};
 
 #[cfg(feature = "wallet_deps")]
 pub mod wallet_deps {
@@ -42,11 +49,11 @@ pub mod wallet_deps {
             FileStorageConfigurationRepository, HttpConfigurationRepository, UpdateableConfigurationRepository,
             UpdatingFileHttpConfigurationRepository,
         },
-        digid::{DigidSession, HttpDigidSession},
+        credential_issuer::{CredentialIssuerClient, HttpCredentialIssuerClient},
+        digid::{DigidSession, DigidSessionState, HttpDigidSession},
         disclosure::{
             MdocDisclosureMissingAttributes, MdocDisclosureProposal, MdocDisclosureSession, MdocDisclosureSessionState,
         },
-        pid_issuer::{HttpPidIssuerClient, PidIssuerClient},
         storage::Storage,
     };
 }
@@ -56,9 +63,9 @@ pub mod mock {
     pub use crate::{
         account_provider::MockAccountProviderClient,
         config::{default_configuration, LocalConfigurationRepository},
-        digid::MockDigidSession,
+        credential_issuer::MockCredentialIssuerClient,
+        digid::{DigidSessionState, MockDigidSession},
         disclosure::{MockMdocDisclosureMissingAttributes, MockMdocDisclosureProposal, MockMdocDisclosureSession},
-        pid_issuer::MockPidIssuerClient,
         storage::MockStorage,
     };
 }
@@ -1,4 +1,8 @@
-use std::fmt::Debug;
+use std::{fmt::Debug, time::Duration};
+
+use tokio::time::Instant;
+
+use wallet_common::config::wallet_config::LockTimeoutConfiguration;
 
 /// This models the locked state of the wallet. Locking and unlocking
 /// is restricted to the [`Self::lock()`] and [`Self::unlock()`] methods.
@@ -66,6 +70,71 @@ impl Debug for WalletLock {
     }
 }
 
+/// The lifecycle state of the app, as reported by the OS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AppState {
+    Foreground,
+    Background,
+}
+
+/// This keeps track of the last user activity and of when the app was moved
+/// to the background. Based on this, it can determine if either the inactivity
+/// or the background timeout from [`LockTimeoutConfiguration`] has expired,
+/// in which case the wallet should be locked automatically.
+#[derive(Debug)]
+pub struct LockScheduler {
+    last_activity: Instant,
+    background_since: Option<Instant>,
+}
+
+impl LockScheduler {
+    pub fn new() -> Self {
+        LockScheduler {
+            last_activity: Instant::now(),
+            background_since: None,
+        }
+    }
+
+    pub fn register_activity(&mut self) {
+        self.last_activity = Instant::now();
+    }
+
+    pub fn enter_background(&mut self) {
+        // Keep the original timestamp if the app was already in the background.
+        self.background_since.get_or_insert_with(Instant::now);
+    }
+
+    /// Move the app back to the foreground, which counts as user activity.
+    /// The return value indicates if any of the timeouts expired before this.
+    pub fn enter_foreground(&mut self, timeouts: &LockTimeoutConfiguration) -> bool {
+        let expired = self.is_expired(timeouts);
+
+        self.background_since = None;
+        self.register_activity();
+
+        expired
+    }
+
+    /// While the app is in the foreground, only the inactivity timeout applies.
+    /// When in the background, the wallet should be locked when either timeout expires.
+    pub fn is_expired(&self, timeouts: &LockTimeoutConfiguration) -> bool {
+        let inactive_timeout = Duration::from_secs(timeouts.inactive_timeout.into());
+        let inactive_expired = self.last_activity.elapsed() >= inactive_timeout;
+
+        let background_expired = self.background_since.is_some_and(|background_since| {
+            background_since.elapsed() >= Duration::from_secs(timeouts.background_timeout.into())
+        });
+
+        inactive_expired || background_expired
+    }
+}
+
+impl Default for LockScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::{Arc, Mutex};
@@ -111,4 +180,52 @@ mod tests {
         assert!(!lock.is_locked());
         assert!(matches!(callback_is_locked.lock().unwrap().as_ref(), Some(true)));
     }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_lock_scheduler() {
+        let timeouts = LockTimeoutConfiguration {
+            inactive_timeout: 60,
+            background_timeout: 10,
+        };
+        let mut scheduler = LockScheduler::new();
+        assert!(scheduler.background_since.is_none());
+        assert!(!scheduler.is_expired(&timeouts));
+
+        // Activity should reset the inactivity timeout.
+        tokio::time::advance(Duration::from_secs(59)).await;
+        assert!(!scheduler.is_expired(&timeouts));
+        scheduler.register_activity();
+
+        tokio::time::advance(Duration::from_secs(59)).await;
+        assert!(!scheduler.is_expired(&timeouts));
+
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert!(scheduler.is_expired(&timeouts));
+
+        // Returning to the foreground should report that the timeout had expired.
+        assert!(scheduler.enter_foreground(&timeouts));
+        assert!(!scheduler.is_expired(&timeouts));
+
+        // The background timeout should apply only while in the background,
+        // re-entering the background should not reset it.
+        scheduler.enter_background();
+        assert!(scheduler.background_since.is_some());
+
+        tokio::time::advance(Duration::from_secs(5)).await;
+        scheduler.enter_background();
+        assert!(!scheduler.is_expired(&timeouts));
+
+        tokio::time::advance(Duration::from_secs(5)).await;
+        assert!(scheduler.is_expired(&timeouts));
+
+        // Returning to the foreground in time should not report an expired timeout.
+        assert!(scheduler.enter_foreground(&timeouts));
+        scheduler.enter_background();
+        tokio::time::advance(Duration::from_secs(9)).await;
+        assert!(!scheduler.enter_foreground(&timeouts));
+        assert!(scheduler.background_since.is_none());
+
+        tokio::time::advance(Duration::from_secs(30)).await;
+        assert!(!scheduler.is_expired(&timeouts));
+    }
 }
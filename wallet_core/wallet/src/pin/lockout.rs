@@ -0,0 +1,139 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{instruction::InstructionError, storage::KeyedData};
+
+/// The local view of the PIN lockout that is enforced by the Wallet Provider. Any instruction that the Wallet
+/// Provider rejects because of a timeout or blocked account moves this state forward, so that the wallet can refuse
+/// new attempts itself and show how long the user still has to wait.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum PinLockout {
+    /// The PIN may be entered.
+    #[default]
+    Available,
+    /// The PIN may not be entered until the specified time.
+    TimedOut { until: DateTime<Utc> },
+    /// The account was blocked by the Wallet Provider, until it is unblocked there.
+    Blocked,
+}
+
+impl KeyedData for PinLockout {
+    const KEY: &'static str = "pin_lockout";
+}
+
+impl PinLockout {
+    /// Check whether a PIN may be entered at `now`. If not, the error that the Wallet Provider would have
+    /// returned for the attempt is returned instead, with the time that is left until the timeout expires.
+    /// Note that a blocked account may be unblocked by the Wallet Provider, so the PIN may still be entered
+    /// when blocked, in which case the answer of the Wallet Provider determines the next state.
+    pub fn check(&self, now: DateTime<Utc>) -> Result<(), InstructionError> {
+        match self.remaining(now) {
+            Some(remaining) => Err(InstructionError::Timeout {
+                timeout_millis: remaining.num_milliseconds() as u64,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// The time that is left at `now` until the PIN may be entered again, if it is timed out.
+    pub fn remaining(&self, now: DateTime<Utc>) -> Option<Duration> {
+        match self {
+            Self::TimedOut { until } if *until > now => Some(*until - now),
+            _ => None,
+        }
+    }
+
+    /// Determine the next state, based on the result of an instruction sent to the Wallet Provider at `now`.
+    /// Errors that are not related to the PIN leave the state unchanged.
+    pub fn next<T>(self, result: &Result<T, InstructionError>, now: DateTime<Utc>) -> Self {
+        match result {
+            Ok(_) | Err(InstructionError::IncorrectPin { .. }) => Self::Available,
+            Err(InstructionError::Timeout { timeout_millis }) => Self::TimedOut {
+                until: now + Duration::milliseconds(*timeout_millis as i64),
+            },
+            Err(InstructionError::Blocked) => Self::Blocked,
+            Err(_) => self,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+
+    use super::*;
+
+    #[test]
+    fn test_pin_lockout_timeout() {
+        let now = Utc::now();
+
+        let lockout = PinLockout::Available.next::<()>(&Err(InstructionError::Timeout { timeout_millis: 5000 }), now);
+
+        assert_eq!(
+            lockout,
+            PinLockout::TimedOut {
+                until: now + Duration::seconds(5)
+            }
+        );
+
+        // While timed out, the remaining time is reported.
+        assert_matches!(
+            lockout.check(now + Duration::seconds(2)),
+            Err(InstructionError::Timeout { timeout_millis: 3000 })
+        );
+        assert_eq!(
+            lockout.remaining(now + Duration::seconds(2)),
+            Some(Duration::seconds(3))
+        );
+
+        // When the timeout has expired, the PIN may be entered again.
+        assert_matches!(lockout.check(now + Duration::seconds(5)), Ok(()));
+        assert_eq!(lockout.remaining(now + Duration::seconds(5)), None);
+
+        // An incorrect PIN after the timeout resets the state.
+        let lockout = lockout.next::<()>(
+            &Err(InstructionError::IncorrectPin {
+                leftover_attempts: 3,
+                is_final_attempt: false,
+            }),
+            now + Duration::seconds(6),
+        );
+
+        assert_eq!(lockout, PinLockout::Available);
+    }
+
+    #[test]
+    fn test_pin_lockout_blocked() {
+        let now = Utc::now();
+
+        let lockout = PinLockout::Available.next::<()>(&Err(InstructionError::Blocked), now);
+
+        assert_eq!(lockout, PinLockout::Blocked);
+
+        // The Wallet Provider decides whether the account is still blocked.
+        assert_matches!(lockout.check(now), Ok(()));
+        assert_eq!(
+            lockout.next::<()>(&Err(InstructionError::Blocked), now),
+            PinLockout::Blocked
+        );
+        assert_eq!(lockout.next(&Ok(()), now), PinLockout::Available);
+    }
+
+    #[test]
+    fn test_pin_lockout_unrelated_error() {
+        let now = Utc::now();
+        let lockout = PinLockout::TimedOut {
+            until: now + Duration::seconds(5),
+        };
+
+        // Errors unrelated to the PIN do not change the state.
+        assert_eq!(
+            lockout.next::<()>(&Err(InstructionError::InstructionValidation), now),
+            lockout
+        );
+
+        // A successful instruction clears it.
+        assert_eq!(lockout.next(&Ok(()), now), PinLockout::Available);
+    }
+}
@@ -1,2 +1,3 @@
 pub mod key;
+pub mod lockout;
 pub mod validation;
@@ -1,7 +1,9 @@
+use wallet_common::config::wallet_config::{PinCharacters, PinPolicyConfiguration};
+
 #[derive(Debug, thiserror::Error)]
 pub enum PinValidationError {
-    #[error("PIN contains characters that are not digits")]
-    NonDigits,
+    #[error("PIN contains characters that are not allowed")]
+    InvalidCharacters,
     #[error("PIN does not have the required length")]
     InvalidLength,
     #[error("PIN has too few unique digits")]
@@ -12,46 +14,36 @@ pub enum PinValidationError {
     DescendingDigits,
 }
 
-// The expected length of the pin code
-const EXACT_LENGTH: usize = 6;
-// The minimum number of unique digits
-const MIN_UNIQUE_DIGITS: usize = 2;
-// The radix used to parse a digit, 10 for decimal (16 for hexadecimal)
-const RADIX: usize = 10;
-
 /// This function will check whether the pin has a valid length.
-fn pin_length_should_be_correct(pin: &str) -> Result<(), PinValidationError> {
-    if pin.len() != EXACT_LENGTH {
+fn pin_length_should_be_correct(pin: &str, policy: &PinPolicyConfiguration) -> Result<(), PinValidationError> {
+    let length = pin.chars().count();
+
+    if length < policy.min_length as usize || length > policy.max_length as usize {
         Err(PinValidationError::InvalidLength)
     } else {
         Ok(())
     }
 }
 
-/// This function will convert a pin to a vector of digits.
-/// It will return an error when non-digits are detected.
-fn parse_pin_to_digits(pin: &str) -> Result<Vec<u8>, PinValidationError> {
-    let digit_options: Vec<Option<u32>> = pin.chars().map(|c| c.to_digit(RADIX as u32)).collect();
-    if digit_options.iter().any(|c| c.is_none()) {
-        Err(PinValidationError::NonDigits)
-    } else {
-        let digits: Vec<u8> = digit_options.into_iter().map(|c| c.unwrap() as u8).collect();
-        Ok(digits)
-    }
+/// This function will convert a pin to a vector of digits in the radix of the allowed characters,
+/// where letters are converted case insensitively. It will return an error when other characters are detected.
+fn parse_pin_to_digits(pin: &str, allowed_characters: PinCharacters) -> Result<Vec<u8>, PinValidationError> {
+    pin.chars()
+        .map(|c| {
+            c.to_digit(allowed_characters.radix())
+                .map(|d| d as u8)
+                .ok_or(PinValidationError::InvalidCharacters)
+        })
+        .collect()
 }
 
 /// This function will check whether there are enough unique digits.
-// NOTE: this function will panic when the vector digits contains numbers > 9.
-fn pin_should_contain_enough_unique_digits(digits: &[u8]) -> Result<(), PinValidationError> {
-    let count: [u8; RADIX] = {
-        let mut count: [u8; RADIX] = [0; RADIX];
-        for d in digits.iter() {
-            count[*d as usize] += 1;
-        }
-        count
-    };
-    let unique_digits = count.into_iter().filter(|&d| d > 0).count();
-    if unique_digits < MIN_UNIQUE_DIGITS {
+fn pin_should_contain_enough_unique_digits(digits: &[u8], min_unique_digits: u8) -> Result<(), PinValidationError> {
+    let mut unique_digits = digits.to_vec();
+    unique_digits.sort_unstable();
+    unique_digits.dedup();
+
+    if unique_digits.len() < min_unique_digits as usize {
         Err(PinValidationError::TooFewUniqueDigits)
     } else {
         Ok(())
@@ -60,9 +52,12 @@ fn pin_should_contain_enough_unique_digits(digits: &[u8]) -> Result<(), PinValid
 
 /// This function will check whether the pin is ascending or descending
 /// by a difference of 1 without modulo.
-// NOTE: the assumption here is that a pin contains at least 2 digits.
-// NOTE: this function will panic when an empty vector is supplied.
+// NOTE: a pin that contains less than 2 digits is considered to be neither.
 fn pin_should_not_be_ascending_or_descending(digits: &[u8]) -> Result<(), PinValidationError> {
+    if digits.len() < 2 {
+        return Ok(());
+    }
+
     let mut ascending: bool = true;
     let mut descending: bool = true;
     let mut prev: i8 = digits[0] as i8;
@@ -84,12 +79,14 @@ fn pin_should_not_be_ascending_or_descending(digits: &[u8]) -> Result<(), PinVal
     }
 }
 
-/// This function will check whether a pin is not too simple.
-pub fn validate_pin(pin: &str) -> Result<(), PinValidationError> {
-    pin_length_should_be_correct(pin)?;
-    let digits = parse_pin_to_digits(pin)?;
-    pin_should_contain_enough_unique_digits(&digits)?;
-    pin_should_not_be_ascending_or_descending(&digits)?;
+/// This function will check whether a pin meets the requirements of the PIN policy.
+pub fn validate_pin(pin: &str, policy: &PinPolicyConfiguration) -> Result<(), PinValidationError> {
+    pin_length_should_be_correct(pin, policy)?;
+    let digits = parse_pin_to_digits(pin, policy.allowed_characters)?;
+    pin_should_contain_enough_unique_digits(&digits, policy.min_unique_characters)?;
+    if !policy.allow_sequential {
+        pin_should_not_be_ascending_or_descending(&digits)?;
+    }
     Ok(())
 }
 
@@ -99,35 +96,56 @@ mod tests {
 
     #[test]
     fn valid_pin() {
-        assert!(matches!(validate_pin("024791"), Ok(())));
-        assert!(matches!(validate_pin("010101"), Ok(())));
-        assert!(matches!(validate_pin("000001"), Ok(())));
-        assert!(matches!(validate_pin("100000"), Ok(())));
+        assert!(matches!(
+            validate_pin("024791", &PinPolicyConfiguration::default()),
+            Ok(())
+        ));
+        assert!(matches!(
+            validate_pin("010101", &PinPolicyConfiguration::default()),
+            Ok(())
+        ));
+        assert!(matches!(
+            validate_pin("000001", &PinPolicyConfiguration::default()),
+            Ok(())
+        ));
+        assert!(matches!(
+            validate_pin("100000", &PinPolicyConfiguration::default()),
+            Ok(())
+        ));
     }
 
     #[test]
     fn pin_should_have_length_6() {
-        assert!(matches!(validate_pin("02479"), Err(PinValidationError::InvalidLength)));
         assert!(matches!(
-            validate_pin("0247913"),
+            validate_pin("02479", &PinPolicyConfiguration::default()),
+            Err(PinValidationError::InvalidLength)
+        ));
+        assert!(matches!(
+            validate_pin("0247913", &PinPolicyConfiguration::default()),
             Err(PinValidationError::InvalidLength)
         ));
     }
 
     #[test]
     fn pin_should_contain_only_digits() {
-        assert!(matches!(validate_pin("abcdef"), Err(PinValidationError::NonDigits)));
-        assert!(matches!(validate_pin("02479a"), Err(PinValidationError::NonDigits)));
+        assert!(matches!(
+            validate_pin("abcdef", &PinPolicyConfiguration::default()),
+            Err(PinValidationError::InvalidCharacters)
+        ));
+        assert!(matches!(
+            validate_pin("02479a", &PinPolicyConfiguration::default()),
+            Err(PinValidationError::InvalidCharacters)
+        ));
     }
 
     #[test]
     fn pin_should_contain_at_least_2_unique_digits() {
         assert!(matches!(
-            validate_pin("000000"),
+            validate_pin("000000", &PinPolicyConfiguration::default()),
             Err(PinValidationError::TooFewUniqueDigits)
         ));
         assert!(matches!(
-            validate_pin("999999"),
+            validate_pin("999999", &PinPolicyConfiguration::default()),
             Err(PinValidationError::TooFewUniqueDigits)
         ));
     }
@@ -135,11 +153,11 @@ mod tests {
     #[test]
     fn pin_should_not_contain_ascending_digits() {
         assert!(matches!(
-            validate_pin("012345"),
+            validate_pin("012345", &PinPolicyConfiguration::default()),
             Err(PinValidationError::AscendingDigits)
         ));
         assert!(matches!(
-            validate_pin("456789"),
+            validate_pin("456789", &PinPolicyConfiguration::default()),
             Err(PinValidationError::AscendingDigits)
         ));
     }
@@ -147,12 +165,53 @@ mod tests {
     #[test]
     fn pin_should_not_contain_descending_digits() {
         assert!(matches!(
-            validate_pin("543210"),
+            validate_pin("543210", &PinPolicyConfiguration::default()),
             Err(PinValidationError::DescendingDigits)
         ));
         assert!(matches!(
-            validate_pin("987654"),
+            validate_pin("987654", &PinPolicyConfiguration::default()),
             Err(PinValidationError::DescendingDigits)
         ));
     }
+
+    #[test]
+    fn pin_should_adhere_to_configured_policy() {
+        let policy = PinPolicyConfiguration {
+            min_length: 4,
+            max_length: 8,
+            allowed_characters: PinCharacters::Alphanumeric,
+            min_unique_characters: 3,
+            allow_sequential: true,
+        };
+
+        assert!(matches!(validate_pin("a1B2", &policy), Ok(())));
+        assert!(matches!(validate_pin("1234", &policy), Ok(())));
+        assert!(matches!(validate_pin("abcdefgh", &policy), Ok(())));
+        assert!(matches!(
+            validate_pin("a1b", &policy),
+            Err(PinValidationError::InvalidLength)
+        ));
+        assert!(matches!(
+            validate_pin("a1b2c3d4e", &policy),
+            Err(PinValidationError::InvalidLength)
+        ));
+        assert!(matches!(
+            validate_pin("a1b2!", &policy),
+            Err(PinValidationError::InvalidCharacters)
+        ));
+        assert!(matches!(
+            validate_pin("aAaA11", &policy),
+            Err(PinValidationError::TooFewUniqueDigits)
+        ));
+
+        let policy = PinPolicyConfiguration {
+            allow_sequential: false,
+            ..policy
+        };
+
+        assert!(matches!(
+            validate_pin("abcdef", &policy),
+            Err(PinValidationError::AscendingDigits)
+        ));
+    }
 }
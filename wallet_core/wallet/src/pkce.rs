@@ -17,6 +17,11 @@ pub trait PkcePair {
     where
         Self: Sized;
 
+    /// Recreate a pair from a previously generated code verifier, e.g. when restoring a persisted session.
+    fn from_code_verifier(code_verifier: String) -> Self
+    where
+        Self: Sized;
+
     /// The code verifier of this pair, which is a random string of characters.
     fn code_verifier(&self) -> &str;
 
@@ -39,6 +44,13 @@ impl PkcePair for S256PkcePair {
         // by the standard.
         let code_verifier = random_string(CODE_VERIFIER_LENGTH);
 
+        Self::from_code_verifier(code_verifier)
+    }
+
+    fn from_code_verifier(code_verifier: String) -> Self
+    where
+        Self: Sized,
+    {
         let hash = sha256(code_verifier.as_bytes());
         let code_challenge = BASE64_URL_SAFE_NO_PAD.encode(hash);
 
@@ -67,6 +79,10 @@ mod tests {
         assert_eq!(
             sha256(pair.code_verifier().as_bytes()),
             BASE64_URL_SAFE_NO_PAD.decode(pair.code_challenge()).unwrap()
-        )
+        );
+
+        let restored_pair = S256PkcePair::from_code_verifier(pair.code_verifier().to_string());
+
+        assert_eq!(restored_pair.code_challenge(), pair.code_challenge());
     }
 }
@@ -1,6 +1,12 @@
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use url::Url;
 
-use wallet_common::account::{messages::auth::WalletCertificate, serialization::Base64Bytes};
+use wallet_common::account::{
+    messages::auth::WalletCertificate,
+    serialization::{Base64Bytes, DerVerifyingKey},
+};
+
+use crate::digid::DigidSessionState;
 
 pub trait KeyedData: Serialize + DeserializeOwned {
     const KEY: &'static str;
@@ -17,6 +23,34 @@ pub struct InstructionData {
     pub instruction_sequence_number: u64,
 }
 
+/// Remote keys that were generated in the Wallet Provider in advance and have not been used yet.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KeyPoolData {
+    pub keys: Vec<PooledKey>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PooledKey {
+    pub identifier: String,
+    pub public_key: DerVerifyingKey,
+}
+
+/// A DigiD session that was started for issuance, which is persisted so that issuance
+/// can continue if the app is terminated while the user is authenticating in the browser.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DigidSessionData {
+    pub issuer_url: Url,
+    pub is_pid_renewal: bool,
+    pub session: DigidSessionState,
+}
+
+/// The highest version of the wallet configuration that was ever accepted. Configurations with a lower version are
+/// rejected, so that an old configuration cannot be replayed.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ConfigurationVersionData {
+    pub highest_version: u64,
+}
+
 impl KeyedData for RegistrationData {
     const KEY: &'static str = "registration";
 }
@@ -24,3 +58,15 @@ impl KeyedData for RegistrationData {
 impl KeyedData for InstructionData {
     const KEY: &'static str = "instructions";
 }
+
+impl KeyedData for KeyPoolData {
+    const KEY: &'static str = "key_pool";
+}
+
+impl KeyedData for DigidSessionData {
+    const KEY: &'static str = "digid_session";
+}
+
+impl KeyedData for ConfigurationVersionData {
+    const KEY: &'static str = "configuration_version";
+}
@@ -2,13 +2,13 @@ use std::{collections::HashSet, marker::PhantomData, path::PathBuf};
 
 use futures::try_join;
 use sea_orm::{
-    sea_query::Expr, ActiveModelTrait, ColumnTrait, EntityTrait, JoinType, QueryFilter, QueryOrder, QuerySelect,
-    RelationTrait, Select, Set, TransactionTrait,
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, DatabaseTransaction, EntityTrait, JoinType, QueryFilter,
+    QueryOrder, QuerySelect, RelationTrait, Select, Set, TransactionTrait,
 };
 use tokio::fs;
 use uuid::Uuid;
 
-use entity::{history_doc_type, history_event, history_event_doc_type, keyed_data, mdoc, mdoc_copy};
+use entity::{history_doc_type, history_event, history_event_doc_type, keyed_data, mdoc, mdoc_attribute, mdoc_copy};
 use nl_wallet_mdoc::{
     holder::MdocCopies,
     utils::serialization::{cbor_deserialize, cbor_serialize, CborError},
@@ -21,7 +21,7 @@ use super::{
     event_log::WalletEvent,
     key_file::{delete_key_file, get_or_create_key_file},
     sql_cipher_key::SqlCipherKey,
-    Storage, StorageError, StorageResult, StorageState, StoredMdocCopy,
+    Storage, StorageError, StorageResult, StorageState, StoredMdocAttribute, StoredMdocCopy,
 };
 
 const DATABASE_NAME: &str = "wallet";
@@ -130,6 +130,75 @@ where
 
         Ok(mdocs)
     }
+
+    /// Insert the mdocs, along with their copies and attributes, as part of the provided transaction.
+    async fn insert_mdocs_in_transaction(
+        transaction: &DatabaseTransaction,
+        mdocs: Vec<MdocCopies>,
+    ) -> StorageResult<()> {
+        // Construct a vec of tuples of 1 `mdoc`, 1 or more `mdoc_copy` and the `mdoc_attribute` models,
+        // based on the unique `MdocCopies`, to be inserted into the database.
+        let mdoc_models = mdocs
+            .into_iter()
+            .filter(|mdoc_copies| !mdoc_copies.cred_copies.is_empty())
+            .map(|mdoc_copies| {
+                let mdoc_id = Uuid::new_v4();
+
+                let copy_models = mdoc_copies
+                    .cred_copies
+                    .iter()
+                    .map(|mdoc| {
+                        let model = mdoc_copy::ActiveModel {
+                            id: Set(Uuid::new_v4()),
+                            mdoc_id: Set(mdoc_id),
+                            mdoc: Set(cbor_serialize(&mdoc)?),
+                            ..Default::default()
+                        };
+
+                        Ok(model)
+                    })
+                    .collect::<Result<Vec<_>, CborError>>()?;
+
+                // `mdoc_copies.cred_copies` is guaranteed to contain at least one value because of the filter() above.
+                // As all copies contain the same attributes, these are derived from the first copy.
+                let first_mdoc = mdoc_copies.cred_copies.into_iter().next().unwrap();
+                let attribute_models = StoredMdocAttribute::from_mdoc(mdoc_id, &first_mdoc)
+                    .into_iter()
+                    .map(|attribute| mdoc_attribute::ActiveModel::from(attribute.into_model()))
+                    .collect::<Vec<_>>();
+
+                let mdoc_model = mdoc::ActiveModel {
+                    id: Set(mdoc_id),
+                    doc_type: Set(first_mdoc.doc_type),
+                };
+
+                Ok((mdoc_model, copy_models, attribute_models))
+            })
+            .collect::<Result<Vec<_>, CborError>>()?;
+
+        // Make separate vecs out of the vec of tuples.
+        let (mdoc_models, copy_models, attribute_models) = mdoc_models.into_iter().fold(
+            (Vec::new(), Vec::new(), Vec::new()),
+            |(mut mdoc_models, mut copy_models, mut attribute_models), (mdoc_model, copies, attributes)| {
+                mdoc_models.push(mdoc_model);
+                copy_models.extend(copies);
+                attribute_models.extend(attributes);
+
+                (mdoc_models, copy_models, attribute_models)
+            },
+        );
+
+        mdoc::Entity::insert_many(mdoc_models).exec(transaction).await?;
+        mdoc_copy::Entity::insert_many(copy_models).exec(transaction).await?;
+
+        if !attribute_models.is_empty() {
+            mdoc_attribute::Entity::insert_many(attribute_models)
+                .exec(transaction)
+                .await?;
+        }
+
+        Ok(())
+    }
 }
 
 impl<K> Storage for DatabaseStorage<K>
@@ -172,7 +241,7 @@ where
 
         // Close and delete the database, only if this succeeds also delete the key file.
         database.close_and_delete().await?;
-        delete_key_file(&self.storage_path, &key_file_alias).await;
+        delete_key_file::<K>(&self.storage_path, &key_file_alias).await;
 
         Ok(())
     }
@@ -217,51 +286,64 @@ where
         Ok(())
     }
 
-    async fn insert_mdocs(&mut self, mdocs: Vec<MdocCopies>) -> StorageResult<()> {
-        // Construct a vec of tuples of 1 `mdoc` and 1 or more `mdoc_copy` models,
-        // based on the unique `MdocCopies`, to be inserted into the database.
-        let mdoc_models = mdocs
-            .into_iter()
-            .filter(|mdoc_copies| !mdoc_copies.cred_copies.is_empty())
-            .map(|mdoc_copies| {
-                let mdoc_id = Uuid::new_v4();
+    /// Delete data entry in the key-value table using the provided key, if present.
+    async fn delete_data<D: KeyedData>(&mut self) -> StorageResult<()> {
+        let database = self.database()?;
 
-                let copy_models = mdoc_copies
-                    .cred_copies
-                    .iter()
-                    .map(|mdoc| {
-                        let model = mdoc_copy::ActiveModel {
-                            id: Set(Uuid::new_v4()),
-                            mdoc_id: Set(mdoc_id),
-                            mdoc: Set(cbor_serialize(&mdoc)?),
-                            ..Default::default()
-                        };
+        keyed_data::Entity::delete_by_id(D::KEY)
+            .exec(database.connection())
+            .await?;
 
-                        Ok(model)
-                    })
-                    .collect::<Result<Vec<_>, CborError>>()?;
+        Ok(())
+    }
 
-                // `mdoc_copies.cred_copies` is guaranteed to contain at least one value because of the filter() above.
-                let doc_type = mdoc_copies.cred_copies.into_iter().next().unwrap().doc_type;
-                let mdoc_model = mdoc::ActiveModel {
-                    id: Set(mdoc_id),
-                    doc_type: Set(doc_type),
-                };
+    async fn insert_mdocs(&mut self, mdocs: Vec<MdocCopies>) -> StorageResult<()> {
+        let transaction = self.database()?.connection().begin().await?;
 
-                Ok((mdoc_model, copy_models))
-            })
-            .collect::<Result<Vec<_>, CborError>>()?;
+        Self::insert_mdocs_in_transaction(&transaction, mdocs).await?;
 
-        // Make two separate vecs out of the vec of tuples.
-        let (mdoc_models, copy_models): (Vec<_>, Vec<_>) = mdoc_models.into_iter().unzip();
+        transaction.commit().await?;
+
+        Ok(())
+    }
+
+    /// Replace all mdocs that have the same doctype as any of the provided mdocs in a single transaction, so that
+    /// either all of the existing mdocs are replaced or none are. Note that the history events that refer to the
+    /// replaced mdocs are not affected.
+    async fn replace_mdocs(&mut self, mdocs: Vec<MdocCopies>) -> StorageResult<()> {
+        let doc_types = mdocs
+            .iter()
+            .flat_map(|mdoc_copies| mdoc_copies.cred_copies.first())
+            .map(|mdoc| mdoc.doc_type.clone())
+            .collect::<HashSet<_>>();
 
         let transaction = self.database()?.connection().begin().await?;
 
-        mdoc::Entity::insert_many(mdoc_models).exec(&transaction).await?;
-        mdoc_copy::Entity::insert_many(copy_models.into_iter().flatten())
-            .exec(&transaction)
+        let mdoc_ids = mdoc::Entity::find()
+            .select_only()
+            .column(mdoc::Column::Id)
+            .filter(mdoc::Column::DocType.is_in(doc_types))
+            .into_tuple::<Uuid>()
+            .all(&transaction)
             .await?;
 
+        if !mdoc_ids.is_empty() {
+            mdoc_attribute::Entity::delete_many()
+                .filter(mdoc_attribute::Column::MdocId.is_in(mdoc_ids.clone()))
+                .exec(&transaction)
+                .await?;
+            mdoc_copy::Entity::delete_many()
+                .filter(mdoc_copy::Column::MdocId.is_in(mdoc_ids.clone()))
+                .exec(&transaction)
+                .await?;
+            mdoc::Entity::delete_many()
+                .filter(mdoc::Column::Id.is_in(mdoc_ids))
+                .exec(&transaction)
+                .await?;
+        }
+
+        Self::insert_mdocs_in_transaction(&transaction, mdocs).await?;
+
         transaction.commit().await?;
 
         Ok(())
@@ -295,6 +377,23 @@ where
         .await
     }
 
+    async fn fetch_mdoc_attributes(&self) -> StorageResult<Vec<StoredMdocAttribute>> {
+        let attributes = mdoc_attribute::Entity::find()
+            .find_also_related(mdoc::Entity)
+            .order_by_asc(mdoc_attribute::Column::MdocId)
+            .all(self.database()?.connection())
+            .await?;
+
+        // The `mdoc_id` column has a foreign key constraint, so the related `mdoc` is always present.
+        let attributes = attributes
+            .into_iter()
+            .filter_map(|(attribute, mdoc)| mdoc.map(|mdoc| (attribute, mdoc.doc_type)))
+            .map(|(attribute, doc_type)| StoredMdocAttribute::from_model(attribute, doc_type))
+            .collect::<Result<_, _>>()?;
+
+        Ok(attributes)
+    }
+
     async fn log_wallet_event(&mut self, event: WalletEvent) -> StorageResult<()> {
         let transaction = self.database()?.connection().begin().await?;
 
@@ -416,7 +515,7 @@ pub(crate) mod tests {
         account::messages::auth::WalletCertificate, keys::software::SoftwareEncryptionKey, utils::random_bytes,
     };
 
-    use crate::storage::data::RegistrationData;
+    use crate::storage::{data::RegistrationData, mdoc_attributes_match};
 
     use super::*;
 
@@ -437,7 +536,7 @@ pub(crate) mod tests {
         let database_path = storage.database_path_for_name(name);
 
         // Make sure we start with a clean slate.
-        delete_key_file(&storage.storage_path, &key_file_alias).await;
+        delete_key_file::<SoftwareEncryptionKey>(&storage.storage_path, &key_file_alias).await;
         _ = fs::remove_file(database_path).await;
 
         let database = storage
@@ -538,6 +637,18 @@ pub(crate) mod tests {
             registration.wallet_certificate.0
         );
 
+        // Delete the registration, after which it should no longer be present.
+        storage
+            .delete_data::<RegistrationData>()
+            .await
+            .expect("Could not delete registration");
+
+        let deleted_registration = storage
+            .fetch_data::<RegistrationData>()
+            .await
+            .expect("Could not get registration");
+        assert!(deleted_registration.is_none());
+
         // Clear database, state should be uninitialized.
         storage.clear().await.expect("Could not clear storage");
 
@@ -575,6 +686,15 @@ pub(crate) mod tests {
         let mdoc_copy1 = fetched_unique.first().unwrap();
         assert_eq!(&mdoc_copy1.mdoc, mdoc_copies.cred_copies.first().unwrap());
 
+        // The attributes of the mdoc should have been stored once, matching the unique mdoc.
+        let fetched_attributes = storage
+            .fetch_mdoc_attributes()
+            .await
+            .expect("Could not fetch mdoc attributes");
+
+        assert!(!fetched_attributes.is_empty());
+        assert!(mdoc_attributes_match(&fetched_unique, fetched_attributes));
+
         // Increment the usage count for this mdoc.
         storage
             .increment_mdoc_copies_usage_count(vec![mdoc_copy1.mdoc_copy_id])
@@ -631,6 +751,44 @@ pub(crate) mod tests {
         assert!(fetched_unique_doctype_mismatch.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_replace_mdocs() {
+        let mut storage = open_test_database_storage().await;
+
+        let trust_anchors = Examples::iaca_trust_anchors();
+        let mdoc = mdoc_mock::mdoc_from_example_device_response(trust_anchors);
+
+        // Insert mdocs and remember the identifier of the mdoc.
+        storage
+            .insert_mdocs(vec![MdocCopies::from([mdoc.clone(), mdoc.clone()].to_vec())])
+            .await
+            .expect("Could not insert mdocs");
+
+        let original_mdoc_id = storage.fetch_unique_mdocs().await.unwrap().first().unwrap().mdoc_id;
+
+        // Replace the mdocs with new copies of the same doctype.
+        storage
+            .replace_mdocs(vec![MdocCopies::from([mdoc.clone(), mdoc.clone(), mdoc].to_vec())])
+            .await
+            .expect("Could not replace mdocs");
+
+        // Only the new mdoc should be present, along with its attributes.
+        let fetched_unique = storage
+            .fetch_unique_mdocs()
+            .await
+            .expect("Could not fetch unique mdocs");
+
+        assert_eq!(fetched_unique.len(), 1);
+        assert_ne!(fetched_unique.first().unwrap().mdoc_id, original_mdoc_id);
+
+        let fetched_attributes = storage
+            .fetch_mdoc_attributes()
+            .await
+            .expect("Could not fetch mdoc attributes");
+
+        assert!(mdoc_attributes_match(&fetched_unique, fetched_attributes));
+    }
+
     #[tokio::test]
     async fn test_event_log_storage_ordering() {
         let mut storage = open_test_database_storage().await;
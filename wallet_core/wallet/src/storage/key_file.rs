@@ -25,17 +25,22 @@ pub async fn get_or_create_key_file<K: SecureEncryptionKey>(
     // Path to key file will be "<storage_path>/<alias>.key",
     // it will be encrypted with a key named "keyfile_<alias>".
     let path = path_for_key_file(storage_path, alias);
-    let encryption_key = K::new(&format!("{}{}", KEY_IDENTIFIER_PREFIX, alias));
+    let encryption_key = encryption_key_for_key_file::<K>(alias);
 
     // Decrypt file at path, create key and write to file if needed.
     get_or_create_encrypted_file_contents(path.as_path(), &encryption_key, || random_bytes(byte_length)).await
 }
 
-pub async fn delete_key_file(storage_path: &Path, alias: &str) {
+pub async fn delete_key_file<K: SecureEncryptionKey>(storage_path: &Path, alias: &str) {
     let path = path_for_key_file(storage_path, alias);
-    // Ignore any errors when removing the file,
+    // Ignore any errors when removing the file and its encryption key,
     // as we do not want this to propagate.
     let _ = fs::remove_file(&path).await;
+    let _ = encryption_key_for_key_file::<K>(alias).delete().await;
+}
+
+fn encryption_key_for_key_file<K: SecureEncryptionKey>(alias: &str) -> K {
+    K::new(&format!("{}{}", KEY_IDENTIFIER_PREFIX, alias))
 }
 
 fn path_for_key_file(storage_path: &Path, alias: &str) -> PathBuf {
@@ -168,8 +173,8 @@ mod tests {
         let storage_path = env::temp_dir();
 
         // Make sure we start with a clean slate.
-        delete_key_file(&storage_path, &alias1).await;
-        delete_key_file(&storage_path, &alias2).await;
+        delete_key_file::<SoftwareEncryptionKey>(&storage_path, &alias1).await;
+        delete_key_file::<SoftwareEncryptionKey>(&storage_path, &alias2).await;
 
         // Create three keys, two of them with the same alias.
         let key1 = get_or_create_key_file::<SoftwareEncryptionKey>(&storage_path, &alias1, byte_length)
@@ -188,7 +193,7 @@ mod tests {
         assert_eq!(key1, key1_again);
 
         // Cleanup after ourselves.
-        delete_key_file(&storage_path, &alias1).await;
-        delete_key_file(&storage_path, &alias2).await;
+        delete_key_file::<SoftwareEncryptionKey>(&storage_path, &alias1).await;
+        delete_key_file::<SoftwareEncryptionKey>(&storage_path, &alias2).await;
     }
 }
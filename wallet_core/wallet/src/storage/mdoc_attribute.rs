@@ -0,0 +1,194 @@
+use uuid::Uuid;
+
+use entity::mdoc_attribute::{self, ValueType};
+use nl_wallet_mdoc::{holder::Mdoc, DataElementValue};
+
+use super::StoredMdocCopy;
+
+/// An attribute of a stored mdoc, which is kept in a separate table so that it can be read without having to
+/// deserialize the mdoc itself. These are derived from the mdoc when it is inserted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredMdocAttribute {
+    pub mdoc_id: Uuid,
+    pub doc_type: String,
+    pub name_space: String,
+    pub name: String,
+    pub value: MdocAttributeValue,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MdocAttributeValue {
+    Text(String),
+    Bool(bool),
+    Integer(i64),
+    /// Any other value, rendered as JSON.
+    Other(serde_json::Value),
+}
+
+impl StoredMdocAttribute {
+    /// Derive the attributes to store from an [`Mdoc`], in the order in which they are present.
+    pub fn from_mdoc(mdoc_id: Uuid, mdoc: &Mdoc) -> Vec<Self> {
+        mdoc.attributes()
+            .into_iter()
+            .flat_map(|(name_space, entries)| entries.into_iter().map(move |entry| (name_space.clone(), entry)))
+            .map(|(name_space, entry)| StoredMdocAttribute {
+                mdoc_id,
+                doc_type: mdoc.doc_type.clone(),
+                name_space,
+                name: entry.name,
+                value: entry.value.into(),
+            })
+            .collect()
+    }
+
+    pub(super) fn from_model(model: mdoc_attribute::Model, doc_type: String) -> Result<Self, serde_json::Error> {
+        let value = match model.value_type {
+            ValueType::Text => MdocAttributeValue::Text(serde_json::from_value(model.value)?),
+            ValueType::Bool => MdocAttributeValue::Bool(serde_json::from_value(model.value)?),
+            ValueType::Integer => MdocAttributeValue::Integer(serde_json::from_value(model.value)?),
+            ValueType::Other => MdocAttributeValue::Other(model.value),
+        };
+
+        let attribute = StoredMdocAttribute {
+            mdoc_id: model.mdoc_id,
+            doc_type,
+            name_space: model.name_space,
+            name: model.name,
+            value,
+        };
+
+        Ok(attribute)
+    }
+
+    pub(super) fn into_model(self) -> mdoc_attribute::Model {
+        let (value_type, value) = match self.value {
+            MdocAttributeValue::Text(text) => (ValueType::Text, text.into()),
+            MdocAttributeValue::Bool(bool) => (ValueType::Bool, bool.into()),
+            MdocAttributeValue::Integer(integer) => (ValueType::Integer, integer.into()),
+            MdocAttributeValue::Other(value) => (ValueType::Other, value),
+        };
+
+        mdoc_attribute::Model {
+            id: Uuid::new_v4(),
+            mdoc_id: self.mdoc_id,
+            name_space: self.name_space,
+            name: self.name,
+            value_type,
+            value,
+        }
+    }
+
+    fn has_same_content(&self, other: &Self) -> bool {
+        self.doc_type == other.doc_type
+            && self.name_space == other.name_space
+            && self.name == other.name
+            && self.value == other.value
+    }
+}
+
+impl From<DataElementValue> for MdocAttributeValue {
+    fn from(value: DataElementValue) -> Self {
+        match value {
+            DataElementValue::Text(text) => Self::Text(text),
+            DataElementValue::Bool(bool) => Self::Bool(bool),
+            DataElementValue::Integer(integer) if i64::try_from(integer).is_ok() => {
+                // This `unwrap()` is safe because of the `is_ok()` check above.
+                Self::Integer(i64::try_from(integer).unwrap())
+            }
+            // Not every CBOR value can be represented as JSON, e.g. a map with non-text keys.
+            // In that case fall back to the debug representation of the value.
+            value => Self::Other(serde_json::to_value(&value).unwrap_or_else(|_| format!("{:?}", value).into())),
+        }
+    }
+}
+
+/// Check if the stored attributes are consistent with the source mdocs they were derived from. As the mdocs
+/// are the source of truth, a mismatch means that the stored attributes should not be relied upon. Note that
+/// the mdoc identifiers are not compared, as these are not guaranteed to be stable for every [`super::Storage`].
+pub fn mdoc_attributes_match(mdocs: &[StoredMdocCopy], mut attributes: Vec<StoredMdocAttribute>) -> bool {
+    for StoredMdocCopy { mdoc_id, mdoc, .. } in mdocs {
+        for expected in StoredMdocAttribute::from_mdoc(*mdoc_id, mdoc) {
+            match attributes
+                .iter()
+                .position(|attribute| attribute.has_same_content(&expected))
+            {
+                Some(index) => {
+                    attributes.swap_remove(index);
+                }
+                None => return false,
+            }
+        }
+    }
+
+    // Any attributes that remain do not have a source mdoc.
+    attributes.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use nl_wallet_mdoc::{examples::Examples, mock as mdoc_mock};
+
+    use super::*;
+
+    #[test]
+    fn test_mdoc_attribute_value_from_data_element_value() {
+        assert_eq!(
+            MdocAttributeValue::from(DataElementValue::Text("foo".to_string())),
+            MdocAttributeValue::Text("foo".to_string())
+        );
+        assert_eq!(
+            MdocAttributeValue::from(DataElementValue::Bool(true)),
+            MdocAttributeValue::Bool(true)
+        );
+        assert_eq!(
+            MdocAttributeValue::from(DataElementValue::Integer(42.into())),
+            MdocAttributeValue::Integer(42)
+        );
+        assert_eq!(
+            MdocAttributeValue::from(DataElementValue::Array(vec![DataElementValue::Bool(false)])),
+            MdocAttributeValue::Other(serde_json::json!([false]))
+        );
+    }
+
+    #[test]
+    fn test_stored_mdoc_attribute_model_roundtrip() {
+        let mdoc = mdoc_mock::mdoc_from_example_device_response(Examples::iaca_trust_anchors());
+        let attributes = StoredMdocAttribute::from_mdoc(Uuid::new_v4(), &mdoc);
+
+        assert!(!attributes.is_empty());
+
+        for attribute in attributes {
+            let model = attribute.clone().into_model();
+            let roundtrip = StoredMdocAttribute::from_model(model, attribute.doc_type.clone()).unwrap();
+
+            assert_eq!(roundtrip, attribute);
+        }
+    }
+
+    #[test]
+    fn test_mdoc_attributes_match() {
+        let mdoc = mdoc_mock::mdoc_from_example_device_response(Examples::iaca_trust_anchors());
+        let stored_mdoc = StoredMdocCopy {
+            mdoc_id: Uuid::new_v4(),
+            mdoc_copy_id: Uuid::new_v4(),
+            mdoc,
+        };
+        let mdocs = [stored_mdoc];
+        let attributes = StoredMdocAttribute::from_mdoc(mdocs[0].mdoc_id, &mdocs[0].mdoc);
+
+        // The attributes derived from the mdoc should match, regardless of their order.
+        assert!(mdoc_attributes_match(&mdocs, attributes.clone()));
+        assert!(mdoc_attributes_match(
+            &mdocs,
+            attributes.iter().rev().cloned().collect()
+        ));
+
+        // Missing, superfluous or changed attributes should not match.
+        assert!(!mdoc_attributes_match(&mdocs, attributes[1..].to_vec()));
+        assert!(!mdoc_attributes_match(&[], attributes.clone()));
+
+        let mut changed_attributes = attributes;
+        changed_attributes[0].value = MdocAttributeValue::Text("changed".to_string());
+        assert!(!mdoc_attributes_match(&mdocs, changed_attributes));
+    }
+}
@@ -9,7 +9,7 @@ use nl_wallet_mdoc::{holder::MdocCopies, utils::mdocs_map::MdocsMap};
 use super::{
     data::{KeyedData, RegistrationData},
     event_log::WalletEvent,
-    Storage, StorageResult, StorageState, StoredMdocCopy,
+    Storage, StorageResult, StorageState, StoredMdocAttribute, StoredMdocCopy,
 };
 
 /// This is a mock implementation of [`Storage`], used for testing [`crate::Wallet`].
@@ -108,6 +108,14 @@ impl Storage for MockStorage {
         Ok(())
     }
 
+    async fn delete_data<D: KeyedData>(&mut self) -> StorageResult<()> {
+        self.check_query_error()?;
+
+        self.data.remove(D::KEY);
+
+        Ok(())
+    }
+
     async fn insert_mdocs(&mut self, mdocs: Vec<MdocCopies>) -> StorageResult<()> {
         self.check_query_error()?;
 
@@ -116,6 +124,20 @@ impl Storage for MockStorage {
         Ok(())
     }
 
+    async fn replace_mdocs(&mut self, mdocs: Vec<MdocCopies>) -> StorageResult<()> {
+        self.check_query_error()?;
+
+        mdocs
+            .iter()
+            .flat_map(|mdoc_copies| mdoc_copies.cred_copies.first())
+            .for_each(|mdoc| {
+                self.mdocs.0.shift_remove(&mdoc.doc_type);
+            });
+        self.mdocs.add(mdocs.into_iter().flatten()).unwrap();
+
+        Ok(())
+    }
+
     async fn increment_mdoc_copies_usage_count(&mut self, mdoc_copy_ids: Vec<Uuid>) -> StorageResult<()> {
         mdoc_copy_ids.into_iter().for_each(|mdoc_copy_id| {
             self.mdoc_copies_usage_counts
@@ -159,6 +181,18 @@ impl Storage for MockStorage {
         Ok(mdocs)
     }
 
+    async fn fetch_mdoc_attributes(&self) -> StorageResult<Vec<StoredMdocAttribute>> {
+        // Derive the attributes from every unique Mdoc, as the database would have done on insertion.
+        let attributes = self
+            .fetch_unique_mdocs()
+            .await?
+            .into_iter()
+            .flat_map(|StoredMdocCopy { mdoc_id, mdoc, .. }| StoredMdocAttribute::from_mdoc(mdoc_id, &mdoc))
+            .collect();
+
+        Ok(attributes)
+    }
+
     async fn log_wallet_event(&mut self, event: WalletEvent) -> StorageResult<()> {
         // Convert to database entity and back to check whether the `TryFrom` implementations are complete.
         let entity = history_event::Model::try_from(event.clone())?;
@@ -231,6 +265,10 @@ pub(crate) mod tests {
 
         let fetched = storage.fetch_data::<Data>().await.unwrap().unwrap();
         assert_eq!(updated, fetched);
+
+        storage.delete_data::<Data>().await.unwrap();
+
+        assert!(storage.fetch_data::<Data>().await.unwrap().is_none());
     }
 
     #[tokio::test]
@@ -3,6 +3,7 @@ mod database;
 mod database_storage;
 mod event_log;
 mod key_file;
+mod mdoc_attribute;
 mod sql_cipher_key;
 
 #[cfg(any(test, feature = "mock"))]
@@ -19,10 +20,14 @@ use nl_wallet_mdoc::{
 };
 
 pub use self::{
-    data::{InstructionData, KeyedData, RegistrationData},
+    data::{
+        ConfigurationVersionData, DigidSessionData, InstructionData, KeyPoolData, KeyedData, PooledKey,
+        RegistrationData,
+    },
     database_storage::DatabaseStorage,
     event_log::{DocTypeMap, EventStatus, WalletEvent},
     key_file::KeyFileError,
+    mdoc_attribute::{mdoc_attributes_match, StoredMdocAttribute},
 };
 
 #[cfg(any(test, feature = "mock"))]
@@ -78,11 +83,14 @@ pub trait Storage {
     async fn fetch_data<D: KeyedData>(&self) -> StorageResult<Option<D>>;
     async fn insert_data<D: KeyedData>(&mut self, data: &D) -> StorageResult<()>;
     async fn update_data<D: KeyedData>(&mut self, data: &D) -> StorageResult<()>;
+    async fn delete_data<D: KeyedData>(&mut self) -> StorageResult<()>;
 
     async fn insert_mdocs(&mut self, mdocs: Vec<MdocCopies>) -> StorageResult<()>;
+    async fn replace_mdocs(&mut self, mdocs: Vec<MdocCopies>) -> StorageResult<()>;
     async fn increment_mdoc_copies_usage_count(&mut self, mdoc_copy_ids: Vec<Uuid>) -> StorageResult<()>;
     async fn fetch_unique_mdocs(&self) -> StorageResult<Vec<StoredMdocCopy>>;
     async fn fetch_unique_mdocs_by_doctypes(&self, doc_types: &HashSet<&str>) -> StorageResult<Vec<StoredMdocCopy>>;
+    async fn fetch_mdoc_attributes(&self) -> StorageResult<Vec<StoredMdocAttribute>>;
 
     async fn log_wallet_event(&mut self, event: WalletEvent) -> StorageResult<()>;
     async fn fetch_wallet_events(&self) -> StorageResult<Vec<WalletEvent>>;
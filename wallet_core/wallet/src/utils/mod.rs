@@ -1,2 +1,3 @@
 pub mod reqwest;
+pub mod retry;
 pub mod url;
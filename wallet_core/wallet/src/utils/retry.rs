@@ -0,0 +1,157 @@
+use std::{fmt::Display, future::Future, time::Duration};
+
+use tracing::warn;
+
+use wallet_common::config::wallet_config::RetryConfiguration;
+
+/// Whether executing a request more than once has the same effect as executing it once. This determines which
+/// failures can be retried: a request that is not idempotent may only be retried if it never reached the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Idempotency {
+    Idempotent,
+    NonIdempotent,
+}
+
+impl Idempotency {
+    /// Determine if a request can be retried after it failed with the provided `reqwest::Error`.
+    pub fn can_retry_reqwest_error(self, error: &reqwest::Error) -> bool {
+        // A connection error means that the request was never sent, so it cannot have been executed.
+        // Any other failure, most notably a timeout, may have occurred after the server received it.
+        error.is_connect() || (self == Self::Idempotent && error.is_timeout())
+    }
+}
+
+/// Execute `operation` until it succeeds, for at most the maximum number of attempts in `config`, while waiting
+/// with exponential backoff in between. Only errors for which `is_retryable` returns `true` are retried, any other
+/// error is returned immediately.
+pub async fn retry_with_backoff<T, E, F, Fut>(
+    config: &RetryConfiguration,
+    is_retryable: impl Fn(&E) -> bool,
+    mut operation: F,
+) -> Result<T, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    E: Display,
+{
+    let mut attempt = 1;
+
+    loop {
+        match operation().await {
+            Err(error) if attempt < config.max_attempts && is_retryable(&error) => {
+                let backoff = backoff_duration(config, attempt);
+
+                warn!(
+                    "Request failed on attempt {} of {}, retrying in {} ms: {}",
+                    attempt,
+                    config.max_attempts,
+                    backoff.as_millis(),
+                    error
+                );
+
+                tokio::time::sleep(backoff).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// The backoff after the specified attempt, which starts at 1.
+fn backoff_duration(config: &RetryConfiguration, attempt: u8) -> Duration {
+    let backoff_ms = u64::from(config.initial_backoff_ms)
+        .saturating_mul(2u64.saturating_pow(u32::from(attempt.saturating_sub(1))))
+        .min(u64::from(config.max_backoff_ms));
+
+    Duration::from_millis(backoff_ms)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU8, Ordering};
+
+    use tokio::time::Instant;
+
+    use super::*;
+
+    fn test_config() -> RetryConfiguration {
+        RetryConfiguration {
+            max_attempts: 4,
+            initial_backoff_ms: 100,
+            max_backoff_ms: 300,
+        }
+    }
+
+    #[test]
+    fn test_backoff_duration() {
+        let config = test_config();
+
+        let backoffs = (1..=4)
+            .map(|attempt| backoff_duration(&config, attempt).as_millis())
+            .collect::<Vec<_>>();
+
+        assert_eq!(backoffs, vec![100, 200, 300, 300]);
+        assert_eq!(backoff_duration(&config, u8::MAX).as_millis(), 300);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retry_with_backoff() {
+        let attempts = &AtomicU8::new(0);
+        let start = Instant::now();
+
+        // The operation succeeds on the third attempt, after backing off twice.
+        let result = retry_with_backoff(
+            &test_config(),
+            |_| true,
+            move || async move {
+                match attempts.fetch_add(1, Ordering::SeqCst) {
+                    0 | 1 => Err("transient error"),
+                    _ => Ok("success"),
+                }
+            },
+        )
+        .await;
+
+        assert_eq!(result, Ok("success"));
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+        assert_eq!(start.elapsed().as_millis(), 300);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retry_with_backoff_max_attempts() {
+        let attempts = &AtomicU8::new(0);
+
+        let result = retry_with_backoff(
+            &test_config(),
+            |_| true,
+            move || async move {
+                attempts.fetch_add(1, Ordering::SeqCst);
+
+                Err::<(), _>("transient error")
+            },
+        )
+        .await;
+
+        assert_eq!(result, Err("transient error"));
+        assert_eq!(attempts.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retry_with_backoff_not_retryable() {
+        let attempts = &AtomicU8::new(0);
+
+        let result = retry_with_backoff(
+            &test_config(),
+            |error| *error == "transient error",
+            move || async move {
+                attempts.fetch_add(1, Ordering::SeqCst);
+
+                Err::<(), _>("permanent error")
+            },
+        )
+        .await;
+
+        assert_eq!(result, Err("permanent error"));
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,52 @@
+use super::Wallet;
+
+impl<CR, S, PEK, APC, DGS, CIC, MDS> Wallet<CR, S, PEK, APC, DGS, CIC, MDS> {
+    /// Whether the device clock differs significantly from the clock of the Wallet Provider.
+    pub fn is_clock_skewed(&self) -> bool {
+        self.clock_skew.is_significant()
+    }
+
+    pub fn set_clock_skew_callback<F>(&self, callback: F)
+    where
+        F: FnMut(bool) + Send + Sync + 'static,
+    {
+        self.clock_skew.set_clock_skew_callback(callback);
+    }
+
+    pub fn clear_clock_skew_callback(&self) {
+        self.clock_skew.clear_clock_skew_callback()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use chrono::{Duration, Utc};
+
+    use super::super::tests::WalletWithMocks;
+
+    // Tests that the clock skew callback is called with the current state and when the skew becomes significant.
+    #[tokio::test]
+    async fn test_wallet_set_clear_clock_skew_callback() {
+        let wallet = WalletWithMocks::new_unregistered().await;
+
+        let states = Arc::new(Mutex::new(Vec::<bool>::with_capacity(2)));
+        let callback_states = Arc::clone(&states);
+
+        wallet.set_clock_skew_callback(move |is_skewed| callback_states.lock().unwrap().push(is_skewed));
+
+        assert!(!wallet.is_clock_skewed());
+        assert_eq!(*states.lock().unwrap(), vec![false]);
+
+        let now = Utc::now();
+        wallet.clock_skew.report_server_time(now + Duration::days(1), now);
+
+        assert!(wallet.is_clock_skewed());
+        assert_eq!(*states.lock().unwrap(), vec![false, true]);
+
+        wallet.clear_clock_skew_callback();
+
+        assert_eq!(Arc::strong_count(&states), 1);
+    }
+}
@@ -2,11 +2,11 @@ use std::sync::Arc;
 
 use wallet_common::config::wallet_config::WalletConfiguration;
 
-use crate::config::ObservableConfigurationRepository;
+use crate::config::{ConfigurationRepository, ObservableConfigurationRepository};
 
 use super::Wallet;
 
-impl<CR, S, PEK, APC, DGS, PIC, MDS> Wallet<CR, S, PEK, APC, DGS, PIC, MDS>
+impl<CR, S, PEK, APC, DGS, CIC, MDS> Wallet<CR, S, PEK, APC, DGS, CIC, MDS>
 where
     CR: ObservableConfigurationRepository,
 {
@@ -23,6 +23,16 @@ where
     }
 }
 
+impl<CR, S, PEK, APC, DGS, CIC, MDS> Wallet<CR, S, PEK, APC, DGS, CIC, MDS>
+where
+    CR: ConfigurationRepository,
+{
+    /// Whether the feature is enabled by the feature flags in the current configuration.
+    pub fn is_feature_enabled(&self, feature: &str) -> bool {
+        self.config_repository.config().feature_flags.is_enabled(feature)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::{Arc, Mutex};
@@ -78,4 +88,17 @@ mod tests {
 
         assert_eq!(Arc::strong_count(&configs), 1);
     }
+
+    #[tokio::test]
+    async fn test_wallet_is_feature_enabled() {
+        let mut config = default_configuration();
+        config.feature_flags = [("enabled_feature", true), ("disabled_feature", false)]
+            .into_iter()
+            .collect();
+        let wallet = WalletWithMocks::new_unregistered_with_config(config).await;
+
+        assert!(wallet.is_feature_enabled("enabled_feature"));
+        assert!(!wallet.is_feature_enabled("disabled_feature"));
+        assert!(!wallet.is_feature_enabled("unknown_feature"));
+    }
 }
@@ -0,0 +1,57 @@
+use super::Wallet;
+
+impl<CR, S, PEK, APC, DGS, CIC, MDS> Wallet<CR, S, PEK, APC, DGS, CIC, MDS> {
+    pub fn is_online(&self) -> bool {
+        self.connectivity.is_online()
+    }
+
+    pub fn set_connectivity_callback<F>(&self, callback: F)
+    where
+        F: FnMut(bool) + Send + Sync + 'static,
+    {
+        self.connectivity.set_connectivity_callback(callback);
+    }
+
+    pub fn clear_connectivity_callback(&self) {
+        self.connectivity.clear_connectivity_callback()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::account_provider::AccountProviderError;
+
+    use super::super::tests::WalletWithMocks;
+
+    // Tests that the connectivity callback is called with the current state and when a request fails to connect.
+    #[tokio::test]
+    async fn test_wallet_set_clear_connectivity_callback() {
+        let wallet = WalletWithMocks::new_unregistered().await;
+
+        let states = Arc::new(Mutex::new(Vec::<bool>::with_capacity(2)));
+        let callback_states = Arc::clone(&states);
+
+        wallet.set_connectivity_callback(move |is_online| callback_states.lock().unwrap().push(is_online));
+
+        assert!(wallet.is_online());
+        assert_eq!(*states.lock().unwrap(), vec![true]);
+
+        let connect_error = reqwest::Client::new()
+            .get("http://127.0.0.1:9")
+            .send()
+            .await
+            .expect_err("request should fail to connect");
+        wallet
+            .connectivity
+            .report::<(), _>(&Err(AccountProviderError::Networking(connect_error)));
+
+        assert!(!wallet.is_online());
+        assert_eq!(*states.lock().unwrap(), vec![true, false]);
+
+        wallet.clear_connectivity_callback();
+
+        assert_eq!(Arc::strong_count(&states), 1);
+    }
+}
@@ -0,0 +1,12 @@
+use tracing::info;
+
+use super::Wallet;
+
+impl<CR, S, PEK, APC, DGS, CIC, MDS> Wallet<CR, S, PEK, APC, DGS, CIC, MDS> {
+    /// Start a new high-level operation, such as registration or a disclosure. The correlation ID of the operation is
+    /// sent along with every request to the Wallet Provider, the issuer and the verifier while performing it.
+    pub(super) fn start_operation(&self) {
+        let correlation_id = self.correlation.start_operation();
+        info!("Starting operation with correlation ID: {}", correlation_id);
+    }
+}
@@ -0,0 +1,155 @@
+use tracing::{info, instrument};
+
+use platform_support::hw_keystore::PlatformEcdsaKey;
+use wallet_common::account::messages::instructions::{ExportData, ExportDataResult};
+
+use crate::{
+    account_provider::AccountProviderClient,
+    config::ConfigurationRepository,
+    instruction::{InstructionClient, InstructionError},
+    storage::Storage,
+};
+
+use super::Wallet;
+
+#[derive(Debug, thiserror::Error)]
+pub enum WalletDataExportError {
+    #[error("wallet is not registered")]
+    NotRegistered,
+    #[error("wallet is locked")]
+    Locked,
+    #[error("error sending instruction to Wallet Provider: {0}")]
+    Instruction(#[from] InstructionError),
+}
+
+impl<CR, S, PEK, APC, DGS, CIC, MDS> Wallet<CR, S, PEK, APC, DGS, CIC, MDS> {
+    /// Request all data the Wallet Provider holds about this wallet, using an instruction that is confirmed with the
+    /// PIN. The export is returned as signed instruction result, of which the signature is verified.
+    #[instrument(skip_all)]
+    pub async fn request_data_export(&self, pin: String) -> Result<ExportDataResult, WalletDataExportError>
+    where
+        CR: ConfigurationRepository,
+        S: Storage,
+        PEK: PlatformEcdsaKey,
+        APC: AccountProviderClient,
+    {
+        self.start_operation();
+        info!("Requesting data export");
+
+        info!("Checking if registered");
+        let registration_data = self
+            .registration
+            .as_ref()
+            .ok_or_else(|| WalletDataExportError::NotRegistered)?;
+
+        info!("Checking if locked");
+        if self.lock.is_locked() {
+            return Err(WalletDataExportError::Locked);
+        }
+
+        let config = self.config_repository.config();
+
+        let instruction_result_public_keys = config.account_server.instruction_result_public_keys();
+
+        let remote_instruction = InstructionClient::new(
+            pin,
+            &self.storage,
+            &self.hw_privkey,
+            &self.account_provider_client,
+            registration_data,
+            &config.account_server.base_url,
+            &instruction_result_public_keys,
+        );
+
+        info!("Sending export data instruction to Wallet Provider");
+        let result = remote_instruction.send(ExportData).await?;
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+
+    use wallet_common::{
+        account::messages::instructions::{Instruction, InstructionResultClaims},
+        jwt::Jwt,
+        utils,
+    };
+
+    use super::{
+        super::tests::{WalletWithMocks, ACCOUNT_SERVER_KEYS},
+        *,
+    };
+
+    const PIN: &str = "051097";
+
+    #[tokio::test]
+    async fn test_wallet_request_data_export() {
+        let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
+
+        let challenge = utils::random_bytes(32);
+        wallet
+            .account_provider_client
+            .expect_instruction_challenge()
+            .return_once(move |_, _| Ok(challenge));
+
+        let export = ExportDataResult {
+            wallet_id: "wallet_id".to_string(),
+            hw_pubkey: (*ACCOUNT_SERVER_KEYS.certificate_signing_key.verifying_key()).into(),
+            registration_date_time: None,
+            instruction_sequence_number: 1,
+            unsuccessful_pin_entries: 0,
+            last_unsuccessful_pin_entry: None,
+            keys: vec![],
+            events: vec![],
+        };
+        let result_claims = InstructionResultClaims {
+            result: export.clone(),
+            iss: "wallet_unit_test".to_string(),
+            iat: jsonwebtoken::get_current_timestamp(),
+        };
+        let result = Jwt::sign_with_sub(&result_claims, &ACCOUNT_SERVER_KEYS.instruction_result_signing_key)
+            .await
+            .unwrap();
+
+        wallet
+            .account_provider_client
+            .expect_instruction()
+            .return_once(move |_, _: Instruction<ExportData>| Ok(result));
+
+        let result = wallet
+            .request_data_export(PIN.to_string())
+            .await
+            .expect("Could not request data export");
+
+        assert_eq!(result, export);
+    }
+
+    #[tokio::test]
+    async fn test_wallet_request_data_export_error_not_registered() {
+        let wallet = WalletWithMocks::new_unregistered().await;
+
+        let error = wallet
+            .request_data_export(PIN.to_string())
+            .await
+            .expect_err("Data export should have resulted in error");
+
+        assert_matches!(error, WalletDataExportError::NotRegistered);
+    }
+
+    #[tokio::test]
+    async fn test_wallet_request_data_export_error_locked() {
+        let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
+
+        wallet.lock();
+
+        let error = wallet
+            .request_data_export(PIN.to_string())
+            .await
+            .expect_err("Data export should have resulted in error");
+
+        assert_matches!(error, WalletDataExportError::Locked);
+    }
+}
@@ -0,0 +1,187 @@
+use tracing::{info, instrument};
+
+use platform_support::hw_keystore::PlatformEcdsaKey;
+use wallet_common::account::messages::instructions::DeleteWallet;
+
+use crate::{
+    account_provider::AccountProviderClient,
+    config::ConfigurationRepository,
+    instruction::{InstructionClient, InstructionError},
+    storage::Storage,
+};
+
+use super::{Wallet, WalletResetError};
+
+#[derive(Debug, thiserror::Error)]
+pub enum WalletDeregistrationError {
+    #[error("wallet is not registered")]
+    NotRegistered,
+    #[error("wallet is locked")]
+    Locked,
+    #[error("error sending instruction to Wallet Provider: {0}")]
+    Instruction(#[from] InstructionError),
+    #[error("could not remove local wallet data: {0}")]
+    LocalState(#[from] WalletResetError),
+}
+
+impl<CR, S, PEK, APC, DGS, CIC, MDS> Wallet<CR, S, PEK, APC, DGS, CIC, MDS> {
+    /// Delete the account of the wallet at the Wallet Provider, using an instruction that is confirmed with the PIN.
+    /// After the Wallet Provider has deleted the account, all local data is removed, which returns the wallet
+    /// to the unregistered state. If the instruction fails, the wallet is left untouched.
+    #[instrument(skip_all)]
+    pub async fn deregister(&mut self, pin: String) -> Result<(), WalletDeregistrationError>
+    where
+        CR: ConfigurationRepository,
+        S: Storage,
+        PEK: PlatformEcdsaKey,
+        APC: AccountProviderClient,
+    {
+        self.start_operation();
+        info!("Deregistering wallet");
+
+        info!("Checking if registered");
+        let registration_data = self
+            .registration
+            .as_ref()
+            .ok_or_else(|| WalletDeregistrationError::NotRegistered)?;
+
+        info!("Checking if locked");
+        if self.lock.is_locked() {
+            return Err(WalletDeregistrationError::Locked);
+        }
+
+        let config = self.config_repository.config();
+
+        let instruction_result_public_keys = config.account_server.instruction_result_public_keys();
+
+        let remote_instruction = InstructionClient::new(
+            pin,
+            &self.storage,
+            &self.hw_privkey,
+            &self.account_provider_client,
+            registration_data,
+            &config.account_server.base_url,
+            &instruction_result_public_keys,
+        );
+
+        info!("Sending delete wallet instruction to Wallet Provider");
+        remote_instruction.send(DeleteWallet).await?;
+
+        info!("Wallet deleted at Wallet Provider, removing local data");
+        self.clear_local_state().await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+
+    use assert_matches::assert_matches;
+    use http::StatusCode;
+
+    use wallet_common::{
+        account::messages::instructions::{Instruction, InstructionResultClaims},
+        jwt::Jwt,
+        utils,
+    };
+
+    use crate::account_provider::AccountProviderResponseError;
+
+    use super::{
+        super::tests::{WalletWithMocks, ACCOUNT_SERVER_KEYS},
+        *,
+    };
+
+    const PIN: &str = "051097";
+
+    #[tokio::test]
+    async fn test_wallet_deregister() {
+        let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
+
+        let challenge = utils::random_bytes(32);
+        wallet
+            .account_provider_client
+            .expect_instruction_challenge()
+            .return_once(move |_, _| Ok(challenge));
+
+        let result_claims = InstructionResultClaims {
+            result: (),
+            iss: "wallet_unit_test".to_string(),
+            iat: jsonwebtoken::get_current_timestamp(),
+        };
+        let result = Jwt::sign_with_sub(&result_claims, &ACCOUNT_SERVER_KEYS.instruction_result_signing_key)
+            .await
+            .unwrap();
+
+        let wallet_cert = wallet.registration.as_ref().unwrap().wallet_certificate.clone();
+        wallet.account_provider_client.expect_instruction().return_once(
+            move |_, instruction: Instruction<DeleteWallet>| {
+                assert_eq!(instruction.certificate.0, wallet_cert.0);
+
+                Ok(result)
+            },
+        );
+
+        wallet
+            .deregister(PIN.to_string())
+            .await
+            .expect("Could not deregister wallet");
+
+        // The wallet should now be unregistered, locked and its storage should be empty.
+        assert!(!wallet.has_registration());
+        assert!(wallet.is_locked());
+        assert!(wallet.storage.read().await.data.is_empty());
+        assert!(wallet.hw_privkey.is_deleted.load(Ordering::Relaxed));
+    }
+
+    #[tokio::test]
+    async fn test_wallet_deregister_error_not_registered() {
+        let mut wallet = WalletWithMocks::new_unregistered().await;
+
+        let error = wallet
+            .deregister(PIN.to_string())
+            .await
+            .expect_err("Wallet deregistration should have resulted in error");
+
+        assert_matches!(error, WalletDeregistrationError::NotRegistered);
+    }
+
+    #[tokio::test]
+    async fn test_wallet_deregister_error_locked() {
+        let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
+
+        wallet.lock();
+
+        let error = wallet
+            .deregister(PIN.to_string())
+            .await
+            .expect_err("Wallet deregistration should have resulted in error");
+
+        assert_matches!(error, WalletDeregistrationError::Locked);
+        assert!(wallet.has_registration());
+    }
+
+    #[tokio::test]
+    async fn test_wallet_deregister_error_instruction() {
+        let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
+
+        wallet
+            .account_provider_client
+            .expect_instruction_challenge()
+            .return_once(|_, _| Err(AccountProviderResponseError::Status(StatusCode::INTERNAL_SERVER_ERROR).into()));
+
+        let error = wallet
+            .deregister(PIN.to_string())
+            .await
+            .expect_err("Wallet deregistration should have resulted in error");
+
+        assert_matches!(error, WalletDeregistrationError::Instruction(_));
+
+        // Nothing should have been removed from the wallet.
+        assert!(wallet.has_registration());
+        assert!(!wallet.storage.read().await.data.is_empty());
+        assert!(!wallet.hw_privkey.is_deleted.load(Ordering::Relaxed));
+    }
+}
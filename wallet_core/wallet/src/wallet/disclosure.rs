@@ -7,9 +7,10 @@ use url::Url;
 use uuid::Uuid;
 
 use nl_wallet_mdoc::{
-    holder::{MdocDataSource, ProposedAttributes, StoredMdoc},
+    holder::{DisclosurePreview, MdocDataSource, ProposedAttributes, StoredMdoc},
     server_keys::KeysError,
     utils::{cose::CoseError, reader_auth::ReaderRegistration, x509::Certificate},
+    DeviceRequest, ItemsRequest,
 };
 
 use crate::{
@@ -33,6 +34,24 @@ pub struct DisclosureProposal {
     pub reader_registration: ReaderRegistration,
 }
 
+/// The result of previewing a disclosure request, without contacting the verifier.
+#[derive(Debug, Clone)]
+pub enum DisclosureRequestPreview {
+    Available(Vec<DisclosureDocument>),
+    MissingAttributes(MissingAttributesReport),
+}
+
+/// A report on the requested attributes that are not available in the wallet, split by whether the wallet holds
+/// a document of the requested doctype at all. This allows the user to be guided to obtain the missing documents
+/// first, before attempting disclosure again.
+#[derive(Debug, Clone, Default)]
+pub struct MissingAttributesReport {
+    /// The attributes of doctypes for which the wallet does not hold any document.
+    pub missing_documents: Vec<MissingDisclosureAttributes>,
+    /// The attributes that are absent from the documents of the requested doctype that the wallet does hold.
+    pub incomplete_documents: Vec<MissingDisclosureAttributes>,
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum DisclosureError {
     #[error("wallet is not registered")]
@@ -45,6 +64,8 @@ pub enum DisclosureError {
     DisclosureUri(#[source] DisclosureUriError),
     #[error("error in mdoc disclosure session: {0}")]
     DisclosureSession(#[source] nl_wallet_mdoc::Error),
+    #[error("relying party is not known in the registry")]
+    UnknownRelyingParty,
     #[error("not all requested attributes are available, missing: {missing_attributes:?}")]
     AttributesNotAvailable {
         reader_registration: Box<ReaderRegistration>,
@@ -56,11 +77,13 @@ pub enum DisclosureError {
     Instruction(#[source] InstructionError),
     #[error("could not increment usage count of mdoc copies in database: {0}")]
     IncrementUsageCount(#[source] StorageError),
+    #[error("could not fetch mdocs from database: {0}")]
+    MdocStorage(#[source] StorageError),
     #[error("could not store history in database: {0}")]
     HistoryStorage(#[source] StorageError),
 }
 
-impl<CR, S, PEK, APC, DGS, PIC, MDS> Wallet<CR, S, PEK, APC, DGS, PIC, MDS>
+impl<CR, S, PEK, APC, DGS, CIC, MDS> Wallet<CR, S, PEK, APC, DGS, CIC, MDS>
 where
     CR: ConfigurationRepository,
     MDS: MdocDisclosureSession<Self>,
@@ -68,6 +91,7 @@ where
 {
     #[instrument(skip_all)]
     pub async fn start_disclosure(&mut self, uri: &Url) -> Result<DisclosureProposal, DisclosureError> {
+        self.start_operation();
         info!("Performing disclosure based on received URI: {}", uri);
 
         info!("Checking if registered");
@@ -93,40 +117,72 @@ where
             .map_err(DisclosureError::DisclosureUri)?;
 
         // Start the disclosure session based on the `ReaderEngagement`.
-        let session = MDS::start(disclosure_uri, self, &config.rp_trust_anchors())
-            .await
+        let session = self
+            .connectivity
+            .observe(MDS::start(disclosure_uri, self, &config.rp_trust_anchors(), &self.correlation).await)
             .map_err(DisclosureError::DisclosureSession)?;
 
+        // When the configuration contains a registry of relying parties, only those relying parties are trusted.
+        if !config.is_known_rp(session.rp_certificate().as_bytes()) {
+            info!("Relying party is not known in the registry, terminating disclosure session");
+
+            session.terminate().await.map_err(DisclosureError::DisclosureSession)?;
+
+            return Err(DisclosureError::UnknownRelyingParty);
+        }
+
+        let result = Self::proposal_from_session(&session);
+
+        // Retain the session as `Wallet` state, unless its attributes could not be interpreted. Note that this
+        // includes the case where attributes are missing. If these can be shown to the user, the session should
+        // only be terminated on user interaction. This prevents gleaning of missing attributes by a verifier.
+        // TODO: What to do when the missing attributes could not be translated?
+        //       In that case there is no way we can terminate the session with
+        //       user interaction, since the missing attributes cannot be presented.
+        if !matches!(result, Err(DisclosureError::MdocAttributes(_))) {
+            self.disclosure_session.replace(session);
+        }
+
+        result
+    }
+
+    /// Return the proposal of the active disclosure session, as was previously returned by
+    /// [`Wallet::start_disclosure()`]. This allows the proposal to be shown again to the user.
+    pub fn disclosure_proposal(&self) -> Result<DisclosureProposal, DisclosureError> {
+        info!("Checking if registered");
+        if self.registration.is_none() {
+            return Err(DisclosureError::NotRegistered);
+        }
+
+        info!("Checking if locked");
+        if self.lock.is_locked() {
+            return Err(DisclosureError::Locked);
+        }
+
+        info!("Checking if a disclosure session is present");
+        let session = self.disclosure_session.as_ref().ok_or(DisclosureError::SessionState)?;
+
+        Self::proposal_from_session(session)
+    }
+
+    fn proposal_from_session(session: &MDS) -> Result<DisclosureProposal, DisclosureError> {
         let proposal_session = match session.session_state() {
             MdocDisclosureSessionState::MissingAttributes(missing_attr_session) => {
                 // Translate the missing attributes into a `Vec<MissingDisclosureAttributes>`.
-                // If this fails, return `DisclosureError::AttributeMdoc` instead.
+                // If this fails, return `DisclosureError::MdocAttributes` instead.
                 info!(
                     "At least one attribute is missing in order to satisfy the disclosure request, \
                     attempting to translate to MissingDisclosureAttributes"
                 );
 
                 let missing_attributes = missing_attr_session.missing_attributes().to_vec();
-                let error = match MissingDisclosureAttributes::from_mdoc_missing_attributes(missing_attributes) {
-                    Ok(attributes) => {
-                        // If the missing attributes can be translated and shown to the user,
-                        // store the session so that it will only be terminated on user interaction.
-                        // This prevents gleaning of missing attributes by a verifier.
-                        let reader_registration = session.reader_registration().clone().into();
-                        self.disclosure_session.replace(session);
-
-                        DisclosureError::AttributesNotAvailable {
-                            reader_registration,
-                            missing_attributes: attributes,
-                        }
-                    }
-                    // TODO: What to do when the missing attributes could not be translated?
-                    //       In that case there is no way we can terminate the session with
-                    //       user interaction, since the missing attributes cannot be presented.
-                    Err(error) => DisclosureError::MdocAttributes(error),
-                };
+                let missing_attributes = MissingDisclosureAttributes::from_mdoc_missing_attributes(missing_attributes)
+                    .map_err(DisclosureError::MdocAttributes)?;
 
-                return Err(error);
+                return Err(DisclosureError::AttributesNotAvailable {
+                    reader_registration: session.reader_registration().clone().into(),
+                    missing_attributes,
+                });
             }
             MdocDisclosureSessionState::Proposal(proposal_session) => proposal_session,
         };
@@ -147,9 +203,6 @@ where
             reader_registration: session.reader_registration().clone(),
         };
 
-        // Retain the session as `Wallet` state.
-        self.disclosure_session.replace(session);
-
         Ok(proposal)
     }
 
@@ -167,7 +220,18 @@ where
         }
 
         info!("Checking if a disclosure session is present");
-        let session = self.disclosure_session.take().ok_or(DisclosureError::SessionState)?;
+        if self.disclosure_session.is_none() {
+            return Err(DisclosureError::SessionState);
+        }
+
+        self.terminate_disclosure_session().await
+    }
+
+    /// Terminate the current disclosure session, if any, and record its cancellation in the history.
+    pub(super) async fn terminate_disclosure_session(&mut self) -> Result<(), DisclosureError> {
+        let Some(session) = self.disclosure_session.take() else {
+            return Ok(());
+        };
 
         // Prepare history events from session before terminating session
         let event = WalletEvent::new_disclosure(None, session.rp_certificate().clone(), EventStatus::Cancelled);
@@ -242,12 +306,13 @@ where
         //       to the verifier, as we do not know if disclosure fails before or after the
         //       verifier has received the attributes.
 
-        if let Err(error) = self
+        let increment_result = self
             .storage
-            .get_mut()
-            .increment_mdoc_copies_usage_count(session_proposal.proposed_source_identifiers())
+            .write()
             .await
-        {
+            .increment_mdoc_copies_usage_count(session_proposal.proposed_source_identifiers())
+            .await;
+        if let Err(error) = increment_result {
             self.log_empty_disclosure_error(
                 session.rp_certificate().clone(),
                 "Failed to register shared mdoc copy".to_string(),
@@ -259,7 +324,7 @@ where
         // Prepare the `RemoteEcdsaKeyFactory` for signing using the provided PIN.
         let config = self.config_repository.config();
 
-        let instruction_result_public_key = config.account_server.instruction_result_public_key.clone().into();
+        let instruction_result_public_keys = config.account_server.instruction_result_public_keys();
         let remote_instruction = InstructionClient::new(
             pin,
             &self.storage,
@@ -267,7 +332,7 @@ where
             &self.account_provider_client,
             registration_data,
             &config.account_server.base_url,
-            &instruction_result_public_key,
+            &instruction_result_public_keys,
         );
         let remote_key_factory = RemoteEcdsaKeyFactory::new(&remote_instruction);
 
@@ -293,6 +358,7 @@ where
                 }
                 _ => DisclosureError::DisclosureSession(error.error),
             };
+            self.connectivity.report_error(&error);
             return Err(error);
         }
 
@@ -316,7 +382,100 @@ where
     }
 }
 
-impl<CR, S, PEK, APC, DGS, PIC, MDS> MdocDataSource for Wallet<CR, S, PEK, APC, DGS, PIC, MDS>
+impl<CR, S, PEK, APC, DGS, CIC, MDS> Wallet<CR, S, PEK, APC, DGS, CIC, MDS>
+where
+    S: Storage,
+{
+    /// Check whether the attributes in `items_requests` could be disclosed, without contacting a verifier.
+    /// This can be used to check in advance if a request can be satisfied, e.g. when the request was
+    /// scanned from a static QR code that describes what a relying party will ask for.
+    #[instrument(skip_all)]
+    pub async fn preview_request(
+        &self,
+        items_requests: Vec<ItemsRequest>,
+    ) -> Result<DisclosureRequestPreview, DisclosureError> {
+        info!("Previewing disclosure request");
+
+        info!("Checking if registered");
+        if self.registration.is_none() {
+            return Err(DisclosureError::NotRegistered);
+        }
+
+        info!("Checking if locked");
+        if self.lock.is_locked() {
+            return Err(DisclosureError::Locked);
+        }
+
+        let preview = DeviceRequest::new(items_requests)
+            .preview(self)
+            .await
+            .map_err(DisclosureError::DisclosureSession)?;
+
+        let result = match preview {
+            DisclosurePreview::MissingAttributes(missing_attributes) => {
+                info!("At least one attribute is missing in order to satisfy the disclosure request");
+
+                let missing_attributes = MissingDisclosureAttributes::from_mdoc_missing_attributes(missing_attributes)
+                    .map_err(DisclosureError::MdocAttributes)?;
+                let report = self
+                    .missing_attributes_report(missing_attributes)
+                    .await
+                    .map_err(DisclosureError::MdocStorage)?;
+
+                DisclosureRequestPreview::MissingAttributes(report)
+            }
+            DisclosurePreview::Proposal {
+                proposed_attributes, ..
+            } => {
+                info!("All attributes in the disclosure request are present in the database");
+
+                let documents = proposed_attributes
+                    .into_iter()
+                    .map(|(doc_type, attributes)| DisclosureDocument::from_mdoc_attributes(&doc_type, attributes))
+                    .collect::<Result<_, _>>()
+                    .map_err(DisclosureError::MdocAttributes)?;
+
+                DisclosureRequestPreview::Available(documents)
+            }
+        };
+
+        Ok(result)
+    }
+
+    /// Split the missing attributes by whether the wallet holds any document of their doctype.
+    async fn missing_attributes_report(
+        &self,
+        missing_attributes: Vec<MissingDisclosureAttributes>,
+    ) -> Result<MissingAttributesReport, StorageError> {
+        let doc_types = missing_attributes
+            .iter()
+            .map(|attributes| attributes.doc_type)
+            .collect::<HashSet<_>>();
+
+        let held_doc_types = self
+            .storage
+            .read()
+            .await
+            .fetch_unique_mdocs_by_doctypes(&doc_types)
+            .await?
+            .into_iter()
+            .map(|StoredMdocCopy { mdoc, .. }| mdoc.doc_type)
+            .collect::<HashSet<_>>();
+
+        let (incomplete_documents, missing_documents) = missing_attributes
+            .into_iter()
+            .partition(|attributes| held_doc_types.contains(attributes.doc_type));
+
+        let report = MissingAttributesReport {
+            missing_documents,
+            incomplete_documents,
+        };
+
+        Ok(report)
+    }
+}
+
+impl<CR, S, PEK, APC, DGS, CIC, MDS> MdocDataSource for Wallet<CR, S, PEK, APC, DGS, CIC, MDS>
 where
     S: Storage,
 {
@@ -359,7 +518,10 @@ where
 
 #[cfg(test)]
 mod tests {
-    use std::sync::{atomic::Ordering, Arc, Mutex};
+    use std::{
+        sync::{atomic::Ordering, Arc, Mutex},
+        time::Duration,
+    };
 
     use assert_matches::assert_matches;
     use itertools::Itertools;
@@ -371,10 +533,13 @@ mod tests {
         mock as mdoc_mock, verifier::SessionType, DataElementValue,
     };
     use uuid::uuid;
+    use wallet_common::{config::wallet_config::RelyingPartyRegistration, utils};
 
     use crate::{
+        config::{LocalConfigurationRepository, UpdatingConfigurationRepository},
+        connectivity::Connectivity,
         disclosure::{MockMdocDisclosureMissingAttributes, MockMdocDisclosureProposal, MockMdocDisclosureSession},
-        Attribute, AttributeValue, EventStatus,
+        document, Attribute, AttributeValue, EventStatus,
     };
 
     use super::{super::tests::WalletWithMocks, *};
@@ -444,7 +609,7 @@ mod tests {
         );
 
         // Starting disclosure should not cause mdoc copy usage counts to be incremented.
-        assert!(wallet.storage.get_mut().mdoc_copies_usage_counts.is_empty());
+        assert!(wallet.storage.read().await.mdoc_copies_usage_counts.is_empty());
     }
 
     #[tokio::test]
@@ -478,6 +643,58 @@ mod tests {
         assert!(wallet.disclosure_session.is_none());
     }
 
+    #[tokio::test]
+    #[serial]
+    async fn test_wallet_start_disclosure_error_unknown_relying_party() {
+        let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
+
+        // Configure a registry of relying parties, which does not contain the (empty) certificate of the mock session.
+        let registration = |certificate: Vec<u8>| RelyingPartyRegistration {
+            name: "Example RP".to_string(),
+            certificate: certificate.into(),
+        };
+        wallet.config_repository = {
+            let mut config = (*wallet.config_repository.config()).clone();
+            config.disclosure.rp_registrations = vec![registration(b"certificate".to_vec())];
+
+            UpdatingConfigurationRepository::new(
+                LocalConfigurationRepository::new(config),
+                Duration::from_secs(300),
+                Connectivity::default(),
+            )
+            .await
+        };
+
+        // Starting disclosure with a relying party that is not in the registry should result in an error.
+        let error = wallet
+            .start_disclosure(&Url::parse(DISCLOSURE_URI).unwrap())
+            .await
+            .expect_err("Starting disclosure should have resulted in an error");
+
+        assert_matches!(error, DisclosureError::UnknownRelyingParty);
+        assert!(wallet.disclosure_session.is_none());
+
+        // When the relying party is in the registry, disclosure can be started.
+        wallet.config_repository = {
+            let mut config = (*wallet.config_repository.config()).clone();
+            config.disclosure.rp_registrations.push(registration(vec![]));
+
+            UpdatingConfigurationRepository::new(
+                LocalConfigurationRepository::new(config),
+                Duration::from_secs(300),
+                Connectivity::default(),
+            )
+            .await
+        };
+
+        wallet
+            .start_disclosure(&Url::parse(DISCLOSURE_URI).unwrap())
+            .await
+            .expect("Could not start disclosure");
+
+        assert!(wallet.disclosure_session.is_some());
+    }
+
     #[tokio::test]
     async fn test_wallet_start_disclosure_error_session_state() {
         let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
@@ -642,6 +859,98 @@ mod tests {
         assert!(wallet.disclosure_session.is_none());
     }
 
+    #[tokio::test]
+    async fn test_wallet_disclosure_proposal() {
+        let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
+
+        // Set up an active `MdocDisclosureSession` with a proposal.
+        let proposed_attributes = IndexMap::from([(
+            "com.example.pid".to_string(),
+            IndexMap::from([(
+                "com.example.pid".to_string(),
+                vec![Entry {
+                    name: "age_over_18".to_string(),
+                    value: DataElementValue::Bool(true),
+                }],
+            )]),
+        )]);
+        let proposal_session = MockMdocDisclosureProposal {
+            proposed_source_identifiers: vec![PROPOSED_ID],
+            proposed_attributes,
+            ..Default::default()
+        };
+        wallet.disclosure_session = MockMdocDisclosureSession {
+            session_state: MdocDisclosureSessionState::Proposal(proposal_session),
+            ..Default::default()
+        }
+        .into();
+
+        // Getting the proposal should return the proposed attributes, while retaining the session.
+        let proposal = wallet.disclosure_proposal().expect("Could not get disclosure proposal");
+
+        assert_eq!(proposal.documents.len(), 1);
+        let document = proposal.documents.first().unwrap();
+        assert_eq!(document.doc_type, "com.example.pid");
+        assert_eq!(document.attributes.len(), 1);
+        assert!(wallet.disclosure_session.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_wallet_disclosure_proposal_error_attributes_not_available() {
+        let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
+
+        // Set up an active `MdocDisclosureSession` for which attributes are not available.
+        let missing_attributes = vec!["com.example.pid/com.example.pid/age_over_18".parse().unwrap()];
+        let mut missing_attr_session = MockMdocDisclosureMissingAttributes::default();
+        missing_attr_session
+            .expect_missing_attributes()
+            .return_const(missing_attributes);
+        wallet.disclosure_session = MockMdocDisclosureSession {
+            session_state: MdocDisclosureSessionState::MissingAttributes(missing_attr_session),
+            ..Default::default()
+        }
+        .into();
+
+        let error = wallet
+            .disclosure_proposal()
+            .expect_err("Getting disclosure proposal should have resulted in an error");
+
+        assert_matches!(
+            error,
+            DisclosureError::AttributesNotAvailable {
+                reader_registration: _,
+                missing_attributes
+            } if missing_attributes[0].doc_type == "com.example.pid"
+        );
+        assert!(wallet.disclosure_session.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_wallet_disclosure_proposal_error_locked() {
+        let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
+
+        wallet.disclosure_session = MockMdocDisclosureSession::default().into();
+        wallet.lock();
+
+        let error = wallet
+            .disclosure_proposal()
+            .expect_err("Getting disclosure proposal should have resulted in an error");
+
+        assert_matches!(error, DisclosureError::Locked);
+    }
+
+    #[tokio::test]
+    async fn test_wallet_disclosure_proposal_error_session_state() {
+        let wallet = WalletWithMocks::new_registered_and_unlocked().await;
+
+        // Getting the proposal without an active disclosure session should result in an error.
+        let error = wallet
+            .disclosure_proposal()
+            .expect_err("Getting disclosure proposal should have resulted in an error");
+
+        assert_matches!(error, DisclosureError::SessionState);
+    }
+
     #[tokio::test]
     async fn test_wallet_cancel_disclosure() {
         // Prepare a registered and unlocked wallet with an active disclosure session.
@@ -681,7 +990,7 @@ mod tests {
         assert!(!was_terminated.load(Ordering::Relaxed));
 
         // Verify no history events are yet logged
-        let events = wallet.storage.get_mut().fetch_wallet_events().await.unwrap();
+        let events = wallet.storage.read().await.fetch_wallet_events().await.unwrap();
         assert!(events.is_empty());
 
         // Cancelling disclosure should result in a `Wallet` without a disclosure
@@ -693,7 +1002,7 @@ mod tests {
         assert!(was_terminated.load(Ordering::Relaxed));
 
         // Verify a Disclosure Cancel event is logged
-        let events = wallet.storage.get_mut().fetch_wallet_events().await.unwrap();
+        let events = wallet.storage.read().await.fetch_wallet_events().await.unwrap();
         assert_eq!(events.len(), 1);
         assert_matches!(
             &events[0],
@@ -704,7 +1013,7 @@ mod tests {
         );
 
         // Cancelling disclosure should not cause mdoc copy usage counts to be incremented.
-        assert!(wallet.storage.get_mut().mdoc_copies_usage_counts.is_empty());
+        assert!(wallet.storage.read().await.mdoc_copies_usage_counts.is_empty());
     }
 
     #[tokio::test]
@@ -740,7 +1049,7 @@ mod tests {
         assert!(!was_terminated.load(Ordering::Relaxed));
 
         // Verify no history events are yet logged
-        let events = wallet.storage.get_mut().fetch_wallet_events().await.unwrap();
+        let events = wallet.storage.read().await.fetch_wallet_events().await.unwrap();
         assert!(events.is_empty());
 
         // Cancelling disclosure should result in a `Wallet` without a disclosure
@@ -752,7 +1061,7 @@ mod tests {
         assert!(was_terminated.load(Ordering::Relaxed));
 
         // Verify a single Disclosure Error event is logged
-        let events = wallet.storage.get_mut().fetch_wallet_events().await.unwrap();
+        let events = wallet.storage.read().await.fetch_wallet_events().await.unwrap();
         assert_eq!(events.len(), 1);
         assert_matches!(
             &events[0],
@@ -867,7 +1176,7 @@ mod tests {
         assert_eq!(disclosure_count.load(Ordering::Relaxed), 1);
 
         // Verify a single Disclosure Success event is logged, and documents are shared
-        let events = wallet.storage.get_mut().fetch_wallet_events().await.unwrap();
+        let events = wallet.storage.read().await.fetch_wallet_events().await.unwrap();
         assert_eq!(events.len(), 1);
         assert_matches!(
             &events[0],
@@ -879,11 +1188,12 @@ mod tests {
         );
 
         // Test that the usage count got incremented for the proposed mdoc copy id.
-        assert_eq!(wallet.storage.get_mut().mdoc_copies_usage_counts.len(), 1);
+        assert_eq!(wallet.storage.read().await.mdoc_copies_usage_counts.len(), 1);
         assert_eq!(
             wallet
                 .storage
-                .get_mut()
+                .read()
+                .await
                 .mdoc_copies_usage_counts
                 .get(&PROPOSED_ID)
                 .copied()
@@ -917,10 +1227,17 @@ mod tests {
         };
 
         // The mdoc copy usage counts should not be incremented.
-        assert!(wallet.storage.get_mut().mdoc_copies_usage_counts.is_empty());
+        assert!(wallet.storage.read().await.mdoc_copies_usage_counts.is_empty());
 
         // Verify no Disclosure events are logged
-        assert!(wallet.storage.get_mut().fetch_wallet_events().await.unwrap().is_empty());
+        assert!(wallet
+            .storage
+            .read()
+            .await
+            .fetch_wallet_events()
+            .await
+            .unwrap()
+            .is_empty());
     }
 
     #[tokio::test]
@@ -938,7 +1255,14 @@ mod tests {
         assert!(wallet.disclosure_session.is_none());
 
         // Verify no Disclosure events are logged
-        assert!(wallet.storage.get_mut().fetch_wallet_events().await.unwrap().is_empty());
+        assert!(wallet
+            .storage
+            .read()
+            .await
+            .fetch_wallet_events()
+            .await
+            .unwrap()
+            .is_empty());
     }
 
     #[tokio::test]
@@ -976,10 +1300,17 @@ mod tests {
         assert!(wallet.disclosure_session.is_some());
 
         // The mdoc copy usage counts should not be incremented.
-        assert!(wallet.storage.get_mut().mdoc_copies_usage_counts.is_empty());
+        assert!(wallet.storage.read().await.mdoc_copies_usage_counts.is_empty());
 
         // Verify no Disclosure events are logged
-        assert!(wallet.storage.get_mut().fetch_wallet_events().await.unwrap().is_empty());
+        assert!(wallet
+            .storage
+            .read()
+            .await
+            .fetch_wallet_events()
+            .await
+            .unwrap()
+            .is_empty());
     }
 
     #[tokio::test]
@@ -1016,11 +1347,12 @@ mod tests {
         };
 
         // Test that the usage count got incremented for the proposed mdoc copy id.
-        assert_eq!(wallet.storage.get_mut().mdoc_copies_usage_counts.len(), 1);
+        assert_eq!(wallet.storage.read().await.mdoc_copies_usage_counts.len(), 1);
         assert_eq!(
             wallet
                 .storage
-                .get_mut()
+                .read()
+                .await
                 .mdoc_copies_usage_counts
                 .get(&PROPOSED_ID)
                 .copied()
@@ -1029,7 +1361,7 @@ mod tests {
         );
 
         // Verify a Disclosure error event is logged, with no documents shared
-        let events = wallet.storage.get_mut().fetch_wallet_events().await.unwrap();
+        let events = wallet.storage.read().await.fetch_wallet_events().await.unwrap();
         assert_eq!(events.len(), 1);
         assert_matches!(
             &events[0],
@@ -1068,11 +1400,12 @@ mod tests {
         };
 
         // Test that the usage count got incremented again for the proposed mdoc copy id.
-        assert_eq!(wallet.storage.get_mut().mdoc_copies_usage_counts.len(), 1);
+        assert_eq!(wallet.storage.read().await.mdoc_copies_usage_counts.len(), 1);
         assert_eq!(
             wallet
                 .storage
-                .get_mut()
+                .read()
+                .await
                 .mdoc_copies_usage_counts
                 .get(&PROPOSED_ID)
                 .copied()
@@ -1081,7 +1414,7 @@ mod tests {
         );
 
         // Verify another Disclosure error event is logged, with no documents shared
-        let events = wallet.storage.get_mut().fetch_wallet_events().await.unwrap();
+        let events = wallet.storage.read().await.fetch_wallet_events().await.unwrap();
         assert_eq!(events.len(), 2);
         assert_matches!(
             &events[1],
@@ -1127,11 +1460,12 @@ mod tests {
         };
 
         // Test that the usage count got incremented for the proposed mdoc copy id.
-        assert_eq!(wallet.storage.get_mut().mdoc_copies_usage_counts.len(), 1);
+        assert_eq!(wallet.storage.read().await.mdoc_copies_usage_counts.len(), 1);
         assert_eq!(
             wallet
                 .storage
-                .get_mut()
+                .read()
+                .await
                 .mdoc_copies_usage_counts
                 .get(&PROPOSED_ID)
                 .copied()
@@ -1140,7 +1474,7 @@ mod tests {
         );
 
         // Verify a Disclosure error event is logged, and no documents are shared
-        let events = wallet.storage.get_mut().fetch_wallet_events().await.unwrap();
+        let events = wallet.storage.read().await.fetch_wallet_events().await.unwrap();
         assert_eq!(events.len(), 1);
         assert_matches!(
             &events[0],
@@ -1185,11 +1519,12 @@ mod tests {
         };
 
         // Test that the usage count got incremented for the proposed mdoc copy id.
-        assert_eq!(wallet.storage.get_mut().mdoc_copies_usage_counts.len(), 1);
+        assert_eq!(wallet.storage.read().await.mdoc_copies_usage_counts.len(), 1);
         assert_eq!(
             wallet
                 .storage
-                .get_mut()
+                .read()
+                .await
                 .mdoc_copies_usage_counts
                 .get(&PROPOSED_ID)
                 .copied()
@@ -1198,7 +1533,7 @@ mod tests {
         );
 
         // Verify a Disclosure error event is logged, and documents are shared
-        let events = wallet.storage.get_mut().fetch_wallet_events().await.unwrap();
+        let events = wallet.storage.read().await.fetch_wallet_events().await.unwrap();
         assert_eq!(events.len(), 1);
         assert_matches!(
             &events[0],
@@ -1277,10 +1612,10 @@ mod tests {
     #[tokio::test]
     async fn test_mdoc_by_doc_types_error() {
         // Prepare a wallet in initial state.
-        let mut wallet = WalletWithMocks::new_unregistered().await;
+        let wallet = WalletWithMocks::new_unregistered().await;
 
         // Set up `MockStorage` to return an error when performing a query.
-        wallet.storage.get_mut().has_query_error = true;
+        wallet.storage.write().await.has_query_error = true;
 
         // Calling the `MdocDataSource.mdoc_by_doc_types()` method
         // on the `Wallet` should forward the `StorageError`.
@@ -1291,4 +1626,123 @@ mod tests {
 
         assert_matches!(error, StorageError::Database(_));
     }
+
+    fn pid_items_request(attributes: &[&str]) -> ItemsRequest {
+        ItemsRequest {
+            doc_type: "com.example.pid".to_string(),
+            name_spaces: IndexMap::from([(
+                "com.example.pid".to_string(),
+                attributes
+                    .iter()
+                    .map(|attribute| (attribute.to_string(), false))
+                    .collect(),
+            )]),
+            request_info: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_wallet_preview_request() {
+        let wallet = WalletWithMocks::new_registered_and_unlocked().await;
+
+        // The database contains a single PID `Mdoc`.
+        let mdoc = super::super::tests::create_full_pid_mdoc().await;
+        wallet.storage.write().await.mdocs.add([mdoc].into_iter()).unwrap();
+
+        // Previewing a request for attributes that are present should result in the documents to be disclosed.
+        let preview = wallet
+            .preview_request(vec![pid_items_request(&["given_name", "age_over_18"])])
+            .await
+            .expect("Could not preview disclosure request");
+
+        let documents = assert_matches!(preview, DisclosureRequestPreview::Available(documents) => documents);
+        assert_eq!(documents.len(), 1);
+        assert_eq!(documents[0].doc_type, "com.example.pid");
+        assert_eq!(
+            documents[0].attributes.keys().copied().collect::<Vec<_>>(),
+            ["given_name", "age_over_18"]
+        );
+
+        // Previewing a request for a doc type that is not present should result in missing attributes.
+        let address_items_request = ItemsRequest {
+            doc_type: "com.example.address".to_string(),
+            name_spaces: IndexMap::from([(
+                "com.example.address".to_string(),
+                IndexMap::from([("resident_street".to_string(), false)]),
+            )]),
+            request_info: None,
+        };
+        let preview = wallet
+            .preview_request(vec![pid_items_request(&["given_name"]), address_items_request])
+            .await
+            .expect("Could not preview disclosure request");
+
+        let report = assert_matches!(preview, DisclosureRequestPreview::MissingAttributes(report) => report);
+        assert!(report.incomplete_documents.is_empty());
+        assert_eq!(report.missing_documents.len(), 1);
+        assert_eq!(report.missing_documents[0].doc_type, "com.example.address");
+        assert!(report.missing_documents[0].attributes.contains_key("resident_street"));
+    }
+
+    #[tokio::test]
+    async fn test_wallet_preview_request_incomplete_document() {
+        let wallet = WalletWithMocks::new_registered_and_unlocked().await;
+
+        // The database contains a PID `Mdoc` with only the mandatory attributes.
+        let mdoc = super::super::tests::mdoc_from_unsigned(
+            document::create_minimal_unsigned_pid_mdoc(),
+            utils::random_string(16),
+            &super::super::tests::ISSUER_KEY,
+        )
+        .await;
+        wallet.storage.write().await.mdocs.add([mdoc].into_iter()).unwrap();
+
+        // Previewing a request for an attribute that the PID does not contain should report the PID as incomplete.
+        let preview = wallet
+            .preview_request(vec![pid_items_request(&["given_name", "gender"])])
+            .await
+            .expect("Could not preview disclosure request");
+
+        let report = assert_matches!(preview, DisclosureRequestPreview::MissingAttributes(report) => report);
+        assert!(report.missing_documents.is_empty());
+        assert_eq!(report.incomplete_documents.len(), 1);
+        assert_eq!(report.incomplete_documents[0].doc_type, "com.example.pid");
+        assert_eq!(
+            report.incomplete_documents[0]
+                .attributes
+                .keys()
+                .copied()
+                .collect::<Vec<_>>(),
+            ["gender"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_wallet_preview_request_error_locked() {
+        let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
+
+        wallet.lock();
+
+        let error = wallet
+            .preview_request(vec![pid_items_request(&["given_name"])])
+            .await
+            .expect_err("Previewing disclosure request should have resulted in an error");
+
+        assert_matches!(error, DisclosureError::Locked);
+    }
+
+    #[tokio::test]
+    async fn test_wallet_preview_request_error_no_attributes() {
+        let wallet = WalletWithMocks::new_registered_and_unlocked().await;
+
+        let error = wallet
+            .preview_request(vec![pid_items_request(&[])])
+            .await
+            .expect_err("Previewing disclosure request should have resulted in an error");
+
+        assert_matches!(
+            error,
+            DisclosureError::DisclosureSession(nl_wallet_mdoc::Error::Holder(HolderError::NoAttributesRequested))
+        );
+    }
 }
@@ -1,7 +1,13 @@
-use tracing::info;
+use tracing::{info, warn};
+
+use nl_wallet_mdoc::{
+    holder::{Mdoc, TrustAnchor},
+    status_list::StatusType,
+};
 
 use crate::{
-    document::{Document, DocumentPersistence},
+    config::ConfigurationRepository,
+    document::{Document, DocumentMdocError, DocumentPersistence},
     storage::{Storage, StorageError, StoredMdocCopy},
 };
 
@@ -9,41 +15,53 @@ use super::Wallet;
 
 #[derive(Debug, thiserror::Error)]
 pub enum SetDocumentsCallbackError {
-    #[error("Could not fetch mdocs from database storage: {0}")]
+    #[error("could not fetch documents: {0}")]
+    Documents(#[from] DocumentsError),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DocumentsError {
+    #[error("wallet is not registered")]
+    NotRegistered,
+    #[error("wallet is locked")]
+    Locked,
+    #[error("could not fetch mdocs from database storage: {0}")]
     Storage(#[from] StorageError),
+    #[error("could not interpret stored mdoc attributes: {0}")]
+    MdocAttributes(#[source] DocumentMdocError),
 }
 
 pub type DocumentsCallback = Box<dyn FnMut(Vec<Document>) + Send + Sync>;
 
-impl<CR, S, PEK, APC, DGS, PIC, MDS> Wallet<CR, S, PEK, APC, DGS, PIC, MDS>
+impl<CR, S, PEK, APC, DGS, CIC, MDS> Wallet<CR, S, PEK, APC, DGS, CIC, MDS>
 where
     S: Storage,
 {
-    pub(super) async fn emit_documents(&mut self) -> Result<(), StorageError> {
-        info!("Emit mdocs from storage");
-
+    async fn fetch_documents(&self) -> Result<Vec<Document>, DocumentsError> {
         let storage = self.storage.read().await;
 
-        // Note that this currently panics whenever conversion from Mdoc to Documents fails,
-        // as we assume that the (hardcoded) mapping will always be backwards compatible.
-        // This is particularly important when this mapping comes from a trusted registry
-        // in the near future!
+        // Note that this currently fails whenever conversion from Mdoc to Documents fails,
+        // as we assume that the mapping will always be backwards compatible.
         let mut documents = storage
             .fetch_unique_mdocs()
             .await?
             .into_iter()
             .map(|StoredMdocCopy { mdoc_id, mdoc, .. }| {
-                Document::from_mdoc_attributes(
-                    DocumentPersistence::Stored(mdoc_id.to_string()),
-                    &mdoc.doc_type,
-                    mdoc.attributes(),
-                )
-                .expect("Could not interpret stored mdoc attributes")
+                Document::from_mdoc(DocumentPersistence::Stored(mdoc_id.to_string()), &mdoc)
+                    .map_err(DocumentsError::MdocAttributes)
             })
-            .collect::<Vec<_>>();
+            .collect::<Result<Vec<_>, _>>()?;
 
         documents.sort_by_key(Document::priority);
 
+        Ok(documents)
+    }
+
+    pub(super) async fn emit_documents(&mut self) -> Result<(), DocumentsError> {
+        info!("Emit mdocs from storage");
+
+        let documents = self.fetch_documents().await?;
+
         if let Some(ref mut callback) = self.documents_callback {
             callback(documents);
         }
@@ -73,6 +91,91 @@ where
     pub fn clear_documents_callback(&mut self) {
         self.documents_callback.take();
     }
+
+    /// Get all of the documents currently stored in the wallet, sorted by their display priority.
+    pub async fn documents(&self) -> Result<Vec<Document>, DocumentsError> {
+        info!("Retrieving documents");
+
+        info!("Checking if registered");
+        if self.registration.is_none() {
+            return Err(DocumentsError::NotRegistered);
+        }
+
+        info!("Checking if locked");
+        if self.lock.is_locked() {
+            return Err(DocumentsError::Locked);
+        }
+
+        let documents = self.fetch_documents().await?;
+
+        Ok(documents)
+    }
+}
+
+impl<CR, S, PEK, APC, DGS, CIC, MDS> Wallet<CR, S, PEK, APC, DGS, CIC, MDS>
+where
+    CR: ConfigurationRepository,
+    S: Storage,
+{
+    async fn mdoc_status(&self, mdoc: &Mdoc, trust_anchors: &[TrustAnchor<'_>]) -> nl_wallet_mdoc::Result<StatusType> {
+        let Some(status) = mdoc.status()? else {
+            return Ok(StatusType::Valid);
+        };
+
+        self.status_lists
+            .status(
+                &status.status_list,
+                &mdoc.issuer_certificate()?,
+                &self.clock_skew,
+                trust_anchors,
+            )
+            .await
+    }
+
+    /// Check the documents stored in the wallet against the status lists of their issuers, returning those documents
+    /// that have been revoked or suspended so that the user can be warned about them. This is meant to be called
+    /// periodically. Documents of which the status list could not be fetched or verified are skipped.
+    pub async fn revoked_documents(&self) -> Result<Vec<Document>, DocumentsError> {
+        info!("Checking status of documents");
+
+        info!("Checking if registered");
+        if self.registration.is_none() {
+            return Err(DocumentsError::NotRegistered);
+        }
+
+        info!("Checking if locked");
+        if self.lock.is_locked() {
+            return Err(DocumentsError::Locked);
+        }
+
+        let config = self.config_repository.config();
+        let trust_anchors = config.mdoc_trust_anchors();
+
+        let mdocs = self.storage.read().await.fetch_unique_mdocs().await?;
+
+        let mut documents = Vec::new();
+        for StoredMdocCopy { mdoc_id, mdoc, .. } in mdocs {
+            match self.mdoc_status(&mdoc, &trust_anchors).await {
+                Ok(StatusType::Valid) => continue,
+                Ok(status) => info!("Document of doctype {} has status {:?}", mdoc.doc_type, status),
+                Err(error) => {
+                    warn!(
+                        "Could not check status of document of doctype {}: {}",
+                        mdoc.doc_type, error
+                    );
+                    continue;
+                }
+            }
+
+            let document = Document::from_mdoc(DocumentPersistence::Stored(mdoc_id.to_string()), &mdoc)
+                .map_err(DocumentsError::MdocAttributes)?;
+            documents.push(document);
+        }
+
+        documents.sort_by_key(Document::priority);
+
+        Ok(documents)
+    }
 }
 #[cfg(test)]
 mod tests {
@@ -129,7 +232,7 @@ mod tests {
         // The database contains a single `Mdoc`.
         let mdoc = tests::create_full_pid_mdoc().await;
         let mdoc_doc_type = mdoc.doc_type.clone();
-        wallet.storage.get_mut().mdocs.add([mdoc].into_iter()).unwrap();
+        wallet.storage.write().await.mdocs.add([mdoc].into_iter()).unwrap();
 
         // Wrap a `Vec<Document>` in both a `Mutex` and `Arc`,
         // so we can write to it from the closure.
@@ -170,7 +273,7 @@ mod tests {
         let mut wallet = Wallet::new_registered_and_unlocked().await;
 
         // Have the database return an error on query.
-        wallet.storage.get_mut().has_query_error = true;
+        wallet.storage.write().await.has_query_error = true;
 
         // Confirm that setting the callback returns an error.
         let error = wallet
@@ -178,6 +281,115 @@ mod tests {
             .await
             .expect_err("Setting documents callback should have resulted in an error");
 
-        assert_matches!(error, SetDocumentsCallbackError::Storage(_));
+        assert_matches!(error, SetDocumentsCallbackError::Documents(DocumentsError::Storage(_)));
+    }
+
+    #[tokio::test]
+    async fn test_wallet_documents() {
+        let wallet = Wallet::new_registered_and_unlocked().await;
+
+        // The database contains a single `Mdoc`.
+        let mdoc = tests::create_full_pid_mdoc().await;
+        let mdoc_doc_type = mdoc.doc_type.clone();
+        wallet.storage.write().await.mdocs.add([mdoc].into_iter()).unwrap();
+
+        let documents = wallet.documents().await.expect("Could not get documents");
+
+        // The `Document` should contain the attributes, validity and issuer registration of the `Mdoc`.
+        assert_eq!(documents.len(), 1);
+
+        let document = documents.first().unwrap();
+        assert_eq!(document.doc_type, mdoc_doc_type);
+        assert_matches!(document.persistence, DocumentPersistence::Stored(_));
+        assert!(document.attributes.contains_key("given_name"));
+        assert_matches!(&document.validity, Some(validity) if validity.valid_from < validity.valid_until);
+        assert!(document.issuer_registration.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_wallet_documents_error_not_registered() {
+        let wallet = WalletWithMocks::new_unregistered().await;
+
+        let error = wallet
+            .documents()
+            .await
+            .expect_err("Getting documents should have resulted in an error");
+
+        assert_matches!(error, DocumentsError::NotRegistered);
+    }
+
+    #[tokio::test]
+    async fn test_wallet_documents_error_locked() {
+        let mut wallet = Wallet::new_registered_and_unlocked().await;
+
+        wallet.lock();
+
+        let error = wallet
+            .documents()
+            .await
+            .expect_err("Getting documents should have resulted in an error");
+
+        assert_matches!(error, DocumentsError::Locked);
+    }
+
+    #[tokio::test]
+    async fn test_wallet_documents_error_storage() {
+        let wallet = Wallet::new_registered_and_unlocked().await;
+
+        // Have the database return an error on query.
+        wallet.storage.write().await.has_query_error = true;
+
+        let error = wallet
+            .documents()
+            .await
+            .expect_err("Getting documents should have resulted in an error");
+
+        assert_matches!(error, DocumentsError::Storage(_));
+    }
+
+    #[tokio::test]
+    async fn test_wallet_revoked_documents() {
+        let wallet = Wallet::new_registered_and_unlocked().await;
+
+        // The database contains a single `Mdoc`, which does not refer to a status list.
+        let mdoc = tests::create_full_pid_mdoc().await;
+        wallet.storage.write().await.mdocs.add([mdoc].into_iter()).unwrap();
+
+        let documents = wallet
+            .revoked_documents()
+            .await
+            .expect("Could not check status of documents");
+
+        // An `Mdoc` without a status list cannot be revoked.
+        assert!(documents.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_wallet_revoked_documents_error_locked() {
+        let mut wallet = Wallet::new_registered_and_unlocked().await;
+
+        wallet.lock();
+
+        let error = wallet
+            .revoked_documents()
+            .await
+            .expect_err("Checking status of documents should have resulted in an error");
+
+        assert_matches!(error, DocumentsError::Locked);
+    }
+
+    #[tokio::test]
+    async fn test_wallet_revoked_documents_error_storage() {
+        let wallet = Wallet::new_registered_and_unlocked().await;
+
+        // Have the database return an error on query.
+        wallet.storage.write().await.has_query_error = true;
+
+        let error = wallet
+            .revoked_documents()
+            .await
+            .expect_err("Checking status of documents should have resulted in an error");
+
+        assert_matches!(error, DocumentsError::Storage(_));
     }
 }
@@ -37,12 +37,12 @@ pub enum HistoryError {
 
 type HistoryResult<T> = Result<T, HistoryError>;
 
-impl<CR, S, PEK, APC, DGS, PIC, MDS> Wallet<CR, S, PEK, APC, DGS, PIC, MDS>
+impl<CR, S, PEK, APC, DGS, CIC, MDS> Wallet<CR, S, PEK, APC, DGS, CIC, MDS>
 where
     S: Storage,
 {
     pub(super) async fn store_history_event(&mut self, event: WalletEvent) -> Result<(), StorageError> {
-        self.storage.get_mut().log_wallet_event(event).await
+        self.storage.write().await.log_wallet_event(event).await
     }
 
     pub async fn get_history(&self) -> HistoryResult<Vec<HistoryEvent>> {
@@ -101,6 +101,15 @@ pub enum HistoryEvent {
     },
 }
 
+impl HistoryEvent {
+    pub fn timestamp(&self) -> &DateTime<Utc> {
+        match self {
+            Self::Issuance { timestamp, .. } => timestamp,
+            Self::Disclosure { timestamp, .. } => timestamp,
+        }
+    }
+}
+
 impl TryFrom<WalletEvent> for HistoryEvent {
     type Error = HistoryError;
 
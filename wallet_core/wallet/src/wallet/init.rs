@@ -1,18 +1,25 @@
+use std::{sync::Arc, time::Duration};
+
 use tokio::sync::RwLock;
 
+use nl_wallet_mdoc::status_list::{HttpStatusListClient, StatusListCache};
+
 use platform_support::{
     hw_keystore::{hardware::HardwareEncryptionKey, PlatformEcdsaKey},
     utils::{hardware::HardwareUtilities, PlatformUtilities, UtilitiesError},
 };
+use wallet_common::correlation::CorrelationTracker;
 
 use crate::{
     account_provider::HttpAccountProviderClient,
+    clock_skew::ClockSkew,
     config::{
         default_configuration, ConfigServerConfiguration, ConfigurationError, ConfigurationRepository,
         UpdatingConfigurationRepository,
     },
-    lock::WalletLock,
-    pid_issuer::HttpPidIssuerClient,
+    connectivity::Connectivity,
+    credential_issuer::HttpCredentialIssuerClient,
+    lock::{LockScheduler, WalletLock},
     storage::{DatabaseStorage, RegistrationData, Storage, StorageError, StorageState},
 };
 
@@ -20,6 +27,9 @@ use super::Wallet;
 
 const WALLET_KEY_ID: &str = "wallet";
 
+/// The maximum time the status list of a document is cached, before it is fetched again from its issuer.
+const STATUS_LIST_MAX_TTL: Duration = Duration::from_secs(60 * 60);
+
 #[derive(Debug, thiserror::Error)]
 pub enum WalletInitError {
     #[error("wallet configuration error")]
@@ -36,25 +46,51 @@ impl Wallet {
         tracing::warn!("TLS validation disabled");
 
         let storage_path = HardwareUtilities::storage_path().await?;
-        let storage = DatabaseStorage::<HardwareEncryptionKey>::init(storage_path.clone());
+        let mut storage = DatabaseStorage::<HardwareEncryptionKey>::init(storage_path.clone());
+        let connectivity = Connectivity::default();
+        let clock_skew = ClockSkew::default();
+        let correlation = CorrelationTracker::default();
+
+        // The database is opened before the configuration is initialized, as it contains the highest version of the
+        // configuration that was accepted before. The storage is then shared with the configuration repository.
+        let registration = Self::fetch_registration(&mut storage).await?;
+        let storage = Arc::new(RwLock::new(storage));
+
         let config_repository = UpdatingConfigurationRepository::init(
             storage_path,
             ConfigServerConfiguration::default(),
             default_configuration(),
+            Arc::clone(&storage),
+            connectivity.clone(),
         )
         .await?;
 
-        Self::init_registration(
+        // The HTTP clients retry requests according to the configuration that is current at startup.
+        let retry_config = config_repository.config().retry.clone();
+
+        let mut wallet = Self::new(
             config_repository,
             storage,
-            HttpAccountProviderClient::default(),
-            HttpPidIssuerClient::default(),
-        )
-        .await
+            HttpAccountProviderClient::new(retry_config.clone(), clock_skew.clone(), correlation.clone()),
+            HttpCredentialIssuerClient::with_retry_config(retry_config, correlation.clone()),
+            registration,
+        );
+
+        // Share the connectivity state with the configuration repository,
+        // so that it can defer updating the configuration while offline.
+        wallet.connectivity = connectivity;
+
+        // Share the clock skew with the account provider client, which observes the time of the Wallet Provider.
+        wallet.clock_skew = clock_skew;
+
+        // Share the correlation of operations with the HTTP clients, which add its ID to every request.
+        wallet.correlation = correlation;
+
+        Ok(wallet)
     }
 }
 
-impl<CR, S, PEK, APC, DGS, PIC, MDS> Wallet<CR, S, PEK, APC, DGS, PIC, MDS>
+impl<CR, S, PEK, APC, DGS, CIC, MDS> Wallet<CR, S, PEK, APC, DGS, CIC, MDS>
 where
     CR: ConfigurationRepository,
     S: Storage,
@@ -62,22 +98,29 @@ where
 {
     pub(super) fn new(
         config_repository: CR,
-        storage: S,
+        storage: Arc<RwLock<S>>,
         account_provider_client: APC,
-        pid_issuer: PIC,
+        issuer_client: CIC,
         registration: Option<RegistrationData>,
     ) -> Self {
         Wallet {
             config_repository,
-            storage: RwLock::new(storage),
+            storage,
             hw_privkey: PEK::new(WALLET_KEY_ID),
             account_provider_client,
             digid_session: None,
-            pid_issuer,
+            selected_issuer_url: None,
+            is_pid_renewal: false,
+            issuer_client,
             disclosure_session: None,
             lock: WalletLock::new(true),
+            lock_scheduler: LockScheduler::new(),
             registration,
             documents_callback: None,
+            status_lists: StatusListCache::new(HttpStatusListClient::default(), STATUS_LIST_MAX_TTL),
+            connectivity: Connectivity::default(),
+            clock_skew: ClockSkew::default(),
+            correlation: CorrelationTracker::default(),
         }
     }
 
@@ -86,15 +129,15 @@ where
         config_repository: CR,
         mut storage: S,
         account_provider_client: APC,
-        pid_issuer: PIC,
+        issuer_client: CIC,
     ) -> Result<Self, WalletInitError> {
         let registration = Self::fetch_registration(&mut storage).await?;
 
         let wallet = Self::new(
             config_repository,
-            storage,
+            Arc::new(RwLock::new(storage)),
             account_provider_client,
-            pid_issuer,
+            issuer_client,
             registration,
         );
 
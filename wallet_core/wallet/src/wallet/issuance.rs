@@ -1,5 +1,7 @@
+use std::collections::HashSet;
+
 use p256::ecdsa::signature;
-use tracing::{info, instrument};
+use tracing::{info, instrument, warn};
 use url::Url;
 
 use nl_wallet_mdoc::{server_keys::KeysError, utils::issuer_auth::IssuerRegistration};
@@ -8,14 +10,14 @@ use platform_support::hw_keystore::PlatformEcdsaKey;
 use crate::{
     account_provider::AccountProviderClient,
     config::ConfigurationRepository,
+    credential_issuer::{CredentialIssuerClient, CredentialIssuerError},
     digid::{DigidError, DigidSession},
-    document::{Document, DocumentMdocError},
+    document::{Document, DocumentMdocError, PID_DOCTYPE},
     instruction::{InstructionClient, InstructionError, RemoteEcdsaKeyError, RemoteEcdsaKeyFactory},
-    pid_issuer::{PidIssuerClient, PidIssuerError},
-    storage::{Storage, StorageError, WalletEvent},
+    storage::{DigidSessionData, Storage, StorageError, WalletEvent},
 };
 
-use super::Wallet;
+use super::{key_pool, DocumentsError, Wallet};
 
 #[derive(Debug, thiserror::Error)]
 pub enum PidIssuanceError {
@@ -30,7 +32,7 @@ pub enum PidIssuanceError {
     #[error("could not finish DigiD session: {0}")]
     DigidSessionFinish(#[source] DigidError),
     #[error("could not retrieve PID from issuer: {0}")]
-    PidIssuer(#[source] PidIssuerError),
+    PidIssuer(#[source] CredentialIssuerError),
     #[error("error sending instruction to Wallet Provider: {0}")]
     Instruction(#[from] InstructionError),
     #[error("invalid signature received from Wallet Provider: {0}")]
@@ -39,23 +41,47 @@ pub enum PidIssuanceError {
     Document(#[from] DocumentMdocError),
     #[error("could not access mdocs database: {0}")]
     MdocStorage(#[source] StorageError),
+    #[error("could not emit documents: {0}")]
+    Documents(#[source] DocumentsError),
     #[error("could not store history in database: {0}")]
     HistoryStorage(#[source] StorageError),
+    #[error("could not persist DigiD session in database: {0}")]
+    DigidSessionStorage(#[source] StorageError),
     #[error("key '{0}' not found in Wallet Provider")]
     KeyNotFound(String),
     #[error("issuer not authenticated")]
     MissingIssuerRegistration,
+    #[error("credential issuer is not present in the configuration: {0}")]
+    UnknownIssuer(Url),
+    #[error("wallet does not contain a PID to renew")]
+    NoPidToRenew,
 }
 
-impl<CR, S, PEK, APC, DGS, PIC, MDS> Wallet<CR, S, PEK, APC, DGS, PIC, MDS>
+impl<CR, S, PEK, APC, DGS, CIC, MDS> Wallet<CR, S, PEK, APC, DGS, CIC, MDS>
 where
     CR: ConfigurationRepository,
     DGS: DigidSession,
-    PIC: PidIssuerClient,
+    CIC: CredentialIssuerClient,
     S: Storage,
 {
     #[instrument(skip_all)]
     pub async fn create_pid_issuance_auth_url(&mut self) -> Result<Url, PidIssuanceError> {
+        let pid_issuer_url = self.config_repository.config().pid_issuance.pid_issuer_url.clone();
+
+        self.create_issuance_auth_url(pid_issuer_url).await
+    }
+
+    /// Start issuance by the credential issuer with the specified base URL, which should either be the PID issuer or
+    /// one of the other credential issuers in the configuration. The user authenticates with the identity provider
+    /// configured for that issuer, which defaults to DigiD, after which the resulting access token is presented to
+    /// the selected issuer in [`Self::continue_pid_issuance`].
+    #[instrument(skip_all)]
+    pub async fn create_issuance_auth_url(&mut self, issuer_url: Url) -> Result<Url, PidIssuanceError> {
+        self.start_issuance(issuer_url, false).await
+    }
+
+    async fn start_issuance(&mut self, issuer_url: Url, is_pid_renewal: bool) -> Result<Url, PidIssuanceError> {
+        self.start_operation();
         info!("Generating DigiD auth URL, starting OpenID connect discovery");
 
         info!("Checking if registered");
@@ -68,32 +94,77 @@ where
             return Err(PidIssuanceError::Locked);
         }
 
-        info!("Checking if there is a DigidSession or PidIssuerClient has session");
-        if self.digid_session.is_some() || self.pid_issuer.has_session() {
+        info!("Checking if there is a DigidSession or CredentialIssuerClient has session");
+        if self.digid_session.is_some() || self.issuer_client.has_session() {
             return Err(PidIssuanceError::SessionState);
         }
 
-        let pid_issuance_config = &self.config_repository.config().pid_issuance;
+        let config = self.config_repository.config();
+
+        info!("Checking if issuer is known");
+        let identity_provider = config
+            .issuer_identity_provider(&issuer_url)
+            .ok_or_else(|| PidIssuanceError::UnknownIssuer(issuer_url.clone()))?;
+
         // Assume that redirect URI creation is checked when updating the `Configuration`.
-        let digid_redirect_uri = pid_issuance_config.digid_redirect_uri().unwrap();
+        let digid_redirect_uri = config.pid_issuance.digid_redirect_uri().unwrap();
+
+        let session = self
+            .connectivity
+            .observe(DGS::start(identity_provider, digid_redirect_uri).await)
+            .map_err(PidIssuanceError::DigidSessionStart)?;
 
-        let session = DGS::start(
-            pid_issuance_config.digid_url.clone(),
-            pid_issuance_config.digid_client_id.to_string(),
-            digid_redirect_uri,
-        )
-        .await
-        .map_err(PidIssuanceError::DigidSessionStart)?;
+        info!("DigiD auth URL generated, persisting DigiD session");
 
-        info!("DigiD auth URL generated");
+        // Persist the session, so that issuance can continue if the app is terminated while in the browser.
+        self.store_digid_session(&session, &issuer_url, is_pid_renewal).await?;
 
         let auth_url = session.auth_url();
         self.digid_session.replace(session);
+        self.selected_issuer_url.replace(issuer_url);
+        self.is_pid_renewal = is_pid_renewal;
 
         Ok(auth_url)
     }
 
-    pub fn cancel_pid_issuance(&mut self) -> Result<(), PidIssuanceError> {
+    /// Start renewal of the PID, e.g. because it has expired or because the attributes of the user have changed.
+    /// This re-runs DigiD authentication for the PID issuer, after which issuance continues as usual. The difference
+    /// is that accepting the issuance replaces the PID and address cards that are currently in the wallet, instead of
+    /// adding new cards next to them. The history of the replaced cards is preserved.
+    #[instrument(skip_all)]
+    pub async fn renew_pid(&mut self) -> Result<Url, PidIssuanceError> {
+        info!("Starting PID renewal");
+
+        info!("Checking if registered");
+        if self.registration.is_none() {
+            return Err(PidIssuanceError::NotRegistered);
+        }
+
+        info!("Checking if locked");
+        if self.lock.is_locked() {
+            return Err(PidIssuanceError::Locked);
+        }
+
+        info!("Checking if a PID is present");
+        let has_pid = !self
+            .storage
+            .read()
+            .await
+            .fetch_unique_mdocs_by_doctypes(&HashSet::from([PID_DOCTYPE]))
+            .await
+            .map_err(PidIssuanceError::MdocStorage)?
+            .is_empty();
+
+        if !has_pid {
+            return Err(PidIssuanceError::NoPidToRenew);
+        }
+
+        let pid_issuer_url = self.config_repository.config().pid_issuance.pid_issuer_url.clone();
+
+        self.start_issuance(pid_issuer_url, true).await
+    }
+
+    pub async fn cancel_pid_issuance(&mut self) -> Result<(), PidIssuanceError> {
         info!("PID issuance cancelled");
 
         info!("Checking if registered");
@@ -106,13 +177,17 @@ where
             return Err(PidIssuanceError::Locked);
         }
 
-        if self.digid_session.is_none() {
+        // The session may only be present in storage, if the app was terminated while the user was in the browser.
+        if self.digid_session.is_none() && self.fetch_digid_session().await?.is_none() {
             return Err(PidIssuanceError::SessionState);
         }
 
         info!("Removing DigiD session");
 
+        self.remove_digid_session().await?;
         self.digid_session.take();
+        self.selected_issuer_url.take();
+        self.is_pid_renewal = false;
 
         Ok(())
     }
@@ -131,23 +206,62 @@ where
             return Err(PidIssuanceError::Locked);
         }
 
-        // Try to take ownership of any active `DigidSession`.
-        let session = self.digid_session.take().ok_or(PidIssuanceError::SessionState)?;
+        // If the app was terminated while the user was in the browser, restore the persisted `DigidSession`.
+        if self.digid_session.is_none() {
+            self.restore_digid_session().await?;
+        }
+
+        // Try to take ownership of any active `DigidSession`, together with the issuer it was started for.
+        let (mut session, issuer_url) = self
+            .digid_session
+            .take()
+            .zip(self.selected_issuer_url.take())
+            .ok_or(PidIssuanceError::SessionState)?;
 
-        let access_token = session
-            .get_access_token(redirect_uri)
-            .await
+        // The authorization code can only be used once, so the persisted session is of no further use.
+        self.remove_digid_session().await?;
+
+        // If the session was retained after issuance failed earlier, the authorization code in the
+        // redirect URI has already been used. In that case, use the refresh token to get a new access token.
+        let access_token = if session.can_refresh_access_token() {
+            info!("DigiD session was already authenticated, refreshing access token");
+
+            session.refresh_access_token().await
+        } else {
+            session.get_access_token(redirect_uri).await
+        };
+        let access_token = self
+            .connectivity
+            .observe(access_token)
             .map_err(PidIssuanceError::DigidSessionFinish)?;
 
         info!("DigiD access token retrieved, starting actual PID issuance");
 
-        let config = self.config_repository.config();
+        let unsigned_mdocs = match self
+            .connectivity
+            .observe(self.issuer_client.start_issuance(&issuer_url, &access_token).await)
+        {
+            Ok(unsigned_mdocs) => unsigned_mdocs,
+            Err(error) => {
+                // Retain the session if it can be refreshed, so that issuance
+                // can be retried without authenticating with DigiD again.
+                if session.can_refresh_access_token() {
+                    info!("Retaining DigiD session for retrying PID issuance");
+
+                    if let Err(storage_error) = self
+                        .store_digid_session(&session, &issuer_url, self.is_pid_renewal)
+                        .await
+                    {
+                        warn!("Could not persist retained DigiD session: {}", storage_error);
+                    }
 
-        let unsigned_mdocs = self
-            .pid_issuer
-            .start_retrieve_pid(&config.pid_issuance.pid_issuer_url, &access_token)
-            .await
-            .map_err(PidIssuanceError::PidIssuer)?;
+                    self.digid_session.replace(session);
+                    self.selected_issuer_url.replace(issuer_url);
+                }
+
+                return Err(PidIssuanceError::PidIssuer(error));
+            }
+        };
 
         info!("PID received successfully from issuer, returning preview documents");
 
@@ -161,6 +275,74 @@ where
         Ok(documents)
     }
 
+    async fn fetch_digid_session(&self) -> Result<Option<DigidSessionData>, PidIssuanceError> {
+        self.storage
+            .read()
+            .await
+            .fetch_data::<DigidSessionData>()
+            .await
+            .map_err(PidIssuanceError::DigidSessionStorage)
+    }
+
+    async fn store_digid_session(
+        &self,
+        session: &DGS,
+        issuer_url: &Url,
+        is_pid_renewal: bool,
+    ) -> Result<(), PidIssuanceError> {
+        let data = DigidSessionData {
+            issuer_url: issuer_url.clone(),
+            is_pid_renewal,
+            session: session.to_state(),
+        };
+
+        let mut storage = self.storage.write().await;
+
+        match storage
+            .fetch_data::<DigidSessionData>()
+            .await
+            .map_err(PidIssuanceError::DigidSessionStorage)?
+        {
+            Some(_) => storage.update_data(&data).await,
+            None => storage.insert_data(&data).await,
+        }
+        .map_err(PidIssuanceError::DigidSessionStorage)
+    }
+
+    async fn remove_digid_session(&self) -> Result<(), PidIssuanceError> {
+        self.storage
+            .write()
+            .await
+            .delete_data::<DigidSessionData>()
+            .await
+            .map_err(PidIssuanceError::DigidSessionStorage)
+    }
+
+    async fn restore_digid_session(&mut self) -> Result<(), PidIssuanceError> {
+        let Some(data) = self.fetch_digid_session().await? else {
+            return Ok(());
+        };
+
+        info!("Restoring persisted DigiD session");
+
+        let identity_provider = self
+            .config_repository
+            .config()
+            .issuer_identity_provider(&data.issuer_url)
+            .ok_or_else(|| PidIssuanceError::UnknownIssuer(data.issuer_url.clone()))?;
+
+        let session = self
+            .connectivity
+            .observe(DGS::restore(identity_provider, data.session).await)
+            .map_err(PidIssuanceError::DigidSessionStart)?;
+
+        self.digid_session.replace(session);
+        self.selected_issuer_url.replace(data.issuer_url);
+        self.is_pid_renewal = data.is_pid_renewal;
+
+        Ok(())
+    }
+
     #[instrument(skip_all)]
     pub async fn reject_pid_issuance(&mut self) -> Result<(), PidIssuanceError> {
         info!("Checking if registered");
@@ -173,13 +355,17 @@ where
             return Err(PidIssuanceError::Locked);
         }
 
-        info!("Checking if PidIssuerClient has session");
-        if !self.pid_issuer.has_session() {
+        info!("Checking if CredentialIssuerClient has session");
+        if !self.issuer_client.has_session() {
             return Err(PidIssuanceError::SessionState);
         }
 
         info!("Rejecting any PID held in memory");
-        self.pid_issuer.reject_pid().await.map_err(PidIssuanceError::PidIssuer)
+        self.is_pid_renewal = false;
+        self.issuer_client
+            .reject_issuance()
+            .await
+            .map_err(PidIssuanceError::PidIssuer)
     }
 
     #[instrument(skip_all)]
@@ -202,14 +388,33 @@ where
             return Err(PidIssuanceError::Locked);
         }
 
-        info!("Checking if PidIssuerClient has session");
-        if !self.pid_issuer.has_session() {
-            return Err(PidIssuanceError::SessionState);
-        }
+        info!("Checking if CredentialIssuerClient has session");
+        let issuer_url = self
+            .issuer_client
+            .session_issuer_url()
+            .cloned()
+            .ok_or(PidIssuanceError::SessionState)?;
 
         let config = self.config_repository.config();
 
-        let instruction_result_public_key = config.account_server.instruction_result_public_key.clone().into();
+        // The configuration may have been updated since the session was started, so check the issuer again.
+        let mdoc_trust_anchors = config
+            .issuer_trust_anchors(&issuer_url)
+            .ok_or(PidIssuanceError::UnknownIssuer(issuer_url))?;
+
+        // Use the keys that were generated in advance, if any. These are taken out of the pool before they are
+        // used, so that they can never be used twice. As the pool only saves time, fall back to generating all
+        // keys during issuance if they cannot be taken from the pool.
+        let pooled_keys = key_pool::take_key_pool(&mut *self.storage.write().await)
+            .await
+            .unwrap_or_else(|error| {
+                warn!("Could not take keys from key pool in database: {}", error);
+
+                Vec::new()
+            });
+        let pooled_keys_count = pooled_keys.len();
+
+        let instruction_result_public_keys = config.account_server.instruction_result_public_keys();
 
         let remote_instruction = InstructionClient::new(
             pin,
@@ -218,30 +423,47 @@ where
             &self.account_provider_client,
             registration_data,
             &config.account_server.base_url,
-            &instruction_result_public_key,
+            &instruction_result_public_keys,
         );
-        let remote_key_factory = RemoteEcdsaKeyFactory::new(&remote_instruction);
+        let remote_key_factory = RemoteEcdsaKeyFactory::new_with_key_pool(&remote_instruction, pooled_keys);
 
         info!("Accepting PID by signing mdoc using Wallet Provider");
 
-        let mdocs = self
-            .pid_issuer
-            .accept_pid(&config.mdoc_trust_anchors(), &&remote_key_factory)
-            .await
-            .map_err(|error| {
-                match error {
-                    // We knowingly call unwrap() on the downcast to `RemoteEcdsaKeyError` here because we know
-                    // that it is the error type of the `RemoteEcdsaKeyFactory` we provide above.
-                    PidIssuerError::MdocError(nl_wallet_mdoc::Error::KeysError(KeysError::KeyGeneration(error))) => {
-                        match *error.downcast::<RemoteEcdsaKeyError>().unwrap() {
-                            RemoteEcdsaKeyError::Instruction(error) => PidIssuanceError::Instruction(error),
-                            RemoteEcdsaKeyError::Signature(error) => PidIssuanceError::Signature(error),
-                            RemoteEcdsaKeyError::KeyNotFound(identifier) => PidIssuanceError::KeyNotFound(identifier),
-                        }
+        let mdocs_result = self.connectivity.observe(
+            self.issuer_client
+                .accept_issuance(&mdoc_trust_anchors, &&remote_key_factory, &self.clock_skew)
+                .await,
+        );
+
+        // Return the keys that were not handed out to the issuer to the pool. Failing to do so only means
+        // that these keys are lost, so this should not prevent the issued mdocs from being stored below.
+        let unused_keys = remote_key_factory.into_unused_pooled_keys();
+        if !unused_keys.is_empty() {
+            info!(
+                "Used {} keys from key pool, returning {} unused keys",
+                pooled_keys_count - unused_keys.len(),
+                unused_keys.len()
+            );
+
+            if let Err(error) = key_pool::add_to_key_pool(&mut *self.storage.write().await, unused_keys).await {
+                warn!("Could not return unused keys to key pool in database: {}", error);
+            }
+        }
+
+        let mdocs = mdocs_result.map_err(|error| {
+            match error {
+                // We knowingly call unwrap() on the downcast to `RemoteEcdsaKeyError` here because we know
+                // that it is the error type of the `RemoteEcdsaKeyFactory` we provide above.
+                CredentialIssuerError::MdocError(nl_wallet_mdoc::Error::KeysError(KeysError::KeyGeneration(error))) => {
+                    match *error.downcast::<RemoteEcdsaKeyError>().unwrap() {
+                        RemoteEcdsaKeyError::Instruction(error) => PidIssuanceError::Instruction(error),
+                        RemoteEcdsaKeyError::Signature(error) => PidIssuanceError::Signature(error),
+                        RemoteEcdsaKeyError::KeyNotFound(identifier) => PidIssuanceError::KeyNotFound(identifier),
                     }
-                    _ => PidIssuanceError::PidIssuer(error),
                 }
-            })?;
+                _ => PidIssuanceError::PidIssuer(error),
+            }
+        })?;
 
         // Prepare events before storing mdocs, to avoid cloning mdocs
         let event = {
@@ -262,18 +484,24 @@ where
             WalletEvent::new_issuance(mdocs.into(), certificate)
         };
 
-        info!("PID accepted, storing mdoc in database");
-        self.storage
-            .get_mut()
-            .insert_mdocs(mdocs)
-            .await
-            .map_err(PidIssuanceError::MdocStorage)?;
+        // When renewing the PID, the existing cards are replaced in the same database transaction.
+        let mut storage = self.storage.write().await;
+        let store_result = if self.is_pid_renewal {
+            info!("PID renewal accepted, replacing mdocs in database");
+            storage.replace_mdocs(mdocs).await
+        } else {
+            info!("PID accepted, storing mdoc in database");
+            storage.insert_mdocs(mdocs).await
+        };
+        drop(storage);
+        store_result.map_err(PidIssuanceError::MdocStorage)?;
+        self.is_pid_renewal = false;
 
         self.store_history_event(event)
             .await
             .map_err(PidIssuanceError::HistoryStorage)?;
 
-        self.emit_documents().await.map_err(PidIssuanceError::MdocStorage)?;
+        self.emit_documents().await.map_err(PidIssuanceError::Documents)?;
 
         Ok(())
     }
@@ -281,24 +509,47 @@ where
 
 #[cfg(test)]
 mod tests {
-    use std::sync::{Arc, Mutex};
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
 
     use assert_matches::assert_matches;
     use chrono::{Days, Utc};
-    use mockall::predicate::*;
+    use mockall::{predicate::*, Sequence};
     use serial_test::serial;
     use url::Url;
 
     use nl_wallet_mdoc::{basic_sa_ext::UnsignedMdoc, holder::HolderError, issuer_shared::IssuanceError, Tdate};
+    use wallet_common::config::wallet_config::{CredentialIssuerConfiguration, IdentityProviderConfiguration};
 
     use crate::{
-        digid::{MockDigidSession, OpenIdError},
+        config::{default_configuration, LocalConfigurationRepository, UpdatingConfigurationRepository},
+        connectivity::Connectivity,
+        digid::{DigidSessionState, MockDigidSession, OpenIdError},
         document::{self, DocumentPersistence},
         wallet::tests,
     };
 
     use super::{super::tests::WalletWithMocks, *};
 
+    const OTHER_ISSUER_URL: &str = "http://diploma-issuer.example.com/";
+
+    fn pid_issuer_url() -> Url {
+        default_configuration().pid_issuance.pid_issuer_url
+    }
+
+    fn digid_session_state() -> DigidSessionState {
+        DigidSessionState {
+            redirect_uri_base: default_configuration().pid_issuance.digid_redirect_uri().unwrap(),
+            csrf_token: "csrf_token".to_string(),
+            nonce: "nonce".to_string(),
+            pkce_code_verifier: "pkce_code_verifier".to_string(),
+            auth_url: Url::parse("http://example.com/auth").unwrap(),
+            refresh_token: None,
+        }
+    }
+
     #[tokio::test]
     #[serial]
     async fn test_create_pid_issuance_auth_url() {
@@ -310,10 +561,11 @@ mod tests {
 
         // Set up `DigidSession` to have `start()` and `auth_url()` called on it.
         let session_start_context = MockDigidSession::start_context();
-        session_start_context.expect().returning(|_, _, _| {
+        session_start_context.expect().returning(|_, _| {
             let mut session = MockDigidSession::default();
 
             session.expect_auth_url().return_const(Url::parse(AUTH_URL).unwrap());
+            session.expect_to_state().return_const(digid_session_state());
 
             Ok(session)
         });
@@ -326,6 +578,92 @@ mod tests {
 
         assert_eq!(auth_url.as_str(), AUTH_URL);
         assert!(wallet.digid_session.is_some());
+        assert_eq!(wallet.selected_issuer_url, Some(pid_issuer_url()));
+
+        // The session should have been persisted, for the same issuer.
+        let session_data = wallet
+            .fetch_digid_session()
+            .await
+            .unwrap()
+            .expect("No persisted DigiD session");
+
+        assert_eq!(session_data.issuer_url, pid_issuer_url());
+        assert_eq!(session_data.session, digid_session_state());
+        assert!(!session_data.is_pid_renewal);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_create_issuance_auth_url_identity_provider() {
+        const AUTH_URL: &str = "http://eherkenning.example.com/auth";
+
+        // Prepare a registered and unlocked wallet with another credential
+        // issuer in its configuration, which uses its own identity provider.
+        let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
+
+        let issuer_url = Url::parse(OTHER_ISSUER_URL).unwrap();
+        let identity_provider = IdentityProviderConfiguration {
+            url: Url::parse("http://eherkenning.example.com/").unwrap(),
+            client_id: "eherkenning-client".to_string(),
+            scopes: vec!["openid".to_string()],
+        };
+        wallet.config_repository = {
+            let mut config = (*wallet.config_repository.config()).clone();
+            config.credential_issuers = vec![CredentialIssuerConfiguration {
+                issuer_url: issuer_url.clone(),
+                mdoc_trust_anchors: config.mdoc_trust_anchors.clone(),
+                identity_provider: identity_provider.clone().into(),
+            }];
+
+            UpdatingConfigurationRepository::new(
+                LocalConfigurationRepository::new(config),
+                Duration::from_secs(300),
+                Connectivity::default(),
+            )
+            .await
+        };
+
+        // The session should be started with the identity provider of the
+        // issuer, but with the same redirect URI as used for DigiD.
+        let redirect_uri = default_configuration().pid_issuance.digid_redirect_uri().unwrap();
+        let session_start_context = MockDigidSession::start_context();
+        session_start_context
+            .expect()
+            .with(eq(identity_provider), eq(redirect_uri))
+            .returning(|_, _| {
+                let mut session = MockDigidSession::default();
+
+                session.expect_auth_url().return_const(Url::parse(AUTH_URL).unwrap());
+                session.expect_to_state().return_const(digid_session_state());
+                session.expect_to_state().return_const(digid_session_state());
+
+                Ok(session)
+            });
+
+        let auth_url = wallet
+            .create_issuance_auth_url(issuer_url.clone())
+            .await
+            .expect("Could not generate issuance auth URL");
+
+        assert_eq!(auth_url.as_str(), AUTH_URL);
+        assert!(wallet.digid_session.is_some());
+        assert_eq!(wallet.selected_issuer_url, Some(issuer_url));
+    }
+
+    #[tokio::test]
+    async fn test_create_issuance_auth_url_error_unknown_issuer() {
+        let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
+
+        // Creating a DigiD authentication URL for an issuer that
+        // is not in the configuration should result in an error.
+        let error = wallet
+            .create_issuance_auth_url(Url::parse(OTHER_ISSUER_URL).unwrap())
+            .await
+            .expect_err("Issuance auth URL generation should have resulted in error");
+
+        assert_matches!(error, PidIssuanceError::UnknownIssuer(_));
+        assert!(wallet.digid_session.is_none());
+        assert!(wallet.selected_issuer_url.is_none());
     }
 
     #[tokio::test]
@@ -378,11 +716,11 @@ mod tests {
         // Prepare another wallet.
         let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
 
-        // Have the `PidIssuerClient` report that it has an active session.
-        wallet.pid_issuer.has_session = true;
+        // Have the `CredentialIssuerClient` report that it has an active session.
+        wallet.issuer_client.session_issuer_url = pid_issuer_url().into();
 
         // Creating a DigiD authentication URL on a `Wallet` that has
-        // an active `PidIssuerClient` session should return an error.
+        // an active `CredentialIssuerClient` session should return an error.
         let error = wallet
             .create_pid_issuance_auth_url()
             .await
@@ -400,7 +738,7 @@ mod tests {
         let session_start_context = MockDigidSession::start_context();
         session_start_context
             .expect()
-            .return_once(|_, _, _| Err(OpenIdError::from(openid::error::Error::CannotBeABase).into()));
+            .return_once(|_, _| Err(OpenIdError::from(openid::error::Error::CannotBeABase).into()));
 
         // The error should be forwarded when attempting to create a DigiD authentication URL.
         let error = wallet
@@ -411,6 +749,66 @@ mod tests {
         assert_matches!(error, PidIssuanceError::DigidSessionStart(_));
     }
 
+    #[tokio::test]
+    #[serial]
+    async fn test_renew_pid() {
+        const AUTH_URL: &str = "http://example.com/auth";
+
+        // Prepare a registered and unlocked wallet that contains a PID.
+        let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
+        wallet
+            .storage
+            .write()
+            .await
+            .insert_mdocs(vec![vec![tests::create_full_pid_mdoc().await].into()])
+            .await
+            .unwrap();
+
+        // Set up `DigidSession` to have `start()` and `auth_url()` called on it.
+        let session_start_context = MockDigidSession::start_context();
+        session_start_context.expect().returning(|_, _| {
+            let mut session = MockDigidSession::default();
+
+            session.expect_auth_url().return_const(Url::parse(AUTH_URL).unwrap());
+            session.expect_to_state().return_const(digid_session_state());
+
+            Ok(session)
+        });
+
+        // Starting PID renewal should result in a DigiD authentication URL for the PID issuer.
+        let auth_url = wallet.renew_pid().await.expect("Could not start PID renewal");
+
+        assert_eq!(auth_url.as_str(), AUTH_URL);
+        assert!(wallet.digid_session.is_some());
+        assert_eq!(wallet.selected_issuer_url, Some(pid_issuer_url()));
+        assert!(wallet.is_pid_renewal);
+        assert!(wallet.fetch_digid_session().await.unwrap().unwrap().is_pid_renewal);
+
+        // Cancelling the renewal should reset the state.
+        wallet
+            .cancel_pid_issuance()
+            .await
+            .expect("Could not cancel PID renewal");
+
+        assert!(!wallet.is_pid_renewal);
+        assert!(wallet.fetch_digid_session().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_renew_pid_error_no_pid() {
+        let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
+
+        // Renewing the PID on a wallet without a PID should result in an error.
+        let error = wallet
+            .renew_pid()
+            .await
+            .expect_err("PID renewal should have resulted in error");
+
+        assert_matches!(error, PidIssuanceError::NoPidToRenew);
+        assert!(wallet.digid_session.is_none());
+        assert!(!wallet.is_pid_renewal);
+    }
+
     #[tokio::test]
     async fn test_cancel_pid_issuance() {
         let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
@@ -421,9 +819,39 @@ mod tests {
         assert!(wallet.digid_session.is_some());
 
         // Cancelling PID issuance should clear this session.
-        wallet.cancel_pid_issuance().expect("Could not cancel PID issuance");
+        wallet
+            .cancel_pid_issuance()
+            .await
+            .expect("Could not cancel PID issuance");
 
         assert!(wallet.digid_session.is_none());
+        assert!(wallet.selected_issuer_url.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cancel_pid_issuance_persisted() {
+        let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
+
+        // Set up a `DigidSession` that is only present in storage, as if the app was restarted.
+        wallet
+            .storage
+            .write()
+            .await
+            .insert_data(&DigidSessionData {
+                issuer_url: pid_issuer_url(),
+                is_pid_renewal: false,
+                session: digid_session_state(),
+            })
+            .await
+            .unwrap();
+
+        // Cancelling PID issuance should remove the persisted session.
+        wallet
+            .cancel_pid_issuance()
+            .await
+            .expect("Could not cancel PID issuance");
+
+        assert!(wallet.fetch_digid_session().await.unwrap().is_none());
     }
 
     #[tokio::test]
@@ -436,6 +864,7 @@ mod tests {
         // Cancelling PID issuance on a locked wallet should result in an error.
         let error = wallet
             .cancel_pid_issuance()
+            .await
             .expect_err("Cancelling PID issuance should have resulted in an error");
 
         assert_matches!(error, PidIssuanceError::Locked);
@@ -449,6 +878,7 @@ mod tests {
         // Cancelling PID issuance on an unregistered wallet should result in an error.
         let error = wallet
             .cancel_pid_issuance()
+            .await
             .expect_err("Cancelling PID issuance should have resulted in an error");
 
         assert_matches!(error, PidIssuanceError::NotRegistered);
@@ -463,6 +893,7 @@ mod tests {
         // active DigiD session should result in an error.
         let error = wallet
             .cancel_pid_issuance()
+            .await
             .expect_err("Cancelling PID issuance should have resulted in an error");
 
         assert_matches!(error, PidIssuanceError::SessionState);
@@ -480,6 +911,7 @@ mod tests {
         wallet.digid_session = {
             let mut session = MockDigidSession::default();
 
+            session.expect_can_refresh_access_token().return_const(false);
             session
                 .expect_get_access_token()
                 .with(eq(Url::parse(REDIRECT_URI).unwrap()))
@@ -488,9 +920,10 @@ mod tests {
             session
         }
         .into();
+        wallet.selected_issuer_url = pid_issuer_url().into();
 
-        // Set up the `PidIssuerClient` to return one `UnsignedMdoc`.
-        wallet.pid_issuer.unsigned_mdocs = vec![document::create_full_unsigned_pid_mdoc()];
+        // Set up the `CredentialIssuerClient` to return one `UnsignedMdoc`.
+        wallet.issuer_client.unsigned_mdocs = vec![document::create_full_unsigned_pid_mdoc()];
 
         // Continuing PID issuance should result in one preview `Document`.
         let documents = wallet
@@ -502,6 +935,59 @@ mod tests {
         assert_matches!(documents[0].persistence, DocumentPersistence::InMemory);
     }
 
+    #[tokio::test]
+    #[serial]
+    async fn test_continue_pid_issuance_restored() {
+        // Prepare a registered and unlocked wallet.
+        let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
+
+        // Set up a `DigidSession` that is only present in storage, as if the app was restarted.
+        wallet
+            .storage
+            .write()
+            .await
+            .insert_data(&DigidSessionData {
+                issuer_url: pid_issuer_url(),
+                is_pid_renewal: true,
+                session: digid_session_state(),
+            })
+            .await
+            .unwrap();
+
+        // Set up `DigidSession` to be restored for DigiD, after which it returns an access token.
+        let session_restore_context = MockDigidSession::restore_context();
+        session_restore_context
+            .expect()
+            .with(
+                eq(default_configuration().pid_issuance.digid()),
+                eq(digid_session_state()),
+            )
+            .return_once(|_, _| {
+                let mut session = MockDigidSession::default();
+
+                session.expect_can_refresh_access_token().return_const(false);
+                session
+                    .expect_get_access_token()
+                    .with(eq(Url::parse(REDIRECT_URI).unwrap()))
+                    .return_once(|_| Ok(ACCESS_TOKEN.to_string()));
+
+                Ok(session)
+            });
+
+        // Set up the `CredentialIssuerClient` to return one `UnsignedMdoc`.
+        wallet.issuer_client.unsigned_mdocs = vec![document::create_full_unsigned_pid_mdoc()];
+
+        // Continuing PID issuance should restore the session and result in one preview `Document`.
+        let documents = wallet
+            .continue_pid_issuance(&Url::parse(REDIRECT_URI).unwrap())
+            .await
+            .expect("Could not continue PID issuance");
+
+        assert_eq!(documents.len(), 1);
+        assert!(wallet.is_pid_renewal);
+        assert!(wallet.fetch_digid_session().await.unwrap().is_none());
+    }
+
     #[tokio::test]
     async fn test_continue_pid_issuance_error_locked() {
         // Prepare a registered and locked wallet.
@@ -555,6 +1041,7 @@ mod tests {
         wallet.digid_session = {
             let mut session = MockDigidSession::default();
 
+            session.expect_can_refresh_access_token().return_const(false);
             session
                 .expect_get_access_token()
                 .with(eq(Url::parse(REDIRECT_URI).unwrap()))
@@ -563,6 +1050,7 @@ mod tests {
             session
         }
         .into();
+        wallet.selected_issuer_url = pid_issuer_url().into();
 
         // Continuing PID issuance on a wallet should forward this error.
         let error = wallet
@@ -581,19 +1069,41 @@ mod tests {
         // Set up a `DigidSession` that returns an access token.
         wallet.digid_session = {
             let mut session = MockDigidSession::default();
+            let mut seq = Sequence::new();
+
+            session.expect_to_state().return_const(digid_session_state());
 
+            // The session is not refreshable before authentication, but is afterwards.
+            session
+                .expect_can_refresh_access_token()
+                .times(1)
+                .in_sequence(&mut seq)
+                .return_const(false);
             session
                 .expect_get_access_token()
                 .with(eq(Url::parse(REDIRECT_URI).unwrap()))
+                .times(1)
+                .in_sequence(&mut seq)
                 .return_once(|_| Ok(ACCESS_TOKEN.to_string()));
+            session
+                .expect_can_refresh_access_token()
+                .times(2)
+                .in_sequence(&mut seq)
+                .return_const(true);
+            session
+                .expect_refresh_access_token()
+                .times(1)
+                .in_sequence(&mut seq)
+                .return_once(|| Ok(ACCESS_TOKEN.to_string()));
 
             session
         }
         .into();
+        wallet.selected_issuer_url = pid_issuer_url().into();
 
-        // Set up the `PidIssuerClient` to return an error.
-        wallet.pid_issuer.next_error =
-            PidIssuerError::from(nl_wallet_mdoc::Error::from(IssuanceError::MissingSessionId)).into();
+        // Set up the `CredentialIssuerClient` to return an error.
+        wallet.issuer_client.next_error =
+            CredentialIssuerError::from(nl_wallet_mdoc::Error::from(IssuanceError::MissingSessionId)).into();
 
         // Continuing PID issuance on a wallet should forward this error.
         let error = wallet
@@ -602,6 +1112,23 @@ mod tests {
             .expect_err("Continuing PID issuance should have resulted in error");
 
         assert_matches!(error, PidIssuanceError::PidIssuer(_));
+
+        // The authenticated `DigidSession` should have been retained, so that issuance can be retried.
+        assert!(wallet.digid_session.is_some());
+        assert!(wallet.selected_issuer_url.is_some());
+        assert!(wallet.fetch_digid_session().await.unwrap().is_some());
+
+        // Retrying should refresh the access token, after which issuance should succeed.
+        wallet.issuer_client.unsigned_mdocs = vec![document::create_full_unsigned_pid_mdoc()];
+
+        let documents = wallet
+            .continue_pid_issuance(&Url::parse(REDIRECT_URI).unwrap())
+            .await
+            .expect("Could not continue PID issuance");
+
+        assert_eq!(documents.len(), 1);
+        assert!(wallet.digid_session.is_none());
+        assert!(wallet.fetch_digid_session().await.unwrap().is_none());
     }
 
     #[tokio::test]
@@ -613,6 +1140,7 @@ mod tests {
         wallet.digid_session = {
             let mut session = MockDigidSession::default();
 
+            session.expect_can_refresh_access_token().return_const(false);
             session
                 .expect_get_access_token()
                 .with(eq(Url::parse(REDIRECT_URI).unwrap()))
@@ -621,9 +1149,10 @@ mod tests {
             session
         }
         .into();
+        wallet.selected_issuer_url = pid_issuer_url().into();
 
-        // Set up the `PidIssuerClient` to return an `UnsignedMdoc` with an unknown doctype.
-        wallet.pid_issuer.unsigned_mdocs = vec![UnsignedMdoc {
+        // Set up the `CredentialIssuerClient` to return an `UnsignedMdoc` with an unknown doctype.
+        wallet.issuer_client.unsigned_mdocs = vec![UnsignedMdoc {
             doc_type: "foobar".to_string(),
             valid_from: Tdate::now(),
             valid_until: (Utc::now() + Days::new(365)).into(),
@@ -645,8 +1174,8 @@ mod tests {
         // Prepare a registered and unlocked wallet.
         let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
 
-        // Set up the `PidIssuerClient` to report having a session
-        wallet.pid_issuer.has_session = true;
+        // Set up the `CredentialIssuerClient` to report having a session
+        wallet.issuer_client.session_issuer_url = pid_issuer_url().into();
 
         // Cancelling PID issuance should not fail.
         wallet
@@ -691,7 +1220,7 @@ mod tests {
         let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
 
         // Rejecting PID issuance on a `Wallet` that has a
-        // `PidIssuerClient` with no session should return an error.
+        // `CredentialIssuerClient` with no session should return an error.
         let error = wallet
             .reject_pid_issuance()
             .await
@@ -705,10 +1234,10 @@ mod tests {
         // Prepare a registered and unlocked wallet.
         let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
 
-        // Set up the `PidIssuerClient` to report having a session, then return an error.
-        wallet.pid_issuer.has_session = true;
-        wallet.pid_issuer.next_error =
-            PidIssuerError::from(nl_wallet_mdoc::Error::from(IssuanceError::MissingSessionId)).into();
+        // Set up the `CredentialIssuerClient` to report having a session, then return an error.
+        wallet.issuer_client.session_issuer_url = pid_issuer_url().into();
+        wallet.issuer_client.next_error =
+            CredentialIssuerError::from(nl_wallet_mdoc::Error::from(IssuanceError::MissingSessionId)).into();
 
         // Rejecting PID issuance on a wallet should forward this error.
         let error = wallet
@@ -738,10 +1267,10 @@ mod tests {
             .await
             .expect("Could not set documents callback");
 
-        // Have the `PidIssuerClient` accept the PID with a single
+        // Have the `CredentialIssuerClient` accept the PID with a single
         // instance of `MdocCopies`, which contains a single valid `Mdoc`.
-        wallet.pid_issuer.has_session = true;
-        wallet.pid_issuer.mdoc_copies = vec![vec![tests::create_full_pid_mdoc().await].into()];
+        wallet.issuer_client.session_issuer_url = pid_issuer_url().into();
+        wallet.issuer_client.mdoc_copies = vec![vec![tests::create_full_pid_mdoc().await].into()];
 
         // Accept the PID issuance with the PIN.
         wallet
@@ -768,15 +1297,103 @@ mod tests {
         assert_eq!(document.doc_type, "com.example.pid");
     }
 
+    #[tokio::test]
+    async fn test_accept_issuance_credential_issuer() {
+        // Prepare a registered and unlocked wallet with another credential issuer in its configuration.
+        let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
+
+        let issuer_url = Url::parse(OTHER_ISSUER_URL).unwrap();
+        wallet.config_repository = {
+            let mut config = (*wallet.config_repository.config()).clone();
+            config.credential_issuers = vec![CredentialIssuerConfiguration {
+                issuer_url: issuer_url.clone(),
+                mdoc_trust_anchors: config.mdoc_trust_anchors.clone(),
+                identity_provider: None,
+            }];
+
+            UpdatingConfigurationRepository::new(
+                LocalConfigurationRepository::new(config),
+                Duration::from_secs(300),
+                Connectivity::default(),
+            )
+            .await
+        };
+
+        // Have the `CredentialIssuerClient` hold a session with the other
+        // issuer and accept the issuance with a single valid `Mdoc`.
+        wallet.issuer_client.session_issuer_url = issuer_url.into();
+        wallet.issuer_client.mdoc_copies = vec![vec![tests::create_full_pid_mdoc().await].into()];
+
+        wallet
+            .accept_pid_issuance(PIN.to_string())
+            .await
+            .expect("Could not accept issuance");
+
+        let events = wallet.storage.read().await.fetch_wallet_events().await.unwrap();
+        assert_eq!(events.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_accept_pid_renewal() {
+        // Prepare a registered and unlocked wallet that contains a PID and has an issuance event in its history.
+        let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
+
+        wallet.issuer_client.session_issuer_url = pid_issuer_url().into();
+        wallet.issuer_client.mdoc_copies = vec![vec![tests::create_full_pid_mdoc().await].into()];
+
+        wallet
+            .accept_pid_issuance(PIN.to_string())
+            .await
+            .expect("Could not accept PID issuance");
+
+        // Accept a renewed PID.
+        wallet.is_pid_renewal = true;
+        wallet.issuer_client.session_issuer_url = pid_issuer_url().into();
+        wallet.issuer_client.mdoc_copies = vec![vec![tests::create_full_pid_mdoc().await].into()];
+
+        wallet
+            .accept_pid_issuance(PIN.to_string())
+            .await
+            .expect("Could not accept PID renewal");
+
+        assert!(!wallet.is_pid_renewal);
+
+        // The existing PID should have been replaced, instead of another one being added.
+        let mdocs = wallet.storage.read().await.fetch_unique_mdocs().await.unwrap();
+        assert_eq!(mdocs.len(), 1);
+
+        // The history should contain both issuance events.
+        let events = wallet.storage.read().await.fetch_wallet_events().await.unwrap();
+        assert_eq!(events.len(), 2);
+        assert!(events.iter().all(|event| matches!(event, WalletEvent::Issuance { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_accept_issuance_error_unknown_issuer() {
+        // Prepare a registered and unlocked wallet.
+        let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
+
+        // Have the `CredentialIssuerClient` hold a session with an issuer that is not in the configuration.
+        wallet.issuer_client.session_issuer_url = Url::parse(OTHER_ISSUER_URL).unwrap().into();
+        wallet.issuer_client.mdoc_copies = vec![vec![tests::create_full_pid_mdoc().await].into()];
+
+        let error = wallet
+            .accept_pid_issuance(PIN.to_string())
+            .await
+            .expect_err("Accepting issuance should have resulted in an error");
+
+        assert_matches!(error, PidIssuanceError::UnknownIssuer(_));
+    }
+
     #[tokio::test]
     async fn test_accept_pid_issuance_missing_issuer_registration() {
         // Prepare a registered and unlocked wallet.
         let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
 
-        // Have the `PidIssuerClient` accept the PID with a single instance of `MdocCopies`, which contains a single
+        // Have the `CredentialIssuerClient` accept the PID with a single instance of `MdocCopies`, which contains a single
         // valid `Mdoc`, but signed with a Certificate that is missing IssuerRegistration
-        wallet.pid_issuer.has_session = true;
-        wallet.pid_issuer.mdoc_copies = vec![vec![tests::create_full_pid_mdoc_unauthenticated().await].into()];
+        wallet.issuer_client.session_issuer_url = pid_issuer_url().into();
+        wallet.issuer_client.mdoc_copies = vec![vec![tests::create_full_pid_mdoc_unauthenticated().await].into()];
 
         // Accept the PID issuance with the PIN.
         let error = wallet
@@ -826,7 +1443,7 @@ mod tests {
         // Prepare a registered and unlocked wallet.
         let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
 
-        // Accepting PID issuance on a `Wallet` with a `PidIssuerClient`
+        // Accepting PID issuance on a `Wallet` with a `CredentialIssuerClient`
         // that has no session should result in an error.
         let error = wallet
             .accept_pid_issuance(PIN.to_string())
@@ -840,9 +1457,9 @@ mod tests {
         // Prepare a registered and unlocked wallet.
         let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
 
-        // Have the `PidIssuerClient` return a particular `RemoteEcdsaKeyError`.
-        wallet.pid_issuer.has_session = true;
-        wallet.pid_issuer.next_error = PidIssuerError::MdocError(nl_wallet_mdoc::Error::KeysError(
+        // Have the `CredentialIssuerClient` return a particular `RemoteEcdsaKeyError`.
+        wallet.issuer_client.session_issuer_url = pid_issuer_url().into();
+        wallet.issuer_client.next_error = CredentialIssuerError::MdocError(nl_wallet_mdoc::Error::KeysError(
             KeysError::KeyGeneration(Box::new(key_error)),
         ))
         .into();
@@ -886,10 +1503,10 @@ mod tests {
         // Prepare a registered and unlocked wallet.
         let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
 
-        // Have the `PidIssuerClient` return an error.
-        wallet.pid_issuer.has_session = true;
-        wallet.pid_issuer.next_error =
-            PidIssuerError::MdocError(nl_wallet_mdoc::Error::from(HolderError::ReaderAuthMissing)).into();
+        // Have the `CredentialIssuerClient` return an error.
+        wallet.issuer_client.session_issuer_url = pid_issuer_url().into();
+        wallet.issuer_client.next_error =
+            CredentialIssuerError::MdocError(nl_wallet_mdoc::Error::from(HolderError::ReaderAuthMissing)).into();
 
         // Accepting PID issuance should result in an error.
         let error = wallet
@@ -905,11 +1522,11 @@ mod tests {
         // Prepare a registered and unlocked wallet.
         let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
 
-        // Have the `PidIssuerClient` report a a session
+        // Have the `CredentialIssuerClient` report a a session
         // and have the database return an error on query.
-        wallet.pid_issuer.has_session = true;
-        wallet.pid_issuer.mdoc_copies = vec![vec![tests::create_full_pid_mdoc().await].into()];
-        wallet.storage.get_mut().has_query_error = true;
+        wallet.issuer_client.session_issuer_url = pid_issuer_url().into();
+        wallet.issuer_client.mdoc_copies = vec![vec![tests::create_full_pid_mdoc().await].into()];
+        wallet.storage.write().await.has_query_error = true;
 
         // Accepting PID issuance should result in an error.
         let error = wallet
@@ -0,0 +1,410 @@
+use std::{iter, sync::Arc};
+
+use p256::ecdsa::VerifyingKey;
+use tokio::sync::RwLock;
+use tracing::{info, instrument};
+
+use platform_support::hw_keystore::PlatformEcdsaKey;
+use wallet_common::{
+    account::messages::instructions::{GenerateKey, GenerateKeyResult},
+    config::wallet_config::WalletConfiguration,
+    utils::random_string,
+};
+
+use crate::{
+    account_provider::AccountProviderClient,
+    config::ConfigurationRepository,
+    instruction::{InstructionClient, InstructionError},
+    storage::{KeyPoolData, PooledKey, RegistrationData, Storage, StorageError},
+};
+
+use super::Wallet;
+
+/// The number of remote keys that are kept available in advance, which is enough for the PID and address cards.
+const KEY_POOL_SIZE: usize = 10;
+
+#[derive(Debug, thiserror::Error)]
+pub enum KeyPoolError {
+    #[error("wallet is not registered")]
+    NotRegistered,
+    #[error("wallet is locked")]
+    Locked,
+    #[error("error sending instruction to Wallet Provider: {0}")]
+    Instruction(#[from] InstructionError),
+    #[error("could not access key pool in database: {0}")]
+    Storage(#[from] StorageError),
+}
+
+/// Replenishes the key pool without borrowing the [`Wallet`] it was created from, so that the wallet does not need
+/// to stay borrowed while the instruction is sent to the Wallet Provider. See [`Wallet::key_pool_replenisher()`].
+pub struct KeyPoolReplenisher<S, PEK, APC> {
+    pin: String,
+    count: usize,
+    storage: Arc<RwLock<S>>,
+    hw_privkey: PEK,
+    account_provider_client: APC,
+    registration: RegistrationData,
+    config: Arc<WalletConfiguration>,
+}
+
+impl<S, PEK, APC> KeyPoolReplenisher<S, PEK, APC>
+where
+    S: Storage,
+    PEK: PlatformEcdsaKey,
+    APC: AccountProviderClient,
+{
+    /// Generate the keys that were missing from the pool when this replenisher was created and add them to the pool.
+    /// Returns the number of keys that were added to the pool.
+    #[instrument(skip_all)]
+    pub async fn replenish(self) -> Result<usize, KeyPoolError> {
+        generate_pooled_keys(
+            self.pin,
+            self.count,
+            &self.storage,
+            &self.hw_privkey,
+            &self.account_provider_client,
+            &self.registration,
+            &self.config,
+        )
+        .await?;
+
+        Ok(self.count)
+    }
+}
+
+impl<CR, S, PEK, APC, DGS, CIC, MDS> Wallet<CR, S, PEK, APC, DGS, CIC, MDS> {
+    /// Generate remote keys in the Wallet Provider in advance, until the pool contains [`KEY_POOL_SIZE`] unused keys.
+    /// These keys are handed out during issuance, which saves a round trip to the Wallet Provider. As generating keys
+    /// requires an instruction, this should be called right after unlocking the wallet with the PIN. Returns the
+    /// number of keys that were added to the pool.
+    #[instrument(skip_all)]
+    pub async fn replenish_key_pool(&self, pin: String) -> Result<usize, KeyPoolError>
+    where
+        CR: ConfigurationRepository,
+        S: Storage,
+        PEK: PlatformEcdsaKey,
+        APC: AccountProviderClient,
+    {
+        let (registration_data, count) = self.key_pool_shortfall().await?;
+
+        generate_pooled_keys(
+            pin,
+            count,
+            &self.storage,
+            &self.hw_privkey,
+            &self.account_provider_client,
+            registration_data,
+            &self.config_repository.config(),
+        )
+        .await?;
+
+        Ok(count)
+    }
+
+    /// Prepare replenishing the key pool like [`Wallet::replenish_key_pool()`] does, but return a
+    /// [`KeyPoolReplenisher`] that owns everything it needs. This allows the caller to release its lock
+    /// on the wallet before generating the keys, which requires a round trip to the Wallet Provider.
+    #[instrument(skip_all)]
+    pub async fn key_pool_replenisher(&self, pin: String) -> Result<KeyPoolReplenisher<S, PEK, APC>, KeyPoolError>
+    where
+        CR: ConfigurationRepository,
+        S: Storage,
+        PEK: PlatformEcdsaKey + Clone,
+        APC: AccountProviderClient + Clone,
+    {
+        let (registration_data, count) = self.key_pool_shortfall().await?;
+
+        let replenisher = KeyPoolReplenisher {
+            pin,
+            count,
+            storage: Arc::clone(&self.storage),
+            hw_privkey: self.hw_privkey.clone(),
+            account_provider_client: self.account_provider_client.clone(),
+            registration: registration_data.clone(),
+            config: self.config_repository.config(),
+        };
+
+        Ok(replenisher)
+    }
+
+    /// Check whether the wallet is able to replenish the key pool and return the number of keys missing from it.
+    async fn key_pool_shortfall(&self) -> Result<(&RegistrationData, usize), KeyPoolError>
+    where
+        S: Storage,
+    {
+        self.start_operation();
+        info!("Replenishing key pool");
+
+        info!("Checking if registered");
+        let registration_data = self.registration.as_ref().ok_or_else(|| KeyPoolError::NotRegistered)?;
+
+        info!("Checking if locked");
+        if self.lock.is_locked() {
+            return Err(KeyPoolError::Locked);
+        }
+
+        let pool_size = fetch_key_pool(&*self.storage.read().await).await?.len();
+        let count = KEY_POOL_SIZE.saturating_sub(pool_size);
+
+        if count == 0 {
+            info!("Key pool is full, not generating any keys");
+        }
+
+        Ok((registration_data, count))
+    }
+}
+
+async fn generate_pooled_keys<S, PEK, APC>(
+    pin: String,
+    count: usize,
+    storage: &RwLock<S>,
+    hw_privkey: &PEK,
+    account_provider_client: &APC,
+    registration_data: &RegistrationData,
+    config: &WalletConfiguration,
+) -> Result<(), KeyPoolError>
+where
+    S: Storage,
+    PEK: PlatformEcdsaKey,
+    APC: AccountProviderClient,
+{
+    if count == 0 {
+        return Ok(());
+    }
+
+    let instruction_result_public_keys = config.account_server.instruction_result_public_keys();
+
+    let remote_instruction = InstructionClient::new(
+        pin,
+        storage,
+        hw_privkey,
+        account_provider_client,
+        registration_data,
+        &config.account_server.base_url,
+        &instruction_result_public_keys,
+    );
+
+    info!("Generating {} keys for the key pool in Wallet Provider", count);
+    let identifiers = iter::repeat_with(|| random_string(32)).take(count).collect();
+    let result: GenerateKeyResult = remote_instruction.send(GenerateKey { identifiers }).await?;
+
+    add_to_key_pool(
+        &mut *storage.write().await,
+        result
+            .public_keys
+            .into_iter()
+            .map(|(identifier, public_key)| (identifier, public_key.0)),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Remove all keys from the pool and return them. The keys are removed before they are used, so that a key can never
+/// be handed out twice, even if the wallet is unable to update the pool after using it.
+pub(super) async fn take_key_pool<S>(storage: &mut S) -> Result<Vec<(String, VerifyingKey)>, StorageError>
+where
+    S: Storage,
+{
+    let key_pool = fetch_key_pool(storage).await?;
+
+    if !key_pool.is_empty() {
+        store_key_pool(storage, Vec::new()).await?;
+    }
+
+    Ok(key_pool)
+}
+
+/// Add keys to the pool, keeping the keys that are in the pool already. The caller should hold the write lock on
+/// the storage for the duration of this call, so that no keys are lost when the pool is modified concurrently.
+pub(super) async fn add_to_key_pool<S>(
+    storage: &mut S,
+    keys: impl IntoIterator<Item = (String, VerifyingKey)>,
+) -> Result<(), StorageError>
+where
+    S: Storage,
+{
+    let mut key_pool = fetch_key_pool(storage).await?;
+    key_pool.extend(keys);
+
+    store_key_pool(storage, key_pool).await
+}
+
+pub(super) async fn fetch_key_pool<S>(storage: &S) -> Result<Vec<(String, VerifyingKey)>, StorageError>
+where
+    S: Storage,
+{
+    let key_pool = storage
+        .fetch_data::<KeyPoolData>()
+        .await?
+        .map(|key_pool| {
+            key_pool
+                .keys
+                .into_iter()
+                .map(|key| (key.identifier, key.public_key.0))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(key_pool)
+}
+
+pub(super) async fn store_key_pool<S>(storage: &mut S, keys: Vec<(String, VerifyingKey)>) -> Result<(), StorageError>
+where
+    S: Storage,
+{
+    let key_pool = KeyPoolData {
+        keys: keys
+            .into_iter()
+            .map(|(identifier, public_key)| PooledKey {
+                identifier,
+                public_key: public_key.into(),
+            })
+            .collect(),
+    };
+
+    match storage.fetch_data::<KeyPoolData>().await? {
+        Some(_) => storage.update_data(&key_pool).await,
+        None => storage.insert_data(&key_pool).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use p256::{ecdsa::SigningKey, elliptic_curve::rand_core::OsRng};
+
+    use wallet_common::{
+        account::messages::instructions::{Instruction, InstructionResultClaims},
+        jwt::Jwt,
+        utils,
+    };
+
+    use super::{
+        super::tests::{WalletWithMocks, ACCOUNT_SERVER_KEYS},
+        *,
+    };
+
+    const PIN: &str = "051097";
+
+    fn random_pooled_keys(count: usize) -> Vec<(String, VerifyingKey)> {
+        iter::repeat_with(|| (random_string(32), *SigningKey::random(&mut OsRng).verifying_key()))
+            .take(count)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_wallet_replenish_key_pool() {
+        let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
+
+        // Start with a partially filled key pool.
+        store_key_pool(&mut *wallet.storage.write().await, random_pooled_keys(4))
+            .await
+            .unwrap();
+
+        let challenge = utils::random_bytes(32);
+        wallet
+            .account_provider_client
+            .expect_instruction_challenge()
+            .return_once(move |_, _| Ok(challenge));
+
+        wallet.account_provider_client.expect_instruction().return_once(
+            move |_, instruction: Instruction<GenerateKey>| {
+                let identifiers = instruction
+                    .instruction
+                    .dangerous_parse_unverified()
+                    .unwrap()
+                    .payload
+                    .identifiers;
+
+                // Only the shortfall should be generated.
+                assert_eq!(identifiers.len(), KEY_POOL_SIZE - 4);
+
+                let result_claims = InstructionResultClaims {
+                    result: GenerateKeyResult {
+                        public_keys: identifiers
+                            .into_iter()
+                            .map(|identifier| (identifier, (*SigningKey::random(&mut OsRng).verifying_key()).into()))
+                            .collect(),
+                    },
+                    iss: "wallet_unit_test".to_string(),
+                    iat: jsonwebtoken::get_current_timestamp(),
+                };
+                let result = futures::executor::block_on(Jwt::sign_with_sub(
+                    &result_claims,
+                    &ACCOUNT_SERVER_KEYS.instruction_result_signing_key,
+                ))
+                .unwrap();
+
+                Ok(result)
+            },
+        );
+
+        let count = wallet
+            .replenish_key_pool(PIN.to_string())
+            .await
+            .expect("Could not replenish key pool");
+
+        assert_eq!(count, KEY_POOL_SIZE - 4);
+
+        let key_pool = fetch_key_pool(&*wallet.storage.read().await).await.unwrap();
+        assert_eq!(key_pool.len(), KEY_POOL_SIZE);
+
+        // Replenishing a full pool should not send any instruction.
+        let count = wallet
+            .replenish_key_pool(PIN.to_string())
+            .await
+            .expect("Could not replenish key pool");
+
+        assert_eq!(count, 0);
+    }
+
+    #[tokio::test]
+    async fn test_wallet_replenish_key_pool_error_locked() {
+        let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
+
+        wallet.lock();
+
+        let error = wallet
+            .replenish_key_pool(PIN.to_string())
+            .await
+            .expect_err("Replenishing key pool should have resulted in error");
+
+        assert_matches!(error, KeyPoolError::Locked);
+    }
+
+    #[tokio::test]
+    async fn test_wallet_replenish_key_pool_error_not_registered() {
+        let wallet = WalletWithMocks::new_unregistered().await;
+
+        let error = wallet
+            .replenish_key_pool(PIN.to_string())
+            .await
+            .expect_err("Replenishing key pool should have resulted in error");
+
+        assert_matches!(error, KeyPoolError::NotRegistered);
+    }
+
+    #[tokio::test]
+    async fn test_take_and_add_to_key_pool() {
+        let wallet = WalletWithMocks::new_registered_and_unlocked().await;
+        let mut storage = wallet.storage.write().await;
+
+        let keys = random_pooled_keys(3);
+        store_key_pool(&mut *storage, keys.clone()).await.unwrap();
+
+        // Taking the keys should leave the pool empty, so that they cannot be taken a second time.
+        let taken_keys = take_key_pool(&mut *storage).await.unwrap();
+        assert_eq!(taken_keys, keys);
+        assert!(fetch_key_pool(&*storage).await.unwrap().is_empty());
+        assert!(take_key_pool(&mut *storage).await.unwrap().is_empty());
+
+        // Keys that were added to the pool in the meantime should be kept when returning unused keys.
+        let new_keys = random_pooled_keys(2);
+        add_to_key_pool(&mut *storage, new_keys.clone()).await.unwrap();
+        add_to_key_pool(&mut *storage, taken_keys[1..].to_vec()).await.unwrap();
+
+        let key_pool = fetch_key_pool(&*storage).await.unwrap();
+        assert_eq!(key_pool, [new_keys, taken_keys[1..].to_vec()].concat());
+    }
+}
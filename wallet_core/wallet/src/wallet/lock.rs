@@ -1,14 +1,17 @@
 use futures::future::TryFutureExt;
 use platform_support::hw_keystore::PlatformEcdsaKey;
-use tracing::{info, instrument};
+use tracing::{info, instrument, warn};
 
 use wallet_common::account::messages::instructions::CheckPin;
 
 use crate::{
     account_provider::AccountProviderClient,
     config::ConfigurationRepository,
+    disclosure::MdocDisclosureSession,
     instruction::{InstructionClient, InstructionError},
-    storage::Storage,
+    lock::AppState,
+    pin::lockout::PinLockout,
+    storage::{Storage, StorageError},
 };
 
 use super::Wallet;
@@ -21,9 +24,11 @@ pub enum WalletUnlockError {
     NotLocked,
     #[error("error sending instruction to Wallet Provider: {0}")]
     Instruction(#[from] InstructionError),
+    #[error("could not read PIN lockout from database storage: {0}")]
+    Storage(#[from] StorageError),
 }
 
-impl<CR, S, PEK, APC, DGS, PIC, MDS> Wallet<CR, S, PEK, APC, DGS, PIC, MDS> {
+impl<CR, S, PEK, APC, DGS, CIC, MDS> Wallet<CR, S, PEK, APC, DGS, CIC, MDS> {
     pub fn is_locked(&self) -> bool {
         self.lock.is_locked()
     }
@@ -44,6 +49,93 @@ impl<CR, S, PEK, APC, DGS, PIC, MDS> Wallet<CR, S, PEK, APC, DGS, PIC, MDS> {
         self.lock.lock()
     }
 
+    /// Register user activity, which resets the inactivity timeout.
+    pub fn register_activity(&mut self) {
+        self.lock_scheduler.register_activity();
+    }
+
+    /// Notify the wallet of a change in the lifecycle state of the app. Moving to the background starts the
+    /// background timeout. When the app returns to the foreground after any of the lock timeouts expired,
+    /// the wallet is locked and any disclosure session in progress is cancelled.
+    pub async fn notify_app_state(&mut self, state: AppState)
+    where
+        CR: ConfigurationRepository,
+        S: Storage,
+        MDS: MdocDisclosureSession<Self>,
+    {
+        info!("App state changed to: {:?}", state);
+
+        match state {
+            AppState::Background => self.lock_scheduler.enter_background(),
+            AppState::Foreground => {
+                let config = self.config_repository.config();
+
+                if self.lock_scheduler.enter_foreground(&config.lock_timeouts) {
+                    info!("Lock timeout expired while in background, locking wallet");
+
+                    self.lock_on_timeout().await;
+                }
+            }
+        }
+    }
+
+    /// Lock the wallet if any of the lock timeouts have expired. This should be called periodically.
+    pub async fn check_lock_timeouts(&mut self)
+    where
+        CR: ConfigurationRepository,
+        S: Storage,
+        MDS: MdocDisclosureSession<Self>,
+    {
+        if self.lock.is_locked() {
+            return;
+        }
+
+        let config = self.config_repository.config();
+
+        if self.lock_scheduler.is_expired(&config.lock_timeouts) {
+            info!("Lock timeout expired, locking wallet");
+
+            self.lock_on_timeout().await;
+        }
+    }
+
+    /// Lock the wallet and cancel any disclosure session in progress, as the user has
+    /// apparently left the app, which means the proposal should not be left open.
+    async fn lock_on_timeout(&mut self)
+    where
+        CR: ConfigurationRepository,
+        S: Storage,
+        MDS: MdocDisclosureSession<Self>,
+    {
+        self.lock.lock();
+
+        if let Err(error) = self.terminate_disclosure_session().await {
+            warn!("Could not cancel disclosure session after locking wallet: {}", error);
+        }
+    }
+
+    /// Get the PIN lockout, as last reported by the Wallet Provider. When timed out, this can be used to show the user
+    /// how long it takes before the PIN can be entered again.
+    pub async fn pin_lockout(&self) -> Result<PinLockout, WalletUnlockError>
+    where
+        S: Storage,
+    {
+        info!("Checking if registered");
+        if self.registration.is_none() {
+            return Err(WalletUnlockError::NotRegistered);
+        }
+
+        let lockout = self
+            .storage
+            .read()
+            .await
+            .fetch_data::<PinLockout>()
+            .await?
+            .unwrap_or_default();
+
+        Ok(lockout)
+    }
+
     #[instrument(skip_all)]
     pub async fn unlock(&mut self, pin: String) -> Result<(), WalletUnlockError>
     where
@@ -52,6 +144,7 @@ impl<CR, S, PEK, APC, DGS, PIC, MDS> Wallet<CR, S, PEK, APC, DGS, PIC, MDS> {
         PEK: PlatformEcdsaKey,
         APC: AccountProviderClient,
     {
+        self.start_operation();
         info!("Validating pin");
 
         info!("Checking if registered");
@@ -67,7 +160,7 @@ impl<CR, S, PEK, APC, DGS, PIC, MDS> Wallet<CR, S, PEK, APC, DGS, PIC, MDS> {
 
         let config = self.config_repository.config();
 
-        let instruction_result_public_key = config.account_server.instruction_result_public_key.clone().into();
+        let instruction_result_public_keys = config.account_server.instruction_result_public_keys();
 
         let remote_instruction = InstructionClient::new(
             pin,
@@ -76,18 +169,21 @@ impl<CR, S, PEK, APC, DGS, PIC, MDS> Wallet<CR, S, PEK, APC, DGS, PIC, MDS> {
             &self.account_provider_client,
             registration_data,
             &config.account_server.base_url,
-            &instruction_result_public_key,
+            &instruction_result_public_keys,
         );
 
         info!("Sending unlock instruction to Wallet Provider");
-        remote_instruction
+        let result = remote_instruction
             .send(CheckPin)
             .inspect_ok(|_| {
                 info!("Unlock instruction successful, unlocking wallet");
 
                 self.lock.unlock();
+                self.lock_scheduler.register_activity();
             })
-            .await?;
+            .await;
+        self.connectivity.report(&result);
+        result?;
 
         Ok(())
     }
@@ -97,18 +193,19 @@ impl<CR, S, PEK, APC, DGS, PIC, MDS> Wallet<CR, S, PEK, APC, DGS, PIC, MDS> {
 mod tests {
     use std::{
         ops::Deref,
-        sync::{Arc, Mutex},
+        sync::{atomic::Ordering, Arc, Mutex},
+        time::Duration,
     };
 
     use assert_matches::assert_matches;
     use http::StatusCode;
-    use mockall::predicate::*;
+    use mockall::{predicate::*, Sequence};
 
     use p256::{ecdsa::SigningKey, elliptic_curve::rand_core::OsRng};
     use wallet_common::{
         account::{
             messages::{
-                errors::{ErrorData, ErrorType, IncorrectPinData, PinTimeoutData},
+                errors::{ErrorData, ErrorType, IncorrectPinData, InstructionSequenceNumberData, PinTimeoutData},
                 instructions::{CheckPin, Instruction, InstructionResultClaims},
             },
             signed::SequenceNumberComparison,
@@ -118,7 +215,13 @@ mod tests {
         utils,
     };
 
-    use crate::{account_provider::AccountProviderResponseError, pin::key::PinKey};
+    use crate::{
+        account_provider::AccountProviderResponseError,
+        disclosure::MockMdocDisclosureSession,
+        pin::key::PinKey,
+        storage::{InstructionData, WalletEvent},
+        EventStatus,
+    };
 
     use super::{
         super::tests::{WalletWithMocks, ACCOUNT_SERVER_KEYS},
@@ -240,6 +343,84 @@ mod tests {
         assert_eq!(is_locked_vec.lock().unwrap().len(), 3);
     }
 
+    #[tokio::test(start_paused = true)]
+    async fn test_wallet_lock_timeouts() {
+        let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
+
+        let is_locked_vec = Arc::new(Mutex::new(Vec::<bool>::new()));
+        let callback_is_locked_vec = Arc::clone(&is_locked_vec);
+        wallet.set_lock_callback(move |is_locked| callback_is_locked_vec.lock().unwrap().push(is_locked));
+
+        let lock_timeouts = wallet.config_repository.config().lock_timeouts.clone();
+        let inactive_timeout = Duration::from_secs(lock_timeouts.inactive_timeout.into());
+        let background_timeout = Duration::from_secs(lock_timeouts.background_timeout.into());
+
+        // Registering activity should postpone locking the wallet.
+        tokio::time::advance(inactive_timeout - Duration::from_secs(1)).await;
+        wallet.check_lock_timeouts().await;
+        assert!(!wallet.is_locked());
+
+        wallet.register_activity();
+        tokio::time::advance(inactive_timeout - Duration::from_secs(1)).await;
+        wallet.check_lock_timeouts().await;
+        assert!(!wallet.is_locked());
+
+        // The wallet should be locked once the inactivity timeout expires.
+        tokio::time::advance(Duration::from_secs(1)).await;
+        wallet.check_lock_timeouts().await;
+        assert!(wallet.is_locked());
+
+        // The wallet should be locked when returning from the background too late.
+        wallet.lock.unlock();
+        wallet.register_activity();
+        wallet.notify_app_state(AppState::Background).await;
+        tokio::time::advance(background_timeout).await;
+        wallet.notify_app_state(AppState::Foreground).await;
+        assert!(wallet.is_locked());
+
+        // Returning from the background in time should leave the wallet unlocked.
+        wallet.lock.unlock();
+        wallet.register_activity();
+        wallet.notify_app_state(AppState::Background).await;
+        tokio::time::advance(background_timeout - Duration::from_secs(1)).await;
+        wallet.notify_app_state(AppState::Foreground).await;
+        assert!(!wallet.is_locked());
+
+        assert_eq!(
+            is_locked_vec.lock().unwrap().deref(),
+            &vec![false, true, false, true, false]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_wallet_lock_timeout_cancels_disclosure() {
+        let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
+
+        wallet.disclosure_session = MockMdocDisclosureSession::default().into();
+        let was_terminated = Arc::clone(&wallet.disclosure_session.as_ref().unwrap().was_terminated);
+
+        let lock_timeouts = wallet.config_repository.config().lock_timeouts.clone();
+        let background_timeout = Duration::from_secs(lock_timeouts.background_timeout.into());
+
+        // Staying in the background for too long should lock the wallet and cancel the disclosure session.
+        wallet.notify_app_state(AppState::Background).await;
+        tokio::time::advance(background_timeout).await;
+        wallet.notify_app_state(AppState::Foreground).await;
+
+        assert!(wallet.is_locked());
+        assert!(wallet.disclosure_session.is_none());
+        assert!(was_terminated.load(Ordering::Relaxed));
+
+        let events = wallet.storage.read().await.fetch_wallet_events().await.unwrap();
+        assert_matches!(
+            &events[..],
+            [WalletEvent::Disclosure {
+                status: EventStatus::Cancelled,
+                ..
+            }]
+        );
+    }
+
     #[tokio::test]
     async fn test_wallet_unlock_error_not_registered() {
         // Prepare an unregistered wallet
@@ -466,7 +647,7 @@ mod tests {
         wallet.lock();
 
         // Have the database return an error when fetching the sequence number.
-        wallet.storage.get_mut().has_query_error = true;
+        wallet.storage.write().await.has_query_error = true;
 
         // Unlocking the wallet should now result in an
         // `InstructionError::StoreInstructionSequenceNumber` error.
@@ -480,4 +661,304 @@ mod tests {
             WalletUnlockError::Instruction(InstructionError::StoreInstructionSequenceNumber(_))
         );
     }
+
+    fn sequence_number_error(sequence_number: u64) -> AccountProviderResponseError {
+        AccountProviderResponseError::Data(
+            StatusCode::CONFLICT,
+            ErrorData {
+                typ: ErrorType::InstructionSequenceNumber(InstructionSequenceNumberData { sequence_number }),
+                title: "sequence number".to_string(),
+            },
+        )
+    }
+
+    async fn set_instruction_sequence_number(wallet: &mut WalletWithMocks, sequence_number: u64) {
+        wallet
+            .storage
+            .write()
+            .await
+            .insert_data(&InstructionData {
+                instruction_sequence_number: sequence_number,
+            })
+            .await
+            .unwrap();
+    }
+
+    async fn instruction_sequence_number(wallet: &WalletWithMocks) -> u64 {
+        wallet
+            .storage
+            .read()
+            .await
+            .fetch_data::<InstructionData>()
+            .await
+            .unwrap()
+            .expect("No instruction data in storage")
+            .instruction_sequence_number
+    }
+
+    async fn expect_check_pin_instruction(wallet: &mut WalletWithMocks) {
+        let result_claims = InstructionResultClaims {
+            result: (),
+            iss: "wallet_unit_test".to_string(),
+            iat: jsonwebtoken::get_current_timestamp(),
+        };
+        let result = Jwt::sign_with_sub(&result_claims, &ACCOUNT_SERVER_KEYS.instruction_result_signing_key)
+            .await
+            .unwrap();
+
+        wallet
+            .account_provider_client
+            .expect_instruction()
+            .times(1)
+            .return_once(move |_, _: Instruction<CheckPin>| Ok(result));
+    }
+
+    #[tokio::test]
+    async fn test_wallet_unlock_instruction_sequence_number_resync() {
+        let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
+
+        wallet.lock();
+
+        // Simulate the wallet having lost track of the sequence numbers it used, e.g. because it crashed
+        // after sending an instruction but before the incremented sequence number was stored.
+        set_instruction_sequence_number(&mut wallet, 3).await;
+
+        let hw_pubkey = wallet.hw_privkey.verifying_key().await.unwrap();
+        let mut sequence = Sequence::new();
+
+        // The Wallet Provider rejects the first challenge request and reports the last sequence number it knows.
+        wallet
+            .account_provider_client
+            .expect_instruction_challenge()
+            .times(1)
+            .in_sequence(&mut sequence)
+            .return_once(move |_, challenge_request| {
+                let claims = challenge_request
+                    .message
+                    .parse_and_verify_with_sub(&hw_pubkey.into())
+                    .unwrap();
+                assert_eq!(claims.sequence_number, 4);
+
+                Err(sequence_number_error(10).into())
+            });
+
+        // The second challenge request should continue from the sequence number known by the Wallet Provider.
+        wallet
+            .account_provider_client
+            .expect_instruction_challenge()
+            .times(1)
+            .in_sequence(&mut sequence)
+            .return_once(move |_, challenge_request| {
+                let claims = challenge_request
+                    .message
+                    .parse_and_verify_with_sub(&hw_pubkey.into())
+                    .unwrap();
+                assert_eq!(claims.sequence_number, 11);
+
+                Ok(utils::random_bytes(32))
+            });
+
+        expect_check_pin_instruction(&mut wallet).await;
+
+        wallet.unlock(PIN.to_string()).await.expect("Could not unlock wallet");
+
+        assert!(!wallet.is_locked());
+        assert_eq!(instruction_sequence_number(&wallet).await, 12);
+    }
+
+    #[tokio::test]
+    async fn test_wallet_unlock_instruction_sequence_number_ahead() {
+        let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
+
+        wallet.lock();
+
+        // Simulate the wallet having crashed after storing an incremented sequence number, but before sending
+        // the instruction. As the Wallet Provider only requires the sequence number to increase, no resync occurs.
+        set_instruction_sequence_number(&mut wallet, 7).await;
+
+        let hw_pubkey = wallet.hw_privkey.verifying_key().await.unwrap();
+
+        wallet
+            .account_provider_client
+            .expect_instruction_challenge()
+            .times(1)
+            .return_once(move |_, challenge_request| {
+                let claims = challenge_request
+                    .message
+                    .parse_and_verify_with_sub(&hw_pubkey.into())
+                    .unwrap();
+                assert_eq!(claims.sequence_number, 8);
+
+                Ok(utils::random_bytes(32))
+            });
+
+        expect_check_pin_instruction(&mut wallet).await;
+
+        wallet.unlock(PIN.to_string()).await.expect("Could not unlock wallet");
+
+        assert_eq!(instruction_sequence_number(&wallet).await, 9);
+    }
+
+    #[tokio::test]
+    async fn test_wallet_unlock_error_instruction_sequence_number_resync() {
+        let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
+
+        wallet.lock();
+
+        // If the Wallet Provider keeps rejecting the sequence number, the wallet should only retry once.
+        wallet
+            .account_provider_client
+            .expect_instruction_challenge()
+            .times(2)
+            .returning(|_, _| Err(sequence_number_error(10).into()));
+
+        let error = wallet
+            .unlock(PIN.to_string())
+            .await
+            .expect_err("Wallet unlocking should have resulted in error");
+
+        assert_matches!(
+            error,
+            WalletUnlockError::Instruction(InstructionError::SequenceNumberMismatch { sequence_number: 10 })
+        );
+
+        // The wallet should have fast-forwarded to the reported sequence number before retrying.
+        assert_eq!(instruction_sequence_number(&wallet).await, 11);
+    }
+
+    #[tokio::test]
+    async fn test_wallet_unlock_pin_lockout_timeout() {
+        let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
+
+        wallet.lock();
+
+        // The account server should only be contacted once, as the
+        // timeout it reports should be enforced by the wallet afterwards.
+        wallet
+            .account_provider_client
+            .expect_instruction_challenge()
+            .times(1)
+            .return_once(|_, _| Ok(utils::random_bytes(32)));
+
+        wallet
+            .account_provider_client
+            .expect_instruction()
+            .times(1)
+            .return_once(move |_, _: Instruction<CheckPin>| {
+                Err(AccountProviderResponseError::Data(
+                    StatusCode::FORBIDDEN,
+                    ErrorData {
+                        typ: ErrorType::PinTimeout(PinTimeoutData {
+                            time_left_in_ms: 60_000,
+                        }),
+                        title: "pin timeout".to_string(),
+                    },
+                )
+                .into())
+            });
+
+        let error = wallet
+            .unlock(PIN.to_string())
+            .await
+            .expect_err("Wallet unlocking should have resulted in error");
+
+        assert_matches!(
+            error,
+            WalletUnlockError::Instruction(InstructionError::Timeout { timeout_millis: 60_000 })
+        );
+        assert_matches!(
+            wallet.pin_lockout().await.unwrap(),
+            PinLockout::TimedOut { until } if until > chrono::Utc::now()
+        );
+
+        // Unlocking again should fail without contacting the account server, reporting the time left.
+        let error = wallet
+            .unlock(PIN.to_string())
+            .await
+            .expect_err("Wallet unlocking should have resulted in error");
+
+        assert_matches!(
+            error,
+            WalletUnlockError::Instruction(InstructionError::Timeout { timeout_millis }) if timeout_millis <= 60_000
+        );
+        assert!(wallet.is_locked());
+    }
+
+    #[tokio::test]
+    async fn test_wallet_unlock_pin_lockout_expired() {
+        let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
+
+        wallet.lock();
+
+        // Store a timeout that has already expired.
+        wallet
+            .storage
+            .write()
+            .await
+            .insert_data(&PinLockout::TimedOut {
+                until: chrono::Utc::now() - chrono::Duration::seconds(1),
+            })
+            .await
+            .unwrap();
+
+        wallet
+            .account_provider_client
+            .expect_instruction_challenge()
+            .times(1)
+            .return_once(|_, _| Ok(utils::random_bytes(32)));
+        expect_check_pin_instruction(&mut wallet).await;
+
+        wallet
+            .unlock(PIN.to_string())
+            .await
+            .expect("Wallet unlocking should have succeeded");
+
+        // After a successful instruction, the PIN lockout should be cleared.
+        assert!(!wallet.is_locked());
+        assert_eq!(wallet.pin_lockout().await.unwrap(), PinLockout::Available);
+    }
+
+    #[tokio::test]
+    async fn test_wallet_unlock_pin_lockout_blocked() {
+        let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
+
+        wallet.lock();
+
+        // Store that the account was blocked, after which the account was unblocked by the account server.
+        wallet
+            .storage
+            .write()
+            .await
+            .insert_data(&PinLockout::Blocked)
+            .await
+            .unwrap();
+
+        wallet
+            .account_provider_client
+            .expect_instruction_challenge()
+            .times(1)
+            .return_once(|_, _| Ok(utils::random_bytes(32)));
+        expect_check_pin_instruction(&mut wallet).await;
+
+        wallet
+            .unlock(PIN.to_string())
+            .await
+            .expect("Wallet unlocking should have succeeded");
+
+        // The answer of the account server should override the stored PIN lockout.
+        assert!(!wallet.is_locked());
+        assert_eq!(wallet.pin_lockout().await.unwrap(), PinLockout::Available);
+    }
+
+    #[tokio::test]
+    async fn test_wallet_pin_lockout_error_not_registered() {
+        let wallet = WalletWithMocks::new_unregistered().await;
+
+        let error = wallet
+            .pin_lockout()
+            .await
+            .expect_err("Getting PIN lockout should have resulted in error");
+
+        assert_matches!(error, WalletUnlockError::NotRegistered);
+    }
 }
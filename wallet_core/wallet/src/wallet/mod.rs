@@ -1,39 +1,62 @@
+mod clock_skew;
 mod config;
+mod connectivity;
+mod correlation;
+mod data_export;
+mod deregistration;
 mod disclosure;
 mod documents;
 mod history;
 mod init;
 mod issuance;
+mod key_pool;
 mod lock;
 mod registration;
+mod reset;
+mod self_check;
 mod uri;
 
 #[cfg(test)]
 mod tests;
 
+use std::sync::Arc;
+
 use tokio::sync::RwLock;
+use url::Url;
 use uuid::Uuid;
 
-use nl_wallet_mdoc::holder::{CborHttpClient, DisclosureSession};
+use nl_wallet_mdoc::{
+    holder::{CborHttpClient, DisclosureSession},
+    status_list::StatusListCache,
+};
 use platform_support::hw_keystore::hardware::{HardwareEcdsaKey, HardwareEncryptionKey};
+use wallet_common::correlation::CorrelationTracker;
 
 use crate::{
     account_provider::HttpAccountProviderClient,
+    clock_skew::ClockSkew,
     config::UpdatingFileHttpConfigurationRepository,
+    connectivity::Connectivity,
+    credential_issuer::HttpCredentialIssuerClient,
     digid::HttpDigidSession,
-    lock::WalletLock,
-    pid_issuer::HttpPidIssuerClient,
+    lock::{LockScheduler, WalletLock},
     storage::{DatabaseStorage, RegistrationData},
 };
 
 pub use self::{
-    disclosure::{DisclosureError, DisclosureProposal},
+    data_export::WalletDataExportError,
+    deregistration::WalletDeregistrationError,
+    disclosure::{DisclosureError, DisclosureProposal, DisclosureRequestPreview, MissingAttributesReport},
+    documents::DocumentsError,
     history::{EventStatus, HistoryError, HistoryEvent},
     init::WalletInitError,
     issuance::PidIssuanceError,
+    key_pool::KeyPoolError,
     lock::WalletUnlockError,
     registration::WalletRegistrationError,
-    uri::{UriIdentificationError, UriType},
+    reset::WalletResetError,
+    self_check::WalletSelfCheckError,
+    uri::{UriIdentificationError, UriProcessingError, UriProcessingResult, UriType},
 };
 
 use self::documents::DocumentsCallback;
@@ -44,17 +67,24 @@ pub struct Wallet<
     PEK = HardwareEcdsaKey,                        // PlatformEcdsaKey
     APC = HttpAccountProviderClient,               // AccountProviderClient
     DGS = HttpDigidSession,                        // DigidSession
-    PIC = HttpPidIssuerClient,                     // PidIssuerClient
+    CIC = HttpCredentialIssuerClient,              // CredentialIssuerClient
     MDS = DisclosureSession<CborHttpClient, Uuid>, // MdocDisclosureSession
 > {
     config_repository: CR,
-    storage: RwLock<S>,
+    storage: Arc<RwLock<S>>,
     hw_privkey: PEK,
     account_provider_client: APC,
     digid_session: Option<DGS>,
-    pid_issuer: PIC,
+    selected_issuer_url: Option<Url>,
+    is_pid_renewal: bool,
+    issuer_client: CIC,
     disclosure_session: Option<MDS>,
     lock: WalletLock,
+    lock_scheduler: LockScheduler,
     registration: Option<RegistrationData>,
     documents_callback: Option<DocumentsCallback>,
+    status_lists: StatusListCache,
+    connectivity: Connectivity,
+    clock_skew: ClockSkew,
+    correlation: CorrelationTracker,
 }
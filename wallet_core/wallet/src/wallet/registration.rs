@@ -39,11 +39,19 @@ pub enum WalletRegistrationError {
     StoreCertificate(#[from] StorageError),
 }
 
-impl<CR, S, PEK, APC, DGS, PIC, MDS> Wallet<CR, S, PEK, APC, DGS, PIC, MDS> {
+impl<CR, S, PEK, APC, DGS, CIC, MDS> Wallet<CR, S, PEK, APC, DGS, CIC, MDS> {
     pub fn has_registration(&self) -> bool {
         self.registration.is_some()
     }
 
+    /// Check whether the PIN meets the requirements of the PIN policy in the current configuration.
+    pub fn validate_pin(&self, pin: &str) -> Result<(), PinValidationError>
+    where
+        CR: ConfigurationRepository,
+    {
+        validate_pin(pin, &self.config_repository.config().pin_policy)
+    }
+
     #[instrument(skip_all)]
     pub async fn register(&mut self, pin: String) -> Result<(), WalletRegistrationError>
     where
@@ -52,6 +60,7 @@ impl<CR, S, PEK, APC, DGS, PIC, MDS> Wallet<CR, S, PEK, APC, DGS, PIC, MDS> {
         APC: AccountProviderClient,
         PEK: PlatformEcdsaKey,
     {
+        self.start_operation();
         info!("Checking if already registered");
 
         // Registration is only allowed if we do not currently have a registration on record.
@@ -62,19 +71,18 @@ impl<CR, S, PEK, APC, DGS, PIC, MDS> Wallet<CR, S, PEK, APC, DGS, PIC, MDS> {
         info!("Validating PIN");
 
         // Make sure the PIN adheres to the requirements.
-        validate_pin(&pin)?; // TODO: do not keep PIN in memory while request is in flight
+        self.validate_pin(&pin)?; // TODO: do not keep PIN in memory while request is in flight
 
         info!("Requesting challenge from account server");
 
         let config = &self.config_repository.config().account_server;
         let base_url = config.base_url.clone();
-        let certificate_public_key = config.certificate_public_key.clone();
+        let certificate_public_keys = config.certificate_public_keys();
 
         // Retrieve a challenge from the account server
         let challenge = self
-            .account_provider_client
-            .registration_challenge(&base_url)
-            .await
+            .connectivity
+            .observe(self.account_provider_client.registration_challenge(&base_url).await)
             .map_err(WalletRegistrationError::ChallengeRequest)?;
 
         info!("Challenge received from account server, signing and sending registration to account server");
@@ -96,9 +104,12 @@ impl<CR, S, PEK, APC, DGS, PIC, MDS> Wallet<CR, S, PEK, APC, DGS, PIC, MDS> {
 
         // Send the registration message to the account server and receive the wallet certificate in response.
         let cert = self
-            .account_provider_client
-            .register(&base_url, registration_message)
-            .await
+            .connectivity
+            .observe(
+                self.account_provider_client
+                    .register(&base_url, registration_message)
+                    .await,
+            )
             .map_err(WalletRegistrationError::RegistrationRequest)?;
 
         info!("Certificate received from account server, verifying contents");
@@ -106,7 +117,7 @@ impl<CR, S, PEK, APC, DGS, PIC, MDS> Wallet<CR, S, PEK, APC, DGS, PIC, MDS> {
         // Double check that the public key returned in the wallet certificate
         // matches that of our hardware key.
         let cert_claims = cert
-            .parse_and_verify_with_sub(&certificate_public_key.into())
+            .parse_and_verify_with_sub_from_keys(&certificate_public_keys)
             .map_err(WalletRegistrationError::CertificateValidation)?;
         if cert_claims.hw_pubkey.0 != hw_pubkey {
             return Err(WalletRegistrationError::PublicKeyMismatch);
@@ -115,7 +126,7 @@ impl<CR, S, PEK, APC, DGS, PIC, MDS> Wallet<CR, S, PEK, APC, DGS, PIC, MDS> {
         info!("Storing received registration");
 
         // If the storage database does not exist, create it now.
-        let storage = self.storage.get_mut();
+        let mut storage = self.storage.write().await;
         let storage_state = storage.state().await?;
         if !matches!(storage_state, StorageState::Opened) {
             storage.open().await?;
@@ -145,7 +156,10 @@ mod tests {
     use p256::{ecdsa::SigningKey, elliptic_curve::rand_core::OsRng};
     use wallet_common::{account::signed::SequenceNumberComparison, jwt::Jwt, utils};
 
-    use crate::{account_provider::AccountProviderResponseError, wallet::tests::ACCOUNT_SERVER_KEYS};
+    use crate::{
+        account_provider::AccountProviderResponseError, config::default_configuration,
+        wallet::tests::ACCOUNT_SERVER_KEYS,
+    };
 
     use super::{super::tests::WalletWithMocks, *};
 
@@ -158,7 +172,7 @@ mod tests {
 
         // The wallet should report that it is currently unregistered and locked.
         assert!(!wallet.has_registration());
-        assert!(wallet.storage.get_mut().data.is_empty());
+        assert!(wallet.storage.read().await.data.is_empty());
         assert!(wallet.is_locked());
 
         // Have the account server respond with a random
@@ -212,7 +226,8 @@ mod tests {
         // The registration should be stored in the database.
         let stored_registration: RegistrationData = wallet
             .storage
-            .get_mut()
+            .read()
+            .await
             .fetch_data()
             .await
             .unwrap()
@@ -245,7 +260,22 @@ mod tests {
 
         assert_matches!(error, WalletRegistrationError::InvalidPin(_));
         assert!(!wallet.has_registration());
-        assert!(wallet.storage.get_mut().data.is_empty());
+        assert!(wallet.storage.read().await.data.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_wallet_validate_pin_configured_policy() {
+        let mut config = default_configuration();
+        config.pin_policy.allow_sequential = true;
+
+        let wallet = WalletWithMocks::new_unregistered_with_config(config).await;
+
+        // A sequential PIN is accepted when the configured policy allows it, but the other rules still apply.
+        wallet.validate_pin("123456").expect("PIN should be valid");
+        assert_matches!(
+            wallet.validate_pin("111111"),
+            Err(PinValidationError::TooFewUniqueDigits)
+        );
     }
 
     #[tokio::test]
@@ -265,7 +295,7 @@ mod tests {
 
         assert_matches!(error, WalletRegistrationError::ChallengeRequest(_));
         assert!(!wallet.has_registration());
-        assert!(wallet.storage.get_mut().data.is_empty());
+        assert!(wallet.storage.read().await.data.is_empty());
     }
 
     #[tokio::test]
@@ -292,7 +322,7 @@ mod tests {
 
         assert_matches!(error, WalletRegistrationError::HardwarePublicKey(_));
         assert!(!wallet.has_registration());
-        assert!(wallet.storage.get_mut().data.is_empty());
+        assert!(wallet.storage.read().await.data.is_empty());
     }
 
     #[tokio::test]
@@ -319,7 +349,7 @@ mod tests {
 
         assert_matches!(error, WalletRegistrationError::Signing(_));
         assert!(!wallet.has_registration());
-        assert!(wallet.storage.get_mut().data.is_empty());
+        assert!(wallet.storage.read().await.data.is_empty());
     }
 
     #[tokio::test]
@@ -344,7 +374,7 @@ mod tests {
 
         assert_matches!(error, WalletRegistrationError::RegistrationRequest(_));
         assert!(!wallet.has_registration());
-        assert!(wallet.storage.get_mut().data.is_empty());
+        assert!(wallet.storage.read().await.data.is_empty());
     }
 
     #[tokio::test]
@@ -375,7 +405,39 @@ mod tests {
 
         assert_matches!(error, WalletRegistrationError::CertificateValidation(_));
         assert!(!wallet.has_registration());
-        assert!(wallet.storage.get_mut().data.is_empty());
+        assert!(wallet.storage.read().await.data.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_wallet_register_previous_certificate_public_key() {
+        // Configure the wallet to also accept a key that the account server used before its current key.
+        let previous_key = SigningKey::random(&mut OsRng);
+        let mut config = default_configuration();
+        config.account_server.previous_certificate_public_keys = vec![(*previous_key.verifying_key()).into()];
+
+        let mut wallet = WalletWithMocks::new_unregistered_with_config(config).await;
+
+        wallet
+            .account_provider_client
+            .expect_registration_challenge()
+            .return_once(|_| Ok(utils::random_bytes(32)));
+
+        // Have the account server sign the wallet certificate with the previous key.
+        let cert = Jwt::sign_with_sub(&wallet.valid_certificate_claims().await, &previous_key)
+            .await
+            .unwrap();
+
+        wallet
+            .account_provider_client
+            .expect_register()
+            .return_once(|_, _| Ok(cert));
+
+        wallet
+            .register(PIN.to_string())
+            .await
+            .expect("Could not register wallet");
+
+        assert!(wallet.has_registration());
     }
 
     #[tokio::test]
@@ -408,7 +470,7 @@ mod tests {
 
         assert_matches!(error, WalletRegistrationError::PublicKeyMismatch);
         assert!(!wallet.has_registration());
-        assert!(wallet.storage.get_mut().data.is_empty());
+        assert!(wallet.storage.read().await.data.is_empty());
     }
 
     #[tokio::test]
@@ -429,7 +491,7 @@ mod tests {
 
         // Have the database return an error
         // when inserting the wallet certificate.
-        wallet.storage.get_mut().has_query_error = true;
+        wallet.storage.write().await.has_query_error = true;
 
         let error = wallet
             .register(PIN.to_string())
@@ -438,6 +500,6 @@ mod tests {
 
         assert_matches!(error, WalletRegistrationError::StoreCertificate(_));
         assert!(!wallet.has_registration());
-        assert!(wallet.storage.get_mut().data.is_empty());
+        assert!(wallet.storage.read().await.data.is_empty());
     }
 }
@@ -0,0 +1,201 @@
+use std::error::Error;
+
+use tracing::{info, instrument};
+
+use platform_support::hw_keystore::PlatformEcdsaKey;
+use wallet_common::account::messages::auth::{RevocationRequest, RevocationRequestMessage};
+
+use crate::{
+    account_provider::{AccountProviderClient, AccountProviderError},
+    config::ConfigurationRepository,
+    lock::LockScheduler,
+    storage::{Storage, StorageError, StorageState},
+};
+
+use super::Wallet;
+
+#[derive(Debug, thiserror::Error)]
+pub enum WalletResetError {
+    #[error("could not sign revocation request: {0}")]
+    Signing(#[source] wallet_common::errors::Error),
+    #[error("could not revoke wallet certificate at Wallet Provider: {0}")]
+    Revocation(#[source] AccountProviderError),
+    #[error("could not clear database: {0}")]
+    Storage(#[from] StorageError),
+    #[error("could not delete hardware key: {0}")]
+    HardwareKey(#[source] Box<dyn Error + Send + Sync>),
+}
+
+impl<CR, S, PEK, APC, DGS, CIC, MDS> Wallet<CR, S, PEK, APC, DGS, CIC, MDS> {
+    /// Remove all data from the wallet and return it to the unregistered state. If the wallet is registered,
+    /// its certificate is revoked at the Wallet Provider first. As this step is performed before anything
+    /// is deleted locally, a failed revocation leaves the wallet untouched, so that the reset can be retried.
+    #[instrument(skip_all)]
+    pub async fn reset(&mut self) -> Result<(), WalletResetError>
+    where
+        CR: ConfigurationRepository,
+        S: Storage,
+        PEK: PlatformEcdsaKey,
+        APC: AccountProviderClient,
+    {
+        if let Some(registration) = &self.registration {
+            info!("Revoking wallet certificate at account server");
+
+            let revocation_request = RevocationRequestMessage {
+                message: RevocationRequest::new_signed("wallet", &self.hw_privkey)
+                    .await
+                    .map_err(WalletResetError::Signing)?,
+                certificate: registration.wallet_certificate.clone(),
+            };
+
+            let base_url = self.config_repository.config().account_server.base_url.clone();
+
+            self.account_provider_client
+                .revoke(&base_url, revocation_request)
+                .await
+                .map_err(WalletResetError::Revocation)?;
+        }
+
+        self.clear_local_state().await
+    }
+
+    /// Remove all local data, i.e. the database, hardware key and any in-memory state. This
+    /// is shared with [`Wallet::deregister()`], after the Wallet Provider has been informed.
+    pub(super) async fn clear_local_state(&mut self) -> Result<(), WalletResetError>
+    where
+        S: Storage,
+        PEK: PlatformEcdsaKey,
+    {
+        info!("Clearing database");
+
+        // The database can only be cleared when it is open, so make sure that is the case.
+        {
+            let mut storage = self.storage.write().await;
+            match storage.state().await? {
+                StorageState::Uninitialized => (),
+                StorageState::Unopened => {
+                    storage.open().await?;
+                    storage.clear().await?;
+                }
+                StorageState::Opened => storage.clear().await?,
+            }
+        }
+
+        info!("Deleting hardware key");
+
+        self.hw_privkey
+            .delete()
+            .await
+            .map_err(|e| WalletResetError::HardwareKey(e.into()))?;
+
+        info!("Resetting wallet state");
+
+        self.registration.take();
+        self.digid_session.take();
+        self.selected_issuer_url.take();
+        self.is_pid_renewal = false;
+        self.disclosure_session.take();
+        self.lock_scheduler = LockScheduler::new();
+        self.lock.lock();
+
+        // Let the UI know that there are no longer any documents present.
+        if let Some(ref mut callback) = self.documents_callback {
+            callback(Default::default());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{atomic::Ordering, Arc, Mutex};
+
+    use assert_matches::assert_matches;
+    use http::StatusCode;
+
+    use wallet_common::keys::EcdsaKey;
+
+    use crate::{account_provider::AccountProviderResponseError, document::Document};
+
+    use super::{super::tests::WalletWithMocks, *};
+
+    #[tokio::test]
+    async fn test_wallet_reset() {
+        let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
+
+        let hw_pubkey = wallet.hw_privkey.verifying_key().await.unwrap();
+        let certificate = wallet.registration.as_ref().unwrap().wallet_certificate.clone();
+
+        // The revocation request should contain the wallet certificate and be signed with the hardware key.
+        wallet
+            .account_provider_client
+            .expect_revoke()
+            .return_once(move |_, revocation_request| {
+                assert_eq!(revocation_request.certificate.0, certificate.0);
+
+                let claims = revocation_request
+                    .message
+                    .parse_and_verify_with_sub(&hw_pubkey.into())
+                    .expect("Could not verify revocation request");
+
+                assert_eq!(claims.iss, "wallet");
+
+                Ok(())
+            });
+
+        // Register a documents callback, which should receive an empty list after the reset.
+        let documents = Arc::new(Mutex::new(None));
+        let callback_documents = Arc::clone(&documents);
+        wallet.documents_callback = Some(Box::new(move |documents: Vec<Document>| {
+            callback_documents.lock().unwrap().replace(documents);
+        }));
+
+        wallet.reset().await.expect("Could not reset wallet");
+
+        // The wallet should now be unregistered, locked and its storage should be empty.
+        assert!(!wallet.has_registration());
+        assert!(wallet.is_locked());
+        assert!(wallet.storage.read().await.data.is_empty());
+        assert_matches!(wallet.storage.read().await.state, StorageState::Uninitialized);
+        assert_matches!(documents.lock().unwrap().as_deref(), Some([]));
+        assert!(wallet.hw_privkey.is_deleted.load(Ordering::Relaxed));
+    }
+
+    #[tokio::test]
+    async fn test_wallet_reset_unregistered() {
+        let mut wallet = WalletWithMocks::new_unregistered().await;
+
+        // No revocation request should be sent when the wallet is not registered.
+        wallet.account_provider_client.expect_revoke().never();
+
+        wallet.reset().await.expect("Could not reset wallet");
+
+        assert!(!wallet.has_registration());
+        assert!(wallet.is_locked());
+        assert!(wallet.hw_privkey.is_deleted.load(Ordering::Relaxed));
+    }
+
+    #[tokio::test]
+    async fn test_wallet_reset_error_revocation() {
+        let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
+
+        wallet
+            .account_provider_client
+            .expect_revoke()
+            .return_once(|_, _| Err(AccountProviderResponseError::Status(StatusCode::INTERNAL_SERVER_ERROR).into()));
+
+        let error = wallet
+            .reset()
+            .await
+            .expect_err("Wallet reset should have resulted in error");
+
+        assert_matches!(error, WalletResetError::Revocation(_));
+
+        // Nothing should have been removed from the wallet.
+        assert!(wallet.has_registration());
+        assert!(!wallet.is_locked());
+        assert!(!wallet.storage.read().await.data.is_empty());
+        assert!(!wallet.hw_privkey.is_deleted.load(Ordering::Relaxed));
+    }
+}
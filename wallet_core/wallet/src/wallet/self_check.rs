@@ -0,0 +1,179 @@
+use std::error::Error;
+
+use tracing::{info, instrument};
+
+use platform_support::hw_keystore::PlatformEcdsaKey;
+use wallet_common::jwt::JwtError;
+
+use crate::{
+    config::ConfigurationRepository,
+    storage::{mdoc_attributes_match, RegistrationData, Storage, StorageError},
+};
+
+use super::Wallet;
+
+#[derive(Debug, thiserror::Error)]
+pub enum WalletSelfCheckError {
+    #[error("could not read from database: {0}")]
+    Storage(#[from] StorageError),
+    #[error("registration in database does not match the registration in memory")]
+    RegistrationMismatch,
+    #[error("could not validate registration certificate: {0}")]
+    CertificateValidation(#[source] JwtError),
+    #[error("could not get hardware public key: {0}")]
+    HardwarePublicKey(#[source] Box<dyn Error + Send + Sync>),
+    #[error("public key in registration certificate does not match hardware public key")]
+    PublicKeyMismatch,
+    #[error("cached mdoc attributes do not match the stored mdocs")]
+    AttributeMismatch,
+}
+
+impl<CR, S, PEK, APC, DGS, CIC, MDS> Wallet<CR, S, PEK, APC, DGS, CIC, MDS> {
+    /// Check the consistency of the wallet state, which is useful after an unexpected crash. This checks that the
+    /// registration in memory matches that in the database, that the registration certificate belongs to the
+    /// hardware key and that the cached mdoc attributes match the stored mdocs. Note that this does not contact
+    /// the Wallet Provider.
+    #[instrument(skip_all)]
+    pub async fn self_check(&self) -> Result<(), WalletSelfCheckError>
+    where
+        CR: ConfigurationRepository,
+        S: Storage,
+        PEK: PlatformEcdsaKey,
+    {
+        info!("Performing self-check");
+
+        // If the wallet is not registered, there is nothing to check.
+        let Some(registration) = &self.registration else {
+            info!("Wallet is not registered, skipping self-check");
+
+            return Ok(());
+        };
+
+        info!("Checking registration in database");
+
+        let stored_registration = self.storage.read().await.fetch_data::<RegistrationData>().await?;
+        if stored_registration
+            .map(|stored_registration| stored_registration.wallet_certificate.0 != registration.wallet_certificate.0)
+            .unwrap_or(true)
+        {
+            return Err(WalletSelfCheckError::RegistrationMismatch);
+        }
+
+        info!("Checking registration certificate against hardware public key");
+
+        let certificate_public_keys = self.config_repository.config().account_server.certificate_public_keys();
+        let cert_claims = registration
+            .wallet_certificate
+            .parse_and_verify_with_sub_from_keys(&certificate_public_keys)
+            .map_err(WalletSelfCheckError::CertificateValidation)?;
+        let hw_pubkey = self
+            .hw_privkey
+            .verifying_key()
+            .await
+            .map_err(|e| WalletSelfCheckError::HardwarePublicKey(e.into()))?;
+
+        if cert_claims.hw_pubkey.0 != hw_pubkey {
+            return Err(WalletSelfCheckError::PublicKeyMismatch);
+        }
+
+        info!("Checking cached mdoc attributes against stored mdocs");
+
+        let storage = self.storage.read().await;
+        let mdocs = storage.fetch_unique_mdocs().await?;
+        let attributes = storage.fetch_mdoc_attributes().await?;
+
+        if !mdoc_attributes_match(&mdocs, attributes) {
+            return Err(WalletSelfCheckError::AttributeMismatch);
+        }
+
+        info!("Self-check successful");
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use p256::{ecdsa::SigningKey, elliptic_curve::rand_core::OsRng};
+
+    use wallet_common::jwt::Jwt;
+
+    use super::{
+        super::tests::{create_full_pid_mdoc, WalletWithMocks, ACCOUNT_SERVER_KEYS},
+        *,
+    };
+
+    #[tokio::test]
+    async fn test_wallet_self_check() {
+        // An unregistered wallet should pass the self-check.
+        let wallet = WalletWithMocks::new_unregistered().await;
+
+        wallet.self_check().await.expect("Self-check should succeed");
+
+        // A registered wallet should pass the self-check.
+        let wallet = WalletWithMocks::new_registered_and_unlocked().await;
+
+        wallet.self_check().await.expect("Self-check should succeed");
+
+        // A registered wallet containing an mdoc should pass the self-check.
+        let mdoc = create_full_pid_mdoc().await;
+        wallet.storage.write().await.mdocs.add([mdoc].into_iter()).unwrap();
+
+        wallet.self_check().await.expect("Self-check should succeed");
+    }
+
+    #[tokio::test]
+    async fn test_wallet_self_check_error_registration_mismatch() {
+        let wallet = WalletWithMocks::new_registered_and_unlocked().await;
+
+        // Remove the registration from the database.
+        wallet.storage.write().await.data.clear();
+
+        let error = wallet
+            .self_check()
+            .await
+            .expect_err("Self-check should have resulted in an error");
+
+        assert_matches!(error, WalletSelfCheckError::RegistrationMismatch);
+    }
+
+    #[tokio::test]
+    async fn test_wallet_self_check_error_storage() {
+        let wallet = WalletWithMocks::new_registered_and_unlocked().await;
+
+        // Have the database return an error on query.
+        wallet.storage.write().await.has_query_error = true;
+
+        let error = wallet
+            .self_check()
+            .await
+            .expect_err("Self-check should have resulted in an error");
+
+        assert_matches!(error, WalletSelfCheckError::Storage(_));
+    }
+
+    #[tokio::test]
+    async fn test_wallet_self_check_error_public_key_mismatch() {
+        let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
+
+        // Replace the registration certificate with one that contains a different public key.
+        let mut cert_claims = wallet.valid_certificate_claims().await;
+        cert_claims.hw_pubkey = (*SigningKey::random(&mut OsRng).verifying_key()).into();
+        let certificate = Jwt::sign_with_sub(&cert_claims, &ACCOUNT_SERVER_KEYS.certificate_signing_key)
+            .await
+            .unwrap();
+
+        let mut registration = wallet.registration.take().unwrap();
+        registration.wallet_certificate = certificate;
+        wallet.storage.write().await.update_data(&registration).await.unwrap();
+        wallet.registration = Some(registration);
+
+        let error = wallet
+            .self_check()
+            .await
+            .expect_err("Self-check should have resulted in an error");
+
+        assert_matches!(error, WalletSelfCheckError::PublicKeyMismatch);
+    }
+}
@@ -1,10 +1,17 @@
-use std::{sync::Mutex, time::Duration};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
 
 use once_cell::sync::Lazy;
 use p256::{
     ecdsa::{Signature, SigningKey, VerifyingKey},
     elliptic_curve::rand_core::OsRng,
 };
+use tokio::sync::RwLock;
 
 use nl_wallet_mdoc::{
     basic_sa_ext::UnsignedMdoc, holder::Mdoc, mock as mdoc_mock, server_keys::PrivateKey, IssuerSigned,
@@ -12,6 +19,7 @@ use nl_wallet_mdoc::{
 use platform_support::hw_keystore::PlatformEcdsaKey;
 use wallet_common::{
     account::messages::auth::{WalletCertificate, WalletCertificateClaims},
+    config::wallet_config::WalletConfiguration,
     generator::TimeGenerator,
     jwt::Jwt,
     keys::{software::SoftwareEcdsaKey, ConstructibleWithIdentifier, EcdsaKey, SecureEcdsaKey, WithIdentifier},
@@ -22,10 +30,11 @@ use wallet_common::{
 use crate::{
     account_provider::MockAccountProviderClient,
     config::{default_configuration, LocalConfigurationRepository, UpdatingConfigurationRepository},
+    connectivity::Connectivity,
+    credential_issuer::MockCredentialIssuerClient,
     digid::MockDigidSession,
     disclosure::MockMdocDisclosureSession,
     document,
-    pid_issuer::MockPidIssuerClient,
     pin::key as pin_key,
     storage::{KeyedData, MockStorage, RegistrationData, StorageState},
 };
@@ -51,6 +60,7 @@ pub struct FallibleSoftwareEcdsaKey {
     key: SoftwareEcdsaKey,
     pub next_public_key_error: Mutex<Option<<SoftwareEcdsaKey as EcdsaKey>::Error>>,
     pub next_private_key_error: Mutex<Option<<SoftwareEcdsaKey as EcdsaKey>::Error>>,
+    pub is_deleted: AtomicBool,
 }
 
 /// An alias for the `Wallet<>` with all mock dependencies.
@@ -60,7 +70,7 @@ pub type WalletWithMocks = Wallet<
     FallibleSoftwareEcdsaKey,
     MockAccountProviderClient,
     MockDigidSession,
-    MockPidIssuerClient,
+    MockCredentialIssuerClient,
     MockMdocDisclosureSession,
 >;
 
@@ -111,7 +121,7 @@ pub async fn mdoc_from_unsigned(unsigned_mdoc: UnsignedMdoc, private_key_id: Str
     let mdoc_public_key = (&SoftwareEcdsaKey::new(&private_key_id).verifying_key().await.unwrap())
         .try_into()
         .unwrap();
-    let (issuer_signed, _) = IssuerSigned::sign(unsigned_mdoc, mdoc_public_key, &issuer_key.issuance_key)
+    let (issuer_signed, _) = IssuerSigned::sign(unsigned_mdoc, mdoc_public_key, &issuer_key.issuance_key, None)
         .await
         .unwrap();
 
@@ -131,11 +141,19 @@ impl From<SoftwareEcdsaKey> for FallibleSoftwareEcdsaKey {
             key: value,
             next_public_key_error: Mutex::new(None),
             next_private_key_error: Mutex::new(None),
+            is_deleted: AtomicBool::new(false),
         }
     }
 }
 
-impl PlatformEcdsaKey for FallibleSoftwareEcdsaKey {}
+impl PlatformEcdsaKey for FallibleSoftwareEcdsaKey {
+    // Deletion is not forwarded, as the underlying `SoftwareEcdsaKey` is shared between tests that run concurrently.
+    async fn delete(&self) -> Result<(), Self::Error> {
+        self.is_deleted.store(true, Ordering::Relaxed);
+
+        Ok(())
+    }
+}
 
 impl ConstructibleWithIdentifier for FallibleSoftwareEcdsaKey {
     fn new(identifier: &str) -> Self {
@@ -177,11 +195,16 @@ impl EcdsaKey for FallibleSoftwareEcdsaKey {
 impl WalletWithMocks {
     /// Creates an unregistered `Wallet` with mock dependencies.
     pub async fn new_unregistered() -> Self {
+        Self::new_unregistered_with_config(default_configuration()).await
+    }
+
+    /// Creates an unregistered `Wallet` with mock dependencies, based on the provided `Configuration`.
+    pub async fn new_unregistered_with_config(config: WalletConfiguration) -> Self {
         let keys = Lazy::force(&ACCOUNT_SERVER_KEYS);
 
         // Override public key material in the `Configuration`.
         let config = {
-            let mut config = default_configuration();
+            let mut config = config;
 
             config.account_server.certificate_public_key = (*keys.certificate_signing_key.verifying_key()).into();
             config.account_server.instruction_result_public_key =
@@ -192,15 +215,18 @@ impl WalletWithMocks {
             config
         };
 
-        let config_repository =
-            UpdatingConfigurationRepository::new(LocalConfigurationRepository::new(config), Duration::from_secs(300))
-                .await;
+        let config_repository = UpdatingConfigurationRepository::new(
+            LocalConfigurationRepository::new(config),
+            Duration::from_secs(300),
+            Connectivity::default(),
+        )
+        .await;
 
         Wallet::new(
             config_repository,
-            MockStorage::default(),
+            Arc::new(RwLock::new(MockStorage::default())),
             MockAccountProviderClient::default(),
-            MockPidIssuerClient::default(),
+            MockCredentialIssuerClient::default(),
             None,
         )
     }
@@ -217,8 +243,8 @@ impl WalletWithMocks {
 
         // Store the registration in `Storage`, populate the field
         // on `Wallet` and set the wallet to unlocked.
-        wallet.storage.get_mut().state = StorageState::Opened;
-        wallet.storage.get_mut().data.insert(
+        wallet.storage.write().await.state = StorageState::Opened;
+        wallet.storage.write().await.data.insert(
             <RegistrationData as KeyedData>::KEY,
             serde_json::to_string(&registration).unwrap(),
         );
@@ -257,15 +283,18 @@ impl WalletWithMocks {
 
     /// Creates mocks and calls `Wallet::init_registration()`, except for the `MockStorage` instance.
     pub async fn init_registration_mocks_with_storage(storage: MockStorage) -> Result<Self, WalletInitError> {
-        let config_repository =
-            UpdatingConfigurationRepository::new(LocalConfigurationRepository::default(), Duration::from_secs(300))
-                .await;
+        let config_repository = UpdatingConfigurationRepository::new(
+            LocalConfigurationRepository::default(),
+            Duration::from_secs(300),
+            Connectivity::default(),
+        )
+        .await;
 
         Wallet::init_registration(
             config_repository,
             storage,
             MockAccountProviderClient::default(),
-            MockPidIssuerClient::default(),
+            MockCredentialIssuerClient::default(),
         )
         .await
     }
@@ -1,51 +1,136 @@
-use tracing::info;
+use tracing::{info, instrument};
 use url::Url;
 
-use crate::{config::ConfigurationRepository, digid::DigidSession};
+use wallet_common::config::wallet_config::WalletConfiguration;
 
-use super::Wallet;
+use crate::{
+    config::ConfigurationRepository, credential_issuer::CredentialIssuerClient, digid::DigidSession,
+    disclosure::MdocDisclosureSession, document::Document, storage::Storage,
+};
+
+use super::{DisclosureError, DisclosureProposal, PidIssuanceError, Wallet};
+
+/// The name of the query parameter of an issuance offer URI that contains the base URL of the issuer.
+const ISSUANCE_OFFER_ISSUER_PARAM: &str = "issuer";
 
 #[derive(Debug)]
 pub enum UriType {
+    /// The redirect back to the wallet after authenticating with DigiD.
     PidIssuance(Url),
+    /// The engagement of a relying party that requests disclosure of attributes.
     Disclosure(Url),
+    /// An offer to start issuance by the credential issuer with the contained base URL.
+    IssuanceOffer(Url),
 }
 
 #[derive(Debug, thiserror::Error)]
 pub enum UriIdentificationError {
     #[error("could not parse URI: {0}")]
     Parse(#[from] url::ParseError),
+    #[error("issuance offer URI does not contain a valid issuer URL")]
+    IssuanceOfferIssuer,
     #[error("unknown URI")]
     Unknown,
 }
 
-impl<CR, S, PEK, APC, DGS, PIC, MDS> Wallet<CR, S, PEK, APC, DGS, PIC, MDS>
+/// The result of [`Wallet::process_uri()`], which is the result of the subsystem the URI was dispatched to.
+#[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
+pub enum UriProcessingResult {
+    PidIssuance(Vec<Document>),
+    Disclosure(DisclosureProposal),
+    /// The DigiD authentication URL, which the user should be sent to in order to continue the issuance offer.
+    IssuanceOffer(Url),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum UriProcessingError {
+    #[error("{0}")]
+    Identification(#[from] UriIdentificationError),
+    #[error("{0}")]
+    Issuance(#[from] PidIssuanceError),
+    #[error("{0}")]
+    Disclosure(#[from] DisclosureError),
+}
+
+/// Classifies the universal links received by the app, based on the URL prefixes in the configuration.
+struct UriRouter {
+    digid_redirect_uri: Url,
+    disclosure_uri_base: Url,
+    issuance_offer_uri_base: Url,
+}
+
+impl UriRouter {
+    fn from_config(config: &WalletConfiguration) -> Result<Self, url::ParseError> {
+        let router = UriRouter {
+            digid_redirect_uri: config.pid_issuance.digid_redirect_uri()?,
+            disclosure_uri_base: config.disclosure.uri_base()?,
+            issuance_offer_uri_base: config.issuance_offer.uri_base()?,
+        };
+
+        Ok(router)
+    }
+
+    fn route(&self, uri: Url) -> Result<UriType, UriIdentificationError> {
+        if uri.as_str().starts_with(self.digid_redirect_uri.as_str()) {
+            return Ok(UriType::PidIssuance(uri));
+        }
+
+        if uri.as_str().starts_with(self.disclosure_uri_base.as_str()) {
+            return Ok(UriType::Disclosure(uri));
+        }
+
+        if uri.as_str().starts_with(self.issuance_offer_uri_base.as_str()) {
+            let issuer_url = uri
+                .query_pairs()
+                .find_map(|(name, value)| (name == ISSUANCE_OFFER_ISSUER_PARAM).then_some(value))
+                .and_then(|issuer_url| Url::parse(&issuer_url).ok())
+                .ok_or(UriIdentificationError::IssuanceOfferIssuer)?;
+
+            return Ok(UriType::IssuanceOffer(issuer_url));
+        }
+
+        Err(UriIdentificationError::Unknown)
+    }
+}
+
+impl<CR, S, PEK, APC, DGS, CIC, MDS> Wallet<CR, S, PEK, APC, DGS, CIC, MDS>
 where
     CR: ConfigurationRepository,
-    DGS: DigidSession,
 {
     pub fn identify_uri(&self, uri_str: &str) -> Result<UriType, UriIdentificationError> {
         info!("Identifying type of URI: {}", uri_str);
 
         let uri = Url::parse(uri_str)?;
 
-        if self
-            .digid_session
-            .as_ref()
-            .map(|session| session.matches_received_redirect_uri(&uri))
-            .unwrap_or_default()
-        {
-            return Ok(UriType::PidIssuance(uri));
-        }
-
         // Assume that redirect URI creation is checked when updating the `Configuration`.
-        let disclosure_redirect_uri_base = self.config_repository.config().disclosure.uri_base().unwrap();
+        let router = UriRouter::from_config(&self.config_repository.config()).unwrap();
 
-        if uri.as_str().starts_with(disclosure_redirect_uri_base.as_str()) {
-            return Ok(UriType::Disclosure(uri));
-        }
+        router.route(uri)
+    }
+}
 
-        Err(UriIdentificationError::Unknown)
+impl<CR, S, PEK, APC, DGS, CIC, MDS> Wallet<CR, S, PEK, APC, DGS, CIC, MDS>
+where
+    CR: ConfigurationRepository,
+    S: Storage,
+    DGS: DigidSession,
+    CIC: CredentialIssuerClient,
+    MDS: MdocDisclosureSession<Self>,
+{
+    /// Identify the type of the URI and dispatch it to the subsystem that handles it, i.e. continue PID issuance
+    /// after DigiD authentication, start a disclosure session or start issuance by the offering credential issuer.
+    #[instrument(skip_all)]
+    pub async fn process_uri(&mut self, uri_str: &str) -> Result<UriProcessingResult, UriProcessingError> {
+        let result = match self.identify_uri(uri_str)? {
+            UriType::PidIssuance(uri) => UriProcessingResult::PidIssuance(self.continue_pid_issuance(&uri).await?),
+            UriType::Disclosure(uri) => UriProcessingResult::Disclosure(self.start_disclosure(&uri).await?),
+            UriType::IssuanceOffer(issuer_url) => {
+                UriProcessingResult::IssuanceOffer(self.create_issuance_auth_url(issuer_url).await?)
+            }
+        };
+
+        Ok(result)
     }
 }
 
@@ -53,27 +138,22 @@ where
 mod tests {
     use assert_matches::assert_matches;
 
-    use crate::digid::MockDigidSession;
-
     use super::{super::tests::WalletWithMocks, *};
 
     #[tokio::test]
-    async fn test_wallet_identify_redirect_uri() {
-        // Prepare an unregistered wallet.
-        let mut wallet = WalletWithMocks::new_unregistered().await;
+    async fn test_wallet_identify_uri() {
+        let wallet = WalletWithMocks::new_unregistered().await;
+        let config = wallet.config_repository.config();
 
-        // Set up some URLs to work with.
+        // Set up some URLs to work with, based on the configured prefixes.
         let example_uri = "https://example.com";
-        let digid_uri = "redirect://here";
-
-        let mut disclosure_uri_base = wallet.config_repository.config().disclosure.uri_base().unwrap();
-
-        // Add a trailing slash to the base path, if needed.
-        if !disclosure_uri_base.path().ends_with('/') {
-            disclosure_uri_base.path_segments_mut().unwrap().push("/");
-        }
-
-        let disclosure_uri = disclosure_uri_base.join("abcd").unwrap();
+        let digid_uri = format!(
+            "{}?code=foo&state=bar",
+            config.pid_issuance.digid_redirect_uri().unwrap()
+        );
+        let disclosure_uri = format!("{}/abcd", config.disclosure.uri_base().unwrap());
+        let issuance_offer_base = config.issuance_offer.uri_base().unwrap();
+        let issuance_offer_uri = format!("{}?issuer=https%3A%2F%2Fissuer.example.com%2F", issuance_offer_base);
 
         // The example URI should not be recognised.
         assert_matches!(
@@ -81,39 +161,70 @@ mod tests {
             UriIdentificationError::Unknown
         );
 
-        // The wallet should not recognise the DigiD URI, as there is no `DigidSession`.
+        // An invalid URI should result in a parse error.
         assert_matches!(
-            wallet.identify_uri(digid_uri).unwrap_err(),
-            UriIdentificationError::Unknown
+            wallet.identify_uri("not a uri").unwrap_err(),
+            UriIdentificationError::Parse(_)
         );
 
-        // Set up a `DigidSession` that will match the URI.
-        let digid_session = {
-            let mut digid_session = MockDigidSession::new();
+        assert_matches!(wallet.identify_uri(&digid_uri).unwrap(), UriType::PidIssuance(_));
+        assert_matches!(wallet.identify_uri(&disclosure_uri).unwrap(), UriType::Disclosure(_));
+        assert_matches!(
+            wallet.identify_uri(&issuance_offer_uri).unwrap(),
+            UriType::IssuanceOffer(issuer_url) if issuer_url.as_str() == "https://issuer.example.com/"
+        );
 
-            digid_session
-                .expect_matches_received_redirect_uri()
-                .returning(move |url| url.as_str() == digid_uri);
+        // An issuance offer without a valid issuer URL should be rejected.
+        assert_matches!(
+            wallet.identify_uri(issuance_offer_base.as_str()).unwrap_err(),
+            UriIdentificationError::IssuanceOfferIssuer
+        );
+        assert_matches!(
+            wallet
+                .identify_uri(&format!("{}?issuer=foobar", issuance_offer_base))
+                .unwrap_err(),
+            UriIdentificationError::IssuanceOfferIssuer
+        );
+    }
 
-            digid_session
-        };
-        wallet.digid_session = digid_session.into();
+    #[tokio::test]
+    async fn test_wallet_process_uri() {
+        let mut wallet = WalletWithMocks::new_registered_and_unlocked().await;
+        let config = wallet.config_repository.config();
+
+        let digid_uri = config.pid_issuance.digid_redirect_uri().unwrap();
+        let disclosure_uri = format!(
+            "{}/Zm9vYmFy?return_url=https%3A%2F%2Fexample.com&session_type=same_device",
+            config.disclosure.uri_base().unwrap()
+        );
+        let issuance_offer_uri = format!(
+            "{}?issuer=https%3A%2F%2Funknown.example.com%2F",
+            config.issuance_offer.uri_base().unwrap()
+        );
 
-        // The wallet should now recognise the DigiD URI.
-        assert_matches!(wallet.identify_uri(digid_uri).unwrap(), UriType::PidIssuance(_));
+        // A DigiD redirect without an active `DigidSession` should be dispatched to PID issuance, which rejects it.
+        assert_matches!(
+            wallet.process_uri(digid_uri.as_str()).await.unwrap_err(),
+            UriProcessingError::Issuance(PidIssuanceError::SessionState)
+        );
 
-        // After clearing the `DigidSession`, the URI should not be recognised again.
-        wallet.digid_session = None;
+        // An issuance offer by an issuer that is not in the configuration should be rejected.
+        assert_matches!(
+            wallet.process_uri(&issuance_offer_uri).await.unwrap_err(),
+            UriProcessingError::Issuance(PidIssuanceError::UnknownIssuer(_))
+        );
 
+        // A disclosure URI should start a disclosure session, which is a proposal by default.
         assert_matches!(
-            wallet.identify_uri(digid_uri).unwrap_err(),
-            UriIdentificationError::Unknown
+            wallet.process_uri(&disclosure_uri).await.unwrap(),
+            UriProcessingResult::Disclosure(_)
         );
+        assert!(wallet.disclosure_session.is_some());
 
-        // The disclosure URI should be recognised.
+        // An unknown URI should not be dispatched at all.
         assert_matches!(
-            wallet.identify_uri(disclosure_uri.as_str()).unwrap(),
-            UriType::Disclosure(_)
+            wallet.process_uri("https://example.com").await.unwrap_err(),
+            UriProcessingError::Identification(UriIdentificationError::Unknown)
         );
     }
 }
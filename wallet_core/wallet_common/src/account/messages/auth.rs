@@ -9,6 +9,7 @@ use crate::{
     errors::{Error, Result},
     jwt::{Jwt, JwtSubject},
     keys::{EphemeralEcdsaKey, SecureEcdsaKey},
+    utils::random_string,
 };
 
 // Registration challenge response
@@ -73,6 +74,78 @@ pub struct Certificate {
     pub certificate: WalletCertificate,
 }
 
+// Wallet certificate revocation request
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RevocationRequestClaims {
+    pub iss: String,
+    pub iat: u64,
+    /// Unique identifier of the request, so that the Wallet Provider can reject it when it is replayed.
+    pub jti: String,
+}
+
+impl JwtSubject for RevocationRequestClaims {
+    const SUB: &'static str = "revocation_request";
+}
+
+pub type RevocationRequest = Jwt<RevocationRequestClaims>;
+
+/// Sent by the wallet to have its certificate revoked and its account removed. As this should also be possible
+/// when the user is not able to enter the PIN, the request is only signed with the hardware key.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RevocationRequestMessage {
+    pub message: RevocationRequest,
+    pub certificate: WalletCertificate,
+}
+
+impl RevocationRequest {
+    pub async fn new_signed(issuer: &str, hw_privkey: &impl SecureEcdsaKey) -> Result<Self> {
+        let claims = RevocationRequestClaims {
+            iss: issuer.to_string(),
+            iat: jsonwebtoken::get_current_timestamp(),
+            jti: random_string(32),
+        };
+
+        Ok(Jwt::sign_with_sub(&claims, hw_privkey).await?)
+    }
+}
+
+// Wallet certificate revocation list
+
+/// The reason a wallet certificate was revoked: either the wallet was reset by the user, or it was blocked because
+/// the PIN was entered incorrectly too many times.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CertificateRevocationReason {
+    Reset,
+    Blocked,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RevokedCertificate {
+    /// The `wallet_id` claim of the revoked wallet certificates.
+    pub wallet_id: String,
+    pub reason: CertificateRevocationReason,
+    /// The time of revocation, in seconds since the epoch.
+    pub revoked_at: u64,
+}
+
+/// The list of revoked wallet certificates that is periodically published by the Wallet Provider, signed with the
+/// certificate signing key, so that it can be consumed by relying parties and issuers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RevocationListClaims {
+    pub revoked_certificates: Vec<RevokedCertificate>,
+
+    pub iss: String,
+    pub iat: u64,
+}
+
+impl JwtSubject for RevocationListClaims {
+    const SUB: &'static str = "wallet_certificate_revocation_list";
+}
+
+pub type RevocationList = Jwt<RevocationListClaims>;
+
 #[cfg(test)]
 mod tests {
     use crate::account::signed::SequenceNumberComparison;
@@ -31,8 +31,16 @@ pub enum ErrorType {
     IncorrectPin(IncorrectPinData),
     PinTimeout(PinTimeoutData),
     AccountBlocked,
+    AccountSuspended,
     InstructionValidation,
+    KeyUsageLimit,
+    InstructionSequenceNumber(InstructionSequenceNumberData),
     KeyNotFound(String),
+    RevocationValidation,
+    WalletBusy,
+    IntegrityEvidence,
+    RateLimited(RateLimitedData),
+    KeyEscrowUnavailable,
 }
 
 #[derive(Debug, Copy, Clone, Serialize, Deserialize)]
@@ -46,6 +54,20 @@ pub struct PinTimeoutData {
     pub time_left_in_ms: u64,
 }
 
+/// Sent by the Wallet Provider when the sequence number in an instruction challenge request has already been
+/// used, so that the client can continue from the last sequence number known to the Wallet Provider.
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub struct InstructionSequenceNumberData {
+    pub sequence_number: u64,
+}
+
+/// Sent by the Wallet Provider when a wallet has sent too many requests, containing the time after which the wallet
+/// may send requests again. This is also sent as the `Retry-After` header, rounded up to whole seconds.
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub struct RateLimitedData {
+    pub retry_after_in_ms: u64,
+}
+
 impl Display for ErrorData {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.title)
@@ -64,8 +86,16 @@ impl From<&ErrorType> for StatusCode {
             ErrorType::IncorrectPin(_) => StatusCode::FORBIDDEN,
             ErrorType::PinTimeout(_) => StatusCode::FORBIDDEN,
             ErrorType::AccountBlocked => StatusCode::UNAUTHORIZED,
+            ErrorType::AccountSuspended => StatusCode::UNAUTHORIZED,
             ErrorType::InstructionValidation => StatusCode::FORBIDDEN,
+            ErrorType::KeyUsageLimit => StatusCode::FORBIDDEN,
+            ErrorType::InstructionSequenceNumber(_) => StatusCode::CONFLICT,
             ErrorType::KeyNotFound(_) => StatusCode::NOT_FOUND,
+            ErrorType::RevocationValidation => StatusCode::UNAUTHORIZED,
+            ErrorType::WalletBusy => StatusCode::TOO_MANY_REQUESTS,
+            ErrorType::IntegrityEvidence => StatusCode::FORBIDDEN,
+            ErrorType::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+            ErrorType::KeyEscrowUnavailable => StatusCode::NOT_IMPLEMENTED,
         }
     }
 }
@@ -93,6 +123,18 @@ mod tests {
         );
     }
 
+    #[test]
+    fn error_data_should_serialize_sequence_number() {
+        let error_data = ErrorData {
+            typ: ErrorType::InstructionSequenceNumber(InstructionSequenceNumberData { sequence_number: 42 }),
+            title: "title123".to_string(),
+        };
+        assert_eq!(
+            json!({"type":"InstructionSequenceNumber","data":{"sequence_number":42},"title":"title123"}),
+            serde_json::to_value(error_data).unwrap()
+        );
+    }
+
     #[test]
     fn error_data_should_serialize_without_data() {
         let error_data = ErrorData {
@@ -1,4 +1,6 @@
+use chrono::{DateTime, Utc};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 
 use crate::{
@@ -17,11 +19,63 @@ use super::auth::WalletCertificate;
 pub struct Instruction<T> {
     pub instruction: SignedDouble<T>,
     pub certificate: WalletCertificate,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub integrity_evidence: Option<IntegrityEvidence>,
+}
+
+/// Evidence that an instruction was sent by a genuine instance of the app on a genuine device, as provided by the
+/// platform. This is bound to the instruction challenge through the hash returned by [`integrity_request_hash()`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum IntegrityEvidence {
+    /// An encrypted integrity verdict from the Google Play Integrity API, of which the nonce is the request hash.
+    PlayIntegrity { token: String },
+    /// An attestation object from the Apple App Attest service for a newly generated key, of which the client data
+    /// hash is the request hash.
+    AppAttest {
+        key_id: Base64Bytes,
+        attestation: Base64Bytes,
+    },
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct CheckPin;
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DeleteWallet;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ExportData;
+
+/// All data the Wallet Provider holds about a wallet user, as returned by the [`ExportData`] instruction. As this is
+/// returned as instruction result, it is signed by the Wallet Provider. The wrapped private keys of the wallet are
+/// not included, as these can only be used by the HSM of the Wallet Provider.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ExportDataResult {
+    pub wallet_id: String,
+    pub hw_pubkey: DerVerifyingKey,
+    pub registration_date_time: Option<DateTime<Utc>>,
+    pub instruction_sequence_number: u64,
+    pub unsuccessful_pin_entries: u8,
+    pub last_unsuccessful_pin_entry: Option<DateTime<Utc>>,
+    pub keys: Vec<ExportedKey>,
+    pub events: Vec<ExportedEvent>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ExportedKey {
+    pub identifier: String,
+    pub signature_count: u64,
+}
+
+/// An event from the audit log of the wallet, e.g. a registration or an instruction that was handled.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ExportedEvent {
+    pub event_type: String,
+    pub instruction: Option<String>,
+    pub date_time: DateTime<Utc>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct GenerateKey {
     pub identifiers: Vec<String>,
@@ -42,6 +96,43 @@ pub struct SignResult {
     pub signatures_by_identifier: HashMap<String, DerSignature>,
 }
 
+/// Gives or withdraws the consent of the user for escrow of the private keys of the wallet by the Wallet Provider,
+/// so that these can be recovered when migrating to another device. While consent is given, keys that are generated
+/// later on are escrowed as well. When the consent is withdrawn, all escrowed keys are deleted.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UpdateKeyEscrowConsent {
+    pub consent: bool,
+}
+
+/// Multiple instructions that are signed and sent to the Wallet Provider as a single instruction, which saves round
+/// trips, e.g. when generating keys and immediately signing with them during issuance. The batch as a whole uses one
+/// challenge and one instruction sequence number. The Wallet Provider handles the instructions in order and stops at
+/// the first one that fails, in which case the effects of the instructions before it are not rolled back.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct InstructionBatch {
+    pub instructions: Vec<BatchedInstruction>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchedInstruction {
+    GenerateKey(GenerateKey),
+    Sign(Sign),
+}
+
+/// The results of the instructions in an [`InstructionBatch`], in the same order.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct InstructionBatchResult {
+    pub results: Vec<BatchedInstructionResult>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchedInstructionResult {
+    GenerateKey(GenerateKeyResult),
+    Sign(SignResult),
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InstructionResultClaims<R> {
     pub result: R,
@@ -76,7 +167,16 @@ pub struct InstructionChallengeRequestMessage {
 pub trait InstructionEndpoint: Serialize + DeserializeOwned {
     const ENDPOINT: &'static str;
 
+    /// High-risk instructions have to be accompanied by [`IntegrityEvidence`], if the platform supports this.
+    const REQUIRES_INTEGRITY_EVIDENCE: bool = false;
+
     type Result: Serialize + DeserializeOwned;
+
+    /// Whether this particular instruction has to be accompanied by [`IntegrityEvidence`], which by default is
+    /// determined by [`Self::REQUIRES_INTEGRITY_EVIDENCE`].
+    fn requires_integrity_evidence(&self) -> bool {
+        Self::REQUIRES_INTEGRITY_EVIDENCE
+    }
 }
 
 impl<R> JwtSubject for InstructionResultClaims<R> {
@@ -109,9 +209,23 @@ impl InstructionEndpoint for CheckPin {
     type Result = ();
 }
 
+impl InstructionEndpoint for DeleteWallet {
+    const ENDPOINT: &'static str = "delete_wallet";
+
+    type Result = ();
+}
+
+impl InstructionEndpoint for ExportData {
+    const ENDPOINT: &'static str = "export_data";
+
+    type Result = ExportDataResult;
+}
+
 impl InstructionEndpoint for GenerateKey {
     const ENDPOINT: &'static str = "generate_key";
 
+    const REQUIRES_INTEGRITY_EVIDENCE: bool = true;
+
     type Result = GenerateKeyResult;
 }
 
@@ -121,6 +235,48 @@ impl InstructionEndpoint for Sign {
     type Result = SignResult;
 }
 
+impl InstructionEndpoint for UpdateKeyEscrowConsent {
+    const ENDPOINT: &'static str = "update_key_escrow_consent";
+
+    const REQUIRES_INTEGRITY_EVIDENCE: bool = true;
+
+    type Result = ();
+}
+
+impl InstructionEndpoint for InstructionBatch {
+    const ENDPOINT: &'static str = "batch";
+
+    type Result = InstructionBatchResult;
+
+    /// A batch requires integrity evidence if any of its instructions does.
+    fn requires_integrity_evidence(&self) -> bool {
+        self.instructions
+            .iter()
+            .any(|instruction| instruction.requires_integrity_evidence())
+    }
+}
+
+impl BatchedInstruction {
+    pub fn requires_integrity_evidence(&self) -> bool {
+        match self {
+            Self::GenerateKey(instruction) => instruction.requires_integrity_evidence(),
+            Self::Sign(instruction) => instruction.requires_integrity_evidence(),
+        }
+    }
+}
+
+impl From<GenerateKey> for BatchedInstruction {
+    fn from(value: GenerateKey) -> Self {
+        Self::GenerateKey(value)
+    }
+}
+
+impl From<Sign> for BatchedInstruction {
+    fn from(value: Sign) -> Self {
+        Self::Sign(value)
+    }
+}
+
 impl<T> Instruction<T>
 where
     T: Serialize + DeserializeOwned,
@@ -145,6 +301,12 @@ where
         Ok(Self {
             instruction: signed,
             certificate,
+            integrity_evidence: None,
         })
     }
 }
+
+/// The hash that [`IntegrityEvidence`] for an instruction should be bound to, which is derived from its challenge.
+pub fn integrity_request_hash(challenge: &[u8]) -> Vec<u8> {
+    Sha256::digest(challenge).to_vec()
+}
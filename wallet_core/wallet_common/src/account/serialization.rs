@@ -18,7 +18,7 @@ use crate::{errors::Error, jwt::EcdsaDecodingKey};
 use super::signed::{SignedDouble, SignedInner};
 
 /// Bytes that (de)serialize to base64.
-#[derive(Clone, Eq, PartialEq)]
+#[derive(Clone, Eq, PartialEq, Hash)]
 pub struct Base64Bytes(pub Vec<u8>);
 impl From<Vec<u8>> for Base64Bytes {
     fn from(val: Vec<u8>) -> Self {
@@ -0,0 +1,57 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use subtle::ConstantTimeEq;
+use tracing::warn;
+
+use crate::utils::sha256;
+
+/// Middleware that only passes on requests that contain the API key of which the SHA-256 digest is provided as state,
+/// as bearer token. The digests of the keys are compared in constant time, so that the time the comparison takes does
+/// not reveal how much of the provided key is correct.
+pub async fn require_api_key<B>(
+    State(api_key_digest): State<Arc<Vec<u8>>>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    if !has_api_key(request.headers(), &api_key_digest) {
+        warn!("rejecting request without valid API key");
+
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    next.run(request).await
+}
+
+fn has_api_key(headers: &HeaderMap, api_key_digest: &[u8]) -> bool {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|api_key| bool::from(sha256(api_key.as_bytes()).ct_eq(api_key_digest)))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use super::*;
+
+    #[test]
+    fn test_has_api_key() {
+        let api_key_digest = sha256(b"secret");
+        let headers =
+            |value: &'static str| HeaderMap::from_iter([(header::AUTHORIZATION, HeaderValue::from_static(value))]);
+
+        assert!(has_api_key(&headers("Bearer secret"), &api_key_digest));
+        assert!(!has_api_key(&headers("Bearer secre"), &api_key_digest));
+        assert!(!has_api_key(&headers("Bearer secrets"), &api_key_digest));
+        assert!(!has_api_key(&headers("Basic secret"), &api_key_digest));
+        assert!(!has_api_key(&HeaderMap::new(), &api_key_digest));
+    }
+}
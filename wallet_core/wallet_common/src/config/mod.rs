@@ -1 +1,6 @@
 pub mod wallet_config;
+
+/// HTTP header in which the wallet sends a random identifier to the configuration server, which is stable across
+/// requests but unrelated to any other identifier of the wallet. The configuration server uses it to serve a new
+/// configuration to a fixed part of the wallets only.
+pub const ROLLOUT_ID_HEADER: &str = "x-rollout-id";
@@ -1,16 +1,20 @@
 use std::{
-    collections::hash_map::DefaultHasher,
+    collections::{hash_map::DefaultHasher, BTreeMap},
     fmt::Debug,
     hash::{Hash, Hasher},
 };
 
 use etag::EntityTag;
 use once_cell::sync::Lazy;
+use p256::ecdsa::VerifyingKey;
 use serde::{Deserialize, Serialize};
 use url::{ParseError, Url};
 use webpki::TrustAnchor;
 
-use crate::{account::serialization::DerVerifyingKey, trust_anchor::DerTrustAnchor};
+use crate::{
+    account::serialization::{Base64Bytes, DerVerifyingKey},
+    trust_anchor::DerTrustAnchor,
+};
 
 // This should always equal the deep/universal link configured for the app.
 static UNIVERSAL_LINK_BASE: Lazy<Url> =
@@ -19,9 +23,19 @@ static UNIVERSAL_LINK_BASE: Lazy<Url> =
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Hash)]
 pub struct WalletConfiguration {
     pub lock_timeouts: LockTimeoutConfiguration,
+    #[serde(default)]
+    pub retry: RetryConfiguration,
+    #[serde(default)]
+    pub pin_policy: PinPolicyConfiguration,
     pub account_server: AccountServerConfiguration,
     pub pid_issuance: PidIssuanceConfiguration,
+    #[serde(default)]
+    pub credential_issuers: Vec<CredentialIssuerConfiguration>,
     pub disclosure: DisclosureConfiguration,
+    #[serde(default)]
+    pub issuance_offer: IssuanceOfferConfiguration,
+    #[serde(default)]
+    pub feature_flags: FeatureFlags,
     pub mdoc_trust_anchors: Vec<DerTrustAnchor>,
     pub version: u64,
 }
@@ -34,6 +48,39 @@ impl WalletConfiguration {
             .collect()
     }
 
+    /// Return the trust anchors for the mdocs issued by the issuer with the specified base URL. The PID issuer uses
+    /// the general [`Self::mdoc_trust_anchors`], other issuers only the trust anchors configured for them.
+    /// If the issuer is not present in the configuration at all, `None` is returned.
+    pub fn issuer_trust_anchors(&self, issuer_url: &Url) -> Option<Vec<TrustAnchor>> {
+        if *issuer_url == self.pid_issuance.pid_issuer_url {
+            return Some(self.mdoc_trust_anchors());
+        }
+
+        self.credential_issuers
+            .iter()
+            .find(|issuer| issuer.issuer_url == *issuer_url)
+            .map(CredentialIssuerConfiguration::mdoc_trust_anchors)
+    }
+
+    /// Return the OpenID identity provider with which the user authenticates for the issuer with the specified
+    /// base URL. This is DigiD for the PID issuer and for any other issuer that does not configure its own
+    /// identity provider. If the issuer is not present in the configuration at all, `None` is returned.
+    pub fn issuer_identity_provider(&self, issuer_url: &Url) -> Option<IdentityProviderConfiguration> {
+        if *issuer_url == self.pid_issuance.pid_issuer_url {
+            return Some(self.pid_issuance.digid());
+        }
+
+        self.credential_issuers
+            .iter()
+            .find(|issuer| issuer.issuer_url == *issuer_url)
+            .map(|issuer| {
+                issuer
+                    .identity_provider
+                    .clone()
+                    .unwrap_or_else(|| self.pid_issuance.digid())
+            })
+    }
+
     pub fn to_hash(&self) -> u64 {
         let mut hasher = DefaultHasher::new();
         self.hash(&mut hasher);
@@ -64,6 +111,74 @@ impl Default for LockTimeoutConfiguration {
     }
 }
 
+/// Retry policy for requests to the Wallet Provider and to credential issuers that failed because of
+/// a transient error. Note that requests that are not idempotent are only retried if they were never sent.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Hash)]
+pub struct RetryConfiguration {
+    /// Maximum number of attempts for a single request, including the first one
+    pub max_attempts: u8,
+    /// Backoff before the first retry in milliseconds, which doubles for every subsequent retry
+    pub initial_backoff_ms: u32,
+    /// Maximum backoff between two attempts in milliseconds
+    pub max_backoff_ms: u32,
+}
+
+impl Default for RetryConfiguration {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff_ms: 500,
+            max_backoff_ms: 4000,
+        }
+    }
+}
+
+/// Requirements that a new PIN should meet, which are checked when the PIN is chosen during registration.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Hash)]
+pub struct PinPolicyConfiguration {
+    /// Minimum number of characters in the PIN
+    pub min_length: u8,
+    /// Maximum number of characters in the PIN
+    pub max_length: u8,
+    /// The class of characters that the PIN may consist of
+    pub allowed_characters: PinCharacters,
+    /// Minimum number of distinct characters in the PIN, which prevents repeating a single character
+    pub min_unique_characters: u8,
+    /// Whether the PIN may consist of consecutively ascending or descending characters, e.g. "123456"
+    pub allow_sequential: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum PinCharacters {
+    /// Only the digits 0 to 9
+    Digits,
+    /// The digits 0 to 9 and the (case insensitive) letters a to z
+    Alphanumeric,
+}
+
+impl PinCharacters {
+    /// The radix used to parse a character, 10 for decimal and 36 for the digits and letters a to z
+    pub fn radix(&self) -> u32 {
+        match self {
+            PinCharacters::Digits => 10,
+            PinCharacters::Alphanumeric => 36,
+        }
+    }
+}
+
+impl Default for PinPolicyConfiguration {
+    fn default() -> Self {
+        Self {
+            min_length: 6,
+            max_length: 6,
+            allowed_characters: PinCharacters::Digits,
+            min_unique_characters: 2,
+            allow_sequential: false,
+        }
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize, Eq, PartialEq, Hash)]
 pub struct AccountServerConfiguration {
     // The base URL for the Account Server API
@@ -71,6 +186,11 @@ pub struct AccountServerConfiguration {
     // The known public key for the Wallet Provider
     pub certificate_public_key: DerVerifyingKey,
     pub instruction_result_public_key: DerVerifyingKey,
+    // The public keys the Wallet Provider used before the current keys, which are still accepted during key rotation
+    #[serde(default)]
+    pub previous_certificate_public_keys: Vec<DerVerifyingKey>,
+    #[serde(default)]
+    pub previous_instruction_result_public_keys: Vec<DerVerifyingKey>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Hash)]
@@ -79,12 +199,106 @@ pub struct PidIssuanceConfiguration {
     pub digid_url: Url,
     pub digid_client_id: String,
     pub digid_redirect_path: String,
+    /// The scopes to request from DigiD. When empty, all scopes that DigiD advertises during discovery are requested.
+    #[serde(default)]
+    pub digid_scopes: Vec<String>,
+}
+
+/// An issuer of credentials other than the PID, e.g. diplomas or permits.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Hash)]
+pub struct CredentialIssuerConfiguration {
+    pub issuer_url: Url,
+    pub mdoc_trust_anchors: Vec<DerTrustAnchor>,
+    /// The identity provider with which the user authenticates for this issuer, e.g. eHerkenning.
+    /// When absent, the user authenticates with DigiD.
+    #[serde(default)]
+    pub identity_provider: Option<IdentityProviderConfiguration>,
+}
+
+/// An OpenID Connect provider with which the user authenticates before issuance, e.g. DigiD or eHerkenning.
+/// Note that the redirect back to the wallet is the same for all identity providers.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Hash)]
+pub struct IdentityProviderConfiguration {
+    pub url: Url,
+    pub client_id: String,
+    /// The scopes to request. When empty, all scopes that the provider advertises during discovery are requested.
+    #[serde(default)]
+    pub scopes: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Hash)]
 pub struct DisclosureConfiguration {
     pub uri_base_path: String,
     pub rp_trust_anchors: Vec<DerTrustAnchor>,
+    /// The relying parties that are known in the registry. When empty, every relying party with a reader
+    /// authentication certificate that chains to one of the [`Self::rp_trust_anchors`] is accepted.
+    #[serde(default)]
+    pub rp_registrations: Vec<RelyingPartyRegistration>,
+}
+
+/// A relying party that is known in the registry, identified by its reader authentication certificate.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Hash)]
+pub struct RelyingPartyRegistration {
+    /// The name of the relying party, which is only used for logging.
+    pub name: String,
+    /// The DER encoded reader authentication certificate of the relying party.
+    pub certificate: Base64Bytes,
+}
+
+/// Universal links that start issuance by one of the configured credential issuers. The base URL of the issuer is
+/// passed in the `issuer` query parameter, e.g. `<universal link base>/issuance?issuer=https://issuer.example.com/`.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, Hash)]
+pub struct IssuanceOfferConfiguration {
+    pub uri_base_path: String,
+}
+
+impl Default for IssuanceOfferConfiguration {
+    fn default() -> Self {
+        Self {
+            uri_base_path: "issuance".to_string(),
+        }
+    }
+}
+
+/// Flags by which features of the app are enabled or disabled per environment, keyed by the name of the feature.
+/// Features that are absent are disabled, so that the app can check a flag before it is added to the configuration.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Eq, PartialEq, Hash)]
+#[serde(transparent)]
+pub struct FeatureFlags(BTreeMap<String, bool>);
+
+impl FeatureFlags {
+    pub fn is_enabled(&self, feature: &str) -> bool {
+        self.0.get(feature).copied().unwrap_or_default()
+    }
+}
+
+impl<S: Into<String>> FromIterator<(S, bool)> for FeatureFlags {
+    fn from_iter<T: IntoIterator<Item = (S, bool)>>(iter: T) -> Self {
+        Self(
+            iter.into_iter()
+                .map(|(feature, enabled)| (feature.into(), enabled))
+                .collect(),
+        )
+    }
+}
+
+impl AccountServerConfiguration {
+    /// Return the current certificate public key, followed by the previous ones.
+    pub fn certificate_public_keys(&self) -> Vec<VerifyingKey> {
+        Self::public_keys(&self.certificate_public_key, &self.previous_certificate_public_keys)
+    }
+
+    /// Return the current instruction result public key, followed by the previous ones.
+    pub fn instruction_result_public_keys(&self) -> Vec<VerifyingKey> {
+        Self::public_keys(
+            &self.instruction_result_public_key,
+            &self.previous_instruction_result_public_keys,
+        )
+    }
+
+    fn public_keys(current: &DerVerifyingKey, previous: &[DerVerifyingKey]) -> Vec<VerifyingKey> {
+        std::iter::once(current).chain(previous).map(|key| key.0).collect()
+    }
 }
 
 impl Debug for AccountServerConfiguration {
@@ -99,6 +313,23 @@ impl PidIssuanceConfiguration {
     pub fn digid_redirect_uri(&self) -> Result<Url, ParseError> {
         UNIVERSAL_LINK_BASE.join(&self.digid_redirect_path)
     }
+
+    pub fn digid(&self) -> IdentityProviderConfiguration {
+        IdentityProviderConfiguration {
+            url: self.digid_url.clone(),
+            client_id: self.digid_client_id.clone(),
+            scopes: self.digid_scopes.clone(),
+        }
+    }
+}
+
+impl CredentialIssuerConfiguration {
+    pub fn mdoc_trust_anchors(&self) -> Vec<TrustAnchor> {
+        self.mdoc_trust_anchors
+            .iter()
+            .map(|anchor| (&anchor.owned_trust_anchor).into())
+            .collect()
+    }
 }
 
 impl DisclosureConfiguration {
@@ -112,4 +343,24 @@ impl DisclosureConfiguration {
             .map(|anchor| (&anchor.owned_trust_anchor).into())
             .collect()
     }
+
+    /// Return the registration of the relying party with the DER encoded reader authentication `certificate`. When
+    /// no relying parties are registered, `None` is returned, as the registry is not used in that case.
+    pub fn rp_registration(&self, certificate: &[u8]) -> Option<&RelyingPartyRegistration> {
+        self.rp_registrations
+            .iter()
+            .find(|registration| registration.certificate.0 == certificate)
+    }
+
+    /// Whether the relying party with the DER encoded reader authentication `certificate` is known, i.e. when it is
+    /// present in the registry or when no relying parties are registered at all.
+    pub fn is_known_rp(&self, certificate: &[u8]) -> bool {
+        self.rp_registrations.is_empty() || self.rp_registration(certificate).is_some()
+    }
+}
+
+impl IssuanceOfferConfiguration {
+    pub fn uri_base(&self) -> Result<Url, ParseError> {
+        UNIVERSAL_LINK_BASE.join(&self.uri_base_path)
+    }
 }
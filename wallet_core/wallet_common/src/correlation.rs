@@ -0,0 +1,155 @@
+use std::{
+    fmt::{Display, Formatter},
+    sync::{Arc, Mutex},
+};
+
+use http::{HeaderMap, HeaderName, HeaderValue};
+
+use crate::utils::random_string;
+
+/// The header in which the correlation ID of a request is sent by the wallet and returned by the servers.
+pub const CORRELATION_ID_HEADER: &str = "x-correlation-id";
+
+const CORRELATION_ID_LENGTH: usize = 16;
+const CORRELATION_ID_MAX_LENGTH: usize = 64;
+
+/// Identifies a high-level operation of the wallet, such as registration or a disclosure, so that the log lines of
+/// all services that handled a request of that operation can be correlated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorrelationId(String);
+
+impl CorrelationId {
+    pub fn new_random() -> Self {
+        Self(random_string(CORRELATION_ID_LENGTH))
+    }
+
+    /// Parse a correlation ID received from a client. As it is logged, only short alphanumeric values (also allowing
+    /// `-` and `_`) are accepted.
+    pub fn parse(value: &str) -> Option<Self> {
+        let is_valid = !value.is_empty()
+            && value.len() <= CORRELATION_ID_MAX_LENGTH
+            && value.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+        is_valid.then(|| Self(value.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn header_value(&self) -> HeaderValue {
+        // The contents of the ID are restricted to characters that are valid in a header value.
+        HeaderValue::from_str(&self.0).unwrap()
+    }
+}
+
+impl Display for CorrelationId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Keeps track of the correlation ID of the operation that the wallet is currently performing, which is started
+/// by calling [`Self::start_operation()`]. The HTTP clients add the current ID to every request they send.
+///
+/// Cloning this type is cheap and all clones share the same state, so that the wallet that starts operations and the
+/// HTTP clients that send the requests can each hold a clone.
+#[derive(Debug, Clone, Default)]
+pub struct CorrelationTracker(Arc<Mutex<Option<CorrelationId>>>);
+
+impl CorrelationTracker {
+    /// Start a new operation, returning its newly generated correlation ID.
+    pub fn start_operation(&self) -> CorrelationId {
+        let correlation_id = CorrelationId::new_random();
+        self.0.lock().unwrap().replace(correlation_id.clone());
+
+        correlation_id
+    }
+
+    pub fn current(&self) -> Option<CorrelationId> {
+        self.0.lock().unwrap().clone()
+    }
+
+    /// The headers to add to a request, which are empty when no operation has been started yet.
+    pub fn headers(&self) -> HeaderMap {
+        self.current()
+            .map(|correlation_id| {
+                HeaderMap::from_iter([(
+                    HeaderName::from_static(CORRELATION_ID_HEADER),
+                    correlation_id.header_value(),
+                )])
+            })
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(feature = "axum")]
+pub use self::middleware::track_correlation_id;
+
+#[cfg(feature = "axum")]
+mod middleware {
+    use axum::{http::Request, middleware::Next, response::Response};
+    use tracing::{info_span, Instrument};
+
+    use super::*;
+
+    /// Middleware that handles every request within a span that contains the correlation ID of the request, so that
+    /// it is part of every log line. When the request contains no valid correlation ID, a new one is generated. The
+    /// correlation ID is returned to the client in the response headers.
+    pub async fn track_correlation_id<B>(request: Request<B>, next: Next<B>) -> Response {
+        let correlation_id = request
+            .headers()
+            .get(CORRELATION_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(CorrelationId::parse)
+            .unwrap_or_else(CorrelationId::new_random);
+
+        let span = info_span!(
+            "request",
+            method = %request.method(),
+            path = request.uri().path(),
+            correlation_id = %correlation_id,
+        );
+        let mut response = next.run(request).instrument(span).await;
+        response
+            .headers_mut()
+            .insert(CORRELATION_ID_HEADER, correlation_id.header_value());
+
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_correlation_id_parse() {
+        assert!(CorrelationId::parse("aBc-123_xyz").is_some());
+        assert!(CorrelationId::parse("").is_none());
+        assert!(CorrelationId::parse("with space").is_none());
+        assert!(CorrelationId::parse("line\nbreak").is_none());
+        assert!(CorrelationId::parse(&"a".repeat(CORRELATION_ID_MAX_LENGTH + 1)).is_none());
+
+        let correlation_id = CorrelationId::new_random();
+        assert_eq!(CorrelationId::parse(correlation_id.as_str()), Some(correlation_id));
+    }
+
+    #[test]
+    fn test_correlation_tracker() {
+        let tracker = CorrelationTracker::default();
+        assert!(tracker.current().is_none());
+        assert!(tracker.headers().is_empty());
+
+        // All clones share the current operation.
+        let correlation_id = tracker.clone().start_operation();
+        assert_eq!(tracker.current(), Some(correlation_id.clone()));
+        assert_eq!(
+            tracker.headers().get(CORRELATION_ID_HEADER).unwrap(),
+            correlation_id.as_str()
+        );
+
+        let next_correlation_id = tracker.start_operation();
+        assert_ne!(next_correlation_id, correlation_id);
+    }
+}
@@ -0,0 +1,119 @@
+use std::fmt::{Display, Formatter};
+
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+
+/// The JSON body of an error response of the PID issuer, which is sent with the `application/problem+json` content
+/// type. Like [`crate::account::messages::errors::ErrorData`], it is loosely based on
+/// [RFC 7807](https://datatracker.ietf.org/doc/html/rfc7807) and contains:
+///
+/// * A `code`, which identifies the error and can be relied upon by the wallet to decide how to present it.
+/// * A `title`, which contains a human-readable description of the error, intended for logging.
+/// * Whether the request is `retryable`, i.e. may succeed when it is sent again without any action from the user.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IssuerErrorData {
+    pub code: IssuerErrorCode,
+    pub title: String,
+    pub retryable: bool,
+}
+
+/// The list of uniquely identifiable errors of the PID issuer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssuerErrorCode {
+    /// An unexpected error occurred at the PID issuer.
+    Unexpected,
+    /// DigiD could not be reached by the PID issuer.
+    DigidUnavailable,
+    /// DigiD did not accept the access token, which requires the user to log in again.
+    DigidAccessTokenRejected,
+    /// DigiD did not provide a BSN for the user.
+    DigidNoBsn,
+    /// No person is registered with the BSN of the user.
+    PersonNotFound,
+    /// The registration of the person lacks attributes that are required for issuance.
+    IncompletePerson,
+    /// The source of the attributes could not be reached or returned an invalid response.
+    AttributesUnavailable,
+    /// The issuance session is unknown or has already ended.
+    SessionState,
+    /// The requested status list is not published by the PID issuer.
+    StatusListNotFound,
+    /// Issuance was attempted too often for the same person or from the same client. The time after which it may be
+    /// attempted again is sent in the `Retry-After` header.
+    RateLimited,
+    /// The proof of possession of a previously issued mdoc, presented to obtain fresh copies, was not accepted.
+    ReissuanceRejected,
+    /// None of the doc types can be issued with the scopes that the user granted in DigiD.
+    NoDocTypesGranted,
+}
+
+impl IssuerErrorCode {
+    /// Whether the error is temporary, as opposed to an error that occurs again when the request is repeated.
+    pub fn is_retryable(self) -> bool {
+        matches!(
+            self,
+            Self::DigidUnavailable | Self::AttributesUnavailable | Self::RateLimited
+        )
+    }
+}
+
+impl IssuerErrorData {
+    pub fn new(code: IssuerErrorCode, title: String) -> Self {
+        IssuerErrorData {
+            code,
+            title,
+            retryable: code.is_retryable(),
+        }
+    }
+}
+
+impl Display for IssuerErrorData {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.title)
+    }
+}
+
+/// As for the errors of the Wallet Provider, every error code maps to a single HTTP response code.
+impl From<IssuerErrorCode> for StatusCode {
+    fn from(value: IssuerErrorCode) -> Self {
+        match value {
+            IssuerErrorCode::Unexpected => StatusCode::INTERNAL_SERVER_ERROR,
+            IssuerErrorCode::DigidUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+            IssuerErrorCode::DigidAccessTokenRejected => StatusCode::UNAUTHORIZED,
+            IssuerErrorCode::DigidNoBsn => StatusCode::FORBIDDEN,
+            IssuerErrorCode::PersonNotFound => StatusCode::NOT_FOUND,
+            IssuerErrorCode::IncompletePerson => StatusCode::UNPROCESSABLE_ENTITY,
+            IssuerErrorCode::AttributesUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+            IssuerErrorCode::SessionState => StatusCode::BAD_REQUEST,
+            IssuerErrorCode::StatusListNotFound => StatusCode::NOT_FOUND,
+            IssuerErrorCode::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            IssuerErrorCode::ReissuanceRejected => StatusCode::FORBIDDEN,
+            IssuerErrorCode::NoDocTypesGranted => StatusCode::FORBIDDEN,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_issuer_error_data_json() {
+        let error_data = IssuerErrorData::new(IssuerErrorCode::DigidUnavailable, "title123".to_string());
+
+        assert_eq!(
+            json!({"code": "digid_unavailable", "title": "title123", "retryable": true}),
+            serde_json::to_value(&error_data).unwrap()
+        );
+        assert_eq!(
+            serde_json::from_value::<IssuerErrorData>(
+                json!({"code": "person_not_found", "title": "title123", "retryable": false})
+            )
+            .unwrap(),
+            IssuerErrorData::new(IssuerErrorCode::PersonNotFound, "title123".to_string())
+        );
+    }
+}
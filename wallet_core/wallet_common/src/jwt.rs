@@ -2,14 +2,16 @@ use std::marker::PhantomData;
 
 use base64::prelude::*;
 
-use jsonwebtoken::{Algorithm, DecodingKey, Header, Validation};
+use jsonwebtoken::{
+    jwk::{self, AlgorithmParameters, EllipticCurve},
+    Algorithm, DecodingKey, Header, Validation,
+};
 use p256::ecdsa::VerifyingKey;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
 use crate::{account::serialization::DerVerifyingKey, keys::SecureEcdsaKey};
 
-// TODO implement keyring and use kid header item for key rollover
-
 // JWT type, using `<T>` and `Phantomdata<T>` in the same way and for the same reason as `SignedDouble<T>`; see the
 // comment there.
 #[derive(Debug, Clone)]
@@ -30,6 +32,12 @@ pub enum JwtError {
     Validation(#[source] jsonwebtoken::errors::Error),
     #[error("error signing JWT: {0}")]
     Signing(#[source] Box<dyn std::error::Error + Send + Sync + 'static>),
+    #[error("no public key to verify JWT with")]
+    NoMatchingKey,
+    #[error("unexpected JWT type: {0:?}")]
+    UnexpectedType(Option<String>),
+    #[error("JWT header does not contain a P-256 public key")]
+    UnsupportedJwk,
 }
 
 pub trait JwtSubject {
@@ -96,6 +104,65 @@ where
 
         Ok(payload)
     }
+
+    /// Verify a JWT of type `typ` that is signed with the public key in the `jwk` field of its header, as is the case
+    /// for a proof of possession of that key, and parse and return its payload along with that public key. Note that
+    /// this only establishes that the signer holds the private key, not who the signer is.
+    pub fn parse_and_verify_with_jwk(&self, typ: &str, validation_options: &Validation) -> Result<(T, VerifyingKey)> {
+        let header = jsonwebtoken::decode_header(&self.0).map_err(JwtError::Validation)?;
+        if header.typ.as_deref() != Some(typ) {
+            return Err(JwtError::UnexpectedType(header.typ));
+        }
+
+        let pubkey = header
+            .jwk
+            .as_ref()
+            .and_then(jwk_verifying_key)
+            .ok_or(JwtError::UnsupportedJwk)?;
+        let payload = self.parse_and_verify(&pubkey.into(), validation_options)?;
+
+        Ok((payload, pubkey))
+    }
+
+    /// Verify the JWT against a set of public keys, e.g. the current and previous keys of a key that is rotated, and
+    /// parse and return its payload. If the `kid` in the JWT header matches the JWK thumbprint of one of the keys, only
+    /// that key is used. Otherwise, which is the case for JWTs signed before the key had a key ID, each of the keys is
+    /// tried in turn.
+    pub fn parse_and_verify_from_keys(&self, pubkeys: &[VerifyingKey], validation_options: &Validation) -> Result<T> {
+        let kid = jsonwebtoken::decode_header(&self.0).map_err(JwtError::Validation)?.kid;
+
+        if let Some(pubkey) = kid.and_then(|kid| pubkeys.iter().find(|pubkey| jwk_thumbprint(pubkey) == kid)) {
+            return self.parse_and_verify(&(*pubkey).into(), validation_options);
+        }
+
+        let mut result = Err(JwtError::NoMatchingKey);
+        for pubkey in pubkeys {
+            result = self.parse_and_verify(&(*pubkey).into(), validation_options);
+            if result.is_ok() {
+                break;
+            }
+        }
+
+        result
+    }
+}
+
+/// Decode the public key of a P-256 JWK, if it is one.
+fn jwk_verifying_key(jwk: &jwk::Jwk) -> Option<VerifyingKey> {
+    let AlgorithmParameters::EllipticCurve(params) = &jwk.algorithm else {
+        return None;
+    };
+    if params.curve != EllipticCurve::P256 {
+        return None;
+    }
+
+    let x = BASE64_URL_SAFE_NO_PAD.decode(&params.x).ok()?;
+    let y = BASE64_URL_SAFE_NO_PAD.decode(&params.y).ok()?;
+    if x.len() != 32 || y.len() != 32 {
+        return None;
+    }
+
+    VerifyingKey::from_sec1_bytes(&[vec![4u8], x, y].concat()).ok()
 }
 
 impl<T> Jwt<T>
@@ -144,10 +211,33 @@ where
         self.parse_and_verify(pubkey, &validation_options)
     }
 
+    /// Verify the JWT against a set of public keys, see [`Jwt::parse_and_verify_from_keys()`], and parse and return
+    /// its payload.
+    pub fn parse_and_verify_with_sub_from_keys(&self, pubkeys: &[VerifyingKey]) -> Result<T> {
+        let mut validation_options = validations();
+        validation_options.required_spec_claims.insert("sub".to_string());
+        self.parse_and_verify_from_keys(pubkeys, &validation_options)
+    }
+
     pub async fn sign_with_sub(payload: &T, privkey: &impl SecureEcdsaKey) -> Result<Jwt<T>> {
+        Self::sign_with_sub_and_kid(payload, "0", privkey).await
+    }
+
+    /// Sign the payload with the JWK thumbprint of the public key as `kid` in the header, so that a verifier that
+    /// accepts multiple keys can select the right one, see [`Jwt::parse_and_verify_with_sub_from_keys()`].
+    pub async fn sign_with_sub_and_thumbprint(payload: &T, privkey: &impl SecureEcdsaKey) -> Result<Jwt<T>> {
+        let pubkey = privkey
+            .verifying_key()
+            .await
+            .map_err(|err| JwtError::Signing(Box::new(err)))?;
+
+        Self::sign_with_sub_and_kid(payload, &jwk_thumbprint(&pubkey), privkey).await
+    }
+
+    async fn sign_with_sub_and_kid(payload: &T, kid: &str, privkey: &impl SecureEcdsaKey) -> Result<Jwt<T>> {
         let header = &Header {
             alg: Algorithm::ES256,
-            kid: "0".to_owned().into(),
+            kid: kid.to_owned().into(),
             ..Default::default()
         };
         let claims = &JwtPayload {
@@ -160,6 +250,61 @@ where
     }
 }
 
+/// Compute the JWK thumbprint of an ECDSA public key as specified in RFC 7638, using SHA-256, which is used as key ID.
+pub fn jwk_thumbprint(pubkey: &VerifyingKey) -> String {
+    let (x, y) = jwk_coordinates(pubkey);
+
+    // The members of the JWK are required to be in lexicographical order, without any whitespace.
+    let jwk = format!(r#"{{"crv":"P-256","kty":"EC","x":"{}","y":"{}"}}"#, x, y);
+
+    BASE64_URL_SAFE_NO_PAD.encode(Sha256::digest(jwk.as_bytes()))
+}
+
+fn jwk_coordinates(pubkey: &VerifyingKey) -> (String, String) {
+    let point = pubkey.to_encoded_point(false);
+
+    // Both coordinates are always present in an uncompressed point.
+    let x = BASE64_URL_SAFE_NO_PAD.encode(point.x().unwrap());
+    let y = BASE64_URL_SAFE_NO_PAD.encode(point.y().unwrap());
+
+    (x, y)
+}
+
+/// ECDSA public key in the JSON Web Key format of RFC 7517, with its JWK thumbprint as key ID.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Jwk {
+    pub kty: String,
+    pub crv: String,
+    pub x: String,
+    pub y: String,
+    pub kid: String,
+    #[serde(rename = "use")]
+    pub key_use: String,
+    pub alg: String,
+}
+
+impl Jwk {
+    pub fn new(pubkey: &VerifyingKey, key_use: impl Into<String>) -> Self {
+        let (x, y) = jwk_coordinates(pubkey);
+
+        Jwk {
+            kty: "EC".to_string(),
+            crv: "P-256".to_string(),
+            x,
+            y,
+            kid: jwk_thumbprint(pubkey),
+            key_use: key_use.into(),
+            alg: "ES256".to_string(),
+        }
+    }
+}
+
+/// A set of public keys in the JSON Web Key Set format of RFC 7517.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Jwks {
+    pub keys: Vec<Jwk>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 struct JwtPayload<T> {
     #[serde(flatten)]
@@ -266,6 +411,143 @@ mod tests {
         assert_eq!(t, parsed);
     }
 
+    #[tokio::test]
+    async fn test_sign_and_verify_with_sub_from_keys() {
+        let private_key = SigningKey::random(&mut OsRng);
+        let previous_private_key = SigningKey::random(&mut OsRng);
+        let other_private_key = SigningKey::random(&mut OsRng);
+        let pubkeys = [*private_key.verifying_key(), *previous_private_key.verifying_key()];
+        let t = ToyMessage::default();
+
+        // the JWT has the JWK thumbprint of the key as `kid`
+        let jwt = Jwt::sign_with_sub_and_thumbprint(&t, &private_key).await.unwrap();
+        let jwt_header: HashMap<String, serde_json::Value> = part(0, &jwt.0);
+        assert_eq!(
+            *jwt_header.get("kid").unwrap(),
+            serde_json::Value::String(jwk_thumbprint(private_key.verifying_key()))
+        );
+        assert_eq!(jwt.parse_and_verify_with_sub_from_keys(&pubkeys).unwrap(), t);
+
+        // a JWT without a matching `kid` can be verified with any of the keys
+        let jwt = Jwt::sign_with_sub(&t, &previous_private_key).await.unwrap();
+        assert_eq!(jwt.parse_and_verify_with_sub_from_keys(&pubkeys).unwrap(), t);
+
+        // a JWT signed by a key that is not in the set is rejected
+        let jwt = Jwt::sign_with_sub_and_thumbprint(&t, &other_private_key).await.unwrap();
+        jwt.parse_and_verify_with_sub_from_keys(&pubkeys).unwrap_err();
+        jwt.parse_and_verify_with_sub_from_keys(&[]).unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn test_sign_and_verify_from_keys() {
+        let private_key = SigningKey::random(&mut OsRng);
+        let previous_private_key = SigningKey::random(&mut OsRng);
+        let pubkeys = [*private_key.verifying_key(), *previous_private_key.verifying_key()];
+        let t = ToyMessage::default();
+
+        // a JWT with the JWK thumbprint of the key as `kid` is verified with that key
+        let kid_header = Header {
+            kid: jwk_thumbprint(private_key.verifying_key()).into(),
+            ..header()
+        };
+        let kid_jwt = Jwt::sign(&t, &kid_header, &private_key).await.unwrap();
+        assert_eq!(kid_jwt.parse_and_verify_from_keys(&pubkeys, &validations()).unwrap(), t);
+
+        // a JWT without a `kid` can be verified with any of the keys
+        let jwt = Jwt::sign(&t, &header(), &previous_private_key).await.unwrap();
+        assert_eq!(jwt.parse_and_verify_from_keys(&pubkeys, &validations()).unwrap(), t);
+
+        // a JWT of which the `kid` refers to a key that is not in the set is rejected
+        kid_jwt
+            .parse_and_verify_from_keys(&pubkeys[1..], &validations())
+            .unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn test_sign_and_verify_with_jwk() {
+        let private_key = SigningKey::random(&mut OsRng);
+        let other_private_key = SigningKey::random(&mut OsRng);
+        let t = ToyMessage::default();
+
+        let jwk_header = |pubkey: &VerifyingKey, typ: &str| {
+            let (x, y) = jwk_coordinates(pubkey);
+            Header {
+                typ: typ.to_string().into(),
+                jwk: serde_json::from_value(serde_json::json!({"kty": "EC", "crv": "P-256", "x": x, "y": y})).unwrap(),
+                ..header()
+            }
+        };
+
+        // the JWT is verified against the key in its header, which is returned
+        let jwt = Jwt::sign(&t, &jwk_header(private_key.verifying_key(), "toy+jwt"), &private_key)
+            .await
+            .unwrap();
+        let (parsed, pubkey) = jwt.parse_and_verify_with_jwk("toy+jwt", &validations()).unwrap();
+        assert_eq!(parsed, t);
+        assert_eq!(pubkey, *private_key.verifying_key());
+
+        // the type should match
+        assert!(matches!(
+            jwt.parse_and_verify_with_jwk("other+jwt", &validations()),
+            Err(JwtError::UnexpectedType(Some(typ))) if typ == "toy+jwt"
+        ));
+
+        // a JWT signed with a key other than the one in its header is rejected
+        let jwt = Jwt::sign(
+            &t,
+            &jwk_header(other_private_key.verifying_key(), "toy+jwt"),
+            &private_key,
+        )
+        .await
+        .unwrap();
+        assert!(matches!(
+            jwt.parse_and_verify_with_jwk("toy+jwt", &validations()),
+            Err(JwtError::Validation(_))
+        ));
+
+        // a JWT without a key in its header is rejected
+        let jwt = Jwt::sign(
+            &t,
+            &Header {
+                typ: "toy+jwt".to_string().into(),
+                ..header()
+            },
+            &private_key,
+        )
+        .await
+        .unwrap();
+        assert!(matches!(
+            jwt.parse_and_verify_with_jwk("toy+jwt", &validations()),
+            Err(JwtError::UnsupportedJwk)
+        ));
+    }
+
+    #[test]
+    fn test_jwk_thumbprint() {
+        // The example key of RFC 7638 is an RSA key, so use the P-256 example key of RFC 7517, appendix A.1 instead.
+        let jwk = Jwk {
+            kty: "EC".to_string(),
+            crv: "P-256".to_string(),
+            x: "MKBCTNIcKUSDii11ySs3526iDZ8AiTo7Tu6KPAqv7D4".to_string(),
+            y: "4Etl6SRW2YiLUrN5vfvVHuhp7x8PxltmWWlbbM4IFyM".to_string(),
+            kid: "cn-I_WNMClehiVp51i_0VpOENW1upEerA8sEam5hn-s".to_string(),
+            key_use: "sig".to_string(),
+            alg: "ES256".to_string(),
+        };
+        let pubkey = VerifyingKey::from_sec1_bytes(
+            &[
+                vec![4u8],
+                BASE64_URL_SAFE_NO_PAD.decode(&jwk.x).unwrap(),
+                BASE64_URL_SAFE_NO_PAD.decode(&jwk.y).unwrap(),
+            ]
+            .concat(),
+        )
+        .unwrap();
+
+        assert_eq!(jwk_thumbprint(&pubkey), jwk.kid);
+        assert_eq!(Jwk::new(&pubkey, "sig"), jwk);
+    }
+
     /// Decode and deserialize the specified part of the JWT.
     fn part<T: DeserializeOwned>(i: u8, jwt: &str) -> T {
         let bts = BASE64_URL_SAFE_NO_PAD
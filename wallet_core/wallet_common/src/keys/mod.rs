@@ -1,4 +1,4 @@
-use std::error::Error;
+use std::{error::Error, future::Future};
 
 use p256::ecdsa::{Signature, VerifyingKey};
 
@@ -42,12 +42,16 @@ pub trait WithIdentifier {
 /// Contract for encryption keys suitable for use in the wallet, e.g. for securely storing the database key.
 /// Should be sufficiently secured e.g. through Android's TEE/StrongBox or Apple's SE.
 /// Handles to private keys are requested through [`ConstructibleWithIdentifier::new()`].
+/// The returned futures are `Send`, so that the keys can also be used from background tasks.
 pub trait SecureEncryptionKey: ConstructibleWithIdentifier {
     // from ConstructibleWithIdentifier: new(), identifier()
     type Error: Error + Send + Sync + 'static;
 
-    async fn encrypt(&self, msg: &[u8]) -> Result<Vec<u8>, Self::Error>;
-    async fn decrypt(&self, msg: &[u8]) -> Result<Vec<u8>, Self::Error>;
+    fn encrypt(&self, msg: &[u8]) -> impl Future<Output = Result<Vec<u8>, Self::Error>> + Send;
+    fn decrypt(&self, msg: &[u8]) -> impl Future<Output = Result<Vec<u8>, Self::Error>> + Send;
+
+    /// Permanently delete the key. A new key will be created when this identifier is used again.
+    fn delete(&self) -> impl Future<Output = Result<(), Self::Error>> + Send;
 }
 
 #[cfg(any(test, feature = "mock"))]
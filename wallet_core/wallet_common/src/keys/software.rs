@@ -12,9 +12,9 @@ use crate::{keys::WithIdentifier, utils::random_bytes};
 
 use super::{ConstructibleWithIdentifier, EcdsaKey, SecureEcdsaKey, SecureEncryptionKey};
 
-// static for storing identifier -> signing key mapping, will only every grow
+// static for storing identifier -> signing key mapping
 static SIGNING_KEYS: Lazy<Mutex<HashMap<String, SigningKey>>> = Lazy::new(|| Mutex::new(HashMap::new()));
-// static for storing identifier -> aes cipher mapping, will only ever grow
+// static for storing identifier -> aes cipher mapping
 static ENCRYPTION_CIPHERS: Lazy<Mutex<HashMap<String, Aes256Gcm>>> = Lazy::new(|| Mutex::new(HashMap::new()));
 
 #[derive(Debug, Clone)]
@@ -34,19 +34,35 @@ impl SoftwareEcdsaKey {
     }
 }
 
+impl SoftwareEcdsaKey {
+    /// Remove a key from the map of [`SoftwareEcdsaKey`]s, after which a new random key will be generated for the
+    /// identifier on first use.
+    pub fn remove(identifier: &str) {
+        SIGNING_KEYS
+            .lock()
+            .expect("Could not get lock on SIGNING_KEYS")
+            .remove(identifier);
+    }
+}
+
 impl EcdsaKey for SoftwareEcdsaKey {
     type Error = p256::ecdsa::Error;
 
     async fn verifying_key(&self) -> Result<VerifyingKey, Self::Error> {
-        let signing_keys = SIGNING_KEYS.lock().expect("Could not get lock on SIGNING_KEYS");
-        let key = signing_keys.get(&self.identifier).unwrap().verifying_key();
+        let mut signing_keys = SIGNING_KEYS.lock().expect("Could not get lock on SIGNING_KEYS");
+        let key = signing_keys
+            .entry(self.identifier.clone())
+            .or_insert_with(|| SigningKey::random(&mut OsRng))
+            .verifying_key();
 
         Ok(*key)
     }
 
     async fn try_sign(&self, msg: &[u8]) -> Result<Signature, Self::Error> {
-        let signing_keys = SIGNING_KEYS.lock().expect("Could not get lock on SIGNING_KEYS");
-        let key = signing_keys.get(&self.identifier).unwrap();
+        let mut signing_keys = SIGNING_KEYS.lock().expect("Could not get lock on SIGNING_KEYS");
+        let key = signing_keys
+            .entry(self.identifier.clone())
+            .or_insert_with(|| SigningKey::random(&mut OsRng));
         p256::ecdsa::signature::Signer::try_sign(key, msg)
     }
 }
@@ -119,14 +135,14 @@ impl SecureEncryptionKey for SoftwareEncryptionKey {
         let nonce_bytes = random_bytes(12);
         let nonce = Nonce::from_slice(&nonce_bytes); // 96-bits; unique per message
 
-        let encryption_ciphers = ENCRYPTION_CIPHERS
+        let mut encryption_ciphers = ENCRYPTION_CIPHERS
             .lock()
             .expect("Could not get lock on ENCRYPTION_CIPHERS");
 
         // Encrypt the provided message
         let encrypted_msg = encryption_ciphers
-            .get(&self.identifier)
-            .unwrap()
+            .entry(self.identifier.clone())
+            .or_insert_with(|| Aes256Gcm::new(&Aes256Gcm::generate_key(&mut OsRng)))
             .encrypt(nonce, msg)
             .expect("Could not encrypt message");
 
@@ -140,17 +156,25 @@ impl SecureEncryptionKey for SoftwareEncryptionKey {
         // Re-create the nonce from the first 12 bytes
         let nonce = Nonce::from_slice(&msg[..12]);
 
-        let encryption_ciphers = ENCRYPTION_CIPHERS
+        let mut encryption_ciphers = ENCRYPTION_CIPHERS
             .lock()
             .expect("Could not get lock on ENCRYPTION_CIPHERS");
 
-        // Decrypt the provided message with the retrieved nonce
+        // Decrypt the provided message with the retrieved nonce, which fails if the message was modified
         let decrypted_msg = encryption_ciphers
-            .get(&self.identifier)
-            .unwrap()
-            .decrypt(nonce, &msg[12..])
-            .expect("Could not decrypt message");
+            .entry(self.identifier.clone())
+            .or_insert_with(|| Aes256Gcm::new(&Aes256Gcm::generate_key(&mut OsRng)))
+            .decrypt(nonce, &msg[12..])?;
 
         Ok(decrypted_msg)
     }
+
+    async fn delete(&self) -> Result<(), Self::Error> {
+        ENCRYPTION_CIPHERS
+            .lock()
+            .expect("Could not get lock on ENCRYPTION_CIPHERS")
+            .remove(&self.identifier);
+
+        Ok(())
+    }
 }
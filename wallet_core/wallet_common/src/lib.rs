@@ -1,9 +1,19 @@
 pub mod account;
+#[cfg(feature = "axum")]
+pub mod api_key;
 pub mod config;
+pub mod correlation;
 pub mod errors;
 pub mod generator;
+pub mod issuer_error;
 pub mod jwt;
 pub mod keys;
+#[cfg(feature = "metrics")]
+pub mod metrics;
 pub mod spawn;
+#[cfg(feature = "telemetry")]
+pub mod telemetry;
+#[cfg(feature = "tls")]
+pub mod tls;
 pub mod trust_anchor;
 pub mod utils;
@@ -0,0 +1,58 @@
+use std::time::Instant;
+
+use axum::{
+    extract::MatchedPath,
+    http::Request,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use metrics::{histogram, increment_counter};
+use metrics_exporter_prometheus::{BuildError, Matcher, PrometheusBuilder, PrometheusHandle};
+
+pub const HTTP_REQUESTS_TOTAL: &str = "http_requests_total";
+pub const HTTP_REQUEST_DURATION_SECONDS: &str = "http_request_duration_seconds";
+
+const HTTP_REQUEST_DURATION_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// Install the Prometheus recorder as global recorder for the `metrics` macros. This should be called at most once,
+/// by the server binary, as any subsequent call will fail.
+pub fn install_recorder() -> Result<PrometheusHandle, BuildError> {
+    PrometheusBuilder::new()
+        .set_buckets_for_metric(
+            Matcher::Full(HTTP_REQUEST_DURATION_SECONDS.to_string()),
+            HTTP_REQUEST_DURATION_BUCKETS,
+        )?
+        .install_recorder()
+}
+
+/// Router that exposes all recorded metrics in the Prometheus text format on `/metrics`.
+pub fn metrics_router(handle: PrometheusHandle) -> Router {
+    Router::new().route("/metrics", get(move || async move { handle.render() }))
+}
+
+/// Middleware that records the number of requests and their latency, labeled by method, route and status code. The
+/// route is the path template that matched the request (e.g. `/sessions/:session_id`), rather than the actual path,
+/// so that the number of time series does not grow with the number of sessions. When no recorder has been installed,
+/// this does nothing.
+pub async fn track_http_metrics<B>(request: Request<B>, next: Next<B>) -> Response {
+    let start = Instant::now();
+
+    let method = request.method().to_string();
+    let path = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|matched_path| matched_path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+
+    let response = next.run(request).await.into_response();
+
+    let status = response.status().as_u16().to_string();
+    let labels = [("method", method), ("path", path), ("status", status)];
+
+    increment_counter!(HTTP_REQUESTS_TOTAL, &labels);
+    histogram!(HTTP_REQUEST_DURATION_SECONDS, start.elapsed().as_secs_f64(), &labels);
+
+    response
+}
@@ -0,0 +1,86 @@
+use opentelemetry::{trace::TraceError, KeyValue};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{
+    runtime,
+    trace::{self, Tracer},
+    Resource,
+};
+use serde::Deserialize;
+use tracing::{level_filters::LevelFilter, Subscriber};
+use tracing_opentelemetry::OpenTelemetryLayer;
+use tracing_subscriber::{
+    layer::SubscriberExt,
+    registry::LookupSpan,
+    util::{SubscriberInitExt, TryInitError},
+    EnvFilter,
+};
+use url::Url;
+
+#[derive(Debug, thiserror::Error)]
+pub enum TelemetryError {
+    #[error("could not install OTLP exporter: {0}")]
+    Exporter(#[from] TraceError),
+    #[error("could not install global subscriber: {0}")]
+    Subscriber(#[from] TryInitError),
+}
+
+/// Configuration of the exporter that sends the spans of a server to an OpenTelemetry collector over OTLP (gRPC).
+#[derive(Clone, Deserialize)]
+pub struct OtlpSettings {
+    /// The endpoint of the collector, e.g. `http://localhost:4317`.
+    pub endpoint: Url,
+    /// The service name under which the spans are reported. When omitted, the name of the server is used.
+    pub service_name: Option<String>,
+}
+
+/// Create a layer that exports all spans to the configured collector in batches. This should be called from within a
+/// Tokio runtime, on which the exporter is spawned.
+pub fn otlp_layer<S>(
+    settings: &OtlpSettings,
+    default_service_name: &str,
+) -> Result<OpenTelemetryLayer<S, Tracer>, TraceError>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    let service_name = settings
+        .service_name
+        .clone()
+        .unwrap_or_else(|| default_service_name.to_string());
+
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .tonic()
+                .with_endpoint(settings.endpoint.as_str()),
+        )
+        .with_trace_config(trace::config().with_resource(Resource::new([KeyValue::new("service.name", service_name)])))
+        .install_batch(runtime::Tokio)?;
+
+    Ok(tracing_opentelemetry::layer().with_tracer(tracer))
+}
+
+/// Install the global subscriber, which outputs logs filtered by the `RUST_LOG` environment variable (at INFO level by
+/// default) and, when configured, exports spans over OTLP.
+pub fn init_tracing(otlp: Option<&OtlpSettings>, default_service_name: &str) -> Result<(), TelemetryError> {
+    let filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::INFO.into())
+        .from_env_lossy();
+    let otlp_layer = otlp
+        .map(|settings| otlp_layer(settings, default_service_name))
+        .transpose()?;
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(tracing_subscriber::fmt::layer())
+        .with(otlp_layer)
+        .try_init()?;
+
+    Ok(())
+}
+
+/// Export the spans that have not been exported yet and stop the exporter. This should be called before the server
+/// exits, as spans are exported in batches.
+pub fn shutdown_tracing() {
+    opentelemetry::global::shutdown_tracer_provider();
+}
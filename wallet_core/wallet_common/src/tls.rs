@@ -0,0 +1,133 @@
+use std::{
+    fmt::Display,
+    fs::{self, File},
+    io::BufReader,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use axum_server::tls_rustls::RustlsConfig;
+use rustls::{Certificate, PrivateKey, ServerConfig};
+use serde::Deserialize;
+use tokio::{task::JoinHandle, time};
+use tracing::{info, warn};
+
+/// The files of the TLS configuration are checked for changes every so often.
+const RELOAD_INTERVAL_SECONDS: u64 = 60;
+
+#[derive(Debug, thiserror::Error)]
+pub enum TlsError {
+    #[error("could not read PEM file {0}: {1}")]
+    Io(String, #[source] std::io::Error),
+    #[error("no certificates found in {0}")]
+    NoCertificates(String),
+    #[error("no private key found in {0}")]
+    NoPrivateKey(String),
+    #[error("invalid TLS configuration: {0}")]
+    Rustls(#[from] rustls::Error),
+}
+
+/// Configuration of a server that is served over TLS. The files are checked for changes periodically, so that a
+/// renewed certificate is picked up without restarting the server.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TlsSettings {
+    /// PEM encoded certificate chain of the server, starting with the certificate of the server itself.
+    pub certificate_path: PathBuf,
+    /// PEM encoded PKCS8 private key of the server.
+    pub private_key_path: PathBuf,
+}
+
+pub fn read_certificates(path: &Path) -> Result<Vec<Certificate>, TlsError> {
+    let file = File::open(path).map_err(|e| TlsError::Io(path.display().to_string(), e))?;
+    let certificates = rustls_pemfile::certs(&mut BufReader::new(file))
+        .map_err(|e| TlsError::Io(path.display().to_string(), e))?
+        .into_iter()
+        .map(Certificate)
+        .collect();
+
+    Ok(certificates)
+}
+
+pub fn read_private_key(path: &Path) -> Result<PrivateKey, TlsError> {
+    let file = File::open(path).map_err(|e| TlsError::Io(path.display().to_string(), e))?;
+    let private_key = rustls_pemfile::pkcs8_private_keys(&mut BufReader::new(file))
+        .map_err(|e| TlsError::Io(path.display().to_string(), e))?
+        .into_iter()
+        .next()
+        .ok_or_else(|| TlsError::NoPrivateKey(path.display().to_string()))?;
+
+    Ok(PrivateKey(private_key))
+}
+
+/// Create the configuration of a server that does not authenticate its clients from the configured files.
+pub fn server_config(settings: &TlsSettings) -> Result<ServerConfig, TlsError> {
+    let certificates = read_certificates(&settings.certificate_path)?;
+    if certificates.is_empty() {
+        return Err(TlsError::NoCertificates(settings.certificate_path.display().to_string()));
+    }
+
+    let mut config = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(certificates, read_private_key(&settings.private_key_path)?)?;
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+
+    Ok(config)
+}
+
+/// Create the TLS configuration of a server, together with the handle of the task that reloads it when the files
+/// change.
+pub fn tls_config(settings: &TlsSettings) -> Result<(RustlsConfig, JoinHandle<()>), TlsError> {
+    let config = RustlsConfig::from_config(Arc::new(server_config(settings)?));
+
+    let settings = settings.clone();
+    let reload_task = start_reload_task(
+        config.clone(),
+        vec![settings.certificate_path.clone(), settings.private_key_path.clone()],
+        move || server_config(&settings),
+    );
+
+    Ok((config, reload_task))
+}
+
+fn modification_times(paths: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    paths
+        .iter()
+        .map(|path| fs::metadata(path).and_then(|metadata| metadata.modified()).ok())
+        .collect()
+}
+
+/// Periodically check whether any of the `paths` was modified and if so, replace the server configuration in `config`
+/// by the one created by `build`. New connections use the new configuration, while existing connections are left
+/// untouched. When the new configuration cannot be created, e.g. because the certificate was replaced but the private
+/// key not yet, the current configuration is kept and creating it is retried at the next check.
+pub fn start_reload_task<F, E>(config: RustlsConfig, paths: Vec<PathBuf>, build: F) -> JoinHandle<()>
+where
+    F: Fn() -> Result<ServerConfig, E> + Send + 'static,
+    E: Display,
+{
+    let mut interval = time::interval(Duration::from_secs(RELOAD_INTERVAL_SECONDS));
+    let mut loaded_modification_times = modification_times(&paths);
+
+    tokio::spawn(async move {
+        loop {
+            interval.tick().await;
+
+            let current_modification_times = modification_times(&paths);
+            if current_modification_times == loaded_modification_times {
+                continue;
+            }
+
+            match build() {
+                Ok(server_config) => {
+                    config.reload_from_config(Arc::new(server_config));
+                    loaded_modification_times = current_modification_times;
+
+                    info!("reloaded TLS configuration");
+                }
+                Err(error) => warn!("could not reload TLS configuration, keeping the current one: {}", error),
+            }
+        }
+    })
+}
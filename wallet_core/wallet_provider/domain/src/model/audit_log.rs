@@ -0,0 +1,84 @@
+use std::fmt::{Display, Formatter};
+
+use chrono::{DateTime, Local};
+use serde::Serialize;
+
+/// The events of a wallet that are recorded in the audit log of the Wallet Provider. An instruction event contains
+/// the endpoint name of the instruction that was handled.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "type", content = "instruction")]
+pub enum AuditLogEventType {
+    Registration,
+    Instruction(String),
+    PinFailure,
+    Blocked,
+    Revocation,
+    Suspended,
+    Unsuspended,
+    KeyEscrowConsentGiven,
+    KeyEscrowConsentWithdrawn,
+    KeyEscrowRecovery,
+}
+
+/// An entry of the audit log. Entries are only ever added, never updated or removed, and are retained after the wallet
+/// user they refer to has been deleted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AuditLogEvent {
+    pub wallet_id: String,
+    #[serde(flatten)]
+    pub event_type: AuditLogEventType,
+    pub date_time: DateTime<Local>,
+}
+
+impl AuditLogEventType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Registration => "registration",
+            Self::Instruction(_) => "instruction",
+            Self::PinFailure => "pin_failure",
+            Self::Blocked => "blocked",
+            Self::Revocation => "revocation",
+            Self::Suspended => "suspended",
+            Self::Unsuspended => "unsuspended",
+            Self::KeyEscrowConsentGiven => "key_escrow_consent_given",
+            Self::KeyEscrowConsentWithdrawn => "key_escrow_consent_withdrawn",
+            Self::KeyEscrowRecovery => "key_escrow_recovery",
+        }
+    }
+
+    pub fn instruction(&self) -> Option<&str> {
+        match self {
+            Self::Instruction(instruction) => Some(instruction),
+            _ => None,
+        }
+    }
+
+    /// Reconstruct the event type from its name and optional instruction, as returned by [`Self::name`] and
+    /// [`Self::instruction`]. Returns `None` if this combination is not valid.
+    pub fn from_parts(name: &str, instruction: Option<String>) -> Option<Self> {
+        let event_type = match (name, instruction) {
+            ("registration", None) => Self::Registration,
+            ("instruction", Some(instruction)) => Self::Instruction(instruction),
+            ("pin_failure", None) => Self::PinFailure,
+            ("blocked", None) => Self::Blocked,
+            ("revocation", None) => Self::Revocation,
+            ("suspended", None) => Self::Suspended,
+            ("unsuspended", None) => Self::Unsuspended,
+            ("key_escrow_consent_given", None) => Self::KeyEscrowConsentGiven,
+            ("key_escrow_consent_withdrawn", None) => Self::KeyEscrowConsentWithdrawn,
+            ("key_escrow_recovery", None) => Self::KeyEscrowRecovery,
+            _ => return None,
+        };
+
+        Some(event_type)
+    }
+}
+
+impl Display for AuditLogEventType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Instruction(instruction) => write!(f, "{} ({})", self.name(), instruction),
+            _ => write!(f, "{}", self.name()),
+        }
+    }
+}
@@ -1,7 +1,9 @@
+pub mod audit_log;
 pub mod encrypted;
 pub mod encrypter;
 pub mod hsm;
 pub mod pin_policy;
+pub mod revocation;
 pub mod wallet_user;
 pub mod wrapped_key;
 
@@ -0,0 +1,12 @@
+use chrono::{DateTime, Local};
+
+pub use wallet_common::account::messages::auth::CertificateRevocationReason;
+
+/// A wallet of which the certificates have been revoked, which is published on the revocation list. Revocations are
+/// retained after the wallet user they refer to has been deleted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RevokedWalletCertificate {
+    pub wallet_id: String,
+    pub reason: CertificateRevocationReason,
+    pub date_time: DateTime<Local>,
+}
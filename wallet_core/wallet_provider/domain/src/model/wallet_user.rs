@@ -20,6 +20,8 @@ pub struct WalletUser {
     pub last_unsuccessful_pin_entry: Option<DateTime<Local>>,
     pub instruction_challenge: Option<InstructionChallenge>,
     pub instruction_sequence_number: u64,
+    /// The time at which the user consented to escrow of the private keys of the wallet, if consent is given.
+    pub key_escrow_consent: Option<DateTime<Local>>,
 }
 
 impl Debug for WalletUser {
@@ -39,6 +41,7 @@ pub enum WalletUserQueryResult {
     Found(Box<WalletUser>),
     NotFound,
     Blocked,
+    Suspended,
 }
 
 pub struct WalletUserCreate {
@@ -94,6 +97,7 @@ SssTb0eI53lvfdvG/xkNcktwsXEIPL1y3lUKn1u1ZhFTnQn4QKmnvaN4uQ==
             last_unsuccessful_pin_entry: None,
             instruction_challenge: None,
             instruction_sequence_number: 0,
+            key_escrow_consent: None,
         }
     }
 }
@@ -0,0 +1,21 @@
+use chrono::{DateTime, Local};
+
+use crate::model::audit_log::AuditLogEvent;
+
+use super::{errors::PersistenceError, transaction::Committable};
+
+type Result<T> = std::result::Result<T, PersistenceError>;
+
+pub trait AuditLogRepository {
+    type TransactionType: Committable;
+
+    async fn insert_audit_log_event(&self, transaction: &Self::TransactionType, event: AuditLogEvent) -> Result<()>;
+
+    /// Find the audit log events of a wallet, optionally only those since the provided time, ordered by time.
+    async fn find_audit_log_events(
+        &self,
+        transaction: &Self::TransactionType,
+        wallet_id: &str,
+        since: Option<DateTime<Local>>,
+    ) -> Result<Vec<AuditLogEvent>>;
+}
@@ -10,6 +10,8 @@ pub enum PersistenceError {
     Execution(#[source] Box<dyn Error + Send + Sync>),
     #[error("not found: {0}")]
     NotFound(String),
+    #[error("instruction sequence number {0} is not larger than the stored sequence number")]
+    SequenceNumberConflict(u64),
     #[error("verifying key conversion error: {0}")]
     VerifyingKeyConversion(#[from] p256::pkcs8::spki::Error),
     #[error("signing key conversion error: {0}")]
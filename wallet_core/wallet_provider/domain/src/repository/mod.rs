@@ -1,8 +1,10 @@
+mod audit_log_repository;
 mod errors;
 mod transaction;
 mod wallet_user_repository;
 
 pub use self::{
+    audit_log_repository::AuditLogRepository,
     errors::PersistenceError,
     transaction::{Committable, TransactionStarter},
     wallet_user_repository::WalletUserRepository,
@@ -2,6 +2,8 @@ use chrono::{DateTime, Local};
 use std::collections::HashMap;
 
 use crate::model::{
+    encrypted::Encrypted,
+    revocation::RevokedWalletCertificate,
     wallet_user::{InstructionChallenge, WalletUserCreate, WalletUserKeys, WalletUserQueryResult},
     wrapped_key::WrappedKey,
 };
@@ -21,8 +23,16 @@ pub trait WalletUserRepository {
         wallet_id: &str,
     ) -> Result<WalletUserQueryResult>;
 
+    async fn delete_wallet_user(&self, transaction: &Self::TransactionType, wallet_id: &str) -> Result<()>;
+
     async fn clear_instruction_challenge(&self, transaction: &Self::TransactionType, wallet_id: &str) -> Result<()>;
 
+    async fn delete_expired_instruction_challenges(
+        &self,
+        transaction: &Self::TransactionType,
+        time: DateTime<Local>,
+    ) -> Result<u64>;
+
     async fn update_instruction_challenge_and_sequence_number(
         &self,
         transaction: &Self::TransactionType,
@@ -48,14 +58,77 @@ pub trait WalletUserRepository {
 
     async fn reset_unsuccessful_pin_entries(&self, transaction: &Self::TransactionType, wallet_id: &str) -> Result<()>;
 
+    async fn update_wallet_user_suspended(
+        &self,
+        transaction: &Self::TransactionType,
+        wallet_id: &str,
+        is_suspended: bool,
+    ) -> Result<()>;
+
+    async fn update_key_escrow_consent(
+        &self,
+        transaction: &Self::TransactionType,
+        wallet_id: &str,
+        key_escrow_consent: Option<DateTime<Local>>,
+    ) -> Result<()>;
+
     async fn save_keys(&self, transaction: &Self::TransactionType, keys: WalletUserKeys) -> Result<()>;
 
+    async fn find_all_keys(
+        &self,
+        transaction: &Self::TransactionType,
+        wallet_user_id: uuid::Uuid,
+    ) -> Result<HashMap<String, WrappedKey>>;
+
     async fn find_keys_by_identifiers(
         &self,
         transaction: &Self::TransactionType,
         wallet_user_id: uuid::Uuid,
         key_identifiers: &[String],
     ) -> Result<HashMap<String, WrappedKey>>;
+
+    async fn find_key_signature_counts(
+        &self,
+        transaction: &Self::TransactionType,
+        wallet_user_id: uuid::Uuid,
+    ) -> Result<HashMap<String, u64>>;
+
+    async fn increment_key_signature_counts(
+        &self,
+        transaction: &Self::TransactionType,
+        wallet_user_id: uuid::Uuid,
+        counts: HashMap<String, u64>,
+    ) -> Result<()>;
+
+    async fn save_escrowed_keys(
+        &self,
+        transaction: &Self::TransactionType,
+        wallet_user_id: uuid::Uuid,
+        escrowed_keys: HashMap<String, Encrypted<WrappedKey>>,
+    ) -> Result<()>;
+
+    async fn delete_escrowed_keys(&self, transaction: &Self::TransactionType, wallet_user_id: uuid::Uuid)
+        -> Result<()>;
+
+    /// Find the escrowed keys of a wallet, which may be blocked or suspended.
+    async fn find_escrowed_keys(
+        &self,
+        transaction: &Self::TransactionType,
+        wallet_id: &str,
+    ) -> Result<HashMap<String, Encrypted<WrappedKey>>>;
+
+    /// Record that the certificates of a wallet are revoked. Recording a wallet that was already revoked has no effect.
+    async fn insert_revoked_wallet_certificate(
+        &self,
+        transaction: &Self::TransactionType,
+        revoked: RevokedWalletCertificate,
+    ) -> Result<()>;
+
+    /// Find all revoked wallet certificates, ordered by time of revocation.
+    async fn find_revoked_wallet_certificates(
+        &self,
+        transaction: &Self::TransactionType,
+    ) -> Result<Vec<RevokedWalletCertificate>>;
 }
 
 #[cfg(feature = "mock")]
@@ -89,6 +162,10 @@ pub mod mock {
             )))
         }
 
+        async fn delete_wallet_user(&self, _transaction: &Self::TransactionType, _wallet_id: &str) -> Result<()> {
+            Ok(())
+        }
+
         async fn update_instruction_challenge_and_sequence_number(
             &self,
             _transaction: &Self::TransactionType,
@@ -116,6 +193,14 @@ pub mod mock {
             Ok(())
         }
 
+        async fn delete_expired_instruction_challenges(
+            &self,
+            _transaction: &Self::TransactionType,
+            _time: DateTime<Local>,
+        ) -> Result<u64> {
+            Ok(0)
+        }
+
         async fn register_unsuccessful_pin_entry(
             &self,
             _transaction: &Self::TransactionType,
@@ -134,10 +219,36 @@ pub mod mock {
             Ok(())
         }
 
+        async fn update_wallet_user_suspended(
+            &self,
+            _transaction: &Self::TransactionType,
+            _wallet_id: &str,
+            _is_suspended: bool,
+        ) -> Result<()> {
+            Ok(())
+        }
+
+        async fn update_key_escrow_consent(
+            &self,
+            _transaction: &Self::TransactionType,
+            _wallet_id: &str,
+            _key_escrow_consent: Option<DateTime<Local>>,
+        ) -> Result<()> {
+            Ok(())
+        }
+
         async fn save_keys(&self, _transaction: &Self::TransactionType, _keys: WalletUserKeys) -> Result<()> {
             Ok(())
         }
 
+        async fn find_all_keys(
+            &self,
+            _transaction: &Self::TransactionType,
+            _wallet_user_id: Uuid,
+        ) -> Result<HashMap<String, WrappedKey>> {
+            Ok(HashMap::new())
+        }
+
         async fn find_keys_by_identifiers(
             &self,
             _transaction: &Self::TransactionType,
@@ -146,5 +257,62 @@ pub mod mock {
         ) -> Result<HashMap<String, WrappedKey>> {
             Ok(HashMap::new())
         }
+
+        async fn find_key_signature_counts(
+            &self,
+            _transaction: &Self::TransactionType,
+            _wallet_user_id: Uuid,
+        ) -> Result<HashMap<String, u64>> {
+            Ok(HashMap::new())
+        }
+
+        async fn increment_key_signature_counts(
+            &self,
+            _transaction: &Self::TransactionType,
+            _wallet_user_id: Uuid,
+            _counts: HashMap<String, u64>,
+        ) -> Result<()> {
+            Ok(())
+        }
+
+        async fn save_escrowed_keys(
+            &self,
+            _transaction: &Self::TransactionType,
+            _wallet_user_id: Uuid,
+            _escrowed_keys: HashMap<String, Encrypted<WrappedKey>>,
+        ) -> Result<()> {
+            Ok(())
+        }
+
+        async fn delete_escrowed_keys(
+            &self,
+            _transaction: &Self::TransactionType,
+            _wallet_user_id: Uuid,
+        ) -> Result<()> {
+            Ok(())
+        }
+
+        async fn find_escrowed_keys(
+            &self,
+            _transaction: &Self::TransactionType,
+            _wallet_id: &str,
+        ) -> Result<HashMap<String, Encrypted<WrappedKey>>> {
+            Ok(HashMap::new())
+        }
+
+        async fn insert_revoked_wallet_certificate(
+            &self,
+            _transaction: &Self::TransactionType,
+            _revoked: RevokedWalletCertificate,
+        ) -> Result<()> {
+            Ok(())
+        }
+
+        async fn find_revoked_wallet_certificates(
+            &self,
+            _transaction: &Self::TransactionType,
+        ) -> Result<Vec<RevokedWalletCertificate>> {
+            Ok(vec![])
+        }
     }
 }
@@ -0,0 +1,48 @@
+use async_trait::async_trait;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // The audit log intentionally has no foreign key to the wallet user, as its entries
+        // should be retained after the wallet user has been deleted.
+        manager
+            .create_table(
+                Table::create()
+                    .table(AuditLog::Table)
+                    .col(ColumnDef::new(AuditLog::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(AuditLog::WalletId).string().not_null())
+                    .col(ColumnDef::new(AuditLog::EventType).string().not_null())
+                    .col(ColumnDef::new(AuditLog::Instruction).string())
+                    .col(ColumnDef::new(AuditLog::DateTime).timestamp_with_time_zone().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("ix_audit_log_wallet_id_date_time")
+                    .table(AuditLog::Table)
+                    .col(AuditLog::WalletId)
+                    .col(AuditLog::DateTime)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum AuditLog {
+    Table,
+    Id,
+    WalletId,
+    EventType,
+    Instruction,
+    DateTime,
+}
@@ -0,0 +1,44 @@
+use async_trait::async_trait;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(WalletUser::Table)
+                    .add_column(ColumnDef::new(WalletUser::KeyEscrowConsent).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(WalletUserKey::Table)
+                    .add_column(ColumnDef::new(WalletUserKey::EscrowedPrivateKey).binary())
+                    .add_column(ColumnDef::new(WalletUserKey::EscrowIv).binary())
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum WalletUser {
+    Table,
+    KeyEscrowConsent,
+}
+
+#[derive(Iden)]
+enum WalletUserKey {
+    Table,
+    EscrowedPrivateKey,
+    EscrowIv,
+}
@@ -0,0 +1,55 @@
+use async_trait::async_trait;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Like the audit log, this table intentionally has no foreign key to the wallet user, as revocations
+        // should be published after the wallet user has been deleted.
+        manager
+            .create_table(
+                Table::create()
+                    .table(RevokedWalletCertificate::Table)
+                    .col(
+                        ColumnDef::new(RevokedWalletCertificate::WalletId)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(RevokedWalletCertificate::Tenant).string().not_null())
+                    .col(ColumnDef::new(RevokedWalletCertificate::Reason).string().not_null())
+                    .col(
+                        ColumnDef::new(RevokedWalletCertificate::DateTime)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("ix_revoked_wallet_certificate_tenant_date_time")
+                    .table(RevokedWalletCertificate::Table)
+                    .col(RevokedWalletCertificate::Tenant)
+                    .col(RevokedWalletCertificate::DateTime)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum RevokedWalletCertificate {
+    Table,
+    WalletId,
+    Tenant,
+    Reason,
+    DateTime,
+}
@@ -7,6 +7,13 @@ use wallet_provider_database_settings::Settings;
 mod m20230616_000001_create_wallet_user_table;
 mod m20230908_000001_create_wallet_user_key_table;
 mod m20230926_000001_create_wallet_user_challenge_instruction;
+mod m20240115_000001_create_audit_log_table;
+mod m20240122_000001_add_wallet_user_is_suspended;
+mod m20240129_000001_add_wallet_user_key_signature_count;
+mod m20240205_000001_add_key_escrow;
+mod m20240212_000001_add_wallet_user_tenant;
+mod m20240219_000001_create_revoked_wallet_certificate_table;
+mod m20240226_000001_add_audit_log_tenant;
 
 pub struct Migrator;
 
@@ -16,6 +23,13 @@ impl MigratorTrait for Migrator {
             Box::new(m20230616_000001_create_wallet_user_table::Migration),
             Box::new(m20230908_000001_create_wallet_user_key_table::Migration),
             Box::new(m20230926_000001_create_wallet_user_challenge_instruction::Migration),
+            Box::new(m20240115_000001_create_audit_log_table::Migration),
+            Box::new(m20240122_000001_add_wallet_user_is_suspended::Migration),
+            Box::new(m20240129_000001_add_wallet_user_key_signature_count::Migration),
+            Box::new(m20240205_000001_add_key_escrow::Migration),
+            Box::new(m20240212_000001_add_wallet_user_tenant::Migration),
+            Box::new(m20240219_000001_create_revoked_wallet_certificate_table::Migration),
+            Box::new(m20240226_000001_add_audit_log_tenant::Migration),
         ]
     }
 }
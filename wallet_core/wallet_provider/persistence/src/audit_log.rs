@@ -0,0 +1,72 @@
+use chrono::{DateTime, Local};
+use sea_orm::{ActiveModelTrait, ActiveValue::Set, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder};
+use uuid::Uuid;
+
+use wallet_provider_domain::{
+    model::audit_log::{AuditLogEvent, AuditLogEventType},
+    repository::PersistenceError,
+};
+
+use crate::{entity::audit_log, PersistenceConnection};
+
+type Result<T> = std::result::Result<T, PersistenceError>;
+
+pub async fn insert_audit_log_event<S, T>(db: &T, tenant: &str, event: AuditLogEvent) -> Result<()>
+where
+    S: ConnectionTrait,
+    T: PersistenceConnection<S>,
+{
+    audit_log::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        tenant: Set(tenant.to_string()),
+        wallet_id: Set(event.wallet_id),
+        event_type: Set(event.event_type.name().to_string()),
+        instruction: Set(event.event_type.instruction().map(str::to_string)),
+        date_time: Set(event.date_time.into()),
+    }
+    .insert(db.connection())
+    .await
+    .map(|_| ())
+    .map_err(|e| PersistenceError::Execution(e.into()))
+}
+
+/// Find the audit log events of the wallet with the wallet identifier within the tenant, optionally only those that
+/// occurred since the provided time.
+pub async fn find_audit_log_events<S, T>(
+    db: &T,
+    tenant: &str,
+    wallet_id: &str,
+    since: Option<DateTime<Local>>,
+) -> Result<Vec<AuditLogEvent>>
+where
+    S: ConnectionTrait,
+    T: PersistenceConnection<S>,
+{
+    let mut query = audit_log::Entity::find()
+        .filter(audit_log::Column::Tenant.eq(tenant))
+        .filter(audit_log::Column::WalletId.eq(wallet_id));
+    if let Some(since) = since {
+        query = query.filter(audit_log::Column::DateTime.gte(since));
+    }
+
+    let models = query
+        .order_by_asc(audit_log::Column::DateTime)
+        .all(db.connection())
+        .await
+        .map_err(|e| PersistenceError::Execution(e.into()))?;
+
+    models
+        .into_iter()
+        .map(|model| {
+            let event_type = AuditLogEventType::from_parts(&model.event_type, model.instruction).ok_or_else(|| {
+                PersistenceError::Execution(format!("invalid audit log event type: {}", model.event_type).into())
+            })?;
+
+            Ok(AuditLogEvent {
+                wallet_id: model.wallet_id,
+                event_type,
+                date_time: model.date_time.into(),
+            })
+        })
+        .collect()
+}
@@ -10,6 +10,7 @@ use crate::PersistenceConnection;
 
 const DB_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
 
+#[derive(Clone)]
 pub struct Db(DatabaseConnection);
 
 impl Db {
@@ -2,6 +2,8 @@
 
 pub mod prelude;
 
+pub mod audit_log;
+pub mod revoked_wallet_certificate;
 pub mod wallet_user;
 pub mod wallet_user_instruction_challenge;
 pub mod wallet_user_key;
@@ -1,5 +1,7 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.3
 
+pub use super::audit_log::Entity as AuditLog;
+pub use super::revoked_wallet_certificate::Entity as RevokedWalletCertificate;
 pub use super::wallet_user::Entity as WalletUser;
 pub use super::wallet_user_instruction_challenge::Entity as WalletUserInstructionChallenge;
 pub use super::wallet_user_key::Entity as WalletUserKey;
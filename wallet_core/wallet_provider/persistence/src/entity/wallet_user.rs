@@ -19,6 +19,9 @@ pub struct Model {
     pub pin_entries: i16,
     pub last_unsuccessful_pin: Option<DateTimeWithTimeZone>,
     pub is_blocked: bool,
+    pub is_suspended: bool,
+    pub key_escrow_consent: Option<DateTimeWithTimeZone>,
+    pub tenant: String,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -11,6 +11,11 @@ pub struct Model {
     pub identifier: String,
     #[sea_orm(column_type = "Binary(BlobSize::Blob(None))")]
     pub encrypted_private_key: Vec<u8>,
+    pub signature_count: i64,
+    #[sea_orm(column_type = "Binary(BlobSize::Blob(None))", nullable)]
+    pub escrowed_private_key: Option<Vec<u8>>,
+    #[sea_orm(column_type = "Binary(BlobSize::Blob(None))", nullable)]
+    pub escrow_iv: Option<Vec<u8>>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
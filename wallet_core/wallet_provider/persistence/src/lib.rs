@@ -1,10 +1,16 @@
+pub mod audit_log;
 pub mod database;
 pub mod entity;
 pub mod repositories;
+pub mod revocation;
 pub mod transaction;
 pub mod wallet_user;
 pub mod wallet_user_key;
 
+/// The tenant of wallet users that were created before tenants were introduced and of the tenant that is configured
+/// in the root of the Wallet Provider settings.
+pub const DEFAULT_TENANT: &str = "default";
+
 pub trait PersistenceConnection<T> {
     fn connection(&self) -> &T;
 }
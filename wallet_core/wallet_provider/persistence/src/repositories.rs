@@ -1,53 +1,90 @@
 use std::collections::HashMap;
 
 use chrono::{DateTime, Local};
+use tracing::instrument;
 use uuid::{self, Uuid};
 
 use wallet_provider_domain::{
     model::{
+        audit_log::AuditLogEvent,
+        encrypted::Encrypted,
+        revocation::RevokedWalletCertificate,
         wallet_user::{InstructionChallenge, WalletUserCreate, WalletUserKeys, WalletUserQueryResult},
         wrapped_key::WrappedKey,
     },
-    repository::{PersistenceError, TransactionStarter, WalletUserRepository},
+    repository::{AuditLogRepository, PersistenceError, TransactionStarter, WalletUserRepository},
 };
 
-use crate::{database::Db, transaction, transaction::Transaction, wallet_user, wallet_user_key};
+use crate::{
+    audit_log, database::Db, revocation, transaction, transaction::Transaction, wallet_user, wallet_user_key,
+    DEFAULT_TENANT,
+};
 
-pub struct Repositories(Db);
+/// The repositories of a single tenant. Wallet users are created within, and only found within, this tenant.
+pub struct Repositories {
+    db: Db,
+    tenant: String,
+}
 
 impl Repositories {
     pub fn new(db: Db) -> Self {
-        Self(db)
+        Self::new_for_tenant(db, DEFAULT_TENANT.to_string())
+    }
+
+    pub fn new_for_tenant(db: Db, tenant: String) -> Self {
+        Self { db, tenant }
+    }
+
+    /// The repositories of another tenant, sharing the database connection pool of these repositories.
+    pub fn for_tenant(&self, tenant: String) -> Self {
+        Self::new_for_tenant(self.db.clone(), tenant)
+    }
+
+    pub fn tenant(&self) -> &str {
+        &self.tenant
     }
 }
 
 impl TransactionStarter for Repositories {
     type TransactionType = Transaction;
 
+    #[instrument(skip_all)]
     async fn begin_transaction(&self) -> Result<Self::TransactionType, PersistenceError> {
-        transaction::begin_transaction(&self.0).await
+        transaction::begin_transaction(&self.db).await
     }
 }
 
 impl WalletUserRepository for Repositories {
     type TransactionType = Transaction;
 
+    #[instrument(skip_all)]
     async fn create_wallet_user(
         &self,
         transaction: &Self::TransactionType,
         user: WalletUserCreate,
     ) -> Result<(), PersistenceError> {
-        wallet_user::create_wallet_user(transaction, user).await
+        wallet_user::create_wallet_user(transaction, &self.tenant, user).await
     }
 
+    #[instrument(skip_all)]
     async fn find_wallet_user_by_wallet_id(
         &self,
         transaction: &Self::TransactionType,
         wallet_id: &str,
     ) -> Result<WalletUserQueryResult, PersistenceError> {
-        wallet_user::find_wallet_user_by_wallet_id(transaction, wallet_id).await
+        wallet_user::find_wallet_user_by_wallet_id(transaction, &self.tenant, wallet_id).await
     }
 
+    #[instrument(skip_all)]
+    async fn delete_wallet_user(
+        &self,
+        transaction: &Self::TransactionType,
+        wallet_id: &str,
+    ) -> Result<(), PersistenceError> {
+        wallet_user::delete_wallet_user(transaction, &self.tenant, wallet_id).await
+    }
+
+    #[instrument(skip_all)]
     async fn clear_instruction_challenge(
         &self,
         transaction: &Self::TransactionType,
@@ -56,6 +93,16 @@ impl WalletUserRepository for Repositories {
         wallet_user::clear_instruction_challenge(transaction, wallet_id).await
     }
 
+    #[instrument(skip_all)]
+    async fn delete_expired_instruction_challenges(
+        &self,
+        transaction: &Self::TransactionType,
+        time: DateTime<Local>,
+    ) -> Result<u64, PersistenceError> {
+        wallet_user::delete_expired_instruction_challenges(transaction, time).await
+    }
+
+    #[instrument(skip_all)]
     async fn update_instruction_sequence_number(
         &self,
         transaction: &Self::TransactionType,
@@ -65,6 +112,7 @@ impl WalletUserRepository for Repositories {
         wallet_user::update_instruction_sequence_number(transaction, wallet_id, instruction_sequence_number).await
     }
 
+    #[instrument(skip_all)]
     async fn update_instruction_challenge_and_sequence_number(
         &self,
         transaction: &Self::TransactionType,
@@ -81,6 +129,7 @@ impl WalletUserRepository for Repositories {
         .await
     }
 
+    #[instrument(skip_all)]
     async fn register_unsuccessful_pin_entry(
         &self,
         transaction: &Self::TransactionType,
@@ -91,6 +140,7 @@ impl WalletUserRepository for Repositories {
         wallet_user::register_unsuccessful_pin_entry(transaction, wallet_id, is_blocked, datetime).await
     }
 
+    #[instrument(skip_all)]
     async fn reset_unsuccessful_pin_entries(
         &self,
         transaction: &Self::TransactionType,
@@ -99,6 +149,27 @@ impl WalletUserRepository for Repositories {
         wallet_user::reset_unsuccessful_pin_entries(transaction, wallet_id).await
     }
 
+    #[instrument(skip_all)]
+    async fn update_wallet_user_suspended(
+        &self,
+        transaction: &Self::TransactionType,
+        wallet_id: &str,
+        is_suspended: bool,
+    ) -> Result<(), PersistenceError> {
+        wallet_user::update_wallet_user_suspended(transaction, &self.tenant, wallet_id, is_suspended).await
+    }
+
+    #[instrument(skip_all)]
+    async fn update_key_escrow_consent(
+        &self,
+        transaction: &Self::TransactionType,
+        wallet_id: &str,
+        key_escrow_consent: Option<DateTime<Local>>,
+    ) -> Result<(), PersistenceError> {
+        wallet_user::update_key_escrow_consent(transaction, wallet_id, key_escrow_consent).await
+    }
+
+    #[instrument(skip_all)]
     async fn save_keys(
         &self,
         transaction: &Self::TransactionType,
@@ -107,6 +178,7 @@ impl WalletUserRepository for Repositories {
         wallet_user_key::create_keys(transaction, keys).await
     }
 
+    #[instrument(skip_all)]
     async fn find_keys_by_identifiers(
         &self,
         transaction: &Self::TransactionType,
@@ -115,6 +187,102 @@ impl WalletUserRepository for Repositories {
     ) -> Result<HashMap<String, WrappedKey>, PersistenceError> {
         wallet_user_key::find_keys_by_identifiers(transaction, wallet_user_id, key_identifiers).await
     }
+
+    #[instrument(skip_all)]
+    async fn find_all_keys(
+        &self,
+        transaction: &Self::TransactionType,
+        wallet_user_id: Uuid,
+    ) -> Result<HashMap<String, WrappedKey>, PersistenceError> {
+        wallet_user_key::find_all_keys(transaction, wallet_user_id).await
+    }
+
+    #[instrument(skip_all)]
+    async fn find_key_signature_counts(
+        &self,
+        transaction: &Self::TransactionType,
+        wallet_user_id: Uuid,
+    ) -> Result<HashMap<String, u64>, PersistenceError> {
+        wallet_user_key::find_key_signature_counts(transaction, wallet_user_id).await
+    }
+
+    #[instrument(skip_all)]
+    async fn increment_key_signature_counts(
+        &self,
+        transaction: &Self::TransactionType,
+        wallet_user_id: Uuid,
+        counts: HashMap<String, u64>,
+    ) -> Result<(), PersistenceError> {
+        wallet_user_key::increment_key_signature_counts(transaction, wallet_user_id, counts).await
+    }
+
+    #[instrument(skip_all)]
+    async fn save_escrowed_keys(
+        &self,
+        transaction: &Self::TransactionType,
+        wallet_user_id: Uuid,
+        escrowed_keys: HashMap<String, Encrypted<WrappedKey>>,
+    ) -> Result<(), PersistenceError> {
+        wallet_user_key::save_escrowed_keys(transaction, wallet_user_id, escrowed_keys).await
+    }
+
+    #[instrument(skip_all)]
+    async fn delete_escrowed_keys(
+        &self,
+        transaction: &Self::TransactionType,
+        wallet_user_id: Uuid,
+    ) -> Result<(), PersistenceError> {
+        wallet_user_key::delete_escrowed_keys(transaction, wallet_user_id).await
+    }
+
+    #[instrument(skip_all)]
+    async fn find_escrowed_keys(
+        &self,
+        transaction: &Self::TransactionType,
+        wallet_id: &str,
+    ) -> Result<HashMap<String, Encrypted<WrappedKey>>, PersistenceError> {
+        wallet_user_key::find_escrowed_keys(transaction, &self.tenant, wallet_id).await
+    }
+
+    #[instrument(skip_all)]
+    async fn insert_revoked_wallet_certificate(
+        &self,
+        transaction: &Self::TransactionType,
+        revoked: RevokedWalletCertificate,
+    ) -> Result<(), PersistenceError> {
+        revocation::insert_revoked_wallet_certificate(transaction, &self.tenant, revoked).await
+    }
+
+    #[instrument(skip_all)]
+    async fn find_revoked_wallet_certificates(
+        &self,
+        transaction: &Self::TransactionType,
+    ) -> Result<Vec<RevokedWalletCertificate>, PersistenceError> {
+        revocation::find_revoked_wallet_certificates(transaction, &self.tenant).await
+    }
+}
+
+impl AuditLogRepository for Repositories {
+    type TransactionType = Transaction;
+
+    #[instrument(skip_all)]
+    async fn insert_audit_log_event(
+        &self,
+        transaction: &Self::TransactionType,
+        event: AuditLogEvent,
+    ) -> Result<(), PersistenceError> {
+        audit_log::insert_audit_log_event(transaction, &self.tenant, event).await
+    }
+
+    #[instrument(skip_all)]
+    async fn find_audit_log_events(
+        &self,
+        transaction: &Self::TransactionType,
+        wallet_id: &str,
+        since: Option<DateTime<Local>>,
+    ) -> Result<Vec<AuditLogEvent>, PersistenceError> {
+        audit_log::find_audit_log_events(transaction, &self.tenant, wallet_id, since).await
+    }
 }
 
 #[cfg(feature = "mock")]
@@ -126,10 +294,13 @@ pub mod mock {
 
     use wallet_provider_domain::{
         model::{
+            audit_log::AuditLogEvent,
+            encrypted::Encrypted,
+            revocation::RevokedWalletCertificate,
             wallet_user::{InstructionChallenge, WalletUserCreate, WalletUserKeys, WalletUserQueryResult},
             wrapped_key::WrappedKey,
         },
-        repository::{MockTransaction, PersistenceError, TransactionStarter, WalletUserRepository},
+        repository::{AuditLogRepository, MockTransaction, PersistenceError, TransactionStarter, WalletUserRepository},
     };
 
     mockall::mock! {
@@ -150,6 +321,12 @@ pub mod mock {
                 wallet_id: &str,
             ) -> Result<WalletUserQueryResult, PersistenceError>;
 
+            async fn delete_wallet_user(
+                &self,
+                _transaction: &MockTransaction,
+                wallet_id: &str,
+            ) -> Result<(), PersistenceError>;
+
             async fn register_unsuccessful_pin_entry(
                 &self,
                 _transaction: &MockTransaction,
@@ -164,12 +341,32 @@ pub mod mock {
                 _wallet_id: &str,
             ) -> Result<(), PersistenceError>;
 
+            async fn update_wallet_user_suspended(
+                &self,
+                _transaction: &MockTransaction,
+                _wallet_id: &str,
+                _is_suspended: bool,
+            ) -> Result<(), PersistenceError>;
+
+            async fn update_key_escrow_consent(
+                &self,
+                _transaction: &MockTransaction,
+                _wallet_id: &str,
+                _key_escrow_consent: Option<DateTime<Local>>,
+            ) -> Result<(), PersistenceError>;
+
             async fn clear_instruction_challenge(
                 &self,
                 _transaction: &MockTransaction,
                 _wallet_id: &str,
             ) -> Result<(), PersistenceError>;
 
+            async fn delete_expired_instruction_challenges(
+                &self,
+                _transaction: &MockTransaction,
+                _time: DateTime<Local>,
+            ) -> Result<u64, PersistenceError>;
+
             async fn update_instruction_challenge_and_sequence_number(
                 &self,
                 _transaction: &MockTransaction,
@@ -197,6 +394,72 @@ pub mod mock {
                 wallet_user_id: Uuid,
                 key_identifiers: &[String],
             ) -> Result<HashMap<String, WrappedKey>, PersistenceError>;
+
+            async fn find_all_keys(
+                &self,
+                _transaction: &MockTransaction,
+                wallet_user_id: Uuid,
+            ) -> Result<HashMap<String, WrappedKey>, PersistenceError>;
+
+            async fn find_key_signature_counts(
+                &self,
+                _transaction: &MockTransaction,
+                wallet_user_id: Uuid,
+            ) -> Result<HashMap<String, u64>, PersistenceError>;
+
+            async fn increment_key_signature_counts(
+                &self,
+                _transaction: &MockTransaction,
+                wallet_user_id: Uuid,
+                counts: HashMap<String, u64>,
+            ) -> Result<(), PersistenceError>;
+
+            async fn save_escrowed_keys(
+                &self,
+                _transaction: &MockTransaction,
+                wallet_user_id: Uuid,
+                escrowed_keys: HashMap<String, Encrypted<WrappedKey>>,
+            ) -> Result<(), PersistenceError>;
+
+            async fn delete_escrowed_keys(
+                &self,
+                _transaction: &MockTransaction,
+                wallet_user_id: Uuid,
+            ) -> Result<(), PersistenceError>;
+
+            async fn find_escrowed_keys(
+                &self,
+                _transaction: &MockTransaction,
+                wallet_id: &str,
+            ) -> Result<HashMap<String, Encrypted<WrappedKey>>, PersistenceError>;
+
+            async fn insert_revoked_wallet_certificate(
+                &self,
+                _transaction: &MockTransaction,
+                revoked: RevokedWalletCertificate,
+            ) -> Result<(), PersistenceError>;
+
+            async fn find_revoked_wallet_certificates(
+                &self,
+                _transaction: &MockTransaction,
+            ) -> Result<Vec<RevokedWalletCertificate>, PersistenceError>;
+        }
+
+        impl AuditLogRepository for TransactionalWalletUserRepository {
+            type TransactionType = MockTransaction;
+
+            async fn insert_audit_log_event(
+                &self,
+                _transaction: &MockTransaction,
+                event: AuditLogEvent,
+            ) -> Result<(), PersistenceError>;
+
+            async fn find_audit_log_events(
+                &self,
+                _transaction: &MockTransaction,
+                wallet_id: &str,
+                since: Option<DateTime<Local>>,
+            ) -> Result<Vec<AuditLogEvent>, PersistenceError>;
         }
 
         impl TransactionStarter for TransactionalWalletUserRepository {
@@ -0,0 +1,84 @@
+use sea_orm::{
+    sea_query::OnConflict, ActiveValue::Set, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder,
+};
+
+use wallet_provider_domain::{
+    model::revocation::{CertificateRevocationReason, RevokedWalletCertificate},
+    repository::PersistenceError,
+};
+
+use crate::{entity::revoked_wallet_certificate, PersistenceConnection};
+
+type Result<T> = std::result::Result<T, PersistenceError>;
+
+pub async fn insert_revoked_wallet_certificate<S, T>(
+    db: &T,
+    tenant: &str,
+    revoked: RevokedWalletCertificate,
+) -> Result<()>
+where
+    S: ConnectionTrait,
+    T: PersistenceConnection<S>,
+{
+    let model = revoked_wallet_certificate::ActiveModel {
+        wallet_id: Set(revoked.wallet_id),
+        tenant: Set(tenant.to_string()),
+        reason: Set(reason_name(revoked.reason).to_string()),
+        date_time: Set(revoked.date_time.into()),
+    };
+
+    // A wallet can be blocked before it is reset, in which case the first revocation is retained.
+    revoked_wallet_certificate::Entity::insert(model)
+        .on_conflict(
+            OnConflict::column(revoked_wallet_certificate::Column::WalletId)
+                .do_nothing()
+                .to_owned(),
+        )
+        .exec_without_returning(db.connection())
+        .await
+        .map(|_| ())
+        .map_err(|e| PersistenceError::Execution(e.into()))
+}
+
+pub async fn find_revoked_wallet_certificates<S, T>(db: &T, tenant: &str) -> Result<Vec<RevokedWalletCertificate>>
+where
+    S: ConnectionTrait,
+    T: PersistenceConnection<S>,
+{
+    let models = revoked_wallet_certificate::Entity::find()
+        .filter(revoked_wallet_certificate::Column::Tenant.eq(tenant))
+        .order_by_asc(revoked_wallet_certificate::Column::DateTime)
+        .all(db.connection())
+        .await
+        .map_err(|e| PersistenceError::Execution(e.into()))?;
+
+    models
+        .into_iter()
+        .map(|model| {
+            let reason = reason_from_name(&model.reason).ok_or_else(|| {
+                PersistenceError::Execution(format!("invalid revocation reason: {}", model.reason).into())
+            })?;
+
+            Ok(RevokedWalletCertificate {
+                wallet_id: model.wallet_id,
+                reason,
+                date_time: model.date_time.into(),
+            })
+        })
+        .collect()
+}
+
+fn reason_name(reason: CertificateRevocationReason) -> &'static str {
+    match reason {
+        CertificateRevocationReason::Reset => "reset",
+        CertificateRevocationReason::Blocked => "blocked",
+    }
+}
+
+fn reason_from_name(name: &str) -> Option<CertificateRevocationReason> {
+    match name {
+        "reset" => Some(CertificateRevocationReason::Reset),
+        "blocked" => Some(CertificateRevocationReason::Blocked),
+        _ => None,
+    }
+}
@@ -4,7 +4,7 @@ use p256::{
     pkcs8::{DecodePublicKey, EncodePublicKey},
 };
 use sea_orm::{
-    sea_query::{Expr, IntoIden, OnConflict, Query, SimpleExpr},
+    sea_query::{Expr, OnConflict, Query, SelectStatement, SimpleExpr},
     ActiveModelTrait,
     ActiveValue::Set,
     ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter,
@@ -21,13 +21,13 @@ use wallet_provider_domain::{
 };
 
 use crate::{
-    entity::{wallet_user, wallet_user_instruction_challenge},
+    entity::{wallet_user, wallet_user_instruction_challenge, wallet_user_key},
     PersistenceConnection,
 };
 
 type Result<T> = std::result::Result<T, PersistenceError>;
 
-pub async fn create_wallet_user<S, T>(db: &T, user: WalletUserCreate) -> Result<()>
+pub async fn create_wallet_user<S, T>(db: &T, tenant: &str, user: WalletUserCreate) -> Result<()>
 where
     S: ConnectionTrait,
     T: PersistenceConnection<S>,
@@ -42,6 +42,9 @@ where
         pin_entries: Set(0),
         last_unsuccessful_pin: Set(None),
         is_blocked: Set(false),
+        is_suspended: Set(false),
+        key_escrow_consent: Set(None),
+        tenant: Set(tenant.to_string()),
     }
     .insert(db.connection())
     .await
@@ -49,7 +52,8 @@ where
     .map_err(|e| PersistenceError::Execution(e.into()))
 }
 
-pub async fn find_wallet_user_by_wallet_id<S, T>(db: &T, wallet_id: &str) -> Result<WalletUserQueryResult>
+/// Find the wallet user with the wallet identifier within the tenant. Wallet users of other tenants are not found.
+pub async fn find_wallet_user_by_wallet_id<S, T>(db: &T, tenant: &str, wallet_id: &str) -> Result<WalletUserQueryResult>
 where
     S: ConnectionTrait,
     T: PersistenceConnection<S>,
@@ -57,6 +61,7 @@ where
     let user_challenge = wallet_user::Entity::find()
         .find_also_related(wallet_user_instruction_challenge::Entity)
         .filter(wallet_user::Column::WalletId.eq(wallet_id))
+        .filter(wallet_user::Column::Tenant.eq(tenant))
         .one(db.connection())
         .await
         .map_err(|e| PersistenceError::Execution(e.into()))?;
@@ -65,6 +70,8 @@ where
         .map(|(wallet_user, challenge)| {
             if wallet_user.is_blocked {
                 WalletUserQueryResult::Blocked
+            } else if wallet_user.is_suspended {
+                WalletUserQueryResult::Suspended
             } else {
                 WalletUserQueryResult::Found(Box::new(WalletUser {
                     id: wallet_user.id,
@@ -81,11 +88,53 @@ where
                         expiration_date_time: DateTime::<Local>::from(c.expiration_date_time),
                     }),
                     instruction_sequence_number: u64::try_from(wallet_user.instruction_sequence_number).unwrap(),
+                    key_escrow_consent: wallet_user.key_escrow_consent.map(DateTime::<Local>::from),
                 }))
             }
         })
         .unwrap_or(WalletUserQueryResult::NotFound))
 }
+/// Select the id of the wallet user with the wallet identifier within the tenant, for use in a subquery.
+pub(crate) fn wallet_user_id_in_tenant(tenant: &str, wallet_id: &str) -> SelectStatement {
+    Query::select()
+        .column(wallet_user::Column::Id)
+        .from(wallet_user::Entity)
+        .and_where(Expr::col(wallet_user::Column::WalletId).eq(wallet_id))
+        .and_where(Expr::col(wallet_user::Column::Tenant).eq(tenant))
+        .to_owned()
+}
+
+/// Delete the wallet user with the wallet identifier within the tenant, including its keys and instruction challenge.
+pub async fn delete_wallet_user<S, T>(db: &T, tenant: &str, wallet_id: &str) -> Result<()>
+where
+    S: ConnectionTrait,
+    T: PersistenceConnection<S>,
+{
+    // The keys and instruction challenge refer to the wallet user, so these should be deleted first.
+    wallet_user_key::Entity::delete_many()
+        .filter(wallet_user_key::Column::WalletUserId.in_subquery(wallet_user_id_in_tenant(tenant, wallet_id)))
+        .exec(db.connection())
+        .await
+        .map_err(|e| PersistenceError::Execution(e.into()))?;
+
+    wallet_user_instruction_challenge::Entity::delete_many()
+        .filter(
+            wallet_user_instruction_challenge::Column::WalletUserId
+                .in_subquery(wallet_user_id_in_tenant(tenant, wallet_id)),
+        )
+        .exec(db.connection())
+        .await
+        .map_err(|e| PersistenceError::Execution(e.into()))?;
+
+    wallet_user::Entity::delete_many()
+        .filter(wallet_user::Column::WalletId.eq(wallet_id))
+        .filter(wallet_user::Column::Tenant.eq(tenant))
+        .exec(db.connection())
+        .await
+        .map(|_| ())
+        .map_err(|e| PersistenceError::Execution(e.into()))
+}
+
 pub async fn clear_instruction_challenge<S, T>(db: &T, wallet_id: &str) -> Result<()>
 where
     S: ConnectionTrait,
@@ -113,6 +162,22 @@ where
     Ok(())
 }
 
+/// Delete the instruction challenges of all wallet users that expired before the provided time and return how many
+/// were deleted. Expired challenges are otherwise only removed when the wallet requests a new challenge or sends an
+/// instruction.
+pub async fn delete_expired_instruction_challenges<S, T>(db: &T, time: DateTime<Local>) -> Result<u64>
+where
+    S: ConnectionTrait,
+    T: PersistenceConnection<S>,
+{
+    wallet_user_instruction_challenge::Entity::delete_many()
+        .filter(wallet_user_instruction_challenge::Column::ExpirationDateTime.lt(time))
+        .exec(db.connection())
+        .await
+        .map(|result| result.rows_affected)
+        .map_err(|e| PersistenceError::Execution(e.into()))
+}
+
 pub async fn update_instruction_challenge_and_sequence_number<S, T>(
     db: &T,
     wallet_id: &str,
@@ -173,15 +238,70 @@ where
     S: ConnectionTrait,
     T: PersistenceConnection<S>,
 {
-    update_fields(
-        db,
-        wallet_id,
-        vec![(
+    // Only update the sequence number if it is larger than the stored one, so that two concurrent instructions
+    // cannot both be accepted with the same sequence number and replays are rejected regardless of timing.
+    let result = wallet_user::Entity::update_many()
+        .col_expr(
             wallet_user::Column::InstructionSequenceNumber,
             Expr::value(instruction_sequence_number),
-        )],
-    )
-    .await
+        )
+        .filter(wallet_user::Column::WalletId.eq(wallet_id))
+        .filter(wallet_user::Column::InstructionSequenceNumber.lt(instruction_sequence_number))
+        .exec(db.connection())
+        .await
+        .map_err(|e| PersistenceError::Execution(e.into()))?;
+
+    if result.rows_affected == 0 {
+        return Err(PersistenceError::SequenceNumberConflict(instruction_sequence_number));
+    }
+
+    Ok(())
+}
+
+/// Update the suspension of the wallet user with the wallet identifier within the tenant.
+pub async fn update_wallet_user_suspended<S, T>(db: &T, tenant: &str, wallet_id: &str, is_suspended: bool) -> Result<()>
+where
+    S: ConnectionTrait,
+    T: PersistenceConnection<S>,
+{
+    let result = wallet_user::Entity::update_many()
+        .col_expr(wallet_user::Column::IsSuspended, Expr::value(is_suspended))
+        .filter(wallet_user::Column::WalletId.eq(wallet_id))
+        .filter(wallet_user::Column::Tenant.eq(tenant))
+        .exec(db.connection())
+        .await
+        .map_err(|e| PersistenceError::Execution(e.into()))?;
+
+    if result.rows_affected == 0 {
+        return Err(PersistenceError::NotFound(format!("wallet user {}", wallet_id)));
+    }
+
+    Ok(())
+}
+
+pub async fn update_key_escrow_consent<S, T>(
+    db: &T,
+    wallet_id: &str,
+    key_escrow_consent: Option<DateTime<Local>>,
+) -> Result<()>
+where
+    S: ConnectionTrait,
+    T: PersistenceConnection<S>,
+{
+    let key_escrow_consent: Option<DateTime<Utc>> = key_escrow_consent.map(Into::into);
+
+    let result = wallet_user::Entity::update_many()
+        .col_expr(wallet_user::Column::KeyEscrowConsent, Expr::value(key_escrow_consent))
+        .filter(wallet_user::Column::WalletId.eq(wallet_id))
+        .exec(db.connection())
+        .await
+        .map_err(|e| PersistenceError::Execution(e.into()))?;
+
+    if result.rows_affected == 0 {
+        return Err(PersistenceError::NotFound(format!("wallet user {}", wallet_id)));
+    }
+
+    Ok(())
 }
 
 pub async fn register_unsuccessful_pin_entry<S, T>(
@@ -217,24 +337,6 @@ where
     update_pin_entries(db, wallet_id, Expr::value(0), datetime, false).await
 }
 
-async fn update_fields<S, T, C>(db: &T, wallet_id: &str, col_values: Vec<(C, SimpleExpr)>) -> Result<()>
-where
-    S: ConnectionTrait,
-    T: PersistenceConnection<S>,
-    C: IntoIden,
-{
-    col_values
-        .into_iter()
-        .fold(wallet_user::Entity::update_many(), |stmt, col_value| {
-            stmt.col_expr(col_value.0, col_value.1)
-        })
-        .filter(wallet_user::Column::WalletId.eq(wallet_id))
-        .exec(db.connection())
-        .await
-        .map(|_| ())
-        .map_err(|e| PersistenceError::Execution(e.into()))
-}
-
 async fn update_pin_entries<S, T>(
     db: &T,
     wallet_id: &str,
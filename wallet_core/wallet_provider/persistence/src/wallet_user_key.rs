@@ -1,13 +1,17 @@
 use std::collections::HashMap;
 
-use sea_orm::{ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QuerySelect, Set};
+use sea_orm::{sea_query::Expr, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QuerySelect, Set};
 
 use wallet_provider_domain::{
-    model::{wallet_user::WalletUserKeys, wrapped_key::WrappedKey},
+    model::{
+        encrypted::{Encrypted, InitializationVector},
+        wallet_user::WalletUserKeys,
+        wrapped_key::WrappedKey,
+    },
     repository::PersistenceError,
 };
 
-use crate::{entity::wallet_user_key, PersistenceConnection};
+use crate::{entity::wallet_user_key, wallet_user::wallet_user_id_in_tenant, PersistenceConnection};
 
 type Result<T> = std::result::Result<T, PersistenceError>;
 
@@ -24,6 +28,9 @@ where
             wallet_user_id: Set(create.wallet_user_id),
             identifier: Set(key_create.key_identifier),
             encrypted_private_key: Set(key_create.key.into()),
+            signature_count: Set(0),
+            escrowed_private_key: Set(None),
+            escrow_iv: Set(None),
         })
         .collect::<Vec<_>>();
 
@@ -63,3 +70,161 @@ where
                 .collect()
         })
 }
+
+pub async fn find_all_keys<S, T>(db: &T, wallet_user_id: uuid::Uuid) -> Result<HashMap<String, WrappedKey>>
+where
+    S: ConnectionTrait,
+    T: PersistenceConnection<S>,
+{
+    wallet_user_key::Entity::find()
+        .select_only()
+        .column(wallet_user_key::Column::Identifier)
+        .column(wallet_user_key::Column::EncryptedPrivateKey)
+        .filter(wallet_user_key::Column::WalletUserId.eq(wallet_user_id))
+        .into_tuple::<(String, Vec<u8>)>()
+        .all(db.connection())
+        .await
+        .map_err(|e| PersistenceError::Execution(e.into()))
+        .map(|result| {
+            result
+                .into_iter()
+                .map(|(id, key_data)| (id, WrappedKey::new(key_data)))
+                .collect()
+        })
+}
+
+pub async fn find_key_signature_counts<S, T>(db: &T, wallet_user_id: uuid::Uuid) -> Result<HashMap<String, u64>>
+where
+    S: ConnectionTrait,
+    T: PersistenceConnection<S>,
+{
+    wallet_user_key::Entity::find()
+        .select_only()
+        .column(wallet_user_key::Column::Identifier)
+        .column(wallet_user_key::Column::SignatureCount)
+        .filter(wallet_user_key::Column::WalletUserId.eq(wallet_user_id))
+        .into_tuple::<(String, i64)>()
+        .all(db.connection())
+        .await
+        .map_err(|e| PersistenceError::Execution(e.into()))
+        .map(|result| {
+            result
+                .into_iter()
+                .map(|(id, count)| (id, u64::try_from(count).unwrap_or_default()))
+                .collect()
+        })
+}
+
+pub async fn increment_key_signature_counts<S, T>(
+    db: &T,
+    wallet_user_id: uuid::Uuid,
+    counts: HashMap<String, u64>,
+) -> Result<()>
+where
+    S: ConnectionTrait,
+    T: PersistenceConnection<S>,
+{
+    for (identifier, count) in counts {
+        let count = i64::try_from(count).map_err(|e| PersistenceError::Execution(e.into()))?;
+
+        wallet_user_key::Entity::update_many()
+            .col_expr(
+                wallet_user_key::Column::SignatureCount,
+                Expr::col(wallet_user_key::Column::SignatureCount).add(count),
+            )
+            .filter(
+                wallet_user_key::Column::WalletUserId
+                    .eq(wallet_user_id)
+                    .and(wallet_user_key::Column::Identifier.eq(identifier)),
+            )
+            .exec(db.connection())
+            .await
+            .map_err(|e| PersistenceError::Execution(e.into()))?;
+    }
+
+    Ok(())
+}
+
+/// Store the escrowed copies of the keys of a wallet user, by key identifier. Keys that do not exist are ignored.
+pub async fn save_escrowed_keys<S, T>(
+    db: &T,
+    wallet_user_id: uuid::Uuid,
+    escrowed_keys: HashMap<String, Encrypted<WrappedKey>>,
+) -> Result<()>
+where
+    S: ConnectionTrait,
+    T: PersistenceConnection<S>,
+{
+    for (identifier, escrowed_key) in escrowed_keys {
+        wallet_user_key::Entity::update_many()
+            .col_expr(
+                wallet_user_key::Column::EscrowedPrivateKey,
+                Expr::value(Some(escrowed_key.data)),
+            )
+            .col_expr(wallet_user_key::Column::EscrowIv, Expr::value(Some(escrowed_key.iv.0)))
+            .filter(
+                wallet_user_key::Column::WalletUserId
+                    .eq(wallet_user_id)
+                    .and(wallet_user_key::Column::Identifier.eq(identifier)),
+            )
+            .exec(db.connection())
+            .await
+            .map_err(|e| PersistenceError::Execution(e.into()))?;
+    }
+
+    Ok(())
+}
+
+pub async fn delete_escrowed_keys<S, T>(db: &T, wallet_user_id: uuid::Uuid) -> Result<()>
+where
+    S: ConnectionTrait,
+    T: PersistenceConnection<S>,
+{
+    let no_data: Option<Vec<u8>> = None;
+
+    wallet_user_key::Entity::update_many()
+        .col_expr(
+            wallet_user_key::Column::EscrowedPrivateKey,
+            Expr::value(no_data.clone()),
+        )
+        .col_expr(wallet_user_key::Column::EscrowIv, Expr::value(no_data))
+        .filter(wallet_user_key::Column::WalletUserId.eq(wallet_user_id))
+        .exec(db.connection())
+        .await
+        .map(|_| ())
+        .map_err(|e| PersistenceError::Execution(e.into()))
+}
+
+/// Find the escrowed keys of the wallet with the provided id within the tenant, regardless of whether the wallet is
+/// blocked or suspended, as the device of such a wallet may well have been lost.
+pub async fn find_escrowed_keys<S, T>(
+    db: &T,
+    tenant: &str,
+    wallet_id: &str,
+) -> Result<HashMap<String, Encrypted<WrappedKey>>>
+where
+    S: ConnectionTrait,
+    T: PersistenceConnection<S>,
+{
+    wallet_user_key::Entity::find()
+        .select_only()
+        .column(wallet_user_key::Column::Identifier)
+        .column(wallet_user_key::Column::EscrowedPrivateKey)
+        .column(wallet_user_key::Column::EscrowIv)
+        .filter(
+            wallet_user_key::Column::WalletUserId
+                .in_subquery(wallet_user_id_in_tenant(tenant, wallet_id))
+                .and(wallet_user_key::Column::EscrowedPrivateKey.is_not_null())
+                .and(wallet_user_key::Column::EscrowIv.is_not_null()),
+        )
+        .into_tuple::<(String, Vec<u8>, Vec<u8>)>()
+        .all(db.connection())
+        .await
+        .map_err(|e| PersistenceError::Execution(e.into()))
+        .map(|result| {
+            result
+                .into_iter()
+                .map(|(id, key_data, iv)| (id, Encrypted::new(key_data, InitializationVector(iv))))
+                .collect()
+        })
+}
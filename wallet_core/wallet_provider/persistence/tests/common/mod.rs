@@ -24,7 +24,7 @@ use wallet_provider_persistence::{
     database::Db,
     entity::{wallet_user, wallet_user_instruction_challenge},
     wallet_user::{create_wallet_user, update_instruction_challenge_and_sequence_number},
-    PersistenceConnection,
+    PersistenceConnection, DEFAULT_TENANT,
 };
 
 #[ctor]
@@ -46,12 +46,21 @@ pub async fn db_from_env() -> Result<Db, PersistenceError> {
 enum EncrypterError {}
 
 pub async fn create_wallet_user_with_random_keys<S, T>(db: &T, id: Uuid, wallet_id: String)
+where
+    S: ConnectionTrait,
+    T: PersistenceConnection<S>,
+{
+    create_wallet_user_with_random_keys_in_tenant(db, DEFAULT_TENANT, id, wallet_id).await
+}
+
+pub async fn create_wallet_user_with_random_keys_in_tenant<S, T>(db: &T, tenant: &str, id: Uuid, wallet_id: String)
 where
     S: ConnectionTrait,
     T: PersistenceConnection<S>,
 {
     create_wallet_user(
         db,
+        tenant,
         WalletUserCreate {
             id,
             wallet_id,
@@ -81,7 +90,7 @@ where
         .expect("Could not fetch wallet user")
 }
 
-pub async fn create_instruction_challenge_with_random_data<S, T>(db: &T, wallet_id: String)
+pub async fn create_instruction_challenge_with_random_data<S, T>(db: &T, wallet_id: String, sequence_number: u64)
 where
     S: ConnectionTrait,
     T: PersistenceConnection<S>,
@@ -93,7 +102,7 @@ where
             expiration_date_time: Local::now(), // irrelevant for these tests
             bytes: random_bytes(32),
         },
-        0, // irrelevant for these tests
+        sequence_number,
     )
     .await
     .expect("Could not create wallet user");
@@ -0,0 +1,50 @@
+use chrono::{Duration, Local};
+
+use wallet_common::utils::random_string;
+use wallet_provider_domain::model::revocation::{CertificateRevocationReason, RevokedWalletCertificate};
+use wallet_provider_persistence::{
+    revocation::{find_revoked_wallet_certificates, insert_revoked_wallet_certificate},
+    DEFAULT_TENANT,
+};
+
+pub mod common;
+
+#[cfg_attr(not(feature = "db_test"), ignore)]
+#[tokio::test]
+async fn test_revoked_wallet_certificates() {
+    let db = common::db_from_env().await.expect("Could not connect to database");
+
+    let tenant = random_string(16);
+    let wallet_id = random_string(32);
+    let blocked = RevokedWalletCertificate {
+        wallet_id: wallet_id.clone(),
+        reason: CertificateRevocationReason::Blocked,
+        date_time: Local::now(),
+    };
+
+    insert_revoked_wallet_certificate(&db, &tenant, blocked.clone())
+        .await
+        .expect("Could not insert revoked wallet certificate");
+
+    // revoking the same wallet again should retain the first revocation
+    insert_revoked_wallet_certificate(
+        &db,
+        &tenant,
+        RevokedWalletCertificate {
+            wallet_id: wallet_id.clone(),
+            reason: CertificateRevocationReason::Reset,
+            date_time: Local::now() + Duration::seconds(1),
+        },
+    )
+    .await
+    .expect("Could not insert revoked wallet certificate");
+
+    let revoked = find_revoked_wallet_certificates(&db, &tenant).await.unwrap();
+    assert_eq!(revoked.len(), 1);
+    assert_eq!(revoked[0].wallet_id, wallet_id);
+    assert_eq!(revoked[0].reason, CertificateRevocationReason::Blocked);
+
+    // the revocation should not be published for another tenant
+    let revoked = find_revoked_wallet_certificates(&db, DEFAULT_TENANT).await.unwrap();
+    assert!(!revoked.iter().any(|revoked| revoked.wallet_id == wallet_id));
+}
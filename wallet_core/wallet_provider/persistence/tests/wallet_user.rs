@@ -1,10 +1,33 @@
+use std::collections::HashMap;
+
+use chrono::{Duration, Local};
 use uuid::Uuid;
 
-use wallet_common::{generator::Generator, utils::random_string};
-use wallet_provider_domain::{repository::Committable, EpochGenerator};
+use wallet_common::{
+    generator::Generator,
+    utils::{random_bytes, random_string},
+};
+use wallet_provider_domain::{
+    model::{
+        audit_log::{AuditLogEvent, AuditLogEventType},
+        encrypted::{Encrypted, InitializationVector},
+        wallet_user::{InstructionChallenge, WalletUserKey, WalletUserKeys, WalletUserQueryResult},
+        wrapped_key::WrappedKey,
+    },
+    repository::{Committable, PersistenceError},
+    EpochGenerator,
+};
 use wallet_provider_persistence::{
+    audit_log::{find_audit_log_events, insert_audit_log_event},
     transaction,
-    wallet_user::{clear_instruction_challenge, register_unsuccessful_pin_entry},
+    wallet_user::{
+        clear_instruction_challenge, delete_expired_instruction_challenges, delete_wallet_user,
+        find_wallet_user_by_wallet_id, register_unsuccessful_pin_entry,
+        update_instruction_challenge_and_sequence_number, update_instruction_sequence_number,
+        update_wallet_user_suspended,
+    },
+    wallet_user_key::{create_keys, find_escrowed_keys, save_escrowed_keys},
+    DEFAULT_TENANT,
 };
 
 pub mod common;
@@ -89,13 +112,13 @@ async fn test_insert_instruction_challenge_on_conflict() {
     assert!(challenges.is_empty());
 
     // insert an instruction challenge for the first time, we should only find that one afterwards
-    common::create_instruction_challenge_with_random_data(&db, wallet_id.clone()).await;
+    common::create_instruction_challenge_with_random_data(&db, wallet_id.clone(), 1).await;
     let challenges = common::find_instruction_challenges_by_wallet_id(&db, wallet_id.clone()).await;
     assert_eq!(challenges.len(), 1);
     assert_eq!(challenges[0].wallet_user_id, wallet_user_id.clone());
 
     let og_id = challenges[0].id;
-    common::create_instruction_challenge_with_random_data(&db, wallet_id.clone()).await;
+    common::create_instruction_challenge_with_random_data(&db, wallet_id.clone(), 2).await;
 
     // insert another instruction challenge, this should update the bytes and expiration date in the first one
     let challenges = common::find_instruction_challenges_by_wallet_id(&db, wallet_id.clone()).await;
@@ -112,7 +135,7 @@ async fn test_insert_instruction_challenge_on_conflict() {
     let challenges = common::find_instruction_challenges_by_wallet_id(&db, wallet_id.clone()).await;
     assert_eq!(challenges.len(), 0);
 
-    common::create_instruction_challenge_with_random_data(&db, wallet_id.clone()).await;
+    common::create_instruction_challenge_with_random_data(&db, wallet_id.clone(), 3).await;
 
     // insert an instruction challenge for the second time, we should only find that one afterwards
     let challenges = common::find_instruction_challenges_by_wallet_id(&db, wallet_id.clone()).await;
@@ -134,7 +157,7 @@ async fn test_insert_instruction_challenge_on_conflict() {
     assert!(challenges.is_empty());
 
     // insert an instruction challenge for our second wallet, we should only find one per wallet
-    common::create_instruction_challenge_with_random_data(&db, wallet_id2.clone()).await;
+    common::create_instruction_challenge_with_random_data(&db, wallet_id2.clone(), 1).await;
     let challenges = common::find_instruction_challenges_by_wallet_id(&db, wallet_id2.clone()).await;
     assert_eq!(challenges.len(), 1);
     assert_eq!(challenges[0].wallet_user_id, wallet_user_id2.clone());
@@ -144,6 +167,76 @@ async fn test_insert_instruction_challenge_on_conflict() {
     assert_eq!(challenges[0].wallet_user_id, wallet_user_id.clone());
 }
 
+#[cfg_attr(not(feature = "db_test"), ignore)]
+#[tokio::test]
+async fn test_delete_expired_instruction_challenges() {
+    let db = common::db_from_env().await.expect("Could not connect to database");
+    let now = Local::now();
+
+    // create one wallet with an expired challenge and one with a challenge that is still valid
+    let mut wallet_ids = Vec::new();
+    for expiration_date_time in [now - Duration::minutes(1), now + Duration::minutes(1)] {
+        let wallet_id = random_string(32);
+        common::create_wallet_user_with_random_keys(&db, Uuid::new_v4(), wallet_id.clone()).await;
+        update_instruction_challenge_and_sequence_number(
+            &db,
+            &wallet_id,
+            InstructionChallenge {
+                bytes: random_bytes(32),
+                expiration_date_time,
+            },
+            1,
+        )
+        .await
+        .expect("Could not create instruction challenge");
+
+        wallet_ids.push(wallet_id);
+    }
+
+    let count = delete_expired_instruction_challenges(&db, now)
+        .await
+        .expect("Could not delete expired instruction challenges");
+
+    // other tests may have left expired challenges in the database, which are deleted as well
+    assert!(count >= 1);
+
+    let challenges = common::find_instruction_challenges_by_wallet_id(&db, wallet_ids[0].clone()).await;
+    assert!(challenges.is_empty());
+
+    let challenges = common::find_instruction_challenges_by_wallet_id(&db, wallet_ids[1].clone()).await;
+    assert_eq!(challenges.len(), 1);
+}
+
+#[cfg_attr(not(feature = "db_test"), ignore)]
+#[tokio::test]
+async fn test_update_instruction_sequence_number() {
+    let db = common::db_from_env().await.expect("Could not connect to database");
+
+    let wallet_user_id = Uuid::new_v4();
+    let wallet_id = random_string(32);
+
+    common::create_wallet_user_with_random_keys(&db, wallet_user_id, wallet_id.clone()).await;
+
+    update_instruction_sequence_number(&db, &wallet_id, 2)
+        .await
+        .expect("Could not update instruction sequence number");
+
+    let wallet_user = common::find_wallet_user(&db, wallet_user_id).await.unwrap();
+    assert_eq!(wallet_user.instruction_sequence_number, 2);
+
+    // both replaying and lowering the sequence number should be rejected
+    for sequence_number in [2, 1] {
+        let error = update_instruction_sequence_number(&db, &wallet_id, sequence_number)
+            .await
+            .expect_err("Updating the instruction sequence number should fail");
+
+        assert!(matches!(error, PersistenceError::SequenceNumberConflict(number) if number == sequence_number));
+    }
+
+    let wallet_user = common::find_wallet_user(&db, wallet_user_id).await.unwrap();
+    assert_eq!(wallet_user.instruction_sequence_number, 2);
+}
+
 #[cfg_attr(not(feature = "db_test"), ignore)]
 #[tokio::test]
 async fn test_register_unsuccessful_pin_entry() {
@@ -166,3 +259,159 @@ async fn test_register_unsuccessful_pin_entry() {
     assert_eq!(before.pin_entries + 1, after.pin_entries);
     assert_eq!(EpochGenerator.generate(), after.last_unsuccessful_pin.unwrap());
 }
+
+#[cfg_attr(not(feature = "db_test"), ignore)]
+#[tokio::test]
+async fn test_update_wallet_user_suspended() {
+    let db = common::db_from_env().await.expect("Could not connect to database");
+
+    let wallet_user_id = Uuid::new_v4();
+    let wallet_id = random_string(32);
+
+    common::create_wallet_user_with_random_keys(&db, wallet_user_id, wallet_id.clone()).await;
+
+    update_wallet_user_suspended(&db, DEFAULT_TENANT, &wallet_id, true)
+        .await
+        .expect("Could not suspend wallet user");
+
+    let result = find_wallet_user_by_wallet_id(&db, DEFAULT_TENANT, &wallet_id)
+        .await
+        .unwrap();
+    assert!(matches!(result, WalletUserQueryResult::Suspended));
+
+    update_wallet_user_suspended(&db, DEFAULT_TENANT, &wallet_id, false)
+        .await
+        .expect("Could not unsuspend wallet user");
+
+    let result = find_wallet_user_by_wallet_id(&db, DEFAULT_TENANT, &wallet_id)
+        .await
+        .unwrap();
+    assert!(matches!(result, WalletUserQueryResult::Found(_)));
+
+    // suspending an unknown wallet user should fail
+    let error = update_wallet_user_suspended(&db, DEFAULT_TENANT, &random_string(32), true)
+        .await
+        .expect_err("Suspending an unknown wallet user should fail");
+    assert!(matches!(error, PersistenceError::NotFound(_)));
+}
+
+#[cfg_attr(not(feature = "db_test"), ignore)]
+#[tokio::test]
+async fn test_find_wallet_user_by_wallet_id_in_tenant() {
+    let db = common::db_from_env().await.expect("Could not connect to database");
+
+    let wallet_id = random_string(32);
+
+    common::create_wallet_user_with_random_keys_in_tenant(&db, "white_label", Uuid::new_v4(), wallet_id.clone()).await;
+
+    let result = find_wallet_user_by_wallet_id(&db, "white_label", &wallet_id)
+        .await
+        .unwrap();
+    assert!(matches!(result, WalletUserQueryResult::Found(_)));
+
+    // the wallet user should not be found within another tenant
+    let result = find_wallet_user_by_wallet_id(&db, DEFAULT_TENANT, &wallet_id)
+        .await
+        .unwrap();
+    assert!(matches!(result, WalletUserQueryResult::NotFound));
+}
+
+#[cfg_attr(not(feature = "db_test"), ignore)]
+#[tokio::test]
+async fn test_delete_wallet_user() {
+    let db = common::db_from_env().await.expect("Could not connect to database");
+
+    let wallet_user_id = Uuid::new_v4();
+    let wallet_id = random_string(32);
+
+    common::create_wallet_user_with_random_keys(&db, wallet_user_id, wallet_id.clone()).await;
+    common::create_instruction_challenge_with_random_data(&db, wallet_id.clone(), 1).await;
+
+    delete_wallet_user(&db, DEFAULT_TENANT, &wallet_id)
+        .await
+        .expect("Could not delete wallet user");
+
+    // the wallet user should be gone, including its instruction challenge
+    assert!(common::find_wallet_user(&db, wallet_user_id).await.is_none());
+    assert!(common::find_instruction_challenges_by_wallet_id(&db, wallet_id)
+        .await
+        .is_empty());
+}
+
+#[cfg_attr(not(feature = "db_test"), ignore)]
+#[tokio::test]
+async fn test_wallet_user_in_other_tenant() {
+    let db = common::db_from_env().await.expect("Could not connect to database");
+
+    let wallet_user_id = Uuid::new_v4();
+    let wallet_id = random_string(32);
+
+    common::create_wallet_user_with_random_keys_in_tenant(&db, "white_label", wallet_user_id, wallet_id.clone()).await;
+    create_keys(
+        &db,
+        WalletUserKeys {
+            wallet_user_id,
+            keys: vec![WalletUserKey {
+                wallet_user_key_id: Uuid::new_v4(),
+                key_identifier: "key1".to_string(),
+                key: WrappedKey::new(random_bytes(32)),
+            }],
+        },
+    )
+    .await
+    .unwrap();
+    save_escrowed_keys(
+        &db,
+        wallet_user_id,
+        HashMap::from([(
+            "key1".to_string(),
+            Encrypted::new(vec![1, 2, 3], InitializationVector(vec![4, 5, 6])),
+        )]),
+    )
+    .await
+    .unwrap();
+    insert_audit_log_event(
+        &db,
+        "white_label",
+        AuditLogEvent {
+            wallet_id: wallet_id.clone(),
+            event_type: AuditLogEventType::Suspended,
+            date_time: Local::now(),
+        },
+    )
+    .await
+    .unwrap();
+
+    // the wallet user cannot be suspended, deleted or have its escrowed keys or audit log read from another tenant
+    let error = update_wallet_user_suspended(&db, DEFAULT_TENANT, &wallet_id, true)
+        .await
+        .expect_err("Suspending a wallet user of another tenant should fail");
+    assert!(matches!(error, PersistenceError::NotFound(_)));
+
+    assert!(find_escrowed_keys(&db, DEFAULT_TENANT, &wallet_id)
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(find_audit_log_events(&db, DEFAULT_TENANT, &wallet_id, None)
+        .await
+        .unwrap()
+        .is_empty());
+
+    delete_wallet_user(&db, DEFAULT_TENANT, &wallet_id).await.unwrap();
+
+    let result = find_wallet_user_by_wallet_id(&db, "white_label", &wallet_id)
+        .await
+        .unwrap();
+    assert!(matches!(result, WalletUserQueryResult::Found(_)));
+    assert_eq!(
+        find_escrowed_keys(&db, "white_label", &wallet_id).await.unwrap().len(),
+        1
+    );
+    assert_eq!(
+        find_audit_log_events(&db, "white_label", &wallet_id, None)
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+}
@@ -1,11 +1,20 @@
+use std::collections::HashMap;
+
 use p256::{ecdsa::SigningKey, elliptic_curve::rand_core::OsRng};
 use uuid::Uuid;
 
 use wallet_provider_domain::model::{
+    encrypted::{Encrypted, InitializationVector},
     wallet_user::{WalletUserKey, WalletUserKeys},
     wrapped_key::WrappedKey,
 };
-use wallet_provider_persistence::wallet_user_key::{create_keys, find_keys_by_identifiers};
+use wallet_provider_persistence::{
+    wallet_user_key::{
+        create_keys, delete_escrowed_keys, find_all_keys, find_escrowed_keys, find_key_signature_counts,
+        find_keys_by_identifiers, increment_key_signature_counts, save_escrowed_keys,
+    },
+    DEFAULT_TENANT,
+};
 
 pub mod common;
 
@@ -55,3 +64,99 @@ async fn test_create_keys() {
     let key2: Vec<u8> = key2.key.into();
     assert_eq!(vec![key1, key2], keys);
 }
+
+#[cfg_attr(not(feature = "db_test"), ignore)]
+#[tokio::test]
+async fn test_increment_key_signature_counts() {
+    let db = common::db_from_env().await.expect("Could not connect to database");
+
+    let wallet_user_id = Uuid::new_v4();
+    let wallet_id = Uuid::new_v4().to_string();
+
+    common::create_wallet_user_with_random_keys(&db, wallet_user_id, wallet_id.clone()).await;
+
+    let keys = ["key1", "key2"]
+        .into_iter()
+        .map(|identifier| WalletUserKey {
+            wallet_user_key_id: Uuid::new_v4(),
+            key_identifier: identifier.to_string(),
+            key: WrappedKey::new(SigningKey::random(&mut OsRng).to_bytes().to_vec()),
+        })
+        .collect();
+    create_keys(&db, WalletUserKeys { wallet_user_id, keys }).await.unwrap();
+
+    let counts = find_key_signature_counts(&db, wallet_user_id).await.unwrap();
+    assert_eq!(
+        counts,
+        HashMap::from([("key1".to_string(), 0), ("key2".to_string(), 0)])
+    );
+
+    increment_key_signature_counts(&db, wallet_user_id, HashMap::from([("key1".to_string(), 2)]))
+        .await
+        .unwrap();
+    increment_key_signature_counts(
+        &db,
+        wallet_user_id,
+        HashMap::from([("key1".to_string(), 1), ("key2".to_string(), 1)]),
+    )
+    .await
+    .unwrap();
+
+    let counts = find_key_signature_counts(&db, wallet_user_id).await.unwrap();
+    assert_eq!(
+        counts,
+        HashMap::from([("key1".to_string(), 3), ("key2".to_string(), 1)])
+    );
+}
+
+#[cfg_attr(not(feature = "db_test"), ignore)]
+#[tokio::test]
+async fn test_escrowed_keys() {
+    let db = common::db_from_env().await.expect("Could not connect to database");
+
+    let wallet_user_id = Uuid::new_v4();
+    let wallet_id = Uuid::new_v4().to_string();
+
+    common::create_wallet_user_with_random_keys(&db, wallet_user_id, wallet_id.clone()).await;
+
+    let keys = ["key1", "key2"]
+        .into_iter()
+        .map(|identifier| WalletUserKey {
+            wallet_user_key_id: Uuid::new_v4(),
+            key_identifier: identifier.to_string(),
+            key: WrappedKey::new(SigningKey::random(&mut OsRng).to_bytes().to_vec()),
+        })
+        .collect();
+    create_keys(&db, WalletUserKeys { wallet_user_id, keys }).await.unwrap();
+
+    let all_keys = find_all_keys(&db, wallet_user_id).await.unwrap();
+    assert_eq!(all_keys.len(), 2);
+    assert!(find_escrowed_keys(&db, DEFAULT_TENANT, &wallet_id)
+        .await
+        .unwrap()
+        .is_empty());
+
+    save_escrowed_keys(
+        &db,
+        wallet_user_id,
+        HashMap::from([(
+            "key1".to_string(),
+            Encrypted::new(vec![1, 2, 3], InitializationVector(vec![4, 5, 6])),
+        )]),
+    )
+    .await
+    .unwrap();
+
+    let escrowed_keys = find_escrowed_keys(&db, DEFAULT_TENANT, &wallet_id).await.unwrap();
+    assert_eq!(escrowed_keys.len(), 1);
+    assert_eq!(escrowed_keys["key1"].data, vec![1, 2, 3]);
+    assert_eq!(escrowed_keys["key1"].iv.0, vec![4, 5, 6]);
+
+    delete_escrowed_keys(&db, wallet_user_id).await.unwrap();
+
+    assert!(find_escrowed_keys(&db, DEFAULT_TENANT, &wallet_id)
+        .await
+        .unwrap()
+        .is_empty());
+    assert_eq!(find_all_keys(&db, wallet_user_id).await.unwrap().len(), 2);
+}
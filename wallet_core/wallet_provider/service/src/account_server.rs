@@ -1,41 +1,60 @@
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{Arc, Mutex, PoisonError},
+};
 
-use chrono::{DateTime, Duration, Local};
+use chrono::{DateTime, Duration, Local, TimeZone};
+use metrics::increment_counter;
 use p256::{ecdsa::VerifyingKey, pkcs8::EncodePublicKey};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
-use tracing::debug;
+use tracing::{debug, info, warn};
 use uuid::Uuid;
 
 use wallet_common::{
     account::{
         messages::{
-            auth::{Registration, WalletCertificate, WalletCertificateClaims},
+            auth::{
+                Registration, RevocationList, RevocationListClaims, RevocationRequestClaims, RevocationRequestMessage,
+                RevokedCertificate, WalletCertificate, WalletCertificateClaims,
+            },
             errors::{IncorrectPinData, PinTimeoutData},
             instructions::{
-                Instruction, InstructionChallengeRequestMessage, InstructionResult, InstructionResultClaims,
+                integrity_request_hash, Instruction, InstructionChallengeRequestMessage, InstructionEndpoint,
+                InstructionResult, InstructionResultClaims,
             },
         },
         serialization::Base64Bytes,
         signed::{ChallengeResponsePayload, SequenceNumberComparison, SignedDouble},
     },
     generator::Generator,
-    jwt::{EcdsaDecodingKey, Jwt, JwtError, JwtSubject},
+    jwt::{Jwt, JwtError, JwtSubject},
     utils::{random_bytes, random_string},
 };
 use wallet_provider_domain::{
     model::{
+        audit_log::{AuditLogEvent, AuditLogEventType},
         encrypter::{Decrypter, Encrypter},
         hsm::{Hsm, WalletUserHsm},
         pin_policy::{PinPolicyEvaluation, PinPolicyEvaluator},
-        wallet_user::{InstructionChallenge, WalletUser, WalletUserCreate, WalletUserQueryResult},
+        revocation::{CertificateRevocationReason, RevokedWalletCertificate},
+        wallet_user::{
+            InstructionChallenge, WalletUser, WalletUserCreate, WalletUserKey, WalletUserKeys, WalletUserQueryResult,
+        },
     },
-    repository::{Committable, PersistenceError, TransactionStarter, WalletUserRepository},
+    repository::{AuditLogRepository, Committable, PersistenceError, TransactionStarter, WalletUserRepository},
 };
 
 use crate::{
+    anomaly::{AnomalyDetectedError, AnomalyDetector, AnomalyVerdict, InstructionMetadata},
     hsm::HsmError,
+    instruction_queue::{InstructionQueue, InstructionQueueGuard, WalletBusyError},
     instructions::HandleInstruction,
+    integrity::{IntegrityError, IntegrityVerifier},
+    key_escrow::KeyEscrow,
+    key_usage::{KeyUsageLimitError, KeyUsageLimits},
     keys::{CertificateSigningKey, InstructionResultSigningKey},
+    rate_limiter::{RateLimitedError, RateLimiter},
 };
 
 #[derive(Debug, thiserror::Error)]
@@ -57,8 +76,12 @@ pub enum ChallengeError {
     Validation(#[from] wallet_common::errors::Error),
     #[error("wallet certificate validation error: {0}")]
     WalletCertificate(#[from] WalletCertificateError),
-    #[error("instruction sequence number validation failed")]
-    SequenceNumberValidation,
+    #[error("instruction sequence number validation failed (known: {known})")]
+    SequenceNumberValidation { known: u64 },
+    #[error("{0}")]
+    WalletBusy(#[from] WalletBusyError),
+    #[error("{0}")]
+    RateLimited(#[from] RateLimitedError),
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -77,6 +100,8 @@ pub enum WalletCertificateError {
     UserNotRegistered,
     #[error("registered wallet user blocked")]
     UserBlocked,
+    #[error("registered wallet user suspended")]
+    UserSuspended,
     #[error("could not retrieve registered wallet user: {0}")]
     Persistence(#[from] PersistenceError),
     #[error("hsm error: {0}")]
@@ -127,6 +152,62 @@ pub enum InstructionError {
     KeyNotFound(String),
     #[error("hsm error: {0}")]
     HsmError(#[from] HsmError),
+    #[error("{0}")]
+    WalletBusy(#[from] WalletBusyError),
+    #[error("{0}")]
+    RateLimited(#[from] RateLimitedError),
+    #[error("integrity evidence verification error: {0}")]
+    IntegrityEvidence(#[from] IntegrityError),
+    #[error("key usage limit error: {0}")]
+    KeyUsageLimit(#[from] KeyUsageLimitError),
+    #[error("key escrow is not available")]
+    KeyEscrowUnavailable,
+    #[error("{0}")]
+    AnomalyDetected(#[from] AnomalyDetectedError),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RevocationError {
+    #[error("wallet certificate validation error: {0}")]
+    WalletCertificate(#[from] WalletCertificateError),
+    #[error("revocation request validation error: {0}")]
+    Validation(#[source] JwtError),
+    #[error("revocation request was not issued recently")]
+    Expired,
+    #[error("revocation request has already been received")]
+    Replayed,
+    #[error("could not delete wallet user: {0}")]
+    Storage(#[from] PersistenceError),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SuspensionError {
+    #[error("no registered wallet user found")]
+    UserNotRegistered,
+    #[error("could not update wallet user: {0}")]
+    Storage(#[from] PersistenceError),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum KeyEscrowError {
+    #[error("target wallet user not found")]
+    TargetNotFound,
+    #[error("source and target wallet are the same")]
+    SameWallet,
+    #[error("no escrowed keys found")]
+    NoEscrowedKeys,
+    #[error("could not recover escrowed keys: {0}")]
+    Storage(#[from] PersistenceError),
+    #[error("hsm error: {0}")]
+    HsmError(#[from] HsmError),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RevocationListError {
+    #[error("could not retrieve revoked wallet certificates: {0}")]
+    Storage(#[from] PersistenceError),
+    #[error("could not sign revocation list: {0}")]
+    JwtSigning(#[source] JwtError),
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -180,40 +261,64 @@ impl JwtSubject for RegistrationChallengeClaims {
     const SUB: &'static str = "registration_challenge";
 }
 
+/// Revocation requests that were issued longer ago than this, or this much in the future, are rejected.
+const REVOCATION_REQUEST_MAX_AGE_SECONDS: i64 = 5 * 60;
+
 pub struct AccountServer {
     instruction_challenge_timeout: Duration,
+    instruction_queue: InstructionQueue,
+    instruction_rate_limiter: RateLimiter,
+    /// The identifiers of the revocation requests that were received within the maximum age, with their issue time.
+    revocation_request_ids: Mutex<HashMap<String, DateTime<Local>>>,
 
     pub name: String,
 
-    certificate_signing_pubkey: EcdsaDecodingKey,
+    certificate_signing_pubkeys: Vec<VerifyingKey>,
     encryption_key_identifier: String,
     pin_public_disclosure_protection_key_identifier: String,
 }
 
 impl AccountServer {
+    /// The certificate signing public keys consist of the current key, followed by the keys that were used before it.
+    /// Wallet certificates and registration challenges signed with any of these keys are accepted.
+    #[allow(clippy::too_many_arguments)]
     pub async fn new(
         instruction_challenge_timeout: Duration,
+        instruction_queue_max_wait: Duration,
+        instruction_rate_limit_max_requests: u32,
+        instruction_rate_limit_window: Duration,
         name: String,
-        certificate_signing_pubkey: EcdsaDecodingKey,
+        certificate_signing_pubkeys: Vec<VerifyingKey>,
         encryption_key_identifier: String,
         pin_public_disclosure_protection_key_identifier: String,
     ) -> Result<Self, AccountServerInitError> {
         Ok(AccountServer {
             instruction_challenge_timeout,
+            instruction_queue: InstructionQueue::new(instruction_queue_max_wait),
+            instruction_rate_limiter: RateLimiter::new(
+                instruction_rate_limit_max_requests,
+                instruction_rate_limit_window,
+            ),
+            revocation_request_ids: Mutex::new(HashMap::new()),
             name,
-            certificate_signing_pubkey,
+            certificate_signing_pubkeys,
             encryption_key_identifier,
             pin_public_disclosure_protection_key_identifier,
         })
     }
 
+    /// Change the limits of the instruction rate limiter, e.g. when the settings are reloaded.
+    pub fn set_instruction_rate_limit(&self, max_requests: u32, window: Duration) {
+        self.instruction_rate_limiter.set_limits(max_requests, window);
+    }
+
     // Only used for registration. When a registered user sends an instruction, we should store
     // the challenge per user, instead globally.
     pub async fn registration_challenge(
         &self,
         certificate_signing_key: &impl CertificateSigningKey,
     ) -> Result<Vec<u8>, ChallengeError> {
-        let challenge = Jwt::sign_with_sub(
+        let challenge = Jwt::sign_with_sub_and_thumbprint(
             &RegistrationChallengeClaims {
                 wallet_id: random_string(32),
                 random: random_bytes(32).into(),
@@ -241,6 +346,12 @@ impl AccountServer {
         R: TransactionStarter<TransactionType = T> + WalletUserRepository<TransactionType = T>,
         H: Decrypter<VerifyingKey, Error = HsmError> + Hsm<Error = HsmError>,
     {
+        // Wait for any other request of this wallet to finish before retrieving the wallet user,
+        // so that the sequence number that is checked below is not outdated.
+        let _queue_guard = self
+            .enter_instruction_queue::<ChallengeError>(&challenge_request.certificate)
+            .await?;
+
         debug!("Starting database transaction");
 
         let tx = repositories.begin_transaction().await?;
@@ -264,7 +375,9 @@ impl AccountServer {
 
         if parsed.sequence_number <= user.instruction_sequence_number {
             tx.commit().await?;
-            return Err(ChallengeError::SequenceNumberValidation);
+            return Err(ChallengeError::SequenceNumberValidation {
+                known: user.instruction_sequence_number,
+            });
         }
 
         debug!("Sequence number valid, persisting generated challenge and incremented sequence number");
@@ -281,7 +394,14 @@ impl AccountServer {
                 challenge.clone(),
                 parsed.sequence_number,
             )
-            .await?;
+            .await
+            .map_err(|error| match error {
+                // A concurrent request stored a sequence number at least as large as the provided one.
+                PersistenceError::SequenceNumberConflict(_) => ChallengeError::SequenceNumberValidation {
+                    known: parsed.sequence_number,
+                },
+                error => error.into(),
+            })?;
         tx.commit().await?;
 
         debug!("Responding with generated challenge");
@@ -289,29 +409,74 @@ impl AccountServer {
         Ok(challenge.bytes)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn handle_instruction<T, R, I, IR, G, H>(
         &self,
-        instruction: Instruction<I>,
+        mut instruction: Instruction<I>,
+        ip_address: Option<IpAddr>,
         instruction_result_signing_key: &impl InstructionResultSigningKey,
         generators: &G,
         repositories: &R,
         pin_policy: &impl PinPolicyEvaluator,
+        integrity_verifier: &impl IntegrityVerifier,
+        key_usage_limits: &KeyUsageLimits,
+        key_escrow: Option<&KeyEscrow>,
+        anomaly_detector: &impl AnomalyDetector,
         wallet_user_hsm: &H,
     ) -> Result<InstructionResult<IR>, InstructionError>
     where
         T: Committable,
-        R: TransactionStarter<TransactionType = T> + WalletUserRepository<TransactionType = T>,
-        I: HandleInstruction<Result = IR> + Serialize + DeserializeOwned,
+        R: TransactionStarter<TransactionType = T>
+            + WalletUserRepository<TransactionType = T>
+            + AuditLogRepository<TransactionType = T>,
+        I: InstructionEndpoint + HandleInstruction<Result = IR> + Serialize + DeserializeOwned,
         IR: Serialize + DeserializeOwned,
         G: Generator<Uuid> + Generator<DateTime<Local>>,
         H: WalletUserHsm<Error = HsmError> + Hsm<Error = HsmError> + Decrypter<VerifyingKey, Error = HsmError>,
     {
+        // Hold the turn of this wallet until the instruction has been handled completely, so that concurrent
+        // instructions cannot both be verified against the same sequence number and challenge.
+        let _queue_guard = self
+            .enter_instruction_queue::<InstructionError>(&instruction.certificate)
+            .await?;
+
         debug!("Verifying certificate and retrieving wallet user");
 
         let wallet_user = self
             .verify_wallet_certificate(&instruction.certificate, repositories, wallet_user_hsm)
             .await?;
 
+        debug!("Inspecting instruction for anomalies");
+
+        let metadata = InstructionMetadata {
+            instruction_type: I::ENDPOINT,
+            wallet_id: wallet_user.wallet_id.clone(),
+            ip_address,
+            received_at: generators.generate(),
+        };
+        match anomaly_detector.inspect(&metadata).await {
+            AnomalyVerdict::Normal => {}
+            AnomalyVerdict::Suspicious(reason) => {
+                warn!(
+                    "Suspicious {} instruction of user {}: {}",
+                    I::ENDPOINT,
+                    &wallet_user.id,
+                    reason
+                );
+                increment_counter!("wallet_provider_anomalies_total", "verdict" => "suspicious");
+            }
+            AnomalyVerdict::Reject(reason) => {
+                warn!(
+                    "Rejecting {} instruction of user {}: {}",
+                    I::ENDPOINT,
+                    &wallet_user.id,
+                    reason
+                );
+                increment_counter!("wallet_provider_anomalies_total", "verdict" => "reject");
+                return Err(AnomalyDetectedError(reason).into());
+            }
+        }
+
         debug!(
             "Starting database transaction and instruction handling process for user {}",
             &wallet_user.id
@@ -340,6 +505,8 @@ impl AccountServer {
             return Err(pin_eval.into());
         }
 
+        let integrity_evidence = instruction.integrity_evidence.take();
+
         debug!("Verifying instruction");
 
         match self
@@ -347,6 +514,25 @@ impl AccountServer {
             .await
         {
             Ok(payload) => {
+                // Verify the evidence before updating the wallet user, so that rejected evidence rolls back the
+                // transaction and leaves the pin retries and the instruction sequence number as they were.
+                if payload.payload.requires_integrity_evidence() {
+                    debug!("Verifying integrity evidence");
+
+                    // The instruction was verified against the challenge, so it must be present.
+                    let challenge = wallet_user
+                        .instruction_challenge
+                        .as_ref()
+                        .ok_or(InstructionValidationError::ChallengeMismatch)?;
+                    let time: DateTime<Local> = generators.generate();
+
+                    integrity_verifier.verify(
+                        integrity_evidence.as_ref(),
+                        &integrity_request_hash(&challenge.bytes),
+                        time.into(),
+                    )?;
+                }
+
                 debug!("Instruction successfully verified, resetting pin retries");
 
                 repositories
@@ -357,13 +543,37 @@ impl AccountServer {
 
                 repositories
                     .update_instruction_sequence_number(&tx, &wallet_user.wallet_id, payload.sequence_number)
-                    .await?;
+                    .await
+                    .map_err(|error| match error {
+                        PersistenceError::SequenceNumberConflict(_) => {
+                            InstructionError::Validation(InstructionValidationError::SequenceNumberMismatch)
+                        }
+                        error => error.into(),
+                    })?;
+
+                // All checks have passed at this point, so the instruction is recorded in the same transaction that
+                // updates the wallet user, which is rolled back on any of the errors above.
+                record_audit_log_event(
+                    repositories,
+                    &tx,
+                    &wallet_user.wallet_id,
+                    AuditLogEventType::Instruction(I::ENDPOINT.to_string()),
+                    generators.generate(),
+                )
+                .await?;
 
                 tx.commit().await?;
 
                 let instruction_result = payload
                     .payload
-                    .handle(&wallet_user, generators, repositories, wallet_user_hsm)
+                    .handle(
+                        &wallet_user,
+                        generators,
+                        repositories,
+                        wallet_user_hsm,
+                        key_usage_limits,
+                        key_escrow,
+                    )
                     .await?;
                 self.sign_instruction_result(instruction_result_signing_key, instruction_result)
                     .await
@@ -372,14 +582,42 @@ impl AccountServer {
                 let error = if matches!(validation_error, InstructionValidationError::VerificationFailed(_)) {
                     debug!("Instruction validation failed, registering unsuccessful pin entry");
 
+                    let is_blocked = matches!(pin_eval, PinPolicyEvaluation::BlockedPermanently);
+                    let time = generators.generate();
+
                     repositories
-                        .register_unsuccessful_pin_entry(
+                        .register_unsuccessful_pin_entry(&tx, &wallet_user.wallet_id, is_blocked, time)
+                        .await?;
+
+                    record_audit_log_event(
+                        repositories,
+                        &tx,
+                        &wallet_user.wallet_id,
+                        AuditLogEventType::PinFailure,
+                        time,
+                    )
+                    .await?;
+                    if is_blocked {
+                        record_audit_log_event(
+                            repositories,
                             &tx,
                             &wallet_user.wallet_id,
-                            matches!(pin_eval, PinPolicyEvaluation::BlockedPermanently),
-                            generators.generate(),
+                            AuditLogEventType::Blocked,
+                            time,
                         )
                         .await?;
+                        repositories
+                            .insert_revoked_wallet_certificate(
+                                &tx,
+                                RevokedWalletCertificate {
+                                    wallet_id: wallet_user.wallet_id.clone(),
+                                    reason: CertificateRevocationReason::Blocked,
+                                    date_time: time,
+                                },
+                            )
+                            .await?;
+                    }
+
                     Err(pin_eval.into())
                 } else {
                     Err(validation_error)?
@@ -391,17 +629,20 @@ impl AccountServer {
         }
     }
 
-    pub async fn register<T, R, H>(
+    pub async fn register<T, R, H, G>(
         &self,
         certificate_signing_key: &impl CertificateSigningKey,
-        uuid_generator: &impl Generator<Uuid>,
+        generators: &G,
         repositories: &R,
         hsm: &H,
         registration_message: SignedDouble<Registration>,
     ) -> Result<WalletCertificate, RegistrationError>
     where
         T: Committable,
-        R: TransactionStarter<TransactionType = T> + WalletUserRepository<TransactionType = T>,
+        R: TransactionStarter<TransactionType = T>
+            + WalletUserRepository<TransactionType = T>
+            + AuditLogRepository<TransactionType = T>,
+        G: Generator<Uuid> + Generator<DateTime<Local>>,
         H: Encrypter<VerifyingKey, Error = HsmError> + Hsm<Error = HsmError>,
     {
         debug!("Parsing message to lookup public keys");
@@ -416,7 +657,7 @@ impl AccountServer {
 
         let challenge = &unverified.challenge.0;
         let wallet_id = self
-            .verify_registration_challenge(&self.certificate_signing_pubkey, challenge)?
+            .verify_registration_challenge(&self.certificate_signing_pubkeys, challenge)?
             .wallet_id;
 
         let hw_pubkey = unverified.payload.hw_pubkey.0;
@@ -436,7 +677,7 @@ impl AccountServer {
 
         debug!("Creating new wallet user");
 
-        let uuid = uuid_generator.generate();
+        let uuid: Uuid = generators.generate();
         repositories
             .create_wallet_user(
                 &tx,
@@ -449,6 +690,15 @@ impl AccountServer {
             )
             .await?;
 
+        record_audit_log_event(
+            repositories,
+            &tx,
+            &wallet_id,
+            AuditLogEventType::Registration,
+            generators.generate(),
+        )
+        .await?;
+
         debug!("Generating new wallet certificate for user {}", uuid);
 
         let cert_result = self
@@ -460,6 +710,280 @@ impl AccountServer {
         Ok(cert_result)
     }
 
+    /// Revoke the wallet certificate by deleting the wallet user, including its keys. After this, the certificate
+    /// will no longer be accepted, so the wallet has to register again. The certificate is published on the
+    /// revocation list, see [`Self::revocation_list()`].
+    ///
+    /// As the request is not bound to a challenge, it is only accepted if it was issued recently and its identifier
+    /// has not been received before. The identifiers are only tracked within this process, but a request that was
+    /// processed successfully cannot be replayed on another instance either, as the wallet user no longer exists.
+    pub async fn revoke<T, R, H>(
+        &self,
+        revocation_request: RevocationRequestMessage,
+        repositories: &R,
+        time_generator: &impl Generator<DateTime<Local>>,
+        hsm: &H,
+    ) -> Result<(), RevocationError>
+    where
+        T: Committable,
+        R: TransactionStarter<TransactionType = T>
+            + WalletUserRepository<TransactionType = T>
+            + AuditLogRepository<TransactionType = T>,
+        H: Decrypter<VerifyingKey, Error = HsmError> + Hsm<Error = HsmError>,
+    {
+        debug!("Verifying certificate and retrieving wallet user");
+
+        let user = self
+            .verify_wallet_certificate(&revocation_request.certificate, repositories, hsm)
+            .await?;
+
+        debug!("Parsing and verifying revocation request for user {}", user.id);
+
+        let claims = revocation_request
+            .message
+            .parse_and_verify_with_sub(&user.hw_pubkey.into())
+            .map_err(RevocationError::Validation)?;
+
+        let time = time_generator.generate();
+        self.register_revocation_request(claims, time)?;
+
+        debug!("Revocation request valid, deleting wallet user");
+
+        let tx = repositories.begin_transaction().await?;
+        repositories.delete_wallet_user(&tx, &user.wallet_id).await?;
+        record_audit_log_event(repositories, &tx, &user.wallet_id, AuditLogEventType::Revocation, time).await?;
+        repositories
+            .insert_revoked_wallet_certificate(
+                &tx,
+                RevokedWalletCertificate {
+                    wallet_id: user.wallet_id,
+                    reason: CertificateRevocationReason::Reset,
+                    date_time: time,
+                },
+            )
+            .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    fn register_revocation_request(
+        &self,
+        claims: RevocationRequestClaims,
+        time: DateTime<Local>,
+    ) -> Result<(), RevocationError> {
+        let max_age = Duration::seconds(REVOCATION_REQUEST_MAX_AGE_SECONDS);
+        let issued_at = i64::try_from(claims.iat)
+            .ok()
+            .and_then(|iat| Local.timestamp_opt(iat, 0).single())
+            .filter(|issued_at| *issued_at >= time - max_age && *issued_at <= time + max_age)
+            .ok_or(RevocationError::Expired)?;
+
+        let mut request_ids = self
+            .revocation_request_ids
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        // Requests that were issued before the maximum age are rejected anyway, so their identifiers can be forgotten.
+        request_ids.retain(|_, issued_at| *issued_at >= time - max_age);
+
+        if request_ids.insert(claims.jti, issued_at).is_some() {
+            return Err(RevocationError::Replayed);
+        }
+
+        Ok(())
+    }
+
+    /// Create the list of revoked wallet certificates, i.e. those of wallets that were reset or blocked, signed with
+    /// the certificate signing key.
+    pub async fn revocation_list<T, R>(
+        &self,
+        certificate_signing_key: &impl CertificateSigningKey,
+        repositories: &R,
+        time_generator: &impl Generator<DateTime<Local>>,
+    ) -> Result<RevocationList, RevocationListError>
+    where
+        T: Committable,
+        R: TransactionStarter<TransactionType = T> + WalletUserRepository<TransactionType = T>,
+    {
+        let tx = repositories.begin_transaction().await?;
+        let revoked = repositories.find_revoked_wallet_certificates(&tx).await?;
+        tx.commit().await?;
+
+        let claims = RevocationListClaims {
+            revoked_certificates: revoked
+                .into_iter()
+                .map(|revoked| RevokedCertificate {
+                    wallet_id: revoked.wallet_id,
+                    reason: revoked.reason,
+                    revoked_at: revoked.date_time.timestamp() as u64,
+                })
+                .collect(),
+            iss: self.name.clone(),
+            iat: time_generator.generate().timestamp() as u64,
+        };
+
+        Jwt::sign_with_sub_and_thumbprint(&claims, certificate_signing_key)
+            .await
+            .map_err(RevocationListError::JwtSigning)
+    }
+
+    /// Delete the instruction challenges of all wallets that have expired, returning the number of deleted challenges.
+    /// As the challenges are stored in the database, this can be done by any instance of the Wallet Provider.
+    pub async fn delete_expired_instruction_challenges<T, R>(
+        &self,
+        repositories: &R,
+        time_generator: &impl Generator<DateTime<Local>>,
+    ) -> Result<u64, PersistenceError>
+    where
+        T: Committable,
+        R: TransactionStarter<TransactionType = T> + WalletUserRepository<TransactionType = T>,
+    {
+        let tx = repositories.begin_transaction().await?;
+        let count = repositories
+            .delete_expired_instruction_challenges(&tx, time_generator.generate())
+            .await?;
+        tx.commit().await?;
+
+        Ok(count)
+    }
+
+    /// Suspend the wallet with the specified id, e.g. because its device has been reported stolen, or lift its
+    /// suspension. While suspended, any instruction challenge or instruction sent by the wallet is rejected.
+    pub async fn update_wallet_suspended<T, R>(
+        &self,
+        wallet_id: &str,
+        is_suspended: bool,
+        repositories: &R,
+        time_generator: &impl Generator<DateTime<Local>>,
+    ) -> Result<(), SuspensionError>
+    where
+        T: Committable,
+        R: TransactionStarter<TransactionType = T>
+            + WalletUserRepository<TransactionType = T>
+            + AuditLogRepository<TransactionType = T>,
+    {
+        debug!("Updating suspension of wallet user to {}", is_suspended);
+
+        let tx = repositories.begin_transaction().await?;
+
+        repositories
+            .update_wallet_user_suspended(&tx, wallet_id, is_suspended)
+            .await
+            .map_err(|error| match error {
+                PersistenceError::NotFound(_) => SuspensionError::UserNotRegistered,
+                error => error.into(),
+            })?;
+
+        let event_type = if is_suspended {
+            AuditLogEventType::Suspended
+        } else {
+            AuditLogEventType::Unsuspended
+        };
+        record_audit_log_event(repositories, &tx, wallet_id, event_type, time_generator.generate()).await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Recover the escrowed keys of a wallet, e.g. one of which the device has been lost, to the wallet with the target
+    /// id, which should be registered on the new device of the same user. The keys are added to the target wallet
+    /// with the same identifiers and are escrowed for it as well if its user consented to key escrow. As the keys
+    /// should only be usable by one wallet, the source wallet user is deleted, including its keys and escrow, and its
+    /// certificate is published on the revocation list. Establishing that both wallets belong to the same user is the
+    /// responsibility of the caller. Returns the number of keys that were recovered.
+    pub async fn recover_escrowed_keys<T, R, G, H>(
+        &self,
+        wallet_id: &str,
+        target_wallet_id: &str,
+        key_escrow: &KeyEscrow,
+        repositories: &R,
+        generators: &G,
+        hsm: &H,
+    ) -> Result<usize, KeyEscrowError>
+    where
+        T: Committable,
+        R: TransactionStarter<TransactionType = T>
+            + WalletUserRepository<TransactionType = T>
+            + AuditLogRepository<TransactionType = T>,
+        G: Generator<Uuid> + Generator<DateTime<Local>>,
+        H: Hsm<Error = HsmError>,
+    {
+        debug!("Recovering escrowed keys");
+
+        if wallet_id == target_wallet_id {
+            return Err(KeyEscrowError::SameWallet);
+        }
+
+        let tx = repositories.begin_transaction().await?;
+
+        let target_user = match repositories
+            .find_wallet_user_by_wallet_id(&tx, target_wallet_id)
+            .await?
+        {
+            WalletUserQueryResult::Found(user) => user,
+            _ => return Err(KeyEscrowError::TargetNotFound),
+        };
+
+        let escrowed_keys = repositories.find_escrowed_keys(&tx, wallet_id).await?;
+        if escrowed_keys.is_empty() {
+            return Err(KeyEscrowError::NoEscrowedKeys);
+        }
+
+        // The escrowed keys can be stored for the target wallet as they are, as these are not bound to a wallet.
+        let target_escrowed_keys = target_user.key_escrow_consent.map(|_| escrowed_keys.clone());
+        let keys = key_escrow.recover_keys(hsm, escrowed_keys).await?;
+        let key_count = keys.len();
+
+        repositories
+            .save_keys(
+                &tx,
+                WalletUserKeys {
+                    wallet_user_id: target_user.id,
+                    keys: keys
+                        .into_iter()
+                        .map(|(key_identifier, key)| WalletUserKey {
+                            wallet_user_key_id: generators.generate(),
+                            key_identifier,
+                            key,
+                        })
+                        .collect(),
+                },
+            )
+            .await?;
+
+        if let Some(target_escrowed_keys) = target_escrowed_keys {
+            repositories
+                .save_escrowed_keys(&tx, target_user.id, target_escrowed_keys)
+                .await?;
+        }
+
+        debug!("Keys recovered, deleting source wallet user");
+
+        let time = generators.generate();
+        repositories.delete_wallet_user(&tx, wallet_id).await?;
+        repositories
+            .insert_revoked_wallet_certificate(
+                &tx,
+                RevokedWalletCertificate {
+                    wallet_id: wallet_id.to_string(),
+                    reason: CertificateRevocationReason::Reset,
+                    date_time: time,
+                },
+            )
+            .await?;
+
+        for wallet_id in [wallet_id, target_wallet_id] {
+            record_audit_log_event(repositories, &tx, wallet_id, AuditLogEventType::KeyEscrowRecovery, time).await?;
+        }
+        record_audit_log_event(repositories, &tx, wallet_id, AuditLogEventType::Revocation, time).await?;
+
+        tx.commit().await?;
+
+        Ok(key_count)
+    }
+
     async fn new_wallet_certificate<H>(
         &self,
         certificate_signing_key: &impl CertificateSigningKey,
@@ -488,25 +1012,46 @@ impl AccountServer {
             iat: jsonwebtoken::get_current_timestamp(),
         };
 
-        Jwt::sign_with_sub(&cert, certificate_signing_key)
+        Jwt::sign_with_sub_and_thumbprint(&cert, certificate_signing_key)
             .await
             .map_err(RegistrationError::JwtSigning)
     }
 
     fn verify_registration_challenge(
         &self,
-        certificate_signing_pubkey: &EcdsaDecodingKey,
+        certificate_signing_pubkeys: &[VerifyingKey],
         challenge: &[u8],
     ) -> Result<RegistrationChallengeClaims, RegistrationError> {
-        Jwt::parse_and_verify_with_sub(
+        Jwt::parse_and_verify_with_sub_from_keys(
             &String::from_utf8(challenge.to_owned())
                 .map_err(RegistrationError::ChallengeDecoding)?
                 .into(),
-            certificate_signing_pubkey,
+            certificate_signing_pubkeys,
         )
         .map_err(RegistrationError::ChallengeValidation)
     }
 
+    /// Wait for the turn of the wallet the certificate was issued to in the [`InstructionQueue`]. Note that this only
+    /// checks the signature of the certificate, the wallet user it belongs to still needs to be verified.
+    async fn enter_instruction_queue<E>(&self, certificate: &WalletCertificate) -> Result<InstructionQueueGuard<'_>, E>
+    where
+        E: From<WalletCertificateError> + From<WalletBusyError> + From<RateLimitedError>,
+    {
+        let cert_data = certificate
+            .parse_and_verify_with_sub_from_keys(&self.certificate_signing_pubkeys)
+            .map_err(WalletCertificateError::from)?;
+
+        debug!("Checking rate limit of wallet {}", cert_data.wallet_id);
+
+        self.instruction_rate_limiter.check(&cert_data.wallet_id)?;
+
+        debug!("Waiting for other requests of wallet {} to finish", cert_data.wallet_id);
+
+        let guard = self.instruction_queue.enter(&cert_data.wallet_id).await?;
+
+        Ok(guard)
+    }
+
     async fn verify_wallet_certificate<T, R, H>(
         &self,
         certificate: &WalletCertificate,
@@ -520,7 +1065,7 @@ impl AccountServer {
     {
         debug!("Parsing and verifying the provided certificate");
 
-        let cert_data = certificate.parse_and_verify_with_sub(&self.certificate_signing_pubkey)?;
+        let cert_data = certificate.parse_and_verify_with_sub_from_keys(&self.certificate_signing_pubkeys)?;
 
         debug!("Starting database transaction");
 
@@ -542,6 +1087,10 @@ impl AccountServer {
                 debug!("User found for the provided certificate is blocked");
                 Err(WalletCertificateError::UserBlocked)
             }
+            WalletUserQueryResult::Suspended => {
+                debug!("User found for the provided certificate is suspended");
+                Err(WalletCertificateError::UserSuspended)
+            }
             WalletUserQueryResult::Found(user_boxed) => {
                 debug!("Generating pin public key hash");
 
@@ -625,7 +1174,7 @@ impl AccountServer {
             iat: jsonwebtoken::get_current_timestamp(),
         };
 
-        Jwt::sign_with_sub(&claims, instruction_result_signing_key)
+        Jwt::sign_with_sub_and_thumbprint(&claims, instruction_result_signing_key)
             .await
             .map_err(InstructionError::Signing)
     }
@@ -669,6 +1218,34 @@ where
     Ok(())
 }
 
+/// Record an event in the audit log, as part of the provided transaction. The event is also emitted as a tracing event
+/// with the `audit` target, so that it can be routed to a separate log.
+pub(crate) async fn record_audit_log_event<T, R>(
+    repositories: &R,
+    transaction: &T,
+    wallet_id: &str,
+    event_type: AuditLogEventType,
+    date_time: DateTime<Local>,
+) -> Result<(), PersistenceError>
+where
+    T: Committable,
+    R: AuditLogRepository<TransactionType = T>,
+{
+    info!(target: "audit", wallet_id, event = %event_type, "Wallet {}: {}", wallet_id, event_type);
+    increment_counter!("wallet_provider_events_total", "event_type" => event_type.name());
+
+    repositories
+        .insert_audit_log_event(
+            transaction,
+            AuditLogEvent {
+                wallet_id: wallet_id.to_string(),
+                event_type,
+                date_time,
+            },
+        )
+        .await
+}
+
 #[cfg(any(test, feature = "mock"))]
 pub mod mock {
     use chrono::Duration;
@@ -677,12 +1254,15 @@ pub mod mock {
     use super::*;
 
     pub async fn account_server_and_hsm(
-        certificate_signing_pubkey: EcdsaDecodingKey,
+        certificate_signing_pubkey: VerifyingKey,
     ) -> (AccountServer, MockPkcs11Client<HsmError>) {
         let account_server = AccountServer::new(
             Duration::milliseconds(15000),
+            Duration::milliseconds(10000),
+            100,
+            Duration::milliseconds(60000),
             "mock_account_server".into(),
-            certificate_signing_pubkey,
+            vec![certificate_signing_pubkey],
             "encryption_key_1".into(),
             "signing_key_2".into(),
         )
@@ -708,7 +1288,10 @@ mod tests {
 
     use wallet_common::{
         account::{
-            messages::instructions::{CheckPin, InstructionChallengeRequest},
+            messages::{
+                auth::RevocationRequest,
+                instructions::{CheckPin, GenerateKey, InstructionChallengeRequest},
+            },
             serialization::DerVerifyingKey,
         },
         keys::{software::SoftwareEcdsaKey, ConstructibleWithIdentifier, EcdsaKey},
@@ -716,14 +1299,16 @@ mod tests {
     use wallet_provider_domain::{
         generator::mock::MockGenerators,
         model::{
-            hsm::mock::MockPkcs11Client, wallet_user::WalletUserKeys, wrapped_key::WrappedKey, FailingPinPolicy,
+            encrypted::Encrypted, hsm::mock::MockPkcs11Client, wrapped_key::WrappedKey, FailingPinPolicy,
             TimeoutPinPolicy,
         },
         repository::{MockTransaction, MockTransactionStarter},
-        EpochGenerator, FixedUuidGenerator,
+        EpochGenerator,
     };
     use wallet_provider_persistence::repositories::mock::MockTransactionalWalletUserRepository;
 
+    use crate::{anomaly::IpAddressBurstDetector, integrity::IntegrityPolicy};
+
     use super::*;
 
     async fn do_registration(
@@ -747,11 +1332,16 @@ mod tests {
             .expect_begin_transaction()
             .returning(|| Ok(MockTransaction));
         wallet_user_repo.expect_create_wallet_user().returning(|_, _| Ok(()));
+        wallet_user_repo
+            .expect_insert_audit_log_event()
+            .withf(|_, event| event.event_type == AuditLogEventType::Registration)
+            .times(1)
+            .returning(|_, _| Ok(()));
 
         account_server
             .register(
                 certificate_signing_key,
-                &FixedUuidGenerator,
+                &MockGenerators,
                 &wallet_user_repo,
                 hsm,
                 registration_message,
@@ -826,8 +1416,16 @@ mod tests {
                     expiration_date_time: Local::now() + Duration::milliseconds(15000),
                 }),
                 instruction_sequence_number: self.instruction_sequence_number,
+                key_escrow_consent: None,
             })))
         }
+        async fn delete_wallet_user(
+            &self,
+            _transaction: &Self::TransactionType,
+            _wallet_id: &str,
+        ) -> Result<(), PersistenceError> {
+            Ok(())
+        }
         async fn register_unsuccessful_pin_entry(
             &self,
             _transaction: &Self::TransactionType,
@@ -844,6 +1442,22 @@ mod tests {
         ) -> Result<(), PersistenceError> {
             Ok(())
         }
+        async fn update_wallet_user_suspended(
+            &self,
+            _transaction: &Self::TransactionType,
+            _wallet_id: &str,
+            _is_suspended: bool,
+        ) -> Result<(), PersistenceError> {
+            Ok(())
+        }
+        async fn update_key_escrow_consent(
+            &self,
+            _transaction: &Self::TransactionType,
+            _wallet_id: &str,
+            _key_escrow_consent: Option<DateTime<Local>>,
+        ) -> Result<(), PersistenceError> {
+            Ok(())
+        }
         async fn clear_instruction_challenge(
             &self,
             _transaction: &Self::TransactionType,
@@ -851,6 +1465,13 @@ mod tests {
         ) -> Result<(), PersistenceError> {
             Ok(())
         }
+        async fn delete_expired_instruction_challenges(
+            &self,
+            _transaction: &Self::TransactionType,
+            _time: DateTime<Local>,
+        ) -> Result<u64, PersistenceError> {
+            Ok(0)
+        }
         async fn update_instruction_challenge_and_sequence_number(
             &self,
             _transaction: &Self::TransactionType,
@@ -891,6 +1512,95 @@ mod tests {
                 })
                 .collect())
         }
+
+        async fn find_all_keys(
+            &self,
+            _transaction: &Self::TransactionType,
+            _wallet_user_id: Uuid,
+        ) -> Result<HashMap<String, WrappedKey>, PersistenceError> {
+            Ok(HashMap::new())
+        }
+
+        async fn find_key_signature_counts(
+            &self,
+            _transaction: &Self::TransactionType,
+            _wallet_user_id: Uuid,
+        ) -> Result<HashMap<String, u64>, PersistenceError> {
+            Ok(HashMap::new())
+        }
+
+        async fn increment_key_signature_counts(
+            &self,
+            _transaction: &Self::TransactionType,
+            _wallet_user_id: Uuid,
+            _counts: HashMap<String, u64>,
+        ) -> Result<(), PersistenceError> {
+            Ok(())
+        }
+
+        async fn save_escrowed_keys(
+            &self,
+            _transaction: &Self::TransactionType,
+            _wallet_user_id: Uuid,
+            _escrowed_keys: HashMap<String, Encrypted<WrappedKey>>,
+        ) -> Result<(), PersistenceError> {
+            Ok(())
+        }
+
+        async fn delete_escrowed_keys(
+            &self,
+            _transaction: &Self::TransactionType,
+            _wallet_user_id: Uuid,
+        ) -> Result<(), PersistenceError> {
+            Ok(())
+        }
+
+        async fn find_escrowed_keys(
+            &self,
+            _transaction: &Self::TransactionType,
+            _wallet_id: &str,
+        ) -> Result<HashMap<String, Encrypted<WrappedKey>>, PersistenceError> {
+            Ok(HashMap::new())
+        }
+
+        async fn insert_revoked_wallet_certificate(
+            &self,
+            _transaction: &Self::TransactionType,
+            _revoked: RevokedWalletCertificate,
+        ) -> Result<(), PersistenceError> {
+            Ok(())
+        }
+
+        async fn find_revoked_wallet_certificates(
+            &self,
+            _transaction: &Self::TransactionType,
+        ) -> Result<Vec<RevokedWalletCertificate>, PersistenceError> {
+            Ok(vec![RevokedWalletCertificate {
+                wallet_id: "revoked_wallet".to_string(),
+                reason: CertificateRevocationReason::Blocked,
+                date_time: Local.timestamp_opt(1_700_000_000, 0).unwrap(),
+            }])
+        }
+    }
+
+    impl AuditLogRepository for WalletUserTestRepo {
+        type TransactionType = MockTransaction;
+
+        async fn insert_audit_log_event(
+            &self,
+            _transaction: &Self::TransactionType,
+            _event: AuditLogEvent,
+        ) -> Result<(), PersistenceError> {
+            Ok(())
+        }
+        async fn find_audit_log_events(
+            &self,
+            _transaction: &Self::TransactionType,
+            _wallet_id: &str,
+            _since: Option<DateTime<Local>>,
+        ) -> Result<Vec<AuditLogEvent>, PersistenceError> {
+            Ok(vec![])
+        }
     }
 
     impl TransactionStarter for WalletUserTestRepo {
@@ -945,7 +1655,7 @@ mod tests {
                 )
                 .await
                 .expect_err("should return instruction sequence number mismatch error"),
-            ChallengeError::SequenceNumberValidation
+            ChallengeError::SequenceNumberValidation { known: 42 }
         );
 
         let challenge = account_server
@@ -976,6 +1686,7 @@ mod tests {
                     )
                     .await
                     .unwrap(),
+                    None,
                     &instruction_result_signing_key,
                     &MockGenerators,
                     &WalletUserTestRepo {
@@ -985,6 +1696,10 @@ mod tests {
                         instruction_sequence_number: 43,
                     },
                     &FailingPinPolicy,
+                    &IntegrityPolicy::default(),
+                    &KeyUsageLimits::default(),
+                    None,
+                    &None::<IpAddressBurstDetector>,
                     &hsm,
                 )
                 .await
@@ -1000,6 +1715,7 @@ mod tests {
                 Instruction::new_signed(CheckPin, 44, &hw_privkey, &pin_privkey, &challenge, cert.clone())
                     .await
                     .unwrap(),
+                None,
                 &instruction_result_signing_key,
                 &MockGenerators,
                 &WalletUserTestRepo {
@@ -1009,12 +1725,379 @@ mod tests {
                     instruction_sequence_number: 2,
                 },
                 &TimeoutPinPolicy,
+                &IntegrityPolicy::default(),
+                &KeyUsageLimits::default(),
+                None,
+                &None::<IpAddressBurstDetector>,
                 &hsm,
             )
             .await
             .expect("should return instruction result");
     }
 
+    #[tokio::test]
+    async fn test_concurrent_instructions() {
+        let certificate_signing_key = SoftwareEcdsaKey::new("certificate_signing_key");
+        let certificate_signing_pubkey = certificate_signing_key.verifying_key().await.unwrap();
+        let instruction_result_signing_key = SoftwareEcdsaKey::new("instruction_result_signing_key");
+
+        let (mut account_server, hsm) = mock::account_server_and_hsm(certificate_signing_pubkey.into()).await;
+        let hw_privkey = SigningKey::random(&mut OsRng);
+        let pin_privkey = SigningKey::random(&mut OsRng);
+
+        let cert = do_registration(
+            &account_server,
+            &hsm,
+            &certificate_signing_key,
+            &hw_privkey,
+            &pin_privkey,
+        )
+        .await;
+
+        let challenge = random_bytes(32);
+        let deps = WalletUserTestRepo {
+            hw: *hw_privkey.verifying_key(),
+            pin: *pin_privkey.verifying_key(),
+            challenge: Some(challenge.clone()),
+            instruction_sequence_number: 2,
+        };
+
+        let mut instructions = Vec::new();
+        for _ in 0..20 {
+            instructions.push(
+                Instruction::new_signed(CheckPin, 44, &hw_privkey, &pin_privkey, &challenge, cert.clone())
+                    .await
+                    .unwrap(),
+            );
+        }
+
+        // Send many instructions for the same wallet at once, all of which should be handled one after the other.
+        let integrity_policy = IntegrityPolicy::default();
+        let key_usage_limits = KeyUsageLimits::default();
+        let results = futures::future::join_all(instructions.into_iter().map(|instruction| {
+            account_server.handle_instruction(
+                instruction,
+                None,
+                &instruction_result_signing_key,
+                &MockGenerators,
+                &deps,
+                &TimeoutPinPolicy,
+                &integrity_policy,
+                &key_usage_limits,
+                None,
+                &None::<IpAddressBurstDetector>,
+                &hsm,
+            )
+        }))
+        .await;
+
+        assert!(results.iter().all(Result::is_ok));
+        assert!(account_server.instruction_queue.is_empty());
+
+        // While another request of the wallet is being handled for longer than the maximum wait, a new
+        // request should be rejected as the wallet being busy.
+        account_server.instruction_queue = InstructionQueue::new(Duration::milliseconds(10));
+        let wallet_id = cert
+            .parse_and_verify_with_sub(&certificate_signing_pubkey.into())
+            .unwrap()
+            .wallet_id;
+        let _guard = account_server.instruction_queue.enter(&wallet_id).await.unwrap();
+
+        let error = account_server
+            .handle_instruction(
+                Instruction::new_signed(CheckPin, 44, &hw_privkey, &pin_privkey, &challenge, cert.clone())
+                    .await
+                    .unwrap(),
+                None,
+                &instruction_result_signing_key,
+                &MockGenerators,
+                &deps,
+                &TimeoutPinPolicy,
+                &IntegrityPolicy::default(),
+                &KeyUsageLimits::default(),
+                None,
+                &None::<IpAddressBurstDetector>,
+                &hsm,
+            )
+            .await
+            .expect_err("should return wallet busy error");
+
+        assert_matches!(error, InstructionError::WalletBusy(_));
+    }
+
+    struct RejectingAnomalyDetector;
+
+    impl AnomalyDetector for RejectingAnomalyDetector {
+        async fn inspect(&self, metadata: &InstructionMetadata) -> AnomalyVerdict {
+            AnomalyVerdict::Reject(format!("{} from {:?}", metadata.instruction_type, metadata.ip_address))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handle_instruction_anomaly_detected() {
+        let certificate_signing_key = SoftwareEcdsaKey::new("certificate_signing_key");
+        let certificate_signing_pubkey = certificate_signing_key.verifying_key().await.unwrap();
+        let instruction_result_signing_key = SoftwareEcdsaKey::new("instruction_result_signing_key");
+
+        let (account_server, hsm) = mock::account_server_and_hsm(certificate_signing_pubkey.into()).await;
+        let hw_privkey = SigningKey::random(&mut OsRng);
+        let pin_privkey = SigningKey::random(&mut OsRng);
+
+        let cert = do_registration(
+            &account_server,
+            &hsm,
+            &certificate_signing_key,
+            &hw_privkey,
+            &pin_privkey,
+        )
+        .await;
+
+        let challenge = random_bytes(32);
+        let error = account_server
+            .handle_instruction(
+                Instruction::new_signed(CheckPin, 44, &hw_privkey, &pin_privkey, &challenge, cert)
+                    .await
+                    .unwrap(),
+                Some(IpAddr::from([192, 0, 2, 1])),
+                &instruction_result_signing_key,
+                &MockGenerators,
+                &WalletUserTestRepo {
+                    hw: *hw_privkey.verifying_key(),
+                    pin: *pin_privkey.verifying_key(),
+                    challenge: Some(challenge.clone()),
+                    instruction_sequence_number: 2,
+                },
+                &TimeoutPinPolicy,
+                &IntegrityPolicy::default(),
+                &KeyUsageLimits::default(),
+                None,
+                &RejectingAnomalyDetector,
+                &hsm,
+            )
+            .await
+            .expect_err("should reject the instruction");
+
+        assert_matches!(error, InstructionError::AnomalyDetected(AnomalyDetectedError(reason)) if reason == "check_pin from Some(192.0.2.1)");
+    }
+
+    #[tokio::test]
+    async fn test_handle_instruction_integrity_evidence_rejected() {
+        let certificate_signing_key = SoftwareEcdsaKey::new("certificate_signing_key");
+        let certificate_signing_pubkey = certificate_signing_key.verifying_key().await.unwrap();
+        let instruction_result_signing_key = SoftwareEcdsaKey::new("instruction_result_signing_key");
+
+        let (account_server, hsm) = mock::account_server_and_hsm(certificate_signing_pubkey.into()).await;
+        let hw_privkey = SigningKey::random(&mut OsRng);
+        let pin_privkey = SigningKey::random(&mut OsRng);
+
+        let cert = do_registration(
+            &account_server,
+            &hsm,
+            &certificate_signing_key,
+            &hw_privkey,
+            &pin_privkey,
+        )
+        .await;
+
+        let challenge = random_bytes(32);
+        let hw_pubkey = *hw_privkey.verifying_key();
+        let encrypted_pin_pubkey = Encrypter::<VerifyingKey>::encrypt(
+            &MockPkcs11Client::<HsmError>::default(),
+            "encryption_key_1",
+            *pin_privkey.verifying_key(),
+        )
+        .await
+        .unwrap();
+
+        let mut wallet_user_repo = MockTransactionalWalletUserRepository::new();
+        wallet_user_repo
+            .expect_begin_transaction()
+            .returning(|| Ok(MockTransaction));
+        wallet_user_repo.expect_find_wallet_user_by_wallet_id().returning({
+            let challenge = challenge.clone();
+            move |_, wallet_id| {
+                Ok(WalletUserQueryResult::Found(Box::new(WalletUser {
+                    id: uuid!("d944f36e-ffbd-402f-b6f3-418cf4c49e08"),
+                    wallet_id: wallet_id.to_string(),
+                    hw_pubkey: DerVerifyingKey(hw_pubkey),
+                    encrypted_pin_pubkey: encrypted_pin_pubkey.clone(),
+                    unsuccessful_pin_entries: 0,
+                    last_unsuccessful_pin_entry: None,
+                    instruction_challenge: Some(InstructionChallenge {
+                        bytes: challenge.clone(),
+                        expiration_date_time: Local::now() + Duration::milliseconds(15000),
+                    }),
+                    instruction_sequence_number: 2,
+                    key_escrow_consent: None,
+                })))
+            }
+        });
+        wallet_user_repo
+            .expect_clear_instruction_challenge()
+            .returning(|_, _| Ok(()));
+        // Neither the pin retries nor the instruction sequence number should be touched.
+        wallet_user_repo.expect_reset_unsuccessful_pin_entries().never();
+        wallet_user_repo.expect_register_unsuccessful_pin_entry().never();
+        wallet_user_repo.expect_update_instruction_sequence_number().never();
+        // Nor should the instruction be recorded in the audit log.
+        wallet_user_repo.expect_insert_audit_log_event().never();
+
+        let error = account_server
+            .handle_instruction(
+                Instruction::new_signed(
+                    GenerateKey {
+                        identifiers: vec!["key".to_string()],
+                    },
+                    44,
+                    &hw_privkey,
+                    &pin_privkey,
+                    &challenge,
+                    cert,
+                )
+                .await
+                .unwrap(),
+                None,
+                &instruction_result_signing_key,
+                &MockGenerators,
+                &wallet_user_repo,
+                &TimeoutPinPolicy,
+                &IntegrityPolicy {
+                    required: true,
+                    ..Default::default()
+                },
+                &KeyUsageLimits::default(),
+                None,
+                &None::<IpAddressBurstDetector>,
+                &hsm,
+            )
+            .await
+            .expect_err("should reject the missing integrity evidence");
+
+        assert_matches!(error, InstructionError::IntegrityEvidence(IntegrityError::Missing));
+    }
+
+    #[tokio::test]
+    async fn test_revoke() {
+        let certificate_signing_key = SoftwareEcdsaKey::new("certificate_signing_key");
+        let certificate_signing_pubkey = certificate_signing_key.verifying_key().await.unwrap();
+
+        let (account_server, hsm) = mock::account_server_and_hsm(certificate_signing_pubkey.into()).await;
+        let hw_privkey = SigningKey::random(&mut OsRng);
+        let pin_privkey = SigningKey::random(&mut OsRng);
+
+        let cert = do_registration(
+            &account_server,
+            &hsm,
+            &certificate_signing_key,
+            &hw_privkey,
+            &pin_privkey,
+        )
+        .await;
+
+        let repo = WalletUserTestRepo {
+            hw: *hw_privkey.verifying_key(),
+            pin: *pin_privkey.verifying_key(),
+            challenge: None,
+            instruction_sequence_number: 0,
+        };
+
+        // A revocation request signed with another key than the hardware key should be rejected.
+        let revocation_request = RevocationRequestMessage {
+            message: RevocationRequest::new_signed("wallet", &pin_privkey).await.unwrap(),
+            certificate: cert.clone(),
+        };
+
+        let error = account_server
+            .revoke(revocation_request, &repo, &EpochGenerator, &hsm)
+            .await
+            .expect_err("Revocation request should not validate");
+
+        assert_matches!(error, RevocationError::Validation(_));
+
+        // A revocation request that was issued too long ago should be rejected.
+        let revocation_request = RevocationRequestMessage {
+            message: RevocationRequest::new_signed("wallet", &hw_privkey).await.unwrap(),
+            certificate: cert.clone(),
+        };
+
+        let error = account_server
+            .revoke(revocation_request, &repo, &EpochGenerator, &hsm)
+            .await
+            .expect_err("Revocation request should be expired");
+
+        assert_matches!(error, RevocationError::Expired);
+
+        // The EpochGenerator generates the time 0, at which this request was issued.
+        let claims = RevocationRequestClaims {
+            iss: "wallet".to_string(),
+            iat: 0,
+            jti: random_string(32),
+        };
+        let message = Jwt::sign_with_sub(&claims, &hw_privkey).await.unwrap();
+
+        account_server
+            .revoke(
+                RevocationRequestMessage {
+                    message: message.clone(),
+                    certificate: cert.clone(),
+                },
+                &repo,
+                &EpochGenerator,
+                &hsm,
+            )
+            .await
+            .expect("Could not revoke wallet certificate");
+
+        // Sending the same request again should be rejected.
+        let error = account_server
+            .revoke(
+                RevocationRequestMessage {
+                    message,
+                    certificate: cert,
+                },
+                &repo,
+                &EpochGenerator,
+                &hsm,
+            )
+            .await
+            .expect_err("Replayed revocation request should be rejected");
+
+        assert_matches!(error, RevocationError::Replayed);
+    }
+
+    #[tokio::test]
+    async fn test_revocation_list() {
+        let certificate_signing_key = SoftwareEcdsaKey::new("certificate_signing_key");
+        let certificate_signing_pubkey = certificate_signing_key.verifying_key().await.unwrap();
+
+        let (account_server, _) = mock::account_server_and_hsm(certificate_signing_pubkey).await;
+        let repo = WalletUserTestRepo {
+            hw: *SigningKey::random(&mut OsRng).verifying_key(),
+            pin: *SigningKey::random(&mut OsRng).verifying_key(),
+            challenge: None,
+            instruction_sequence_number: 0,
+        };
+
+        let revocation_list = account_server
+            .revocation_list(&certificate_signing_key, &repo, &EpochGenerator)
+            .await
+            .expect("Could not create revocation list");
+
+        let claims = Jwt::parse_and_verify_with_sub_from_keys(&revocation_list, &[certificate_signing_pubkey])
+            .expect("Revocation list should verify");
+
+        assert_eq!(
+            claims.revoked_certificates,
+            vec![RevokedCertificate {
+                wallet_id: "revoked_wallet".to_string(),
+                reason: CertificateRevocationReason::Blocked,
+                revoked_at: 1_700_000_000,
+            }]
+        );
+        assert_eq!(claims.iss, "mock_account_server");
+        assert_eq!(claims.iat, 0);
+    }
+
     #[tokio::test]
     async fn valid_wallet_certificate_should_verify() {
         let certificate_signing_key = SoftwareEcdsaKey::new("certificate_signing_key");
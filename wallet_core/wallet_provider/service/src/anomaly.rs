@@ -0,0 +1,188 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    net::IpAddr,
+    sync::Mutex,
+};
+
+use chrono::{DateTime, Duration, Local};
+
+/// Metadata of an instruction that is passed to an [`AnomalyDetector`], after the wallet certificate of the
+/// instruction has been verified but before the instruction itself is handled.
+#[derive(Debug, Clone)]
+pub struct InstructionMetadata {
+    /// The type of the instruction, i.e. the endpoint on which it was received.
+    pub instruction_type: &'static str,
+    pub wallet_id: String,
+    /// The IP address from which the instruction was sent, if known.
+    pub ip_address: Option<IpAddr>,
+    pub received_at: DateTime<Local>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnomalyVerdict {
+    /// Nothing out of the ordinary was detected.
+    Normal,
+    /// The instruction is suspicious and will be logged, but is handled as usual.
+    Suspicious(String),
+    /// The instruction is rejected with an [`AnomalyDetectedError`].
+    Reject(String),
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("instruction rejected because of anomalous behaviour: {0}")]
+pub struct AnomalyDetectedError(pub String);
+
+/// Extension point for detecting fraud or other anomalous behaviour of wallets, which is consulted for every
+/// instruction the Wallet Provider receives. Deployments can plug in their own implementation, for example one that
+/// forwards the metadata to an external fraud detection service.
+pub trait AnomalyDetector {
+    async fn inspect(&self, metadata: &InstructionMetadata) -> AnomalyVerdict;
+}
+
+/// Treats every instruction as normal when no detector is configured.
+impl<T: AnomalyDetector> AnomalyDetector for Option<T> {
+    async fn inspect(&self, metadata: &InstructionMetadata) -> AnomalyVerdict {
+        match self {
+            Some(detector) => detector.inspect(metadata).await,
+            None => AnomalyVerdict::Normal,
+        }
+    }
+}
+
+/// Default rule in the style of impossible travel detection: the instructions of a single wallet are expected to be
+/// sent from a small number of IP addresses within a short period of time. When the instructions of a wallet within
+/// `window` originate from more than `max_ip_addresses` distinct addresses, the instruction is either flagged as
+/// suspicious or rejected.
+///
+/// Like the [`crate::rate_limiter::RateLimiter`], this only considers instructions handled by the same instance of
+/// the Wallet Provider.
+pub struct IpAddressBurstDetector {
+    max_ip_addresses: usize,
+    window: Duration,
+    reject: bool,
+    state: Mutex<IpAddressBurstState>,
+}
+
+struct IpAddressBurstState {
+    last_cleanup: Option<DateTime<Local>>,
+    wallets: HashMap<String, VecDeque<(IpAddr, DateTime<Local>)>>,
+}
+
+impl IpAddressBurstDetector {
+    pub fn new(max_ip_addresses: usize, window: Duration, reject: bool) -> Self {
+        IpAddressBurstDetector {
+            max_ip_addresses,
+            window,
+            reject,
+            state: Mutex::new(IpAddressBurstState {
+                last_cleanup: None,
+                wallets: HashMap::new(),
+            }),
+        }
+    }
+
+    fn check(&self, wallet_id: &str, ip_address: IpAddr, time: DateTime<Local>) -> AnomalyVerdict {
+        let mut state = self.state.lock().unwrap();
+        let start = time - self.window;
+
+        // Forget the wallets that did not send instructions within the window at most once per window length, so
+        // that these do not accumulate without having to go over all wallets on every instruction.
+        if state.last_cleanup.map_or(true, |last_cleanup| last_cleanup <= start) {
+            state
+                .wallets
+                .retain(|_, addresses| addresses.back().is_some_and(|(_, last_seen)| *last_seen > start));
+            state.last_cleanup = Some(time);
+        }
+
+        let addresses = state.wallets.entry(wallet_id.to_string()).or_default();
+        while addresses.front().is_some_and(|(_, seen)| *seen <= start) {
+            addresses.pop_front();
+        }
+        addresses.retain(|(address, _)| *address != ip_address);
+        addresses.push_back((ip_address, time));
+
+        if addresses.len() <= self.max_ip_addresses {
+            return AnomalyVerdict::Normal;
+        }
+
+        let reason = format!(
+            "instructions sent from {} different IP addresses within {} seconds",
+            addresses.len(),
+            self.window.num_seconds()
+        );
+
+        if self.reject {
+            AnomalyVerdict::Reject(reason)
+        } else {
+            AnomalyVerdict::Suspicious(reason)
+        }
+    }
+}
+
+impl AnomalyDetector for IpAddressBurstDetector {
+    async fn inspect(&self, metadata: &InstructionMetadata) -> AnomalyVerdict {
+        match metadata.ip_address {
+            Some(ip_address) => self.check(&metadata.wallet_id, ip_address, metadata.received_at),
+            None => AnomalyVerdict::Normal,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+
+    use super::*;
+
+    fn ip(last_octet: u8) -> IpAddr {
+        IpAddr::from([192, 0, 2, last_octet])
+    }
+
+    #[test]
+    fn test_ip_address_burst_detector() {
+        let detector = IpAddressBurstDetector::new(2, Duration::minutes(5), false);
+        let now = Local::now();
+
+        assert_eq!(detector.check("wallet1", ip(1), now), AnomalyVerdict::Normal);
+        assert_eq!(detector.check("wallet1", ip(2), now), AnomalyVerdict::Normal);
+        // A repeated address is not counted twice.
+        assert_eq!(detector.check("wallet1", ip(2), now), AnomalyVerdict::Normal);
+        // Other wallets are tracked separately.
+        assert_eq!(detector.check("wallet2", ip(3), now), AnomalyVerdict::Normal);
+
+        assert_matches!(
+            detector.check("wallet1", ip(3), now + Duration::minutes(1)),
+            AnomalyVerdict::Suspicious(_)
+        );
+
+        // After the window has passed, the earlier addresses are forgotten.
+        assert_eq!(
+            detector.check("wallet1", ip(4), now + Duration::minutes(10)),
+            AnomalyVerdict::Normal
+        );
+    }
+
+    #[test]
+    fn test_ip_address_burst_detector_reject() {
+        let detector = IpAddressBurstDetector::new(1, Duration::minutes(5), true);
+        let now = Local::now();
+
+        assert_eq!(detector.check("wallet1", ip(1), now), AnomalyVerdict::Normal);
+        assert_matches!(detector.check("wallet1", ip(2), now), AnomalyVerdict::Reject(_));
+    }
+
+    #[tokio::test]
+    async fn test_no_anomaly_detector() {
+        let metadata = InstructionMetadata {
+            instruction_type: "check_pin",
+            wallet_id: "wallet1".to_string(),
+            ip_address: Some(ip(1)),
+            received_at: Local::now(),
+        };
+
+        assert_eq!(
+            AnomalyDetector::inspect(&None::<IpAddressBurstDetector>, &metadata).await,
+            AnomalyVerdict::Normal
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{path::PathBuf, sync::Arc, time::Duration};
 
 use cryptoki::{
     context::{CInitializeArgs, Pkcs11},
@@ -14,6 +14,7 @@ use p256::{
 };
 use r2d2_cryptoki::{Pool, SessionManager, SessionType};
 use sec1::EcParameters;
+use tracing::instrument;
 
 use wallet_common::{spawn, utils::sha256};
 use wallet_provider_domain::model::{
@@ -120,7 +121,16 @@ pub struct Pkcs11Hsm {
 }
 
 impl Pkcs11Hsm {
-    pub fn new(library_path: PathBuf, user_pin: String, wrapping_key_identifier: String) -> Result<Self> {
+    /// Connect to the HSM through the PKCS#11 library at `library_path`, using a pool of at most `max_sessions`
+    /// sessions. Sessions are replaced after `max_session_lifetime` and are checked before being handed out, so that
+    /// sessions that were invalidated, e.g. because the connection to the HSM was lost, are not used.
+    pub fn new(
+        library_path: PathBuf,
+        user_pin: String,
+        max_sessions: u8,
+        max_session_lifetime: Duration,
+        wrapping_key_identifier: String,
+    ) -> Result<Self> {
         let pkcs11_client = Pkcs11::new(library_path)?;
         pkcs11_client.initialize(CInitializeArgs::OsThreads)?;
 
@@ -131,13 +141,40 @@ impl Pkcs11Hsm {
 
         let manager = SessionManager::new(pkcs11_client, slot, SessionType::RwUser(AuthPin::new(user_pin)));
 
-        let pool = Pool::builder().build(manager).unwrap();
+        let pool = Pool::builder()
+            .max_size(max_sessions.into())
+            .max_lifetime(Some(max_session_lifetime))
+            .test_on_check_out(true)
+            .build(manager)?;
+
         Ok(Self {
             pool,
             wrapping_key_identifier,
         })
     }
 
+    /// The same HSM, sharing the pool of sessions, but wrapping and unwrapping attestation private keys with another
+    /// key. This is used to isolate the keys of the wallet users of different tenants.
+    pub fn with_wrapping_key_identifier(&self, wrapping_key_identifier: String) -> Self {
+        Self {
+            pool: self.pool.clone(),
+            wrapping_key_identifier,
+        }
+    }
+
+    /// Check that the HSM can be reached, by obtaining a session from the pool and querying its state.
+    pub async fn health_check(&self) -> Result<()> {
+        let pool = self.pool.clone();
+
+        spawn::blocking(move || {
+            let session = pool.get()?;
+            let _ = session.get_session_info()?;
+
+            Ok(())
+        })
+        .await
+    }
+
     async fn get_key_handle(&self, identifier: &str, handle_type: HandleType) -> Result<ObjectHandle> {
         let pool = self.pool.clone();
         let identifier = String::from(identifier);
@@ -273,6 +310,7 @@ impl Hsm for Pkcs11Hsm {
 }
 
 impl Pkcs11Client for Pkcs11Hsm {
+    #[instrument(skip_all)]
     async fn generate_generic_secret_key(&self, identifier: &str) -> Result<PrivateKeyHandle> {
         let pool = self.pool.clone();
         let identifier = String::from(identifier);
@@ -298,6 +336,7 @@ impl Pkcs11Client for Pkcs11Hsm {
         .await
     }
 
+    #[instrument(skip_all)]
     async fn generate_wrapping_key(&self, identifier: &str) -> Result<PrivateKeyHandle> {
         let pool = self.pool.clone();
         let identifier = String::from(identifier);
@@ -324,6 +363,7 @@ impl Pkcs11Client for Pkcs11Hsm {
         .await
     }
 
+    #[instrument(skip_all)]
     async fn generate_session_signing_key_pair(&self) -> Result<(PublicKeyHandle, PrivateKeyHandle)> {
         let pool = self.pool.clone();
 
@@ -350,6 +390,7 @@ impl Pkcs11Client for Pkcs11Hsm {
         .await
     }
 
+    #[instrument(skip_all)]
     async fn generate_signing_key_pair(&self, identifier: &str) -> Result<(PublicKeyHandle, PrivateKeyHandle)> {
         let pool = self.pool.clone();
         let identifier = String::from(identifier);
@@ -379,18 +420,21 @@ impl Pkcs11Client for Pkcs11Hsm {
         .await
     }
 
+    #[instrument(skip_all)]
     async fn get_private_key_handle(&self, identifier: &str) -> Result<PrivateKeyHandle> {
         self.get_key_handle(identifier, HandleType::Private)
             .await
             .map(PrivateKeyHandle)
     }
 
+    #[instrument(skip_all)]
     async fn get_public_key_handle(&self, identifier: &str) -> Result<PublicKeyHandle> {
         self.get_key_handle(identifier, HandleType::Public)
             .await
             .map(PublicKeyHandle)
     }
 
+    #[instrument(skip_all)]
     async fn get_verifying_key(&self, public_key_handle: PublicKeyHandle) -> Result<VerifyingKey> {
         let pool = self.pool.clone();
 
@@ -414,6 +458,7 @@ impl Pkcs11Client for Pkcs11Hsm {
         .await
     }
 
+    #[instrument(skip_all)]
     async fn wrap_key(&self, wrapping_key: PrivateKeyHandle, key: PrivateKeyHandle) -> Result<WrappedKey> {
         let pool = self.pool.clone();
 
@@ -431,6 +476,7 @@ impl Pkcs11Client for Pkcs11Hsm {
         .await
     }
 
+    #[instrument(skip_all)]
     async fn unwrap_signing_key(
         &self,
         unwrapping_key: PrivateKeyHandle,
@@ -459,6 +505,7 @@ impl Pkcs11Client for Pkcs11Hsm {
         .map(PrivateKeyHandle)
     }
 
+    #[instrument(skip_all)]
     async fn delete_key(&self, private_key_handle: PrivateKeyHandle) -> Result<()> {
         let pool = self.pool.clone();
 
@@ -470,6 +517,7 @@ impl Pkcs11Client for Pkcs11Hsm {
         .await
     }
 
+    #[instrument(skip_all)]
     async fn sign(
         &self,
         private_key_handle: PrivateKeyHandle,
@@ -491,6 +539,7 @@ impl Pkcs11Client for Pkcs11Hsm {
         .await
     }
 
+    #[instrument(skip_all)]
     async fn verify(
         &self,
         private_key_handle: PrivateKeyHandle,
@@ -514,6 +563,7 @@ impl Pkcs11Client for Pkcs11Hsm {
         .await
     }
 
+    #[instrument(skip_all)]
     async fn random_bytes(&self, length: u32) -> Result<Vec<u8>> {
         let pool = self.pool.clone();
 
@@ -525,6 +575,7 @@ impl Pkcs11Client for Pkcs11Hsm {
         .await
     }
 
+    #[instrument(skip_all)]
     async fn encrypt(
         &self,
         key_handle: PrivateKeyHandle,
@@ -542,6 +593,7 @@ impl Pkcs11Client for Pkcs11Hsm {
         .await
     }
 
+    #[instrument(skip_all)]
     async fn decrypt(
         &self,
         key_handle: PrivateKeyHandle,
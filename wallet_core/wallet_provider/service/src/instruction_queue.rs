@@ -0,0 +1,151 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use chrono::Duration;
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
+
+#[derive(Debug, thiserror::Error)]
+#[error("wallet is busy handling another instruction")]
+pub struct WalletBusyError;
+
+/// Serializes the handling of instruction challenges and instructions per wallet, so that concurrent requests from
+/// the same wallet (e.g. when the wallet retries a request that is still being handled) cannot both pass the sequence
+/// number check or update the same wallet user at the same time. Requests for different wallets never wait for each
+/// other. Requests for the same wallet are handled in the order in which they arrived, as the underlying lock is
+/// fair. A request that has to wait longer than the configured maximum is rejected with a [`WalletBusyError`].
+///
+/// Note that this only serializes requests that are handled by the same instance of the Wallet Provider.
+pub struct InstructionQueue {
+    max_wait: std::time::Duration,
+    wallets: Mutex<HashMap<String, Arc<AsyncMutex<()>>>>,
+}
+
+/// Holds the turn of a wallet in the [`InstructionQueue`] until it is dropped.
+pub struct InstructionQueueGuard<'a> {
+    queue: &'a InstructionQueue,
+    wallet_id: String,
+    guard: Option<OwnedMutexGuard<()>>,
+}
+
+impl InstructionQueue {
+    /// Create a new queue, in which a request waits at most `max_wait` for its turn. A negative duration is
+    /// interpreted as not waiting at all.
+    pub fn new(max_wait: Duration) -> Self {
+        InstructionQueue {
+            max_wait: max_wait.to_std().unwrap_or_default(),
+            wallets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Wait for the turn of the wallet with the specified id. Its turn ends when the returned guard is dropped.
+    pub async fn enter(&self, wallet_id: &str) -> Result<InstructionQueueGuard<'_>, WalletBusyError> {
+        let wallet_lock = Arc::clone(
+            self.wallets
+                .lock()
+                .unwrap()
+                .entry(wallet_id.to_string())
+                .or_default(),
+        );
+
+        let guard = tokio::time::timeout(self.max_wait, wallet_lock.lock_owned())
+            .await
+            .map_err(|_| WalletBusyError)?;
+
+        Ok(InstructionQueueGuard {
+            queue: self,
+            wallet_id: wallet_id.to_string(),
+            guard: Some(guard),
+        })
+    }
+
+    /// The number of wallets that currently have a request being handled or waiting.
+    pub fn len(&self) -> usize {
+        self.wallets.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Drop for InstructionQueueGuard<'_> {
+    fn drop(&mut self) {
+        // Release the lock first, so that the reference count below only includes the requests that are waiting.
+        self.guard.take();
+
+        // Remove the lock for this wallet if no other request holds or waits for it. As the map is locked while
+        // checking, no other request can obtain a new reference to it in the meantime.
+        let mut wallets = self.queue.wallets.lock().unwrap();
+        if wallets
+            .get(&self.wallet_id)
+            .is_some_and(|wallet_lock| Arc::strong_count(wallet_lock) == 1)
+        {
+            wallets.remove(&self.wallet_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    use assert_matches::assert_matches;
+    use futures::future;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_instruction_queue_concurrent_same_wallet() {
+        let queue = InstructionQueue::new(Duration::seconds(10));
+        let sequence_number = AtomicU64::new(0);
+
+        // Simulate many concurrent instructions for the same wallet, each of which reads the sequence number,
+        // yields to the other tasks and then writes the incremented sequence number back.
+        let results = future::join_all((0..100).map(|_| async {
+            let _guard = queue.enter("wallet_1").await?;
+
+            let current = sequence_number.load(Ordering::SeqCst);
+            tokio::task::yield_now().await;
+            sequence_number.store(current + 1, Ordering::SeqCst);
+
+            Ok::<_, WalletBusyError>(())
+        }))
+        .await;
+
+        // Without serialization updates would have been lost.
+        assert!(results.iter().all(Result::is_ok));
+        assert_eq!(sequence_number.load(Ordering::SeqCst), 100);
+        assert!(queue.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_instruction_queue_different_wallets() {
+        let queue = InstructionQueue::new(Duration::zero());
+
+        // Holding the turn of one wallet does not block another one.
+        let _guard1 = queue.enter("wallet_1").await.unwrap();
+        let guard2 = queue.enter("wallet_2").await.unwrap();
+
+        assert_eq!(queue.len(), 2);
+
+        drop(guard2);
+
+        assert_eq!(queue.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_instruction_queue_busy() {
+        let queue = InstructionQueue::new(Duration::milliseconds(10));
+
+        let guard = queue.enter("wallet_1").await.unwrap();
+
+        // Another request for the same wallet times out while the first is still being handled.
+        assert_matches!(queue.enter("wallet_1").await.err(), Some(WalletBusyError));
+
+        // After the first request has finished, the wallet can be entered again.
+        drop(guard);
+        let _guard = queue.enter("wallet_1").await.expect("wallet should no longer be busy");
+    }
+}
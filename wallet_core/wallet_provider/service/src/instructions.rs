@@ -1,24 +1,36 @@
 use std::{collections::HashMap, sync::Arc};
 
+use chrono::{DateTime, Local, Utc};
 use serde::Serialize;
 use uuid::Uuid;
 
 use wallet_common::{
     account::{
-        messages::instructions::{CheckPin, GenerateKey, GenerateKeyResult, Sign, SignResult},
+        messages::instructions::{
+            BatchedInstruction, BatchedInstructionResult, CheckPin, DeleteWallet, ExportData, ExportDataResult,
+            ExportedEvent, ExportedKey, GenerateKey, GenerateKeyResult, InstructionBatch, InstructionBatchResult, Sign,
+            SignResult, UpdateKeyEscrowConsent,
+        },
         serialization::{DerSignature, DerVerifyingKey},
     },
     generator::Generator,
 };
 use wallet_provider_domain::{
     model::{
-        hsm::{WalletUserHsm, WrappedKeySigningPayload},
+        audit_log::AuditLogEventType,
+        hsm::{Hsm, WalletUserHsm, WrappedKeySigningPayload},
+        revocation::{CertificateRevocationReason, RevokedWalletCertificate},
         wallet_user::{WalletUser, WalletUserKey, WalletUserKeys},
     },
-    repository::{Committable, TransactionStarter, WalletUserRepository},
+    repository::{AuditLogRepository, Committable, TransactionStarter, WalletUserRepository},
 };
 
-use crate::{account_server::InstructionError, hsm::HsmError};
+use crate::{
+    account_server::{record_audit_log_event, InstructionError},
+    hsm::HsmError,
+    key_escrow::KeyEscrow,
+    key_usage::KeyUsageLimits,
+};
 
 pub trait HandleInstruction {
     type Result: Serialize;
@@ -26,9 +38,13 @@ pub trait HandleInstruction {
     async fn handle<T>(
         self,
         wallet_user: &WalletUser,
-        uuid_generator: &impl Generator<Uuid>,
-        wallet_user_repository: &(impl TransactionStarter<TransactionType = T> + WalletUserRepository<TransactionType = T>),
-        wallet_user_hsm: &impl WalletUserHsm<Error = HsmError>,
+        generators: &(impl Generator<Uuid> + Generator<DateTime<Local>>),
+        wallet_user_repository: &(impl TransactionStarter<TransactionType = T>
+              + WalletUserRepository<TransactionType = T>
+              + AuditLogRepository<TransactionType = T>),
+        wallet_user_hsm: &(impl WalletUserHsm<Error = HsmError> + Hsm<Error = HsmError>),
+        key_usage_limits: &KeyUsageLimits,
+        key_escrow: Option<&KeyEscrow>,
     ) -> Result<Self::Result, InstructionError>
     where
         T: Committable;
@@ -40,9 +56,13 @@ impl HandleInstruction for CheckPin {
     async fn handle<T>(
         self,
         _wallet_user: &WalletUser,
-        _uuid_generator: &impl Generator<Uuid>,
-        _wallet_user_repository: &(impl TransactionStarter<TransactionType = T> + WalletUserRepository<TransactionType = T>),
-        _wallet_user_hsm: &impl WalletUserHsm<Error = HsmError>,
+        _generators: &(impl Generator<Uuid> + Generator<DateTime<Local>>),
+        _wallet_user_repository: &(impl TransactionStarter<TransactionType = T>
+              + WalletUserRepository<TransactionType = T>
+              + AuditLogRepository<TransactionType = T>),
+        _wallet_user_hsm: &(impl WalletUserHsm<Error = HsmError> + Hsm<Error = HsmError>),
+        _key_usage_limits: &KeyUsageLimits,
+        _key_escrow: Option<&KeyEscrow>,
     ) -> Result<(), InstructionError>
     where
         T: Committable,
@@ -51,15 +71,120 @@ impl HandleInstruction for CheckPin {
     }
 }
 
+impl HandleInstruction for DeleteWallet {
+    type Result = ();
+
+    async fn handle<T>(
+        self,
+        wallet_user: &WalletUser,
+        generators: &(impl Generator<Uuid> + Generator<DateTime<Local>>),
+        wallet_user_repository: &(impl TransactionStarter<TransactionType = T>
+              + WalletUserRepository<TransactionType = T>
+              + AuditLogRepository<TransactionType = T>),
+        _wallet_user_hsm: &(impl WalletUserHsm<Error = HsmError> + Hsm<Error = HsmError>),
+        _key_usage_limits: &KeyUsageLimits,
+        _key_escrow: Option<&KeyEscrow>,
+    ) -> Result<(), InstructionError>
+    where
+        T: Committable,
+    {
+        let tx = wallet_user_repository.begin_transaction().await?;
+        wallet_user_repository
+            .delete_wallet_user(&tx, &wallet_user.wallet_id)
+            .await?;
+        wallet_user_repository
+            .insert_revoked_wallet_certificate(
+                &tx,
+                RevokedWalletCertificate {
+                    wallet_id: wallet_user.wallet_id.clone(),
+                    reason: CertificateRevocationReason::Reset,
+                    date_time: generators.generate(),
+                },
+            )
+            .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+}
+
+impl HandleInstruction for ExportData {
+    type Result = ExportDataResult;
+
+    async fn handle<T>(
+        self,
+        wallet_user: &WalletUser,
+        _generators: &(impl Generator<Uuid> + Generator<DateTime<Local>>),
+        wallet_user_repository: &(impl TransactionStarter<TransactionType = T>
+              + WalletUserRepository<TransactionType = T>
+              + AuditLogRepository<TransactionType = T>),
+        _wallet_user_hsm: &(impl WalletUserHsm<Error = HsmError> + Hsm<Error = HsmError>),
+        _key_usage_limits: &KeyUsageLimits,
+        _key_escrow: Option<&KeyEscrow>,
+    ) -> Result<ExportDataResult, InstructionError>
+    where
+        T: Committable,
+    {
+        let tx = wallet_user_repository.begin_transaction().await?;
+        let signature_counts = wallet_user_repository
+            .find_key_signature_counts(&tx, wallet_user.id)
+            .await?;
+        let events = wallet_user_repository
+            .find_audit_log_events(&tx, &wallet_user.wallet_id, None)
+            .await?;
+        tx.commit().await?;
+
+        let mut keys: Vec<ExportedKey> = signature_counts
+            .into_iter()
+            .map(|(identifier, signature_count)| ExportedKey {
+                identifier,
+                signature_count,
+            })
+            .collect();
+        keys.sort_by(|a, b| a.identifier.cmp(&b.identifier));
+
+        let registration_date_time = events
+            .iter()
+            .find(|event| event.event_type == AuditLogEventType::Registration)
+            .map(|event| event.date_time.with_timezone(&Utc));
+
+        let events = events
+            .into_iter()
+            .map(|event| ExportedEvent {
+                event_type: event.event_type.name().to_string(),
+                instruction: event.event_type.instruction().map(str::to_string),
+                date_time: event.date_time.with_timezone(&Utc),
+            })
+            .collect();
+
+        Ok(ExportDataResult {
+            wallet_id: wallet_user.wallet_id.clone(),
+            hw_pubkey: wallet_user.hw_pubkey.clone(),
+            registration_date_time,
+            instruction_sequence_number: wallet_user.instruction_sequence_number,
+            unsuccessful_pin_entries: wallet_user.unsuccessful_pin_entries,
+            last_unsuccessful_pin_entry: wallet_user
+                .last_unsuccessful_pin_entry
+                .map(|date_time| date_time.with_timezone(&Utc)),
+            keys,
+            events,
+        })
+    }
+}
+
 impl HandleInstruction for GenerateKey {
     type Result = GenerateKeyResult;
 
     async fn handle<T>(
         self,
         wallet_user: &WalletUser,
-        uuid_generator: &impl Generator<Uuid>,
-        wallet_user_repository: &(impl TransactionStarter<TransactionType = T> + WalletUserRepository<TransactionType = T>),
-        wallet_user_hsm: &impl WalletUserHsm<Error = HsmError>,
+        generators: &(impl Generator<Uuid> + Generator<DateTime<Local>>),
+        wallet_user_repository: &(impl TransactionStarter<TransactionType = T>
+              + WalletUserRepository<TransactionType = T>
+              + AuditLogRepository<TransactionType = T>),
+        wallet_user_hsm: &(impl WalletUserHsm<Error = HsmError> + Hsm<Error = HsmError>),
+        _key_usage_limits: &KeyUsageLimits,
+        key_escrow: Option<&KeyEscrow>,
     ) -> Result<GenerateKeyResult, InstructionError>
     where
         T: Committable,
@@ -73,7 +198,7 @@ impl HandleInstruction for GenerateKey {
                 (
                     (identifier.clone(), DerVerifyingKey::from(public_key)),
                     WalletUserKey {
-                        wallet_user_key_id: uuid_generator.generate(),
+                        wallet_user_key_id: generators.generate(),
                         key_identifier: identifier,
                         key: wrapped_key,
                     },
@@ -81,6 +206,19 @@ impl HandleInstruction for GenerateKey {
             })
             .unzip();
 
+        // While the user consents to key escrow, newly generated keys are escrowed as well.
+        let escrowed_keys = match key_escrow {
+            Some(key_escrow) if wallet_user.key_escrow_consent.is_some() => {
+                let keys = wrapped_keys
+                    .iter()
+                    .map(|key| (key.key_identifier.clone(), key.key.clone()))
+                    .collect();
+
+                Some(key_escrow.escrow_keys(wallet_user_hsm, keys).await?)
+            }
+            _ => None,
+        };
+
         let tx = wallet_user_repository.begin_transaction().await?;
         wallet_user_repository
             .save_keys(
@@ -91,6 +229,11 @@ impl HandleInstruction for GenerateKey {
                 },
             )
             .await?;
+        if let Some(escrowed_keys) = escrowed_keys {
+            wallet_user_repository
+                .save_escrowed_keys(&tx, wallet_user.id, escrowed_keys)
+                .await?;
+        }
         tx.commit().await?;
 
         Ok(GenerateKeyResult { public_keys })
@@ -103,9 +246,13 @@ impl HandleInstruction for Sign {
     async fn handle<T>(
         self,
         wallet_user: &WalletUser,
-        _uuid_generator: &impl Generator<Uuid>,
-        wallet_user_repository: &(impl TransactionStarter<TransactionType = T> + WalletUserRepository<TransactionType = T>),
-        wallet_user_hsm: &impl WalletUserHsm<Error = HsmError>,
+        _generators: &(impl Generator<Uuid> + Generator<DateTime<Local>>),
+        wallet_user_repository: &(impl TransactionStarter<TransactionType = T>
+              + WalletUserRepository<TransactionType = T>
+              + AuditLogRepository<TransactionType = T>),
+        wallet_user_hsm: &(impl WalletUserHsm<Error = HsmError> + Hsm<Error = HsmError>),
+        key_usage_limits: &KeyUsageLimits,
+        _key_escrow: Option<&KeyEscrow>,
     ) -> Result<SignResult, InstructionError>
     where
         T: Committable,
@@ -120,6 +267,24 @@ impl HandleInstruction for Sign {
         let mut found_keys = wallet_user_repository
             .find_keys_by_identifiers(&tx, wallet_user.id, identifiers)
             .await?;
+
+        if key_usage_limits.is_limited() {
+            // Account for the signatures before they are created, so that failing to sign counts towards the limits.
+            let requested_counts = identifiers.iter().fold(HashMap::new(), |mut counts, identifier| {
+                *counts.entry(identifier.clone()).or_insert(0u64) += 1;
+                counts
+            });
+            let current_counts = wallet_user_repository
+                .find_key_signature_counts(&tx, wallet_user.id)
+                .await?;
+
+            key_usage_limits.check(&current_counts, &requested_counts)?;
+
+            wallet_user_repository
+                .increment_key_signature_counts(&tx, wallet_user.id, requested_counts)
+                .await?;
+        }
+
         tx.commit().await?;
 
         let message_with_keys: Vec<WrappedKeySigningPayload> = self
@@ -155,28 +320,155 @@ impl HandleInstruction for Sign {
     }
 }
 
+impl HandleInstruction for UpdateKeyEscrowConsent {
+    type Result = ();
+
+    async fn handle<T>(
+        self,
+        wallet_user: &WalletUser,
+        generators: &(impl Generator<Uuid> + Generator<DateTime<Local>>),
+        wallet_user_repository: &(impl TransactionStarter<TransactionType = T>
+              + WalletUserRepository<TransactionType = T>
+              + AuditLogRepository<TransactionType = T>),
+        wallet_user_hsm: &(impl WalletUserHsm<Error = HsmError> + Hsm<Error = HsmError>),
+        _key_usage_limits: &KeyUsageLimits,
+        key_escrow: Option<&KeyEscrow>,
+    ) -> Result<(), InstructionError>
+    where
+        T: Committable,
+    {
+        let key_escrow = key_escrow.ok_or(InstructionError::KeyEscrowUnavailable)?;
+        let time: DateTime<Local> = generators.generate();
+
+        let tx = wallet_user_repository.begin_transaction().await?;
+
+        let event_type = if self.consent {
+            // Escrow all keys the wallet has now, including those that may have been escrowed before.
+            let keys = wallet_user_repository.find_all_keys(&tx, wallet_user.id).await?;
+            let escrowed_keys = key_escrow.escrow_keys(wallet_user_hsm, keys).await?;
+
+            wallet_user_repository
+                .save_escrowed_keys(&tx, wallet_user.id, escrowed_keys)
+                .await?;
+            wallet_user_repository
+                .update_key_escrow_consent(&tx, &wallet_user.wallet_id, Some(time))
+                .await?;
+
+            AuditLogEventType::KeyEscrowConsentGiven
+        } else {
+            wallet_user_repository.delete_escrowed_keys(&tx, wallet_user.id).await?;
+            wallet_user_repository
+                .update_key_escrow_consent(&tx, &wallet_user.wallet_id, None)
+                .await?;
+
+            AuditLogEventType::KeyEscrowConsentWithdrawn
+        };
+
+        record_audit_log_event(wallet_user_repository, &tx, &wallet_user.wallet_id, event_type, time).await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+}
+
+impl HandleInstruction for InstructionBatch {
+    type Result = InstructionBatchResult;
+
+    async fn handle<T>(
+        self,
+        wallet_user: &WalletUser,
+        generators: &(impl Generator<Uuid> + Generator<DateTime<Local>>),
+        wallet_user_repository: &(impl TransactionStarter<TransactionType = T>
+              + WalletUserRepository<TransactionType = T>
+              + AuditLogRepository<TransactionType = T>),
+        wallet_user_hsm: &(impl WalletUserHsm<Error = HsmError> + Hsm<Error = HsmError>),
+        key_usage_limits: &KeyUsageLimits,
+        key_escrow: Option<&KeyEscrow>,
+    ) -> Result<InstructionBatchResult, InstructionError>
+    where
+        T: Committable,
+    {
+        let mut results = Vec::with_capacity(self.instructions.len());
+
+        // Handle the instructions in order, as later instructions may depend on earlier ones,
+        // e.g. when signing with keys that are generated in the same batch.
+        for instruction in self.instructions {
+            let result = match instruction {
+                BatchedInstruction::GenerateKey(instruction) => BatchedInstructionResult::GenerateKey(
+                    instruction
+                        .handle(
+                            wallet_user,
+                            generators,
+                            wallet_user_repository,
+                            wallet_user_hsm,
+                            key_usage_limits,
+                            key_escrow,
+                        )
+                        .await?,
+                ),
+                BatchedInstruction::Sign(instruction) => BatchedInstructionResult::Sign(
+                    instruction
+                        .handle(
+                            wallet_user,
+                            generators,
+                            wallet_user_repository,
+                            wallet_user_hsm,
+                            key_usage_limits,
+                            key_escrow,
+                        )
+                        .await?,
+                ),
+            };
+
+            results.push(result);
+        }
+
+        Ok(InstructionBatchResult { results })
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use std::collections::HashMap;
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
 
+    use assert_matches::assert_matches;
+    use chrono::Local;
     use p256::ecdsa::{signature::Verifier, SigningKey};
     use rand::rngs::OsRng;
 
     use wallet_common::{
         account::{
-            messages::instructions::{CheckPin, GenerateKey, Sign},
+            messages::instructions::{
+                BatchedInstructionResult, CheckPin, DeleteWallet, ExportData, GenerateKey, InstructionBatch,
+                InstructionEndpoint, Sign, UpdateKeyEscrowConsent,
+            },
             serialization::Base64Bytes,
         },
         utils::random_bytes,
     };
     use wallet_provider_domain::{
-        model::{hsm::mock::MockPkcs11Client, wallet_user, wrapped_key::WrappedKey},
+        generator::mock::MockGenerators,
+        model::{
+            audit_log::{AuditLogEvent, AuditLogEventType},
+            hsm::mock::MockPkcs11Client,
+            revocation::CertificateRevocationReason,
+            wallet_user::{self, WalletUserKeys},
+            wrapped_key::WrappedKey,
+        },
         repository::MockTransaction,
-        FixedUuidGenerator,
     };
     use wallet_provider_persistence::repositories::mock::MockTransactionalWalletUserRepository;
 
-    use crate::instructions::HandleInstruction;
+    use crate::{
+        account_server::InstructionError,
+        instructions::HandleInstruction,
+        key_escrow::KeyEscrow,
+        key_usage::{KeyUsageLimitError, KeyUsageLimits},
+    };
 
     #[tokio::test]
     async fn should_handle_checkpin() {
@@ -186,14 +478,112 @@ mod tests {
         instruction
             .handle(
                 &wallet_user,
-                &FixedUuidGenerator,
+                &MockGenerators,
                 &MockTransactionalWalletUserRepository::new(),
                 &MockPkcs11Client::default(),
+                &KeyUsageLimits::default(),
+                None,
             )
             .await
             .unwrap();
     }
 
+    #[tokio::test]
+    async fn should_handle_delete_wallet() {
+        let wallet_user = wallet_user::mock::wallet_user_1();
+        let wallet_id = wallet_user.wallet_id.clone();
+
+        let mut wallet_user_repo = MockTransactionalWalletUserRepository::new();
+        wallet_user_repo
+            .expect_begin_transaction()
+            .returning(|| Ok(MockTransaction));
+        wallet_user_repo
+            .expect_delete_wallet_user()
+            .withf({
+                let wallet_id = wallet_id.clone();
+                move |_, id| id == wallet_id
+            })
+            .times(1)
+            .returning(|_, _| Ok(()));
+        wallet_user_repo
+            .expect_insert_revoked_wallet_certificate()
+            .withf(move |_, revoked| {
+                revoked.wallet_id == wallet_id && revoked.reason == CertificateRevocationReason::Reset
+            })
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let instruction = DeleteWallet {};
+        instruction
+            .handle(
+                &wallet_user,
+                &MockGenerators,
+                &wallet_user_repo,
+                &MockPkcs11Client::default(),
+                &KeyUsageLimits::default(),
+                None,
+            )
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn should_handle_export_data() {
+        let wallet_user = wallet_user::mock::wallet_user_1();
+        let wallet_id = wallet_user.wallet_id.clone();
+        let registration_date_time = Local::now();
+
+        let mut wallet_user_repo = MockTransactionalWalletUserRepository::new();
+        wallet_user_repo
+            .expect_begin_transaction()
+            .returning(|| Ok(MockTransaction));
+        wallet_user_repo
+            .expect_find_key_signature_counts()
+            .return_once(|_, _| Ok(HashMap::from([("key2".to_string(), 3), ("key1".to_string(), 0)])));
+        wallet_user_repo
+            .expect_find_audit_log_events()
+            .withf(move |_, id, since| id == wallet_id && since.is_none())
+            .return_once(move |_, wallet_id, _| {
+                Ok(vec![
+                    AuditLogEvent {
+                        wallet_id: wallet_id.to_string(),
+                        event_type: AuditLogEventType::Registration,
+                        date_time: registration_date_time,
+                    },
+                    AuditLogEvent {
+                        wallet_id: wallet_id.to_string(),
+                        event_type: AuditLogEventType::Instruction(ExportData::ENDPOINT.to_string()),
+                        date_time: registration_date_time,
+                    },
+                ])
+            });
+
+        let result = ExportData
+            .handle(
+                &wallet_user,
+                &MockGenerators,
+                &wallet_user_repo,
+                &MockPkcs11Client::default(),
+                &KeyUsageLimits::default(),
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(result.wallet_id, wallet_user.wallet_id);
+        assert_eq!(result.registration_date_time, Some(registration_date_time.into()));
+        assert_eq!(
+            result
+                .keys
+                .iter()
+                .map(|key| (key.identifier.as_str(), key.signature_count))
+                .collect::<Vec<_>>(),
+            vec![("key1", 0), ("key2", 3)]
+        );
+        assert_eq!(result.events.len(), 2);
+        assert_eq!(result.events[1].instruction.as_deref(), Some(ExportData::ENDPOINT));
+    }
+
     #[tokio::test]
     async fn should_handle_generate_key() {
         let wallet_user = wallet_user::mock::wallet_user_1();
@@ -211,9 +601,11 @@ mod tests {
         let result = instruction
             .handle(
                 &wallet_user,
-                &FixedUuidGenerator,
+                &MockGenerators,
                 &wallet_user_repo,
                 &MockPkcs11Client::default(),
+                &KeyUsageLimits::default(),
+                None,
             )
             .await
             .unwrap();
@@ -256,7 +648,14 @@ mod tests {
             });
 
         let result = instruction
-            .handle(&wallet_user, &FixedUuidGenerator, &wallet_user_repo, &pkcs11_client)
+            .handle(
+                &wallet_user,
+                &MockGenerators,
+                &wallet_user_repo,
+                &pkcs11_client,
+                &KeyUsageLimits::default(),
+                None,
+            )
             .await
             .unwrap();
 
@@ -267,4 +666,246 @@ mod tests {
                 signing_key.verifying_key().verify(&random_msg.0, &signature.0).unwrap();
             })
     }
+
+    #[tokio::test]
+    async fn should_reject_sign_exceeding_key_usage_limits() {
+        let wallet_user = wallet_user::mock::wallet_user_1();
+
+        let random_msg: Base64Bytes = random_bytes(32).into();
+        let instruction = Sign {
+            messages_with_identifiers: vec![(random_msg, vec!["key1".to_string()])],
+        };
+        let signing_key_bytes = SigningKey::random(&mut OsRng).to_bytes().to_vec();
+
+        let mut wallet_user_repo = MockTransactionalWalletUserRepository::new();
+        wallet_user_repo
+            .expect_begin_transaction()
+            .returning(|| Ok(MockTransaction));
+        wallet_user_repo
+            .expect_find_keys_by_identifiers()
+            .return_once(move |_, _, _| {
+                Ok(HashMap::from([(
+                    "key1".to_string(),
+                    WrappedKey::new(signing_key_bytes),
+                )]))
+            });
+        wallet_user_repo
+            .expect_find_key_signature_counts()
+            .return_once(|_, _| Ok(HashMap::from([("key1".to_string(), 10)])));
+        wallet_user_repo.expect_increment_key_signature_counts().never();
+
+        let error = instruction
+            .handle(
+                &wallet_user,
+                &MockGenerators,
+                &wallet_user_repo,
+                &MockPkcs11Client::default(),
+                &KeyUsageLimits {
+                    max_signatures_per_key: Some(10),
+                    max_signatures_per_wallet: None,
+                },
+                None,
+            )
+            .await
+            .expect_err("signing should exceed the key usage limit");
+
+        assert_matches!(
+            error,
+            InstructionError::KeyUsageLimit(KeyUsageLimitError::KeyLimitExceeded { limit: 10, .. })
+        );
+    }
+
+    #[tokio::test]
+    async fn should_escrow_generated_keys_with_consent() {
+        let mut wallet_user = wallet_user::mock::wallet_user_1();
+        wallet_user.key_escrow_consent = Some(Local::now());
+
+        let instruction = GenerateKey {
+            identifiers: vec!["key1".to_string(), "key2".to_string()],
+        };
+
+        let mut wallet_user_repo = MockTransactionalWalletUserRepository::new();
+        wallet_user_repo
+            .expect_begin_transaction()
+            .returning(|| Ok(MockTransaction));
+        wallet_user_repo.expect_save_keys().returning(|_, _| Ok(()));
+        wallet_user_repo
+            .expect_save_escrowed_keys()
+            .withf(|_, _, escrowed_keys| escrowed_keys.contains_key("key1") && escrowed_keys.contains_key("key2"))
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+
+        instruction
+            .handle(
+                &wallet_user,
+                &MockGenerators,
+                &wallet_user_repo,
+                &MockPkcs11Client::default(),
+                &KeyUsageLimits::default(),
+                Some(&KeyEscrow::new("escrow_key".to_string())),
+            )
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn should_handle_update_key_escrow_consent() {
+        let wallet_user = wallet_user::mock::wallet_user_1();
+
+        let mut wallet_user_repo = MockTransactionalWalletUserRepository::new();
+        wallet_user_repo
+            .expect_begin_transaction()
+            .returning(|| Ok(MockTransaction));
+        wallet_user_repo
+            .expect_find_all_keys()
+            .return_once(|_, _| Ok(HashMap::from([("key1".to_string(), WrappedKey::new(vec![1, 2, 3]))])));
+        wallet_user_repo
+            .expect_save_escrowed_keys()
+            .withf(|_, _, escrowed_keys| escrowed_keys.len() == 1 && escrowed_keys.contains_key("key1"))
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+        wallet_user_repo
+            .expect_update_key_escrow_consent()
+            .withf(|_, _, consent| consent.is_some())
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+        wallet_user_repo
+            .expect_insert_audit_log_event()
+            .withf(|_, event| event.event_type == AuditLogEventType::KeyEscrowConsentGiven)
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        UpdateKeyEscrowConsent { consent: true }
+            .handle(
+                &wallet_user,
+                &MockGenerators,
+                &wallet_user_repo,
+                &MockPkcs11Client::default(),
+                &KeyUsageLimits::default(),
+                Some(&KeyEscrow::new("escrow_key".to_string())),
+            )
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn should_handle_withdraw_key_escrow_consent() {
+        let wallet_user = wallet_user::mock::wallet_user_1();
+
+        let mut wallet_user_repo = MockTransactionalWalletUserRepository::new();
+        wallet_user_repo
+            .expect_begin_transaction()
+            .returning(|| Ok(MockTransaction));
+        wallet_user_repo
+            .expect_delete_escrowed_keys()
+            .times(1)
+            .returning(|_, _| Ok(()));
+        wallet_user_repo
+            .expect_update_key_escrow_consent()
+            .withf(|_, _, consent| consent.is_none())
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+        wallet_user_repo
+            .expect_insert_audit_log_event()
+            .withf(|_, event| event.event_type == AuditLogEventType::KeyEscrowConsentWithdrawn)
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        UpdateKeyEscrowConsent { consent: false }
+            .handle(
+                &wallet_user,
+                &MockGenerators,
+                &wallet_user_repo,
+                &MockPkcs11Client::default(),
+                &KeyUsageLimits::default(),
+                Some(&KeyEscrow::new("escrow_key".to_string())),
+            )
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn should_reject_key_escrow_consent_when_unavailable() {
+        let wallet_user = wallet_user::mock::wallet_user_1();
+
+        let error = UpdateKeyEscrowConsent { consent: true }
+            .handle(
+                &wallet_user,
+                &MockGenerators,
+                &MockTransactionalWalletUserRepository::new(),
+                &MockPkcs11Client::default(),
+                &KeyUsageLimits::default(),
+                None,
+            )
+            .await
+            .expect_err("key escrow consent should be rejected");
+
+        assert_matches!(error, InstructionError::KeyEscrowUnavailable);
+    }
+
+    #[tokio::test]
+    async fn should_handle_batch() {
+        let wallet_user = wallet_user::mock::wallet_user_1();
+
+        let random_msg: Base64Bytes = random_bytes(32).into();
+        let instruction = InstructionBatch {
+            instructions: vec![
+                GenerateKey {
+                    identifiers: vec!["key1".to_string()],
+                }
+                .into(),
+                Sign {
+                    messages_with_identifiers: vec![(random_msg.clone(), vec!["key1".to_string()])],
+                }
+                .into(),
+            ],
+        };
+
+        // Keep track of the keys that are saved, so that the same keys are found when signing.
+        let saved_keys = Arc::new(Mutex::new(HashMap::<String, WrappedKey>::new()));
+        let found_keys = Arc::clone(&saved_keys);
+
+        let mut wallet_user_repo = MockTransactionalWalletUserRepository::new();
+        wallet_user_repo
+            .expect_begin_transaction()
+            .returning(|| Ok(MockTransaction));
+        wallet_user_repo
+            .expect_save_keys()
+            .times(1)
+            .returning(move |_, keys: WalletUserKeys| {
+                saved_keys
+                    .lock()
+                    .unwrap()
+                    .extend(keys.keys.into_iter().map(|key| (key.key_identifier, key.key)));
+                Ok(())
+            });
+        wallet_user_repo
+            .expect_find_keys_by_identifiers()
+            .times(1)
+            .returning(move |_, _, _| Ok(found_keys.lock().unwrap().clone()));
+
+        let result = instruction
+            .handle(
+                &wallet_user,
+                &MockGenerators,
+                &wallet_user_repo,
+                &MockPkcs11Client::default(),
+                &KeyUsageLimits::default(),
+                None,
+            )
+            .await
+            .unwrap();
+
+        let [BatchedInstructionResult::GenerateKey(generate_key_result), BatchedInstructionResult::Sign(sign_result)] =
+            &result.results[..]
+        else {
+            panic!("unexpected batch results: {:?}", result.results);
+        };
+
+        let (identifier, public_key) = generate_key_result.public_keys.first().unwrap();
+        assert_eq!(identifier, "key1");
+
+        let signature = sign_result.signatures_by_identifier.get("key1").unwrap();
+        public_key.0.verify(&random_msg.0, &signature.0).unwrap();
+    }
 }
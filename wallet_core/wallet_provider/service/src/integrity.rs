@@ -0,0 +1,612 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use josekit::{
+    jwe::{self, alg::aeskw::AeskwJweDecrypter, A256KW},
+    jws::{self, alg::ecdsa::EcdsaJwsVerifier, ES256},
+    JoseError,
+};
+use serde::Deserialize;
+use serde_bytes::ByteBuf;
+use sha2::{Digest, Sha256};
+use tracing::debug;
+use webpki::{EndEntityCert, KeyUsage, Time, TrustAnchor, ECDSA_P256_SHA256, ECDSA_P384_SHA256, ECDSA_P384_SHA384};
+use x509_parser::{certificate::X509Certificate, prelude::FromDer};
+
+use wallet_common::account::messages::instructions::IntegrityEvidence;
+
+#[derive(Debug, thiserror::Error)]
+pub enum IntegrityError {
+    #[error("integrity evidence is required, but was not provided")]
+    Missing,
+    #[error("integrity evidence of type {0} is not supported")]
+    Unsupported(&'static str),
+    #[error("could not decrypt or verify Play Integrity token: {0}")]
+    PlayIntegrityToken(#[source] JoseError),
+    #[error("could not decode Play Integrity verdict: {0}")]
+    PlayIntegrityVerdictDecoding(#[source] serde_json::Error),
+    #[error("Play Integrity verdict rejected: {0}")]
+    PlayIntegrityVerdict(&'static str),
+    #[error("could not decode App Attest attestation: {0}")]
+    AppAttestDecoding(String),
+    #[error("App Attest certificate chain is invalid: {0}")]
+    AppAttestCertificate(#[source] webpki::Error),
+    #[error("App Attest attestation rejected: {0}")]
+    AppAttestAttestation(&'static str),
+}
+
+pub trait IntegrityVerifier {
+    /// Verify the (optional) integrity evidence that accompanies a high-risk instruction, which should be bound to
+    /// `request_hash`, as derived from the instruction challenge.
+    fn verify(
+        &self,
+        evidence: Option<&IntegrityEvidence>,
+        request_hash: &[u8],
+        time: DateTime<Utc>,
+    ) -> Result<(), IntegrityError>;
+}
+
+/// Determines how the integrity evidence of high-risk instructions is verified. Evidence for which a verifier is
+/// configured is always verified, while missing evidence or evidence for which no verifier is configured is only
+/// rejected if `required` is set. This allows the Wallet Provider to accept instructions from platforms or builds
+/// that are not (yet) able to provide evidence.
+#[derive(Default)]
+pub struct IntegrityPolicy {
+    pub required: bool,
+    pub play_integrity: Option<PlayIntegrityVerifier>,
+    pub app_attest: Option<AppAttestVerifier>,
+}
+
+impl IntegrityVerifier for IntegrityPolicy {
+    fn verify(
+        &self,
+        evidence: Option<&IntegrityEvidence>,
+        request_hash: &[u8],
+        time: DateTime<Utc>,
+    ) -> Result<(), IntegrityError> {
+        match evidence {
+            None if self.required => Err(IntegrityError::Missing),
+            None => {
+                debug!("No integrity evidence provided, which is not required");
+
+                Ok(())
+            }
+            Some(IntegrityEvidence::PlayIntegrity { token }) => match self.play_integrity.as_ref() {
+                Some(verifier) => verifier.verify(token, request_hash, time),
+                None if self.required => Err(IntegrityError::Unsupported("Play Integrity")),
+                None => Ok(()),
+            },
+            Some(IntegrityEvidence::AppAttest { key_id, attestation }) => match self.app_attest.as_ref() {
+                Some(verifier) => verifier.verify(&key_id.0, &attestation.0, request_hash, time),
+                None if self.required => Err(IntegrityError::Unsupported("App Attest")),
+                None => Ok(()),
+            },
+        }
+    }
+}
+
+const PLAY_RECOGNIZED: &str = "PLAY_RECOGNIZED";
+const MEETS_DEVICE_INTEGRITY: &str = "MEETS_DEVICE_INTEGRITY";
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PlayIntegrityVerdict {
+    request_details: PlayIntegrityRequestDetails,
+    app_integrity: PlayIntegrityAppIntegrity,
+    device_integrity: PlayIntegrityDeviceIntegrity,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PlayIntegrityRequestDetails {
+    request_package_name: String,
+    nonce: String,
+    timestamp_millis: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PlayIntegrityAppIntegrity {
+    app_recognition_verdict: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PlayIntegrityDeviceIntegrity {
+    #[serde(default)]
+    device_recognition_verdict: Vec<String>,
+}
+
+/// Verifies integrity verdicts from the Google Play Integrity API locally, using the response encryption keys that
+/// are managed by the Wallet Provider itself, instead of using the Google server to decrypt them.
+pub struct PlayIntegrityVerifier {
+    package_name: String,
+    decrypter: AeskwJweDecrypter,
+    verifier: EcdsaJwsVerifier,
+    max_age: Duration,
+}
+
+impl PlayIntegrityVerifier {
+    /// Create a new verifier from the AES decryption key and the DER encoded EC verification key,
+    /// as downloaded from the Google Play Console. Verdicts older than `max_age` are rejected.
+    pub fn new(
+        package_name: String,
+        decryption_key: &[u8],
+        verification_key: &[u8],
+        max_age: Duration,
+    ) -> Result<Self, JoseError> {
+        Ok(PlayIntegrityVerifier {
+            package_name,
+            decrypter: A256KW.decrypter_from_bytes(decryption_key)?,
+            verifier: ES256.verifier_from_der(verification_key)?,
+            max_age,
+        })
+    }
+
+    fn verify(&self, token: &str, request_hash: &[u8], time: DateTime<Utc>) -> Result<(), IntegrityError> {
+        let (jws, _) = jwe::deserialize_compact(token, &self.decrypter).map_err(IntegrityError::PlayIntegrityToken)?;
+        let (payload, _) = jws::deserialize_compact(jws, &self.verifier).map_err(IntegrityError::PlayIntegrityToken)?;
+        let verdict: PlayIntegrityVerdict =
+            serde_json::from_slice(&payload).map_err(IntegrityError::PlayIntegrityVerdictDecoding)?;
+
+        let request_details = verdict.request_details;
+
+        if request_details.request_package_name != self.package_name {
+            return Err(IntegrityError::PlayIntegrityVerdict("package name mismatch"));
+        }
+
+        // The nonce was sent as URL-safe base64, but may be returned with padding.
+        let nonce = URL_SAFE_NO_PAD
+            .decode(request_details.nonce.trim_end_matches('='))
+            .map_err(|_| IntegrityError::PlayIntegrityVerdict("invalid nonce encoding"))?;
+        if nonce != request_hash {
+            return Err(IntegrityError::PlayIntegrityVerdict("nonce mismatch"));
+        }
+
+        let timestamp = request_details
+            .timestamp_millis
+            .parse()
+            .ok()
+            .and_then(|millis| Utc.timestamp_millis_opt(millis).single())
+            .ok_or(IntegrityError::PlayIntegrityVerdict("invalid timestamp"))?;
+        if (time - timestamp).abs() > self.max_age {
+            return Err(IntegrityError::PlayIntegrityVerdict("verdict expired"));
+        }
+
+        if verdict.app_integrity.app_recognition_verdict != PLAY_RECOGNIZED {
+            return Err(IntegrityError::PlayIntegrityVerdict("app not recognized"));
+        }
+
+        if !verdict
+            .device_integrity
+            .device_recognition_verdict
+            .iter()
+            .any(|verdict| verdict == MEETS_DEVICE_INTEGRITY)
+        {
+            return Err(IntegrityError::PlayIntegrityVerdict("device integrity not met"));
+        }
+
+        Ok(())
+    }
+}
+
+const APP_ATTEST_FORMAT: &str = "apple-appattest";
+const APP_ATTEST_NONCE_OID: &str = "1.2.840.113635.100.8.2";
+const APP_ATTEST_AAGUID_PRODUCTION: &[u8; 16] = b"appattest\0\0\0\0\0\0\0";
+const APP_ATTEST_AAGUID_DEVELOPMENT: &[u8; 16] = b"appattestdevelop";
+
+#[derive(Debug, Deserialize)]
+struct AppAttestAttestation {
+    fmt: String,
+    #[serde(rename = "attStmt")]
+    att_stmt: AppAttestStatement,
+    #[serde(rename = "authData")]
+    auth_data: ByteBuf,
+}
+
+#[derive(Debug, Deserialize)]
+struct AppAttestStatement {
+    x5c: Vec<ByteBuf>,
+}
+
+/// Verifies attestation objects from the Apple App Attest service. As the Wallet Provider does not keep track of
+/// attested keys, the app attests a new key for every high-risk instruction, using the request hash as client data
+/// hash. This means that only attestations need to be verified and no assertions.
+pub struct AppAttestVerifier {
+    app_id: String,
+    root_ca: Vec<u8>,
+    allow_development: bool,
+}
+
+impl AppAttestVerifier {
+    /// Create a new verifier for the app with the provided identifier, consisting of the team identifier and bundle
+    /// identifier, using the DER encoded Apple App Attestation Root CA certificate as trust anchor. Attestations
+    /// from the development environment are only accepted if `allow_development` is set.
+    pub fn new(app_id: String, root_ca: Vec<u8>, allow_development: bool) -> Result<Self, webpki::Error> {
+        // Check that the root CA certificate can be used as a trust anchor.
+        TrustAnchor::try_from_cert_der(&root_ca)?;
+
+        Ok(AppAttestVerifier {
+            app_id,
+            root_ca,
+            allow_development,
+        })
+    }
+
+    fn verify(
+        &self,
+        key_id: &[u8],
+        attestation: &[u8],
+        request_hash: &[u8],
+        time: DateTime<Utc>,
+    ) -> Result<(), IntegrityError> {
+        let attestation: AppAttestAttestation =
+            ciborium::from_reader(attestation).map_err(|error| IntegrityError::AppAttestDecoding(error.to_string()))?;
+
+        if attestation.fmt != APP_ATTEST_FORMAT {
+            return Err(IntegrityError::AppAttestAttestation("unexpected format"));
+        }
+
+        let (leaf, intermediates) = attestation
+            .att_stmt
+            .x5c
+            .split_first()
+            .ok_or(IntegrityError::AppAttestAttestation("missing certificates"))?;
+        let intermediates = intermediates.iter().map(|cert| cert.as_slice()).collect::<Vec<_>>();
+
+        // The certificates in the chain do not contain an extended key usage, so accept any.
+        let trust_anchor =
+            TrustAnchor::try_from_cert_der(&self.root_ca).map_err(IntegrityError::AppAttestCertificate)?;
+        EndEntityCert::try_from(leaf.as_slice())
+            .and_then(|cert| {
+                cert.verify_for_usage(
+                    &[&ECDSA_P256_SHA256, &ECDSA_P384_SHA256, &ECDSA_P384_SHA384],
+                    &[trust_anchor],
+                    &intermediates,
+                    Time::from_seconds_since_unix_epoch(time.timestamp() as u64),
+                    KeyUsage::client_auth(),
+                    &[],
+                )
+            })
+            .map_err(IntegrityError::AppAttestCertificate)?;
+
+        let (_, leaf) =
+            X509Certificate::from_der(leaf).map_err(|error| IntegrityError::AppAttestDecoding(error.to_string()))?;
+
+        // The leaf certificate contains the nonce, which binds the authenticator data to the client data hash.
+        let nonce = Sha256::new()
+            .chain_update(&attestation.auth_data)
+            .chain_update(request_hash)
+            .finalize();
+        let nonce_extension = leaf
+            .extensions()
+            .iter()
+            .find(|extension| extension.oid.to_id_string() == APP_ATTEST_NONCE_OID)
+            .ok_or(IntegrityError::AppAttestAttestation("missing nonce"))?;
+        if nonce_extension.value != app_attest_nonce_extension(&nonce) {
+            return Err(IntegrityError::AppAttestAttestation("nonce mismatch"));
+        }
+
+        // The key identifier is the SHA-256 hash of the public key of the attested key.
+        if Sha256::digest(&leaf.public_key().subject_public_key.data).as_slice() != key_id {
+            return Err(IntegrityError::AppAttestAttestation("key identifier mismatch"));
+        }
+
+        self.verify_authenticator_data(&attestation.auth_data, key_id)
+    }
+
+    fn verify_authenticator_data(&self, auth_data: &[u8], key_id: &[u8]) -> Result<(), IntegrityError> {
+        // The authenticator data contains the RP ID hash (32 bytes), flags (1 byte), the counter (4 bytes),
+        // the AAGUID (16 bytes), the length of the credential identifier (2 bytes) and the credential identifier.
+        if auth_data.len() < 55 {
+            return Err(IntegrityError::AppAttestAttestation("authenticator data too short"));
+        }
+
+        if auth_data[..32] != Sha256::digest(self.app_id.as_bytes())[..] {
+            return Err(IntegrityError::AppAttestAttestation("app identifier mismatch"));
+        }
+
+        if auth_data[33..37] != [0, 0, 0, 0] {
+            return Err(IntegrityError::AppAttestAttestation("counter is not zero"));
+        }
+
+        let aaguid = &auth_data[37..53];
+        if aaguid != APP_ATTEST_AAGUID_PRODUCTION
+            && !(self.allow_development && aaguid == APP_ATTEST_AAGUID_DEVELOPMENT)
+        {
+            return Err(IntegrityError::AppAttestAttestation("unexpected environment"));
+        }
+
+        let credential_id_length = u16::from_be_bytes([auth_data[53], auth_data[54]]) as usize;
+        if auth_data.get(55..55 + credential_id_length) != Some(key_id) {
+            return Err(IntegrityError::AppAttestAttestation("credential identifier mismatch"));
+        }
+
+        Ok(())
+    }
+}
+
+/// The DER encoding of the nonce extension, which is a sequence containing an explicitly tagged octet string.
+fn app_attest_nonce_extension(nonce: &[u8]) -> Vec<u8> {
+    [&[0x30, 0x24, 0xa1, 0x22, 0x04, 0x20], nonce].concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use ciborium::cbor;
+    use josekit::{
+        jwe::{JweHeader, A256KW},
+        jws::{JwsHeader, ES256},
+    };
+    use rcgen::{
+        BasicConstraints, Certificate, CertificateParams, CustomExtension, IsCa, KeyPair, PKCS_ECDSA_P256_SHA256,
+    };
+    use serde_json::json;
+
+    use wallet_common::utils::random_bytes;
+
+    use super::*;
+
+    const PACKAGE_NAME: &str = "nl.rijksoverheid.edi.wallet";
+    const APP_ID: &str = "TEAMID1234.nl.rijksoverheid.edi.wallet";
+
+    struct PlayIntegrityKeys {
+        decryption_key: Vec<u8>,
+        verification_key: Vec<u8>,
+        signing_key: Vec<u8>,
+    }
+
+    fn play_integrity_keys() -> PlayIntegrityKeys {
+        let key_pair = ES256.generate_key_pair().unwrap();
+
+        PlayIntegrityKeys {
+            decryption_key: random_bytes(32),
+            verification_key: key_pair.to_der_public_key(),
+            signing_key: key_pair.to_der_private_key(),
+        }
+    }
+
+    fn play_integrity_token(keys: &PlayIntegrityKeys, verdict: serde_json::Value) -> String {
+        let signer = ES256.signer_from_der(&keys.signing_key).unwrap();
+        let jws = jws::serialize_compact(verdict.to_string().as_bytes(), &JwsHeader::new(), &signer).unwrap();
+
+        let mut header = JweHeader::new();
+        header.set_content_encryption("A256GCM");
+        let encrypter = A256KW.encrypter_from_bytes(&keys.decryption_key).unwrap();
+
+        jwe::serialize_compact(jws.as_bytes(), &header, &encrypter).unwrap()
+    }
+
+    fn play_integrity_verdict(request_hash: &[u8], time: DateTime<Utc>) -> serde_json::Value {
+        json!({
+            "requestDetails": {
+                "requestPackageName": PACKAGE_NAME,
+                "nonce": URL_SAFE_NO_PAD.encode(request_hash),
+                "timestampMillis": time.timestamp_millis().to_string(),
+            },
+            "appIntegrity": {
+                "appRecognitionVerdict": "PLAY_RECOGNIZED",
+                "packageName": PACKAGE_NAME,
+            },
+            "deviceIntegrity": {
+                "deviceRecognitionVerdict": ["MEETS_DEVICE_INTEGRITY"],
+            },
+        })
+    }
+
+    fn play_integrity_policy(keys: &PlayIntegrityKeys) -> IntegrityPolicy {
+        IntegrityPolicy {
+            required: true,
+            play_integrity: PlayIntegrityVerifier::new(
+                PACKAGE_NAME.to_string(),
+                &keys.decryption_key,
+                &keys.verification_key,
+                Duration::minutes(5),
+            )
+            .unwrap()
+            .into(),
+            app_attest: None,
+        }
+    }
+
+    #[test]
+    fn test_integrity_policy_missing_evidence() {
+        let request_hash = random_bytes(32);
+
+        IntegrityPolicy::default()
+            .verify(None, &request_hash, Utc::now())
+            .expect("missing evidence should be accepted when not required");
+
+        let policy = IntegrityPolicy {
+            required: true,
+            ..Default::default()
+        };
+
+        assert_matches!(
+            policy.verify(None, &request_hash, Utc::now()),
+            Err(IntegrityError::Missing)
+        );
+        assert_matches!(
+            policy.verify(
+                Some(&IntegrityEvidence::PlayIntegrity {
+                    token: "token".to_string()
+                }),
+                &request_hash,
+                Utc::now()
+            ),
+            Err(IntegrityError::Unsupported(_))
+        );
+    }
+
+    #[test]
+    fn test_play_integrity() {
+        let keys = play_integrity_keys();
+        let policy = play_integrity_policy(&keys);
+        let request_hash = random_bytes(32);
+        let now = Utc::now();
+
+        let evidence = IntegrityEvidence::PlayIntegrity {
+            token: play_integrity_token(&keys, play_integrity_verdict(&request_hash, now)),
+        };
+
+        policy
+            .verify(Some(&evidence), &request_hash, now)
+            .expect("Play Integrity verdict should be valid");
+
+        // The verdict should not be accepted for another request or after it has expired.
+        assert_matches!(
+            policy.verify(Some(&evidence), &random_bytes(32), now),
+            Err(IntegrityError::PlayIntegrityVerdict("nonce mismatch"))
+        );
+        assert_matches!(
+            policy.verify(Some(&evidence), &request_hash, now + Duration::minutes(10)),
+            Err(IntegrityError::PlayIntegrityVerdict("verdict expired"))
+        );
+    }
+
+    #[test]
+    fn test_play_integrity_device_integrity_not_met() {
+        let keys = play_integrity_keys();
+        let policy = play_integrity_policy(&keys);
+        let request_hash = random_bytes(32);
+        let now = Utc::now();
+
+        let mut verdict = play_integrity_verdict(&request_hash, now);
+        verdict["deviceIntegrity"]["deviceRecognitionVerdict"] = json!([]);
+        let evidence = IntegrityEvidence::PlayIntegrity {
+            token: play_integrity_token(&keys, verdict),
+        };
+
+        assert_matches!(
+            policy.verify(Some(&evidence), &request_hash, now),
+            Err(IntegrityError::PlayIntegrityVerdict("device integrity not met"))
+        );
+    }
+
+    #[test]
+    fn test_play_integrity_wrong_keys() {
+        let keys = play_integrity_keys();
+        let policy = play_integrity_policy(&play_integrity_keys());
+        let request_hash = random_bytes(32);
+        let now = Utc::now();
+
+        let evidence = IntegrityEvidence::PlayIntegrity {
+            token: play_integrity_token(&keys, play_integrity_verdict(&request_hash, now)),
+        };
+
+        assert_matches!(
+            policy.verify(Some(&evidence), &request_hash, now),
+            Err(IntegrityError::PlayIntegrityToken(_))
+        );
+    }
+
+    /// Generate a root CA and an attestation for a new key in the same way as the App Attest service does.
+    fn app_attest_attestation(request_hash: &[u8], aaguid: &[u8; 16]) -> (Vec<u8>, Vec<u8>, IntegrityEvidence) {
+        let mut ca_params = CertificateParams::new(vec![]);
+        ca_params.alg = &PKCS_ECDSA_P256_SHA256;
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca = Certificate::from_params(ca_params).unwrap();
+
+        let key_pair = KeyPair::generate(&PKCS_ECDSA_P256_SHA256).unwrap();
+        let key_id = Sha256::digest(key_pair.public_key_raw()).to_vec();
+
+        let auth_data = [
+            Sha256::digest(APP_ID.as_bytes()).as_slice(),
+            &[0x40],
+            &[0, 0, 0, 0],
+            aaguid,
+            &(key_id.len() as u16).to_be_bytes(),
+            &key_id,
+        ]
+        .concat();
+
+        let nonce = Sha256::new()
+            .chain_update(&auth_data)
+            .chain_update(request_hash)
+            .finalize();
+
+        let mut leaf_params = CertificateParams::new(vec![]);
+        leaf_params.alg = &PKCS_ECDSA_P256_SHA256;
+        leaf_params.key_pair = Some(key_pair);
+        leaf_params.custom_extensions = vec![CustomExtension::from_oid_content(
+            &[1, 2, 840, 113635, 100, 8, 2],
+            app_attest_nonce_extension(&nonce),
+        )];
+        let leaf = Certificate::from_params(leaf_params).unwrap();
+
+        let mut attestation = Vec::new();
+        ciborium::into_writer(
+            &cbor!({
+                "fmt" => APP_ATTEST_FORMAT,
+                "attStmt" => {
+                    "x5c" => [ciborium::Value::Bytes(leaf.serialize_der_with_signer(&ca).unwrap())],
+                    "receipt" => ciborium::Value::Bytes(vec![]),
+                },
+                "authData" => ciborium::Value::Bytes(auth_data),
+            })
+            .unwrap(),
+            &mut attestation,
+        )
+        .unwrap();
+
+        let evidence = IntegrityEvidence::AppAttest {
+            key_id: key_id.clone().into(),
+            attestation: attestation.into(),
+        };
+
+        (ca.serialize_der().unwrap(), key_id, evidence)
+    }
+
+    fn app_attest_policy(root_ca: Vec<u8>, allow_development: bool) -> IntegrityPolicy {
+        IntegrityPolicy {
+            required: true,
+            play_integrity: None,
+            app_attest: AppAttestVerifier::new(APP_ID.to_string(), root_ca, allow_development)
+                .unwrap()
+                .into(),
+        }
+    }
+
+    #[test]
+    fn test_app_attest() {
+        let request_hash = random_bytes(32);
+        let (root_ca, _, evidence) = app_attest_attestation(&request_hash, APP_ATTEST_AAGUID_PRODUCTION);
+        let policy = app_attest_policy(root_ca, false);
+
+        policy
+            .verify(Some(&evidence), &request_hash, Utc::now())
+            .expect("App Attest attestation should be valid");
+
+        assert_matches!(
+            policy.verify(Some(&evidence), &random_bytes(32), Utc::now()),
+            Err(IntegrityError::AppAttestAttestation("nonce mismatch"))
+        );
+    }
+
+    #[test]
+    fn test_app_attest_development() {
+        let request_hash = random_bytes(32);
+        let (root_ca, _, evidence) = app_attest_attestation(&request_hash, APP_ATTEST_AAGUID_DEVELOPMENT);
+
+        assert_matches!(
+            app_attest_policy(root_ca.clone(), false).verify(Some(&evidence), &request_hash, Utc::now()),
+            Err(IntegrityError::AppAttestAttestation("unexpected environment"))
+        );
+
+        app_attest_policy(root_ca, true)
+            .verify(Some(&evidence), &request_hash, Utc::now())
+            .expect("App Attest attestation from development environment should be valid");
+    }
+
+    #[test]
+    fn test_app_attest_untrusted() {
+        let request_hash = random_bytes(32);
+        let (_, _, evidence) = app_attest_attestation(&request_hash, APP_ATTEST_AAGUID_PRODUCTION);
+        let (other_root_ca, _, _) = app_attest_attestation(&request_hash, APP_ATTEST_AAGUID_PRODUCTION);
+
+        assert_matches!(
+            app_attest_policy(other_root_ca, false).verify(Some(&evidence), &request_hash, Utc::now()),
+            Err(IntegrityError::AppAttestCertificate(_))
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+
+use futures::future;
+
+use wallet_provider_domain::model::{encrypted::Encrypted, hsm::Hsm, wrapped_key::WrappedKey};
+
+use crate::hsm::HsmError;
+
+/// Escrow of the private keys of wallet users, so that these can be recovered when a user migrates to another device,
+/// provided that the user consented to this. The private keys are wrapped by the attestation wrapping key of the HSM
+/// already. For escrow, the wrapped keys are additionally encrypted with a separate escrow key in the HSM, so that
+/// recovering a private key takes both the escrow key and the wrapping key.
+pub struct KeyEscrow {
+    escrow_key_identifier: String,
+}
+
+impl KeyEscrow {
+    pub fn new(escrow_key_identifier: String) -> Self {
+        Self { escrow_key_identifier }
+    }
+
+    pub async fn escrow_keys<H>(
+        &self,
+        hsm: &H,
+        keys: HashMap<String, WrappedKey>,
+    ) -> Result<HashMap<String, Encrypted<WrappedKey>>, HsmError>
+    where
+        H: Hsm<Error = HsmError>,
+    {
+        let escrowed_keys = future::try_join_all(keys.into_iter().map(|(identifier, wrapped_key)| async move {
+            let escrowed_key = hsm.encrypt(&self.escrow_key_identifier, wrapped_key.into()).await?;
+            Ok::<_, HsmError>((identifier, escrowed_key))
+        }))
+        .await?;
+
+        Ok(escrowed_keys.into_iter().collect())
+    }
+
+    pub async fn recover_keys<H>(
+        &self,
+        hsm: &H,
+        escrowed_keys: HashMap<String, Encrypted<WrappedKey>>,
+    ) -> Result<HashMap<String, WrappedKey>, HsmError>
+    where
+        H: Hsm<Error = HsmError>,
+    {
+        let keys = future::try_join_all(escrowed_keys.into_iter().map(|(identifier, escrowed_key)| async move {
+            let wrapped_key = hsm.decrypt(&self.escrow_key_identifier, escrowed_key).await?;
+            Ok::<_, HsmError>((identifier, WrappedKey::new(wrapped_key)))
+        }))
+        .await?;
+
+        Ok(keys.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use wallet_provider_domain::model::{hsm::mock::MockPkcs11Client, wrapped_key::WrappedKey};
+
+    use crate::hsm::HsmError;
+
+    use super::KeyEscrow;
+
+    #[tokio::test]
+    async fn test_escrow_and_recover_keys() {
+        let hsm = MockPkcs11Client::<HsmError>::default();
+        let key_escrow = KeyEscrow::new("escrow_key".to_string());
+
+        let keys = HashMap::from([
+            ("key1".to_string(), WrappedKey::new(vec![1, 2, 3])),
+            ("key2".to_string(), WrappedKey::new(vec![4, 5, 6])),
+        ]);
+
+        let escrowed_keys = key_escrow.escrow_keys(&hsm, keys).await.unwrap();
+        assert_eq!(escrowed_keys.len(), 2);
+
+        let recovered_keys = key_escrow.recover_keys(&hsm, escrowed_keys).await.unwrap();
+        let recovered_key: Vec<u8> = recovered_keys["key2"].clone().into();
+        assert_eq!(recovered_keys.len(), 2);
+        assert_eq!(recovered_key, vec![4, 5, 6]);
+    }
+}
@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+
+#[derive(Debug, thiserror::Error)]
+pub enum KeyUsageLimitError {
+    #[error("signature limit of {limit} exceeded for key: {identifier}")]
+    KeyLimitExceeded { identifier: String, limit: u64 },
+    #[error("signature limit of {limit} exceeded for wallet")]
+    WalletLimitExceeded { limit: u64 },
+}
+
+/// Limits the number of signatures that can be created with the keys of a wallet, so that a compromised instance of
+/// the app cannot sign an unbounded number of payloads. Signatures can be limited per key, per wallet or both. When
+/// neither limit is configured, the number of signatures is not limited.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct KeyUsageLimits {
+    pub max_signatures_per_key: Option<u64>,
+    pub max_signatures_per_wallet: Option<u64>,
+}
+
+impl KeyUsageLimits {
+    pub fn is_limited(&self) -> bool {
+        self.max_signatures_per_key.is_some() || self.max_signatures_per_wallet.is_some()
+    }
+
+    /// Check if the requested number of signatures per key identifier can be created, given the number of signatures
+    /// that have already been created with all keys of the wallet.
+    pub fn check(
+        &self,
+        current_counts: &HashMap<String, u64>,
+        requested_counts: &HashMap<String, u64>,
+    ) -> Result<(), KeyUsageLimitError> {
+        if let Some(limit) = self.max_signatures_per_key {
+            for (identifier, requested) in requested_counts {
+                let current = current_counts.get(identifier).copied().unwrap_or_default();
+
+                if current.saturating_add(*requested) > limit {
+                    return Err(KeyUsageLimitError::KeyLimitExceeded {
+                        identifier: identifier.clone(),
+                        limit,
+                    });
+                }
+            }
+        }
+
+        if let Some(limit) = self.max_signatures_per_wallet {
+            let current = current_counts
+                .values()
+                .fold(0u64, |total, count| total.saturating_add(*count));
+            let requested = requested_counts
+                .values()
+                .fold(0u64, |total, count| total.saturating_add(*count));
+
+            if current.saturating_add(requested) > limit {
+                return Err(KeyUsageLimitError::WalletLimitExceeded { limit });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+
+    use super::*;
+
+    fn counts(counts: &[(&str, u64)]) -> HashMap<String, u64> {
+        counts
+            .iter()
+            .map(|(identifier, count)| (identifier.to_string(), *count))
+            .collect()
+    }
+
+    #[test]
+    fn test_key_usage_limits_unlimited() {
+        let limits = KeyUsageLimits::default();
+
+        assert!(!limits.is_limited());
+        assert_matches!(
+            limits.check(&counts(&[("key1", u64::MAX)]), &counts(&[("key1", 1)])),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_key_usage_limits_per_key() {
+        let limits = KeyUsageLimits {
+            max_signatures_per_key: Some(3),
+            max_signatures_per_wallet: None,
+        };
+        let current = counts(&[("key1", 2), ("key2", 0)]);
+
+        assert_matches!(limits.check(&current, &counts(&[("key1", 1), ("key2", 3)])), Ok(()));
+        assert_matches!(
+            limits.check(&current, &counts(&[("key1", 2)])),
+            Err(KeyUsageLimitError::KeyLimitExceeded { identifier, limit: 3 }) if identifier == "key1"
+        );
+    }
+
+    #[test]
+    fn test_key_usage_limits_per_wallet() {
+        let limits = KeyUsageLimits {
+            max_signatures_per_key: None,
+            max_signatures_per_wallet: Some(5),
+        };
+        let current = counts(&[("key1", 2), ("key2", 1)]);
+
+        assert_matches!(limits.check(&current, &counts(&[("key1", 1), ("key3", 1)])), Ok(()));
+        assert_matches!(
+            limits.check(&current, &counts(&[("key1", 2), ("key2", 1)])),
+            Err(KeyUsageLimitError::WalletLimitExceeded { limit: 5 })
+        );
+    }
+}
@@ -1,5 +1,11 @@
 pub mod account_server;
+pub mod anomaly;
 pub mod hsm;
+pub mod instruction_queue;
 pub mod instructions;
+pub mod integrity;
+pub mod key_escrow;
+pub mod key_usage;
 pub mod keys;
 pub mod pin_policy;
+pub mod rate_limiter;
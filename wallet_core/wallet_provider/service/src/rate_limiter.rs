@@ -0,0 +1,193 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration as StdDuration, Instant},
+};
+
+use chrono::Duration;
+
+#[derive(Debug, thiserror::Error)]
+#[error("wallet has sent too many requests, retry after {} ms", .retry_after.as_millis())]
+pub struct RateLimitedError {
+    pub retry_after: StdDuration,
+}
+
+/// Limits the number of instruction challenges and instructions that a single wallet can send within a fixed window
+/// of time, so that a misbehaving wallet cannot keep the Wallet Provider (and its HSM) occupied. Every wallet has its
+/// own window, which starts at its first request. A request exceeding the maximum is rejected with a
+/// [`RateLimitedError`] that contains the time remaining until the window ends.
+///
+/// Note that, like the [`crate::instruction_queue::InstructionQueue`], this only limits requests that are handled by
+/// the same instance of the Wallet Provider.
+pub struct RateLimiter {
+    state: Mutex<RateLimiterState>,
+}
+
+struct RateLimiterState {
+    max_requests: u32,
+    window: StdDuration,
+    last_cleanup: Instant,
+    wallets: HashMap<String, RateLimitWindow>,
+}
+
+struct RateLimitWindow {
+    start: Instant,
+    requests: u32,
+}
+
+impl RateLimiter {
+    /// Create a new rate limiter that allows `max_requests` per wallet within `window`. A negative duration is
+    /// interpreted as a window of zero length, which effectively disables rate limiting.
+    pub fn new(max_requests: u32, window: Duration) -> Self {
+        RateLimiter {
+            state: Mutex::new(RateLimiterState {
+                max_requests,
+                window: window.to_std().unwrap_or_default(),
+                last_cleanup: Instant::now(),
+                wallets: HashMap::new(),
+            }),
+        }
+    }
+
+    /// Change the maximum number of requests and the length of the window. Windows that have already started keep
+    /// their start time, but are evaluated against the new limits.
+    pub fn set_limits(&self, max_requests: u32, window: Duration) {
+        let mut state = self.state.lock().unwrap();
+
+        state.max_requests = max_requests;
+        state.window = window.to_std().unwrap_or_default();
+    }
+
+    /// Register a request for the wallet with the specified id, returning an error if it exceeds the limit.
+    pub fn check(&self, wallet_id: &str) -> Result<(), RateLimitedError> {
+        self.check_at(wallet_id, Instant::now())
+    }
+
+    fn check_at(&self, wallet_id: &str, now: Instant) -> Result<(), RateLimitedError> {
+        let mut state = self.state.lock().unwrap();
+        let max_requests = state.max_requests;
+        let window = state.window;
+
+        // Remove the windows that have ended at most once per window length, so that wallets which stopped sending
+        // requests do not accumulate without having to go over all windows on every request.
+        if now.saturating_duration_since(state.last_cleanup) >= window {
+            state
+                .wallets
+                .retain(|_, wallet_window| now.saturating_duration_since(wallet_window.start) < window);
+            state.last_cleanup = now;
+        }
+
+        let wallet_window = state.wallets.entry(wallet_id.to_string()).or_insert(RateLimitWindow {
+            start: now,
+            requests: 0,
+        });
+
+        let elapsed = now.saturating_duration_since(wallet_window.start);
+        if elapsed >= window {
+            wallet_window.start = now;
+            wallet_window.requests = 0;
+        }
+
+        if wallet_window.requests >= max_requests && !window.is_zero() {
+            return Err(RateLimitedError {
+                retry_after: window - now.saturating_duration_since(wallet_window.start),
+            });
+        }
+
+        wallet_window.requests += 1;
+
+        Ok(())
+    }
+
+    /// The number of wallets for which a window is being tracked.
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().wallets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+
+    use super::*;
+
+    #[test]
+    fn test_rate_limiter() {
+        let rate_limiter = RateLimiter::new(2, Duration::seconds(10));
+        let start = Instant::now();
+
+        rate_limiter.check_at("wallet_1", start).unwrap();
+        rate_limiter
+            .check_at("wallet_1", start + StdDuration::from_secs(1))
+            .unwrap();
+
+        // The third request within the window is rejected, with the time left until the window ends.
+        let error = rate_limiter
+            .check_at("wallet_1", start + StdDuration::from_secs(4))
+            .expect_err("request should be rate limited");
+        assert_eq!(error.retry_after, StdDuration::from_secs(6));
+
+        // Other wallets are not affected.
+        rate_limiter
+            .check_at("wallet_2", start + StdDuration::from_secs(4))
+            .unwrap();
+
+        // After the window has ended, requests are accepted again.
+        rate_limiter
+            .check_at("wallet_1", start + StdDuration::from_secs(10))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_rate_limiter_cleanup() {
+        let rate_limiter = RateLimiter::new(1, Duration::seconds(10));
+        let start = Instant::now();
+
+        rate_limiter.check_at("wallet_1", start).unwrap();
+        rate_limiter.check_at("wallet_2", start).unwrap();
+
+        assert_eq!(rate_limiter.len(), 2);
+
+        // A request after the windows have ended removes the windows of the other wallets.
+        rate_limiter
+            .check_at("wallet_1", start + StdDuration::from_secs(20))
+            .unwrap();
+
+        assert_eq!(rate_limiter.len(), 1);
+    }
+
+    #[test]
+    fn test_rate_limiter_set_limits() {
+        let rate_limiter = RateLimiter::new(1, Duration::seconds(10));
+        let start = Instant::now();
+
+        rate_limiter.check_at("wallet_1", start).unwrap();
+        rate_limiter
+            .check_at("wallet_1", start + StdDuration::from_secs(1))
+            .expect_err("request should be rate limited");
+
+        // Raising the maximum allows more requests within the window that has already started.
+        rate_limiter.set_limits(2, Duration::seconds(10));
+
+        rate_limiter
+            .check_at("wallet_1", start + StdDuration::from_secs(2))
+            .unwrap();
+        let error = rate_limiter
+            .check_at("wallet_1", start + StdDuration::from_secs(3))
+            .expect_err("request should be rate limited");
+        assert_eq!(error.retry_after, StdDuration::from_secs(7));
+    }
+
+    #[test]
+    fn test_rate_limiter_disabled() {
+        let rate_limiter = RateLimiter::new(0, Duration::zero());
+        let start = Instant::now();
+
+        assert_matches!(rate_limiter.check_at("wallet_1", start), Ok(()));
+        assert_matches!(rate_limiter.check_at("wallet_1", start), Ok(()));
+    }
+}
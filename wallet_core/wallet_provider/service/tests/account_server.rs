@@ -1,3 +1,7 @@
+use std::collections::HashMap;
+
+use assert_matches::assert_matches;
+use chrono::{DateTime, Local};
 use p256::ecdsa::SigningKey;
 use rand::rngs::OsRng;
 use uuid::Uuid;
@@ -5,30 +9,46 @@ use uuid::Uuid;
 use wallet_common::{
     account::messages::{
         auth::{Registration, WalletCertificate, WalletCertificateClaims},
-        instructions::{InstructionChallengeRequest, InstructionChallengeRequestMessage},
+        instructions::{
+            Instruction, InstructionChallengeRequest, InstructionChallengeRequestMessage, Sign, SignResult,
+        },
     },
     generator::Generator,
     keys::{software::SoftwareEcdsaKey, ConstructibleWithIdentifier, EcdsaKey},
 };
 use wallet_provider_database_settings::Settings;
 use wallet_provider_domain::{
-    model::{hsm::mock::MockPkcs11Client, wallet_user::WalletUserQueryResult},
-    repository::{PersistenceError, TransactionStarter, WalletUserRepository},
+    model::{
+        audit_log::AuditLogEventType,
+        hsm::{mock::MockPkcs11Client, WalletUserHsm},
+        wallet_user::{WalletUser, WalletUserKey, WalletUserKeys, WalletUserQueryResult},
+        TimeoutPinPolicy,
+    },
+    repository::{AuditLogRepository, Committable, PersistenceError, TransactionStarter, WalletUserRepository},
     EpochGenerator,
 };
 use wallet_provider_persistence::{database::Db, repositories::Repositories};
 use wallet_provider_service::{
-    account_server::{mock, AccountServer},
+    account_server::{mock, AccountServer, InstructionError, WalletCertificateError},
+    anomaly::IpAddressBurstDetector,
     hsm::HsmError,
+    integrity::IntegrityPolicy,
+    key_escrow::KeyEscrow,
+    key_usage::KeyUsageLimits,
     keys::CertificateSigningKey,
 };
 
-struct UuidGenerator;
-impl Generator<Uuid> for UuidGenerator {
+struct Generators;
+impl Generator<Uuid> for Generators {
     fn generate(&self) -> Uuid {
         Uuid::new_v4()
     }
 }
+impl Generator<DateTime<Local>> for Generators {
+    fn generate(&self) -> DateTime<Local> {
+        Local::now()
+    }
+}
 
 async fn db_from_env() -> Result<Db, PersistenceError> {
     let _ = tracing::subscriber::set_global_default(
@@ -60,13 +80,7 @@ async fn do_registration(
         .expect("Could not sign new registration");
 
     let certificate = account_server
-        .register(
-            certificate_signing_key,
-            &UuidGenerator,
-            repos,
-            hsm,
-            registration_message,
-        )
+        .register(certificate_signing_key, &Generators, repos, hsm, registration_message)
         .await
         .expect("Could not process registration message at account server");
 
@@ -77,6 +91,11 @@ async fn do_registration(
     (certificate, cert_data)
 }
 
+async fn find_wallet_user(repos: &Repositories, wallet_id: &str) -> WalletUserQueryResult {
+    let tx = repos.begin_transaction().await.unwrap();
+    repos.find_wallet_user_by_wallet_id(&tx, wallet_id).await.unwrap()
+}
+
 async fn assert_instruction_data(
     repos: &Repositories,
     wallet_id: &str,
@@ -155,3 +174,188 @@ async fn test_instruction_challenge() {
 
     assert_ne!(challenge1, challenge2);
 }
+
+#[cfg_attr(not(feature = "db_test"), ignore)]
+#[tokio::test]
+async fn test_registration_audit_log() {
+    let db = db_from_env().await.expect("Could not connect to database");
+    let repos = Repositories::new(db);
+
+    let certificate_signing_key = SoftwareEcdsaKey::new("certificate_signing_key");
+    let certificate_signing_pubkey = certificate_signing_key.verifying_key().await.unwrap();
+
+    let (account_server, hsm) = mock::account_server_and_hsm(certificate_signing_pubkey.into()).await;
+    let hw_privkey = SigningKey::random(&mut OsRng);
+    let pin_privkey = SigningKey::random(&mut OsRng);
+
+    let (_, cert_data) = do_registration(
+        &account_server,
+        &hsm,
+        &certificate_signing_key,
+        &hw_privkey,
+        &pin_privkey,
+        &repos,
+    )
+    .await;
+
+    let tx = repos.begin_transaction().await.unwrap();
+    let events = repos
+        .find_audit_log_events(&tx, &cert_data.wallet_id, None)
+        .await
+        .unwrap();
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].wallet_id, cert_data.wallet_id);
+    assert_eq!(events[0].event_type, AuditLogEventType::Registration);
+}
+
+#[cfg_attr(not(feature = "db_test"), ignore)]
+#[tokio::test]
+async fn test_recover_escrowed_keys() {
+    let db = db_from_env().await.expect("Could not connect to database");
+    let repos = Repositories::new(db);
+
+    let certificate_signing_key = SoftwareEcdsaKey::new("certificate_signing_key");
+    let certificate_signing_pubkey = certificate_signing_key.verifying_key().await.unwrap();
+    let instruction_result_signing_key = SoftwareEcdsaKey::new("instruction_result_signing_key");
+
+    let (account_server, hsm) = mock::account_server_and_hsm(certificate_signing_pubkey).await;
+    let key_escrow = KeyEscrow::new("escrow_key".to_string());
+
+    let source_hw_privkey = SigningKey::random(&mut OsRng);
+    let source_pin_privkey = SigningKey::random(&mut OsRng);
+    let (source_certificate, source_cert_data) = do_registration(
+        &account_server,
+        &hsm,
+        &certificate_signing_key,
+        &source_hw_privkey,
+        &source_pin_privkey,
+        &repos,
+    )
+    .await;
+
+    let (_, target_cert_data) = do_registration(
+        &account_server,
+        &hsm,
+        &certificate_signing_key,
+        &SigningKey::random(&mut OsRng),
+        &SigningKey::random(&mut OsRng),
+        &repos,
+    )
+    .await;
+
+    // Store an escrowed key for the source wallet.
+    let WalletUserQueryResult::Found(source_user) = find_wallet_user(&repos, &source_cert_data.wallet_id).await else {
+        panic!("User should have been found");
+    };
+    let (_, wrapped_key) = hsm.generate_wrapped_key().await.unwrap();
+    let escrowed_keys = key_escrow
+        .escrow_keys(&hsm, HashMap::from([("key".to_string(), wrapped_key.clone())]))
+        .await
+        .unwrap();
+
+    let tx = repos.begin_transaction().await.unwrap();
+    repos
+        .save_keys(
+            &tx,
+            WalletUserKeys {
+                wallet_user_id: source_user.id,
+                keys: vec![WalletUserKey {
+                    wallet_user_key_id: Uuid::new_v4(),
+                    key_identifier: "key".to_string(),
+                    key: wrapped_key,
+                }],
+            },
+        )
+        .await
+        .unwrap();
+    repos
+        .save_escrowed_keys(&tx, source_user.id, escrowed_keys)
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+
+    // Request an instruction challenge for the source wallet before its keys are recovered.
+    let challenge = account_server
+        .instruction_challenge(
+            InstructionChallengeRequestMessage {
+                certificate: source_certificate.clone(),
+                message: InstructionChallengeRequest::new_signed(1, "wallet", &source_hw_privkey)
+                    .await
+                    .unwrap(),
+            },
+            &repos,
+            &EpochGenerator,
+            &hsm,
+        )
+        .await
+        .unwrap();
+
+    let recovered_keys = account_server
+        .recover_escrowed_keys(
+            &source_cert_data.wallet_id,
+            &target_cert_data.wallet_id,
+            &key_escrow,
+            &repos,
+            &Generators,
+            &hsm,
+        )
+        .await
+        .expect("Could not recover escrowed keys");
+
+    assert_eq!(recovered_keys, 1);
+
+    // The key is now available to the target wallet only.
+    let WalletUserQueryResult::Found(target_user) = find_wallet_user(&repos, &target_cert_data.wallet_id).await else {
+        panic!("User should have been found");
+    };
+    let WalletUser { id: target_user_id, .. } = *target_user;
+    let tx = repos.begin_transaction().await.unwrap();
+    let target_keys = repos.find_all_keys(&tx, target_user_id).await.unwrap();
+    assert!(target_keys.contains_key("key"));
+
+    assert_matches!(
+        find_wallet_user(&repos, &source_cert_data.wallet_id).await,
+        WalletUserQueryResult::NotFound
+    );
+    let revoked = repos.find_revoked_wallet_certificates(&tx).await.unwrap();
+    assert!(revoked
+        .iter()
+        .any(|revoked| revoked.wallet_id == source_cert_data.wallet_id));
+
+    // The source wallet can no longer sign with the recovered key, even with a challenge it received before.
+    let instruction = Instruction::new_signed(
+        Sign {
+            messages_with_identifiers: vec![(b"message".to_vec().into(), vec!["key".to_string()])],
+        },
+        2,
+        &source_hw_privkey,
+        &source_pin_privkey,
+        &challenge,
+        source_certificate,
+    )
+    .await
+    .unwrap();
+
+    let error = account_server
+        .handle_instruction::<_, _, _, SignResult, _, _>(
+            instruction,
+            None,
+            &instruction_result_signing_key,
+            &Generators,
+            &repos,
+            &TimeoutPinPolicy,
+            &IntegrityPolicy::default(),
+            &KeyUsageLimits::default(),
+            None,
+            &None::<IpAddressBurstDetector>,
+            &hsm,
+        )
+        .await
+        .expect_err("Signing with the source wallet should fail");
+
+    assert_matches!(
+        error,
+        InstructionError::WalletCertificate(WalletCertificateError::UserNotRegistered)
+    );
+}
@@ -9,10 +9,15 @@ use mime::Mime;
 use once_cell::sync::Lazy;
 use tracing::log::debug;
 
-use wallet_common::account::messages::errors::{ErrorData, ErrorType};
+use wallet_common::account::messages::errors::{ErrorData, ErrorType, InstructionSequenceNumberData, RateLimitedData};
+use wallet_provider_domain::repository::PersistenceError;
 use wallet_provider_service::{
-    account_server::{ChallengeError, InstructionError, RegistrationError, WalletCertificateError},
+    account_server::{
+        ChallengeError, InstructionError, KeyEscrowError, RegistrationError, RevocationError, SuspensionError,
+        WalletCertificateError,
+    },
     hsm,
+    rate_limiter::RateLimitedError,
 };
 
 pub static APPLICATION_PROBLEM_JSON: Lazy<Mime> =
@@ -45,7 +50,7 @@ impl IntoResponse for WalletProviderError {
 
         let bytes = serde_json::to_vec(&self.body).expect("Could not encode ErrorData to JSON.");
 
-        (
+        let mut response = (
             self.status_code,
             [(
                 header::CONTENT_TYPE,
@@ -53,7 +58,17 @@ impl IntoResponse for WalletProviderError {
             )],
             bytes,
         )
-            .into_response()
+            .into_response();
+
+        // Also communicate the time after which to retry in the standard header, which is specified in seconds.
+        if let ErrorType::RateLimited(data) = &self.body.typ {
+            response.headers_mut().insert(
+                header::RETRY_AFTER,
+                HeaderValue::from(data.retry_after_in_ms.div_ceil(1000)),
+            );
+        }
+
+        response
     }
 }
 
@@ -83,7 +98,15 @@ impl ConvertibleError for ChallengeError {
     fn error_type(&self) -> ErrorType {
         match self {
             ChallengeError::WalletCertificate(WalletCertificateError::UserBlocked) => ErrorType::AccountBlocked,
+            ChallengeError::WalletCertificate(WalletCertificateError::UserSuspended) => ErrorType::AccountSuspended,
             ChallengeError::WalletCertificate(_) => ErrorType::ChallengeValidation,
+            ChallengeError::SequenceNumberValidation { known } => {
+                ErrorType::InstructionSequenceNumber(InstructionSequenceNumberData {
+                    sequence_number: *known,
+                })
+            }
+            ChallengeError::WalletBusy(_) => ErrorType::WalletBusy,
+            ChallengeError::RateLimited(error) => rate_limited_error_type(error),
             _ => ErrorType::ChallengeValidation,
         }
     }
@@ -115,8 +138,16 @@ impl ConvertibleError for InstructionError {
             InstructionError::IncorrectPin(data) => ErrorType::IncorrectPin(*data),
             InstructionError::PinTimeout(data) => ErrorType::PinTimeout(*data),
             InstructionError::AccountBlocked => ErrorType::AccountBlocked,
+            InstructionError::WalletCertificate(WalletCertificateError::UserSuspended) => ErrorType::AccountSuspended,
             InstructionError::Validation(_) => ErrorType::InstructionValidation,
             InstructionError::KeyNotFound(data) => ErrorType::KeyNotFound(data.to_string()),
+            InstructionError::WalletBusy(_) => ErrorType::WalletBusy,
+            InstructionError::RateLimited(error) => rate_limited_error_type(error),
+            InstructionError::IntegrityEvidence(_) => ErrorType::IntegrityEvidence,
+            InstructionError::KeyUsageLimit(_) => ErrorType::KeyUsageLimit,
+            InstructionError::KeyEscrowUnavailable => ErrorType::KeyEscrowUnavailable,
+            // Do not reveal to the wallet that its behaviour was detected as anomalous.
+            InstructionError::AnomalyDetected(_) => ErrorType::InstructionValidation,
             InstructionError::Signing(_)
             | InstructionError::Storage(_)
             | InstructionError::WalletCertificate(_)
@@ -125,6 +156,44 @@ impl ConvertibleError for InstructionError {
     }
 }
 
+impl ConvertibleError for RevocationError {
+    fn error_type(&self) -> ErrorType {
+        match self {
+            RevocationError::WalletCertificate(WalletCertificateError::UserBlocked) => ErrorType::AccountBlocked,
+            RevocationError::WalletCertificate(WalletCertificateError::UserSuspended) => ErrorType::AccountSuspended,
+            RevocationError::WalletCertificate(_)
+            | RevocationError::Validation(_)
+            | RevocationError::Expired
+            | RevocationError::Replayed => ErrorType::RevocationValidation,
+            RevocationError::Storage(_) => ErrorType::Unexpected,
+        }
+    }
+}
+
+fn rate_limited_error_type(error: &RateLimitedError) -> ErrorType {
+    ErrorType::RateLimited(RateLimitedData {
+        retry_after_in_ms: error.retry_after.as_millis().try_into().unwrap_or(u64::MAX),
+    })
+}
+
+impl ConvertibleError for SuspensionError {
+    fn error_type(&self) -> ErrorType {
+        ErrorType::Unexpected
+    }
+}
+
+impl ConvertibleError for KeyEscrowError {
+    fn error_type(&self) -> ErrorType {
+        ErrorType::Unexpected
+    }
+}
+
+impl ConvertibleError for PersistenceError {
+    fn error_type(&self) -> ErrorType {
+        ErrorType::Unexpected
+    }
+}
+
 impl ConvertibleError for hsm::HsmError {
     fn error_type(&self) -> ErrorType {
         ErrorType::Unexpected
@@ -182,4 +251,34 @@ mod tests {
         });
         assert_eq!(wp_error_body, expected_body);
     }
+
+    #[test]
+    fn test_sequence_number_error_conversion() {
+        let error = ChallengeError::SequenceNumberValidation { known: 42 };
+        let wp_error = WalletProviderError::from(error);
+
+        assert_eq!(wp_error.status_code, StatusCode::CONFLICT);
+
+        let wp_error_body = serde_json::to_value(wp_error.body).expect("Could not encode error to JSON");
+
+        let expected_body = json!({
+                "type": "InstructionSequenceNumber",
+                "title": "instruction sequence number validation failed (known: 42)",
+                "data": {
+                    "sequence_number": 42,
+                }
+        });
+        assert_eq!(wp_error_body, expected_body);
+    }
+
+    #[test]
+    fn test_rate_limited_error_response() {
+        let error = InstructionError::RateLimited(RateLimitedError {
+            retry_after: std::time::Duration::from_millis(1500),
+        });
+        let response = WalletProviderError::from(error).into_response();
+
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers().get(header::RETRY_AFTER).unwrap(), "2");
+    }
 }
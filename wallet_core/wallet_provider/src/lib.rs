@@ -1,4 +1,5 @@
 pub mod errors;
+pub mod logging;
 pub mod router;
 pub mod router_state;
 pub mod server;
@@ -0,0 +1,50 @@
+use std::error::Error;
+
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::{
+    filter::ParseError, fmt, layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter, Layer, Registry,
+};
+
+use wallet_common::telemetry;
+
+use crate::settings::Settings;
+
+/// Handle to replace the filter of the global subscriber, so that the log level can be changed at runtime.
+pub type LogFilterHandle = reload::Handle<EnvFilter, Registry>;
+
+/// Install the global subscriber, which outputs logs in JSON when structured logging is enabled and exports spans over
+/// OTLP when configured.
+pub fn init(settings: &Settings) -> Result<LogFilterHandle, Box<dyn Error>> {
+    let (filter, handle) = reload::Layer::new(env_filter(settings.log_level.as_deref())?);
+
+    let fmt_layer = if settings.structured_logging {
+        fmt::layer().json().boxed()
+    } else {
+        fmt::layer().boxed()
+    };
+
+    let otlp_layer = settings
+        .otlp
+        .as_ref()
+        .map(|otlp| telemetry::otlp_layer(otlp, "wallet_provider"))
+        .transpose()?;
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt_layer)
+        .with(otlp_layer)
+        .try_init()?;
+
+    Ok(handle)
+}
+
+/// Create a filter from the configured directives or, when these are absent, from the `RUST_LOG` environment
+/// variable. Logs of which the level is not specified by the directives are filtered at INFO level.
+pub fn env_filter(log_level: Option<&str>) -> Result<EnvFilter, ParseError> {
+    let builder = EnvFilter::builder().with_default_directive(LevelFilter::INFO.into());
+
+    match log_level {
+        Some(directives) => builder.parse(directives),
+        None => Ok(builder.from_env_lossy()),
+    }
+}
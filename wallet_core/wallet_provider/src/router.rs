@@ -1,30 +1,48 @@
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
 
 use axum::{
-    extract::State,
-    http::StatusCode,
-    response::Json,
-    routing::{get, post},
+    extract::{ConnectInfo, Path, Query, State},
+    http::{header, StatusCode},
+    middleware,
+    response::{IntoResponse, Json, Response},
+    routing::{get, post, put},
     Router,
 };
-use serde::Serialize;
+use chrono::{DateTime, Local};
+use metrics_exporter_prometheus::PrometheusHandle;
+use serde::{Deserialize, Serialize};
 use tower_http::trace::TraceLayer;
-use tracing::info;
+use tracing::{info, warn};
 
 use wallet_common::{
     account::{
         messages::{
-            auth::{Certificate, Challenge, Registration},
+            auth::{Certificate, Challenge, Registration, RevocationRequestMessage},
             instructions::{
-                CheckPin, GenerateKey, GenerateKeyResult, Instruction, InstructionChallengeRequestMessage,
-                InstructionEndpoint, InstructionResultMessage, Sign, SignResult,
+                CheckPin, DeleteWallet, ExportData, ExportDataResult, GenerateKey, GenerateKeyResult, Instruction,
+                InstructionBatch, InstructionBatchResult, InstructionChallengeRequestMessage, InstructionEndpoint,
+                InstructionResultMessage, Sign, SignResult, UpdateKeyEscrowConsent,
             },
         },
         serialization::DerVerifyingKey,
         signed::SignedDouble,
     },
+    api_key::require_api_key,
+    correlation::track_correlation_id,
+    jwt::Jwks,
     keys::EcdsaKey,
+    metrics::{metrics_router, track_http_metrics},
+    utils::sha256,
+};
+use wallet_provider_domain::{
+    model::audit_log::AuditLogEvent,
+    repository::{AuditLogRepository, Committable, TransactionStarter},
 };
+use wallet_provider_service::account_server::{KeyEscrowError, SuspensionError};
 
 use crate::{errors::WalletProviderError, router_state::RouterState};
 
@@ -41,19 +59,45 @@ use crate::{errors::WalletProviderError, router_state::RouterState};
 /// be able to handle these errors appropriately.
 type Result<T> = std::result::Result<T, WalletProviderError>;
 
-pub fn router(router_state: RouterState) -> Router {
-    let state = Arc::new(router_state);
+/// The router of the default tenant, which is served on the root, and of every additional tenant, which is served on
+/// `/tenants/<name>`.
+pub fn router(state: Arc<RouterState>, tenants: &HashMap<String, Arc<RouterState>>) -> Router {
+    let mut router = Router::new()
+        .nest("/", health_router(Arc::clone(&state)))
+        .merge(tenant_router(state));
+
+    for (name, tenant_state) in tenants {
+        router = router.nest(&format!("/tenants/{}", name), tenant_router(Arc::clone(tenant_state)));
+    }
+
+    router
+        .layer(middleware::from_fn(track_http_metrics))
+        .layer(middleware::from_fn(track_correlation_id))
+}
+
+/// The routes that are served for every tenant, i.e. the API of the wallet app and the public keys of the tenant.
+fn tenant_router(state: Arc<RouterState>) -> Router {
     Router::new()
-        .nest("/", health_router())
         .nest(
             "/api/v1",
             Router::new()
                 .route("/enroll", post(enroll))
                 .route("/createwallet", post(create_wallet))
+                .route("/revokewallet", post(revoke_wallet))
                 .route("/instructions/challenge", post(instruction_challenge))
                 .route(&format!("/instructions/{}", CheckPin::ENDPOINT), post(check_pin))
                 .route(&format!("/instructions/{}", GenerateKey::ENDPOINT), post(generate_key))
                 .route(&format!("/instructions/{}", Sign::ENDPOINT), post(sign))
+                .route(&format!("/instructions/{}", InstructionBatch::ENDPOINT), post(batch))
+                .route(
+                    &format!("/instructions/{}", DeleteWallet::ENDPOINT),
+                    post(delete_wallet),
+                )
+                .route(&format!("/instructions/{}", ExportData::ENDPOINT), post(export_data))
+                .route(
+                    &format!("/instructions/{}", UpdateKeyEscrowConsent::ENDPOINT),
+                    post(update_key_escrow_consent),
+                )
                 .layer(TraceLayer::new_for_http())
                 .with_state(Arc::clone(&state)),
         )
@@ -64,10 +108,79 @@ pub fn router(router_state: RouterState) -> Router {
                 .layer(TraceLayer::new_for_http())
                 .with_state(Arc::clone(&state)),
         )
+        .nest(
+            "/.well-known",
+            Router::new()
+                .route("/jwks.json", get(jwks))
+                .route("/revocation-list.jwt", get(revocation_list))
+                .layer(TraceLayer::new_for_http())
+                .with_state(state),
+        )
+}
+
+/// The router for requests that should only be reachable from within the infrastructure of the Wallet Provider,
+/// which is served on a separate socket. Apart from the health checks and the metrics, all requests need to provide
+/// the configured API key as bearer token. Like the public router, the administrative routes of the default tenant
+/// are served on the root and those of every additional tenant on `/tenants/<name>`, so that a wallet can only be
+/// managed through the tenant it was registered with.
+pub fn internal_router(
+    state: Arc<RouterState>,
+    tenants: &HashMap<String, Arc<RouterState>>,
+    api_key: &str,
+    metrics_handle: Option<PrometheusHandle>,
+) -> Router {
+    let api_key_digest = Arc::new(sha256(api_key.as_bytes()));
+
+    let mut router = Router::new().nest("/", health_router(Arc::clone(&state)));
+    if let Some(metrics_handle) = metrics_handle {
+        router = router.merge(metrics_router(metrics_handle));
+    }
+
+    let mut admin_router = tenant_admin_router(state);
+    for (name, tenant_state) in tenants {
+        admin_router = admin_router.nest(
+            &format!("/tenants/{}", name),
+            tenant_admin_router(Arc::clone(tenant_state)),
+        );
+    }
+
+    router
+        .merge(admin_router.route_layer(middleware::from_fn_with_state(api_key_digest, require_api_key)))
+        .layer(TraceLayer::new_for_http())
+        .layer(middleware::from_fn(track_correlation_id))
+}
+
+/// The administrative routes that are served for every tenant, which only operate on the wallets of that tenant.
+fn tenant_admin_router(state: Arc<RouterState>) -> Router {
+    Router::new()
+        .route("/audit-log/:wallet_id", get(audit_log))
+        .route(
+            "/admin/wallets/:wallet_id/suspension",
+            put(suspend_wallet).delete(unsuspend_wallet),
+        )
+        .route(
+            "/admin/wallets/:wallet_id/key-escrow/recovery",
+            post(recover_escrowed_keys),
+        )
+        .with_state(state)
+}
+
+fn health_router(state: Arc<RouterState>) -> Router {
+    Router::new()
+        .route("/health", get(|| async {}))
+        .route("/health/hsm", get(hsm_health))
+        .with_state(state)
 }
 
-fn health_router() -> Router {
-    Router::new().route("/health", get(|| async {}))
+async fn hsm_health(State(state): State<Arc<RouterState>>) -> StatusCode {
+    match state.hsm.health_check().await {
+        Ok(_) => StatusCode::OK,
+        Err(error) => {
+            warn!("HSM health check failed: {}", error);
+
+            StatusCode::SERVICE_UNAVAILABLE
+        }
+    }
 }
 
 async fn enroll(State(state): State<Arc<RouterState>>) -> Result<(StatusCode, Json<Challenge>)> {
@@ -111,6 +224,22 @@ async fn create_wallet(
     Ok((StatusCode::CREATED, body.into()))
 }
 
+async fn revoke_wallet(
+    State(state): State<Arc<RouterState>>,
+    Json(payload): Json<RevocationRequestMessage>,
+) -> Result<StatusCode> {
+    info!("Received revoke wallet request, revoking with account server");
+
+    state
+        .account_server
+        .revoke(payload, &state.repositories, state.as_ref(), &state.hsm)
+        .await?;
+
+    info!("Wallet certificate revoked");
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
 async fn instruction_challenge(
     State(state): State<Arc<RouterState>>,
     Json(payload): Json<InstructionChallengeRequestMessage>,
@@ -131,30 +260,78 @@ async fn instruction_challenge(
     Ok((StatusCode::OK, body.into()))
 }
 
+/// The IP address of the client, which is only known when the router is served with connect info.
+fn ip_address(connect_info: Option<ConnectInfo<SocketAddr>>) -> Option<IpAddr> {
+    connect_info.map(|ConnectInfo(address)| address.ip())
+}
+
 async fn check_pin(
     State(state): State<Arc<RouterState>>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
     Json(payload): Json<Instruction<CheckPin>>,
 ) -> Result<(StatusCode, Json<InstructionResultMessage<()>>)> {
     info!("Received check pin request, handling the CheckPin instruction");
-    let body = state.handle_instruction(payload).await?;
+    let body = state.handle_instruction(payload, ip_address(connect_info)).await?;
     Ok((StatusCode::OK, body.into()))
 }
 
 async fn generate_key(
     State(state): State<Arc<RouterState>>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
     Json(payload): Json<Instruction<GenerateKey>>,
 ) -> Result<(StatusCode, Json<InstructionResultMessage<GenerateKeyResult>>)> {
     info!("Received generate key request, handling the GenerateKey instruction");
-    let body = state.handle_instruction(payload).await?;
+    let body = state.handle_instruction(payload, ip_address(connect_info)).await?;
     Ok((StatusCode::OK, body.into()))
 }
 
 async fn sign(
     State(state): State<Arc<RouterState>>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
     Json(payload): Json<Instruction<Sign>>,
 ) -> Result<(StatusCode, Json<InstructionResultMessage<SignResult>>)> {
     info!("Received sign request, handling the SignRequest instruction");
-    let body = state.handle_instruction(payload).await?;
+    let body = state.handle_instruction(payload, ip_address(connect_info)).await?;
+    Ok((StatusCode::OK, body.into()))
+}
+
+async fn batch(
+    State(state): State<Arc<RouterState>>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    Json(payload): Json<Instruction<InstructionBatch>>,
+) -> Result<(StatusCode, Json<InstructionResultMessage<InstructionBatchResult>>)> {
+    info!("Received batch request, handling the InstructionBatch instruction");
+    let body = state.handle_instruction(payload, ip_address(connect_info)).await?;
+    Ok((StatusCode::OK, body.into()))
+}
+
+async fn delete_wallet(
+    State(state): State<Arc<RouterState>>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    Json(payload): Json<Instruction<DeleteWallet>>,
+) -> Result<(StatusCode, Json<InstructionResultMessage<()>>)> {
+    info!("Received delete wallet request, handling the DeleteWallet instruction");
+    let body = state.handle_instruction(payload, ip_address(connect_info)).await?;
+    Ok((StatusCode::OK, body.into()))
+}
+
+async fn export_data(
+    State(state): State<Arc<RouterState>>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    Json(payload): Json<Instruction<ExportData>>,
+) -> Result<(StatusCode, Json<InstructionResultMessage<ExportDataResult>>)> {
+    info!("Received export data request, handling the ExportData instruction");
+    let body = state.handle_instruction(payload, ip_address(connect_info)).await?;
+    Ok((StatusCode::OK, body.into()))
+}
+
+async fn update_key_escrow_consent(
+    State(state): State<Arc<RouterState>>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    Json(payload): Json<Instruction<UpdateKeyEscrowConsent>>,
+) -> Result<(StatusCode, Json<InstructionResultMessage<()>>)> {
+    info!("Received update key escrow consent request, handling the UpdateKeyEscrowConsent instruction");
+    let body = state.handle_instruction(payload, ip_address(connect_info)).await?;
     Ok((StatusCode::OK, body.into()))
 }
 
@@ -175,3 +352,125 @@ async fn public_keys(State(state): State<Arc<RouterState>>) -> Result<(StatusCod
 
     Ok((StatusCode::OK, body.into()))
 }
+
+/// Publishes the public keys of the current and previous certificate and instruction result signing keys, with their
+/// JWK thumbprint as key ID, so that JWTs signed by the Wallet Provider can be verified during key rotation.
+async fn jwks(State(state): State<Arc<RouterState>>) -> (StatusCode, Json<Jwks>) {
+    (StatusCode::OK, state.jwks.clone().into())
+}
+
+/// Publishes the signed list of revoked wallet certificates, which is regenerated periodically. Until the first list
+/// has been generated, this responds with 503 Service Unavailable.
+async fn revocation_list(State(state): State<Arc<RouterState>>) -> Response {
+    match state.revocation_list.read().unwrap().as_ref() {
+        Some(revocation_list) => (
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, "application/jwt")],
+            revocation_list.0.clone(),
+        )
+            .into_response(),
+        None => StatusCode::SERVICE_UNAVAILABLE.into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+struct AuditLogParams {
+    since: Option<DateTime<Local>>,
+}
+
+async fn audit_log(
+    State(state): State<Arc<RouterState>>,
+    Path(wallet_id): Path<String>,
+    Query(params): Query<AuditLogParams>,
+) -> Result<(StatusCode, Json<Vec<AuditLogEvent>>)> {
+    info!("Received audit log request");
+
+    let tx = state.repositories.begin_transaction().await?;
+    let events = state
+        .repositories
+        .find_audit_log_events(&tx, &wallet_id, params.since)
+        .await?;
+    tx.commit().await?;
+
+    Ok((StatusCode::OK, events.into()))
+}
+
+async fn suspend_wallet(State(state): State<Arc<RouterState>>, Path(wallet_id): Path<String>) -> Result<StatusCode> {
+    info!("Received suspend wallet request");
+
+    update_wallet_suspended(&state, &wallet_id, true).await
+}
+
+async fn unsuspend_wallet(State(state): State<Arc<RouterState>>, Path(wallet_id): Path<String>) -> Result<StatusCode> {
+    info!("Received unsuspend wallet request");
+
+    update_wallet_suspended(&state, &wallet_id, false).await
+}
+
+async fn update_wallet_suspended(state: &RouterState, wallet_id: &str, is_suspended: bool) -> Result<StatusCode> {
+    let result = state
+        .account_server
+        .update_wallet_suspended(wallet_id, is_suspended, &state.repositories, state)
+        .await;
+
+    match result {
+        Ok(()) => {
+            info!("Wallet suspension updated");
+
+            Ok(StatusCode::NO_CONTENT)
+        }
+        Err(SuspensionError::UserNotRegistered) => Ok(StatusCode::NOT_FOUND),
+        Err(error) => Err(error.into()),
+    }
+}
+
+#[derive(Deserialize)]
+struct KeyEscrowRecoveryRequest {
+    target_wallet_id: String,
+}
+
+#[derive(Serialize)]
+struct KeyEscrowRecoveryResponse {
+    recovered_keys: usize,
+}
+
+/// Recovers the escrowed keys of a wallet to the wallet on the new device of the same user, after support has
+/// established that both wallets belong to that user. The wallet the keys are recovered from is revoked.
+async fn recover_escrowed_keys(
+    State(state): State<Arc<RouterState>>,
+    Path(wallet_id): Path<String>,
+    Json(payload): Json<KeyEscrowRecoveryRequest>,
+) -> Result<Response> {
+    info!("Received key escrow recovery request");
+
+    let Some(key_escrow) = state.key_escrow.as_ref() else {
+        warn!("Key escrow is not configured");
+
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+
+    let result = state
+        .account_server
+        .recover_escrowed_keys(
+            &wallet_id,
+            &payload.target_wallet_id,
+            key_escrow,
+            &state.repositories,
+            state.as_ref(),
+            &state.hsm,
+        )
+        .await;
+
+    match result {
+        Ok(recovered_keys) => {
+            info!("Recovered {} escrowed keys", recovered_keys);
+
+            Ok((StatusCode::OK, Json(KeyEscrowRecoveryResponse { recovered_keys })).into_response())
+        }
+        Err(KeyEscrowError::TargetNotFound | KeyEscrowError::NoEscrowedKeys) => {
+            Ok(StatusCode::NOT_FOUND.into_response())
+        }
+        Err(KeyEscrowError::SameWallet) => Ok(StatusCode::BAD_REQUEST.into_response()),
+        Err(error) => Err(error.into()),
+    }
+}
@@ -1,43 +1,120 @@
-use std::error::Error;
+use std::{
+    collections::HashMap,
+    error::Error,
+    net::IpAddr,
+    sync::{Arc, RwLock},
+    time::Duration as StdDuration,
+};
 
 use chrono::{DateTime, Duration, Local};
+use metrics::{counter, increment_counter};
+use p256::ecdsa::VerifyingKey;
 use serde::{de::DeserializeOwned, Serialize};
-use tracing::info;
+use tokio::{task::JoinHandle, time};
+use tracing::{debug, info, warn};
 use uuid::Uuid;
 
 use wallet_common::{
-    account::messages::instructions::{Instruction, InstructionEndpoint, InstructionResultMessage},
+    account::messages::{
+        auth::RevocationList,
+        instructions::{Instruction, InstructionEndpoint, InstructionResultMessage},
+    },
     generator::Generator,
+    jwt::{Jwk, Jwks},
     keys::EcdsaKey,
 };
-use wallet_provider_persistence::{database::Db, repositories::Repositories};
+use wallet_provider_domain::model::hsm::Hsm;
+use wallet_provider_persistence::{database::Db, repositories::Repositories, DEFAULT_TENANT};
 use wallet_provider_service::{
     account_server::AccountServer,
+    anomaly::IpAddressBurstDetector,
     hsm::Pkcs11Hsm,
     instructions::HandleInstruction,
+    integrity::{AppAttestVerifier, IntegrityPolicy, PlayIntegrityVerifier},
+    key_escrow::KeyEscrow,
+    key_usage::KeyUsageLimits,
     keys::{CertificateSigning, InstructionResultSigning, WalletProviderEcdsaKey},
     pin_policy::PinPolicy,
 };
 
-use crate::{errors::WalletProviderError, settings::Settings};
+use crate::{
+    errors::WalletProviderError,
+    settings::{KeyUsageLimitSettings, PinPolicySettings, Settings},
+};
 
 pub struct RouterState {
     pub account_server: AccountServer,
-    pub pin_policy: PinPolicy,
+    pub pin_policy: RwLock<Arc<PinPolicy>>,
+    pub integrity_policy: IntegrityPolicy,
+    pub key_usage_limits: RwLock<KeyUsageLimits>,
+    pub key_escrow: Option<KeyEscrow>,
+    pub anomaly_detector: Option<IpAddressBurstDetector>,
     pub repositories: Repositories,
     pub hsm: Pkcs11Hsm,
     pub certificate_signing_key: CertificateSigning,
     pub instruction_result_signing_key: InstructionResultSigning,
+    pub jwks: Jwks,
+    /// The most recently generated list of revoked wallet certificates, see [`Self::start_revocation_list_task()`].
+    pub revocation_list: RwLock<Option<RevocationList>>,
 }
 
+/// Value of the `use` member of the JWKs of certificate signing keys.
+pub const CERTIFICATE_SIGNING_KEY_USE: &str = "wallet_certificate";
+/// Value of the `use` member of the JWKs of instruction result signing keys.
+pub const INSTRUCTION_RESULT_SIGNING_KEY_USE: &str = "instruction_result";
+
+/// Counter of the number of rows purged by the cleanup task.
+pub const CLEANUP_PURGED_ROWS_TOTAL: &str = "wallet_provider_cleanup_purged_rows_total";
+/// Counter of the number of cleanup runs that failed.
+pub const CLEANUP_FAILURES_TOTAL: &str = "wallet_provider_cleanup_failures_total";
+
 impl RouterState {
+    /// Create the state of the default tenant, connecting to the HSM and the database.
     pub async fn new_from_settings(settings: Settings) -> Result<RouterState, Box<dyn Error>> {
         let hsm = Pkcs11Hsm::new(
-            settings.hsm.library_path,
-            settings.hsm.user_pin,
-            settings.attestation_wrapping_key_identifier,
+            settings.hsm.library_path.clone(),
+            settings.hsm.user_pin.clone(),
+            settings.hsm.max_sessions,
+            settings.hsm.max_session_lifetime_in_sec,
+            settings.attestation_wrapping_key_identifier.clone(),
         )?;
 
+        let db = Db::new(settings.database.connection_string()).await?;
+
+        Self::new_for_tenant(settings, hsm, Repositories::new(db)).await
+    }
+
+    /// Create the state of every additional tenant in the settings, by name. The tenants share the connections to the
+    /// HSM and the database with this state.
+    pub async fn new_tenants(&self, settings: &Settings) -> Result<HashMap<String, Arc<RouterState>>, Box<dyn Error>> {
+        let mut tenants = HashMap::with_capacity(settings.tenants.len());
+
+        for name in settings.tenants.keys() {
+            if name == DEFAULT_TENANT
+                || name.is_empty()
+                || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            {
+                return Err(format!("invalid tenant name: {}", name).into());
+            }
+
+            let tenant_settings = settings.for_tenant(name).unwrap();
+            let hsm = self
+                .hsm
+                .with_wrapping_key_identifier(tenant_settings.attestation_wrapping_key_identifier.clone());
+            let repositories = self.repositories.for_tenant(name.clone());
+
+            let state = Self::new_for_tenant(tenant_settings, hsm, repositories).await?;
+            tenants.insert(name.clone(), Arc::new(state));
+        }
+
+        Ok(tenants)
+    }
+
+    async fn new_for_tenant(
+        settings: Settings,
+        hsm: Pkcs11Hsm,
+        repositories: Repositories,
+    ) -> Result<RouterState, Box<dyn Error>> {
         let certificate_signing_key = CertificateSigning(WalletProviderEcdsaKey::new(
             settings.certificate_signing_key_identifier,
             hsm.clone(),
@@ -47,60 +124,142 @@ impl RouterState {
             hsm.clone(),
         ));
 
-        let certificate_signing_pubkey = certificate_signing_key.verifying_key().await?;
+        let certificate_signing_pubkeys = public_keys(
+            certificate_signing_key.verifying_key().await?,
+            &settings.previous_certificate_signing_key_identifiers,
+            &hsm,
+        )
+        .await?;
+        let instruction_result_signing_pubkeys = public_keys(
+            instruction_result_signing_key.verifying_key().await?,
+            &settings.previous_instruction_result_signing_key_identifiers,
+            &hsm,
+        )
+        .await?;
+
+        let jwks = Jwks {
+            keys: certificate_signing_pubkeys
+                .iter()
+                .map(|pubkey| Jwk::new(pubkey, CERTIFICATE_SIGNING_KEY_USE))
+                .chain(
+                    instruction_result_signing_pubkeys
+                        .iter()
+                        .map(|pubkey| Jwk::new(pubkey, INSTRUCTION_RESULT_SIGNING_KEY_USE)),
+                )
+                .collect(),
+        };
 
         let account_server = AccountServer::new(
             settings.instruction_challenge_timeout_in_ms,
+            settings.instruction_queue_max_wait_in_ms,
+            settings.instruction_rate_limit.max_requests,
+            settings.instruction_rate_limit.window_in_ms,
             "account_server".into(),
-            certificate_signing_pubkey.into(),
+            certificate_signing_pubkeys,
             settings.pin_pubkey_encryption_key_identifier,
             settings.pin_public_disclosure_protection_key_identifier,
         )
         .await?;
 
-        let db = Db::new(settings.database.connection_string()).await?;
+        let pin_policy = pin_policy(&settings.pin_policy)?;
 
-        let pin_policy = PinPolicy::new(
-            settings.pin_policy.rounds,
-            settings.pin_policy.attempts_per_round,
-            settings
-                .pin_policy
-                .timeouts_in_ms
-                .into_iter()
-                .map(|t| Duration::milliseconds(i64::from(t)))
-                .collect(),
-        );
+        let integrity_policy = IntegrityPolicy {
+            required: settings.integrity.required,
+            play_integrity: settings
+                .integrity
+                .play_integrity
+                .map(|play_integrity| {
+                    PlayIntegrityVerifier::new(
+                        play_integrity.package_name,
+                        &play_integrity.decryption_key,
+                        &play_integrity.verification_key,
+                        play_integrity.max_age_in_ms,
+                    )
+                })
+                .transpose()?,
+            app_attest: settings
+                .integrity
+                .app_attest
+                .map(|app_attest| {
+                    AppAttestVerifier::new(app_attest.app_id, app_attest.root_ca, app_attest.allow_development)
+                })
+                .transpose()?,
+        };
+
+        let key_usage_limits = key_usage_limits(&settings.key_usage_limits);
 
-        let repositories = Repositories::new(db);
+        let key_escrow = settings
+            .key_escrow
+            .map(|key_escrow| KeyEscrow::new(key_escrow.escrow_key_identifier));
+
+        let anomaly_detector = settings.anomaly_detection.map(|anomaly_detection| {
+            IpAddressBurstDetector::new(
+                anomaly_detection.max_ip_addresses,
+                anomaly_detection.window_in_ms,
+                anomaly_detection.reject,
+            )
+        });
 
         let state = RouterState {
             account_server,
             repositories,
-            pin_policy,
+            pin_policy: RwLock::new(Arc::new(pin_policy)),
+            integrity_policy,
+            key_usage_limits: RwLock::new(key_usage_limits),
+            key_escrow,
+            anomaly_detector,
             hsm,
             certificate_signing_key,
             instruction_result_signing_key,
+            jwks,
+            revocation_list: RwLock::new(None),
         };
 
         Ok(state)
     }
 
+    /// Apply the settings that can be changed while the Wallet Provider is running, i.e. the pin policy, the
+    /// instruction rate limit and the key usage limits. Any other changes to the settings are ignored. Instructions
+    /// that are already being handled are not affected.
+    pub fn update_tunable_settings(&self, settings: &Settings) -> Result<(), Box<dyn Error>> {
+        let pin_policy = pin_policy(&settings.pin_policy)?;
+
+        *self.pin_policy.write().unwrap() = Arc::new(pin_policy);
+        *self.key_usage_limits.write().unwrap() = key_usage_limits(&settings.key_usage_limits);
+        self.account_server.set_instruction_rate_limit(
+            settings.instruction_rate_limit.max_requests,
+            settings.instruction_rate_limit.window_in_ms,
+        );
+
+        Ok(())
+    }
+
     pub async fn handle_instruction<I, R>(
         &self,
         instruction: Instruction<I>,
+        ip_address: Option<IpAddr>,
     ) -> Result<InstructionResultMessage<<I as HandleInstruction>::Result>, WalletProviderError>
     where
         I: InstructionEndpoint<Result = R> + HandleInstruction<Result = R>,
         R: Serialize + DeserializeOwned,
     {
+        // Take the current tunable settings, so that these are not locked while the instruction is being handled.
+        let pin_policy = Arc::clone(&self.pin_policy.read().unwrap());
+        let key_usage_limits = *self.key_usage_limits.read().unwrap();
+
         let result = self
             .account_server
             .handle_instruction(
                 instruction,
+                ip_address,
                 &self.instruction_result_signing_key,
                 self,
                 &self.repositories,
-                &self.pin_policy,
+                pin_policy.as_ref(),
+                &self.integrity_policy,
+                &key_usage_limits,
+                self.key_escrow.as_ref(),
+                &self.anomaly_detector,
                 &self.hsm,
             )
             .await?;
@@ -109,6 +268,100 @@ impl RouterState {
 
         Ok(InstructionResultMessage { result })
     }
+
+    /// Periodically delete expired instruction challenges. When multiple instances of the Wallet Provider share the
+    /// database, the challenges may be deleted by any of them, so running this on every instance is harmless. The
+    /// number of purged rows and failed runs are recorded as metrics, labeled by the kind of state that is purged.
+    pub fn start_cleanup_task(self: Arc<Self>, interval: StdDuration) -> JoinHandle<()> {
+        let mut interval = time::interval(interval);
+        tokio::spawn(async move {
+            loop {
+                interval.tick().await;
+                match self
+                    .account_server
+                    .delete_expired_instruction_challenges(&self.repositories, self.as_ref())
+                    .await
+                {
+                    Ok(count) => {
+                        debug!("deleted {} expired instruction challenges", count);
+                        counter!(CLEANUP_PURGED_ROWS_TOTAL, count, "kind" => "instruction_challenge");
+                    }
+                    Err(error) => {
+                        warn!("could not delete expired instruction challenges: {}", error);
+                        increment_counter!(CLEANUP_FAILURES_TOTAL, "kind" => "instruction_challenge");
+                    }
+                }
+            }
+        })
+    }
+
+    /// Periodically regenerate the list of revoked wallet certificates. The first list is generated immediately;
+    /// when generating a list fails, the previous list remains published.
+    pub fn start_revocation_list_task(self: Arc<Self>, interval: StdDuration) -> JoinHandle<()> {
+        let mut interval = time::interval(interval);
+        tokio::spawn(async move {
+            loop {
+                interval.tick().await;
+                match self
+                    .account_server
+                    .revocation_list(&self.certificate_signing_key, &self.repositories, self.as_ref())
+                    .await
+                {
+                    Ok(revocation_list) => {
+                        debug!("generated revocation list");
+                        *self.revocation_list.write().unwrap() = Some(revocation_list);
+                    }
+                    Err(error) => warn!("could not generate revocation list: {}", error),
+                }
+            }
+        })
+    }
+}
+
+fn pin_policy(settings: &PinPolicySettings) -> Result<PinPolicy, Box<dyn Error>> {
+    // Check this here, as the constructor of PinPolicy panics when it does not hold.
+    if settings.rounds == 0
+        || settings.attempts_per_round == 0
+        || settings.timeouts_in_ms.len() != usize::from(settings.rounds) - 1
+    {
+        return Err("pin policy should have at least one round of at least one attempt, \
+                    and a timeout for every round but the first"
+            .into());
+    }
+
+    let pin_policy = PinPolicy::new(
+        settings.rounds,
+        settings.attempts_per_round,
+        settings
+            .timeouts_in_ms
+            .iter()
+            .map(|t| Duration::milliseconds(i64::from(*t)))
+            .collect(),
+    );
+
+    Ok(pin_policy)
+}
+
+fn key_usage_limits(settings: &KeyUsageLimitSettings) -> KeyUsageLimits {
+    KeyUsageLimits {
+        max_signatures_per_key: settings.max_signatures_per_key,
+        max_signatures_per_wallet: settings.max_signatures_per_wallet,
+    }
+}
+
+/// Fetch the public keys of the previous keys from the HSM, preceded by the public key of the current key.
+async fn public_keys(
+    current_pubkey: VerifyingKey,
+    previous_key_identifiers: &[String],
+    hsm: &Pkcs11Hsm,
+) -> Result<Vec<VerifyingKey>, Box<dyn Error>> {
+    let mut pubkeys = vec![current_pubkey];
+
+    for identifier in previous_key_identifiers {
+        pubkeys.push(Hsm::get_verifying_key(hsm, identifier).await?);
+    }
+
+    Ok(pubkeys)
 }
 
 impl Generator<uuid::Uuid> for RouterState {
@@ -1,21 +1,151 @@
 use std::{
+    collections::HashMap,
     error::Error,
+    future::Future,
+    iter,
     net::{SocketAddr, TcpListener},
+    pin::Pin,
+    sync::Arc,
 };
 
-use tracing::debug;
+use tokio::{
+    signal::unix::{signal, SignalKind},
+    task::JoinHandle,
+};
+use tracing::{debug, info, warn};
+
+use wallet_common::{metrics, tls::tls_config};
+
+use super::{
+    logging::{self, LogFilterHandle},
+    router,
+    router_state::RouterState,
+    settings::Settings,
+};
 
-use super::{router, router_state::RouterState, settings::Settings};
+type ServerFuture = Pin<Box<dyn Future<Output = Result<(), Box<dyn Error + Send + Sync>>> + Send>>;
 
-pub async fn serve(settings: Settings) -> Result<(), Box<dyn Error>> {
+/// Serve the Wallet Provider. When the process receives SIGHUP, the settings are read again and the tunable settings
+/// are applied, see [`RouterState::update_tunable_settings()`]. The log level is only changed when a handle to the
+/// filter of the global subscriber is provided.
+pub async fn serve(settings: Settings, log_filter_handle: Option<LogFilterHandle>) -> Result<(), Box<dyn Error>> {
     let socket = SocketAddr::new(settings.webserver.ip, settings.webserver.port);
     let listener = TcpListener::bind(socket)?;
     debug!("listening on {}", socket);
 
-    let router_state = RouterState::new_from_settings(settings).await?;
+    let internal_listener = settings
+        .internal_webserver
+        .as_ref()
+        .map(|webserver| {
+            let socket = SocketAddr::new(webserver.ip, webserver.port);
+            debug!("listening for internal requests on {}", socket);
+
+            TcpListener::bind(socket).map(|listener| (listener, webserver.api_key.clone()))
+        })
+        .transpose()?;
+
+    let metrics_handle = settings.metrics_enabled.then(metrics::install_recorder).transpose()?;
+    if metrics_handle.is_some() && internal_listener.is_none() {
+        warn!("metrics are enabled, but cannot be exposed because no internal webserver is configured");
+    }
+
+    let router_state = Arc::new(RouterState::new_from_settings(settings.clone()).await?);
+    let tenant_states = router_state.new_tenants(&settings).await?;
+    // The instruction challenges of all tenants are stored in the same table, so a single task suffices.
+    let _cleanup_task = Arc::clone(&router_state).start_cleanup_task(settings.cleanup_interval_in_ms);
+    let _revocation_list_tasks = iter::once(&router_state)
+        .chain(tenant_states.values())
+        .map(|state| Arc::clone(state).start_revocation_list_task(settings.revocation_list_interval_in_ms))
+        .collect::<Vec<_>>();
+
+    let app = router::router(Arc::clone(&router_state), &tenant_states);
+    let internal_app = internal_listener.map(|(internal_listener, api_key)| {
+        let internal_app = router::internal_router(Arc::clone(&router_state), &tenant_states, &api_key, metrics_handle);
+
+        (internal_listener, internal_app)
+    });
+    let _reload_task = start_reload_task(Arc::clone(&router_state), tenant_states, log_filter_handle)?;
+
+    // The TLS configuration is reloaded by a separate task when the certificate or private key changes.
+    let tls = settings.webserver.tls.as_ref().map(tls_config).transpose()?;
+    let app = app.into_make_service_with_connect_info::<SocketAddr>();
+    let server: ServerFuture = match tls {
+        Some((config, _reload_task)) => {
+            debug!("serving {} using TLS", socket);
+            let server = axum_server::from_tcp_rustls(listener, config).serve(app);
+
+            Box::pin(async move { server.await.map_err(Box::<dyn Error + Send + Sync>::from) })
+        }
+        None => {
+            let server = axum::Server::from_tcp(listener)?.serve(app);
+
+            Box::pin(async move { server.await.map_err(Box::<dyn Error + Send + Sync>::from) })
+        }
+    };
+
+    match internal_app {
+        Some((internal_listener, internal_app)) => {
+            let internal_server = axum::Server::from_tcp(internal_listener)?.serve(internal_app.into_make_service());
+
+            tokio::try_join!(server, async {
+                internal_server.await.map_err(Box::<dyn Error + Send + Sync>::from)
+            })
+            .map_err(|error| error as Box<dyn Error>)?;
+        }
+        None => server.await.map_err(|error| error as Box<dyn Error>)?,
+    }
+
+    Ok(())
+}
+
+fn start_reload_task(
+    router_state: Arc<RouterState>,
+    tenant_states: HashMap<String, Arc<RouterState>>,
+    log_filter_handle: Option<LogFilterHandle>,
+) -> Result<JoinHandle<()>, std::io::Error> {
+    let mut hangup = signal(SignalKind::hangup())?;
+
+    let task = tokio::spawn(async move {
+        while hangup.recv().await.is_some() {
+            info!("received SIGHUP, reloading settings");
+
+            if let Err(error) = reload_settings(&router_state, &tenant_states, log_filter_handle.as_ref()) {
+                warn!("could not reload settings: {}", error);
+            }
+        }
+    });
+
+    Ok(task)
+}
+
+fn reload_settings(
+    router_state: &RouterState,
+    tenant_states: &HashMap<String, Arc<RouterState>>,
+    log_filter_handle: Option<&LogFilterHandle>,
+) -> Result<(), Box<dyn Error>> {
+    let settings = Settings::new()?;
+
+    // Parse the filter before applying any of the settings, so that an invalid filter does not cause a partial reload.
+    let filter = log_filter_handle
+        .map(|_| logging::env_filter(settings.log_level.as_deref()))
+        .transpose()?;
+
+    router_state.update_tunable_settings(&settings)?;
+
+    // Tenants cannot be added or removed without a restart, so the settings of removed tenants are left untouched.
+    for (name, tenant_state) in tenant_states {
+        match settings.for_tenant(name) {
+            Some(tenant_settings) => tenant_state.update_tunable_settings(&tenant_settings)?,
+            None => warn!(
+                "tenant {} was removed from the settings, which requires a restart",
+                name
+            ),
+        }
+    }
 
-    let app = router::router(router_state);
-    axum::Server::from_tcp(listener)?.serve(app.into_make_service()).await?;
+    if let (Some(handle), Some(filter)) = (log_filter_handle, filter) {
+        handle.reload(filter)?;
+    }
 
     Ok(())
 }
@@ -1,10 +1,11 @@
-use std::{env, net::IpAddr, path::PathBuf};
+use std::{collections::HashMap, env, net::IpAddr, path::PathBuf, time::Duration as StdDuration};
 
 use chrono::Duration;
 use config::{Config, ConfigError, Environment, File};
 use serde::Deserialize;
-use serde_with::{serde_as, DurationMilliSeconds};
+use serde_with::{base64::Base64, serde_as, DurationMilliSeconds, DurationSeconds};
 
+use wallet_common::{telemetry::OtlpSettings, tls::TlsSettings};
 use wallet_provider_database_settings::{Database, DatabaseDefaults};
 
 #[serde_as]
@@ -12,22 +13,86 @@ use wallet_provider_database_settings::{Database, DatabaseDefaults};
 pub struct Settings {
     pub certificate_signing_key_identifier: String,
     pub instruction_result_signing_key_identifier: String,
+    /// Identifiers of keys that were used to sign wallet certificates before the current key. Wallet certificates
+    /// signed with these keys are still accepted and the public keys are published in the JWKS.
+    #[serde(default)]
+    pub previous_certificate_signing_key_identifiers: Vec<String>,
+    /// Identifiers of keys that were used to sign instruction results before the current key, of which the public keys
+    /// are published in the JWKS.
+    #[serde(default)]
+    pub previous_instruction_result_signing_key_identifiers: Vec<String>,
     pub attestation_wrapping_key_identifier: String,
     pub pin_pubkey_encryption_key_identifier: String,
     pub pin_public_disclosure_protection_key_identifier: String,
     pub database: Database,
     pub webserver: Webserver,
+    pub internal_webserver: Option<InternalWebserver>,
     pub hsm: Hsm,
     pub pin_policy: PinPolicySettings,
+    pub integrity: IntegritySettings,
     pub structured_logging: bool,
+    /// Filter directives in the format of the `RUST_LOG` environment variable, e.g. `info,wallet_provider=debug`.
+    /// When absent, `RUST_LOG` is used, with INFO as default level.
+    pub log_level: Option<String>,
+    pub metrics_enabled: bool,
+    /// When configured, spans are exported to an OpenTelemetry collector.
+    pub otlp: Option<OtlpSettings>,
     #[serde_as(as = "DurationMilliSeconds<i64>")]
     pub instruction_challenge_timeout_in_ms: Duration,
+    #[serde_as(as = "DurationMilliSeconds<i64>")]
+    pub instruction_queue_max_wait_in_ms: Duration,
+    pub instruction_rate_limit: RateLimitSettings,
+    /// The interval at which the list of revoked wallet certificates is regenerated.
+    #[serde_as(as = "DurationMilliSeconds<u64>")]
+    pub revocation_list_interval_in_ms: StdDuration,
+    /// The interval at which expired instruction challenges are purged from the database.
+    #[serde_as(as = "DurationMilliSeconds<u64>")]
+    pub cleanup_interval_in_ms: StdDuration,
+    #[serde(default)]
+    pub key_usage_limits: KeyUsageLimitSettings,
+    /// When configured, the private keys of wallet users that consent to key escrow are escrowed.
+    pub key_escrow: Option<KeyEscrowSettings>,
+    /// When configured, instructions of a wallet that are sent from many different IP addresses in a short period
+    /// of time are flagged as suspicious or rejected.
+    pub anomaly_detection: Option<AnomalyDetectionSettings>,
+    /// Additional tenants, i.e. wallet apps that are served by this Wallet Provider with their own keys and
+    /// policies, by name. The settings above constitute the default tenant.
+    #[serde(default)]
+    pub tenants: HashMap<String, TenantSettings>,
+}
+
+/// The keys and policies of a tenant. Policies that are not configured are taken from the default tenant. Key escrow
+/// is only enabled for the tenant when it is configured for the tenant itself.
+#[derive(Clone, Deserialize)]
+pub struct TenantSettings {
+    pub certificate_signing_key_identifier: String,
+    pub instruction_result_signing_key_identifier: String,
+    #[serde(default)]
+    pub previous_certificate_signing_key_identifiers: Vec<String>,
+    #[serde(default)]
+    pub previous_instruction_result_signing_key_identifiers: Vec<String>,
+    pub attestation_wrapping_key_identifier: String,
+    pub pin_public_disclosure_protection_key_identifier: String,
+    pub pin_policy: Option<PinPolicySettings>,
+    pub integrity: Option<IntegritySettings>,
+    pub key_usage_limits: Option<KeyUsageLimitSettings>,
+    pub key_escrow: Option<KeyEscrowSettings>,
 }
 
 #[derive(Clone, Deserialize)]
 pub struct Webserver {
     pub ip: IpAddr,
     pub port: u16,
+    /// When configured, the webserver is served over TLS instead of plain HTTP.
+    pub tls: Option<TlsSettings>,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct InternalWebserver {
+    pub ip: IpAddr,
+    pub port: u16,
+    /// The key that has to be provided as bearer token in requests to the audit log and admin endpoints.
+    pub api_key: String,
 }
 
 #[derive(Clone, Deserialize)]
@@ -37,10 +102,75 @@ pub struct PinPolicySettings {
     pub timeouts_in_ms: Vec<u32>,
 }
 
+#[serde_as]
+#[derive(Clone, Deserialize)]
+pub struct RateLimitSettings {
+    pub max_requests: u32,
+    #[serde_as(as = "DurationMilliSeconds<i64>")]
+    pub window_in_ms: Duration,
+}
+
+#[derive(Clone, Default, Deserialize)]
+pub struct KeyUsageLimitSettings {
+    pub max_signatures_per_key: Option<u64>,
+    pub max_signatures_per_wallet: Option<u64>,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct KeyEscrowSettings {
+    /// Identifier of the key in the HSM that is used to encrypt the wrapped private keys for escrow.
+    pub escrow_key_identifier: String,
+}
+
+#[serde_as]
+#[derive(Clone, Deserialize)]
+pub struct AnomalyDetectionSettings {
+    /// The maximum number of distinct IP addresses from which a single wallet can send instructions within the window.
+    pub max_ip_addresses: usize,
+    #[serde_as(as = "DurationMilliSeconds<i64>")]
+    pub window_in_ms: Duration,
+    /// Reject instructions that exceed the maximum, instead of only logging these.
+    #[serde(default)]
+    pub reject: bool,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct IntegritySettings {
+    pub required: bool,
+    pub play_integrity: Option<PlayIntegritySettings>,
+    pub app_attest: Option<AppAttestSettings>,
+}
+
+#[serde_as]
+#[derive(Clone, Deserialize)]
+pub struct PlayIntegritySettings {
+    pub package_name: String,
+    #[serde_as(as = "Base64")]
+    pub decryption_key: Vec<u8>,
+    #[serde_as(as = "Base64")]
+    pub verification_key: Vec<u8>,
+    #[serde_as(as = "DurationMilliSeconds<i64>")]
+    pub max_age_in_ms: Duration,
+}
+
+#[serde_as]
+#[derive(Clone, Deserialize)]
+pub struct AppAttestSettings {
+    pub app_id: String,
+    #[serde_as(as = "Base64")]
+    pub root_ca: Vec<u8>,
+    #[serde(default)]
+    pub allow_development: bool,
+}
+
+#[serde_as]
 #[derive(Clone, Deserialize)]
 pub struct Hsm {
     pub library_path: PathBuf,
     pub user_pin: String,
+    pub max_sessions: u8,
+    #[serde_as(as = "DurationSeconds<u64>")]
+    pub max_session_lifetime_in_sec: StdDuration,
 }
 
 impl Settings {
@@ -64,11 +194,20 @@ impl Settings {
             )?
             .set_default("webserver.ip", "0.0.0.0")?
             .set_default("webserver.port", 3000)?
+            .set_default("hsm.max_sessions", 10)?
+            .set_default("hsm.max_session_lifetime_in_sec", 900)?
             .set_default("pin_policy.rounds", 4)?
             .set_default("pin_policy.attempts_per_round", 4)?
             .set_default("pin_policy.timeouts_in_ms", vec![60_000, 300_000, 3_600_000])?
+            .set_default("integrity.required", false)?
             .set_default("structured_logging", false)?
+            .set_default("metrics_enabled", false)?
             .set_default("instruction_challenge_timeout_in_ms", 15_000)?
+            .set_default("instruction_queue_max_wait_in_ms", 10_000)?
+            .set_default("instruction_rate_limit.max_requests", 60)?
+            .set_default("instruction_rate_limit.window_in_ms", 60_000)?
+            .set_default("revocation_list_interval_in_ms", 300_000)?
+            .set_default("cleanup_interval_in_ms", 60_000)?
             .add_source(File::from(config_path.join("wallet_provider.toml")).required(false))
             .add_source(
                 Environment::with_prefix("wallet_provider")
@@ -79,4 +218,33 @@ impl Settings {
             .build()?
             .try_deserialize()
     }
+
+    /// The settings of the tenant with the provided name, which consist of these settings with the keys and policies
+    /// of the tenant applied. Returns `None` when no such tenant is configured.
+    pub fn for_tenant(&self, name: &str) -> Option<Settings> {
+        let tenant = self.tenants.get(name)?.clone();
+
+        let mut settings = self.clone();
+        settings.certificate_signing_key_identifier = tenant.certificate_signing_key_identifier;
+        settings.instruction_result_signing_key_identifier = tenant.instruction_result_signing_key_identifier;
+        settings.previous_certificate_signing_key_identifiers = tenant.previous_certificate_signing_key_identifiers;
+        settings.previous_instruction_result_signing_key_identifiers =
+            tenant.previous_instruction_result_signing_key_identifiers;
+        settings.attestation_wrapping_key_identifier = tenant.attestation_wrapping_key_identifier;
+        settings.pin_public_disclosure_protection_key_identifier =
+            tenant.pin_public_disclosure_protection_key_identifier;
+        if let Some(pin_policy) = tenant.pin_policy {
+            settings.pin_policy = pin_policy;
+        }
+        if let Some(integrity) = tenant.integrity {
+            settings.integrity = integrity;
+        }
+        if let Some(key_usage_limits) = tenant.key_usage_limits {
+            settings.key_usage_limits = key_usage_limits;
+        }
+        settings.key_escrow = tenant.key_escrow;
+        settings.tenants = HashMap::new();
+
+        Some(settings)
+    }
 }
@@ -21,12 +21,23 @@ fn setup_hsm() -> (Pkcs11Hsm, Settings) {
     let hsm = Pkcs11Hsm::new(
         settings.hsm.library_path,
         settings.hsm.user_pin,
+        settings.hsm.max_sessions,
+        settings.hsm.max_session_lifetime_in_sec,
         settings.attestation_wrapping_key_identifier,
     )
     .unwrap();
     (hsm, Settings::new().unwrap())
 }
 
+#[tokio::test]
+#[serial]
+#[cfg_attr(not(feature = "db_test"), ignore)]
+async fn health_check() {
+    let (hsm, _) = setup_hsm();
+
+    hsm.health_check().await.unwrap();
+}
+
 #[tokio::test]
 #[serial]
 #[cfg_attr(not(feature = "db_test"), ignore)]
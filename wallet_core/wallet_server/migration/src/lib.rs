@@ -3,12 +3,20 @@ use async_trait::async_trait;
 pub use sea_orm_migration::prelude::*;
 
 mod m20220101_000001_create_table;
+mod m20240115_000001_create_session_evidence_table;
+mod m20240226_000001_create_session_state_expiration_index;
+mod m20240304_000001_create_disclosure_audit_log_table;
 
 pub struct Migrator;
 
 #[async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(m20220101_000001_create_table::Migration)]
+        vec![
+            Box::new(m20220101_000001_create_table::Migration),
+            Box::new(m20240115_000001_create_session_evidence_table::Migration),
+            Box::new(m20240226_000001_create_session_state_expiration_index::Migration),
+            Box::new(m20240304_000001_create_disclosure_audit_log_table::Migration),
+        ]
     }
 }
@@ -0,0 +1,36 @@
+use async_trait::async_trait;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(SessionEvidence::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(SessionEvidence::Token).string().not_null().primary_key())
+                    .col(ColumnDef::new(SessionEvidence::SealedData).binary().not_null())
+                    .col(
+                        ColumnDef::new(SessionEvidence::ExpirationDateTime)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum SessionEvidence {
+    Table,
+    Token,
+    SealedData,
+    ExpirationDateTime,
+}
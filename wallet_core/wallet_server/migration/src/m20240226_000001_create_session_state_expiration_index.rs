@@ -0,0 +1,30 @@
+use async_trait::async_trait;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // The expiration time is used to filter expired sessions and to delete these in the cleanup task.
+        manager
+            .create_index(
+                Index::create()
+                    .name("ix_session_state_expiration_date_time")
+                    .table(SessionState::Table)
+                    .col(SessionState::ExpirationDateTime)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum SessionState {
+    Table,
+    ExpirationDateTime,
+}
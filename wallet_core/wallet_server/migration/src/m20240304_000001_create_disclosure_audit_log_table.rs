@@ -0,0 +1,65 @@
+use async_trait::async_trait;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(DisclosureAuditLog::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(DisclosureAuditLog::Token)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(DisclosureAuditLog::Usecase).string().not_null())
+                    .col(ColumnDef::new(DisclosureAuditLog::Status).string())
+                    .col(
+                        ColumnDef::new(DisclosureAuditLog::RequestedAttributes)
+                            .json_binary()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(DisclosureAuditLog::DisclosedAttributes).json_binary())
+                    .col(
+                        ColumnDef::new(DisclosureAuditLog::StartedDateTime)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(DisclosureAuditLog::CompletedDateTime).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await?;
+
+        // The completion time is used to query the audit log.
+        manager
+            .create_index(
+                Index::create()
+                    .name("ix_disclosure_audit_log_completed_date_time")
+                    .table(DisclosureAuditLog::Table)
+                    .col(DisclosureAuditLog::CompletedDateTime)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum DisclosureAuditLog {
+    Table,
+    Token,
+    Usecase,
+    Status,
+    RequestedAttributes,
+    DisclosedAttributes,
+    StartedDateTime,
+    CompletedDateTime,
+}
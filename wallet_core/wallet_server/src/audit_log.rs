@@ -0,0 +1,519 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use nl_wallet_mdoc::{
+    identifiers::{AttributeIdentifier, AttributeIdentifierHolder},
+    server_state::SessionToken,
+    verifier::{DisclosedAttributes, ItemsRequests, SessionResult, StatusResponse},
+};
+
+#[cfg(feature = "postgres")]
+use crate::audit_log::postgres::PostgresAuditLogStore;
+use crate::{
+    settings::AuditLog as AuditLogSettings,
+    store::{CleanupTask, StoreConnection},
+};
+
+/// The maximum number of entries returned by a single query.
+pub const MAX_QUERY_LIMIT: u64 = 1_000;
+
+#[derive(Debug, thiserror::Error)]
+pub enum AuditLogError {
+    #[error("could not (de)serialize audit log entry: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("invalid status in audit log entry: {0}")]
+    InvalidStatus(String),
+    #[error("audit log store error: {0}")]
+    Store(#[source] Box<dyn std::error::Error + Send + Sync + 'static>),
+}
+
+/// An entry in the audit log, describing a disclosure session that has ended. It only contains the identifiers of
+/// the requested and disclosed attributes, never their values.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AuditLogEntry {
+    pub session_id: SessionToken,
+    pub usecase: String,
+    #[serde(flatten)]
+    pub status: StatusResponse,
+    /// The requested attributes, formatted as `<doc_type>/<namespace>/<attribute>`.
+    pub requested_attributes: Vec<String>,
+    /// The disclosed attributes, formatted as `<doc_type>/<namespace>/<attribute>`. Only present if the status is
+    /// `DONE`.
+    pub disclosed_attributes: Vec<String>,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: DateTime<Utc>,
+}
+
+/// Filters applied when querying the audit log, of which the entries are returned in order of completion.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AuditLogQuery {
+    pub usecase: Option<String>,
+    /// Only return entries of sessions that were completed at or after this time.
+    pub since: Option<DateTime<Utc>>,
+    /// Only return entries of sessions that were completed before this time.
+    pub until: Option<DateTime<Utc>>,
+    /// The maximum number of entries to return, which is capped at [`MAX_QUERY_LIMIT`].
+    pub limit: Option<u64>,
+}
+
+/// Records every disclosure session that has ended in a persistent audit log, to support relying parties in their
+/// compliance obligations. A session is recorded when it is started, so that the requested attributes are known, and
+/// is completed once it has ended. Only completed sessions are returned when querying the audit log.
+pub struct AuditLog {
+    entries: Arc<AuditLogStore>,
+    _cleanup_task: CleanupTask,
+}
+
+impl AuditLog {
+    pub fn new(settings: AuditLogSettings, connection: StoreConnection) -> Self {
+        let retention = chrono::Duration::days(settings.retention_days as i64);
+
+        let entries = Arc::new(AuditLogStore::new(connection));
+        // The entries that are older than the retention period are removed every so often.
+        let cleanup_task = CleanupTask::start(Arc::clone(&entries), move |entries| async move {
+            if let Err(error) = entries.cleanup(Utc::now() - retention).await {
+                warn!("could not clean up expired audit log entries: {}", error);
+            }
+        });
+
+        Self {
+            entries,
+            _cleanup_task: cleanup_task,
+        }
+    }
+
+    pub async fn record_started(
+        &self,
+        session_id: &SessionToken,
+        usecase: &str,
+        items_requests: &ItemsRequests,
+    ) -> Result<(), AuditLogError> {
+        let requested_attributes = format_attribute_identifiers(items_requests.attribute_identifiers());
+
+        self.entries
+            .insert(session_id, usecase, requested_attributes, Utc::now())
+            .await
+    }
+
+    /// Completes the entry of a session with its result. Entries that have already been completed are left as is.
+    pub async fn record_completed(
+        &self,
+        session_id: &SessionToken,
+        session_result: &SessionResult,
+    ) -> Result<(), AuditLogError> {
+        let (status, disclosed_attributes) = match session_result {
+            SessionResult::Done {
+                disclosed_attributes, ..
+            } => (
+                StatusResponse::Done,
+                disclosed_attribute_identifiers(disclosed_attributes),
+            ),
+            SessionResult::Failed { .. } => (StatusResponse::Failed, vec![]),
+            SessionResult::Cancelled => (StatusResponse::Cancelled, vec![]),
+        };
+
+        self.entries
+            .complete(session_id, status, disclosed_attributes, Utc::now())
+            .await
+    }
+
+    pub async fn query(&self, query: AuditLogQuery) -> Result<Vec<AuditLogEntry>, AuditLogError> {
+        let limit = query.limit.unwrap_or(MAX_QUERY_LIMIT).min(MAX_QUERY_LIMIT);
+
+        self.entries.query(query, limit).await
+    }
+}
+
+fn format_attribute_identifiers(identifiers: impl IntoIterator<Item = AttributeIdentifier>) -> Vec<String> {
+    identifiers
+        .into_iter()
+        .map(|identifier| {
+            format!(
+                "{}/{}/{}",
+                identifier.doc_type, identifier.namespace, identifier.attribute
+            )
+        })
+        .collect()
+}
+
+fn disclosed_attribute_identifiers(disclosed_attributes: &DisclosedAttributes) -> Vec<String> {
+    format_attribute_identifiers(disclosed_attributes.iter().flat_map(|(doc_type, namespaces)| {
+        namespaces.iter().flat_map(move |(namespace, entries)| {
+            entries.iter().map(move |entry| AttributeIdentifier {
+                doc_type: doc_type.clone(),
+                namespace: namespace.clone(),
+                attribute: entry.name.clone(),
+            })
+        })
+    }))
+}
+
+/// An entry of a session that has been started, but that may not have ended yet.
+#[derive(Debug, Clone)]
+struct MemoryEntry {
+    usecase: String,
+    requested_attributes: Vec<String>,
+    started_at: DateTime<Utc>,
+    completion: Option<(StatusResponse, Vec<String>, DateTime<Utc>)>,
+}
+
+/// Contains the audit log, in the same kind of storage as the sessions.
+enum AuditLogStore {
+    #[cfg(feature = "postgres")]
+    Postgres(PostgresAuditLogStore),
+    Memory(DashMap<SessionToken, MemoryEntry>),
+}
+
+impl AuditLogStore {
+    fn new(connection: StoreConnection) -> Self {
+        match connection {
+            #[cfg(feature = "postgres")]
+            StoreConnection::Postgres(connection) => AuditLogStore::Postgres(PostgresAuditLogStore::new(connection)),
+            StoreConnection::Memory => AuditLogStore::Memory(DashMap::new()),
+        }
+    }
+
+    async fn insert(
+        &self,
+        session_id: &SessionToken,
+        usecase: &str,
+        requested_attributes: Vec<String>,
+        started_at: DateTime<Utc>,
+    ) -> Result<(), AuditLogError> {
+        match self {
+            #[cfg(feature = "postgres")]
+            AuditLogStore::Postgres(postgres) => {
+                postgres
+                    .insert(session_id, usecase, requested_attributes, started_at)
+                    .await
+            }
+            AuditLogStore::Memory(memory) => {
+                memory.insert(
+                    session_id.clone(),
+                    MemoryEntry {
+                        usecase: usecase.to_string(),
+                        requested_attributes,
+                        started_at,
+                        completion: None,
+                    },
+                );
+                Ok(())
+            }
+        }
+    }
+
+    async fn complete(
+        &self,
+        session_id: &SessionToken,
+        status: StatusResponse,
+        disclosed_attributes: Vec<String>,
+        completed_at: DateTime<Utc>,
+    ) -> Result<(), AuditLogError> {
+        match self {
+            #[cfg(feature = "postgres")]
+            AuditLogStore::Postgres(postgres) => {
+                postgres
+                    .complete(session_id, status, disclosed_attributes, completed_at)
+                    .await
+            }
+            AuditLogStore::Memory(memory) => {
+                if let Some(mut entry) = memory.get_mut(session_id) {
+                    entry
+                        .completion
+                        .get_or_insert((status, disclosed_attributes, completed_at));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    async fn query(&self, query: AuditLogQuery, limit: u64) -> Result<Vec<AuditLogEntry>, AuditLogError> {
+        match self {
+            #[cfg(feature = "postgres")]
+            AuditLogStore::Postgres(postgres) => postgres.query(query, limit).await,
+            AuditLogStore::Memory(memory) => {
+                let mut entries = memory
+                    .iter()
+                    .filter_map(|entry| {
+                        let (session_id, entry) = entry.pair();
+                        let (status, disclosed_attributes, completed_at) = entry.completion.clone()?;
+
+                        Some(AuditLogEntry {
+                            session_id: session_id.clone(),
+                            usecase: entry.usecase.clone(),
+                            status,
+                            requested_attributes: entry.requested_attributes.clone(),
+                            disclosed_attributes,
+                            started_at: entry.started_at,
+                            completed_at,
+                        })
+                    })
+                    .filter(|entry| query.usecase.as_ref().map_or(true, |usecase| entry.usecase == *usecase))
+                    .filter(|entry| query.since.map_or(true, |since| entry.completed_at >= since))
+                    .filter(|entry| query.until.map_or(true, |until| entry.completed_at < until))
+                    .collect::<Vec<_>>();
+
+                entries.sort_by_key(|entry| entry.completed_at);
+                entries.truncate(limit as usize);
+
+                Ok(entries)
+            }
+        }
+    }
+
+    async fn cleanup(&self, threshold: DateTime<Utc>) -> Result<(), AuditLogError> {
+        match self {
+            #[cfg(feature = "postgres")]
+            AuditLogStore::Postgres(postgres) => postgres.cleanup(threshold).await,
+            AuditLogStore::Memory(memory) => {
+                memory.retain(|_, entry| entry.started_at > threshold);
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(feature = "postgres")]
+mod postgres {
+    use chrono::{DateTime, Utc};
+    use sea_orm::{
+        sea_query::Expr, ActiveValue, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder,
+        QuerySelect,
+    };
+
+    use nl_wallet_mdoc::{server_state::SessionToken, verifier::StatusResponse};
+
+    use super::{AuditLogEntry, AuditLogError, AuditLogQuery};
+    use crate::entity::disclosure_audit_log;
+
+    pub struct PostgresAuditLogStore {
+        connection: DatabaseConnection,
+    }
+
+    impl PostgresAuditLogStore {
+        pub fn new(connection: DatabaseConnection) -> Self {
+            Self { connection }
+        }
+
+        pub async fn insert(
+            &self,
+            session_id: &SessionToken,
+            usecase: &str,
+            requested_attributes: Vec<String>,
+            started_at: DateTime<Utc>,
+        ) -> Result<(), AuditLogError> {
+            disclosure_audit_log::Entity::insert(disclosure_audit_log::ActiveModel {
+                token: ActiveValue::set(session_id.to_string()),
+                usecase: ActiveValue::set(usecase.to_string()),
+                status: ActiveValue::set(None),
+                requested_attributes: ActiveValue::set(serde_json::to_value(requested_attributes)?),
+                disclosed_attributes: ActiveValue::set(None),
+                started_date_time: ActiveValue::set(started_at.into()),
+                completed_date_time: ActiveValue::set(None),
+            })
+            .exec(&self.connection)
+            .await
+            .map_err(|e| AuditLogError::Store(e.into()))?;
+
+            Ok(())
+        }
+
+        pub async fn complete(
+            &self,
+            session_id: &SessionToken,
+            status: StatusResponse,
+            disclosed_attributes: Vec<String>,
+            completed_at: DateTime<Utc>,
+        ) -> Result<(), AuditLogError> {
+            disclosure_audit_log::Entity::update_many()
+                .col_expr(disclosure_audit_log::Column::Status, Expr::value(status.to_string()))
+                .col_expr(
+                    disclosure_audit_log::Column::DisclosedAttributes,
+                    Expr::value(serde_json::to_value(disclosed_attributes)?),
+                )
+                .col_expr(
+                    disclosure_audit_log::Column::CompletedDateTime,
+                    Expr::value(completed_at),
+                )
+                .filter(disclosure_audit_log::Column::Token.eq(session_id.to_string()))
+                .filter(disclosure_audit_log::Column::CompletedDateTime.is_null())
+                .exec(&self.connection)
+                .await
+                .map_err(|e| AuditLogError::Store(e.into()))?;
+
+            Ok(())
+        }
+
+        pub async fn query(&self, query: AuditLogQuery, limit: u64) -> Result<Vec<AuditLogEntry>, AuditLogError> {
+            let mut select = disclosure_audit_log::Entity::find()
+                .filter(disclosure_audit_log::Column::CompletedDateTime.is_not_null());
+            if let Some(usecase) = query.usecase {
+                select = select.filter(disclosure_audit_log::Column::Usecase.eq(usecase));
+            }
+            if let Some(since) = query.since {
+                select = select.filter(disclosure_audit_log::Column::CompletedDateTime.gte(since));
+            }
+            if let Some(until) = query.until {
+                select = select.filter(disclosure_audit_log::Column::CompletedDateTime.lt(until));
+            }
+
+            let models = select
+                .order_by_asc(disclosure_audit_log::Column::CompletedDateTime)
+                .limit(limit)
+                .all(&self.connection)
+                .await
+                .map_err(|e| AuditLogError::Store(e.into()))?;
+
+            models.into_iter().map(TryInto::try_into).collect()
+        }
+
+        pub async fn cleanup(&self, threshold: DateTime<Utc>) -> Result<(), AuditLogError> {
+            disclosure_audit_log::Entity::delete_many()
+                .filter(disclosure_audit_log::Column::StartedDateTime.lt(threshold))
+                .exec(&self.connection)
+                .await
+                .map_err(|e| AuditLogError::Store(e.into()))?;
+
+            Ok(())
+        }
+    }
+
+    impl TryFrom<disclosure_audit_log::Model> for AuditLogEntry {
+        type Error = AuditLogError;
+
+        fn try_from(value: disclosure_audit_log::Model) -> Result<Self, Self::Error> {
+            let status = value.status.unwrap_or_default();
+
+            Ok(AuditLogEntry {
+                session_id: value.token.into(),
+                usecase: value.usecase,
+                status: status.parse().map_err(|_| AuditLogError::InvalidStatus(status))?,
+                requested_attributes: serde_json::from_value(value.requested_attributes)?,
+                disclosed_attributes: value
+                    .disclosed_attributes
+                    .map(serde_json::from_value)
+                    .transpose()?
+                    .unwrap_or_default(),
+                started_at: value.started_date_time.into(),
+                completed_at: value
+                    .completed_date_time
+                    .expect("only completed entries are queried")
+                    .into(),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indexmap::IndexMap;
+
+    use nl_wallet_mdoc::{basic_sa_ext::Entry, ItemsRequest};
+
+    use super::*;
+
+    fn audit_log() -> AuditLog {
+        AuditLog::new(AuditLogSettings { retention_days: 30 }, StoreConnection::Memory)
+    }
+
+    fn items_requests() -> ItemsRequests {
+        vec![ItemsRequest {
+            doc_type: "com.example.pid".to_string(),
+            request_info: None,
+            name_spaces: IndexMap::from([(
+                "com.example.pid".to_string(),
+                IndexMap::from([("given_name".to_string(), false), ("bsn".to_string(), false)]),
+            )]),
+        }]
+        .into()
+    }
+
+    #[tokio::test]
+    async fn test_audit_log() {
+        let audit_log = audit_log();
+        let done: SessionToken = "done".to_owned().into();
+        let cancelled: SessionToken = "cancelled".to_owned().into();
+        let pending: SessionToken = "pending".to_owned().into();
+
+        for session_id in [&done, &cancelled, &pending] {
+            audit_log
+                .record_started(session_id, "usecase", &items_requests())
+                .await
+                .unwrap();
+        }
+
+        let disclosed_attributes = IndexMap::from([(
+            "com.example.pid".to_string(),
+            IndexMap::from([(
+                "com.example.pid".to_string(),
+                vec![Entry {
+                    name: "given_name".to_string(),
+                    value: ciborium::Value::Text("John".to_string()),
+                }],
+            )]),
+        )]);
+        audit_log
+            .record_completed(
+                &done,
+                &SessionResult::Done {
+                    disclosed_attributes,
+                    transcript_hash: None,
+                },
+            )
+            .await
+            .unwrap();
+        audit_log
+            .record_completed(&cancelled, &SessionResult::Cancelled)
+            .await
+            .unwrap();
+
+        // Completing an entry again does not change it
+        audit_log
+            .record_completed(&done, &SessionResult::Cancelled)
+            .await
+            .unwrap();
+
+        // Only the completed sessions are returned, in order of completion
+        let entries = audit_log.query(AuditLogQuery::default()).await.unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].session_id, done);
+        assert_eq!(entries[0].status, StatusResponse::Done);
+        assert_eq!(
+            entries[0].requested_attributes,
+            vec![
+                "com.example.pid/com.example.pid/given_name",
+                "com.example.pid/com.example.pid/bsn"
+            ]
+        );
+        assert_eq!(
+            entries[0].disclosed_attributes,
+            vec!["com.example.pid/com.example.pid/given_name"]
+        );
+        assert_eq!(entries[1].session_id, cancelled);
+        assert_eq!(entries[1].status, StatusResponse::Cancelled);
+        assert!(entries[1].disclosed_attributes.is_empty());
+
+        // The entries can be filtered by usecase and time of completion
+        let query = AuditLogQuery {
+            usecase: Some("other_usecase".to_string()),
+            ..Default::default()
+        };
+        assert!(audit_log.query(query).await.unwrap().is_empty());
+
+        let query = AuditLogQuery {
+            since: Some(Utc::now()),
+            ..Default::default()
+        };
+        assert!(audit_log.query(query).await.unwrap().is_empty());
+
+        let query = AuditLogQuery {
+            limit: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(audit_log.query(query).await.unwrap().len(), 1);
+    }
+}
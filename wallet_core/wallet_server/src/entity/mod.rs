@@ -2,4 +2,6 @@
 
 pub mod prelude;
 
+pub mod disclosure_audit_log;
+pub mod session_evidence;
 pub mod session_state;
@@ -1,3 +1,6 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.3
 
-pub use super::session_state::Entity as SessionState;
+pub use super::{
+    disclosure_audit_log::Entity as DisclosureAuditLog, session_evidence::Entity as SessionEvidence,
+    session_state::Entity as SessionState,
+};
@@ -0,0 +1,378 @@
+use std::{collections::HashMap, sync::Arc};
+
+use aes_gcm::{
+    aead::{Aead, KeyInit, Payload},
+    Aes256Gcm, Nonce,
+};
+use axum::http::HeaderMap;
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use subtle::ConstantTimeEq;
+use tracing::warn;
+
+use nl_wallet_mdoc::{server_state::SessionToken, verifier::SessionEvidence};
+use wallet_common::utils::{random_bytes, sha256};
+
+#[cfg(feature = "postgres")]
+use crate::forensics::postgres::PostgresEvidenceStore;
+use crate::{
+    settings::Forensics,
+    store::{CleanupTask, StoreConnection},
+};
+
+/// Header that should be present at least twice when retrieving evidence, each time containing the credentials
+/// of a different investigator, formatted as `<investigator>:<token>`.
+pub const FORENSICS_AUTHORIZATION_HEADER: &str = "x-forensics-authorization";
+
+#[derive(Debug, thiserror::Error)]
+pub enum ForensicsError {
+    #[error("sealing key should be 32 bytes, found {0}")]
+    InvalidSealingKey(usize),
+    #[error("could not seal evidence")]
+    Seal,
+    #[error("could not unseal evidence")]
+    Unseal,
+    #[error("could not (de)serialize evidence: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("evidence store error: {0}")]
+    Store(#[source] Box<dyn std::error::Error + Send + Sync + 'static>),
+}
+
+/// Seals [`SessionEvidence`] of completed sessions using AES-GCM before persisting it, keeps it for a limited
+/// amount of time and only allows it to be unsealed with the authorization of two distinct investigators.
+pub struct ForensicStore {
+    cipher: Aes256Gcm,
+    retention: chrono::Duration,
+    investigators: HashMap<String, Vec<u8>>,
+    evidence: Arc<EvidenceStore>,
+    _cleanup_task: CleanupTask,
+}
+
+impl ForensicStore {
+    pub fn try_new(settings: Forensics, connection: StoreConnection) -> Result<Self, ForensicsError> {
+        if settings.sealing_key.0.len() != 32 {
+            return Err(ForensicsError::InvalidSealingKey(settings.sealing_key.0.len()));
+        }
+        let cipher = Aes256Gcm::new_from_slice(&settings.sealing_key.0).expect("key length was checked above");
+
+        let evidence = Arc::new(EvidenceStore::new(connection));
+        // The expired evidence is removed every so often.
+        let cleanup_task = CleanupTask::start(Arc::clone(&evidence), |evidence| async move {
+            if let Err(error) = evidence.cleanup().await {
+                warn!("could not clean up expired evidence: {}", error);
+            }
+        });
+
+        Ok(Self {
+            cipher,
+            retention: chrono::Duration::days(settings.retention_days as i64),
+            investigators: settings
+                .investigators
+                .into_iter()
+                .map(|(name, token_digest)| (name, token_digest.0))
+                .collect(),
+            evidence,
+            _cleanup_task: cleanup_task,
+        })
+    }
+
+    /// Seal and persist the evidence of a session. Only the first evidence stored for a session is kept, so that it
+    /// cannot be replaced by requests that are sent to the session after it has ended.
+    pub async fn store(&self, token: &SessionToken, evidence: &SessionEvidence) -> Result<(), ForensicsError> {
+        let sealed = self.seal(token, &serde_json::to_vec(evidence)?)?;
+        self.evidence.write(token, sealed, Utc::now() + self.retention).await
+    }
+
+    pub async fn retrieve(&self, token: &SessionToken) -> Result<Option<SessionEvidence>, ForensicsError> {
+        self.evidence
+            .get(token)
+            .await?
+            .map(|sealed| Ok(serde_json::from_slice(&self.unseal(token, &sealed)?)?))
+            .transpose()
+    }
+
+    /// Returns `true` if the headers contain valid credentials of at least two distinct investigators.
+    pub fn is_authorized(&self, headers: &HeaderMap) -> bool {
+        let mut authorized_investigators = headers
+            .get_all(FORENSICS_AUTHORIZATION_HEADER)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .filter_map(|value| value.split_once(':'))
+            .filter(|(investigator, token)| {
+                // Compare in constant time, so that the response time does not reveal how much of the digest matches.
+                self.investigators
+                    .get(*investigator)
+                    .is_some_and(|token_digest| bool::from(token_digest.ct_eq(&sha256(token.as_bytes()))))
+            })
+            .map(|(investigator, _)| investigator)
+            .collect::<Vec<_>>();
+        authorized_investigators.sort_unstable();
+        authorized_investigators.dedup();
+
+        if authorized_investigators.len() < 2 {
+            warn!("evidence requested without authorization of two distinct investigators");
+            return false;
+        }
+
+        true
+    }
+
+    /// Seal the evidence with the session token as associated data, so that the sealed evidence cannot be moved to
+    /// another session without failing to unseal.
+    fn seal(&self, token: &SessionToken, plaintext: &[u8]) -> Result<Vec<u8>, ForensicsError> {
+        let nonce_bytes = random_bytes(12);
+        let aad = token.to_string();
+        let payload = Payload {
+            msg: plaintext,
+            aad: aad.as_bytes(),
+        };
+        let ciphertext = self
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce_bytes), payload)
+            .map_err(|_| ForensicsError::Seal)?;
+
+        // concatenate nonce with encrypted payload
+        Ok(nonce_bytes.into_iter().chain(ciphertext).collect())
+    }
+
+    fn unseal(&self, token: &SessionToken, sealed: &[u8]) -> Result<Vec<u8>, ForensicsError> {
+        if sealed.len() < 12 {
+            return Err(ForensicsError::Unseal);
+        }
+        let (nonce_bytes, ciphertext) = sealed.split_at(12);
+        let aad = token.to_string();
+        let payload = Payload {
+            msg: ciphertext,
+            aad: aad.as_bytes(),
+        };
+
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce_bytes), payload)
+            .map_err(|_| ForensicsError::Unseal)
+    }
+}
+
+/// Contains the sealed evidence, in the same kind of storage as the sessions.
+enum EvidenceStore {
+    #[cfg(feature = "postgres")]
+    Postgres(PostgresEvidenceStore),
+    Memory(DashMap<SessionToken, (Vec<u8>, DateTime<Utc>)>),
+}
+
+impl EvidenceStore {
+    fn new(connection: StoreConnection) -> Self {
+        match connection {
+            #[cfg(feature = "postgres")]
+            StoreConnection::Postgres(connection) => EvidenceStore::Postgres(PostgresEvidenceStore::new(connection)),
+            StoreConnection::Memory => EvidenceStore::Memory(DashMap::new()),
+        }
+    }
+
+    async fn get(&self, token: &SessionToken) -> Result<Option<Vec<u8>>, ForensicsError> {
+        match self {
+            #[cfg(feature = "postgres")]
+            EvidenceStore::Postgres(postgres) => postgres.get(token).await,
+            EvidenceStore::Memory(memory) => Ok(memory
+                .get(token)
+                .filter(|entry| entry.1 > Utc::now())
+                .map(|entry| entry.0.clone())),
+        }
+    }
+
+    async fn write(
+        &self,
+        token: &SessionToken,
+        sealed: Vec<u8>,
+        expiration: DateTime<Utc>,
+    ) -> Result<(), ForensicsError> {
+        match self {
+            #[cfg(feature = "postgres")]
+            EvidenceStore::Postgres(postgres) => postgres.write(token, sealed, expiration).await,
+            EvidenceStore::Memory(memory) => {
+                memory.entry(token.clone()).or_insert((sealed, expiration));
+                Ok(())
+            }
+        }
+    }
+
+    async fn cleanup(&self) -> Result<(), ForensicsError> {
+        match self {
+            #[cfg(feature = "postgres")]
+            EvidenceStore::Postgres(postgres) => postgres.cleanup().await,
+            EvidenceStore::Memory(memory) => {
+                let now = Utc::now();
+                memory.retain(|_, (_, expiration)| *expiration > now);
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(feature = "postgres")]
+mod postgres {
+    use chrono::{DateTime, Utc};
+    use sea_orm::{sea_query::OnConflict, ActiveValue, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+
+    use nl_wallet_mdoc::server_state::SessionToken;
+
+    use super::ForensicsError;
+    use crate::entity::session_evidence;
+
+    pub struct PostgresEvidenceStore {
+        connection: DatabaseConnection,
+    }
+
+    impl PostgresEvidenceStore {
+        pub fn new(connection: DatabaseConnection) -> Self {
+            Self { connection }
+        }
+
+        pub async fn get(&self, token: &SessionToken) -> Result<Option<Vec<u8>>, ForensicsError> {
+            let evidence = session_evidence::Entity::find()
+                .filter(session_evidence::Column::Token.eq(token.to_string()))
+                .filter(session_evidence::Column::ExpirationDateTime.gt(Utc::now()))
+                .one(&self.connection)
+                .await
+                .map_err(|e| ForensicsError::Store(e.into()))?;
+
+            Ok(evidence.map(|evidence| evidence.sealed_data))
+        }
+
+        pub async fn write(
+            &self,
+            token: &SessionToken,
+            sealed: Vec<u8>,
+            expiration: DateTime<Utc>,
+        ) -> Result<(), ForensicsError> {
+            session_evidence::Entity::insert(session_evidence::ActiveModel {
+                token: ActiveValue::set(token.to_string()),
+                sealed_data: ActiveValue::set(sealed),
+                expiration_date_time: ActiveValue::set(expiration.into()),
+            })
+            .on_conflict(
+                OnConflict::column(session_evidence::Column::Token)
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .exec_without_returning(&self.connection)
+            .await
+            .map_err(|e| ForensicsError::Store(e.into()))?;
+
+            Ok(())
+        }
+
+        pub async fn cleanup(&self) -> Result<(), ForensicsError> {
+            session_evidence::Entity::delete_many()
+                .filter(session_evidence::Column::ExpirationDateTime.lt(Utc::now()))
+                .exec(&self.connection)
+                .await
+                .map_err(|e| ForensicsError::Store(e.into()))?;
+
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use wallet_common::account::serialization::Base64Bytes;
+
+    use super::*;
+
+    fn forensic_store() -> ForensicStore {
+        let settings = Forensics {
+            sealing_key: random_bytes(32).into(),
+            retention_days: 7,
+            investigators: HashMap::from([
+                ("alice".to_string(), Base64Bytes::from(sha256(b"alice_token"))),
+                ("bob".to_string(), Base64Bytes::from(sha256(b"bob_token"))),
+            ]),
+        };
+
+        ForensicStore::try_new(settings, StoreConnection::Memory).unwrap()
+    }
+
+    fn headers(credentials: &[&'static str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for credential in credentials {
+            headers.append(FORENSICS_AUTHORIZATION_HEADER, HeaderValue::from_static(credential));
+        }
+        headers
+    }
+
+    #[tokio::test]
+    async fn test_seal_unseal() {
+        let store = forensic_store();
+        let token = SessionToken::new();
+
+        let sealed = store.seal(&token, b"evidence").unwrap();
+        assert_ne!(&sealed[12..], b"evidence");
+        assert_eq!(store.unseal(&token, &sealed).unwrap(), b"evidence");
+
+        let mut tampered = sealed.clone();
+        *tampered.last_mut().unwrap() ^= 0x01;
+        assert!(matches!(store.unseal(&token, &tampered), Err(ForensicsError::Unseal)));
+    }
+
+    #[tokio::test]
+    async fn test_unseal_other_session() {
+        let store = forensic_store();
+        let token = SessionToken::new();
+
+        // Sealed evidence that is copied to another session should not unseal.
+        let sealed = store.seal(&token, b"evidence").unwrap();
+        assert!(matches!(
+            store.unseal(&SessionToken::new(), &sealed),
+            Err(ForensicsError::Unseal)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_is_authorized() {
+        let store = forensic_store();
+
+        assert!(store.is_authorized(&headers(&["alice:alice_token", "bob:bob_token"])));
+
+        assert!(!store.is_authorized(&headers(&[])));
+        assert!(!store.is_authorized(&headers(&["alice:alice_token"])));
+        assert!(!store.is_authorized(&headers(&["alice:alice_token", "alice:alice_token"])));
+        assert!(!store.is_authorized(&headers(&["alice:alice_token", "bob:alice_token"])));
+        assert!(!store.is_authorized(&headers(&["alice:alice_token", "mallory:bob_token"])));
+    }
+
+    #[tokio::test]
+    async fn test_write_keeps_first_evidence() {
+        let store = forensic_store();
+        let token = SessionToken::new();
+        let expiration = Utc::now() + chrono::Duration::days(1);
+
+        store
+            .evidence
+            .write(&token, b"first".to_vec(), expiration)
+            .await
+            .unwrap();
+        store
+            .evidence
+            .write(&token, b"second".to_vec(), expiration)
+            .await
+            .unwrap();
+
+        assert_eq!(store.evidence.get(&token).await.unwrap().unwrap(), b"first");
+    }
+
+    #[tokio::test]
+    async fn test_invalid_sealing_key() {
+        let settings = Forensics {
+            sealing_key: random_bytes(16).into(),
+            retention_days: 7,
+            investigators: HashMap::new(),
+        };
+
+        assert!(matches!(
+            ForensicStore::try_new(settings, StoreConnection::Memory),
+            Err(ForensicsError::InvalidSealingKey(16))
+        ));
+    }
+}
@@ -0,0 +1,458 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tracing::warn;
+
+use nl_wallet_mdoc::{
+    server_state::{SessionToken, SESSION_EXPIRY_MINUTES},
+    utils::serialization::cbor_serialize,
+    verifier::DisclosedAttributes,
+};
+use wallet_common::utils::hkdf;
+
+use crate::{
+    settings::{FrequencyCap, FrequencyCapAction, FrequencyCaps},
+    store::CleanupTask,
+};
+
+/// Header that is set on the disclosed attributes response of a session that exceeded a flagging frequency cap.
+pub const FREQUENCY_CAP_EXCEEDED_HEADER: &str = "x-frequency-cap-exceeded";
+
+const PSEUDONYM_LENGTH: usize = 32;
+
+/// The outcome of checking the frequency caps of a session, ordered by severity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FrequencyCapOutcome {
+    Allow,
+    Flag,
+    Reject,
+}
+
+impl From<FrequencyCapAction> for FrequencyCapOutcome {
+    fn from(value: FrequencyCapAction) -> Self {
+        match value {
+            FrequencyCapAction::Flag => Self::Flag,
+            FrequencyCapAction::Reject => Self::Reject,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct FrequencyCapMetrics {
+    pub sessions_checked: u64,
+    pub sessions_flagged: u64,
+    pub sessions_rejected: u64,
+}
+
+struct CheckedSession {
+    outcome: FrequencyCapOutcome,
+    last_checked: DateTime<Utc>,
+}
+
+#[derive(PartialEq, Eq, Hash)]
+struct RequestKey {
+    usecase: String,
+    cap: usize,
+    pseudonym: Vec<u8>,
+}
+
+/// Limits how often a usecase may request certain attributes of the same subject within a time window. Subjects are
+/// identified by a pseudonym, derived from the value of a disclosed attribute, so that no attributes are retained.
+///
+/// As the subject is only known after the disclosure, the caps are enforced when the relying party retrieves the
+/// disclosed attributes. Note that the administration is kept in memory, so it is not shared between instances.
+pub struct FrequencyCapper {
+    pseudonym_key: Vec<u8>,
+    caps: HashMap<String, Vec<FrequencyCap>>,
+    state: Arc<FrequencyCapState>,
+    _cleanup_task: CleanupTask,
+}
+
+struct FrequencyCapState {
+    administration: Mutex<FrequencyCapAdministration>,
+    max_window: chrono::Duration,
+}
+
+/// The administration is kept behind a single lock, so that checking and counting a session is atomic.
+#[derive(Default)]
+struct FrequencyCapAdministration {
+    sessions: HashMap<SessionToken, CheckedSession>,
+    requests: HashMap<RequestKey, VecDeque<DateTime<Utc>>>,
+    metrics: HashMap<String, FrequencyCapMetrics>,
+}
+
+impl FrequencyCapper {
+    pub fn new(settings: FrequencyCaps) -> Self {
+        let max_window = settings
+            .usecases
+            .values()
+            .flatten()
+            .map(|cap| cap.window_seconds)
+            .max()
+            .unwrap_or_default();
+
+        let state = Arc::new(FrequencyCapState {
+            administration: Default::default(),
+            max_window: chrono::Duration::seconds(max_window as i64),
+        });
+        let cleanup_task = CleanupTask::start(Arc::clone(&state), |state| async move { state.cleanup() });
+
+        Self {
+            pseudonym_key: settings.pseudonym_key.0,
+            caps: settings.usecases,
+            state,
+            _cleanup_task: cleanup_task,
+        }
+    }
+
+    /// Returns the indices of the caps of the usecase that apply to the disclosed attributes.
+    fn applicable_caps(&self, usecase: &str, disclosed_attributes: &DisclosedAttributes) -> Vec<usize> {
+        let Some(caps) = self.caps.get(usecase) else {
+            return vec![];
+        };
+
+        caps.iter()
+            .enumerate()
+            .filter(|(_, cap)| {
+                disclosed_attributes
+                    .get(&cap.doc_type)
+                    .and_then(|name_spaces| name_spaces.get(&cap.namespace))
+                    .is_some_and(|entries| entries.iter().any(|entry| cap.attributes.contains(&entry.name)))
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Checks the caps of the usecase of a session against its disclosed attributes. Each session is only counted
+    /// once, subsequent checks return the same outcome. Rejected sessions do not count towards the cap, as the relying
+    /// party does not receive the attributes. A cap of which the subject attribute was not disclosed is considered
+    /// exceeded.
+    pub fn check(
+        &self,
+        session_id: &SessionToken,
+        usecase: &str,
+        disclosed_attributes: &DisclosedAttributes,
+    ) -> FrequencyCapOutcome {
+        let mut outcome = FrequencyCapOutcome::Allow;
+        let mut request_keys = Vec::new();
+        for index in self.applicable_caps(usecase, disclosed_attributes) {
+            let cap = &self.caps[usecase][index];
+            match self.pseudonym(usecase, cap, disclosed_attributes) {
+                Some(pseudonym) => request_keys.push(RequestKey {
+                    usecase: usecase.to_string(),
+                    cap: index,
+                    pseudonym,
+                }),
+                // Without the subject the request cannot be counted, so withholding the subject attribute should not
+                // be a way to circumvent the cap.
+                None => {
+                    warn!(
+                        "subject attribute {} not disclosed, frequency cap of usecase {} considered exceeded",
+                        cap.subject_attribute, usecase
+                    );
+                    outcome = outcome.max(cap.action.into());
+                }
+            }
+        }
+
+        if request_keys.is_empty() && outcome == FrequencyCapOutcome::Allow {
+            return FrequencyCapOutcome::Allow;
+        }
+
+        let now = Utc::now();
+        let mut administration = self.state.administration.lock().unwrap();
+
+        if let Some(session) = administration.sessions.get_mut(session_id) {
+            session.last_checked = now;
+            return session.outcome;
+        }
+
+        // Determine the outcome over all of the caps, before counting the session towards any of them.
+        for request_key in &request_keys {
+            let cap = &self.caps[usecase][request_key.cap];
+            let window_start = now - chrono::Duration::seconds(cap.window_seconds as i64);
+
+            let exceeded = administration.requests.get_mut(request_key).is_some_and(|timestamps| {
+                timestamps.retain(|timestamp| *timestamp > window_start);
+                timestamps.len() >= cap.max_requests as usize
+            });
+
+            if exceeded {
+                warn!(
+                    "frequency cap of usecase {} exceeded for attributes {:?}",
+                    usecase, cap.attributes
+                );
+                outcome = outcome.max(cap.action.into());
+            }
+        }
+
+        if outcome != FrequencyCapOutcome::Reject {
+            for request_key in request_keys {
+                administration.requests.entry(request_key).or_default().push_back(now);
+            }
+        }
+
+        let metrics = administration.metrics.entry(usecase.to_string()).or_default();
+        metrics.sessions_checked += 1;
+        match outcome {
+            FrequencyCapOutcome::Allow => {}
+            FrequencyCapOutcome::Flag => metrics.sessions_flagged += 1,
+            FrequencyCapOutcome::Reject => metrics.sessions_rejected += 1,
+        }
+
+        administration.sessions.insert(
+            session_id.clone(),
+            CheckedSession {
+                outcome,
+                last_checked: now,
+            },
+        );
+
+        outcome
+    }
+
+    /// Returns the metrics per usecase.
+    pub fn metrics(&self) -> HashMap<String, FrequencyCapMetrics> {
+        self.state.administration.lock().unwrap().metrics.clone()
+    }
+
+    fn pseudonym(
+        &self,
+        usecase: &str,
+        cap: &FrequencyCap,
+        disclosed_attributes: &DisclosedAttributes,
+    ) -> Option<Vec<u8>> {
+        let value = disclosed_attributes
+            .get(&cap.doc_type)?
+            .get(&cap.namespace)?
+            .iter()
+            .find(|entry| entry.name == cap.subject_attribute)
+            .map(|entry| &entry.value)?;
+
+        let value = cbor_serialize(value).ok()?;
+        let pseudonym = hkdf(&value, &self.pseudonym_key, usecase, PSEUDONYM_LENGTH).expect("length should be valid");
+
+        Some(pseudonym)
+    }
+}
+
+impl FrequencyCapState {
+    /// Remove the expired sessions and requests.
+    fn cleanup(&self) {
+        let now = Utc::now();
+        let mut administration = self.administration.lock().unwrap();
+
+        // The outcome of a session is retained for as long as the relying party keeps retrieving the attributes.
+        let session_cutoff = now - chrono::Duration::minutes(SESSION_EXPIRY_MINUTES as i64);
+        administration
+            .sessions
+            .retain(|_, session| session.last_checked > session_cutoff);
+
+        let request_cutoff = now - self.max_window;
+        administration.requests.retain(|_, timestamps| {
+            timestamps.retain(|timestamp| *timestamp > request_cutoff);
+            !timestamps.is_empty()
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ciborium::Value;
+    use indexmap::IndexMap;
+
+    use nl_wallet_mdoc::{basic_sa_ext::Entry, verifier::DocumentDisclosedAttributes};
+    use wallet_common::account::serialization::Base64Bytes;
+
+    use super::*;
+
+    const USECASE: &str = "usecase";
+    const DOC_TYPE: &str = "com.example.pid";
+
+    fn frequency_capper(action: FrequencyCapAction) -> FrequencyCapper {
+        FrequencyCapper::new(FrequencyCaps {
+            pseudonym_key: Base64Bytes(random_key()),
+            usecases: HashMap::from([(
+                USECASE.to_string(),
+                vec![FrequencyCap {
+                    doc_type: DOC_TYPE.to_string(),
+                    namespace: DOC_TYPE.to_string(),
+                    attributes: vec!["bsn".to_string()],
+                    subject_attribute: "bsn".to_string(),
+                    max_requests: 2,
+                    window_seconds: 60,
+                    action,
+                }],
+            )]),
+        })
+    }
+
+    fn random_key() -> Vec<u8> {
+        wallet_common::utils::random_bytes(32)
+    }
+
+    fn disclosed_attribute(name: &str, value: &str) -> DisclosedAttributes {
+        let document: DocumentDisclosedAttributes = IndexMap::from([(
+            DOC_TYPE.to_string(),
+            vec![Entry {
+                name: name.to_string(),
+                value: Value::Text(value.to_string()),
+            }],
+        )]);
+
+        IndexMap::from([(DOC_TYPE.to_string(), document)])
+    }
+
+    fn disclosed_attributes(bsn: &str) -> DisclosedAttributes {
+        disclosed_attribute("bsn", bsn)
+    }
+
+    #[tokio::test]
+    async fn test_applicable_caps() {
+        let capper = frequency_capper(FrequencyCapAction::Reject);
+
+        assert_eq!(
+            capper.applicable_caps(USECASE, &disclosed_attributes("999991772")),
+            vec![0]
+        );
+        assert!(capper
+            .applicable_caps(USECASE, &disclosed_attribute("given_name", "Willeke"))
+            .is_empty());
+        assert!(capper
+            .applicable_caps("other_usecase", &disclosed_attributes("999991772"))
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_frequency_cap_reject() {
+        let capper = frequency_capper(FrequencyCapAction::Reject);
+
+        for _ in 0..2 {
+            assert_eq!(
+                capper.check(&SessionToken::new(), USECASE, &disclosed_attributes("999991772")),
+                FrequencyCapOutcome::Allow
+            );
+        }
+
+        // The third request for the same subject exceeds the cap, also when checked again
+        let session_id = SessionToken::new();
+        assert_eq!(
+            capper.check(&session_id, USECASE, &disclosed_attributes("999991772")),
+            FrequencyCapOutcome::Reject
+        );
+        assert_eq!(
+            capper.check(&session_id, USECASE, &disclosed_attributes("999991772")),
+            FrequencyCapOutcome::Reject
+        );
+
+        // Another subject should not be affected
+        assert_eq!(
+            capper.check(&SessionToken::new(), USECASE, &disclosed_attributes("999991773")),
+            FrequencyCapOutcome::Allow
+        );
+
+        // Other usecases are not capped
+        assert_eq!(
+            capper.check(
+                &SessionToken::new(),
+                "other_usecase",
+                &disclosed_attributes("999991772")
+            ),
+            FrequencyCapOutcome::Allow
+        );
+
+        let metrics = capper.metrics();
+        assert_eq!(metrics[USECASE].sessions_checked, 4);
+        assert_eq!(metrics[USECASE].sessions_flagged, 0);
+        assert_eq!(metrics[USECASE].sessions_rejected, 1);
+    }
+
+    #[tokio::test]
+    async fn test_frequency_cap_after_cleanup() {
+        let capper = frequency_capper(FrequencyCapAction::Reject);
+
+        let session_ids = [SessionToken::new(), SessionToken::new()];
+        for session_id in &session_ids {
+            assert_eq!(
+                capper.check(session_id, USECASE, &disclosed_attributes("999991772")),
+                FrequencyCapOutcome::Allow
+            );
+        }
+
+        // Have the outcome of the sessions expire, which should not allow them to circumvent the cap.
+        capper
+            .state
+            .administration
+            .lock()
+            .unwrap()
+            .sessions
+            .values_mut()
+            .for_each(|session| session.last_checked -= chrono::Duration::minutes(SESSION_EXPIRY_MINUTES as i64));
+        capper.state.cleanup();
+
+        assert!(capper.state.administration.lock().unwrap().sessions.is_empty());
+        assert_eq!(
+            capper.check(&session_ids[0], USECASE, &disclosed_attributes("999991772")),
+            FrequencyCapOutcome::Reject
+        );
+    }
+
+    #[tokio::test]
+    async fn test_frequency_cap_flag() {
+        let capper = frequency_capper(FrequencyCapAction::Flag);
+
+        let outcomes = (0..4)
+            .map(|_| capper.check(&SessionToken::new(), USECASE, &disclosed_attributes("999991772")))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            outcomes,
+            vec![
+                FrequencyCapOutcome::Allow,
+                FrequencyCapOutcome::Allow,
+                FrequencyCapOutcome::Flag,
+                FrequencyCapOutcome::Flag
+            ]
+        );
+        assert_eq!(capper.metrics()[USECASE].sessions_flagged, 2);
+    }
+
+    #[tokio::test]
+    async fn test_frequency_cap_subject_not_disclosed() {
+        for (action, expected_outcome) in [
+            (FrequencyCapAction::Flag, FrequencyCapOutcome::Flag),
+            (FrequencyCapAction::Reject, FrequencyCapOutcome::Reject),
+        ] {
+            let capper = FrequencyCapper::new(FrequencyCaps {
+                pseudonym_key: Base64Bytes(random_key()),
+                usecases: HashMap::from([(
+                    USECASE.to_string(),
+                    vec![FrequencyCap {
+                        doc_type: DOC_TYPE.to_string(),
+                        namespace: DOC_TYPE.to_string(),
+                        attributes: vec!["given_name".to_string()],
+                        subject_attribute: "bsn".to_string(),
+                        max_requests: 2,
+                        window_seconds: 60,
+                        action,
+                    }],
+                )]),
+            });
+
+            // Withholding the subject attribute should not circumvent the cap, even for the first request.
+            assert_eq!(
+                capper.check(
+                    &SessionToken::new(),
+                    USECASE,
+                    &disclosed_attribute("given_name", "Willeke")
+                ),
+                expected_outcome
+            );
+            assert_eq!(capper.metrics()[USECASE].sessions_checked, 1);
+        }
+    }
+}
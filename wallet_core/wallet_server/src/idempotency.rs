@@ -0,0 +1,276 @@
+use std::{fmt::Debug, sync::Arc, time::Duration};
+
+use dashmap::{mapref::entry::Entry, DashMap};
+use tokio::time::Instant;
+
+use crate::{settings::IdempotencyKeys, store::CleanupTask, verifier::StartDisclosureResponse};
+
+/// Header with which the requester can make starting a session idempotent.
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// The maximum length of an idempotency key.
+const MAX_KEY_LENGTH: usize = 255;
+
+#[derive(Debug, thiserror::Error)]
+pub enum IdempotencyError {
+    #[error("idempotency key should consist of 1 to 255 visible ASCII characters")]
+    InvalidKey,
+    #[error("idempotency key was already used for a different request")]
+    KeyReused,
+    #[error("request with the same idempotency key is still being processed")]
+    InProgress,
+}
+
+enum IdempotentResponse {
+    InProgress,
+    Completed(Box<StartDisclosureResponse>),
+}
+
+/// The outcome of registering a request with an idempotency key.
+#[derive(Debug)]
+pub enum Idempotent<'a> {
+    /// The same request was processed before, which resulted in this response.
+    Replay(StartDisclosureResponse),
+    /// The request should be processed, after which its response should be stored using the guard.
+    New(IdempotencyGuard<'a>),
+}
+
+/// Guards a request with an idempotency key that is being processed. If the guard is dropped without storing the
+/// response, e.g. because processing the request failed or the requester disconnected, the key is forgotten, so that
+/// the request may be retried with the same key.
+pub struct IdempotencyGuard<'a> {
+    cache: &'a IdempotencyCache,
+    key: IdempotencyKey,
+    completed: bool,
+}
+
+impl Debug for IdempotencyGuard<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IdempotencyGuard")
+            .field("key", &self.key.key)
+            .field("completed", &self.completed)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Idempotency keys are chosen by the requester, so they are scoped to the requester that provided them.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct IdempotencyKey {
+    requester: Vec<u8>,
+    key: String,
+}
+
+impl IdempotencyGuard<'_> {
+    /// Stores the response to the request, which is returned for any retry of the request.
+    pub fn complete(mut self, response: StartDisclosureResponse) {
+        if let Some(mut entry) = self.cache.state.entries.get_mut(&self.key) {
+            entry.response = IdempotentResponse::Completed(Box::new(response));
+        }
+        self.completed = true;
+    }
+}
+
+impl Drop for IdempotencyGuard<'_> {
+    fn drop(&mut self) {
+        if !self.completed {
+            self.cache.state.entries.remove(&self.key);
+        }
+    }
+}
+
+struct IdempotencyEntry {
+    request_digest: Vec<u8>,
+    response: IdempotentResponse,
+    created: Instant,
+}
+
+/// Remembers the response to starting a session for every `Idempotency-Key` provided by the requester, so that a
+/// requester that retries starting a session with the same key receives the existing session instead of a new one.
+/// The key may only be reused for the exact same request and is forgotten once its TTL has passed.
+///
+/// Note that, like the frequency caps, the keys are kept in memory, so a retry only results in the existing session
+/// when it is handled by the same instance.
+pub struct IdempotencyCache {
+    ttl: Duration,
+    state: Arc<IdempotencyState>,
+    _cleanup_task: CleanupTask,
+}
+
+struct IdempotencyState {
+    entries: DashMap<IdempotencyKey, IdempotencyEntry>,
+    ttl: Duration,
+}
+
+impl IdempotencyCache {
+    pub fn new(settings: IdempotencyKeys) -> Self {
+        let ttl = Duration::from_secs(settings.ttl_in_seconds);
+        let state = Arc::new(IdempotencyState {
+            entries: DashMap::new(),
+            ttl,
+        });
+        let cleanup_task = CleanupTask::start(Arc::clone(&state), |state| async move { state.cleanup() });
+
+        Self {
+            ttl,
+            state,
+            _cleanup_task: cleanup_task,
+        }
+    }
+
+    /// Registers the start of processing a request with the specified `key` and digest of the request, returning the
+    /// earlier response if the same request was already processed. The `requester` identifies the requester that
+    /// provided the key, so that requesters cannot replay or block each other's requests.
+    pub fn begin(
+        &self,
+        requester: Vec<u8>,
+        key: &str,
+        request_digest: Vec<u8>,
+    ) -> Result<Idempotent, IdempotencyError> {
+        self.begin_at(requester, key, request_digest, Instant::now())
+    }
+
+    fn begin_at(
+        &self,
+        requester: Vec<u8>,
+        key: &str,
+        request_digest: Vec<u8>,
+        now: Instant,
+    ) -> Result<Idempotent, IdempotencyError> {
+        if key.is_empty() || key.len() > MAX_KEY_LENGTH || !key.bytes().all(|b| b.is_ascii_graphic()) {
+            return Err(IdempotencyError::InvalidKey);
+        }
+
+        let key = IdempotencyKey {
+            requester,
+            key: key.to_string(),
+        };
+        let new_entry = IdempotencyEntry {
+            request_digest,
+            response: IdempotentResponse::InProgress,
+            created: now,
+        };
+
+        // the lock on the entry is released at the end of this block, before a guard could be dropped
+        {
+            match self.state.entries.entry(key.clone()) {
+                Entry::Occupied(mut entry) if now.saturating_duration_since(entry.get().created) >= self.ttl => {
+                    entry.insert(new_entry);
+                }
+                Entry::Occupied(entry) => {
+                    let entry = entry.get();
+                    if entry.request_digest != new_entry.request_digest {
+                        return Err(IdempotencyError::KeyReused);
+                    }
+
+                    return match &entry.response {
+                        IdempotentResponse::InProgress => Err(IdempotencyError::InProgress),
+                        IdempotentResponse::Completed(response) => Ok(Idempotent::Replay(response.as_ref().clone())),
+                    };
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(new_entry);
+                }
+            }
+        }
+
+        Ok(Idempotent::New(IdempotencyGuard {
+            cache: self,
+            key,
+            completed: false,
+        }))
+    }
+}
+
+impl IdempotencyState {
+    /// Remove the keys of which the TTL has passed.
+    fn cleanup(&self) {
+        let now = Instant::now();
+        self.entries
+            .retain(|_, entry| now.saturating_duration_since(entry.created) < self.ttl);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+
+    use super::*;
+
+    fn response() -> StartDisclosureResponse {
+        StartDisclosureResponse {
+            session_url: "https://example.com/deadbeef/status".parse().unwrap(),
+            engagement_url: "https://example.com/engagement".parse().unwrap(),
+            disclosed_attributes_url: "https://example.com/sessions/deadbeef/disclosed_attributes"
+                .parse()
+                .unwrap(),
+        }
+    }
+
+    fn requester() -> Vec<u8> {
+        b"requester".to_vec()
+    }
+
+    #[tokio::test]
+    async fn test_idempotency_cache() {
+        let cache = IdempotencyCache::new(IdempotencyKeys { ttl_in_seconds: 60 });
+        let start = Instant::now();
+
+        let Ok(Idempotent::New(guard)) = cache.begin_at(requester(), "key", vec![1], start) else {
+            panic!("first request should be processed");
+        };
+
+        // A retry while the request is still being processed is rejected.
+        assert_matches!(
+            cache.begin_at(requester(), "key", vec![1], start),
+            Err(IdempotencyError::InProgress)
+        );
+
+        // Once completed, a retry returns the same response, but a different request with the same key is rejected.
+        guard.complete(response());
+        assert_matches!(
+            cache.begin_at(requester(), "key", vec![1], start + Duration::from_secs(30)),
+            Ok(Idempotent::Replay(response)) if response.session_url.path() == "/deadbeef/status"
+        );
+        assert_matches!(
+            cache.begin_at(requester(), "key", vec![2], start + Duration::from_secs(30)),
+            Err(IdempotencyError::KeyReused)
+        );
+
+        // After the TTL, the key may be used again. A request that failed may be retried with the same key.
+        let Ok(Idempotent::New(guard)) = cache.begin_at(requester(), "key", vec![2], start + Duration::from_secs(60))
+        else {
+            panic!("request after the TTL should be processed");
+        };
+        drop(guard);
+        assert_matches!(
+            cache.begin_at(requester(), "key", vec![2], start),
+            Ok(Idempotent::New(_))
+        );
+
+        assert_matches!(
+            cache.begin_at(requester(), "", vec![1], start),
+            Err(IdempotencyError::InvalidKey)
+        );
+        assert_matches!(
+            cache.begin_at(requester(), "key with spaces", vec![1], start),
+            Err(IdempotencyError::InvalidKey)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_idempotency_cache_requester() {
+        let cache = IdempotencyCache::new(IdempotencyKeys { ttl_in_seconds: 60 });
+        let start = Instant::now();
+
+        let Ok(Idempotent::New(guard)) = cache.begin_at(requester(), "key", vec![1], start) else {
+            panic!("first request should be processed");
+        };
+        guard.complete(response());
+
+        // Another requester using the same key neither receives the response, nor is rejected.
+        assert_matches!(
+            cache.begin_at(b"other_requester".to_vec(), "key", vec![2], start),
+            Ok(Idempotent::New(_))
+        );
+    }
+}
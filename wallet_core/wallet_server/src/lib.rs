@@ -1,7 +1,15 @@
+pub mod audit_log;
 pub mod cbor;
 #[cfg(feature = "postgres")]
 pub mod entity;
+pub mod forensics;
+pub mod frequency_caps;
+pub mod idempotency;
+pub mod rate_limits;
 pub mod server;
 pub mod settings;
 pub mod store;
+pub mod tls;
+pub mod usecases;
 pub mod verifier;
+pub mod webhooks;
@@ -1,17 +1,25 @@
 use anyhow::Result;
 
-use wallet_server::{server, settings::Settings, store::DisclosureSessionStore};
+use wallet_common::telemetry;
+use wallet_server::{
+    server,
+    settings::Settings,
+    store::{DisclosureSessionStore, StoreConnection},
+};
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize tracing.
-    tracing_subscriber::fmt::init();
-
     let settings = Settings::new()?;
 
-    let sessions = DisclosureSessionStore::init(settings.store_url.clone()).await?;
+    // Initialize tracing.
+    telemetry::init_tracing(settings.otlp.as_ref(), "wallet_server")?;
+
+    let store_connection = StoreConnection::try_new(settings.store_url.clone()).await?;
+    let sessions = DisclosureSessionStore::init(store_connection.clone(), &settings.sessions);
     // This will block until the server shuts down.
-    server::serve(&settings, sessions).await?;
+    let result = server::serve(&settings, store_connection, sessions).await;
+    telemetry::shutdown_tracing();
+    result?;
 
     Ok(())
 }
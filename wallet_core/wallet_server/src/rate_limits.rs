@@ -0,0 +1,254 @@
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+
+use axum::http::HeaderMap;
+use dashmap::DashMap;
+use metrics::increment_counter;
+use tokio::time::Instant;
+
+use nl_wallet_mdoc::server_state::SessionToken;
+
+use crate::{
+    settings::{RateLimit, RateLimits},
+    store::CleanupTask,
+};
+
+/// Header containing the addresses of the client and the proxies that forwarded the request, of which the last one is
+/// appended by the reverse proxy directly in front of the wallet server.
+const FORWARDED_FOR_HEADER: &str = "x-forwarded-for";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::Display)]
+#[strum(serialize_all = "snake_case")]
+pub enum RateLimitScope {
+    Ip,
+    Session,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("rate limit per {scope} exceeded, retry after {} ms", .retry_after.as_millis())]
+pub struct RateLimitExceeded {
+    pub scope: RateLimitScope,
+    pub retry_after: Duration,
+}
+
+#[derive(PartialEq, Eq, Hash)]
+enum RateLimitKey {
+    Ip(IpAddr),
+    Session(SessionToken),
+}
+
+struct RateLimitWindow {
+    start: Instant,
+    requests: u32,
+}
+
+/// Limits the number of requests to the public endpoints of a disclosure session, both per IP address of the client
+/// and per session, within a fixed window of time. Every IP address and session has its own window, which starts at
+/// its first request.
+///
+/// Note that, like the frequency caps, the windows are kept in memory, so these only limit the requests that are
+/// handled by the same instance.
+pub struct RateLimiter {
+    per_ip: Option<(u32, Duration)>,
+    per_session: Option<(u32, Duration)>,
+    use_forwarded_for: bool,
+    state: Arc<RateLimitState>,
+    _cleanup_task: CleanupTask,
+}
+
+struct RateLimitState {
+    windows: DashMap<RateLimitKey, RateLimitWindow>,
+    max_window: Duration,
+}
+
+fn limit(rate_limit: Option<RateLimit>) -> Option<(u32, Duration)> {
+    rate_limit.map(|rate_limit| (rate_limit.max_requests, Duration::from_millis(rate_limit.window_in_ms)))
+}
+
+impl RateLimiter {
+    pub fn new(settings: RateLimits) -> Self {
+        let per_ip = limit(settings.per_ip);
+        let per_session = limit(settings.per_session);
+
+        let state = Arc::new(RateLimitState {
+            windows: DashMap::new(),
+            max_window: [per_ip, per_session]
+                .into_iter()
+                .flatten()
+                .map(|(_, window)| window)
+                .max()
+                .unwrap_or_default(),
+        });
+        let cleanup_task = CleanupTask::start(Arc::clone(&state), |state| async move { state.cleanup() });
+
+        Self {
+            per_ip,
+            per_session,
+            use_forwarded_for: settings.use_forwarded_for,
+            state,
+            _cleanup_task: cleanup_task,
+        }
+    }
+
+    /// Determines the IP address of the client, which is taken from the `X-Forwarded-For` header if configured and
+    /// present, and from the connection otherwise.
+    pub fn client_ip(&self, headers: &HeaderMap, peer: Option<SocketAddr>) -> Option<IpAddr> {
+        let forwarded_for = self
+            .use_forwarded_for
+            .then(|| headers.get(FORWARDED_FOR_HEADER))
+            .flatten()
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.rsplit(',').next())
+            .and_then(|address| address.trim().parse().ok());
+
+        forwarded_for.or(peer.map(|peer| peer.ip()))
+    }
+
+    /// Registers a request for the session from the client with the specified IP address, returning an error if it
+    /// exceeds either of the limits. Requests of which the IP address is not known are only limited per session.
+    pub fn check(&self, ip: Option<IpAddr>, session_id: &SessionToken) -> Result<(), RateLimitExceeded> {
+        self.check_at(ip, session_id, Instant::now())
+    }
+
+    fn check_at(&self, ip: Option<IpAddr>, session_id: &SessionToken, now: Instant) -> Result<(), RateLimitExceeded> {
+        let result = [
+            ip.zip(self.per_ip)
+                .map(|(ip, limit)| (RateLimitScope::Ip, RateLimitKey::Ip(ip), limit)),
+            self.per_session.map(|limit| {
+                (
+                    RateLimitScope::Session,
+                    RateLimitKey::Session(session_id.clone()),
+                    limit,
+                )
+            }),
+        ]
+        .into_iter()
+        .flatten()
+        .try_for_each(|(scope, key, (max_requests, window))| {
+            self.state
+                .check(key, max_requests, window, now)
+                .map_err(|retry_after| RateLimitExceeded { scope, retry_after })
+        });
+
+        if let Err(error) = &result {
+            increment_counter!("wallet_server_rate_limited_requests_total", "scope" => error.scope.to_string());
+        }
+
+        result
+    }
+}
+
+impl RateLimitState {
+    /// Registers a request in the window of `key`, returning the time remaining until the window ends if the request
+    /// exceeds the maximum.
+    fn check(&self, key: RateLimitKey, max_requests: u32, window: Duration, now: Instant) -> Result<(), Duration> {
+        let mut entry = self.windows.entry(key).or_insert(RateLimitWindow {
+            start: now,
+            requests: 0,
+        });
+
+        if now.saturating_duration_since(entry.start) >= window {
+            entry.start = now;
+            entry.requests = 0;
+        }
+
+        if entry.requests >= max_requests {
+            return Err(window - now.saturating_duration_since(entry.start));
+        }
+
+        entry.requests += 1;
+
+        Ok(())
+    }
+
+    /// Remove the windows that have ended.
+    fn cleanup(&self) {
+        let now = Instant::now();
+        self.windows
+            .retain(|_, window| now.saturating_duration_since(window.start) < self.max_window);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use axum::http::HeaderValue;
+
+    use super::*;
+
+    fn rate_limiter(use_forwarded_for: bool) -> RateLimiter {
+        RateLimiter::new(RateLimits {
+            per_ip: Some(RateLimit {
+                max_requests: 3,
+                window_in_ms: 10_000,
+            }),
+            per_session: Some(RateLimit {
+                max_requests: 2,
+                window_in_ms: 10_000,
+            }),
+            use_forwarded_for,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_rate_limiter() {
+        let rate_limiter = rate_limiter(false);
+        let ip = Some("192.0.2.1".parse().unwrap());
+        let session_1: SessionToken = "session_1".to_owned().into();
+        let session_2: SessionToken = "session_2".to_owned().into();
+        let start = Instant::now();
+
+        rate_limiter.check_at(ip, &session_1, start).unwrap();
+        rate_limiter.check_at(ip, &session_1, start).unwrap();
+
+        // The third request for the same session is rejected, with the time left until the window ends.
+        assert_matches!(
+            rate_limiter.check_at(ip, &session_1, start + Duration::from_secs(4)),
+            Err(RateLimitExceeded { scope: RateLimitScope::Session, retry_after })
+                if retry_after == Duration::from_secs(6)
+        );
+
+        // The request for another session is the fourth of this IP address, which is rejected as well.
+        assert_matches!(
+            rate_limiter.check_at(ip, &session_2, start + Duration::from_secs(4)),
+            Err(RateLimitExceeded {
+                scope: RateLimitScope::Ip,
+                ..
+            })
+        );
+
+        // Requests of which the IP address is unknown are only limited per session.
+        rate_limiter.check_at(None, &session_2, start).unwrap();
+
+        // After the windows have ended, requests are accepted again.
+        rate_limiter
+            .check_at(ip, &session_1, start + Duration::from_secs(10))
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_client_ip() {
+        let peer = Some("192.0.2.1:1234".parse().unwrap());
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            FORWARDED_FOR_HEADER,
+            HeaderValue::from_static("198.51.100.1, 203.0.113.1"),
+        );
+
+        assert_eq!(
+            rate_limiter(false).client_ip(&headers, peer),
+            Some("192.0.2.1".parse().unwrap())
+        );
+        assert_eq!(
+            rate_limiter(true).client_ip(&headers, peer),
+            Some("203.0.113.1".parse().unwrap())
+        );
+        assert_eq!(
+            rate_limiter(true).client_ip(&HeaderMap::new(), peer),
+            Some("192.0.2.1".parse().unwrap())
+        );
+    }
+}
@@ -1,54 +1,106 @@
-use std::net::SocketAddr;
+use std::{net::SocketAddr, sync::Arc};
 
 use anyhow::Result;
-use axum::{routing::get, Router};
+use axum::{middleware, routing::get, Router};
 use tracing::debug;
 
 use nl_wallet_mdoc::{
     server_state::{SessionState, SessionStore},
     verifier::DisclosureData,
 };
+use wallet_common::{
+    api_key::require_api_key,
+    metrics::{install_recorder, metrics_router},
+    tls::tls_config,
+    utils::sha256,
+};
 
-use crate::{settings::Settings, verifier::create_routers};
+use crate::{
+    audit_log::AuditLog, forensics::ForensicStore, settings::Settings, store::StoreConnection, tls::mutual_tls_config,
+    verifier::create_routers,
+};
 
 fn health_router() -> Router {
     Router::new().route("/health", get(|| async {}))
 }
 
-pub async fn serve<S>(settings: &Settings, sessions: S) -> Result<()>
+pub async fn serve<S>(settings: &Settings, store_connection: StoreConnection, sessions: S) -> Result<()>
 where
     S: SessionStore<Data = SessionState<DisclosureData>> + Send + Sync + 'static,
 {
     let wallet_socket = SocketAddr::new(settings.wallet_server.ip, settings.wallet_server.port);
     let requester_socket = SocketAddr::new(settings.requester_server.ip, settings.requester_server.port);
 
-    let (wallet_router, requester_router) = create_routers(settings.clone(), sessions)?;
-
-    debug!("listening for requester on {}", requester_socket);
-    let requester_server = tokio::spawn(async move {
-        axum::Server::bind(&requester_socket)
-            .serve(
-                Router::new()
-                    .nest("/sessions", requester_router)
-                    .nest("/sessions", health_router())
-                    .into_make_service(),
-            )
-            .await
-            .expect("requester server should be started")
-    });
-
-    debug!("listening for wallet on {}", wallet_socket);
-    let wallet_server = tokio::spawn(async move {
-        axum::Server::bind(&wallet_socket)
-            .serve(
-                Router::new()
-                    .nest("/", wallet_router)
-                    .nest("/", health_router())
-                    .into_make_service(),
-            )
-            .await
-            .expect("wallet server should be started")
-    });
+    let forensics = match settings.forensics.clone() {
+        Some(forensics) => Some(ForensicStore::try_new(forensics, store_connection.clone())?),
+        None => None,
+    };
+
+    let audit_log = settings
+        .audit_log
+        .clone()
+        .map(|audit_log| AuditLog::new(audit_log, store_connection));
+
+    let (wallet_router, mut requester_router) = create_routers(settings.clone(), sessions, forensics, audit_log)?;
+    if let Some(api_key) = &settings.requester_server.api_key {
+        let api_key_digest = Arc::new(sha256(api_key.as_bytes()));
+        requester_router =
+            requester_router.route_layer(middleware::from_fn_with_state(api_key_digest, require_api_key));
+    }
+
+    let mut requester_router = Router::new()
+        .nest("/sessions", requester_router)
+        .nest("/sessions", health_router());
+    if settings.metrics_enabled {
+        requester_router = requester_router.merge(metrics_router(install_recorder()?));
+    }
+
+    let requester_server = match &settings.requester_server.mtls {
+        Some(mtls) => {
+            let (tls_config, _tls_reload_task) = mutual_tls_config(mtls)?;
+
+            debug!("listening for requester on {} using mutual TLS", requester_socket);
+            tokio::spawn(async move {
+                axum_server::bind_rustls(requester_socket, tls_config)
+                    .serve(requester_router.into_make_service())
+                    .await
+                    .expect("requester server should be started")
+            })
+        }
+        None => {
+            debug!("listening for requester on {}", requester_socket);
+            tokio::spawn(async move {
+                axum::Server::bind(&requester_socket)
+                    .serve(requester_router.into_make_service())
+                    .await
+                    .expect("requester server should be started")
+            })
+        }
+    };
+
+    let wallet_router = Router::new().nest("/", wallet_router).nest("/", health_router());
+    let wallet_server = match &settings.wallet_server.tls {
+        Some(tls) => {
+            let (tls_config, _tls_reload_task) = tls_config(tls)?;
+
+            debug!("listening for wallet on {} using TLS", wallet_socket);
+            tokio::spawn(async move {
+                axum_server::bind_rustls(wallet_socket, tls_config)
+                    .serve(wallet_router.into_make_service_with_connect_info::<SocketAddr>())
+                    .await
+                    .expect("wallet server should be started")
+            })
+        }
+        None => {
+            debug!("listening for wallet on {}", wallet_socket);
+            tokio::spawn(async move {
+                axum::Server::bind(&wallet_socket)
+                    .serve(wallet_router.into_make_service_with_connect_info::<SocketAddr>())
+                    .await
+                    .expect("wallet server should be started")
+            })
+        }
+    };
 
     tokio::try_join!(requester_server, wallet_server)?;
 
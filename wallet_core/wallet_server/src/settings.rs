@@ -4,7 +4,10 @@ use config::{Config, ConfigError, Environment, File};
 use serde::Deserialize;
 use url::Url;
 
-use wallet_common::account::serialization::Base64Bytes;
+use nl_wallet_mdoc::{
+    server_state::SESSION_EXPIRY_MINUTES, utils::reader_auth::ReturnUrlPrefix, verifier::DisclosedAttributes,
+};
+use wallet_common::{account::serialization::Base64Bytes, telemetry::OtlpSettings, tls::TlsSettings};
 
 #[derive(Deserialize, Clone)]
 pub struct Settings {
@@ -12,20 +15,188 @@ pub struct Settings {
     pub wallet_server: Server,
     // used by the application, SHOULD be reachable only by the application.
     // if it conflicts with wallet_server, the application will crash on startup
-    pub requester_server: Server,
-    pub usecases: HashMap<String, KeyPair>,
+    pub requester_server: RequesterServer,
+    // the relying party usecases served by this instance, by usecase ID
+    pub usecases: HashMap<String, Usecase>,
     pub trust_anchors: Vec<String>,
     pub public_url: Url,
     // used by the application
     pub internal_url: Url,
     // supported schemes are: memory:// (default) and postgres://
     pub store_url: Url,
+    // expiry and limits of sessions in the memory store
+    pub sessions: Sessions,
+    // when present, sessions can be completed without a wallet. MUST NOT be used in production.
+    pub simulation: Option<Simulation>,
+    // when present, evidence of completed sessions is retained for investigating disputes
+    pub forensics: Option<Forensics>,
+    // when present, every completed session is recorded in an audit log, without any attribute values
+    pub audit_log: Option<AuditLog>,
+    // when present, limits how often usecases may request certain attributes of the same subject
+    pub frequency_caps: Option<FrequencyCaps>,
+    // when present, limits the number of requests to the public endpoints of a session
+    pub rate_limits: Option<RateLimits>,
+    // when present, disclosed mdocs that refer to a status list are rejected if they have been revoked
+    pub status_lists: Option<StatusLists>,
+    // when present, requesters can register a callback URL that is notified when a session has ended
+    pub webhooks: Option<Webhooks>,
+    // when present, the disclosed attributes can also be retrieved as a JWT signed with this key
+    pub result_signing: Option<KeyPair>,
+    // when present, starting a session with an Idempotency-Key header that was used before returns the same session
+    pub idempotency_keys: Option<IdempotencyKeys>,
+    // when enabled, metrics are exposed in the Prometheus format on /metrics of the requester server
+    pub metrics_enabled: bool,
+    // when present, spans are exported to an OpenTelemetry collector over OTLP
+    pub otlp: Option<OtlpSettings>,
 }
 
 #[derive(Deserialize, Clone)]
 pub struct Server {
     pub ip: IpAddr,
     pub port: u16,
+    // when present, the server is served over TLS, so that no reverse proxy is required for this
+    pub tls: Option<TlsSettings>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct RequesterServer {
+    pub ip: IpAddr,
+    pub port: u16,
+    // when present, requests other than the health checks and metrics need to provide this API key as bearer token
+    pub api_key: Option<String>,
+    // when present, the requester server is served over TLS and only accepts clients with a certificate issued by
+    // one of the configured client CAs
+    pub mtls: Option<MutualTls>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct MutualTls {
+    // PEM encoded certificate chain and PKCS8 private key of the server
+    pub certificate_path: PathBuf,
+    pub private_key_path: PathBuf,
+    // PEM encoded CA certificates that issue the certificates of the clients
+    pub client_ca_path: PathBuf,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct Sessions {
+    // the time after which a session that has not been active expires
+    pub ttl_in_seconds: u64,
+    // when present, the least recently active session is evicted when a new session would exceed this maximum
+    pub max_sessions: Option<usize>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct Simulation {
+    // canned disclosed attributes per template name, with which a session can be completed
+    pub templates: HashMap<String, DisclosedAttributes>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct Forensics {
+    // AES-256 key with which the evidence is sealed before it is persisted
+    pub sealing_key: Base64Bytes,
+    pub retention_days: u64,
+    // SHA256 digest of the token of each investigator, by name. Evidence can only be retrieved with the tokens of
+    // two distinct investigators.
+    pub investigators: HashMap<String, Base64Bytes>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct AuditLog {
+    // the time after which entries are removed from the audit log, counted from the start of the session
+    pub retention_days: u64,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct FrequencyCaps {
+    // key from which the pseudonyms of subjects are derived, so that no attribute values are retained
+    pub pseudonym_key: Base64Bytes,
+    // caps per usecase
+    pub usecases: HashMap<String, Vec<FrequencyCap>>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct FrequencyCap {
+    pub doc_type: String,
+    pub namespace: String,
+    // the cap applies to sessions requesting any of these attributes
+    pub attributes: Vec<String>,
+    // attribute in the same namespace that identifies the subject
+    pub subject_attribute: String,
+    pub max_requests: u32,
+    pub window_seconds: u64,
+    pub action: FrequencyCapAction,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FrequencyCapAction {
+    // the disclosed attributes are not returned
+    Reject,
+    // the disclosed attributes are returned, with a header signalling the cap was exceeded
+    Flag,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct RateLimits {
+    // limit of the requests from a single IP address, for any session
+    pub per_ip: Option<RateLimit>,
+    // limit of the requests for a single session, from any IP address
+    pub per_session: Option<RateLimit>,
+    // when enabled, the IP address of the client is taken from the last address in the X-Forwarded-For header, as
+    // appended by the reverse proxy in front of the wallet server. MUST NOT be enabled without such a proxy.
+    #[serde(default)]
+    pub use_forwarded_for: bool,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+pub struct RateLimit {
+    pub max_requests: u32,
+    pub window_in_ms: u64,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct StatusLists {
+    // maximum time a fetched status list is cached, even if the issuer allows it to be cached for longer
+    pub max_ttl_seconds: u64,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct Webhooks {
+    // PKCS8 DER encoded ECDSA P-256 key with which the notifications are signed
+    pub signing_key: Base64Bytes,
+    #[serde(default = "default_webhook_timeout_in_ms")]
+    pub timeout_in_ms: u64,
+    // the number of times delivery of a notification is attempted before giving up
+    #[serde(default = "default_webhook_max_attempts")]
+    pub max_attempts: u32,
+}
+
+fn default_webhook_timeout_in_ms() -> u64 {
+    5_000
+}
+
+fn default_webhook_max_attempts() -> u32 {
+    3
+}
+
+#[derive(Deserialize, Clone)]
+pub struct IdempotencyKeys {
+    // the time during which a key results in the same session
+    pub ttl_in_seconds: u64,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct Usecase {
+    // reader certificate and corresponding private key, with which the usecase authenticates to the wallet
+    pub certificate: Base64Bytes,
+    pub private_key: Base64Bytes,
+    // when present, sessions of this usecase may only request these attributes, by doctype and namespace
+    pub allowed_attributes: Option<HashMap<String, HashMap<String, Vec<String>>>>,
+    // when not empty, the return URL of a session of this usecase should match one of these prefixes
+    #[serde(default)]
+    pub return_url_prefixes: Vec<ReturnUrlPrefix>,
 }
 
 #[derive(Deserialize, Clone)]
@@ -48,6 +219,8 @@ impl Settings {
             .set_default("public_url", "http://localhost:3001/")?
             .set_default("internal_url", "http://localhost:3002/")?
             .set_default("store_url", "memory://")?
+            .set_default("sessions.ttl_in_seconds", SESSION_EXPIRY_MINUTES * 60)?
+            .set_default("metrics_enabled", false)?
             .add_source(File::from(config_path.join("wallet_server.toml")).required(false))
             .add_source(
                 Environment::with_prefix("wallet_server")
@@ -1,3 +1,6 @@
+use std::{future::Future, sync::Arc, time::Duration};
+
+use tokio::{task::JoinHandle, time};
 use url::Url;
 
 use nl_wallet_mdoc::{
@@ -5,9 +8,35 @@ use nl_wallet_mdoc::{
     verifier::DisclosureData,
 };
 
+use crate::settings::Sessions;
 #[cfg(feature = "postgres")]
 use crate::store::postgres::PostgresSessionStore;
 
+/// The expired entries of the stores of the wallet server are removed every so often.
+const CLEANUP_INTERVAL_SECONDS: u64 = 60;
+
+/// The connection to the storage configured by `store_url`. It is shared by the session store and the other stores
+/// of the wallet server, so that these use a single connection pool.
+#[derive(Clone)]
+pub enum StoreConnection {
+    #[cfg(feature = "postgres")]
+    Postgres(sea_orm::DatabaseConnection),
+    Memory,
+}
+
+impl StoreConnection {
+    pub async fn try_new(url: Url) -> anyhow::Result<Self> {
+        let connection = match url.scheme() {
+            #[cfg(feature = "postgres")]
+            "postgres" => StoreConnection::Postgres(postgres::connect(url).await?),
+            "memory" => StoreConnection::Memory,
+            e => anyhow::bail!("unsupported store: {}", e),
+        };
+
+        Ok(connection)
+    }
+}
+
 /// This enum effectively switches between the different types that implement `DisclosureSessionStore`,
 /// by implementing this trait itself and forwarding the calls to the type contained in the invariant.
 pub enum DisclosureSessionStore {
@@ -17,15 +46,17 @@ pub enum DisclosureSessionStore {
 }
 
 impl DisclosureSessionStore {
-    pub async fn init(url: Url) -> anyhow::Result<Self> {
-        let session_store = match url.scheme() {
+    pub fn init(connection: StoreConnection, sessions: &Sessions) -> Self {
+        match connection {
             #[cfg(feature = "postgres")]
-            "postgres" => DisclosureSessionStore::Postgres(PostgresSessionStore::connect(url).await?),
-            "memory" => DisclosureSessionStore::Memory(MemorySessionStore::new()),
-            e => unimplemented!("{}", e),
-        };
-
-        Ok(session_store)
+            StoreConnection::Postgres(connection) => {
+                DisclosureSessionStore::Postgres(PostgresSessionStore::new(connection))
+            }
+            StoreConnection::Memory => DisclosureSessionStore::Memory(MemorySessionStore::with_limits(
+                chrono::Duration::seconds(sessions.ttl_in_seconds as i64),
+                sessions.max_sessions,
+            )),
+        }
     }
 }
 
@@ -48,6 +79,14 @@ impl SessionStore for DisclosureSessionStore {
         }
     }
 
+    async fn list(&self) -> Result<Vec<Self::Data>, SessionStoreError> {
+        match self {
+            #[cfg(feature = "postgres")]
+            DisclosureSessionStore::Postgres(postgres) => postgres.list().await,
+            DisclosureSessionStore::Memory(memory) => memory.list().await,
+        }
+    }
+
     async fn cleanup(&self) -> Result<(), SessionStoreError> {
         match self {
             #[cfg(feature = "postgres")]
@@ -57,6 +96,37 @@ impl SessionStore for DisclosureSessionStore {
     }
 }
 
+/// Periodically removes the expired entries of a store, like the cleanup task of the session store does. The task is
+/// stopped when this is dropped.
+pub struct CleanupTask(JoinHandle<()>);
+
+impl CleanupTask {
+    /// Start a task that calls `cleanup` with `state` every [`CLEANUP_INTERVAL_SECONDS`].
+    pub fn start<T, F, Fut>(state: Arc<T>, cleanup: F) -> Self
+    where
+        T: Send + Sync + 'static,
+        F: Fn(Arc<T>) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send,
+    {
+        let mut interval = time::interval(Duration::from_secs(CLEANUP_INTERVAL_SECONDS));
+        let task = tokio::spawn(async move {
+            loop {
+                interval.tick().await;
+                cleanup(Arc::clone(&state)).await;
+            }
+        });
+
+        Self(task)
+    }
+}
+
+impl Drop for CleanupTask {
+    fn drop(&mut self) {
+        // Stop the task at the next .await
+        self.0.abort();
+    }
+}
+
 #[cfg(feature = "postgres")]
 pub mod postgres {
     use std::{marker::PhantomData, time::Duration};
@@ -67,7 +137,7 @@ pub mod postgres {
         QueryFilter,
     };
     use serde::{de::DeserializeOwned, Serialize};
-    use tracing::log::LevelFilter;
+    use tracing::{instrument, log::LevelFilter};
     use url::Url;
 
     use crate::entity::session_state;
@@ -77,44 +147,55 @@ pub mod postgres {
 
     const DB_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
 
+    pub async fn connect(url: Url) -> anyhow::Result<DatabaseConnection> {
+        let mut connection_options = ConnectOptions::new(url);
+        connection_options
+            .connect_timeout(DB_CONNECT_TIMEOUT)
+            .sqlx_logging(true)
+            .sqlx_logging_level(LevelFilter::Trace);
+
+        let connection = Database::connect(connection_options).await?;
+        Ok(connection)
+    }
+
     pub struct PostgresSessionStore<T> {
         connection: DatabaseConnection,
         _marker: PhantomData<T>,
     }
 
     impl<T> PostgresSessionStore<T> {
-        pub async fn connect(url: Url) -> anyhow::Result<Self> {
-            let mut connection_options = ConnectOptions::new(url);
-            connection_options
-                .connect_timeout(DB_CONNECT_TIMEOUT)
-                .sqlx_logging(true)
-                .sqlx_logging_level(LevelFilter::Trace);
-
-            let db = Database::connect(connection_options).await?;
-            Ok(Self {
-                connection: db,
+        pub fn new(connection: DatabaseConnection) -> Self {
+            Self {
+                connection,
                 _marker: PhantomData,
-            })
+            }
         }
     }
 
     impl<T: Clone + Serialize + DeserializeOwned + Send + Sync> SessionStore for PostgresSessionStore<T> {
         type Data = SessionState<T>;
 
+        #[instrument(skip_all)]
         async fn get(&self, token: &SessionToken) -> Result<Option<Self::Data>, SessionStoreError> {
-            // find value by token, deserialize from JSON if it exists
+            // find value by token, deserialize from JSON if it exists and has not expired yet, as the cleanup task
+            // may not have deleted it yet
             let state = session_state::Entity::find()
                 .filter(session_state::Column::Token.eq(token.to_string()))
                 .one(&self.connection)
                 .await
                 .map_err(|e| SessionStoreError::Other(e.into()))?;
 
+            if state.as_ref().is_some_and(|s| s.expiration_date_time <= Utc::now()) {
+                return Err(SessionStoreError::Expired);
+            }
+
             state
                 .map(|s| serde_json::from_value(s.data))
                 .transpose()
                 .map_err(|e| SessionStoreError::Deserialize(Box::new(e)))
         }
 
+        #[instrument(skip_all)]
         async fn write(&self, session: &Self::Data) -> Result<(), SessionStoreError> {
             // insert new value (serialized to JSON), update on conflicting session token
             session_state::Entity::insert(session_state::ActiveModel {
@@ -138,6 +219,21 @@ pub mod postgres {
             Ok(())
         }
 
+        #[instrument(skip_all)]
+        async fn list(&self) -> Result<Vec<Self::Data>, SessionStoreError> {
+            // find all sessions that have not expired yet, as the cleanup task may not have deleted these yet
+            session_state::Entity::find()
+                .filter(session_state::Column::ExpirationDateTime.gt(Utc::now()))
+                .all(&self.connection)
+                .await
+                .map_err(|e| SessionStoreError::Other(e.into()))?
+                .into_iter()
+                .map(|s| serde_json::from_value(s.data))
+                .collect::<Result<_, _>>()
+                .map_err(|e| SessionStoreError::Deserialize(Box::new(e)))
+        }
+
+        #[instrument(skip_all)]
         async fn cleanup(&self) -> Result<(), SessionStoreError> {
             // delete expired sessions
             session_state::Entity::delete_many()
@@ -152,10 +248,12 @@ pub mod postgres {
 
     #[cfg(test)]
     mod tests {
+        use sea_orm::PaginatorTrait;
+        use serde::Deserialize;
+
         use crate::settings::Settings;
 
         use super::*;
-        use serde::Deserialize;
 
         #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
         struct TestData {
@@ -167,9 +265,7 @@ pub mod postgres {
         #[tokio::test]
         async fn test_write() {
             let settings = Settings::new().unwrap();
-            let store = PostgresSessionStore::<TestData>::connect(settings.store_url)
-                .await
-                .unwrap();
+            let store = PostgresSessionStore::<TestData>::new(connect(settings.store_url).await.unwrap());
 
             let expected = SessionState::<TestData>::new(
                 SessionToken::new(),
@@ -184,5 +280,80 @@ pub mod postgres {
             let actual = store.get(&expected.token).await.unwrap().unwrap();
             assert_eq!(actual.session_data, expected.session_data);
         }
+
+        #[cfg_attr(not(feature = "db_test"), ignore)]
+        #[tokio::test]
+        async fn test_expired() {
+            let settings = Settings::new().unwrap();
+            let store = PostgresSessionStore::<TestData>::new(connect(settings.store_url).await.unwrap());
+
+            let mut expired = SessionState::<TestData>::new(
+                SessionToken::new(),
+                TestData {
+                    id: "expired".to_owned(),
+                    data: vec![4, 5, 6],
+                },
+            );
+            expired.last_active = Utc::now() - chrono::Duration::minutes(SESSION_EXPIRY_MINUTES as i64 + 1);
+
+            store.write(&expired).await.unwrap();
+
+            // an expired session is not returned or listed, even before it has been deleted
+            assert!(matches!(
+                store.get(&expired.token).await,
+                Err(SessionStoreError::Expired)
+            ));
+            assert!(!store
+                .list()
+                .await
+                .unwrap()
+                .iter()
+                .any(|session| session.token == expired.token));
+
+            store.cleanup().await.unwrap();
+
+            let count = session_state::Entity::find()
+                .filter(session_state::Column::Token.eq(expired.token.to_string()))
+                .count(&store.connection)
+                .await
+                .unwrap();
+            assert_eq!(count, 0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_cleanup_task() {
+        let count = Arc::new(AtomicUsize::new(0));
+        let cleanup_task = CleanupTask::start(Arc::clone(&count), |count| async move {
+            count.fetch_add(1, Ordering::SeqCst);
+        });
+
+        // The first cleanup happens right away, the next one after the interval.
+        time::sleep(Duration::from_secs(1)).await;
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+
+        time::sleep(Duration::from_secs(CLEANUP_INTERVAL_SECONDS)).await;
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+
+        // No cleanup happens after the task has been dropped.
+        drop(cleanup_task);
+        time::sleep(Duration::from_secs(CLEANUP_INTERVAL_SECONDS * 2)).await;
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_store_url() {
+        let error = StoreConnection::try_new("redis://localhost".parse().unwrap())
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(error.to_string(), "unsupported store: redis");
     }
 }
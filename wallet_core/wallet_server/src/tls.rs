@@ -0,0 +1,61 @@
+use std::sync::Arc;
+
+use axum_server::tls_rustls::RustlsConfig;
+use rustls::{server::AllowAnyAuthenticatedClient, RootCertStore, ServerConfig};
+use tokio::task::JoinHandle;
+
+use wallet_common::tls::{read_certificates, read_private_key, start_reload_task, TlsError};
+
+use crate::settings::MutualTls;
+
+#[derive(Debug, thiserror::Error)]
+pub enum MutualTlsError {
+    #[error(transparent)]
+    Tls(#[from] TlsError),
+    #[error("no client CA certificates found in {0}")]
+    NoClientCaCertificates(String),
+}
+
+fn mutual_tls_server_config(settings: &MutualTls) -> Result<ServerConfig, MutualTlsError> {
+    let mut client_roots = RootCertStore::empty();
+    let client_ca_certificates = read_certificates(&settings.client_ca_path)?;
+    if client_ca_certificates.is_empty() {
+        return Err(MutualTlsError::NoClientCaCertificates(
+            settings.client_ca_path.display().to_string(),
+        ));
+    }
+    for certificate in &client_ca_certificates {
+        client_roots.add(certificate).map_err(TlsError::from)?;
+    }
+
+    let mut config = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_client_cert_verifier(AllowAnyAuthenticatedClient::new(client_roots).boxed())
+        .with_single_cert(
+            read_certificates(&settings.certificate_path)?,
+            read_private_key(&settings.private_key_path)?,
+        )
+        .map_err(TlsError::from)?;
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+
+    Ok(config)
+}
+
+/// Create the TLS configuration for a server that only accepts clients presenting a certificate that was issued by
+/// one of the configured client CAs, together with the task that reloads it when any of the files change.
+pub fn mutual_tls_config(settings: &MutualTls) -> Result<(RustlsConfig, JoinHandle<()>), MutualTlsError> {
+    let config = RustlsConfig::from_config(Arc::new(mutual_tls_server_config(settings)?));
+
+    let settings = settings.clone();
+    let reload_task = start_reload_task(
+        config.clone(),
+        vec![
+            settings.certificate_path.clone(),
+            settings.private_key_path.clone(),
+            settings.client_ca_path.clone(),
+        ],
+        move || mutual_tls_server_config(&settings),
+    );
+
+    Ok((config, reload_task))
+}
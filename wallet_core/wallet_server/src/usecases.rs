@@ -0,0 +1,171 @@
+use std::collections::HashSet;
+
+use nl_wallet_mdoc::{
+    identifiers::{AttributeIdentifier, AttributeIdentifierHolder},
+    utils::reader_auth::ReturnUrlPrefix,
+    verifier::ItemsRequests,
+};
+use url::Url;
+
+use crate::settings::Usecase;
+
+#[derive(Debug, thiserror::Error)]
+pub enum UsecaseError {
+    #[error("attributes not allowed for usecase: {0:?}")]
+    AttributesNotAllowed(Vec<AttributeIdentifier>),
+    #[error("return URL not allowed for usecase: {0}")]
+    ReturnUrlNotAllowed(String),
+}
+
+/// The restrictions on the sessions that may be started for a usecase, as configured in its [`Usecase`] settings.
+#[derive(Debug, Clone, Default)]
+pub struct UsecasePolicy {
+    allowed_attributes: Option<HashSet<AttributeIdentifier>>,
+    return_url_prefixes: Vec<ReturnUrlPrefix>,
+}
+
+impl From<&Usecase> for UsecasePolicy {
+    fn from(value: &Usecase) -> Self {
+        let allowed_attributes = value.allowed_attributes.as_ref().map(|doc_types| {
+            doc_types
+                .iter()
+                .flat_map(|(doc_type, namespaces)| {
+                    namespaces.iter().flat_map(move |(namespace, attributes)| {
+                        attributes.iter().map(move |attribute| AttributeIdentifier {
+                            doc_type: doc_type.clone(),
+                            namespace: namespace.clone(),
+                            attribute: attribute.clone(),
+                        })
+                    })
+                })
+                .collect()
+        });
+
+        UsecasePolicy {
+            allowed_attributes,
+            return_url_prefixes: value.return_url_prefixes.clone(),
+        }
+    }
+}
+
+impl UsecasePolicy {
+    /// Checks that the requested attributes are allowed for the usecase and that the return URL, if any, matches one of
+    /// the configured prefixes. The return URL template is checked by substituting a placeholder for the session ID.
+    pub fn check_request(
+        &self,
+        items_requests: &ItemsRequests,
+        return_url_template: Option<&str>,
+    ) -> Result<(), UsecaseError> {
+        if let Some(allowed_attributes) = &self.allowed_attributes {
+            let not_allowed = items_requests
+                .0
+                .iter()
+                .flat_map(|items_request| items_request.attribute_identifiers())
+                .filter(|attribute| !allowed_attributes.contains(attribute))
+                .collect::<Vec<_>>();
+
+            if !not_allowed.is_empty() {
+                return Err(UsecaseError::AttributesNotAllowed(not_allowed));
+            }
+        }
+
+        if let Some(template) = return_url_template {
+            if !self.return_url_prefixes.is_empty() {
+                let allowed = template
+                    .replace("{session_id}", "session_id")
+                    .parse::<Url>()
+                    .is_ok_and(|url| self.return_url_prefixes.iter().any(|prefix| prefix.matches_url(&url)));
+
+                if !allowed {
+                    return Err(UsecaseError::ReturnUrlNotAllowed(template.to_string()));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use assert_matches::assert_matches;
+    use indexmap::IndexMap;
+
+    use nl_wallet_mdoc::ItemsRequest;
+
+    use super::*;
+
+    fn usecase_policy() -> UsecasePolicy {
+        UsecasePolicy::from(&Usecase {
+            certificate: vec![].into(),
+            private_key: vec![].into(),
+            allowed_attributes: Some(HashMap::from([(
+                "com.example.pid".to_string(),
+                HashMap::from([(
+                    "com.example.pid".to_string(),
+                    vec!["given_name".to_string(), "family_name".to_string()],
+                )]),
+            )])),
+            return_url_prefixes: vec!["https://example.com/return/".parse().unwrap()],
+        })
+    }
+
+    fn items_requests(attributes: &[&str]) -> ItemsRequests {
+        vec![ItemsRequest {
+            doc_type: "com.example.pid".to_string(),
+            request_info: None,
+            name_spaces: IndexMap::from([(
+                "com.example.pid".to_string(),
+                attributes
+                    .iter()
+                    .map(|attribute| (attribute.to_string(), false))
+                    .collect(),
+            )]),
+        }]
+        .into()
+    }
+
+    #[test]
+    fn test_usecase_policy_attributes() {
+        let policy = usecase_policy();
+
+        assert_matches!(policy.check_request(&items_requests(&["given_name"]), None), Ok(()));
+        assert_matches!(
+            policy.check_request(&items_requests(&["given_name", "bsn"]), None),
+            Err(UsecaseError::AttributesNotAllowed(attributes)) if attributes.len() == 1
+        );
+
+        // Without allowed attributes, any attribute may be requested.
+        assert_matches!(
+            UsecasePolicy::default().check_request(&items_requests(&["bsn"]), None),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_usecase_policy_return_url() {
+        let policy = usecase_policy();
+        let items_requests = items_requests(&["given_name"]);
+
+        assert_matches!(
+            policy.check_request(&items_requests, Some("https://example.com/return/{session_id}")),
+            Ok(())
+        );
+        assert_matches!(
+            policy.check_request(&items_requests, Some("https://example.com/other/{session_id}")),
+            Err(UsecaseError::ReturnUrlNotAllowed(_))
+        );
+        assert_matches!(
+            policy.check_request(&items_requests, Some("https://evil.example.com/return/{session_id}")),
+            Err(UsecaseError::ReturnUrlNotAllowed(_))
+        );
+
+        // Without return URL prefixes, any return URL is allowed.
+        assert_matches!(
+            UsecasePolicy::default().check_request(&items_requests, Some("https://example.com/other/")),
+            Ok(())
+        );
+    }
+}
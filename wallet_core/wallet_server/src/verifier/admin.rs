@@ -0,0 +1,88 @@
+use std::{collections::HashMap, sync::Arc};
+
+use axum::{
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    Json,
+};
+use tracing::log::info;
+
+use crate::{
+    audit_log::{AuditLogEntry, AuditLogQuery},
+    frequency_caps::{FrequencyCapMetrics, FrequencyCapper},
+    settings::Settings,
+};
+use nl_wallet_mdoc::{
+    server_state::{SessionState, SessionStore, SessionToken},
+    verifier::{DisclosureData, SessionEvidence},
+};
+
+use super::{ApplicationState, Error};
+
+/// Read the settings again and replace the keys and policies of the usecases and the trust anchors, see
+/// [`ApplicationState::reload()`].
+pub(super) async fn reload<S>(State(state): State<Arc<ApplicationState<S>>>) -> Result<(), Error>
+where
+    S: SessionStore<Data = SessionState<DisclosureData>>,
+{
+    let settings = Settings::new().map_err(|error| Error::Reload(error.into()))?;
+    state.reload(&settings).map_err(|error| Error::Reload(error.into()))?;
+
+    info!(
+        "reloaded keys and trust anchors of {} usecase(s)",
+        settings.usecases.len()
+    );
+
+    Ok(())
+}
+
+pub(super) async fn session_evidence<S>(
+    State(state): State<Arc<ApplicationState<S>>>,
+    Path(session_id): Path<SessionToken>,
+    headers: HeaderMap,
+) -> Result<Json<SessionEvidence>, Error>
+where
+    S: SessionStore<Data = SessionState<DisclosureData>>,
+{
+    // this endpoint is only routed when forensics are enabled
+    let forensics = state.forensics.as_ref().ok_or(Error::SessionEvidenceNotFound)?;
+
+    if !forensics.is_authorized(&headers) {
+        return Err(Error::SessionEvidenceUnauthorized);
+    }
+
+    let evidence = forensics
+        .retrieve(&session_id)
+        .await
+        .map_err(Error::SessionEvidence)?
+        .ok_or(Error::SessionEvidenceNotFound)?;
+
+    Ok(Json(evidence))
+}
+
+pub(super) async fn query_audit_log<S>(
+    State(state): State<Arc<ApplicationState<S>>>,
+    Query(query): Query<AuditLogQuery>,
+) -> Result<Json<Vec<AuditLogEntry>>, Error> {
+    // this endpoint is only routed when the audit log is enabled
+    let Some(audit_log) = &state.audit_log else {
+        return Ok(Json(vec![]));
+    };
+
+    let entries = audit_log.query(query).await.map_err(Error::AuditLog)?;
+
+    Ok(Json(entries))
+}
+
+pub(super) async fn frequency_cap_metrics<S>(
+    State(state): State<Arc<ApplicationState<S>>>,
+) -> Json<HashMap<String, FrequencyCapMetrics>> {
+    // this endpoint is only routed when frequency caps are enabled
+    let metrics = state
+        .frequency_caps
+        .as_ref()
+        .map(FrequencyCapper::metrics)
+        .unwrap_or_default();
+
+    Json(metrics)
+}
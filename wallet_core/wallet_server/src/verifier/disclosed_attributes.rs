@@ -0,0 +1,143 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{header::CONTENT_TYPE, HeaderMap, HeaderValue},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_with::{
+    base64::{Base64, UrlSafe},
+    formats::Unpadded,
+    serde_as,
+};
+use url::Url;
+
+use crate::frequency_caps::{FrequencyCapOutcome, FREQUENCY_CAP_EXCEEDED_HEADER};
+use nl_wallet_mdoc::{
+    server_state::{SessionState, SessionStore, SessionToken},
+    utils::serialization::{cbor_serialize, TaggedBytes},
+    verifier::{DisclosedAttributes, DisclosureData},
+};
+use wallet_common::{
+    jwt::{self, Jwt},
+    utils::sha256,
+};
+
+use super::{ApplicationState, Error};
+
+#[serde_as]
+#[derive(Deserialize)]
+pub(super) struct DisclosedAttributesParams {
+    #[serde_as(as = "Option<Base64<UrlSafe, Unpadded>>")]
+    transcript_hash: Option<Vec<u8>>,
+}
+
+/// The claims of the JWT returned by the signed disclosed attributes endpoint, which serves as portable proof for the
+/// relying party that the attributes were disclosed and verified.
+#[serde_as]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DisclosedAttributesClaims {
+    /// The public URL of the wallet server that verified the disclosure.
+    pub iss: Url,
+    pub sub: SessionToken,
+    /// The time at which the JWT was signed, as the number of seconds since the Unix epoch.
+    pub iat: i64,
+    /// SHA256 digest of the session transcript, absent if the session was completed without a wallet.
+    #[serde_as(as = "Option<Base64<UrlSafe, Unpadded>>")]
+    pub transcript_hash: Option<Vec<u8>>,
+    pub disclosed_attributes: DisclosedAttributes,
+}
+
+pub(super) async fn disclosed_attributes<S>(
+    State(state): State<Arc<ApplicationState<S>>>,
+    Path(session_id): Path<SessionToken>,
+    Query(params): Query<DisclosedAttributesParams>,
+) -> Result<(HeaderMap, Json<DisclosedAttributes>), Error>
+where
+    S: SessionStore<Data = SessionState<DisclosureData>>,
+{
+    let (headers, disclosed_attributes) = checked_disclosed_attributes(&state, &session_id, params).await?;
+
+    Ok((headers, Json(disclosed_attributes)))
+}
+
+pub(super) async fn signed_disclosed_attributes<S>(
+    State(state): State<Arc<ApplicationState<S>>>,
+    Path(session_id): Path<SessionToken>,
+    Query(params): Query<DisclosedAttributesParams>,
+) -> Result<(HeaderMap, String), Error>
+where
+    S: SessionStore<Data = SessionState<DisclosureData>>,
+{
+    let result_signing = state
+        .result_signing
+        .as_ref()
+        .expect("this endpoint is only routed when result signing is enabled");
+
+    let (mut headers, disclosed_attributes) = checked_disclosed_attributes(&state, &session_id, params).await?;
+
+    let transcript_hash = state
+        .verifier
+        .session_evidence(&session_id)
+        .await
+        .map_err(Error::DisclosedAttributes)?
+        .map(|evidence| {
+            let transcript = cbor_serialize(&TaggedBytes(&evidence.session_transcript))
+                .expect("session transcript should always serialize");
+            sha256(&transcript)
+        });
+
+    let claims = DisclosedAttributesClaims {
+        iss: state.public_url.clone(),
+        sub: session_id,
+        iat: chrono::Utc::now().timestamp(),
+        transcript_hash,
+        disclosed_attributes,
+    };
+
+    let mut header = jwt::header();
+    header.x5c = Some(vec![result_signing.certificate.clone()]);
+    let jwt = Jwt::sign(&claims, &header, &result_signing.private_key)
+        .await
+        .map_err(Error::SignDisclosedAttributes)?;
+
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/jwt"));
+
+    Ok((headers, jwt.0))
+}
+
+/// Retrieves the disclosed attributes of a session, enforcing the transcript hash and the frequency caps.
+async fn checked_disclosed_attributes<S>(
+    state: &ApplicationState<S>,
+    session_id: &SessionToken,
+    params: DisclosedAttributesParams,
+) -> Result<(HeaderMap, DisclosedAttributes), Error>
+where
+    S: SessionStore<Data = SessionState<DisclosureData>>,
+{
+    let disclosed_attributes = state
+        .verifier
+        .disclosed_attributes(session_id, params.transcript_hash)
+        .await
+        .map_err(Error::DisclosedAttributes)?;
+
+    let mut headers = HeaderMap::new();
+    if let Some(frequency_caps) = &state.frequency_caps {
+        let usecase = state
+            .verifier
+            .usecase(session_id)
+            .await
+            .map_err(Error::DisclosedAttributes)?;
+
+        match frequency_caps.check(session_id, &usecase, &disclosed_attributes) {
+            FrequencyCapOutcome::Allow => {}
+            FrequencyCapOutcome::Flag => {
+                headers.insert(FREQUENCY_CAP_EXCEEDED_HEADER, HeaderValue::from_static("true"));
+            }
+            FrequencyCapOutcome::Reject => return Err(Error::FrequencyCapExceeded),
+        }
+    }
+
+    Ok((headers, disclosed_attributes))
+}
@@ -0,0 +1,149 @@
+use std::{io::Cursor, net::SocketAddr, sync::Arc};
+
+use axum::{
+    extract::{ConnectInfo, Path, Query, State},
+    http::{header::CONTENT_TYPE, HeaderMap, HeaderValue},
+    Json,
+};
+use image::{DynamicImage, ImageFormat, Luma};
+use qrcode::{render::svg, QrCode};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use nl_wallet_mdoc::{
+    server_state::{SessionState, SessionStore, SessionToken},
+    verifier::DisclosureData,
+};
+
+use super::{check_rate_limits, engagement_url, ApplicationState, Error, ReturnUrlTemplate};
+
+/// The minimum width and height in pixels of a rendered QR code, excluding its quiet zone.
+const QR_CODE_MIN_DIMENSIONS: u32 = 256;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct EngagementResponse {
+    pub engagement_url: Url,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum QrCodeFormat {
+    #[default]
+    Svg,
+    Png,
+}
+
+#[derive(Deserialize)]
+pub(super) struct QrCodeParams {
+    #[serde(default)]
+    format: QrCodeFormat,
+}
+
+/// Returns the engagement URL of a session that the wallet has not yet contacted, i.e. the same universal link that
+/// was returned when the session was started.
+pub(super) async fn engagement<S>(
+    State(state): State<Arc<ApplicationState<S>>>,
+    Path(session_id): Path<SessionToken>,
+    peer: Option<ConnectInfo<SocketAddr>>,
+    headers: HeaderMap,
+) -> Result<Json<EngagementResponse>, Error>
+where
+    S: SessionStore<Data = SessionState<DisclosureData>>,
+{
+    check_rate_limits(&state, &session_id, &headers, peer).map_err(Error::RateLimited)?;
+
+    let engagement_url = stored_engagement_url(&state, session_id).await?;
+
+    Ok(Json(EngagementResponse { engagement_url }))
+}
+
+/// Returns the engagement URL of a session that the wallet has not yet contacted as a QR code, either as SVG (the
+/// default) or as PNG image, so that frontends of relying parties can display it as is.
+pub(super) async fn engagement_qr_code<S>(
+    State(state): State<Arc<ApplicationState<S>>>,
+    Path(session_id): Path<SessionToken>,
+    Query(params): Query<QrCodeParams>,
+    peer: Option<ConnectInfo<SocketAddr>>,
+    headers: HeaderMap,
+) -> Result<(HeaderMap, Vec<u8>), Error>
+where
+    S: SessionStore<Data = SessionState<DisclosureData>>,
+{
+    check_rate_limits(&state, &session_id, &headers, peer).map_err(Error::RateLimited)?;
+
+    let engagement_url = stored_engagement_url(&state, session_id).await?;
+    let (content_type, image) = render_qr_code(engagement_url.as_str(), params.format)?;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, content_type);
+
+    Ok((headers, image))
+}
+
+async fn stored_engagement_url<S>(state: &ApplicationState<S>, session_id: SessionToken) -> Result<Url, Error>
+where
+    S: SessionStore<Data = SessionState<DisclosureData>>,
+{
+    let (engagement, session_type, return_url_template) = state
+        .verifier
+        .engagement(&session_id)
+        .await
+        .map_err(Error::Engagement)?;
+
+    let return_url_template = return_url_template.map(|template| {
+        template
+            .parse::<ReturnUrlTemplate>()
+            .expect("return URL template was validated when starting the session")
+    });
+
+    Ok(engagement_url(
+        &engagement,
+        session_type,
+        return_url_template.map(|t| (t, session_id)),
+    ))
+}
+
+fn render_qr_code(content: &str, format: QrCodeFormat) -> Result<(HeaderValue, Vec<u8>), Error> {
+    let code = QrCode::new(content.as_bytes()).map_err(Error::QrCode)?;
+
+    match format {
+        QrCodeFormat::Svg => {
+            let image = code
+                .render::<svg::Color>()
+                .min_dimensions(QR_CODE_MIN_DIMENSIONS, QR_CODE_MIN_DIMENSIONS)
+                .build();
+
+            Ok((HeaderValue::from_static("image/svg+xml"), image.into_bytes()))
+        }
+        QrCodeFormat::Png => {
+            let image = code
+                .render::<Luma<u8>>()
+                .min_dimensions(QR_CODE_MIN_DIMENSIONS, QR_CODE_MIN_DIMENSIONS)
+                .build();
+
+            let mut bytes = Vec::new();
+            DynamicImage::ImageLuma8(image)
+                .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+                .map_err(Error::QrCodeImage)?;
+
+            Ok((HeaderValue::from_static("image/png"), bytes))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::{super::UL_ENGAGEMENT, *};
+
+    #[rstest]
+    #[case(QrCodeFormat::Svg, "image/svg+xml")]
+    #[case(QrCodeFormat::Png, "image/png")]
+    fn test_render_qr_code(#[case] format: QrCodeFormat, #[case] expected_content_type: &str) {
+        let (content_type, image) = render_qr_code(UL_ENGAGEMENT.as_str(), format).unwrap();
+
+        assert_eq!(content_type, expected_content_type);
+        assert!(!image.is_empty());
+    }
+}
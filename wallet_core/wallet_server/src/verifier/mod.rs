@@ -0,0 +1,808 @@
+mod admin;
+mod disclosed_attributes;
+mod engagement;
+mod sessions;
+mod status;
+
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use axum::{
+    body::Bytes,
+    extract::{ConnectInfo, Path, State},
+    http::{
+        header::{AUTHORIZATION, RETRY_AFTER},
+        HeaderMap, HeaderValue, StatusCode,
+    },
+    middleware,
+    response::{IntoResponse, Response},
+    routing::{delete, get, post},
+    Json, Router,
+};
+use base64::prelude::*;
+use lazy_static::lazy_static;
+use metrics::increment_counter;
+use nutype::nutype;
+use p256::{ecdsa::SigningKey, pkcs8::DecodePrivateKey};
+use qrcode::types::QrError;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use strfmt::strfmt;
+use tower_http::{
+    cors::{Any, CorsLayer},
+    trace::TraceLayer,
+};
+use tracing::log::{error, warn};
+use url::Url;
+
+use crate::{
+    audit_log::{AuditLog, AuditLogError},
+    cbor::Cbor,
+    forensics::{ForensicStore, ForensicsError},
+    frequency_caps::FrequencyCapper,
+    idempotency::{IdempotencyCache, IdempotencyError, Idempotent, IDEMPOTENCY_KEY_HEADER},
+    rate_limits::{RateLimitExceeded, RateLimiter},
+    settings::{Settings, Usecase},
+    usecases::{UsecaseError, UsecasePolicy},
+    webhooks::{WebhookError, WebhookNotifier},
+};
+use nl_wallet_mdoc::{
+    holder::TrustAnchor,
+    server_keys::{KeyRing, PrivateKey},
+    server_state::{SessionState, SessionStore, SessionStoreError, SessionToken},
+    status_list::{HttpStatusListClient, StatusListCache},
+    utils::{reader_auth::ReturnUrlPrefix, serialization::cbor_serialize, x509::Certificate},
+    verifier::{DisclosedAttributes, DisclosureData, ItemsRequests, SessionType, VerificationError, Verifier},
+    ReaderEngagement, SessionData,
+};
+use wallet_common::{
+    correlation::track_correlation_id, jwt::JwtError, metrics::track_http_metrics, trust_anchor::OwnedTrustAnchor,
+    utils::sha256,
+};
+
+use self::{
+    admin::{frequency_cap_metrics, query_audit_log, reload, session_evidence},
+    disclosed_attributes::{disclosed_attributes, signed_disclosed_attributes},
+    engagement::{engagement, engagement_qr_code},
+    sessions::{cancel, list_sessions, simulate},
+    status::{status, StatusNotifier},
+};
+
+pub use self::{disclosed_attributes::DisclosedAttributesClaims, engagement::EngagementResponse};
+
+lazy_static! {
+    static ref UL_ENGAGEMENT: Url =
+        Url::parse("walletdebuginteraction://wallet.edi.rijksoverheid.nl/disclosure/").unwrap();
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("starting mdoc session failed: {0}")]
+    StartSession(#[source] nl_wallet_mdoc::Error),
+    #[error("process mdoc message error: {0}")]
+    ProcessMdoc(#[source] nl_wallet_mdoc::Error),
+    #[error("retrieving status error: {0}")]
+    SessionStatus(#[source] nl_wallet_mdoc::Error),
+    #[error("retrieving engagement error: {0}")]
+    Engagement(#[source] nl_wallet_mdoc::Error),
+    #[error("could not encode QR code: {0}")]
+    QrCode(#[source] QrError),
+    #[error("could not render QR code image: {0}")]
+    QrCodeImage(#[source] image::ImageError),
+    #[error("retrieving disclosed attributes error: {0}")]
+    DisclosedAttributes(#[source] nl_wallet_mdoc::Error),
+    #[error("unknown simulation template: {0}")]
+    UnknownSimulationTemplate(String),
+    #[error("simulating disclosure error: {0}")]
+    SimulateDisclosure(#[source] nl_wallet_mdoc::Error),
+    #[error("listing sessions error: {0}")]
+    ListSessions(#[source] nl_wallet_mdoc::Error),
+    #[error("cancelling session error: {0}")]
+    CancelSession(#[source] nl_wallet_mdoc::Error),
+    #[error("retrieving session evidence error: {0}")]
+    SessionEvidence(#[source] ForensicsError),
+    #[error("session evidence requested without sufficient authorization")]
+    SessionEvidenceUnauthorized,
+    #[error("no session evidence found")]
+    SessionEvidenceNotFound,
+    #[error("audit log error: {0}")]
+    AuditLog(#[source] AuditLogError),
+    #[error("frequency cap of usecase exceeded")]
+    FrequencyCapExceeded,
+    #[error("{0}")]
+    RateLimited(#[source] RateLimitExceeded),
+    #[error("idempotency error: {0}")]
+    Idempotency(#[source] IdempotencyError),
+    #[error("callback URL provided, but webhooks are not enabled")]
+    WebhooksDisabled,
+    #[error("webhook error: {0}")]
+    Webhook(#[source] WebhookError),
+    #[error("signing disclosed attributes error: {0}")]
+    SignDisclosedAttributes(#[source] JwtError),
+    #[error("{0}")]
+    Usecase(#[from] UsecaseError),
+    #[error("reloading keys and trust anchors failed: {0}")]
+    Reload(#[source] Box<dyn std::error::Error + Send + Sync>),
+}
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        warn!("{}", self);
+
+        if let Error::RateLimited(error) = &self {
+            // round up, so that a client honouring the header does not exceed the limit again
+            let retry_after = error.retry_after.as_millis().div_ceil(1000).to_string();
+            return (StatusCode::TOO_MANY_REQUESTS, [(RETRY_AFTER, retry_after)]).into_response();
+        }
+
+        match self {
+            Error::StartSession(nl_wallet_mdoc::Error::Verification(VerificationError::UnregisteredAttributes(_))) => {
+                StatusCode::FORBIDDEN
+            }
+            Error::StartSession(nl_wallet_mdoc::Error::Verification(VerificationError::ReaderRegistration(_))) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+            Error::StartSession(nl_wallet_mdoc::Error::Verification(_)) => StatusCode::BAD_REQUEST,
+            Error::StartSession(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Error::ProcessMdoc(nl_wallet_mdoc::Error::Verification(verification_error))
+            | Error::SessionStatus(nl_wallet_mdoc::Error::Verification(verification_error))
+            | Error::Engagement(nl_wallet_mdoc::Error::Verification(verification_error))
+            | Error::DisclosedAttributes(nl_wallet_mdoc::Error::Verification(verification_error))
+            | Error::CancelSession(nl_wallet_mdoc::Error::Verification(verification_error))
+            | Error::SimulateDisclosure(nl_wallet_mdoc::Error::Verification(verification_error)) => {
+                match verification_error {
+                    VerificationError::UnknownSessionId(_)
+                    | VerificationError::SessionStore(SessionStoreError::NotFound) => StatusCode::NOT_FOUND,
+                    VerificationError::SessionStore(SessionStoreError::Expired) => StatusCode::GONE,
+                    VerificationError::SessionStore(_) => StatusCode::INTERNAL_SERVER_ERROR,
+                    _ => StatusCode::BAD_REQUEST,
+                }
+            }
+            Error::ProcessMdoc(_) => StatusCode::BAD_REQUEST,
+            Error::SessionStatus(_) => StatusCode::BAD_REQUEST,
+            Error::Engagement(_) => StatusCode::BAD_REQUEST,
+            Error::QrCode(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Error::QrCodeImage(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Error::DisclosedAttributes(_) => StatusCode::BAD_REQUEST,
+            Error::UnknownSimulationTemplate(_) => StatusCode::NOT_FOUND,
+            Error::SimulateDisclosure(_) => StatusCode::BAD_REQUEST,
+            Error::ListSessions(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Error::CancelSession(_) => StatusCode::BAD_REQUEST,
+            Error::SessionEvidence(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Error::SessionEvidenceUnauthorized => StatusCode::UNAUTHORIZED,
+            Error::SessionEvidenceNotFound => StatusCode::NOT_FOUND,
+            Error::AuditLog(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Error::FrequencyCapExceeded => StatusCode::FORBIDDEN,
+            Error::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+            Error::Idempotency(IdempotencyError::InvalidKey) => StatusCode::BAD_REQUEST,
+            Error::Idempotency(IdempotencyError::KeyReused) => StatusCode::UNPROCESSABLE_ENTITY,
+            Error::Idempotency(IdempotencyError::InProgress) => StatusCode::CONFLICT,
+            Error::WebhooksDisabled => StatusCode::BAD_REQUEST,
+            Error::Webhook(_) => StatusCode::BAD_REQUEST,
+            Error::SignDisclosedAttributes(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Error::Usecase(UsecaseError::AttributesNotAllowed(_)) => StatusCode::FORBIDDEN,
+            Error::Usecase(UsecaseError::ReturnUrlNotAllowed(_)) => StatusCode::BAD_REQUEST,
+            Error::Reload(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+        .into_response()
+    }
+}
+
+struct RelyingPartyKeyRing(HashMap<String, PrivateKey>);
+
+impl KeyRing for RelyingPartyKeyRing {
+    fn private_key(&self, usecase: &str) -> Option<&PrivateKey> {
+        self.0.get(usecase)
+    }
+}
+
+struct ApplicationState<S> {
+    verifier: Verifier<RelyingPartyKeyRing, S>,
+    usecases: RwLock<HashMap<String, UsecasePolicy>>,
+    internal_url: Url,
+    public_url: Url,
+    simulation_templates: HashMap<String, DisclosedAttributes>,
+    forensics: Option<ForensicStore>,
+    audit_log: Option<AuditLog>,
+    frequency_caps: Option<FrequencyCapper>,
+    rate_limits: Option<RateLimiter>,
+    idempotency_keys: Option<IdempotencyCache>,
+    webhooks: Option<WebhookNotifier>,
+    result_signing: Option<ResultSigningKey>,
+    status_notifier: StatusNotifier,
+}
+
+fn key_ring(usecases: &HashMap<String, Usecase>) -> anyhow::Result<RelyingPartyKeyRing> {
+    let keys = usecases
+        .iter()
+        .map(|(id, usecase)| {
+            Ok((
+                id.clone(),
+                PrivateKey::new(
+                    SigningKey::from_pkcs8_der(&usecase.private_key.0)?,
+                    Certificate::from(&usecase.certificate.0),
+                ),
+            ))
+        })
+        .collect::<anyhow::Result<HashMap<_, _>>>()?;
+
+    Ok(RelyingPartyKeyRing(keys))
+}
+
+fn trust_anchors(certificates: &[String]) -> anyhow::Result<Vec<OwnedTrustAnchor>> {
+    certificates
+        .iter()
+        .map(|certificate| {
+            Ok(Into::<OwnedTrustAnchor>::into(&TryInto::<TrustAnchor>::try_into(
+                &Certificate::from(BASE64_STANDARD.decode(certificate)?),
+            )?))
+        })
+        .collect()
+}
+
+fn usecase_policies(usecases: &HashMap<String, Usecase>) -> HashMap<String, UsecasePolicy> {
+    usecases
+        .iter()
+        .map(|(id, usecase)| (id.clone(), UsecasePolicy::from(usecase)))
+        .collect()
+}
+
+impl<S> ApplicationState<S>
+where
+    S: SessionStore<Data = SessionState<DisclosureData>>,
+{
+    /// Replace the keys and policies of the usecases and the trust anchors by those in `settings`, without affecting
+    /// the sessions. All of them are parsed before any is replaced, so that invalid settings do not cause a partial
+    /// reload. Usecases that are removed can no longer be used to start sessions, but their sessions that were already
+    /// started are not ended.
+    fn reload(&self, settings: &Settings) -> anyhow::Result<()> {
+        let keys = key_ring(&settings.usecases)?;
+        let trust_anchors = trust_anchors(&settings.trust_anchors)?;
+
+        self.verifier.update_keys(keys);
+        self.verifier.update_trust_anchors(trust_anchors);
+        *self.usecases.write().unwrap() = usecase_policies(&settings.usecases);
+
+        Ok(())
+    }
+}
+
+/// Key with which the disclosed attributes are signed, along with its Base64 encoded certificate for in the `x5c`
+/// header of the JWT.
+struct ResultSigningKey {
+    private_key: PrivateKey,
+    certificate: String,
+}
+
+pub fn create_routers<S>(
+    settings: Settings,
+    sessions: S,
+    forensics: Option<ForensicStore>,
+    audit_log: Option<AuditLog>,
+) -> anyhow::Result<(Router, Router)>
+where
+    S: SessionStore<Data = SessionState<DisclosureData>> + Send + Sync + 'static,
+{
+    let simulation_enabled = settings.simulation.is_some();
+    let forensics_enabled = forensics.is_some();
+    let audit_log_enabled = audit_log.is_some();
+    let result_signing_enabled = settings.result_signing.is_some();
+    let frequency_caps_enabled = settings.frequency_caps.is_some();
+    if simulation_enabled {
+        warn!("simulation mode is enabled, sessions can be completed without a wallet");
+    }
+
+    let mut verifier = Verifier::new(
+        settings.public_url.clone(),
+        key_ring(&settings.usecases)?,
+        sessions,
+        trust_anchors(&settings.trust_anchors)?,
+    );
+    if let Some(status_lists) = settings.status_lists {
+        verifier = verifier.with_status_lists(StatusListCache::new(
+            HttpStatusListClient::default(),
+            Duration::from_secs(status_lists.max_ttl_seconds),
+        ));
+    }
+
+    let webhooks = settings
+        .webhooks
+        .map(|webhooks| WebhookNotifier::new(webhooks, Duration::from_secs(settings.sessions.ttl_in_seconds)))
+        .transpose()?;
+
+    let result_signing = settings
+        .result_signing
+        .map(|keypair| {
+            Ok::<_, anyhow::Error>(ResultSigningKey {
+                private_key: PrivateKey::new(
+                    SigningKey::from_pkcs8_der(&keypair.private_key.0)?,
+                    Certificate::from(&keypair.certificate.0),
+                ),
+                certificate: BASE64_STANDARD.encode(&keypair.certificate.0),
+            })
+        })
+        .transpose()?;
+
+    let application_state = Arc::new(ApplicationState {
+        verifier,
+        usecases: RwLock::new(usecase_policies(&settings.usecases)),
+        internal_url: settings.internal_url,
+        public_url: settings.public_url,
+        simulation_templates: settings
+            .simulation
+            .map(|simulation| simulation.templates)
+            .unwrap_or_default(),
+        forensics,
+        audit_log,
+        frequency_caps: settings.frequency_caps.map(FrequencyCapper::new),
+        rate_limits: settings.rate_limits.map(RateLimiter::new),
+        idempotency_keys: settings.idempotency_keys.map(IdempotencyCache::new),
+        webhooks,
+        result_signing,
+        status_notifier: StatusNotifier::new(),
+    });
+
+    let wallet_router = Router::new()
+        .route("/:session_id", post(session::<S>))
+        .route(
+            "/:session_id/status",
+            get(status::<S>)
+                // to be able to request the status from a browser, the cors headers should be set
+                // but only on this endpoint and the engagement endpoints below
+                .layer(CorsLayer::new().allow_methods([Method::GET]).allow_origin(Any)),
+        )
+        .route(
+            "/:session_id/engagement",
+            get(engagement::<S>).layer(CorsLayer::new().allow_methods([Method::GET]).allow_origin(Any)),
+        )
+        .route(
+            "/:session_id/engagement/qr",
+            get(engagement_qr_code::<S>).layer(CorsLayer::new().allow_methods([Method::GET]).allow_origin(Any)),
+        )
+        .layer(TraceLayer::new_for_http())
+        .layer(middleware::from_fn(track_http_metrics))
+        .layer(middleware::from_fn(track_correlation_id))
+        .with_state(application_state.clone());
+
+    let mut requester_router = Router::new()
+        .route("/", post(start::<S>).get(list_sessions::<S>))
+        .route("/:session_id", delete(cancel::<S>))
+        .route("/:session_id/disclosed_attributes", get(disclosed_attributes::<S>))
+        .route("/admin/reload", post(reload::<S>));
+
+    if simulation_enabled {
+        requester_router = requester_router.route("/:session_id/simulate/:template", post(simulate::<S>));
+    }
+    if forensics_enabled {
+        requester_router = requester_router.route("/:session_id/evidence", get(session_evidence::<S>));
+    }
+    if audit_log_enabled {
+        requester_router = requester_router.route("/audit_log", get(query_audit_log::<S>));
+    }
+    if result_signing_enabled {
+        requester_router = requester_router.route(
+            "/:session_id/disclosed_attributes/signed",
+            get(signed_disclosed_attributes::<S>),
+        );
+    }
+    if frequency_caps_enabled {
+        requester_router = requester_router.route("/metrics/frequency_caps", get(frequency_cap_metrics::<S>));
+    }
+
+    let requester_router = requester_router
+        .layer(TraceLayer::new_for_http())
+        .layer(middleware::from_fn(track_http_metrics))
+        .layer(middleware::from_fn(track_correlation_id))
+        .with_state(application_state);
+
+    Ok((wallet_router, requester_router))
+}
+
+async fn session<S>(
+    State(state): State<Arc<ApplicationState<S>>>,
+    Path(session_id): Path<SessionToken>,
+    peer: Option<ConnectInfo<SocketAddr>>,
+    headers: HeaderMap,
+    msg: Bytes,
+) -> Result<Cbor<SessionData>, Error>
+where
+    S: SessionStore<Data = SessionState<DisclosureData>>,
+{
+    if let Err(error) = check_rate_limits(&state, &session_id, &headers, peer) {
+        // Instead of responding with an HTTP error, the session is terminated, so that the wallet ends the session
+        // gracefully and the requester sees that it has failed.
+        if let Err(e) = state.verifier.terminate_session(&session_id, error.to_string()).await {
+            warn!("could not terminate rate limited session: {}", e);
+        }
+        session_updated(&state, &session_id).await;
+
+        return Ok(Cbor(SessionData::new_termination()));
+    }
+
+    // The evidence is only stored by the message that ends the session, not by any message sent after that.
+    let was_done = match &state.forensics {
+        Some(_) => matches!(state.verifier.session_result(&session_id).await, Ok(Some(_))),
+        None => false,
+    };
+
+    let response = state
+        .verifier
+        .process_message(&msg, session_id.clone())
+        .await
+        .map_err(Error::ProcessMdoc)?;
+
+    if let Some(forensics) = state.forensics.as_ref().filter(|_| !was_done) {
+        // Failing to retain the evidence should not affect the wallet, as the session itself has been processed
+        match state.verifier.session_evidence(&session_id).await {
+            Ok(Some(evidence)) => {
+                if let Err(e) = forensics.store(&session_id, &evidence).await {
+                    error!("could not store session evidence: {}", e);
+                }
+            }
+            Ok(None) => {}
+            Err(e) => error!("could not retrieve session evidence: {}", e),
+        }
+    }
+
+    session_updated(&state, &session_id).await;
+
+    Ok(Cbor(response))
+}
+
+/// Registers a request to one of the public endpoints of the session, if rate limits are configured.
+fn check_rate_limits<S>(
+    state: &ApplicationState<S>,
+    session_id: &SessionToken,
+    headers: &HeaderMap,
+    peer: Option<ConnectInfo<SocketAddr>>,
+) -> Result<(), RateLimitExceeded> {
+    let Some(rate_limits) = &state.rate_limits else {
+        return Ok(());
+    };
+
+    let ip = rate_limits.client_ip(headers, peer.map(|ConnectInfo(peer)| peer));
+    rate_limits.check(ip, session_id)
+}
+
+/// Informs everyone interested in the session that it may have changed, i.e. the requests waiting for its status to
+/// change, the audit log and the webhook of the requester.
+async fn session_updated<S>(state: &ApplicationState<S>, session_id: &SessionToken)
+where
+    S: SessionStore<Data = SessionState<DisclosureData>>,
+{
+    state.status_notifier.notify(session_id);
+    record_session_result(state, session_id).await;
+    notify_webhook(state, session_id).await;
+}
+
+/// Completes the entry of the session in the audit log if the session has ended.
+async fn record_session_result<S>(state: &ApplicationState<S>, session_id: &SessionToken)
+where
+    S: SessionStore<Data = SessionState<DisclosureData>>,
+{
+    let Some(audit_log) = &state.audit_log else {
+        return;
+    };
+
+    // Failing to complete the entry should not affect the wallet, as the session itself has been processed
+    match state.verifier.session_result(session_id).await {
+        Ok(Some(session_result)) => {
+            if let Err(e) = audit_log.record_completed(session_id, &session_result).await {
+                error!("could not record session result in audit log: {}", e);
+            }
+        }
+        Ok(None) => {}
+        Err(e) => error!("could not retrieve session result for audit log: {}", e),
+    }
+}
+
+/// Notifies the requester if it registered a callback URL and the session has ended.
+async fn notify_webhook<S>(state: &ApplicationState<S>, session_id: &SessionToken)
+where
+    S: SessionStore<Data = SessionState<DisclosureData>>,
+{
+    let Some(webhooks) = state
+        .webhooks
+        .as_ref()
+        .filter(|webhooks| webhooks.is_registered(session_id))
+    else {
+        return;
+    };
+
+    // Failing to notify the requester should not affect the wallet, as the requester can still poll the status
+    match state.verifier.status(session_id).await {
+        Ok(status) => webhooks.notify(session_id, status),
+        Err(e) => error!("could not retrieve session status for webhook notification: {}", e),
+    }
+}
+
+fn is_valid_return_url_template(s: &str) -> bool {
+    // it should be a valid ReturnUrlPrefix when removing the template parameter
+    let s = s.replace("{session_id}", "");
+    let url = s.parse::<Url>(); // this makes sure no Url-invalid characters are present
+    url.is_ok_and(|mut u| {
+        u.set_query(None); // query is allowed in a template but not in a prefix
+        u.set_fragment(None); // fragment is allowed in a template but not in a prefix
+        u = u
+            .join("path_segment_that_ends_with_a_slash/")
+            .expect("should always result in a valid URL"); // path not ending with a '/' is allowed in a template but not in prefix
+        TryInto::<ReturnUrlPrefix>::try_into(u).is_ok()
+    })
+}
+
+#[nutype(
+    derive(Debug, Deserialize, Serialize, FromStr, AsRef),
+    validate(predicate = is_valid_return_url_template),
+)]
+pub struct ReturnUrlTemplate(String);
+
+#[derive(Deserialize, Serialize)]
+pub struct StartDisclosureRequest {
+    pub usecase: String,
+    pub items_requests: ItemsRequests,
+    pub session_type: SessionType,
+    pub return_url_template: Option<ReturnUrlTemplate>,
+    /// URL to which a signed notification is POSTed once the session has ended, if webhooks are enabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub callback_url: Option<Url>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct StartDisclosureResponse {
+    pub session_url: Url,
+    pub engagement_url: Url,
+    pub disclosed_attributes_url: Url,
+}
+
+/// Adds the query parameters of the engagement URL by adding the session_type and the formatted return_url
+fn format_engagement_url_params(
+    mut engagement_url: Url,
+    session_type: SessionType,
+    return_url_tuple: Option<(ReturnUrlTemplate, SessionToken)>,
+) -> Url {
+    engagement_url
+        .query_pairs_mut()
+        .append_pair("session_type", &session_type.to_string());
+    if let Some((template, session_id)) = return_url_tuple {
+        let return_url = strfmt!(&template.into_inner(), session_id => session_id.to_string())
+            .expect("return_template should always format");
+        engagement_url.query_pairs_mut().append_pair("return_url", &return_url);
+    }
+    engagement_url
+}
+
+/// Encodes the engagement as universal link and adds the query parameters.
+fn engagement_url(
+    engagement: &ReaderEngagement,
+    session_type: SessionType,
+    return_url_tuple: Option<(ReturnUrlTemplate, SessionToken)>,
+) -> Url {
+    // base64 produces an alphanumberic value, cbor_serialize takes a Cbor_IntMap here
+    let engagement_url = UL_ENGAGEMENT
+        .join(&BASE64_URL_SAFE_NO_PAD.encode(cbor_serialize(engagement).unwrap()))
+        .expect("universal link should be hardcoded s.t. this will never fail");
+
+    // add session_type and if available the return_url
+    format_engagement_url_params(engagement_url, session_type, return_url_tuple)
+}
+
+async fn start<S>(
+    State(state): State<Arc<ApplicationState<S>>>,
+    headers: HeaderMap,
+    Json(start_request): Json<StartDisclosureRequest>,
+) -> Result<Json<StartDisclosureResponse>, Error>
+where
+    S: SessionStore<Data = SessionState<DisclosureData>>,
+{
+    // the idempotency key is ignored when idempotency keys are not enabled
+    let idempotency_guard = match (&state.idempotency_keys, headers.get(IDEMPOTENCY_KEY_HEADER)) {
+        (Some(idempotency_keys), Some(key)) => {
+            let key = key
+                .to_str()
+                .map_err(|_| Error::Idempotency(IdempotencyError::InvalidKey))?;
+            let request_digest = sha256(&serde_json::to_vec(&start_request).expect("request should serialize"));
+            // the requester is identified by the credentials it authenticated with and the usecase it represents
+            let authorization = headers.get(AUTHORIZATION).map(HeaderValue::as_bytes);
+            let requester = sha256(
+                &serde_json::to_vec(&(authorization, &start_request.usecase)).expect("requester should serialize"),
+            );
+
+            match idempotency_keys
+                .begin(requester, key, request_digest)
+                .map_err(Error::Idempotency)?
+            {
+                Idempotent::Replay(response) => return Ok(Json(response)),
+                Idempotent::New(guard) => Some(guard),
+            }
+        }
+        _ => None,
+    };
+
+    // if starting the session fails, the guard is dropped so that the request can be retried with the same key
+    let response = start_session(&state, start_request).await?;
+
+    if let Some(guard) = idempotency_guard {
+        guard.complete(response.clone());
+    }
+
+    Ok(Json(response))
+}
+
+async fn start_session<S>(
+    state: &ApplicationState<S>,
+    start_request: StartDisclosureRequest,
+) -> Result<StartDisclosureResponse, Error>
+where
+    S: SessionStore<Data = SessionState<DisclosureData>>,
+{
+    // unknown usecases are rejected by the verifier when starting the session
+    let usecase_policy = state.usecases.read().unwrap().get(&start_request.usecase).cloned();
+    if let Some(usecase_policy) = usecase_policy {
+        usecase_policy.check_request(
+            &start_request.items_requests,
+            start_request.return_url_template.as_ref().map(AsRef::as_ref),
+        )?;
+    }
+
+    if let Some(callback_url) = &start_request.callback_url {
+        state
+            .webhooks
+            .as_ref()
+            .ok_or(Error::WebhooksDisabled)?
+            .check_callback_url(callback_url)
+            .map_err(Error::Webhook)?;
+    }
+
+    let usecase = start_request.usecase.clone();
+    let audited_items_requests = state
+        .audit_log
+        .as_ref()
+        .map(|audit_log| (audit_log, start_request.items_requests.clone()));
+    let (session_id, engagement) = state
+        .verifier
+        .new_session(
+            start_request.items_requests,
+            start_request.session_type,
+            start_request.usecase,
+            start_request
+                .return_url_template
+                .as_ref()
+                .map(|template| template.as_ref().to_owned()),
+        )
+        .await
+        .map_err(Error::StartSession)?;
+
+    // the session is only usable once it has been recorded, so that every completed session is in the audit log
+    if let Some((audit_log, items_requests)) = audited_items_requests {
+        audit_log
+            .record_started(&session_id, &usecase, &items_requests)
+            .await
+            .map_err(Error::AuditLog)?;
+    }
+
+    if let (Some(webhooks), Some(callback_url)) = (&state.webhooks, start_request.callback_url) {
+        webhooks
+            .register_session(session_id.clone(), callback_url)
+            .map_err(Error::Webhook)?;
+    }
+
+    increment_counter!(
+        "wallet_server_disclosure_sessions_started_total",
+        "usecase" => usecase,
+        "session_type" => start_request.session_type.to_string()
+    );
+
+    let session_url = state
+        .public_url
+        .join(&format!("{session_id}/status"))
+        .expect("should always be a valid URL");
+    let disclosed_attributes_url = state
+        .internal_url
+        .join(&format!("sessions/{session_id}/disclosed_attributes"))
+        .expect("should always be a valid URL");
+
+    let engagement_url = engagement_url(
+        &engagement,
+        start_request.session_type,
+        start_request.return_url_template.map(|t| (t, session_id)),
+    );
+
+    Ok(StartDisclosureResponse {
+        session_url,
+        engagement_url,
+        disclosed_attributes_url,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    #[allow(unused_imports)]
+    use super::*;
+
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(
+        "https://example.com",
+        SessionType::CrossDevice,
+        None,
+        "https://example.com?session_type=cross_device"
+    )]
+    #[case(
+        "https://example.com",
+        SessionType::SameDevice,
+        Some("https://example.com/return/{session_id}".parse().unwrap()),
+        "https://example.com?session_type=same_device&return_url=https%3A%2F%2Fexample.com%2Freturn%2Fdeadbeef"
+    )]
+    #[case(
+        "https://example.com",
+        SessionType::CrossDevice,
+        Some("https://example.com/return/{session_id}".parse().unwrap()),
+        "https://example.com?session_type=cross_device&return_url=https%3A%2F%2Fexample.com%2Freturn%2Fdeadbeef"
+    )]
+    #[case(
+        "https://example.com",
+        SessionType::SameDevice,
+        Some("https://example.com/return/".parse().unwrap()),
+        "https://example.com?session_type=same_device&return_url=https%3A%2F%2Fexample.com%2Freturn%2F"
+    )]
+    #[case(
+        "https://example.com/path/",
+        SessionType::CrossDevice,
+        Some("https://example.com/{session_id}/my_return_url/".parse().unwrap()),
+        "https://example.com/path/?session_type=cross_device&return_url=https%3A%2F%2Fexample.com%2Fdeadbeef%2Fmy_return_url%2F"
+    )]
+    #[case(
+        "https://example.com",
+        SessionType::SameDevice,
+        Some("https://example.com/return/{session_id}?hello=world#hashtag".parse().unwrap()),
+        "https://example.com?session_type=same_device&return_url=https%3A%2F%2Fexample.com%2Freturn%2Fdeadbeef%3Fhello%3Dworld%23hashtag"
+    )]
+    #[case(
+        "https://example.com",
+        SessionType::SameDevice,
+        Some("https://example.com/{session_id}?id={session_id}#{session_id}".parse().unwrap()),
+        "https://example.com?session_type=same_device&return_url=https%3A%2F%2Fexample.com%2Fdeadbeef%3Fid%3Ddeadbeef%23deadbeef"
+    )]
+    fn test_format_engagement_url_params(
+        #[case] engagement_url: Url,
+        #[case] session_type: SessionType,
+        #[case] return_url_template: Option<ReturnUrlTemplate>,
+        #[case] expected: Url,
+    ) {
+        let result = format_engagement_url_params(
+            engagement_url,
+            session_type,
+            return_url_template.map(|u| (u, "deadbeef".to_owned().into())),
+        );
+        assert_eq!(result, expected);
+    }
+
+    #[rstest]
+    #[case("https://example.com/{session_id}", true)]
+    #[case("https://example.com/return/{session_id}", true)]
+    #[case("https://example.com/return/{session_id}/url", true)]
+    #[case("https://example.com/{session_id}/", true)]
+    #[case("https://example.com/return/{session_id}/", true)]
+    #[case("https://example.com/return/{session_id}/url/", true)]
+    #[case("https://example.com/return/{session_id}?hello=world&bye=mars#hashtag", true)]
+    #[case("https://example.com/{session_id}/{session_id}", true)]
+    #[case("https://example.com/", true)]
+    #[case("https://example.com/return", true)]
+    #[case("https://example.com/return/url", true)]
+    #[case("https://example.com/return/", true)]
+    #[case("https://example.com/return/url/", true)]
+    #[case("https://example.com/return/?hello=world&bye=mars#hashtag", true)]
+    #[case("https://example.com/{session_id}/{not_session_id}", true)]
+    #[case("file://etc/passwd", false)]
+    #[case("file://etc/{session_id}", false)]
+    #[case("https://{session_id}", false)]
+    fn test_return_url_template(#[case] return_url_string: String, #[case] should_parse: bool) {
+        assert_eq!(return_url_string.parse::<ReturnUrlTemplate>().is_ok(), should_parse);
+        assert_eq!(is_valid_return_url_template(&return_url_string), should_parse)
+    }
+}
@@ -0,0 +1,99 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use serde::Deserialize;
+use serde_with::{serde_as, DisplayFromStr};
+
+use nl_wallet_mdoc::{
+    server_state::{SessionState, SessionStore, SessionToken},
+    verifier::{DisclosureData, SessionSummary, StatusResponse},
+};
+
+use super::{session_updated, ApplicationState, Error};
+
+/// The maximum number of sessions that is returned when listing sessions.
+const MAX_SESSIONS_LIMIT: usize = 1_000;
+
+#[serde_as]
+#[derive(Deserialize)]
+pub(super) struct ListSessionsParams {
+    usecase: Option<String>,
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    #[serde(default)]
+    status: Option<StatusResponse>,
+    /// The number of sessions to skip, for retrieving the next page.
+    #[serde(default)]
+    offset: usize,
+    /// The maximum number of sessions to return, which is capped at [`MAX_SESSIONS_LIMIT`].
+    limit: Option<usize>,
+}
+
+/// Lists the sessions that have not expired, from most to least recently active, optionally only those of a usecase
+/// and with a specific status.
+pub(super) async fn list_sessions<S>(
+    State(state): State<Arc<ApplicationState<S>>>,
+    Query(params): Query<ListSessionsParams>,
+) -> Result<Json<Vec<SessionSummary>>, Error>
+where
+    S: SessionStore<Data = SessionState<DisclosureData>>,
+{
+    let limit = params.limit.unwrap_or(MAX_SESSIONS_LIMIT).min(MAX_SESSIONS_LIMIT);
+
+    let sessions = state
+        .verifier
+        .list_sessions(params.usecase.as_deref(), params.status)
+        .await
+        .map_err(Error::ListSessions)?
+        .into_iter()
+        .skip(params.offset)
+        .take(limit)
+        .collect();
+
+    Ok(Json(sessions))
+}
+
+/// Cancels a session that has not ended yet. The wallet is informed that the session has been terminated the next
+/// time it contacts the wallet server.
+pub(super) async fn cancel<S>(
+    State(state): State<Arc<ApplicationState<S>>>,
+    Path(session_id): Path<SessionToken>,
+) -> Result<(), Error>
+where
+    S: SessionStore<Data = SessionState<DisclosureData>>,
+{
+    state
+        .verifier
+        .cancel_session(&session_id)
+        .await
+        .map_err(Error::CancelSession)?;
+
+    session_updated(&state, &session_id).await;
+
+    Ok(())
+}
+
+pub(super) async fn simulate<S>(
+    State(state): State<Arc<ApplicationState<S>>>,
+    Path((session_id, template)): Path<(SessionToken, String)>,
+) -> Result<(), Error>
+where
+    S: SessionStore<Data = SessionState<DisclosureData>>,
+{
+    let disclosed_attributes = state
+        .simulation_templates
+        .get(&template)
+        .ok_or_else(|| Error::UnknownSimulationTemplate(template.clone()))?;
+
+    state
+        .verifier
+        .simulate_disclosure(&session_id, disclosed_attributes.clone())
+        .await
+        .map_err(Error::SimulateDisclosure)?;
+
+    session_updated(&state, &session_id).await;
+
+    Ok(())
+}
@@ -0,0 +1,202 @@
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+use axum::{
+    extract::{ConnectInfo, Path, Query, State},
+    http::{header::ACCEPT, HeaderMap},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    Json,
+};
+use dashmap::DashMap;
+use futures::{stream, Stream};
+use serde::Deserialize;
+use serde_with::{serde_as, DisplayFromStr};
+use tokio::{sync::watch, time};
+use tracing::log::warn;
+
+use crate::store::CleanupTask;
+use nl_wallet_mdoc::{
+    server_state::{SessionState, SessionStore, SessionToken},
+    verifier::{DisclosureData, StatusResponse},
+};
+
+use super::{check_rate_limits, ApplicationState, Error};
+
+/// The maximum time a long-polling status request waits for the status of a session to change.
+const STATUS_LONG_POLL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The interval at which the status of a session is retrieved again while waiting for it to change, as not every
+/// change is notified: a session can expire, or be updated by another instance of the wallet server sharing the
+/// session store.
+const STATUS_RECHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Wakes the requests that wait for the status of a session to change, so that they do not have to poll the session
+/// store. The channels of sessions that nobody waits for anymore are periodically removed.
+pub(super) struct StatusNotifier {
+    senders: Arc<DashMap<SessionToken, watch::Sender<()>>>,
+    _cleanup_task: CleanupTask,
+}
+
+impl StatusNotifier {
+    pub fn new() -> Self {
+        let senders = Arc::new(DashMap::<SessionToken, watch::Sender<()>>::new());
+        let cleanup_task = CleanupTask::start(Arc::clone(&senders), |senders| async move {
+            senders.retain(|_, sender| sender.receiver_count() > 0)
+        });
+
+        Self {
+            senders,
+            _cleanup_task: cleanup_task,
+        }
+    }
+
+    /// Subscribe to the changes of the session. This should be done before retrieving its status, so that a change
+    /// in between is not missed.
+    pub fn subscribe(&self, session_id: &SessionToken) -> watch::Receiver<()> {
+        self.senders
+            .entry(session_id.clone())
+            .or_insert_with(|| watch::channel(()).0)
+            .subscribe()
+    }
+
+    /// Wake everyone that subscribed to the changes of the session.
+    pub fn notify(&self, session_id: &SessionToken) {
+        if let Some(sender) = self.senders.get(session_id) {
+            sender.send_replace(());
+        }
+    }
+}
+
+#[serde_as]
+#[derive(Deserialize)]
+pub(super) struct StatusParams {
+    /// When present and equal to the current status, the response is held until the status changes or the long-poll
+    /// timeout has passed, after which the then current status is returned.
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    #[serde(default)]
+    known_status: Option<StatusResponse>,
+}
+
+/// Returns the status of the session. Clients that accept `text/event-stream` receive a stream of Server-Sent Events
+/// instead, with an event for the current status and for every subsequent change, until the session has ended.
+pub(super) async fn status<S>(
+    State(state): State<Arc<ApplicationState<S>>>,
+    Path(session_id): Path<SessionToken>,
+    Query(params): Query<StatusParams>,
+    peer: Option<ConnectInfo<SocketAddr>>,
+    headers: HeaderMap,
+) -> Result<Response, Error>
+where
+    S: SessionStore<Data = SessionState<DisclosureData>> + Send + Sync + 'static,
+{
+    check_rate_limits(&state, &session_id, &headers, peer).map_err(Error::RateLimited)?;
+
+    let status = state.verifier.status(&session_id).await.map_err(Error::SessionStatus)?;
+
+    let accepts_event_stream = headers
+        .get(ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.contains(mime::TEXT_EVENT_STREAM.as_ref()));
+    if accepts_event_stream {
+        return Ok(Sse::new(status_events(state, session_id, status))
+            .keep_alive(KeepAlive::default())
+            .into_response());
+    }
+
+    let status = match params.known_status {
+        Some(known_status) if known_status == status && !status.is_final() => {
+            // when the timeout passes, the status has not changed
+            time::timeout(STATUS_LONG_POLL_TIMEOUT, changed_status(&state, &session_id, status))
+                .await
+                .unwrap_or(Ok(status))
+                .map_err(Error::SessionStatus)?
+        }
+        _ => status,
+    };
+
+    Ok(Json(status).into_response())
+}
+
+/// Stream of `status` events, starting with `status` and followed by an event for every change of the status, which
+/// ends when the session has ended or its status can no longer be retrieved.
+fn status_events<S>(
+    state: Arc<ApplicationState<S>>,
+    session_id: SessionToken,
+    status: StatusResponse,
+) -> impl Stream<Item = Result<Event, serde_json::Error>>
+where
+    S: SessionStore<Data = SessionState<DisclosureData>> + Send + Sync + 'static,
+{
+    stream::unfold(Some((state, session_id, status, true)), |next| async move {
+        let (state, session_id, mut status, first) = next?;
+
+        if !first {
+            status = match changed_status(&state, &session_id, status).await {
+                Ok(status) => status,
+                Err(e) => {
+                    warn!("ending status event stream: {}", e);
+                    return None;
+                }
+            };
+        }
+
+        let event = Event::default().event("status").json_data(status);
+        let next = (!status.is_final()).then_some((state, session_id, status, false));
+
+        Some((event, next))
+    })
+}
+
+/// Waits until the status of the session differs from `status` and returns the new status. The status is retrieved
+/// again whenever the session is notified to have changed, or otherwise every [`STATUS_RECHECK_INTERVAL`].
+async fn changed_status<S>(
+    state: &ApplicationState<S>,
+    session_id: &SessionToken,
+    status: StatusResponse,
+) -> Result<StatusResponse, nl_wallet_mdoc::Error>
+where
+    S: SessionStore<Data = SessionState<DisclosureData>>,
+{
+    loop {
+        let mut changes = state.status_notifier.subscribe(session_id);
+
+        let new_status = state.verifier.status(session_id).await?;
+        if new_status != status {
+            return Ok(new_status);
+        }
+
+        // the sender is only removed when there are no receivers, so this cannot return an error immediately
+        let _ = time::timeout(STATUS_RECHECK_INTERVAL, changes.changed()).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_status_notifier() {
+        let notifier = StatusNotifier::new();
+        let session_id = SessionToken::new();
+        let other_session_id = SessionToken::new();
+
+        // notifying without subscribers does nothing
+        notifier.notify(&session_id);
+
+        let mut changes = notifier.subscribe(&session_id);
+        assert!(!changes.has_changed().unwrap());
+
+        notifier.notify(&other_session_id);
+        assert!(!changes.has_changed().unwrap());
+
+        notifier.notify(&session_id);
+        assert!(changes.has_changed().unwrap());
+        changes.changed().await.unwrap();
+
+        // a new subscription does not see earlier changes
+        let new_changes = notifier.subscribe(&session_id);
+        assert!(!new_changes.has_changed().unwrap());
+    }
+}
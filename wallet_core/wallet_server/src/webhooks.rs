@@ -0,0 +1,266 @@
+use std::{sync::Arc, time::Duration};
+
+use base64::prelude::*;
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use metrics::increment_counter;
+use p256::{
+    ecdsa::{signature::Signer, Signature, SigningKey},
+    pkcs8::DecodePrivateKey,
+};
+use reqwest::header::CONTENT_TYPE;
+use serde::Serialize;
+use tokio::time;
+use tracing::{info, warn};
+use url::Url;
+
+use nl_wallet_mdoc::{server_state::SessionToken, verifier::StatusResponse};
+
+use crate::{settings::Webhooks, store::CleanupTask};
+
+/// Header containing the Base64 encoded, DER formatted ECDSA signature over the body of a webhook notification.
+pub const WEBHOOK_SIGNATURE_HEADER: &str = "x-webhook-signature";
+
+/// The delay before retrying a failed notification, which is doubled for every subsequent attempt.
+const RETRY_DELAY_MILLISECONDS: u64 = 500;
+
+#[derive(Debug, thiserror::Error)]
+pub enum WebhookError {
+    #[error("invalid webhook signing key: {0}")]
+    SigningKey(#[from] p256::pkcs8::Error),
+    #[error("callback URL should use https: {0}")]
+    InsecureCallbackUrl(Url),
+    #[error("could not serialize notification: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
+
+/// The body that is POSTed to the callback URL of a session once it has ended.
+#[derive(Debug, Serialize)]
+pub struct WebhookNotification {
+    pub session_id: SessionToken,
+    #[serde(flatten)]
+    pub status: StatusResponse,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Notifies the backend of the relying party when a session has ended, i.e. has the status `DONE`, `FAILED` or
+/// `CANCELLED`, so that it does not have to poll the status of the session. The body of the notification is signed
+/// with the webhook signing key of the server, which the relying party should use to authenticate the notification.
+///
+/// Note that, like the frequency caps, the callback URLs are kept in memory, so a notification is only sent when the
+/// session ends on the same instance on which it was started.
+pub struct WebhookNotifier {
+    client: reqwest::Client,
+    signing_key: SigningKey,
+    max_attempts: u32,
+    allow_http: bool,
+    state: Arc<WebhookState>,
+    _cleanup_task: CleanupTask,
+}
+
+struct WebhookState {
+    callbacks: DashMap<SessionToken, (Url, DateTime<Utc>)>,
+    ttl: chrono::Duration,
+}
+
+impl WebhookNotifier {
+    /// Create a new [`WebhookNotifier`]. Registrations of sessions that have not ended after `session_ttl` are
+    /// forgotten, as these sessions will have expired.
+    pub fn new(settings: Webhooks, session_ttl: Duration) -> Result<Self, WebhookError> {
+        let signing_key = SigningKey::from_pkcs8_der(&settings.signing_key.0)?;
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(settings.timeout_in_ms))
+            .build()
+            .expect("could not build webhook HTTP client");
+
+        let state = Arc::new(WebhookState {
+            callbacks: DashMap::new(),
+            ttl: chrono::Duration::from_std(session_ttl).unwrap_or(chrono::Duration::max_value()),
+        });
+        let cleanup_task = CleanupTask::start(Arc::clone(&state), |state| async move { state.cleanup() });
+
+        Ok(Self {
+            client,
+            signing_key,
+            max_attempts: settings.max_attempts.max(1),
+            allow_http: cfg!(feature = "allow_http_return_url"),
+            state,
+            _cleanup_task: cleanup_task,
+        })
+    }
+
+    /// Checks that notifications can be sent to `callback_url`, i.e. that it uses https.
+    pub fn check_callback_url(&self, callback_url: &Url) -> Result<(), WebhookError> {
+        if callback_url.scheme() != "https" && !(self.allow_http && callback_url.scheme() == "http") {
+            return Err(WebhookError::InsecureCallbackUrl(callback_url.clone()));
+        }
+
+        Ok(())
+    }
+
+    /// Registers the URL to which a notification should be sent once the session has ended.
+    pub fn register_session(&self, session_id: SessionToken, callback_url: Url) -> Result<(), WebhookError> {
+        self.check_callback_url(&callback_url)?;
+
+        self.state.callbacks.insert(session_id, (callback_url, Utc::now()));
+
+        Ok(())
+    }
+
+    pub fn is_registered(&self, session_id: &SessionToken) -> bool {
+        self.state.callbacks.contains_key(session_id)
+    }
+
+    /// Sends a notification to the callback URL of the session in the background if the `status` is final. The
+    /// registration is removed, so that every session results in at most one notification.
+    pub fn notify(&self, session_id: &SessionToken, status: StatusResponse) {
+        if !matches!(
+            status,
+            StatusResponse::Done | StatusResponse::Failed | StatusResponse::Cancelled
+        ) {
+            return;
+        }
+
+        let Some((_, (callback_url, _))) = self.state.callbacks.remove(session_id) else {
+            return;
+        };
+
+        let notification = WebhookNotification {
+            session_id: session_id.clone(),
+            status,
+            timestamp: Utc::now(),
+        };
+        let (body, signature) = match self.sign(&notification) {
+            Ok(signed) => signed,
+            Err(e) => {
+                warn!("could not sign webhook notification: {}", e);
+                return;
+            }
+        };
+
+        let client = self.client.clone();
+        let max_attempts = self.max_attempts;
+        tokio::spawn(async move {
+            Self::deliver(client, callback_url, body, signature, max_attempts).await;
+        });
+    }
+
+    fn sign(&self, notification: &WebhookNotification) -> Result<(Vec<u8>, String), WebhookError> {
+        let body = serde_json::to_vec(notification)?;
+        let signature: Signature = self.signing_key.sign(&body);
+
+        Ok((body, BASE64_STANDARD.encode(signature.to_der())))
+    }
+
+    async fn deliver(client: reqwest::Client, callback_url: Url, body: Vec<u8>, signature: String, max_attempts: u32) {
+        let mut delay = Duration::from_millis(RETRY_DELAY_MILLISECONDS);
+
+        for attempt in 1..=max_attempts {
+            let result = client
+                .post(callback_url.clone())
+                .header(CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())
+                .header(WEBHOOK_SIGNATURE_HEADER, &signature)
+                .body(body.clone())
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+
+            match result {
+                Ok(_) => {
+                    info!("delivered webhook notification to {}", callback_url);
+                    increment_counter!("wallet_server_webhook_notifications_total", "outcome" => "delivered");
+                    return;
+                }
+                Err(e) => warn!(
+                    "attempt {} of {} to deliver webhook notification to {} failed: {}",
+                    attempt, max_attempts, callback_url, e
+                ),
+            }
+
+            if attempt < max_attempts {
+                time::sleep(delay).await;
+                delay *= 2;
+            }
+        }
+
+        increment_counter!("wallet_server_webhook_notifications_total", "outcome" => "failed");
+    }
+}
+
+impl WebhookState {
+    /// Remove the registrations of sessions that were never completed.
+    fn cleanup(&self) {
+        let threshold = Utc::now() - self.ttl;
+        self.callbacks.retain(|_, (_, registered)| *registered > threshold);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p256::{
+        ecdsa::{signature::Verifier, VerifyingKey},
+        elliptic_curve::rand_core::OsRng,
+        pkcs8::EncodePrivateKey,
+    };
+
+    use wallet_common::account::serialization::Base64Bytes;
+
+    use super::*;
+
+    fn notifier() -> (WebhookNotifier, VerifyingKey) {
+        let signing_key = SigningKey::random(&mut OsRng);
+        let settings = Webhooks {
+            signing_key: Base64Bytes(signing_key.to_pkcs8_der().unwrap().as_bytes().to_vec()),
+            timeout_in_ms: 1_000,
+            max_attempts: 1,
+        };
+
+        (
+            WebhookNotifier::new(settings, Duration::from_secs(300)).unwrap(),
+            *signing_key.verifying_key(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_sign_notification() {
+        let (notifier, verifying_key) = notifier();
+
+        let notification = WebhookNotification {
+            session_id: "deadbeef".to_owned().into(),
+            status: StatusResponse::Done,
+            timestamp: Utc::now(),
+        };
+        let (body, signature) = notifier.sign(&notification).unwrap();
+
+        let signature = Signature::from_der(&BASE64_STANDARD.decode(signature).unwrap()).unwrap();
+        verifying_key.verify(&body, &signature).unwrap();
+
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["session_id"], "deadbeef");
+        assert_eq!(json["status"], "DONE");
+    }
+
+    #[tokio::test]
+    async fn test_register_session() {
+        let (notifier, _) = notifier();
+        let session_id: SessionToken = "deadbeef".to_owned().into();
+
+        assert!(notifier
+            .register_session(session_id.clone(), "ftp://example.com/callback".parse().unwrap())
+            .is_err());
+        assert!(!notifier.is_registered(&session_id));
+
+        notifier
+            .register_session(session_id.clone(), "https://example.com/callback".parse().unwrap())
+            .unwrap();
+        assert!(notifier.is_registered(&session_id));
+
+        // Sessions that have not ended yet do not result in a notification.
+        notifier.notify(&session_id, StatusResponse::WaitingForResponse);
+        assert!(notifier.is_registered(&session_id));
+
+        // The registration is removed once the notification is sent.
+        notifier.notify(&session_id, StatusResponse::Cancelled);
+        assert!(!notifier.is_registered(&session_id));
+    }
+}